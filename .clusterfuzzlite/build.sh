@@ -17,9 +17,10 @@
 
 cd "$SRC"
 
-declare -a PROJECTS=(amt hamt kamt common)
+declare -a PROJECTS=(amt bitfield hamt kamt common)
 declare -A PROJECT_PATHS=(
 	[amt]="ref-fvm/ipld/amt/fuzz"
+	[bitfield]="ref-fvm/ipld/bitfield/fuzz"
 	[hamt]="ref-fvm/ipld/hamt/fuzz"
 	[kamt]="ref-fvm/ipld/kamt/fuzz"
 	[common]="ref-fvm/testing/common_fuzz/fuzz"
@@ -0,0 +1,325 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use crate::{ChainEpoch, Policy, QuantSpec};
+
+/// Deadline calculations with respect to a current epoch.
+///
+/// "Deadline" refers to the window during which proofs may be submitted. Windows are
+/// non-overlapping ranges `[open, close)`, but the challenge epoch for a window occurs before the
+/// window opens.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DeadlineInfo {
+    /// The epoch at which this info was calculated.
+    pub current_epoch: ChainEpoch,
+    /// The first epoch of the proving period.
+    pub period_start: ChainEpoch,
+    /// The deadline index, in `[0, wpost_period_deadlines]`.
+    pub index: u64,
+    /// The first epoch from which a proof may be submitted.
+    pub open: ChainEpoch,
+    /// The first epoch from which a proof may no longer be submitted (>= `open`).
+    pub close: ChainEpoch,
+    /// The epoch at which to sample the chain for the challenge (< `open`).
+    pub challenge: ChainEpoch,
+    /// The first epoch at which a fault declaration is rejected (< `open`).
+    pub fault_cutoff: ChainEpoch,
+    /// The policy the info was calculated with.
+    pub policy: Policy,
+}
+
+impl DeadlineInfo {
+    /// Returns the info of the deadline `deadline_idx` of the proving period starting at
+    /// `period_start`.
+    ///
+    /// A `deadline_idx` of `wpost_period_deadlines` or more denotes the (empty) deadline after
+    /// the last one, which opens and closes when the next proving period starts.
+    pub fn new(
+        policy: &Policy,
+        period_start: ChainEpoch,
+        deadline_idx: u64,
+        current_epoch: ChainEpoch,
+    ) -> Self {
+        if deadline_idx < policy.wpost_period_deadlines {
+            let open = period_start + deadline_idx as ChainEpoch * policy.wpost_challenge_window;
+            DeadlineInfo {
+                current_epoch,
+                period_start,
+                index: deadline_idx,
+                open,
+                close: open + policy.wpost_challenge_window,
+                challenge: open - policy.wpost_challenge_lookback,
+                fault_cutoff: open - policy.fault_declaration_cutoff,
+                policy: *policy,
+            }
+        } else {
+            let after_last_deadline = period_start + policy.wpost_proving_period;
+            DeadlineInfo {
+                current_epoch,
+                period_start,
+                index: deadline_idx,
+                open: after_last_deadline,
+                close: after_last_deadline,
+                challenge: after_last_deadline,
+                fault_cutoff: 0,
+                policy: *policy,
+            }
+        }
+    }
+
+    /// Returns the info of the deadline open (or about to open) at `current_epoch`, for a
+    /// miner whose proving periods start at `period_start_seed` modulo the proving period.
+    pub fn from_offset_and_epoch(
+        policy: &Policy,
+        period_start_seed: ChainEpoch,
+        current_epoch: ChainEpoch,
+    ) -> Self {
+        let q = QuantSpec {
+            unit: policy.wpost_proving_period,
+            offset: period_start_seed,
+        };
+        let period_start = q.quantize_down(current_epoch);
+        let deadline_idx = current_deadline_index(policy, current_epoch, period_start);
+        Self::new(policy, period_start, deadline_idx, current_epoch)
+    }
+
+    /// Whether the proving period has begun.
+    pub fn period_started(&self) -> bool {
+        self.current_epoch >= self.period_start
+    }
+
+    /// Whether the proving period has elapsed.
+    pub fn period_elapsed(&self) -> bool {
+        self.current_epoch >= self.next_period_start()
+    }
+
+    /// The last epoch of the proving period.
+    pub fn period_end(&self) -> ChainEpoch {
+        self.period_start + self.policy.wpost_proving_period - 1
+    }
+
+    /// The first epoch of the next proving period.
+    pub fn next_period_start(&self) -> ChainEpoch {
+        self.period_start + self.policy.wpost_proving_period
+    }
+
+    /// Whether the current deadline is currently open.
+    pub fn is_open(&self) -> bool {
+        self.current_epoch >= self.open && self.current_epoch < self.close
+    }
+
+    /// Whether the current deadline has already closed.
+    pub fn has_elapsed(&self) -> bool {
+        self.current_epoch >= self.close
+    }
+
+    /// The last epoch during which a proof may be submitted.
+    pub fn last(&self) -> ChainEpoch {
+        self.close - 1
+    }
+
+    /// The epoch at which the next instance of this deadline opens.
+    pub fn next_open(&self) -> ChainEpoch {
+        self.open + self.policy.wpost_proving_period
+    }
+
+    /// Whether the deadline's fault cutoff has passed.
+    pub fn fault_cutoff_passed(&self) -> bool {
+        self.current_epoch >= self.fault_cutoff
+    }
+
+    /// Returns the quantization spec for events that shouldn't occur in the middle of a deadline,
+    /// i.e. the last epoch of each instance of this deadline.
+    pub fn quant_spec(&self) -> QuantSpec {
+        QuantSpec {
+            unit: self.policy.wpost_proving_period,
+            offset: self.last(),
+        }
+    }
+
+    /// Returns the next instance of this deadline that has not yet elapsed, which is this one if
+    /// it hasn't elapsed yet.
+    pub fn next_not_elapsed(self) -> Self {
+        if !self.has_elapsed() {
+            return self;
+        }
+
+        // The deadline elapsed: advance by some multiple of the proving period.
+        let gap = self.current_epoch - self.close;
+        let delta_periods = 1 + gap / self.policy.wpost_proving_period;
+
+        Self::new(
+            &self.policy,
+            self.period_start + self.policy.wpost_proving_period * delta_periods,
+            self.index,
+            self.current_epoch,
+        )
+    }
+}
+
+/// Returns the index of the deadline open at `current_epoch`, within the proving period starting
+/// at `period_start`.
+pub fn current_deadline_index(
+    policy: &Policy,
+    current_epoch: ChainEpoch,
+    period_start: ChainEpoch,
+) -> u64 {
+    ((current_epoch - period_start) / policy.wpost_challenge_window) as u64
+}
+
+/// Returns the start of the proving period containing `current_epoch`, for a miner whose proving
+/// periods start at `offset` modulo the proving period.
+pub fn current_proving_period_start(
+    policy: &Policy,
+    current_epoch: ChainEpoch,
+    offset: ChainEpoch,
+) -> ChainEpoch {
+    QuantSpec {
+        unit: policy.wpost_proving_period,
+        offset,
+    }
+    .quantize_down(current_epoch)
+}
+
+/// Returns true if the deadline at `deadline_idx` is currently mutable, i.e. if it's at least one
+/// challenge window before its next instance opens.
+pub fn deadline_is_mutable(
+    policy: &Policy,
+    proving_period_start: ChainEpoch,
+    deadline_idx: u64,
+    current_epoch: ChainEpoch,
+) -> bool {
+    let info = DeadlineInfo::new(policy, proving_period_start, deadline_idx, current_epoch)
+        .next_not_elapsed();
+    current_epoch < info.open - policy.wpost_challenge_window
+}
+
+/// Returns true if optimistically accepted proofs submitted to the deadline at `deadline_idx`
+/// during its last instance may currently be disputed.
+pub fn deadline_available_for_optimistic_post_dispute(
+    policy: &Policy,
+    proving_period_start: ChainEpoch,
+    deadline_idx: u64,
+    current_epoch: ChainEpoch,
+) -> bool {
+    if proving_period_start > current_epoch {
+        return false;
+    }
+    let info = DeadlineInfo::new(policy, proving_period_start, deadline_idx, current_epoch)
+        .next_not_elapsed();
+
+    !info.is_open()
+        && current_epoch < (info.close - policy.wpost_proving_period) + policy.wpost_dispute_window
+}
+
+/// Returns true if the deadline at `deadline_idx` may currently be compacted, i.e. if it's mutable
+/// and its proofs can no longer be disputed.
+pub fn deadline_available_for_compaction(
+    policy: &Policy,
+    proving_period_start: ChainEpoch,
+    deadline_idx: u64,
+    current_epoch: ChainEpoch,
+) -> bool {
+    deadline_is_mutable(policy, proving_period_start, deadline_idx, current_epoch)
+        && !deadline_available_for_optimistic_post_dispute(
+            policy,
+            proving_period_start,
+            deadline_idx,
+            current_epoch,
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadline_info() {
+        let policy = Policy::default();
+        let info = DeadlineInfo::new(&policy, 100, 2, 250);
+        assert_eq!(info.open, 220);
+        assert_eq!(info.close, 280);
+        assert_eq!(info.last(), 279);
+        assert_eq!(info.challenge, 200);
+        assert_eq!(info.fault_cutoff, 150);
+        assert!(info.is_open());
+        assert!(!info.has_elapsed());
+        assert!(info.fault_cutoff_passed());
+        assert!(info.period_started());
+        assert!(!info.period_elapsed());
+        assert_eq!(info.period_end(), 2979);
+        assert_eq!(info.next_period_start(), 2980);
+        assert_eq!(info.next_open(), 3100);
+        assert_eq!(info.quant_spec().quantize_up(250), 279);
+
+        // The deadline after the last one opens with the next proving period.
+        let after = DeadlineInfo::new(&policy, 100, 48, 250);
+        assert_eq!(after.open, 2980);
+        assert_eq!(after.close, 2980);
+    }
+
+    #[test]
+    fn from_offset_and_epoch() {
+        let policy = Policy::default();
+        for current_epoch in [0, 59, 60, 1000, 2879, 2880, 2900, 100_000] {
+            let info = DeadlineInfo::from_offset_and_epoch(&policy, 100, current_epoch);
+            assert!(info.period_started());
+            assert!(!info.period_elapsed());
+            assert!(info.is_open(), "{current_epoch}: {info:?}");
+            assert_eq!(
+                info.period_start.rem_euclid(policy.wpost_proving_period),
+                100
+            );
+            assert_eq!(
+                info.period_start,
+                current_proving_period_start(&policy, current_epoch, 100)
+            );
+            assert!(info.index < policy.wpost_period_deadlines);
+        }
+    }
+
+    #[test]
+    fn next_not_elapsed() {
+        let policy = Policy::default();
+        let info = DeadlineInfo::new(&policy, 0, 1, 10);
+        assert_eq!(info.next_not_elapsed(), info);
+
+        let info = DeadlineInfo::new(&policy, 0, 1, 120 + 2 * 2880);
+        let next = info.next_not_elapsed();
+        assert_eq!(next.period_start, 3 * 2880);
+        assert_eq!(next.open, 3 * 2880 + 60);
+        assert!(!next.has_elapsed());
+    }
+
+    #[test]
+    fn mutability_and_disputes() {
+        let policy = Policy::default();
+
+        // Deadline 10 opens at 600 and closes at 660.
+        assert!(deadline_is_mutable(&policy, 0, 10, 539));
+        assert!(!deadline_is_mutable(&policy, 0, 10, 540));
+        assert!(!deadline_is_mutable(&policy, 0, 10, 600));
+        // Once it's closed, the next instance is far enough away.
+        assert!(deadline_is_mutable(&policy, 0, 10, 660));
+
+        // Proofs can be disputed within the dispute window after the deadline closes.
+        assert!(!deadline_available_for_optimistic_post_dispute(
+            &policy, 0, 10, 630
+        ));
+        assert!(deadline_available_for_optimistic_post_dispute(
+            &policy, 0, 10, 660
+        ));
+        assert!(deadline_available_for_optimistic_post_dispute(
+            &policy, 0, 10, 2459
+        ));
+        assert!(!deadline_available_for_optimistic_post_dispute(
+            &policy, 0, 10, 2460
+        ));
+        // Nothing to dispute before the first proving period started.
+        assert!(!deadline_available_for_optimistic_post_dispute(
+            &policy, 100, 10, 50
+        ));
+
+        assert!(!deadline_available_for_compaction(&policy, 0, 10, 660));
+        assert!(deadline_available_for_compaction(&policy, 0, 10, 2460));
+    }
+}
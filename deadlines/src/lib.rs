@@ -0,0 +1,89 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Proving period, deadline, and partition math for Filecoin storage miners.
+//!
+//! A miner's proving period is divided into a fixed number of deadlines, each of which is open
+//! for a "challenge window" of epochs, during which the miner must submit Window PoSts for the
+//! partitions assigned to it. The same calculations are needed by the miner actor and by
+//! client-side tooling (wallets, monitoring, etc.), so they live in this crate, which has no
+//! dependencies and is parameterized by a [`Policy`].
+
+mod deadline;
+mod partition;
+mod quantize;
+
+pub use deadline::*;
+pub use partition::*;
+pub use quantize::*;
+
+/// An epoch of the chain. Identical to `fvm_shared::clock::ChainEpoch`.
+pub type ChainEpoch = i64;
+
+/// The number of epochs in a day, assuming 30 second epochs.
+pub const EPOCHS_IN_DAY: ChainEpoch = 24 * 60 * 60 / 30;
+
+/// The chain finality, in epochs.
+pub const CHAIN_FINALITY: ChainEpoch = 900;
+
+/// The network parameters governing proving periods and deadlines.
+///
+/// All durations are in epochs and must be positive. [`Policy::default`] returns the mainnet
+/// parameters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    /// The length of a proving period.
+    pub wpost_proving_period: ChainEpoch,
+    /// The length of the window during which a deadline is open for Window PoSts.
+    pub wpost_challenge_window: ChainEpoch,
+    /// The number of deadlines in a proving period. Must equal
+    /// `wpost_proving_period / wpost_challenge_window`.
+    pub wpost_period_deadlines: u64,
+    /// How far before a deadline opens the challenge randomness is drawn.
+    pub wpost_challenge_lookback: ChainEpoch,
+    /// How far before a deadline opens fault declarations for it are no longer accepted.
+    pub fault_declaration_cutoff: ChainEpoch,
+    /// How long after a deadline closes its optimistically accepted proofs may be disputed.
+    pub wpost_dispute_window: ChainEpoch,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            wpost_proving_period: EPOCHS_IN_DAY,
+            wpost_challenge_window: 30 * 60 / 30,
+            wpost_period_deadlines: 48,
+            wpost_challenge_lookback: 20,
+            fault_declaration_cutoff: 20 + 50,
+            wpost_dispute_window: 2 * CHAIN_FINALITY,
+        }
+    }
+}
+
+impl Policy {
+    /// Returns true if the policy's parameters are consistent with each other.
+    pub fn is_valid(&self) -> bool {
+        self.wpost_proving_period > 0
+            && self.wpost_challenge_window > 0
+            && self.wpost_challenge_lookback >= 0
+            && self.fault_declaration_cutoff >= 0
+            && self.wpost_dispute_window >= 0
+            && self.wpost_period_deadlines > 0
+            && self.wpost_challenge_window * self.wpost_period_deadlines as ChainEpoch
+                == self.wpost_proving_period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_is_valid() {
+        assert!(Policy::default().is_valid());
+        assert!(!Policy {
+            wpost_period_deadlines: 47,
+            ..Policy::default()
+        }
+        .is_valid());
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+/// The position of a sector within a deadline's partitions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PartitionPosition {
+    /// The index of the partition within the deadline.
+    pub partition: u64,
+    /// The index of the sector within the partition.
+    pub index: u64,
+}
+
+/// Returns the number of partitions needed to hold `sector_count` sectors, when partitions are
+/// filled in order and each holds up to `partition_size` sectors.
+///
+/// # Panics
+///
+/// Panics if `partition_size` is zero.
+pub fn partition_count(sector_count: u64, partition_size: u64) -> u64 {
+    sector_count.div_ceil(partition_size)
+}
+
+/// Returns the position of the `n`th sector (zero-based) of a deadline, when partitions are
+/// filled in order and each holds up to `partition_size` sectors.
+///
+/// # Panics
+///
+/// Panics if `partition_size` is zero.
+pub fn partition_position(n: u64, partition_size: u64) -> PartitionPosition {
+    PartitionPosition {
+        partition: n / partition_size,
+        index: n % partition_size,
+    }
+}
+
+/// Returns the number of free sector slots left in the last, partially filled, partition of a
+/// deadline holding `sector_count` sectors. Returns zero if all partitions are full.
+///
+/// # Panics
+///
+/// Panics if `partition_size` is zero.
+pub fn partition_free_slots(sector_count: u64, partition_size: u64) -> u64 {
+    match sector_count % partition_size {
+        0 => 0,
+        used => partition_size - used,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partitions() {
+        assert_eq!(partition_count(0, 2349), 0);
+        assert_eq!(partition_count(1, 2349), 1);
+        assert_eq!(partition_count(2349, 2349), 1);
+        assert_eq!(partition_count(2350, 2349), 2);
+
+        assert_eq!(
+            partition_position(0, 2349),
+            PartitionPosition {
+                partition: 0,
+                index: 0
+            }
+        );
+        assert_eq!(
+            partition_position(2350, 2349),
+            PartitionPosition {
+                partition: 1,
+                index: 1
+            }
+        );
+
+        assert_eq!(partition_free_slots(0, 2349), 0);
+        assert_eq!(partition_free_slots(1, 2349), 2348);
+        assert_eq!(partition_free_slots(4698, 2349), 0);
+    }
+}
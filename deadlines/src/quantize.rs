@@ -0,0 +1,82 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use crate::ChainEpoch;
+
+/// A spec for quantization: epochs are rounded to the nearest `offset + k * unit`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct QuantSpec {
+    /// The unit of quantization. Must be positive.
+    pub unit: ChainEpoch,
+    /// The offset from zero at which the quantization boundaries lie.
+    pub offset: ChainEpoch,
+}
+
+/// A quantization spec which leaves all epochs unchanged.
+pub const NO_QUANTIZATION: QuantSpec = QuantSpec { unit: 1, offset: 0 };
+
+impl QuantSpec {
+    /// Rounds `epoch` up to the nearest quantization boundary (or leaves it unchanged if it's
+    /// already on one).
+    pub fn quantize_up(&self, epoch: ChainEpoch) -> ChainEpoch {
+        let offset = self.offset.rem_euclid(self.unit);
+        let remainder = (epoch - offset).rem_euclid(self.unit);
+        if remainder == 0 {
+            epoch
+        } else {
+            epoch - remainder + self.unit
+        }
+    }
+
+    /// Rounds `epoch` down to the nearest quantization boundary (or leaves it unchanged if it's
+    /// already on one).
+    pub fn quantize_down(&self, epoch: ChainEpoch) -> ChainEpoch {
+        let offset = self.offset.rem_euclid(self.unit);
+        epoch - (epoch - offset).rem_euclid(self.unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantize_up() {
+        let q = QuantSpec {
+            unit: 10,
+            offset: 3,
+        };
+        assert_eq!(q.quantize_up(3), 3);
+        assert_eq!(q.quantize_up(4), 13);
+        assert_eq!(q.quantize_up(13), 13);
+        assert_eq!(q.quantize_up(0), 3);
+        assert_eq!(q.quantize_up(-5), 3);
+        assert_eq!(q.quantize_up(-8), -7);
+        assert_eq!(q.quantize_up(-7), -7);
+
+        // Offsets larger than the unit are reduced.
+        let q = QuantSpec {
+            unit: 10,
+            offset: 53,
+        };
+        assert_eq!(q.quantize_up(4), 13);
+
+        assert_eq!(NO_QUANTIZATION.quantize_up(42), 42);
+        assert_eq!(NO_QUANTIZATION.quantize_up(-42), -42);
+    }
+
+    #[test]
+    fn quantize_down() {
+        let q = QuantSpec {
+            unit: 10,
+            offset: 3,
+        };
+        assert_eq!(q.quantize_down(3), 3);
+        assert_eq!(q.quantize_down(4), 3);
+        assert_eq!(q.quantize_down(12), 3);
+        assert_eq!(q.quantize_down(13), 13);
+        assert_eq!(q.quantize_down(0), -7);
+        assert_eq!(q.quantize_down(-8), -17);
+
+        assert_eq!(NO_QUANTIZATION.quantize_down(42), 42);
+    }
+}
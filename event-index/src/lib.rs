@@ -0,0 +1,414 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Indexing and filtering of stamped events, with semantics compatible with Ethereum log filters
+//! (`eth_getLogs`, `eth_newFilter`), for clients implementing the Ethereum JSON-RPC API.
+//!
+//! Ethereum logs are represented as events whose topics are stored in the `t1` through `t4`
+//! entries, and whose data is stored in the `d` entry (see FIP-0049 and the EVM actor).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use cid::Cid;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::event::{ActorEvent, StampedEvent};
+use fvm_shared::{ActorID, IPLD_RAW};
+use thiserror::Error;
+
+/// The keys of the entries holding an Ethereum log's topics, in order.
+const TOPIC_KEYS: [&str; 4] = ["t1", "t2", "t3", "t4"];
+
+/// The maximum number of topics in an Ethereum log.
+pub const MAX_TOPICS: usize = TOPIC_KEYS.len();
+
+/// Returns the topics of an Ethereum-style event (the values of its `t1` through `t4` entries),
+/// in order. Topics must be contiguous: if `t2` is missing, `t3` and `t4` are ignored.
+pub fn event_topics(event: &ActorEvent) -> Vec<&[u8]> {
+    TOPIC_KEYS
+        .iter()
+        .map_while(|key| {
+            event
+                .entries
+                .iter()
+                .find(|e| e.key == *key && e.codec == IPLD_RAW)
+                .map(|e| &*e.value)
+        })
+        .collect()
+}
+
+/// An event, along with its position in the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedEvent {
+    /// The epoch at which the message emitting this event was executed.
+    pub epoch: ChainEpoch,
+    /// The CID of the message that emitted this event.
+    pub message: Cid,
+    /// The index of this event among all events emitted at this epoch (the Ethereum log index).
+    pub index: u64,
+    /// The event itself.
+    pub event: StampedEvent,
+}
+
+/// A bound of the range of epochs to search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EpochBound {
+    /// The earliest indexed epoch.
+    Earliest,
+    /// The latest indexed epoch.
+    #[default]
+    Latest,
+    /// A specific epoch.
+    At(ChainEpoch),
+}
+
+/// A filter over indexed events, mirroring an Ethereum log filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// The first epoch to search (inclusive). Defaults to the latest epoch.
+    pub from: EpochBound,
+    /// The last epoch to search (inclusive). Defaults to the latest epoch.
+    pub to: EpochBound,
+    /// The actors whose events match. If empty, events emitted by any actor match.
+    pub emitters: Vec<ActorID>,
+    /// The topics to match, by position. An empty set of alternatives at a position matches any
+    /// topic, otherwise the event's topic at that position must be one of the alternatives.
+    ///
+    /// As with Ethereum log filters, events with fewer topics than specified positions never
+    /// match, even if the trailing positions are wildcards.
+    pub topics: Vec<Vec<Vec<u8>>>,
+}
+
+impl EventFilter {
+    /// Returns true if the event matches the filter's emitters and topics, ignoring the epoch
+    /// range.
+    pub fn matches(&self, event: &StampedEvent) -> bool {
+        if !self.emitters.is_empty() && !self.emitters.contains(&event.emitter) {
+            return false;
+        }
+        let topics = event_topics(&event.event);
+        if topics.len() < self.topics.len() {
+            return false;
+        }
+        self.topics.iter().zip(topics).all(|(alternatives, topic)| {
+            alternatives.is_empty() || alternatives.iter().any(|t| t == topic)
+        })
+    }
+}
+
+/// Event filter error
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("invalid epoch range: {from} > {to}")]
+    InvalidRange { from: ChainEpoch, to: ChainEpoch },
+    #[error("filter has {0} topic positions, at most {MAX_TOPICS} are allowed")]
+    TooManyTopics(usize),
+    #[error("filter matches more than {0} events")]
+    TooManyResults(usize),
+}
+
+/// The events emitted at a single epoch, indexed by emitter and topic.
+#[derive(Debug, Default)]
+struct EpochEvents {
+    events: Vec<IndexedEvent>,
+    by_emitter: HashMap<ActorID, Vec<usize>>,
+    /// Events by (position, topic).
+    by_topic: HashMap<(usize, Vec<u8>), Vec<usize>>,
+}
+
+impl EpochEvents {
+    fn push(&mut self, event: IndexedEvent) {
+        let idx = self.events.len();
+        self.by_emitter
+            .entry(event.event.emitter)
+            .or_default()
+            .push(idx);
+        for (pos, topic) in event_topics(&event.event.event).into_iter().enumerate() {
+            self.by_topic
+                .entry((pos, topic.to_vec()))
+                .or_default()
+                .push(idx);
+        }
+        self.events.push(event);
+    }
+
+    /// Returns the indices of the events that may match the filter, in order, using the most
+    /// selective index available. Candidates must still be checked against the filter.
+    fn candidates(&self, filter: &EventFilter) -> Vec<usize> {
+        let postings = |lists: Vec<Option<&Vec<usize>>>| -> Vec<usize> {
+            lists
+                .into_iter()
+                .flatten()
+                .flatten()
+                .copied()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        };
+        if !filter.emitters.is_empty() {
+            return postings(
+                filter
+                    .emitters
+                    .iter()
+                    .map(|e| self.by_emitter.get(e))
+                    .collect(),
+            );
+        }
+        match filter
+            .topics
+            .iter()
+            .enumerate()
+            .find(|(_, t)| !t.is_empty())
+        {
+            Some((pos, alternatives)) => postings(
+                alternatives
+                    .iter()
+                    .map(|t| self.by_topic.get(&(pos, t.clone())))
+                    .collect(),
+            ),
+            None => (0..self.events.len()).collect(),
+        }
+    }
+}
+
+/// An in-memory index of events by epoch, evaluating [`EventFilter`]s.
+#[derive(Debug, Default)]
+pub struct EventIndex {
+    epochs: BTreeMap<ChainEpoch, EpochEvents>,
+    max_results: Option<usize>,
+}
+
+impl EventIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the number of events a single query may return.
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Indexes the events emitted by a message executed at the given epoch. Messages must be added
+    /// in execution order.
+    pub fn add_message_events(
+        &mut self,
+        epoch: ChainEpoch,
+        message: Cid,
+        events: impl IntoIterator<Item = StampedEvent>,
+    ) {
+        let epoch_events = self.epochs.entry(epoch).or_default();
+        for event in events {
+            let index = epoch_events.events.len() as u64;
+            epoch_events.push(IndexedEvent {
+                epoch,
+                message,
+                index,
+                event,
+            });
+        }
+    }
+
+    /// Drops the events of all epochs after `epoch`, e.g., when the chain reorgs.
+    pub fn revert_to(&mut self, epoch: ChainEpoch) {
+        // No epoch follows the last one.
+        if let Some(next) = epoch.checked_add(1) {
+            self.epochs.split_off(&next);
+        }
+    }
+
+    /// Drops the events of all epochs before `epoch`, to bound the index's size.
+    pub fn prune_before(&mut self, epoch: ChainEpoch) {
+        self.epochs = self.epochs.split_off(&epoch);
+    }
+
+    /// Returns the earliest indexed epoch.
+    pub fn earliest_epoch(&self) -> Option<ChainEpoch> {
+        self.epochs.keys().next().copied()
+    }
+
+    /// Returns the latest indexed epoch.
+    pub fn latest_epoch(&self) -> Option<ChainEpoch> {
+        self.epochs.keys().next_back().copied()
+    }
+
+    /// Returns the events matching the filter, ordered by epoch and index.
+    pub fn query(&self, filter: &EventFilter) -> Result<Vec<&IndexedEvent>, Error> {
+        if filter.topics.len() > MAX_TOPICS {
+            return Err(Error::TooManyTopics(filter.topics.len()));
+        }
+        let (Some(earliest), Some(latest)) = (self.earliest_epoch(), self.latest_epoch()) else {
+            return Ok(Vec::new());
+        };
+        let resolve = |bound| match bound {
+            EpochBound::Earliest => earliest,
+            EpochBound::Latest => latest,
+            EpochBound::At(epoch) => epoch,
+        };
+        let (from, to) = (resolve(filter.from), resolve(filter.to));
+        if from > to {
+            return Err(Error::InvalidRange { from, to });
+        }
+
+        let mut results = Vec::new();
+        for epoch_events in self.epochs.range(from..=to).map(|(_, e)| e) {
+            for idx in epoch_events.candidates(filter) {
+                let event = &epoch_events.events[idx];
+                if !filter.matches(&event.event) {
+                    continue;
+                }
+                if let Some(max) = self.max_results {
+                    if results.len() >= max {
+                        return Err(Error::TooManyResults(max));
+                    }
+                }
+                results.push(event);
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use fvm_shared::event::{Entry, Flags};
+
+    use super::*;
+
+    fn log(emitter: ActorID, topics: &[u8]) -> StampedEvent {
+        let mut entries: Vec<_> = topics
+            .iter()
+            .zip(TOPIC_KEYS)
+            .map(|(t, key)| Entry {
+                flags: Flags::FLAG_INDEXED_ALL,
+                key: key.into(),
+                codec: IPLD_RAW,
+                value: vec![*t; 32],
+            })
+            .collect();
+        entries.push(Entry {
+            flags: Flags::FLAG_INDEXED_ALL,
+            key: "d".into(),
+            codec: IPLD_RAW,
+            value: b"data".to_vec(),
+        });
+        StampedEvent::new(emitter, entries.into())
+    }
+
+    fn topic(t: u8) -> Vec<u8> {
+        vec![t; 32]
+    }
+
+    fn index() -> EventIndex {
+        let msg = |n: u8| Cid::new_v1(IPLD_RAW, Code::Sha2_256.digest(&[n]));
+        let mut index = EventIndex::new();
+        index.add_message_events(10, msg(0), [log(100, &[1, 2]), log(101, &[1])]);
+        index.add_message_events(10, msg(1), [log(100, &[3])]);
+        index.add_message_events(11, msg(2), [log(101, &[1, 2, 3])]);
+        index.add_message_events(12, msg(3), [log(100, &[2, 1])]);
+        index
+    }
+
+    fn positions(events: Vec<&IndexedEvent>) -> Vec<(ChainEpoch, u64)> {
+        events.into_iter().map(|e| (e.epoch, e.index)).collect()
+    }
+
+    #[test]
+    fn block_ranges() {
+        let index = index();
+        let all = EventFilter {
+            from: EpochBound::Earliest,
+            ..Default::default()
+        };
+        assert_eq!(
+            positions(index.query(&all).unwrap()),
+            [(10, 0), (10, 1), (10, 2), (11, 0), (12, 0)]
+        );
+
+        // Defaults to the latest epoch.
+        let latest = EventFilter::default();
+        assert_eq!(positions(index.query(&latest).unwrap()), [(12, 0)]);
+
+        let range = EventFilter {
+            from: EpochBound::At(11),
+            to: EpochBound::At(11),
+            ..Default::default()
+        };
+        assert_eq!(positions(index.query(&range).unwrap()), [(11, 0)]);
+
+        let invalid = EventFilter {
+            from: EpochBound::At(12),
+            to: EpochBound::At(11),
+            ..Default::default()
+        };
+        assert_eq!(
+            index.query(&invalid),
+            Err(Error::InvalidRange { from: 12, to: 11 })
+        );
+    }
+
+    #[test]
+    fn address_and_topic_filters() {
+        let index = index();
+        let filter = |emitters: Vec<ActorID>, topics: Vec<Vec<Vec<u8>>>| EventFilter {
+            from: EpochBound::Earliest,
+            to: EpochBound::Latest,
+            emitters,
+            topics,
+        };
+
+        let by_emitter = filter(vec![101], vec![]);
+        assert_eq!(
+            positions(index.query(&by_emitter).unwrap()),
+            [(10, 1), (11, 0)]
+        );
+
+        // Topic 1 in the first position.
+        let first = filter(vec![], vec![vec![topic(1)]]);
+        assert_eq!(
+            positions(index.query(&first).unwrap()),
+            [(10, 0), (10, 1), (11, 0)]
+        );
+
+        // Any first topic, topic 2 or 1 in the second position.
+        let second = filter(vec![], vec![vec![], vec![topic(2), topic(1)]]);
+        assert_eq!(
+            positions(index.query(&second).unwrap()),
+            [(10, 0), (11, 0), (12, 0)]
+        );
+
+        // Events with too few topics never match.
+        let third = filter(vec![], vec![vec![], vec![], vec![]]);
+        assert_eq!(positions(index.query(&third).unwrap()), [(11, 0)]);
+
+        let both = filter(vec![100], vec![vec![topic(1)], vec![topic(2)]]);
+        assert_eq!(positions(index.query(&both).unwrap()), [(10, 0)]);
+
+        let too_many = filter(vec![], vec![vec![]; 5]);
+        assert_eq!(index.query(&too_many), Err(Error::TooManyTopics(5)));
+    }
+
+    #[test]
+    fn reorgs_and_limits() {
+        let mut index = index().with_max_results(2);
+        let all = EventFilter {
+            from: EpochBound::Earliest,
+            ..Default::default()
+        };
+        assert_eq!(index.query(&all), Err(Error::TooManyResults(2)));
+
+        index.revert_to(ChainEpoch::MAX);
+        assert_eq!(index.latest_epoch(), Some(12));
+        index.revert_to(10);
+        assert_eq!(index.latest_epoch(), Some(10));
+        index.prune_before(10);
+        assert_eq!(index.earliest_epoch(), Some(10));
+
+        let filter = EventFilter {
+            emitters: vec![100],
+            ..Default::default()
+        };
+        assert_eq!(positions(index.query(&filter).unwrap()), [(10, 0), (10, 2)]);
+    }
+}
@@ -1,35 +1,35 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+//! An example of extending the FVM with a custom syscall.
+//!
+//! The kernel below wraps the [`DefaultFilecoinKernel`], delegating the standard kernel operations
+//! to it, and adds a single new syscall (`my_custom_kernel::my_custom_syscall`).
+//!
+//! **This kernel doesn't verify seal, aggregate seal and replica update proofs, nor consensus
+//! faults**: it charges for them as usual, then accepts every proof and reports no fault, so tests
+//! don't need real proofs. Delegate [`FilecoinKernel`] instead (with
+//! `#[delegate(FilecoinKernel, where = "C: CallManager")]`) to verify them.
+use cid::Cid;
 use fvm::call_manager::CallManager;
 use fvm::gas::Gas;
-use fvm::kernel::filecoin::{DefaultFilecoinKernel, FilecoinKernel};
+use fvm::kernel::filecoin::{DefaultFilecoinKernel, FilecoinKernel, NetworkSummary};
 use fvm::kernel::prelude::*;
 use fvm::kernel::Result;
-use fvm::kernel::{
-    ActorOps, CryptoOps, DebugOps, EventOps, IpldBlockOps, MessageOps, NetworkOps, RandomnessOps,
-    SelfOps, SendOps, SyscallHandler, UpgradeOps,
-};
 use fvm::syscalls::Linker;
-use fvm_shared::clock::ChainEpoch;
 use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::piece::PieceInfo;
-use fvm_shared::randomness::RANDOMNESS_LENGTH;
 use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
+    WindowPoStVerifyInfo,
 };
-use fvm_shared::sys::out::network::NetworkContext;
-use fvm_shared::sys::out::vm::MessageContext;
-use fvm_shared::{address::Address, econ::TokenAmount, ActorID, MethodNum};
-
-use ambassador::Delegate;
-use cid::Cid;
 
-// we define a single custom syscall which simply doubles the input
+/// The kernel operations backing our custom syscall. Here, a single operation which simply doubles
+/// the input.
 pub trait CustomKernel: Kernel {
     fn my_custom_syscall(&self, doubleme: i32) -> Result<i32>;
 }
 
-// our custom kernel extends the filecoin kernel
+/// Our custom kernel extends the filecoin kernel.
 #[derive(Delegate)]
 #[delegate(IpldBlockOps, where = "C: CallManager")]
 #[delegate(ActorOps, where = "C: CallManager")]
@@ -63,6 +63,75 @@ where
     }
 }
 
+impl<C> FilecoinKernel for DefaultCustomKernel<C>
+where
+    C: CallManager,
+{
+    fn compute_unsealed_sector_cid(
+        &self,
+        proof_type: RegisteredSealProof,
+        pieces: &[PieceInfo],
+    ) -> Result<Cid> {
+        self.0.compute_unsealed_sector_cid(proof_type, pieces)
+    }
+
+    fn verify_post(&self, verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
+        self.0.verify_post(verify_info)
+    }
+
+    // NOT forwarded: accepts every seal.
+    fn batch_verify_seals(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
+        Ok(vec![true; vis.len()])
+    }
+
+    // NOT forwarded: accepts every seal.
+    fn verify_seal_batch(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
+        let charge = self.price_list().on_verify_seal_batch(vis);
+        let _ = self.charge_gas(&charge.name, charge.total())?;
+        Ok(vec![true; vis.len()])
+    }
+
+    fn verify_post_batch(&self, infos: &[WindowPoStVerifyInfo]) -> Result<Vec<bool>> {
+        self.0.verify_post_batch(infos)
+    }
+
+    // NOT forwarded: never reports a fault.
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> Result<Option<ConsensusFault>> {
+        let charge = self
+            .price_list()
+            .on_verify_consensus_fault(h1.len(), h2.len(), extra.len());
+        let _ = self.charge_gas(&charge.name, charge.total())?;
+        Ok(None)
+    }
+
+    // NOT forwarded: accepts every aggregate.
+    fn verify_aggregate_seals(&self, agg: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
+        let charge = self.price_list().on_verify_aggregate_seals(agg);
+        let _ = self.charge_gas(&charge.name, charge.total())?;
+        Ok(true)
+    }
+
+    // NOT forwarded: accepts every replica update.
+    fn verify_replica_update(&self, rep: &ReplicaUpdateInfo) -> Result<bool> {
+        let charge = self.price_list().on_verify_replica_update(rep);
+        let _ = self.charge_gas(&charge.name, charge.total())?;
+        Ok(true)
+    }
+
+    fn total_fil_circ_supply(&self) -> Result<TokenAmount> {
+        self.0.total_fil_circ_supply()
+    }
+
+    fn network_summary(&self) -> Result<NetworkSummary> {
+        self.0.network_summary()
+    }
+}
+
 impl<C> Kernel for DefaultCustomKernel<C>
 where
     C: CallManager,
@@ -114,60 +183,6 @@ where
     }
 }
 
-impl<C> FilecoinKernel for DefaultCustomKernel<C>
-where
-    C: CallManager,
-{
-    fn compute_unsealed_sector_cid(
-        &self,
-        proof_type: RegisteredSealProof,
-        pieces: &[PieceInfo],
-    ) -> Result<Cid> {
-        self.0.compute_unsealed_sector_cid(proof_type, pieces)
-    }
-
-    fn verify_post(&self, verify_info: &fvm_shared::sector::WindowPoStVerifyInfo) -> Result<bool> {
-        self.0.verify_post(verify_info)
-    }
-
-    // NOT forwarded
-    fn batch_verify_seals(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
-        Ok(vec![true; vis.len()])
-    }
-
-    // NOT forwarded
-    fn verify_consensus_fault(
-        &self,
-        h1: &[u8],
-        h2: &[u8],
-        extra: &[u8],
-    ) -> Result<Option<ConsensusFault>> {
-        let charge = self
-            .price_list()
-            .on_verify_consensus_fault(h1.len(), h2.len(), extra.len());
-        let _ = self.charge_gas(&charge.name, charge.total())?;
-        Ok(None)
-    }
-
-    // NOT forwarded
-    fn verify_aggregate_seals(&self, agg: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
-        let charge = self.price_list().on_verify_aggregate_seals(agg);
-        let _ = self.charge_gas(&charge.name, charge.total())?;
-        Ok(true)
-    }
-
-    // NOT forwarded
-    fn verify_replica_update(&self, rep: &ReplicaUpdateInfo) -> Result<bool> {
-        let charge = self.price_list().on_verify_replica_update(rep);
-        let _ = self.charge_gas(&charge.name, charge.total())?;
-        Ok(true)
-    }
-
-    fn total_fil_circ_supply(&self) -> Result<TokenAmount> {
-        self.0.total_fil_circ_supply()
-    }
-}
-
 impl<K> SyscallHandler<K> for DefaultCustomKernel<K::CallManager>
 where
     K: CustomKernel
@@ -185,17 +200,114 @@ where
         + SelfOps,
 {
     fn link_syscalls(linker: &mut Linker<K>) -> anyhow::Result<()> {
+        // Link the standard filecoin syscalls first...
         DefaultFilecoinKernel::<K::CallManager>::link_syscalls(linker)?;
 
+        // ...then our own.
         linker.link_syscall("my_custom_kernel", "my_custom_syscall", my_custom_syscall)?;
 
         Ok(())
     }
 }
 
+/// The syscall binding: unpacks the arguments from wasm and calls into the kernel.
 pub fn my_custom_syscall(
     context: fvm::syscalls::Context<'_, impl CustomKernel>,
     doubleme: i32,
 ) -> Result<i32> {
     context.kernel.my_custom_syscall(doubleme)
 }
+
+#[cfg(test)]
+mod tests {
+    use fvm::call_manager::DefaultCallManager;
+    use fvm::engine::{Diagnostic, EnginePool};
+    use fvm::externs::{Chain, Consensus, Externs, Rand};
+    use fvm::machine::{DefaultMachine, NetworkConfig};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::clock::ChainEpoch;
+    use fvm_shared::version::NetworkVersion;
+
+    use super::*;
+
+    struct NoExterns;
+
+    impl Externs for NoExterns {}
+
+    impl Rand for NoExterns {
+        fn get_chain_randomness(&self, _: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+            unimplemented!()
+        }
+
+        fn get_beacon_randomness(&self, _: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+            unimplemented!()
+        }
+    }
+
+    impl Consensus for NoExterns {
+        fn verify_consensus_fault(
+            &self,
+            _: &[u8],
+            _: &[u8],
+            _: &[u8],
+        ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+            unimplemented!()
+        }
+    }
+
+    impl Chain for NoExterns {
+        fn get_tipset_cid(&self, _: ChainEpoch) -> anyhow::Result<Cid> {
+            unimplemented!()
+        }
+    }
+
+    type TestKernel =
+        DefaultCustomKernel<DefaultCallManager<DefaultMachine<MemoryBlockstore, NoExterns>>>;
+
+    #[test]
+    fn host_modules() {
+        // An actor whose `invoke` returns 0.
+        #[rustfmt::skip]
+        let wasm = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+            0x03, 0x02, 0x01, 0x00,
+            0x05, 0x03, 0x01, 0x00, 0x01,
+            0x07, 0x13, 0x02,
+            0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+            0x06, b'i', b'n', b'v', b'o', b'k', b'e', 0x00, 0x00,
+            0x0a, 0x06, 0x01, 0x04, 0x00, 0x41, 0x00, 0x0b,
+        ];
+        let new_engine =
+            || EnginePool::new((&NetworkConfig::new(NetworkVersion::V21)).into()).unwrap();
+
+        // Host modules can add namespaces...
+        let engine = new_engine();
+        engine
+            .register_syscalls::<TestKernel>("my_chain", |ns| {
+                ns.link_syscall("my_custom_syscall", my_custom_syscall)?;
+                Ok(())
+            })
+            .unwrap();
+        let report = engine
+            .acquire()
+            .preflight_validate::<TestKernel>(&wasm, NetworkVersion::V21);
+        assert!(report.is_valid(), "{report:?}");
+
+        // ...but not extend (or override) the kernel's.
+        let engine = new_engine();
+        engine
+            .register_syscalls::<TestKernel>("my_custom_kernel", |ns| {
+                ns.link_syscall("my_custom_syscall", my_custom_syscall)?;
+                Ok(())
+            })
+            .unwrap();
+        let report = engine
+            .acquire()
+            .preflight_validate::<TestKernel>(&wasm, NetworkVersion::V21);
+        match &report.diagnostics[..] {
+            [Diagnostic::Link(err)] => assert!(err.contains("collides"), "{err}"),
+            _ => panic!("{report:?}"),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+/*
+ * Copyright 2021-2023 Protocol Labs
+ * SPDX-License-Identifier: Apache-2.0, MIT
+ *
+ * A C ABI for embedding the Filecoin Virtual Machine. See README.md.
+ */
+
+#ifndef FVM_FFI_H
+#define FVM_FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The status returned by every function. */
+enum fvm_status {
+    FVM_OK = 0,
+    /* The call failed; the executor (if any) can still be used. */
+    FVM_ERROR = 1,
+    /* The executor hit a fatal error and can no longer be used, except to free it. */
+    FVM_FATAL = 2,
+};
+
+/* The value of `apply_kind` in fvm_executor_apply_message. */
+enum fvm_apply_kind {
+    FVM_APPLY_EXPLICIT = 0,
+    FVM_APPLY_IMPLICIT = 1,
+    /* Applied like an explicit message, then reverted: nothing is committed to the state. */
+    FVM_APPLY_DRY_RUN = 2,
+};
+
+/* The value of `format` in fvm_executor_last_trace. */
+enum fvm_trace_format {
+    /* Chrome trace event JSON, measured in gas. */
+    FVM_TRACE_CHROME = 0,
+    /* Folded stacks, as consumed by flamegraph tools, measured in gas. */
+    FVM_TRACE_FOLDED_STACKS = 1,
+};
+
+/* A byte buffer allocated by the FVM. Release it with fvm_buffer_free. */
+typedef struct fvm_buffer {
+    uint8_t *data;
+    size_t len;
+} fvm_buffer;
+
+/*
+ * A blockstore implemented by the client. CIDs are passed in their binary form. Every callback
+ * receives `ctx`.
+ */
+typedef struct fvm_blockstore {
+    void *ctx;
+    /*
+     * Returns 0 and points `data` and `data_len` at the block if found, 1 if not found, and any
+     * other value on error. The block must remain valid until it's passed to `release`.
+     */
+    int32_t (*get)(void *ctx, const uint8_t *cid, size_t cid_len, const uint8_t **data,
+                   size_t *data_len);
+    /* Stores a block. Returns 0 on success. */
+    int32_t (*put)(void *ctx, const uint8_t *cid, size_t cid_len, const uint8_t *data,
+                   size_t data_len);
+    /* Releases a block returned by `get`, once the FVM has copied it. May be NULL. */
+    void (*release)(void *ctx, const uint8_t *data, size_t data_len);
+} fvm_blockstore;
+
+/*
+ * The chain data and consensus checks the FVM needs from the client. Every callback receives
+ * `ctx`, and returns 0 on success. NULL callbacks are treated as unsupported: calling them fails
+ * the current message.
+ */
+typedef struct fvm_externs {
+    void *ctx;
+    int32_t (*get_chain_randomness)(void *ctx, int64_t epoch, uint8_t (*out)[32]);
+    int32_t (*get_beacon_randomness)(void *ctx, int64_t epoch, uint8_t (*out)[32]);
+    /* Writes the binary CID of the tipset at `epoch` (at most `out_cap` bytes) into `out`. */
+    int32_t (*get_tipset_cid)(void *ctx, int64_t epoch, uint8_t *out, size_t out_cap,
+                              size_t *out_len);
+    /*
+     * Returns 0 and writes the fault into `target` (an actor ID), `epoch` and `fault_type` if
+     * there is one, 1 if there's none, and any other value on error. Writes the gas used into
+     * `gas_used` in both cases.
+     */
+    int32_t (*verify_consensus_fault)(void *ctx, const uint8_t *h1, size_t h1_len,
+                                      const uint8_t *h2, size_t h2_len, const uint8_t *extra,
+                                      size_t extra_len, uint64_t *target, int64_t *epoch,
+                                      uint8_t *fault_type, int64_t *gas_used);
+} fvm_externs;
+
+typedef struct fvm_executor fvm_executor;
+
+/* Returns the version of this library. */
+const char *fvm_ffi_version(void);
+
+/*
+ * Creates an executor for the machine configured by the JSON document `config`. The callbacks
+ * (and their contexts) must remain valid until the executor is freed.
+ *
+ * On failure, every function writes an error message into `err` (unless NULL).
+ */
+int32_t fvm_executor_new(const uint8_t *config, size_t config_len, fvm_blockstore blockstore,
+                         fvm_externs externs, fvm_executor **out, fvm_buffer *err);
+
+/*
+ * Applies a CBOR-encoded message, writing the result as JSON into `out`. `raw_length` is the
+ * length of the message as included on chain (including its signature).
+ */
+int32_t fvm_executor_apply_message(fvm_executor *executor, const uint8_t *message,
+                                   size_t message_len, uint32_t apply_kind, size_t raw_length,
+                                   fvm_buffer *out, fvm_buffer *err);
+
+/* Writes the execution trace of the last message applied (when tracing) into `out`. */
+int32_t fvm_executor_last_trace(fvm_executor *executor, uint32_t format, fvm_buffer *out,
+                                fvm_buffer *err);
+
+/* Flushes the state tree, writing the new state root (a binary CID) into `out`. */
+int32_t fvm_executor_flush(fvm_executor *executor, fvm_buffer *out, fvm_buffer *err);
+
+/* Frees an executor. Does nothing if `executor` is NULL. */
+void fvm_executor_free(fvm_executor *executor);
+
+/* Frees a buffer returned by the FVM, resetting it to empty. */
+void fvm_buffer_free(fvm_buffer *buf);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FVM_FFI_H */
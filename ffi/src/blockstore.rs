@@ -0,0 +1,87 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::ffi::c_void;
+use std::{ptr, slice};
+
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+/// A blockstore implemented by the client through callbacks. See `fvm_blockstore` in
+/// `fvm_ffi.h`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct FvmBlockstore {
+    /// Passed to every callback.
+    pub ctx: *mut c_void,
+    /// Looks up the block with the given (binary) CID. Returns 0 and points `data` and `data_len`
+    /// at the block if found, 1 if not found, and any other value on error. The block must remain
+    /// valid until it's passed to `release`.
+    pub get: Option<
+        unsafe extern "C" fn(
+            ctx: *mut c_void,
+            cid: *const u8,
+            cid_len: usize,
+            data: *mut *const u8,
+            data_len: *mut usize,
+        ) -> i32,
+    >,
+    /// Stores a block under the given (binary) CID. Returns 0 on success.
+    pub put: Option<
+        unsafe extern "C" fn(
+            ctx: *mut c_void,
+            cid: *const u8,
+            cid_len: usize,
+            data: *const u8,
+            data_len: usize,
+        ) -> i32,
+    >,
+    /// Releases a block returned by `get`, once the FVM has copied it. May be null.
+    pub release: Option<unsafe extern "C" fn(ctx: *mut c_void, data: *const u8, data_len: usize)>,
+}
+
+impl Blockstore for FvmBlockstore {
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        let get = self
+            .get
+            .ok_or_else(|| anyhow!("blockstore has no get callback"))?;
+        let key = k.to_bytes();
+        let mut data = ptr::null();
+        let mut data_len = 0;
+        match unsafe { get(self.ctx, key.as_ptr(), key.len(), &mut data, &mut data_len) } {
+            0 => {
+                let block = if data_len == 0 {
+                    Vec::new()
+                } else {
+                    unsafe { slice::from_raw_parts(data, data_len) }.to_vec()
+                };
+                if let Some(release) = self.release {
+                    unsafe { release(self.ctx, data, data_len) };
+                }
+                Ok(Some(block))
+            }
+            1 => Ok(None),
+            code => Err(anyhow!("blockstore failed to get block {k}: code {code}")),
+        }
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        let put = self
+            .put
+            .ok_or_else(|| anyhow!("blockstore has no put callback"))?;
+        let key = k.to_bytes();
+        match unsafe {
+            put(
+                self.ctx,
+                key.as_ptr(),
+                key.len(),
+                block.as_ptr(),
+                block.len(),
+            )
+        } {
+            0 => Ok(()),
+            code => Err(anyhow!("blockstore failed to put block {k}: code {code}")),
+        }
+    }
+}
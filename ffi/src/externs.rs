@@ -0,0 +1,144 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::ffi::c_void;
+
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use fvm::externs::{Chain, Consensus, Externs, Rand};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::{ConsensusFault, ConsensusFaultType};
+use fvm_shared::MAX_CID_LEN;
+use num_traits::FromPrimitive;
+
+/// The chain data and consensus checks the FVM needs from the client, implemented through
+/// callbacks. See `fvm_externs` in `fvm_ffi.h`.
+///
+/// Null callbacks are treated as unsupported: calling them fails the current message.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct FvmExterns {
+    /// Passed to every callback.
+    pub ctx: *mut c_void,
+    /// Writes the chain randomness for `epoch` into `out`. Returns 0 on success.
+    pub get_chain_randomness:
+        Option<unsafe extern "C" fn(ctx: *mut c_void, epoch: i64, out: *mut [u8; 32]) -> i32>,
+    /// Writes the beacon randomness for `epoch` into `out`. Returns 0 on success.
+    pub get_beacon_randomness:
+        Option<unsafe extern "C" fn(ctx: *mut c_void, epoch: i64, out: *mut [u8; 32]) -> i32>,
+    /// Writes the (binary) CID of the tipset at `epoch` into `out`, which has room for `out_cap`
+    /// bytes, and its length into `out_len`. Returns 0 on success.
+    pub get_tipset_cid: Option<
+        unsafe extern "C" fn(
+            ctx: *mut c_void,
+            epoch: i64,
+            out: *mut u8,
+            out_cap: usize,
+            out_len: *mut usize,
+        ) -> i32,
+    >,
+    /// Checks the block headers `h1`, `h2` (and `extra`) for a consensus fault, writing the gas
+    /// used into `gas_used`. Returns 0 and writes the fault into `target`, `epoch` and
+    /// `fault_type` if there is one, 1 if there's none, and any other value on error.
+    pub verify_consensus_fault: Option<VerifyConsensusFaultFn>,
+}
+
+/// See [`FvmExterns::verify_consensus_fault`].
+pub type VerifyConsensusFaultFn = unsafe extern "C" fn(
+    ctx: *mut c_void,
+    h1: *const u8,
+    h1_len: usize,
+    h2: *const u8,
+    h2_len: usize,
+    extra: *const u8,
+    extra_len: usize,
+    target: *mut u64,
+    epoch: *mut i64,
+    fault_type: *mut u8,
+    gas_used: *mut i64,
+) -> i32;
+
+impl Externs for FvmExterns {}
+
+impl Rand for FvmExterns {
+    fn get_chain_randomness(&self, round: ChainEpoch) -> Result<[u8; 32]> {
+        let f = self
+            .get_chain_randomness
+            .ok_or_else(|| anyhow!("chain randomness is not supported"))?;
+        let mut out = [0u8; 32];
+        match unsafe { f(self.ctx, round, &mut out) } {
+            0 => Ok(out),
+            code => Err(anyhow!("failed to get chain randomness: code {code}")),
+        }
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> Result<[u8; 32]> {
+        let f = self
+            .get_beacon_randomness
+            .ok_or_else(|| anyhow!("beacon randomness is not supported"))?;
+        let mut out = [0u8; 32];
+        match unsafe { f(self.ctx, round, &mut out) } {
+            0 => Ok(out),
+            code => Err(anyhow!("failed to get beacon randomness: code {code}")),
+        }
+    }
+}
+
+impl Chain for FvmExterns {
+    fn get_tipset_cid(&self, epoch: ChainEpoch) -> Result<Cid> {
+        let f = self
+            .get_tipset_cid
+            .ok_or_else(|| anyhow!("tipset CIDs are not supported"))?;
+        let mut out = [0u8; MAX_CID_LEN];
+        let mut len = 0;
+        match unsafe { f(self.ctx, epoch, out.as_mut_ptr(), out.len(), &mut len) } {
+            0 if len <= out.len() => Ok(Cid::read_bytes(&out[..len])?),
+            0 => Err(anyhow!("tipset CID is too long: {len} bytes")),
+            code => Err(anyhow!("failed to get the tipset CID: code {code}")),
+        }
+    }
+}
+
+impl Consensus for FvmExterns {
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> Result<(Option<ConsensusFault>, i64)> {
+        let f = self
+            .verify_consensus_fault
+            .ok_or_else(|| anyhow!("consensus fault verification is not supported"))?;
+        let (mut target, mut epoch, mut fault_type, mut gas_used) = (0, 0, 0, 0);
+        let code = unsafe {
+            f(
+                self.ctx,
+                h1.as_ptr(),
+                h1.len(),
+                h2.as_ptr(),
+                h2.len(),
+                extra.as_ptr(),
+                extra.len(),
+                &mut target,
+                &mut epoch,
+                &mut fault_type,
+                &mut gas_used,
+            )
+        };
+        match code {
+            0 => {
+                let fault_type = ConsensusFaultType::from_u8(fault_type)
+                    .ok_or_else(|| anyhow!("invalid consensus fault type {fault_type}"))?;
+                let fault = ConsensusFault {
+                    target: Address::new_id(target),
+                    epoch,
+                    fault_type,
+                };
+                Ok((Some(fault), gas_used))
+            }
+            1 => Ok((None, gas_used)),
+            code => Err(anyhow!("failed to verify the consensus fault: code {code}")),
+        }
+    }
+}
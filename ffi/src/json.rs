@@ -0,0 +1,179 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! The JSON documents exchanged with the client. Chain types use the Lotus-compatible encodings
+//! of [`fvm_shared::json`], and field names follow Lotus's (PascalCase) conventions.
+
+use cid::Cid;
+use fvm::executor::{ApplyRet, FeeDetails};
+use fvm::machine::{MachineContext, NetworkConfig};
+use fvm::trace::CallFrameSummary;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::event::StampedEvent;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum};
+use serde::{Deserialize, Serialize};
+
+/// The configuration of a machine, as passed to `fvm_executor_new`.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct MachineConfig {
+    pub network_version: u32,
+    #[serde(rename = "ChainID")]
+    pub chain_id: Option<u64>,
+    pub epoch: ChainEpoch,
+    #[serde(default)]
+    pub timestamp: u64,
+    #[serde(with = "fvm_shared::json")]
+    pub state_root: Cid,
+    #[serde(default, with = "fvm_shared::json")]
+    pub base_fee: Option<TokenAmount>,
+    #[serde(default, with = "fvm_shared::json")]
+    pub circ_supply: Option<TokenAmount>,
+    /// Overrides the built-in actors manifest recorded in the state tree.
+    #[serde(default, with = "fvm_shared::json")]
+    pub builtin_actors: Option<Cid>,
+    #[serde(default)]
+    pub tracing: bool,
+    #[serde(default)]
+    pub actor_debugging: bool,
+}
+
+impl MachineConfig {
+    pub fn to_context(&self) -> anyhow::Result<MachineContext> {
+        let mut nc = NetworkConfig::try_new(NetworkVersion::from(self.network_version))
+            .ok_or_else(|| {
+                anyhow::anyhow!("unsupported network version {}", self.network_version)
+            })?;
+        if let Some(chain_id) = self.chain_id {
+            nc.chain_id(chain_id.into());
+        }
+        if let Some(manifest) = self.builtin_actors {
+            nc.override_actors(manifest);
+        }
+        if self.actor_debugging {
+            nc.enable_actor_debugging();
+        }
+        let mut mc = nc.for_epoch(self.epoch, self.timestamp, self.state_root);
+        if let Some(base_fee) = &self.base_fee {
+            mc.set_base_fee(base_fee.clone());
+        }
+        if let Some(circ_supply) = &self.circ_supply {
+            mc.set_circulating_supply(circ_supply.clone());
+        }
+        if self.tracing {
+            mc.enable_tracing();
+        }
+        Ok(mc)
+    }
+}
+
+/// The result of applying a message, as returned by `fvm_executor_apply_message`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApplyResult {
+    #[serde(with = "fvm_shared::json")]
+    pub receipt: Receipt,
+    #[serde(with = "fvm_shared::json")]
+    pub penalty: TokenAmount,
+    #[serde(with = "fvm_shared::json")]
+    pub miner_tip: TokenAmount,
+    #[serde(with = "fvm_shared::json")]
+    pub base_fee_burn: TokenAmount,
+    #[serde(with = "fvm_shared::json")]
+    pub over_estimation_burn: TokenAmount,
+    #[serde(with = "fvm_shared::json")]
+    pub refund: TokenAmount,
+    pub gas_refund: u64,
+    pub gas_burned: u64,
+    pub failure_info: Option<String>,
+    #[serde(with = "fvm_shared::json")]
+    pub events: Vec<StampedEvent>,
+    /// Only recorded when tracing.
+    pub call_frames: Vec<CallFrame>,
+    pub dry_run: bool,
+    /// Only for explicit and dry-run messages that passed validation.
+    pub fee_details: Option<Fees>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Fees {
+    pub origin: ActorID,
+    pub origin_nonce: u64,
+    #[serde(with = "fvm_shared::json")]
+    pub base_fee: TokenAmount,
+    #[serde(with = "fvm_shared::json")]
+    pub gas_fee_cap: TokenAmount,
+    #[serde(with = "fvm_shared::json")]
+    pub gas_premium: TokenAmount,
+    #[serde(with = "fvm_shared::json")]
+    pub effective_premium: TokenAmount,
+}
+
+impl From<&FeeDetails> for Fees {
+    fn from(fees: &FeeDetails) -> Self {
+        Fees {
+            origin: fees.origin,
+            origin_nonce: fees.origin_nonce,
+            base_fee: fees.base_fee.clone(),
+            gas_fee_cap: fees.gas_fee_cap.clone(),
+            gas_premium: fees.gas_premium.clone(),
+            effective_premium: fees.effective_premium.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct CallFrame {
+    pub depth: u32,
+    pub caller: ActorID,
+    #[serde(with = "fvm_shared::json")]
+    pub callee: Address,
+    pub method: MethodNum,
+    pub exit_code: Option<u32>,
+    pub gas_used: u64,
+    pub self_gas_used: u64,
+    pub events_emitted: u64,
+    pub blocks_written: u64,
+}
+
+impl From<&CallFrameSummary> for CallFrame {
+    fn from(frame: &CallFrameSummary) -> Self {
+        CallFrame {
+            depth: frame.depth,
+            caller: frame.caller,
+            callee: frame.callee,
+            method: frame.method,
+            exit_code: frame.exit_code.map(|c| c.value()),
+            gas_used: frame.gas_used.round_up(),
+            self_gas_used: frame.self_gas_used.round_up(),
+            events_emitted: frame.events_emitted,
+            blocks_written: frame.blocks_written,
+        }
+    }
+}
+
+impl From<&ApplyRet> for ApplyResult {
+    fn from(ret: &ApplyRet) -> Self {
+        ApplyResult {
+            receipt: ret.msg_receipt.clone(),
+            penalty: ret.penalty.clone(),
+            miner_tip: ret.miner_tip.clone(),
+            base_fee_burn: ret.base_fee_burn.clone(),
+            over_estimation_burn: ret.over_estimation_burn.clone(),
+            refund: ret.refund.clone(),
+            gas_refund: ret.gas_refund,
+            gas_burned: ret.gas_burned,
+            failure_info: ret.failure_info.as_ref().map(ToString::to_string),
+            events: ret.events.clone(),
+            call_frames: ret.call_frames.iter().map(CallFrame::from).collect(),
+            dry_run: ret.dry_run,
+            fee_details: ret.fee_details.as_ref().map(Fees::from),
+        }
+    }
+}
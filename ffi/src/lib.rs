@@ -0,0 +1,614 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! A C ABI for embedding the FVM in non-Rust clients. The API is declared in
+//! `include/fvm_ffi.h`; see the README for an overview.
+//!
+//! The client provides the blockstore and the chain data the FVM needs through callbacks
+//! ([`FvmBlockstore`], [`FvmExterns`]), configures the machine with a JSON document, passes
+//! messages as CBOR, and receives the results as JSON. Every function returns an [`FvmStatus`] and,
+//! on failure, writes an error message into the `err` buffer (if not null). Buffers returned by
+//! the FVM must be released with [`fvm_buffer_free`].
+
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use anyhow::{anyhow, Context};
+use fvm::call_manager::DefaultCallManager;
+use fvm::engine::{EngineConfig, EnginePool};
+use fvm::executor::{ApplyKind, DefaultExecutor, Executor};
+use fvm::kernel::filecoin::DefaultFilecoinKernel;
+use fvm::machine::DefaultMachine;
+use fvm::trace::export::{to_chrome_trace, to_folded_stacks, Timeline};
+use fvm::trace::ExecutionTrace;
+use fvm_shared::message::Message;
+
+mod blockstore;
+mod externs;
+mod json;
+
+pub use blockstore::FvmBlockstore;
+pub use externs::FvmExterns;
+
+type Kernel = DefaultFilecoinKernel<DefaultCallManager<DefaultMachine<FvmBlockstore, FvmExterns>>>;
+
+/// The status returned by every function.
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FvmStatus {
+    Ok = 0,
+    /// The call failed; the executor (if any) can still be used.
+    Error = 1,
+    /// The executor hit a fatal error (or a bug) and can no longer be used, except to free it.
+    Fatal = 2,
+}
+
+/// A byte buffer allocated by the FVM. Release it with [`fvm_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct FvmBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl FvmBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        FvmBuffer { data, len }
+    }
+}
+
+/// An executor applying messages on top of a state tree. Create it with [`fvm_executor_new`] and
+/// free it with [`fvm_executor_free`].
+pub struct FvmExecutor {
+    /// `None` once the executor has been poisoned.
+    executor: Option<DefaultExecutor<Kernel>>,
+    /// The execution trace of the last message applied (empty unless tracing).
+    last_trace: ExecutionTrace,
+}
+
+/// The format of the traces returned by [`fvm_executor_last_trace`].
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FvmTraceFormat {
+    /// Chrome trace event JSON, measured in gas.
+    Chrome = 0,
+    /// Folded stacks, as consumed by flamegraph tools, measured in gas.
+    FoldedStacks = 1,
+}
+
+/// Writes `bytes` to `out`, if not null.
+unsafe fn write_buffer(out: *mut FvmBuffer, bytes: Vec<u8>) {
+    if let Some(out) = out.as_mut() {
+        *out = FvmBuffer::new(bytes);
+    }
+}
+
+/// Runs `f`, reporting errors and panics through `err`.
+fn guard(err: *mut FvmBuffer, f: impl FnOnce() -> Result<(), (FvmStatus, anyhow::Error)>) -> i32 {
+    let (status, e) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return FvmStatus::Ok as i32,
+        Ok(Err((status, e))) => (status, e),
+        Err(panic) => {
+            let msg = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".into());
+            (FvmStatus::Fatal, anyhow!("panicked: {msg}"))
+        }
+    };
+    unsafe { write_buffer(err, format!("{e:#}").into_bytes()) };
+    status as i32
+}
+
+/// Runs `f` on the executor behind `executor` like [`guard`], poisoning the executor if `f`
+/// panics, as its state can't be trusted anymore.
+unsafe fn guard_executor(
+    executor: *mut FvmExecutor,
+    err: *mut FvmBuffer,
+    f: impl FnOnce(&mut FvmExecutor) -> Result<(), (FvmStatus, anyhow::Error)>,
+) -> i32 {
+    guard(err, || {
+        let handle = executor
+            .as_mut()
+            .ok_or_else(|| error(anyhow!("null executor")))?;
+        match catch_unwind(AssertUnwindSafe(|| f(&mut *handle))) {
+            Ok(res) => res,
+            Err(panic) => {
+                handle.executor = None;
+                resume_unwind(panic)
+            }
+        }
+    })
+}
+
+fn error(e: anyhow::Error) -> (FvmStatus, anyhow::Error) {
+    (FvmStatus::Error, e)
+}
+
+unsafe fn input<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
+impl FvmExecutor {
+    fn executor(&mut self) -> Result<&mut DefaultExecutor<Kernel>, (FvmStatus, anyhow::Error)> {
+        self.executor
+            .as_mut()
+            .ok_or_else(|| (FvmStatus::Fatal, anyhow!("executor is poisoned")))
+    }
+}
+
+/// Returns the version of this library, as a NUL-terminated string.
+#[no_mangle]
+pub extern "C" fn fvm_ffi_version() -> *const c_char {
+    static VERSION: &CStr =
+        match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+            Ok(v) => v,
+            Err(_) => panic!("invalid version"),
+        };
+    VERSION.as_ptr()
+}
+
+/// Creates an executor for the machine configured by the JSON document `config`, reading and
+/// writing blocks through `blockstore`, and writes it to `out`.
+///
+/// # Safety
+///
+/// `config` must point to `config_len` readable bytes, `out` must be writable, and the callbacks
+/// (and their contexts) must remain valid until the executor is freed.
+#[no_mangle]
+pub unsafe extern "C" fn fvm_executor_new(
+    config: *const u8,
+    config_len: usize,
+    blockstore: FvmBlockstore,
+    externs: FvmExterns,
+    out: *mut *mut FvmExecutor,
+    err: *mut FvmBuffer,
+) -> i32 {
+    guard(err, || {
+        let config: json::MachineConfig = serde_json::from_slice(input(config, config_len))
+            .context("invalid machine config")
+            .map_err(error)?;
+        let context = config.to_context().map_err(error)?;
+        let machine = DefaultMachine::new(&context, blockstore, externs)
+            .context("failed to create the machine")
+            .map_err(error)?;
+        let engine = EnginePool::new(EngineConfig::from(&context.network)).map_err(error)?;
+        let executor = DefaultExecutor::new(engine, machine)
+            .context("failed to create the executor")
+            .map_err(error)?;
+        let executor = Box::new(FvmExecutor {
+            executor: Some(executor),
+            last_trace: Vec::new(),
+        });
+        *out = Box::into_raw(executor);
+        Ok(())
+    })
+}
+
+/// Applies a CBOR-encoded message, writing the result (see the README) as JSON to `out`.
+/// `apply_kind` is 0 for explicit (on-chain) messages, 1 for implicit (system) messages, and 2 for
+/// dry runs (explicit messages whose effects are reverted), and `raw_length` is the length of the
+/// message as included on chain (including its signature).
+///
+/// # Safety
+///
+/// `executor` must have been created by [`fvm_executor_new`], and `message` must point to
+/// `message_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fvm_executor_apply_message(
+    executor: *mut FvmExecutor,
+    message: *const u8,
+    message_len: usize,
+    apply_kind: u32,
+    raw_length: usize,
+    out: *mut FvmBuffer,
+    err: *mut FvmBuffer,
+) -> i32 {
+    guard_executor(executor, err, |handle| {
+        let message: Message = fvm_ipld_encoding::from_slice(input(message, message_len))
+            .context("invalid message")
+            .map_err(error)?;
+        let apply_kind = match apply_kind {
+            0 => ApplyKind::Explicit,
+            1 => ApplyKind::Implicit,
+            2 => ApplyKind::DryRun,
+            kind => return Err(error(anyhow!("invalid apply kind {kind}"))),
+        };
+        let ret = match handle
+            .executor()?
+            .execute_message(message, apply_kind, raw_length)
+        {
+            Ok(ret) => ret,
+            Err(e) => {
+                handle.executor = None;
+                return Err((FvmStatus::Fatal, e));
+            }
+        };
+        let result =
+            serde_json::to_vec(&json::ApplyResult::from(&ret)).map_err(|e| error(e.into()))?;
+        handle.last_trace = ret.exec_trace;
+        write_buffer(out, result);
+        Ok(())
+    })
+}
+
+/// Returns the execution trace of the last message applied (only recorded if the machine was
+/// configured with `"Tracing": true`) in the given format.
+///
+/// # Safety
+///
+/// `executor` must have been created by [`fvm_executor_new`].
+#[no_mangle]
+pub unsafe extern "C" fn fvm_executor_last_trace(
+    executor: *mut FvmExecutor,
+    format: u32,
+    out: *mut FvmBuffer,
+    err: *mut FvmBuffer,
+) -> i32 {
+    guard_executor(executor, err, |handle| {
+        let trace = match format {
+            0 => to_chrome_trace(&handle.last_trace, Timeline::Gas),
+            1 => to_folded_stacks(&handle.last_trace, Timeline::Gas),
+            format => return Err(error(anyhow!("invalid trace format {format}"))),
+        };
+        write_buffer(out, trace.into_bytes());
+        Ok(())
+    })
+}
+
+/// Flushes the state tree to the blockstore, writing the new state root (as a binary CID) to
+/// `out`.
+///
+/// # Safety
+///
+/// `executor` must have been created by [`fvm_executor_new`].
+#[no_mangle]
+pub unsafe extern "C" fn fvm_executor_flush(
+    executor: *mut FvmExecutor,
+    out: *mut FvmBuffer,
+    err: *mut FvmBuffer,
+) -> i32 {
+    guard_executor(executor, err, |handle| {
+        let root = match handle.executor()?.flush() {
+            Ok(root) => root,
+            Err(e) => {
+                handle.executor = None;
+                return Err((FvmStatus::Fatal, e));
+            }
+        };
+        write_buffer(out, root.to_bytes());
+        Ok(())
+    })
+}
+
+/// Frees an executor. Does nothing if `executor` is null.
+///
+/// # Safety
+///
+/// `executor` must have been created by [`fvm_executor_new`], and not freed already.
+#[no_mangle]
+pub unsafe extern "C" fn fvm_executor_free(executor: *mut FvmExecutor) {
+    if !executor.is_null() {
+        drop(Box::from_raw(executor));
+    }
+}
+
+/// Frees a buffer returned by the FVM, resetting it to empty.
+///
+/// # Safety
+///
+/// `buf` must be null, empty, or have been filled by the FVM (and not freed already).
+#[no_mangle]
+pub unsafe extern "C" fn fvm_buffer_free(buf: *mut FvmBuffer) {
+    let Some(buf) = buf.as_mut() else {
+        return;
+    };
+    if !buf.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buf.data, buf.len,
+        )));
+    }
+    buf.data = ptr::null_mut();
+    buf.len = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::ffi::c_void;
+
+    use cid::Cid;
+    use fvm_ipld_blockstore::Blockstore;
+    use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+    use multihash_codetable::Code;
+
+    use super::*;
+
+    type Store = HashMap<Vec<u8>, Vec<u8>>;
+
+    unsafe extern "C" fn get(
+        ctx: *mut c_void,
+        cid: *const u8,
+        cid_len: usize,
+        data: *mut *const u8,
+        data_len: *mut usize,
+    ) -> i32 {
+        let store = &*(ctx as *const Store);
+        match store.get(slice::from_raw_parts(cid, cid_len)) {
+            Some(block) => {
+                *data = block.as_ptr();
+                *data_len = block.len();
+                0
+            }
+            None => 1,
+        }
+    }
+
+    unsafe extern "C" fn put(
+        ctx: *mut c_void,
+        cid: *const u8,
+        cid_len: usize,
+        data: *const u8,
+        data_len: usize,
+    ) -> i32 {
+        let store = &mut *(ctx as *mut Store);
+        store.insert(
+            slice::from_raw_parts(cid, cid_len).to_vec(),
+            input(data, data_len).to_vec(),
+        );
+        0
+    }
+
+    fn blockstore(store: &mut Store) -> FvmBlockstore {
+        FvmBlockstore {
+            ctx: store as *mut Store as *mut c_void,
+            get: Some(get),
+            put: Some(put),
+            release: None,
+        }
+    }
+
+    fn no_externs() -> FvmExterns {
+        FvmExterns {
+            ctx: ptr::null_mut(),
+            get_chain_randomness: None,
+            get_beacon_randomness: None,
+            get_tipset_cid: None,
+            verify_consensus_fault: None,
+        }
+    }
+
+    fn take(buf: &mut FvmBuffer) -> String {
+        let s = String::from_utf8(unsafe { input(buf.data, buf.len) }.to_vec()).unwrap();
+        unsafe { fvm_buffer_free(buf) };
+        assert!(buf.data.is_null());
+        s
+    }
+
+    #[test]
+    fn callback_blockstore() {
+        let mut store = Store::new();
+        let bs = blockstore(&mut store);
+        let cid = bs.put_cbor(&(1u8, "a"), Code::Blake2b256).unwrap();
+        assert_eq!(cid.codec(), DAG_CBOR);
+        assert_eq!(
+            bs.get_cbor::<(u8, String)>(&cid).unwrap(),
+            Some((1, "a".into()))
+        );
+        let missing = Cid::default();
+        assert_eq!(bs.get(&missing).unwrap(), None);
+
+        let no_put = FvmBlockstore { put: None, ..bs };
+        assert!(no_put.put_keyed(&cid, b"x").is_err());
+    }
+
+    #[test]
+    fn executor_errors() {
+        let mut store = Store::new();
+        let mut exec = ptr::null_mut();
+        let mut err = FvmBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+
+        let config = br#"{"NetworkVersion": 21}"#;
+        let status = unsafe {
+            fvm_executor_new(
+                config.as_ptr(),
+                config.len(),
+                blockstore(&mut store),
+                no_externs(),
+                &mut exec,
+                &mut err,
+            )
+        };
+        assert_eq!(status, FvmStatus::Error as i32);
+        assert!(take(&mut err).starts_with("invalid machine config"));
+        assert!(exec.is_null());
+
+        // The state root isn't in the blockstore.
+        let root = Cid::default();
+        let config =
+            format!(r#"{{"NetworkVersion": 21, "Epoch": 0, "StateRoot": {{"/": "{root}"}}}}"#);
+        let status = unsafe {
+            fvm_executor_new(
+                config.as_ptr(),
+                config.len(),
+                blockstore(&mut store),
+                no_externs(),
+                &mut exec,
+                &mut err,
+            )
+        };
+        assert_eq!(status, FvmStatus::Error as i32);
+        let msg = take(&mut err);
+        assert!(msg.contains("state-root"), "{msg}");
+
+        let status = unsafe {
+            fvm_executor_apply_message(
+                ptr::null_mut(),
+                ptr::null(),
+                0,
+                0,
+                0,
+                ptr::null_mut(),
+                &mut err,
+            )
+        };
+        assert_eq!(status, FvmStatus::Error as i32);
+        assert_eq!(take(&mut err), "null executor");
+
+        unsafe { fvm_executor_free(ptr::null_mut()) };
+        let version = unsafe { CStr::from_ptr(fvm_ffi_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    }
+
+    /// Creates an executor over a state tree holding a single actor at ID 1000, which returns
+    /// without doing anything, with tracing enabled.
+    fn new_executor(store: &mut Store) -> *mut FvmExecutor {
+        use fvm::machine::Manifest;
+        use fvm::state_tree::{ActorState, StateTree};
+        use fvm_ipld_blockstore::Block;
+        use fvm_shared::state::StateTreeVersion;
+        use fvm_shared::IPLD_RAW;
+
+        #[rustfmt::skip]
+        const WASM: &[u8] = &[
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            // (type (func (param i32) (result i32)))
+            0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+            0x03, 0x02, 0x01, 0x00,
+            // (memory 1)
+            0x05, 0x03, 0x01, 0x00, 0x01,
+            // (export "memory" (memory 0)) (export "invoke" (func 0))
+            0x07, 0x13, 0x02,
+            0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+            0x06, b'i', b'n', b'v', b'o', b'k', b'e', 0x00, 0x00,
+            // (i32.const 0)
+            0x0a, 0x06, 0x01, 0x04, 0x00, 0x41, 0x00, 0x0b,
+        ];
+
+        let bs = blockstore(store);
+        let code = bs
+            .put(Code::Blake2b256, &Block::new(IPLD_RAW, WASM))
+            .unwrap();
+        let manifest = bs
+            .put_cbor(&Manifest::DUMMY_CODES, Code::Blake2b256)
+            .unwrap();
+        let actors = bs.put_cbor(&(1, manifest), Code::Blake2b256).unwrap();
+        let mut st = StateTree::new(bs, StateTreeVersion::V5).unwrap();
+        st.set_actor(
+            1000,
+            ActorState::new(code, fvm_shared::EMPTY_ARR_CID, Default::default(), 0, None),
+        );
+        let root = st.flush().unwrap();
+
+        let config = format!(
+            r#"{{"NetworkVersion": 21, "Epoch": 0, "StateRoot": {{"/": "{root}"}},
+                "BuiltinActors": {{"/": "{actors}"}}, "Tracing": true}}"#
+        );
+        let mut exec = ptr::null_mut();
+        let mut err = FvmBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let status = unsafe {
+            fvm_executor_new(
+                config.as_ptr(),
+                config.len(),
+                blockstore(store),
+                no_externs(),
+                &mut exec,
+                &mut err,
+            )
+        };
+        assert_eq!(status, FvmStatus::Ok as i32, "{}", take(&mut err));
+        exec
+    }
+
+    #[test]
+    fn executor_lifecycle() {
+        use fvm_shared::address::Address;
+
+        let mut store = Store::new();
+        let exec = new_executor(&mut store);
+        let mut out = FvmBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let mut err = FvmBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+
+        let message = fvm_ipld_encoding::to_vec(&Message {
+            version: 0,
+            from: Address::new_id(0),
+            to: Address::new_id(1000),
+            sequence: 0,
+            value: Default::default(),
+            method_num: 1,
+            params: Default::default(),
+            gas_limit: fvm_shared::BLOCK_GAS_LIMIT,
+            gas_fee_cap: Default::default(),
+            gas_premium: Default::default(),
+        })
+        .unwrap();
+        let status = unsafe {
+            fvm_executor_apply_message(
+                exec,
+                message.as_ptr(),
+                message.len(),
+                1,
+                0,
+                &mut out,
+                &mut err,
+            )
+        };
+        assert_eq!(status, FvmStatus::Ok as i32, "{}", take(&mut err));
+        let result: serde_json::Value = serde_json::from_str(&take(&mut out)).unwrap();
+        assert_eq!(result["Receipt"]["ExitCode"], 0);
+        assert_eq!(result["CallFrames"][0]["Callee"], "f01000");
+
+        let status = unsafe { fvm_executor_last_trace(exec, 1, &mut out, &mut err) };
+        assert_eq!(status, FvmStatus::Ok as i32, "{}", take(&mut err));
+        let stacks = take(&mut out);
+        assert!(stacks.contains("f01000"), "{stacks}");
+
+        let status = unsafe { fvm_executor_flush(exec, &mut out, &mut err) };
+        assert_eq!(status, FvmStatus::Ok as i32, "{}", take(&mut err));
+        let root = Cid::try_from(unsafe { input(out.data, out.len) }).unwrap();
+        unsafe { fvm_buffer_free(&mut out) };
+        assert!(blockstore(&mut store).has(&root).unwrap());
+
+        unsafe { fvm_executor_free(exec) };
+    }
+
+    #[test]
+    fn panics_poison_the_executor() {
+        let mut store = Store::new();
+        let exec = new_executor(&mut store);
+        let mut err = FvmBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+
+        let status = unsafe { guard_executor(exec, &mut err, |_| panic!("boom")) };
+        assert_eq!(status, FvmStatus::Fatal as i32);
+        assert_eq!(take(&mut err), "panicked: boom");
+
+        let status = unsafe { fvm_executor_flush(exec, ptr::null_mut(), &mut err) };
+        assert_eq!(status, FvmStatus::Fatal as i32);
+        assert_eq!(take(&mut err), "executor is poisoned");
+
+        unsafe { fvm_executor_free(exec) };
+    }
+}
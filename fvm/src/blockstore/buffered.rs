@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
 
 use anyhow::{anyhow, Result};
@@ -18,6 +18,18 @@ use fvm_shared::commcid::{FIL_COMMITMENT_SEALED, FIL_COMMITMENT_UNSEALED};
 pub struct BufferedBlockstore<BS> {
     base: BS,
     write: RefCell<HashMap<Cid, Vec<u8>>>,
+    read_only: bool,
+    trusted_writes: bool,
+    witness: RefCell<Option<Witness>>,
+}
+
+/// The blocks read since recording started.
+#[derive(Debug, Default)]
+struct Witness {
+    /// The CIDs of the blocks read, in the order in which they were first read.
+    read: Vec<Cid>,
+    /// All CIDs read or written since recording started.
+    seen: HashSet<Cid>,
 }
 
 impl<BS> BufferedBlockstore<BS>
@@ -28,9 +40,95 @@ where
         Self {
             base,
             write: Default::default(),
+            read_only: false,
+            trusted_writes: false,
+            witness: Default::default(),
         }
     }
 
+    /// Creates a read-only blockstore that doesn't buffer anything: all reads go directly to the
+    /// underlying store, and writing any block not already present in the underlying store fails.
+    pub fn read_only(base: BS) -> Self {
+        Self {
+            base,
+            write: Default::default(),
+            read_only: true,
+            trusted_writes: false,
+            witness: Default::default(),
+        }
+    }
+
+    /// Returns true if this blockstore refuses writes.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Sets whether the buffered blocks are trusted to match their CIDs. If so, [`Buffered::flush`]
+    /// writes them with [`Blockstore::put_many_keyed_unchecked`], letting the underlying store skip
+    /// re-hashing them.
+    pub fn set_trusted_writes(&mut self, trusted: bool) {
+        self.trusted_writes = trusted;
+    }
+
+    /// In read-only mode, only allow "writing" blocks that already exist in the base store.
+    fn check_writable(&self, cid: &Cid) -> Result<()> {
+        if self.read_only && !self.base.has(cid)? {
+            return Err(anyhow!(
+                "attempted to write block {} to a read-only blockstore",
+                cid
+            ));
+        }
+        Ok(())
+    }
+
+    /// Starts recording the CIDs of all blocks read from this blockstore, discarding any previous
+    /// recording. See [`BufferedBlockstore::take_witness`].
+    pub fn record_witness(&self) {
+        *self.witness.borrow_mut() = Some(Witness::default());
+    }
+
+    /// Stops recording and returns the CIDs of the blocks read since [`record_witness`] was
+    /// called, in the order in which they were first read. Blocks written (since recording
+    /// started) before being read aren't included, as they aren't part of the prior state.
+    ///
+    /// Returns an empty list if nothing was being recorded.
+    ///
+    /// [`record_witness`]: BufferedBlockstore::record_witness
+    pub fn take_witness(&self) -> Vec<Cid> {
+        self.witness
+            .borrow_mut()
+            .take()
+            .map(|w| w.read)
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of buffered blocks that haven't been flushed yet.
+    pub fn pending_len(&self) -> usize {
+        self.write.borrow().len()
+    }
+
+    /// Returns the CIDs of the buffered (unflushed) blocks reachable from `root`, i.e., the blocks
+    /// that [`Buffered::flush`] would write to the underlying store, in traversal order.
+    pub fn pending_reachable(&self, root: &Cid) -> Result<Vec<Cid>> {
+        find_reachable(&self.write.borrow(), [*root])
+    }
+
+    /// Discards all buffered blocks that aren't reachable from any of the given roots, returning
+    /// the number of blocks discarded. Unlike [`Buffered::flush`], this leaves the reachable blocks
+    /// buffered.
+    ///
+    /// This is useful to drop the blocks written by aborted (reverted) computations, keeping memory
+    /// usage bounded over long executions.
+    pub fn discard_unreachable(&self, roots: &[Cid]) -> Result<usize> {
+        let mut write = self.write.borrow_mut();
+        let reachable: HashSet<Cid> = find_reachable(&write, roots.iter().copied())?
+            .into_iter()
+            .collect();
+        let before = write.len();
+        write.retain(|k, _| reachable.contains(k));
+        Ok(before - write.len())
+    }
+
     pub fn into_inner(self) -> BS {
         self.base
     }
@@ -44,8 +142,12 @@ where
     /// This will recursively traverse the cache and write all data connected by links to this
     /// root Cid, moving the reachable blocks from the write buffer to the backing store.
     fn flush(&self, root: &Cid) -> Result<()> {
-        self.base
-            .put_many_keyed(take_reachable(&mut self.write.borrow_mut(), root)?)
+        let blocks = take_reachable(&mut self.write.borrow_mut(), root)?;
+        if self.trusted_writes {
+            self.base.put_many_keyed_unchecked(blocks)
+        } else {
+            self.base.put_many_keyed(blocks)
+        }
     }
 }
 
@@ -135,12 +237,36 @@ fn scan_for_links(mut buf: &[u8], out: &mut Vec<Cid>) -> Result<()> {
     Ok(())
 }
 
-/// Moves the IPLD DAG under `root` from the cache to the base store.
-fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(Cid, Vec<u8>)>> {
+const IDENTITY: u64 = 0x0;
+
+/// Checks that `k` may be linked from the state, returning false if it must not be traversed (i.e.,
+/// it's a piece commitment).
+fn check_link(k: &Cid) -> Result<bool> {
     const BLAKE2B_256: u64 = 0xb220;
     const BLAKE2B_LEN: u8 = 32;
-    const IDENTITY: u64 = 0x0;
 
+    // Check the codec.
+    match k.codec() {
+        // We ignore piece commitment CIDs.
+        FIL_COMMITMENT_UNSEALED | FIL_COMMITMENT_SEALED => return Ok(false),
+        // We allow raw, cbor, and dag cbor.
+        IPLD_RAW | DAG_CBOR | CBOR => (),
+        // Everything else is rejected.
+        codec => return Err(anyhow!("cid {k} has unexpected codec ({codec})")),
+    }
+    // Check the hash construction.
+    match (k.hash().code(), k.hash().size()) {
+        // Allow non-truncated blake2b-256 and identity hashes.
+        (BLAKE2B_256, BLAKE2B_LEN) | (IDENTITY, _) => Ok(true),
+        // Reject everything else.
+        (hash, length) => Err(anyhow!(
+            "cid {k} has unexpected multihash (code={hash}, len={length})"
+        )),
+    }
+}
+
+/// Moves the IPLD DAG under `root` from the cache to the base store.
+fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(Cid, Vec<u8>)>> {
     // Differences from lotus (vm.Copy):
     // 1. We assume that if we don't have a block in our buffer, it must already be in the client
     //    and don't check. This should only happen if the client is missing state.
@@ -151,25 +277,8 @@ fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(
     let mut result = Vec::new();
 
     while let Some(k) = stack.pop() {
-        // Check the codec.
-        match k.codec() {
-            // We ignore piece commitment CIDs.
-            FIL_COMMITMENT_UNSEALED | FIL_COMMITMENT_SEALED => continue,
-            // We allow raw, cbor, and dag cbor.
-            IPLD_RAW | DAG_CBOR | CBOR => (),
-            // Everything else is rejected.
-            codec => return Err(anyhow!("cid {k} has unexpected codec ({codec})")),
-        }
-        // Check the hash construction.
-        match (k.hash().code(), k.hash().size()) {
-            // Allow non-truncated blake2b-256 and identity hashes.
-            (BLAKE2B_256, BLAKE2B_LEN) | (IDENTITY, _) => (),
-            // Reject everything else.
-            (hash, length) => {
-                return Err(anyhow!(
-                    "cid {k} has unexpected multihash (code={hash}, len={length})"
-                ))
-            }
+        if !check_link(&k)? {
+            continue;
         }
         if k.hash().code() == IDENTITY {
             if k.codec() == DAG_CBOR {
@@ -198,19 +307,58 @@ fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(
     Ok(result)
 }
 
+/// Returns the CIDs of the blocks in the cache reachable from the given roots, traversing them the
+/// same way as [`take_reachable`] but leaving the cache untouched.
+fn find_reachable(
+    cache: &HashMap<Cid, Vec<u8>>,
+    roots: impl IntoIterator<Item = Cid>,
+) -> Result<Vec<Cid>> {
+    let mut stack: Vec<Cid> = roots.into_iter().collect();
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+
+    while let Some(k) = stack.pop() {
+        if !seen.insert(k) || !check_link(&k)? {
+            continue;
+        }
+        if k.hash().code() == IDENTITY {
+            if k.codec() == DAG_CBOR {
+                scan_for_links(k.hash().digest(), &mut stack)?;
+            }
+        } else if let Some(block) = cache.get(&k) {
+            if k.codec() == DAG_CBOR {
+                scan_for_links(block, &mut stack)?;
+            }
+            result.push(k);
+        }
+    }
+
+    Ok(result)
+}
+
 impl<BS> Blockstore for BufferedBlockstore<BS>
 where
     BS: Blockstore,
 {
     fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>> {
-        Ok(if let Some(data) = self.write.borrow().get(cid) {
+        let data = if let Some(data) = self.write.borrow().get(cid) {
             Some(data.clone())
         } else {
             self.base.get(cid)?
-        })
+        };
+        if let Some(witness) = &mut *self.witness.borrow_mut() {
+            if data.is_some() && witness.seen.insert(*cid) {
+                witness.read.push(*cid);
+            }
+        }
+        Ok(data)
     }
 
     fn put_keyed(&self, cid: &Cid, buf: &[u8]) -> Result<()> {
+        self.check_writable(cid)?;
+        if let Some(witness) = &mut *self.witness.borrow_mut() {
+            witness.seen.insert(*cid);
+        }
         self.write.borrow_mut().insert(*cid, Vec::from(buf));
         Ok(())
     }
@@ -229,9 +377,15 @@ where
         D: AsRef<[u8]>,
         I: IntoIterator<Item = (Cid, D)>,
     {
-        self.write
-            .borrow_mut()
-            .extend(blocks.into_iter().map(|(k, v)| (k, v.as_ref().into())));
+        let mut write = self.write.borrow_mut();
+        let mut witness = self.witness.borrow_mut();
+        for (k, v) in blocks {
+            self.check_writable(&k)?;
+            if let Some(witness) = &mut *witness {
+                witness.seen.insert(k);
+            }
+            write.insert(k, v.as_ref().into());
+        }
         Ok(())
     }
 }
@@ -241,11 +395,98 @@ mod tests {
     use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
     use fvm_ipld_encoding::CborStore;
     use fvm_shared::{commcid, IDENTITY_HASH};
-    use multihash_codetable::{Code, Multihash};
+    use multihash_codetable::{Code, Multihash, MultihashDigest};
     use serde::{Deserialize, Serialize};
 
     use super::*;
 
+    #[test]
+    fn witness() {
+        let mem = MemoryBlockstore::default();
+        let a = mem.put_cbor(&1u8, Code::Blake2b256).unwrap();
+        let b = mem.put_cbor(&2u8, Code::Blake2b256).unwrap();
+
+        let buf_store = BufferedBlockstore::new(&mem);
+        // Nothing is recorded until asked.
+        buf_store.get(&a).unwrap();
+        assert!(buf_store.take_witness().is_empty());
+
+        buf_store.record_witness();
+        assert_eq!(buf_store.get_cbor::<u8>(&b).unwrap(), Some(2));
+        assert_eq!(buf_store.get_cbor::<u8>(&a).unwrap(), Some(1));
+        assert_eq!(buf_store.get_cbor::<u8>(&b).unwrap(), Some(2));
+
+        // Blocks written before being read, and missing blocks, aren't recorded.
+        let c = buf_store.put_cbor(&3u8, Code::Blake2b256).unwrap();
+        assert_eq!(buf_store.get_cbor::<u8>(&c).unwrap(), Some(3));
+        let missing = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(b"missing"));
+        assert_eq!(buf_store.get(&missing).unwrap(), None);
+
+        assert_eq!(buf_store.take_witness(), vec![b, a]);
+        assert!(buf_store.take_witness().is_empty());
+    }
+
+    #[test]
+    fn trusted_writes() {
+        /// Records whether blocks were written through the unchecked path.
+        #[derive(Default)]
+        struct Store {
+            mem: MemoryBlockstore,
+            unchecked: std::cell::Cell<usize>,
+        }
+
+        impl Blockstore for Store {
+            fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+                self.mem.get(k)
+            }
+
+            fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+                self.mem.put_keyed(k, block)
+            }
+
+            fn put_many_keyed_unchecked<D, I>(&self, blocks: I) -> Result<()>
+            where
+                D: AsRef<[u8]>,
+                I: IntoIterator<Item = (Cid, D)>,
+            {
+                for (k, b) in blocks {
+                    self.unchecked.set(self.unchecked.get() + 1);
+                    self.mem.put_keyed(&k, b.as_ref())?;
+                }
+                Ok(())
+            }
+        }
+
+        let store = Store::default();
+        let mut buf_store = BufferedBlockstore::new(&store);
+        let a = buf_store.put_cbor(&1u8, Code::Blake2b256).unwrap();
+        buf_store.flush(&a).unwrap();
+        assert_eq!(store.unchecked.get(), 0);
+        assert!(store.has(&a).unwrap());
+
+        buf_store.set_trusted_writes(true);
+        let b = buf_store.put_cbor(&2u8, Code::Blake2b256).unwrap();
+        buf_store.flush(&b).unwrap();
+        assert_eq!(store.unchecked.get(), 1);
+        assert!(store.has(&b).unwrap());
+    }
+
+    #[test]
+    fn read_only_buffered_store() {
+        let mem = MemoryBlockstore::default();
+        let cid = mem.put_cbor(&8u8, Code::Blake2b256).unwrap();
+
+        let ro_store = BufferedBlockstore::read_only(&mem);
+        assert!(ro_store.is_read_only());
+        assert_eq!(ro_store.get_cbor::<u8>(&cid).unwrap(), Some(8));
+        assert!(ro_store.put_cbor(&9u8, Code::Blake2b256).is_err());
+        // Re-writing an existing block is allowed.
+        assert_eq!(ro_store.put_cbor(&8u8, Code::Blake2b256).unwrap(), cid);
+
+        // Flushing is a no-op as there's nothing buffered.
+        ro_store.flush(&cid).unwrap();
+    }
+
     #[test]
     fn basic_buffered_store() {
         let mem = MemoryBlockstore::default();
@@ -329,4 +570,43 @@ mod tests {
         assert_eq!(buf_store.get(&sealed_comm_cid).unwrap(), None);
         assert_eq!(mem.get_cbor::<u8>(&unconnected).unwrap(), None);
     }
+
+    #[test]
+    fn pending_blocks() {
+        let mem = MemoryBlockstore::default();
+        let flushed = mem.put_cbor(&0u8, Code::Blake2b256).unwrap();
+        let buf_store = BufferedBlockstore::new(&mem);
+
+        let leaf = buf_store.put_cbor(&1u8, Code::Blake2b256).unwrap();
+        let shared = buf_store.put_cbor(&(leaf, 2u8), Code::Blake2b256).unwrap();
+        let root_a = buf_store
+            .put_cbor(&(shared, leaf, flushed), Code::Blake2b256)
+            .unwrap();
+        let root_b = buf_store
+            .put_cbor(&(shared, 3u8), Code::Blake2b256)
+            .unwrap();
+        let garbage = buf_store.put_cbor(&4u8, Code::Blake2b256).unwrap();
+        assert_eq!(buf_store.pending_len(), 5);
+
+        // Only buffered blocks are listed, each once.
+        let mut pending = buf_store.pending_reachable(&root_a).unwrap();
+        pending.sort();
+        let mut expected = vec![root_a, shared, leaf];
+        expected.sort();
+        assert_eq!(pending, expected);
+        assert_eq!(buf_store.pending_len(), 5);
+
+        // Discarding keeps everything reachable from any of the roots.
+        assert_eq!(buf_store.discard_unreachable(&[root_a, root_b]).unwrap(), 1);
+        assert_eq!(buf_store.pending_len(), 4);
+        assert_eq!(buf_store.get(&garbage).unwrap(), None);
+
+        // Flushing one root leaves the blocks only reachable from the other one buffered.
+        buf_store.flush(&root_a).unwrap();
+        assert_eq!(buf_store.pending_reachable(&root_b).unwrap(), vec![root_b]);
+        assert_eq!(buf_store.pending_len(), 1);
+        assert_eq!(buf_store.discard_unreachable(&[]).unwrap(), 1);
+        assert_eq!(buf_store.get(&root_b).unwrap(), None);
+        assert_eq!(mem.get_cbor::<u8>(&leaf).unwrap(), Some(1));
+    }
 }
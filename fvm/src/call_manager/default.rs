@@ -5,35 +5,40 @@ use std::rc::Rc;
 use anyhow::{anyhow, Context};
 use cid::Cid;
 use derive_more::{Deref, DerefMut};
-use fvm_ipld_amt::Amt;
 use fvm_ipld_encoding::{to_vec, CBOR};
-use fvm_shared::address::{Address, Payload};
+use fvm_shared::address::{predict_actor_address, Address, ImplicitCreation};
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
 use fvm_shared::event::StampedEvent;
-use fvm_shared::sys::BlockId;
-use fvm_shared::{ActorID, METHOD_SEND};
+use fvm_shared::sys::{BlockId, LogLevel};
+use fvm_shared::{ActorID, MethodNum, METHOD_SEND};
 use num_traits::Zero;
 
 use super::state_access_tracker::{ActorAccessState, StateAccessTracker};
 use super::{Backtrace, CallManager, Entrypoint, InvocationResult, NO_DATA_BLOCK_ID};
-use crate::blockstore::DiscardBlockstore;
 use crate::call_manager::backtrace::Frame;
-use crate::call_manager::FinishRet;
-use crate::eam_actor::EAM_ACTOR_ID;
+use crate::call_manager::{ActorLog, Artifact, FinishRet};
 use crate::engine::Engine;
+use crate::executor::compute_events_root;
 use crate::gas::{Gas, GasTracker};
 use crate::kernel::{
-    Block, BlockRegistry, ClassifyResult, ExecutionError, Kernel, Result, SyscallError,
+    Block, BlockLimits, BlockRegistry, ClassifyResult, ExecutionError, Kernel, Result, SyscallError,
 };
 use crate::machine::limiter::MemoryLimiter;
 use crate::machine::Machine;
 use crate::state_tree::ActorState;
 use crate::syscalls::error::Abort;
 use crate::syscalls::{charge_for_exec, update_gas_available};
-use crate::trace::{ExecutionEvent, ExecutionTrace};
+use crate::trace::{CallFrameSummary, ExecutionEvent, ExecutionTrace};
 use crate::{syscall_error, system_actor};
 
+/// The maximum total size of the debug artifacts stored while applying a single message.
+const MAX_ARTIFACTS_SIZE: usize = 64 << 20;
+
+/// The maximum total size of the debug logs (messages and fields) recorded while applying a single
+/// message.
+const MAX_LOGS_SIZE: usize = 16 << 20;
+
 /// The default [`CallManager`] implementation.
 #[repr(transparent)]
 pub struct DefaultCallManager<M: Machine>(Option<Box<InnerDefaultCallManager<M>>>);
@@ -69,14 +74,30 @@ pub struct InnerDefaultCallManager<M: Machine> {
     backtrace: Backtrace,
     /// The current execution trace.
     exec_trace: ExecutionTrace,
+    /// Per-call-frame summaries, recorded when tracing.
+    call_frames: CallFrameRecorder,
     /// Number of actors that have been invoked in this message execution.
     invocation_count: u64,
     /// Limits on memory throughout the execution.
     limits: M::Limiter,
+    /// The block quota shared by the block registries of all invocations in this call stack.
+    block_limits: Rc<BlockLimits>,
     /// Accumulator for events emitted in this call stack.
     events: EventsAccumulator,
     /// The actor call stack (ActorID and entrypoint name tuple).
     actor_call_stack: Vec<(ActorID, &'static str)>,
+    /// Actors that may not be re-entered (see [`CallManager::acquire_reentry_guard`]).
+    reentry_guards: Vec<ActorID>,
+    /// Debug artifacts stored in this call stack.
+    artifacts: Vec<Artifact>,
+    /// The total size of the stored debug artifacts.
+    artifacts_size: usize,
+    /// Debug logs emitted in this call stack.
+    logs: Vec<ActorLog>,
+    /// The total size of the recorded debug logs.
+    logs_size: usize,
+    /// Whether debug logs were dropped for exceeding the quota.
+    logs_dropped: bool,
 }
 
 #[doc(hidden)]
@@ -113,6 +134,10 @@ where
         gas_premium: TokenAmount,
     ) -> Self {
         let limits = machine.new_limiter();
+        let block_limits = Rc::new(BlockLimits::new(
+            machine.context().max_open_blocks,
+            machine.context().max_open_block_bytes,
+        ));
         let gas_tracker =
             GasTracker::new(Gas::new(gas_limit), Gas::zero(), machine.context().tracing);
 
@@ -157,11 +182,19 @@ where
             call_stack_depth: 0,
             backtrace: Backtrace::default(),
             exec_trace: vec![],
+            call_frames: Default::default(),
             invocation_count: 0,
             limits,
+            block_limits,
             events: Default::default(),
             state_access_tracker,
             actor_call_stack: vec![],
+            reentry_guards: vec![],
+            artifacts: Vec::new(),
+            artifacts_size: 0,
+            logs: Vec::new(),
+            logs_size: 0,
+            logs_dropped: false,
         })))
     }
 
@@ -196,9 +229,22 @@ where
                     ),
                     read_only,
                 });
+                let gas_used = self.gas_tracker.gas_used();
+                self.call_frames.enter(from, to, *method, gas_used);
             }
         }
 
+        // Record the receiver's state root so we can trace changes. We read the state tree
+        // directly so this doesn't affect gas.
+        let old_root = if self.machine.context().tracing {
+            self.traced_state_root(&to)
+        } else {
+            None
+        };
+        // Remember where this call's trace starts, so we can drop the state root changes of its
+        // subcalls if it reverts them.
+        let trace_start = self.exec_trace.len();
+
         // If a specific gas limit has been requested, push a new limit into the gas tracker.
         if let Some(limit) = gas_limit {
             self.gas_tracker.push_limit(limit);
@@ -224,6 +270,20 @@ where
             })
         }
 
+        if !matches!(&result, Ok(ret) if ret.exit_code.is_success()) {
+            self.drop_state_root_changes(trace_start);
+        } else if let Some((actor, old_root)) = old_root {
+            if let Some((_, new_root)) = self.traced_state_root(&to) {
+                if new_root != old_root {
+                    self.trace(ExecutionEvent::StateRootChange {
+                        actor,
+                        old_root,
+                        new_root,
+                    });
+                }
+            }
+        }
+
         if self.machine.context().tracing && matches!(entrypoint, Entrypoint::Invoke(_)) {
             self.trace(match &result {
                 Ok(InvocationResult { exit_code, value }) => {
@@ -237,6 +297,15 @@ where
                 }
                 Err(ExecutionError::Syscall(s)) => ExecutionEvent::CallError(s.clone()),
             });
+            let gas_used = self.gas_tracker.gas_used();
+            self.call_frames.exit(
+                match &result {
+                    Ok(InvocationResult { exit_code, .. }) => Some(*exit_code),
+                    Err(ExecutionError::OutOfGas) => Some(ExitCode::SYS_OUT_OF_GAS),
+                    Err(_) => None,
+                },
+                gas_used,
+            );
         }
 
         result
@@ -249,6 +318,7 @@ where
         self.state_tree_mut().begin_transaction();
         self.events.begin_transaction();
         self.state_access_tracker.begin_transaction();
+        self.gas_tracker.begin_transaction();
 
         let (revert, res) = match f(self) {
             Ok(v) => (!v.exit_code.is_success(), Ok(v)),
@@ -258,6 +328,7 @@ where
         self.state_tree_mut().end_transaction(revert)?;
         self.events.end_transaction(revert)?;
         self.state_access_tracker.end_transaction(revert)?;
+        self.gas_tracker.end_transaction(revert)?;
 
         res
     }
@@ -268,15 +339,26 @@ where
             backtrace,
             gas_tracker,
             mut exec_trace,
+            call_frames,
             events,
+            artifacts,
+            logs,
             ..
         } = *self.0.take().expect("call manager is poisoned");
 
-        let gas_used = gas_tracker.gas_used().round_up();
+        // Refund the gas credited during execution, up to the cap. The credit is rounded down so
+        // that the gas used is never lower than what it would be without rounding.
+        let gas_used = gas_tracker.gas_used();
+        let gas_credit = machine
+            .context()
+            .price_list
+            .gas_credit_refund(gas_used, gas_tracker.gas_credited());
+        let gas_credit = gas_credit.round_down();
+        let gas_used = gas_used.round_up() - gas_credit;
 
         // Finalize any trace events, if we're tracing.
         if machine.context().tracing {
-            exec_trace.extend(gas_tracker.drain_trace().map(ExecutionEvent::GasCharge));
+            exec_trace.extend(gas_tracker.drain_trace());
         }
 
         let res = events.finish();
@@ -291,10 +373,14 @@ where
         (
             Ok(FinishRet {
                 gas_used,
+                gas_credit,
                 backtrace,
                 exec_trace,
+                call_frames: call_frames.frames,
                 events,
                 events_root,
+                artifacts,
+                logs,
             }),
             machine,
         )
@@ -347,10 +433,7 @@ where
         // address? They shouldn't be. The sender can always _replace_ a message with a new message,
         // and completely change how f2 addresses are assigned. Only the message sender can rely on
         // an f2 address (before finality).
-        let mut b = to_vec(&self.origin_address).expect("failed to serialize address");
-        b.extend_from_slice(&self.nonce.to_be_bytes());
-        b.extend_from_slice(&self.num_actors_created.to_be_bytes());
-        Address::new_actor(&b)
+        predict_actor_address(&self.origin_address, self.nonce, self.num_actors_created)
     }
 
     fn create_actor(
@@ -367,6 +450,18 @@ where
             .into());
         }
 
+        // Only builtin code and code recorded as installed in the (committed) init actor state may
+        // be used: the engine's module cache must not decide which code exists.
+        #[cfg(feature = "m2-native")]
+        if self.machine.builtin_actors().id_by_code(&code_id) == 0 {
+            let (state, _) = crate::init_actor::State::load(self.machine.state_tree())?;
+            if !state.is_code_installed(self.blockstore(), &code_id)? {
+                return Err(
+                    syscall_error!(IllegalArgument; "actor code {code_id} is not installed").into(),
+                );
+            }
+        }
+
         // Check to make sure the actor doesn't exist, or is a placeholder.
         let actor = match self.get_actor(actor_id)? {
             // Replace the placeholder
@@ -411,9 +506,48 @@ where
     }
 
     fn append_event(&mut self, evt: StampedEvent) {
+        self.call_frames.record_event();
         self.events.append_event(evt)
     }
 
+    fn acquire_reentry_guard(&mut self, actor: ActorID) {
+        self.reentry_guards.push(actor);
+    }
+
+    fn release_reentry_guard(&mut self, actor: ActorID) {
+        if let Some(idx) = self.reentry_guards.iter().rposition(|&a| a == actor) {
+            self.reentry_guards.remove(idx);
+        }
+    }
+
+    fn record_block_write(&mut self) {
+        self.call_frames.record_block_write();
+    }
+
+    fn store_artifact(&mut self, artifact: Artifact) {
+        // Artifacts with the same name overwrite previous ones stored by the same invocation.
+        let existing = self.artifacts.iter().position(|a| {
+            (a.actor, a.invocation, &a.name)
+                == (artifact.actor, artifact.invocation, &artifact.name)
+        });
+        let replaced_size = existing.map_or(0, |i| self.artifacts[i].data.len());
+        let new_size = self.artifacts_size - replaced_size + artifact.data.len();
+        if new_size > MAX_ARTIFACTS_SIZE {
+            log::error!(
+                "dropping debug artifact {} stored by actor {}: exceeds the quota of {} bytes per message",
+                artifact.name,
+                artifact.actor,
+                MAX_ARTIFACTS_SIZE
+            );
+            return;
+        }
+        self.artifacts_size = new_size;
+        match existing {
+            Some(i) => self.artifacts[i] = artifact,
+            None => self.artifacts.push(artifact),
+        }
+    }
+
     // Helper for creating actors. This really doesn't belong on this trait.
     fn invocation_count(&self) -> u64 {
         self.invocation_count
@@ -514,22 +648,82 @@ where
         Ok(())
     }
 
+    fn record_log(&mut self, log: ActorLog) {
+        let size = log.message.len()
+            + log
+                .fields
+                .iter()
+                .map(|(k, v)| k.len() + v.len())
+                .sum::<usize>();
+        let new_size = self.logs_size + size;
+        if new_size > MAX_LOGS_SIZE {
+            // Only complain once per message, as actors may log in a loop.
+            if !self.logs_dropped {
+                log::error!(
+                    "dropping debug logs emitted by actor {}: exceeds the quota of {} bytes per message",
+                    log.actor,
+                    MAX_LOGS_SIZE
+                );
+                self.logs_dropped = true;
+            }
+            return;
+        }
+        self.logs_size = new_size;
+        self.logs.push(log)
+    }
+
     fn log(&mut self, msg: String) {
         self.trace(ExecutionEvent::Log(msg))
     }
+
+    fn log_structured(
+        &mut self,
+        actor: ActorID,
+        level: LogLevel,
+        message: String,
+        fields: Vec<(String, String)>,
+    ) {
+        self.trace(ExecutionEvent::StructuredLog {
+            actor,
+            level,
+            message,
+            fields,
+        })
+    }
 }
 
 impl<M> DefaultCallManager<M>
 where
     M: Machine,
 {
+    /// Looks up the ID and state root of an actor for tracing purposes, without charging gas or
+    /// recording state accesses. Returns `None` if the actor doesn't exist.
+    fn traced_state_root(&self, addr: &Address) -> Option<(ActorID, Cid)> {
+        let id = self.state_tree().lookup_id(addr).ok()??;
+        let actor = self.state_tree().get_actor(id).ok()??;
+        Some((id, actor.state))
+    }
+
+    /// Drops the state root changes traced since the given position in the execution trace, as
+    /// the changes they report have been reverted.
+    fn drop_state_root_changes(&mut self, since: usize) {
+        let s = &mut **self;
+        if since >= s.exec_trace.len() {
+            return;
+        }
+        let tail = s.exec_trace.split_off(since);
+        s.exec_trace.extend(
+            tail.into_iter()
+                .filter(|e| !matches!(e, ExecutionEvent::StateRootChange { .. })),
+        );
+    }
+
     fn trace(&mut self, trace: ExecutionEvent) {
         // The price of deref magic is that you sometimes need to tell the compiler: no, this is
         // fine.
         let s = &mut **self;
 
-        s.exec_trace
-            .extend(s.gas_tracker.drain_trace().map(ExecutionEvent::GasCharge));
+        s.exec_trace.extend(s.gas_tracker.drain_trace());
 
         s.exec_trace.push(trace);
     }
@@ -552,16 +746,12 @@ where
     ///
     /// 1. Creates the actor.
     /// 2. Initializes it by calling the constructor.
+    ///
+    /// The caller must have checked that the address is eligible ([`ImplicitCreation::Account`]).
     fn create_account_actor_from_send<K>(&mut self, addr: &Address) -> Result<ActorID>
     where
         K: Kernel<CallManager = Self>,
     {
-        if addr.is_bls_zero_address() {
-            return Err(
-                syscall_error!(IllegalArgument; "cannot create the bls zero address actor").into(),
-            );
-        }
-
         // Create the actor in the state tree.
         let id = {
             let code_cid = self.builtin_actors().get_account_code();
@@ -615,32 +805,33 @@ where
     where
         K: Kernel<CallManager = Self>,
     {
-        // Get the receiver; this will resolve the address.
+        // Get the receiver; this will resolve the address, implicitly creating an actor if
+        // necessary (see `ImplicitCreation`).
         let to = match self.resolve_address(&to)? {
             Some(addr) => addr,
-            None => match to.payload() {
-                Payload::BLS(_) | Payload::Secp256k1(_) => {
-                    if read_only {
-                        return Err(syscall_error!(ReadOnly; "cannot auto-create account {to} in read-only calls").into());
-                    }
-                    // Try to create an account actor if the receiver is a key address.
-                    self.create_account_actor_from_send::<K>(&to)?
-                }
-                // Validate that there's an actor at the target ID (we don't care what is there,
-                // just that something is there).
-                Payload::Delegated(da) if da.namespace() == EAM_ACTOR_ID => {
-                    if read_only {
-                        return Err(syscall_error!(ReadOnly; "cannot auto-create account {to} in read-only calls").into());
-                    }
-                    self.create_placeholder_actor_from_send(&to)?
-                }
-                _ => return Err(
+            None => match to.implicit_creation() {
+                ImplicitCreation::None => return Err(
                     syscall_error!(NotFound; "actor does not exist or cannot be created: {}", to)
                         .into(),
                 ),
+                _ if read_only => {
+                    return Err(syscall_error!(ReadOnly; "cannot auto-create account {to} in read-only calls").into());
+                }
+                ImplicitCreation::Account => self.create_account_actor_from_send::<K>(&to)?,
+                ImplicitCreation::Placeholder => self.create_placeholder_actor_from_send(&to)?,
+                ImplicitCreation::Forbidden => {
+                    return Err(
+                        syscall_error!(IllegalArgument; "cannot create the bls zero address actor")
+                            .into(),
+                    );
+                }
             },
         };
 
+        if self.reentry_guards.contains(&to) {
+            return Err(syscall_error!(Forbidden; "actor {to} may not be re-entered: it forbade re-entry until its send returns").into());
+        }
+
         self.actor_call_stack.push((to, entrypoint.func_name()));
         let res = self.call_actor_resolved::<K>(from, to, entrypoint, params, value, read_only);
         self.actor_call_stack.pop();
@@ -703,7 +894,7 @@ where
         )?;
 
         // Store the parametrs, and initialize the block registry for the target actor.
-        let mut block_registry = BlockRegistry::new();
+        let mut block_registry = BlockRegistry::with_shared_limits(self.block_limits.clone());
         let params_id = if let Some(blk) = params {
             block_registry.put_reachable(blk)?
         } else {
@@ -728,6 +919,7 @@ where
             )?;
 
         log::trace!("calling {} -> {}::{}", from, to, entrypoint);
+        let network_version = self.machine.context().network.network_version;
         self.map_mut(|cm| {
             let engine = cm.engine.clone(); // reference the RC.
 
@@ -750,7 +942,7 @@ where
                 let code = &state.code;
                 // Instantiate the module.
                 let instance = engine
-                    .instantiate(&mut store, code)?
+                    .instantiate(&mut store, code, network_version)?
                     .context("actor not found")
                     .map_err(Abort::Fatal)?;
 
@@ -863,6 +1055,19 @@ where
                     };
 
                     if !code.is_success() {
+                        // The user-defined exit code range is reserved for non-builtin actors.
+                        // This isn't enforced (it would be a consensus change), but flag it.
+                        if code.is_user_defined()
+                            && cm.machine.builtin_actors().id_by_code(&state.code) != 0
+                        {
+                            log::warn!(
+                                "builtin actor {} ({}) aborted with user-defined exit code {}",
+                                to,
+                                state.code,
+                                code
+                            );
+                        }
+
                         // Only record backtrace frames for explicit messages sent by the user. We
                         // may want to record frames for failed upgrades, but that complicates
                         // things a bit and I'd like to keep this API the same for now.
@@ -956,6 +1161,66 @@ where
     }
 }
 
+/// Records a [`CallFrameSummary`] for each call frame, when tracing.
+#[derive(Default)]
+struct CallFrameRecorder {
+    /// The summaries of all frames entered so far, in order.
+    frames: Vec<CallFrameSummary>,
+    /// The open frames: their index in `frames`, and the gas used when they were entered.
+    stack: Vec<(usize, Gas)>,
+}
+
+impl CallFrameRecorder {
+    fn enter(&mut self, caller: ActorID, callee: Address, method: MethodNum, gas_used: Gas) {
+        self.stack.push((self.frames.len(), gas_used));
+        self.frames.push(CallFrameSummary {
+            depth: (self.stack.len() - 1) as u32,
+            caller,
+            callee,
+            method,
+            exit_code: None,
+            gas_used: Gas::zero(),
+            self_gas_used: Gas::zero(),
+            events_emitted: 0,
+            blocks_written: 0,
+        });
+    }
+
+    fn exit(&mut self, exit_code: Option<ExitCode>, gas_used: Gas) {
+        let Some((idx, gas_at_entry)) = self.stack.pop() else {
+            return;
+        };
+        let total = gas_used - gas_at_entry;
+        // Subcalls were entered after this frame, and have all exited.
+        let depth = self.frames[idx].depth;
+        let subcalls = self.frames[idx + 1..]
+            .iter()
+            .filter(|f| f.depth == depth + 1)
+            .fold(Gas::zero(), |acc, f| acc + f.gas_used);
+        let frame = &mut self.frames[idx];
+        frame.exit_code = exit_code;
+        frame.gas_used = total;
+        frame.self_gas_used = total - subcalls;
+    }
+
+    fn current(&mut self) -> Option<&mut CallFrameSummary> {
+        let &(idx, _) = self.stack.last()?;
+        self.frames.get_mut(idx)
+    }
+
+    fn record_event(&mut self) {
+        if let Some(frame) = self.current() {
+            frame.events_emitted += 1;
+        }
+    }
+
+    fn record_block_write(&mut self) {
+        if let Some(frame) = self.current() {
+            frame.blocks_written += 1;
+        }
+    }
+}
+
 /// Stores events in layers as they are emitted by actors. As the call stack progresses, when an
 /// actor exits normally, its events should be merged onto the previous layer (merge_last_layer).
 /// If an actor aborts, the last layer should be discarded (discard_last_layer). This will also
@@ -1010,19 +1275,7 @@ impl EventsAccumulator {
             )));
         }
 
-        let root = if !self.events.is_empty() {
-            const EVENTS_AMT_BITWIDTH: u32 = 5;
-            let root = Amt::new_from_iter_with_bit_width(
-                DiscardBlockstore,
-                EVENTS_AMT_BITWIDTH,
-                self.events.iter(),
-            )
-            .context("failed to construct events AMT")
-            .or_fatal()?;
-            Some(root)
-        } else {
-            None
-        };
+        let root = compute_events_root(&self.events).or_fatal()?;
 
         Ok(Events {
             root,
@@ -1030,3 +1283,57 @@ impl EventsAccumulator {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call_frame_recorder() {
+        let mut rec = CallFrameRecorder::default();
+        let gas = Gas::new;
+        let to = Address::new_id;
+
+        // Events and blocks outside of any frame are ignored.
+        rec.record_event();
+
+        rec.enter(100, to(1000), 2, gas(10));
+        rec.record_block_write();
+        rec.enter(1000, to(1001), 3, gas(20));
+        rec.record_event();
+        rec.record_event();
+        rec.enter(1001, to(1002), 4, gas(25));
+        rec.exit(None, gas(26));
+        rec.exit(Some(ExitCode::USR_FORBIDDEN), gas(40));
+        rec.enter(1000, to(1003), 5, gas(45));
+        rec.record_block_write();
+        rec.exit(Some(ExitCode::OK), gas(50));
+        rec.record_block_write();
+        rec.exit(Some(ExitCode::OK), gas(60));
+
+        let summary: Vec<_> = rec
+            .frames
+            .iter()
+            .map(|f| {
+                (
+                    f.depth,
+                    f.callee.id().unwrap(),
+                    f.exit_code,
+                    f.gas_used.round_up(),
+                    f.self_gas_used.round_up(),
+                    f.events_emitted,
+                    f.blocks_written,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, 1000, Some(ExitCode::OK), 50, 25, 0, 2),
+                (1, 1001, Some(ExitCode::USR_FORBIDDEN), 20, 19, 2, 0),
+                (2, 1002, None, 1, 1, 0, 0),
+                (1, 1003, Some(ExitCode::OK), 5, 5, 0, 1),
+            ]
+        );
+    }
+}
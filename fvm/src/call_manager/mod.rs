@@ -5,11 +5,12 @@ use fvm_ipld_encoding::{to_vec, CBOR};
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
+use fvm_shared::sys::LogLevel;
 use fvm_shared::upgrade::UpgradeInfo;
 use fvm_shared::{ActorID, MethodNum, METHOD_CONSTRUCTOR};
 
 use crate::engine::Engine;
-use crate::gas::{Gas, GasCharge, GasTimer, GasTracker, PriceList};
+use crate::gas::{Gas, GasCharge, GasCredit, GasTimer, GasTracker, PriceList};
 use crate::kernel::{self, BlockRegistry, ClassifyResult, Context, Result};
 use crate::machine::{Machine, MachineContext};
 use crate::state_tree::ActorState;
@@ -24,7 +25,7 @@ mod default;
 pub use default::DefaultCallManager;
 use fvm_shared::event::StampedEvent;
 
-use crate::trace::ExecutionTrace;
+use crate::trace::{CallFrameSummary, ExecutionTrace};
 
 /// BlockID representing nil parameters or return data.
 pub const NO_DATA_BLOCK_ID: u32 = 0;
@@ -169,14 +170,56 @@ pub trait CallManager: 'static {
         self.gas_tracker().apply_charge(charge)
     }
 
+    /// Credit gas back to the message, refunded (up to a cap) when the message finishes.
+    fn credit_gas(&self, credit: GasCredit) {
+        self.gas_tracker().credit_gas(credit)
+    }
+
     /// Limit memory usage throughout a message execution.
     fn limiter_mut(&mut self) -> &mut <Self::Machine as Machine>::Limiter;
 
     /// Appends an event to the event accumulator.
     fn append_event(&mut self, evt: StampedEvent);
 
+    /// Rejects calls into `actor` with [`ErrorNumber::Forbidden`] until the guard is released with
+    /// [`CallManager::release_reentry_guard`]. Guards may be nested.
+    ///
+    /// [`ErrorNumber::Forbidden`]: fvm_shared::error::ErrorNumber::Forbidden
+    fn acquire_reentry_guard(&mut self, actor: ActorID);
+
+    /// Releases a guard acquired with [`CallManager::acquire_reentry_guard`].
+    fn release_reentry_guard(&mut self, actor: ActorID);
+
+    /// Records that the current actor wrote a block to the blockstore (for tracing). Does nothing
+    /// by default.
+    fn record_block_write(&mut self) {}
+
+    /// Records a debug artifact. Unlike events, artifacts are kept even if the call that stored
+    /// them is reverted.
+    fn store_artifact(&mut self, artifact: Artifact);
+
+    /// Records a debug log emitted by an actor (with its attribution), to be returned from
+    /// [`CallManager::finish`]. Does nothing by default.
+    fn record_log(&mut self, _log: ActorLog) {}
+
     /// log
     fn log(&mut self, msg: String);
+
+    /// Records a structured log record emitted by the specified actor. By default, this formats
+    /// the record and logs it with [`CallManager::log`].
+    fn log_structured(
+        &mut self,
+        actor: ActorID,
+        level: LogLevel,
+        message: String,
+        fields: Vec<(String, String)>,
+    ) {
+        let mut msg = format!("[{}] actor {}: {}", level, actor, message);
+        for (k, v) in fields {
+            msg.push_str(&format!(" {}={}", k, v));
+        }
+        self.log(msg)
+    }
 }
 
 /// The result of calling actor's entrypoint
@@ -199,11 +242,64 @@ impl Default for InvocationResult {
 
 /// The returned values upon finishing a call manager.
 pub struct FinishRet {
+    /// Gas used, net of any gas credit.
     pub gas_used: u64,
+    /// Gas credited back to the message (e.g., for deleting state) and deducted from `gas_used`.
+    pub gas_credit: u64,
     pub backtrace: Backtrace,
     pub exec_trace: ExecutionTrace,
+    /// Per-call-frame summaries (when tracing).
+    pub call_frames: Vec<CallFrameSummary>,
     pub events: Vec<StampedEvent>,
     pub events_root: Option<Cid>,
+    pub artifacts: Vec<Artifact>,
+    pub logs: Vec<ActorLog>,
+}
+
+/// A debug artifact stored by an actor (in debug mode) through the `debug::store_artifact`
+/// syscall.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Artifact {
+    /// The actor that stored the artifact.
+    pub actor: ActorID,
+    /// The invocation (numbered from the start of the message) that stored the artifact.
+    pub invocation: u64,
+    /// The artifact's name, unique per actor invocation.
+    pub name: String,
+    /// The artifact's contents.
+    pub data: Vec<u8>,
+}
+
+/// A debug log emitted by an actor (in debug mode) through the `debug::log` or
+/// `debug::log_structured` syscalls, attributed to the call that emitted it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActorLog {
+    /// The actor that emitted the log.
+    pub actor: ActorID,
+    /// The depth of the call that emitted the log, from 0 for the message's top-level call.
+    pub depth: u32,
+    /// The method invoked by the call that emitted the log.
+    pub method: MethodNum,
+    /// The log's level, or `None` for unstructured logs.
+    pub level: Option<LogLevel>,
+    /// The log message.
+    pub message: String,
+    /// The fields of structured logs.
+    pub fields: Vec<(String, String)>,
+}
+
+impl std::fmt::Display for ActorLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "actor {} (depth {}, method {}): {}",
+            self.actor, self.depth, self.method, self.message
+        )?;
+        for (k, v) in &self.fields {
+            write!(f, " {}={}", k, v)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Copy)]
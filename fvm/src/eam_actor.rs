@@ -1,5 +1,121 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_shared::address::Address;
+use fvm_shared::well_known::WellKnownActor;
 use fvm_shared::ActorID;
+use multihash_codetable::{Code, MultihashDigest};
 
-pub const EAM_ACTOR_ID: ActorID = 10;
+/// The ID of the Ethereum Address Manager actor. This is also the namespace of the f4 addresses
+/// it assigns.
+pub const EAM_ACTOR_ID: ActorID = WellKnownActor::Eam.id();
+
+/// Predicts the f4 address assigned by the EAM to a contract deployed with `CREATE` by the given
+/// (20 byte) Ethereum address with the given nonce.
+pub fn predict_eth_create_address(from: &[u8; 20], nonce: u64) -> Address {
+    // RLP encode [from, nonce].
+    let nonce_bytes = nonce.to_be_bytes();
+    let nonce_bytes = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+    let mut nonce_rlp = Vec::with_capacity(9);
+    match nonce_bytes {
+        [b] if *b < 0x80 => nonce_rlp.push(*b),
+        _ => {
+            nonce_rlp.push(0x80 + nonce_bytes.len() as u8);
+            nonce_rlp.extend_from_slice(nonce_bytes);
+        }
+    }
+
+    let payload_len = 1 + from.len() + nonce_rlp.len();
+    let mut rlp = Vec::with_capacity(1 + payload_len);
+    rlp.push(0xc0 + payload_len as u8);
+    rlp.push(0x80 + from.len() as u8);
+    rlp.extend_from_slice(from);
+    rlp.extend_from_slice(&nonce_rlp);
+
+    eth_address(&keccak256(&rlp))
+}
+
+/// Predicts the f4 address assigned by the EAM to a contract deployed with `CREATE2` by the given
+/// (20 byte) Ethereum address with the given salt and init code.
+pub fn predict_eth_create2_address(from: &[u8; 20], salt: &[u8; 32], initcode: &[u8]) -> Address {
+    let mut preimage = Vec::with_capacity(1 + 20 + 32 + 32);
+    preimage.push(0xff);
+    preimage.extend_from_slice(from);
+    preimage.extend_from_slice(salt);
+    preimage.extend_from_slice(&keccak256(initcode));
+
+    eth_address(&keccak256(&preimage))
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    Code::Keccak256
+        .digest(data)
+        .digest()
+        .try_into()
+        .expect("keccak256 digests are 32 bytes")
+}
+
+/// Converts a keccak256 hash into the f4 address of the corresponding Ethereum address (the last
+/// 20 bytes of the hash).
+fn eth_address(hash: &[u8; 32]) -> Address {
+    Address::new_delegated(EAM_ACTOR_ID, &hash[12..]).expect("eth addresses are valid subaddresses")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_shared::address::Payload;
+
+    fn eth(addr: &Address) -> String {
+        match addr.payload() {
+            Payload::Delegated(da) => {
+                assert_eq!(da.namespace(), EAM_ACTOR_ID);
+                hex::encode(da.subaddress())
+            }
+            _ => panic!("expected a delegated address"),
+        }
+    }
+
+    fn parse_hex<const N: usize>(s: &str) -> [u8; N] {
+        let mut out = [0u8; N];
+        hex::decode_to_slice(s, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn eth_create() {
+        let from = parse_hex::<20>("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        for (nonce, expected) in [
+            (0, "cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"),
+            (1, "343c43a37d37dff08ae8c4a11544c718abb4fcf8"),
+            (2, "f778b86fa74e846c4f0a1fbd1335fe81c00a0c91"),
+            (3, "fffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c"),
+        ] {
+            assert_eq!(eth(&predict_eth_create_address(&from, nonce)), expected);
+        }
+    }
+
+    #[test]
+    fn eth_create2() {
+        // Examples from EIP-1014.
+        assert_eq!(
+            eth(&predict_eth_create2_address(&[0; 20], &[0; 32], &[0x00])),
+            "4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38"
+        );
+        assert_eq!(
+            eth(&predict_eth_create2_address(
+                &parse_hex("deadbeef00000000000000000000000000000000"),
+                &parse_hex("000000000000000000000000feed000000000000000000000000000000000000"),
+                &[0x00]
+            )),
+            "d04116cdd17bebe565eb2422f2497e06cc1c9833"
+        );
+        assert_eq!(
+            eth(&predict_eth_create2_address(
+                &parse_hex("00000000000000000000000000000000deadbeef"),
+                &parse_hex("00000000000000000000000000000000000000000000000000000000cafebabe"),
+                &parse_hex::<4>("deadbeef")
+            )),
+            "60f3f640a8508fc6a86d45df051962668e1e8ac7"
+        );
+    }
+}
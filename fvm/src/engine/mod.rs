@@ -3,36 +3,45 @@
 
 mod concurrency;
 mod instance_pool;
+mod preflight;
 
 use std::any::{Any, TypeId};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{from_slice, strict_bytes, to_vec, BytesDe, BytesSer};
 use fvm_shared::error::ExitCode;
+use fvm_shared::version::NetworkVersion;
 use fvm_wasm_instrument::gas_metering::GAS_COUNTER_NAME;
+use multihash_codetable::{Code, MultihashDigest};
 use num_traits::Zero;
 use wasmtime::OptLevel::Speed;
 use wasmtime::{
-    Global, GlobalType, InstanceAllocationStrategy, Memory, MemoryType, Module, Mutability, Val,
-    ValType, WasmBacktraceDetails,
+    Global, GlobalType, InstanceAllocationStrategy, Memory, MemoryType, Module, Mutability,
+    UpdateDeadline, Val, ValType, WasmBacktraceDetails,
 };
 
+use crate::executor::ExecutionTimeout;
 use crate::gas::{Gas, GasTimer, WasmGasPrices};
 use crate::machine::limiter::MemoryLimiter;
 use crate::machine::{Machine, NetworkConfig};
 use crate::syscalls::error::Abort;
 use crate::syscalls::{
     charge_for_exec, charge_for_init, record_init_time, update_gas_available, InvocationData,
-    Linker,
+    Linker, Namespace,
 };
 use crate::Kernel;
 
 use self::concurrency::EngineConcurrency;
 use self::instance_pool::InstancePool;
+pub use self::preflight::{Diagnostic, ValidationReport};
 
 /// The expected max stack depth used to determine the number of instances needed for a given
 /// concurrency level.
@@ -44,6 +53,17 @@ pub struct MultiEngine {
     concurrency: u32,
 }
 
+/// How actor code may use floating point numbers.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum FloatPolicy {
+    /// Allow floating point types and instructions, canonicalizing the NaNs they produce so
+    /// execution is deterministic across platforms.
+    #[default]
+    Canonicalize,
+    /// Reject actor code that uses floating point types or instructions.
+    Disallow,
+}
+
 /// The proper way of getting this struct is to convert from `NetworkConfig`
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct EngineConfig {
@@ -53,6 +73,11 @@ pub struct EngineConfig {
     pub concurrency: u32,
     pub wasm_prices: &'static WasmGasPrices,
     pub actor_redirect: Vec<(Cid, Cid)>,
+    /// Enable epoch interruption so executions can be given a deadline (see
+    /// [`Engine::set_deadline`]).
+    pub interruptible: bool,
+    /// How actor code may use floating point numbers.
+    pub float_policy: FloatPolicy,
 }
 
 impl EngineConfig {
@@ -66,6 +91,40 @@ impl EngineConfig {
             48 * 1024,
         )
     }
+
+    /// Returns a digest of this configuration and of the gas instrumentation, i.e., of everything
+    /// that affects the compiled modules, but not the concurrency. Module cache dumps carry the
+    /// fingerprint of the exporting pool, and only pools with the same fingerprint import them
+    /// (see [`EnginePool::export_cache`]).
+    fn cache_fingerprint(&self) -> anyhow::Result<Vec<u8>> {
+        let p = self.wasm_prices;
+        let prices = [
+            p.instruction_default,
+            p.math_default,
+            p.jump_unconditional,
+            p.jump_conditional,
+            p.jump_indirect,
+            p.call,
+            p.memory_fill_base_cost,
+            p.memory_fill_per_byte_cost,
+            p.memory_access_cost,
+            p.memory_copy_per_byte_cost,
+            p.host_call_cost,
+        ]
+        .map(|gas| gas.as_milligas());
+        let encoded = to_vec(&(
+            // The instrumentation itself changes with the FVM version.
+            env!("CARGO_PKG_VERSION"),
+            self.max_call_depth,
+            self.max_wasm_stack,
+            self.max_inst_memory_bytes,
+            prices,
+            &self.actor_redirect,
+            self.interruptible,
+            self.float_policy == FloatPolicy::Disallow,
+        ))?;
+        Ok(Code::Blake2b256.digest(&encoded).digest().to_vec())
+    }
 }
 
 impl From<&NetworkConfig> for EngineConfig {
@@ -77,6 +136,8 @@ impl From<&NetworkConfig> for EngineConfig {
             wasm_prices: &nc.price_list.wasm_rules,
             actor_redirect: nc.actor_redirect.clone(),
             concurrency: 1,
+            interruptible: nc.execution_timeouts,
+            float_policy: nc.float_policy,
         }
     }
 }
@@ -120,6 +181,13 @@ impl Default for MultiEngine {
     }
 }
 
+/// How often the epoch of an interruptible engine is incremented, i.e., the granularity of
+/// execution deadlines.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The maximum number of elements in an actor's table (wasmtime's default).
+const MAX_TABLE_ELEMENTS: u32 = 20_000;
+
 /// Derive a [`wasmtime::Config`] from an [`EngineConfig`]
 fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
     if ec.concurrency < 1 {
@@ -147,6 +215,7 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
     alloc_strat_cfg.max_memories_per_module(1);
     alloc_strat_cfg.total_tables(instance_count);
     alloc_strat_cfg.max_tables_per_module(1);
+    alloc_strat_cfg.table_elements(MAX_TABLE_ELEMENTS);
 
     // Adjust the maximum amount of host memory that can be committed to an instance to
     // match the static linear memory size we reserve for each slot.
@@ -215,6 +284,8 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
     // > is useful for users requiring entirely deterministic WebAssembly
     // > computation. This is not required by the WebAssembly spec, so it is
     // > not enabled by default.
+    //
+    // This is irrelevant when floats are disallowed, but costs nothing in that case.
     c.cranelift_nan_canonicalization(true);
 
     // wasmtime default: 512KiB
@@ -224,7 +295,11 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
 
     // Execution cost accouting is done through wasm instrumentation,
     c.consume_fuel(false);
-    c.epoch_interruption(false);
+
+    // Epoch interruption is only used to enforce (non-deterministic) wall-clock timeouts when
+    // executing messages off-chain. It adds a check to every loop and function entry, so we only
+    // enable it when asked.
+    c.epoch_interruption(ec.interruptible);
 
     // Disable debug-related things, wasm-instrument doesn't fix debug info
     // yet, so those aren't useful, just add overhead
@@ -277,6 +352,36 @@ struct ModuleRecord {
     module: Module,
     /// Byte size of the original Wasm.
     size: usize,
+    /// Whether the module is pinned (see [`EnginePool::pin`]).
+    pinned: bool,
+}
+
+/// The version of the module cache dump format (see [`EnginePool::export_cache`]).
+const CACHE_DUMP_VERSION: u64 = 1;
+
+/// A compiled module in a module cache dump.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct CachedModule {
+    code_cid: Cid,
+    /// See [`ModuleRecord::size`].
+    size: u64,
+    /// The Blake2b-256 digest of `compiled`.
+    #[serde(with = "strict_bytes")]
+    digest: Vec<u8>,
+    /// The compiled module, serialized by Wasmtime.
+    #[serde(with = "strict_bytes")]
+    compiled: Vec<u8>,
+}
+
+/// Statistics on the modules pinned in an [`EnginePool`] (see [`EnginePool::pin`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PinStats {
+    /// The number of pinned modules.
+    pub pinned: usize,
+    /// The number of actor instantiations that used a pinned module.
+    pub hits: u64,
+    /// The number of modules that had to be compiled on first use, during execution.
+    pub misses: u64,
 }
 
 struct EngineInner {
@@ -294,10 +399,20 @@ struct EngineInner {
     dummy_memory: Memory,
 
     module_cache: Mutex<HashMap<Cid, ModuleRecord>>,
-    instance_cache: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
+    /// Linkers keyed by kernel type and network version (syscalls are linked per network
+    /// version).
+    instance_cache: Mutex<HashMap<(TypeId, NetworkVersion), Box<dyn Any + Send>>>,
+    /// Additional host modules registered with [`EnginePool::register_syscalls`], keyed by the
+    /// kernel's type. Each entry is a `Vec<HostModule<K>>`.
+    host_modules: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
     config: EngineConfig,
 
     actor_redirect: HashMap<Cid, Cid>,
+
+    /// See [`PinStats::hits`].
+    pin_hits: AtomicU64,
+    /// See [`PinStats::misses`].
+    pin_misses: AtomicU64,
 }
 
 /// EnginePool represents a limited pool of engines.
@@ -311,9 +426,16 @@ impl EnginePool {
         Engine {
             id: self.0.concurrency_limit.acquire(),
             inner: self.0.clone(),
+            deadline: None,
         }
     }
 
+    /// Returns true if engines from this pool support execution deadlines (see
+    /// [`EngineConfig::interruptible`]).
+    pub fn is_interruptible(&self) -> bool {
+        self.0.config.interruptible
+    }
+
     /// Create a new [`EnginePool`].
     pub fn new(ec: EngineConfig) -> anyhow::Result<Self> {
         let c = wasmtime_config(&ec)?;
@@ -329,7 +451,8 @@ impl EnginePool {
 
         let actor_redirect = ec.actor_redirect.iter().cloned().collect();
 
-        Ok(EnginePool(Arc::new(EngineInner {
+        let interruptible = ec.interruptible;
+        let inner = Arc::new(EngineInner {
             concurrency_limit: EngineConcurrency::new(ec.concurrency),
             instance_limit: InstancePool::new(ec.instance_pool_size(), ec.max_call_depth),
             engine,
@@ -337,10 +460,277 @@ impl EnginePool {
             dummy_gas_global: dummy_gg,
             module_cache: Default::default(),
             instance_cache: Mutex::new(HashMap::new()),
+            host_modules: Mutex::new(HashMap::new()),
             config: ec,
             actor_redirect,
-        })))
+            pin_hits: AtomicU64::new(0),
+            pin_misses: AtomicU64::new(0),
+        });
+
+        if interruptible {
+            // Drive the engine's epoch until the pool is dropped.
+            let weak = Arc::downgrade(&inner);
+            std::thread::Builder::new()
+                .name("fvm-epoch-ticker".into())
+                .spawn(move || {
+                    while let Some(inner) = weak.upgrade() {
+                        inner.engine.increment_epoch();
+                        drop(inner);
+                        std::thread::sleep(EPOCH_TICK);
+                    }
+                })
+                .context("failed to spawn the epoch ticker")?;
+        }
+
+        Ok(EnginePool(inner))
     }
+
+    /// Compiles (if needed) and pins the modules of the given code CIDs, loading them from the
+    /// blockstore. Pinned modules are kept by [`EnginePool::clear_cache`], so frequently used
+    /// actors (e.g., the system, init, account, and EVM actors) never pay the compilation latency
+    /// during execution. Redirected actors are pinned under their new code CID.
+    ///
+    /// Returns the total original byte size of the modules.
+    pub fn pin<'a>(
+        &self,
+        blockstore: &impl Blockstore,
+        cids: impl IntoIterator<Item = &'a Cid>,
+    ) -> anyhow::Result<usize> {
+        let engine = self.acquire();
+        let mut total_size = 0usize;
+        for cid in cids {
+            log::trace!("pinning code CID {cid}");
+            total_size += engine
+                .preload(cid, blockstore)
+                .with_context(|| anyhow!("could not pin actor with code CID {cid}"))?;
+            if let Some(record) = self
+                .0
+                .module_cache
+                .lock()
+                .expect("module_cache poisoned")
+                .get_mut(engine.with_redirect(cid))
+            {
+                record.pinned = true;
+            }
+        }
+        Ok(total_size)
+    }
+
+    /// Unpins the module of the given code CID, letting [`EnginePool::clear_cache`] evict it.
+    /// Returns true if it was pinned.
+    pub fn unpin(&self, cid: &Cid) -> bool {
+        let cid = self.0.actor_redirect.get(cid).unwrap_or(cid);
+        match self
+            .0
+            .module_cache
+            .lock()
+            .expect("module_cache poisoned")
+            .get_mut(cid)
+        {
+            Some(record) => std::mem::replace(&mut record.pinned, false),
+            None => false,
+        }
+    }
+
+    /// Evicts every compiled module that isn't pinned. Evicted modules are recompiled on their
+    /// next use.
+    pub fn clear_cache(&self) {
+        self.0
+            .module_cache
+            .lock()
+            .expect("module_cache poisoned")
+            .retain(|_, record| record.pinned);
+    }
+
+    /// Returns statistics on the pinned modules and their use.
+    pub fn pin_stats(&self) -> PinStats {
+        let pinned = self
+            .0
+            .module_cache
+            .lock()
+            .expect("module_cache poisoned")
+            .values()
+            .filter(|record| record.pinned)
+            .count();
+        PinStats {
+            pinned,
+            hits: self.0.pin_hits.load(Ordering::Relaxed),
+            misses: self.0.pin_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Serializes every compiled module in the cache, keyed by code CID, so that another pool
+    /// with the same [`EngineConfig`] (and FVM version) can load them with
+    /// [`EnginePool::import_cache`] instead of compiling them again. The dump carries a
+    /// fingerprint of the configuration and gas instrumentation, and each module a digest of its
+    /// compiled artifact, both checked on import.
+    pub fn export_cache(&self) -> anyhow::Result<Vec<u8>> {
+        let mut modules = self
+            .0
+            .module_cache
+            .lock()
+            .expect("module_cache poisoned")
+            .iter()
+            .map(|(code_cid, record)| {
+                let compiled = record
+                    .module
+                    .serialize()
+                    .with_context(|| format!("failed to serialize module {code_cid}"))?;
+                Ok(CachedModule {
+                    code_cid: *code_cid,
+                    size: record.size as u64,
+                    digest: Code::Blake2b256.digest(&compiled).digest().to_vec(),
+                    compiled,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        modules.sort_by_key(|m| m.code_cid);
+        let fingerprint = BytesSer(&self.0.config.cache_fingerprint()?);
+        Ok(to_vec(&(CACHE_DUMP_VERSION, fingerprint, modules))?)
+    }
+
+    /// Loads compiled modules exported by [`EnginePool::export_cache`] into the cache, skipping
+    /// modules that are already cached. Fails, without loading anything, if the dump is
+    /// malformed, was made by an incompatible engine or with a different configuration (see
+    /// [`EnginePool::export_cache`]), or if any module doesn't match its digest.
+    ///
+    /// Returns the number of modules loaded.
+    ///
+    /// # Safety
+    ///
+    /// The compiled modules are native code, and are trusted as is: the digests only detect
+    /// corruption. Only import dumps from a trusted source, see [`wasmtime::Module::deserialize`].
+    pub unsafe fn import_cache(&self, dump: &[u8]) -> anyhow::Result<usize> {
+        let (version, fingerprint, modules): (u64, BytesDe, Vec<CachedModule>) =
+            from_slice(dump).context("malformed module cache dump")?;
+        if version != CACHE_DUMP_VERSION {
+            bail!("unsupported module cache dump version {version}");
+        }
+        if fingerprint.0 != self.0.config.cache_fingerprint()? {
+            bail!("module cache dump was exported with a different engine configuration");
+        }
+
+        let mut records = Vec::with_capacity(modules.len());
+        for m in modules {
+            if Code::Blake2b256.digest(&m.compiled).digest() != m.digest {
+                bail!("module {} doesn't match its digest", m.code_cid);
+            }
+            let module = Module::deserialize(&self.0.engine, &m.compiled)
+                .with_context(|| format!("failed to load module {}", m.code_cid))?;
+            records.push((
+                m.code_cid,
+                ModuleRecord {
+                    module,
+                    size: m.size as usize,
+                    pinned: false,
+                },
+            ));
+        }
+
+        let mut cache = self.0.module_cache.lock().expect("module_cache poisoned");
+        let mut loaded = 0;
+        for (code_cid, record) in records {
+            if let Vacant(e) = cache.entry(code_cid) {
+                e.insert(record);
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Register an additional host module, exposing syscalls under the custom namespace `module`
+    /// to actors running on kernels of type `K`. The `link` function is called every time a
+    /// linker is constructed for `K`, after the kernel's own syscalls have been linked.
+    ///
+    /// This lets clients expose chain-specific host functions without defining a new kernel.
+    /// Built-in namespaces (see [`RESERVED_NAMESPACES`][crate::syscalls::RESERVED_NAMESPACES])
+    /// can't be used, and all syscalls linked this way are subject to the usual syscall gas
+    /// charges. Linking fails if the kernel links syscalls into the same namespace.
+    ///
+    /// Host modules should be registered when constructing the machine, before executing any
+    /// messages.
+    pub fn register_syscalls<K: Kernel>(
+        &self,
+        module: &'static str,
+        link: impl Fn(&mut Namespace<'_, K>) -> anyhow::Result<()> + Send + Sync + 'static,
+    ) -> anyhow::Result<()> {
+        crate::syscalls::check_namespace(module)?;
+
+        let type_id = TypeId::of::<K>();
+        {
+            let mut host_modules = self.0.host_modules.lock().expect("host modules poisoned");
+            let modules: &mut Vec<HostModule<K>> = host_modules
+                .entry(type_id)
+                .or_insert_with(|| Box::<Vec<HostModule<K>>>::default())
+                .downcast_mut()
+                .expect("invalid host module entry");
+            if modules.iter().any(|m| m.module == module) {
+                return Err(anyhow!(
+                    "syscall namespace {module:?} is already registered"
+                ));
+            }
+            modules.push(HostModule {
+                module,
+                link: Box::new(link),
+            });
+        }
+
+        // Drop any cached linkers for this kernel so the new module gets linked.
+        self.0
+            .instance_cache
+            .lock()
+            .expect("cache poisoned")
+            .retain(|(id, _), _| *id != type_id);
+
+        Ok(())
+    }
+}
+
+impl EngineInner {
+    /// Create a linker with all syscalls and host modules available to kernel `K` at the given
+    /// network version (but not the gas counter, which is defined per-store).
+    fn new_linker<K: Kernel>(&self, network_version: NetworkVersion) -> anyhow::Result<Linker<K>> {
+        let mut linker = Linker::new(&self.engine, network_version);
+        // Kernels may override the syscalls of the kernels they extend.
+        linker.0.allow_shadowing(true);
+        K::link_syscalls(&mut linker)?;
+        // But host modules may not override anything.
+        linker.0.allow_shadowing(false);
+        self.link_host_modules(&mut linker)?;
+        // The gas counter is redefined for every store the (cached) linker instantiates into.
+        linker.0.allow_shadowing(true);
+        Ok(linker)
+    }
+
+    /// Link all host modules registered for kernel `K`.
+    fn link_host_modules<K: Kernel>(&self, linker: &mut Linker<K>) -> anyhow::Result<()> {
+        let host_modules = self.host_modules.lock().expect("host modules poisoned");
+        let Some(modules) = host_modules.get(&TypeId::of::<K>()) else {
+            return Ok(());
+        };
+        let modules: &Vec<HostModule<K>> =
+            modules.downcast_ref().expect("invalid host module entry");
+        for m in modules {
+            if linker.has_module(m.module) {
+                return Err(anyhow!(
+                    "host module {:?} collides with the kernel's syscall namespace",
+                    m.module
+                ));
+            }
+            (m.link)(&mut linker.namespace(m.module)?)
+                .with_context(|| format!("failed to link host module {:?}", m.module))?;
+        }
+        Ok(())
+    }
+}
+
+/// The function linking a host module's syscalls.
+type LinkHostModule<K> = dyn Fn(&mut Namespace<'_, K>) -> anyhow::Result<()> + Send + Sync;
+
+/// A host module registered with [`EnginePool::register_syscalls`].
+struct HostModule<K> {
+    module: &'static str,
+    link: Box<LinkHostModule<K>>,
 }
 
 struct Cache<K> {
@@ -354,6 +744,7 @@ struct Cache<K> {
 pub struct Engine {
     id: u64,
     inner: Arc<EngineInner>,
+    deadline: Option<Instant>,
 }
 
 /// Release the engine back into the [`EnginePool`].
@@ -391,6 +782,15 @@ impl Engine {
         }
     }
 
+    /// Checks that the supplied bytecode is valid actor code by compiling it. The compiled module
+    /// isn't cached: modules are only ever cached when loaded from the blockstore, so that the
+    /// code an actor can run never depends on what a node happens to have cached.
+    ///
+    /// Returns the size of the compiled module.
+    pub fn check_bytecode(&self, wasm: &[u8]) -> anyhow::Result<usize> {
+        Ok(self.load_raw(wasm)?.size)
+    }
+
     /// Instantiates and caches the Wasm modules for the bytecodes addressed by
     /// the supplied CIDs. Only uncached entries are actually fetched and
     /// instantiated. Blockstore failures and entry inexistence shortcircuit
@@ -421,16 +821,11 @@ impl Engine {
         }
     }
 
-    /// Load the specified wasm module with the internal Engine instance.
-    fn load_raw(&self, raw_wasm: &[u8]) -> anyhow::Result<ModuleRecord> {
-        // First make sure that non-instrumented wasm is valid
-        Module::validate(&self.inner.engine, raw_wasm)
-            .map_err(anyhow::Error::msg)
-            .with_context(|| "failed to validate actor wasm")?;
-
-        // Note: when adding debug mode support (with recorded syscall replay) don't instrument to
-        // avoid breaking debug info
-
+    /// Instrument the (already validated) wasm module with stack limits and gas metering.
+    ///
+    /// Note: when adding debug mode support (with recorded syscall replay) don't instrument to
+    /// avoid breaking debug info
+    fn instrument(&self, raw_wasm: &[u8]) -> anyhow::Result<Vec<u8>> {
         use fvm_wasm_instrument::{gas_metering, stack_limiter};
 
         // stack limiter adds post/pre-ambles to call instructions; We want to do that
@@ -451,47 +846,38 @@ impl Engine {
         //   (code `0xFC 15`) uses what parity-wasm calls the `BULK_PREFIX` but it was added later in
         //   https://github.com/WebAssembly/reference-types/issues/29 and is not recognised by the
         //   parity-wasm module parser, so the contract cannot grow the tables.
-        let raw_wasm = gas_metering::inject(&raw_wasm, self.inner.config.wasm_prices, "gas")
-            .map_err(|_| anyhow::Error::msg("injecting gas counter failed"))?;
+        gas_metering::inject(&raw_wasm, self.inner.config.wasm_prices, "gas")
+            .map_err(|_| anyhow::Error::msg("injecting gas counter failed"))
+    }
+
+    /// Validate the (non-instrumented) wasm module, applying the engine's [`FloatPolicy`].
+    fn validate(&self, raw_wasm: &[u8]) -> anyhow::Result<()> {
+        Module::validate(&self.inner.engine, raw_wasm)?;
+        if self.inner.config.float_policy == FloatPolicy::Disallow {
+            // Wasmtime has already checked everything else, so this only rejects floats.
+            let features = wasmparser::WasmFeatures::all() - wasmparser::WasmFeatures::FLOATS;
+            wasmparser::Validator::new_with_features(features).validate_all(raw_wasm)?;
+        }
+        Ok(())
+    }
+
+    /// Load the specified wasm module with the internal Engine instance.
+    fn load_raw(&self, raw_wasm: &[u8]) -> anyhow::Result<ModuleRecord> {
+        // First make sure that non-instrumented wasm is valid
+        self.validate(raw_wasm)
+            .context("failed to validate actor wasm")?;
+
+        let raw_wasm = self.instrument(raw_wasm)?;
 
         let module = Module::from_binary(&self.inner.engine, &raw_wasm)?;
 
         Ok(ModuleRecord {
             module,
             size: raw_wasm.len(),
+            pinned: false,
         })
     }
 
-    /// Load compiled wasm code into the engine.
-    ///
-    /// # Safety
-    ///
-    /// See [`wasmtime::Module::deserialize`] for safety information.
-    #[allow(dead_code)]
-    unsafe fn load_compiled(&self, k: &Cid, compiled: &[u8]) -> anyhow::Result<Module> {
-        let k = self.with_redirect(k);
-        let mut cache = self
-            .inner
-            .module_cache
-            .lock()
-            .expect("module_cache poisoned");
-        let module = match cache.get(k) {
-            Some(m) => m.module.clone(),
-            None => {
-                let module = Module::deserialize(&self.inner.engine, compiled)?;
-                cache.insert(
-                    *k,
-                    ModuleRecord {
-                        module: module.clone(),
-                        size: compiled.len(),
-                    },
-                );
-                module
-            }
-        };
-        Ok(module)
-    }
-
     /// Lookup and instantiate a loaded wasmtime module with the given store. This will cache the
     /// linker, syscalls, etc.
     ///
@@ -500,21 +886,23 @@ impl Engine {
         &self,
         store: &mut wasmtime::Store<InvocationData<K>>,
         k: &Cid,
+        network_version: NetworkVersion,
     ) -> Result<Option<wasmtime::Instance>, Abort> {
         let k = self.with_redirect(k);
         let mut instance_cache = self.inner.instance_cache.lock().expect("cache poisoned");
 
         let type_id = TypeId::of::<K>();
-        let cache: &mut Cache<K> = match instance_cache.entry(type_id) {
+        let cache: &mut Cache<K> = match instance_cache.entry((type_id, network_version)) {
             Occupied(e) => &mut *e
                 .into_mut()
                 .downcast_mut()
                 .expect("invalid instance cache entry"),
             Vacant(e) => &mut *e
                 .insert({
-                    let mut linker = Linker(wasmtime::Linker::new(&self.inner.engine));
-                    linker.0.allow_shadowing(true);
-                    K::link_syscalls(&mut linker).map_err(Abort::Fatal)?;
+                    let linker = self
+                        .inner
+                        .new_linker::<K>(network_version)
+                        .map_err(Abort::Fatal)?;
                     Box::new(Cache { linker: linker.0 })
                 })
                 .downcast_mut()
@@ -573,7 +961,12 @@ impl Engine {
         };
 
         match module_cache.entry(*k) {
-            Occupied(v) => instantiate(store, &v.get().module),
+            Occupied(v) => {
+                if v.get().pinned {
+                    self.inner.pin_hits.fetch_add(1, Ordering::Relaxed);
+                }
+                instantiate(store, &v.get().module)
+            }
             Vacant(v) => match store
                 .data()
                 .kernel
@@ -583,16 +976,31 @@ impl Engine {
                 .context("failed to lookup wasm module in blockstore")
                 .map_err(Abort::Fatal)?
             {
-                Some(raw_wasm) => instantiate(
-                    store,
-                    &v.insert(self.load_raw(&raw_wasm).map_err(Abort::Fatal)?)
-                        .module,
-                ),
+                Some(raw_wasm) => {
+                    self.inner.pin_misses.fetch_add(1, Ordering::Relaxed);
+                    instantiate(
+                        store,
+                        &v.insert(self.load_raw(&raw_wasm).map_err(Abort::Fatal)?)
+                            .module,
+                    )
+                }
                 None => Ok(None),
             },
         }
     }
 
+    /// Set a deadline after which executions on stores subsequently created by this engine abort
+    /// with an [`ExecutionTimeout`] fatal error, or clear it with `None`.
+    ///
+    /// Fails if the engine isn't [interruptible][EngineConfig::interruptible].
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) -> anyhow::Result<()> {
+        if deadline.is_some() && !self.inner.config.interruptible {
+            return Err(anyhow!("engine does not support execution deadlines"));
+        }
+        self.deadline = deadline;
+        Ok(())
+    }
+
     /// Construct a new wasmtime "store" from the given kernel.
     pub(crate) fn new_store<K: Kernel>(&self, mut kernel: K) -> wasmtime::Store<InvocationData<K>> {
         // Take a new instance and put it into a drop-guard that removes the reservation when
@@ -628,6 +1036,16 @@ impl Engine {
             .expect("failed to create available_gas global");
         store.data_mut().avail_gas_global = gg;
 
+        if self.inner.config.interruptible {
+            // Check the deadline on every epoch tick.
+            let deadline = self.deadline;
+            store.set_epoch_deadline(1);
+            store.epoch_deadline_callback(move |_| match deadline {
+                Some(deadline) if Instant::now() >= deadline => Err(ExecutionTimeout.into()),
+                _ => Ok(UpdateDeadline::Continue(1)),
+            });
+        }
+
         store.limiter(move |data| {
             // Keep the reservation alive as long as the limiter is alive. The limiter limits the
             // store to one instance and one memory, which is covered by the reservation.
@@ -695,10 +1113,12 @@ impl<L: MemoryLimiter> wasmtime::ResourceLimiter for WasmtimeLimiter<L> {
 
 #[cfg(test)]
 mod tests {
+    use fvm_shared::version::NetworkVersion;
     use wasmtime::ResourceLimiter;
 
     use crate::engine::WasmtimeLimiter;
     use crate::machine::limiter::MemoryLimiter;
+    use crate::syscalls::Linker;
 
     #[derive(Default)]
     struct Limiter {
@@ -752,4 +1172,137 @@ mod tests {
         assert!(limits.table_growing(2, 4, None).unwrap());
         assert_eq!(limits.0.memory, 5 * 8);
     }
+
+    #[test]
+    fn syscall_namespaces() {
+        let engine = wasmtime::Engine::default();
+        let mut linker: Linker<()> = Linker::new(&engine, NetworkVersion::V21);
+
+        // Built-in namespaces are reserved.
+        assert!(linker.namespace("ipld").is_err());
+        assert!(linker.namespace("").is_err());
+
+        let ns = linker.namespace("my_chain").unwrap();
+        assert_eq!(ns.module(), "my_chain");
+    }
+
+    #[test]
+    fn pinning() {
+        use cid::Cid;
+        use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
+        use fvm_shared::IPLD_RAW;
+        use multihash_codetable::Code;
+
+        use crate::engine::{EngineConfig, EnginePool, PinStats};
+        use crate::machine::NetworkConfig;
+
+        let pool =
+            EnginePool::new(EngineConfig::from(&NetworkConfig::new(NetworkVersion::V21))).unwrap();
+        let bs = MemoryBlockstore::default();
+        let put = |wasm: &[u8]| {
+            let block = Block::new(IPLD_RAW, wasm);
+            bs.put(Code::Blake2b256, &block).unwrap()
+        };
+        // (module (func (param i32) (result i32) i32.const 0)), and the same with a custom section.
+        let module = b"\0asm\x01\0\0\0\
+            \x01\x06\x01\x60\x01\x7f\x01\x7f\
+            \x03\x02\x01\0\
+            \x0a\x06\x01\x04\0\x41\0\x0b";
+        let a = put(module);
+        let b = put(&[&module[..], b"\0\x02\x01x"].concat());
+        let cached = || pool.0.module_cache.lock().unwrap().len();
+
+        assert!(pool.pin(&bs, &[Cid::default()]).is_err());
+        pool.pin(&bs, &[a]).unwrap();
+        pool.acquire().preload(&b, &bs).unwrap();
+        assert_eq!(
+            pool.pin_stats(),
+            PinStats {
+                pinned: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(cached(), 2);
+
+        // Only unpinned modules are evicted.
+        pool.clear_cache();
+        assert_eq!(cached(), 1);
+        assert!(pool.unpin(&a));
+        assert!(!pool.unpin(&a));
+        assert_eq!(pool.pin_stats().pinned, 0);
+        pool.clear_cache();
+        assert_eq!(cached(), 0);
+    }
+
+    #[test]
+    fn cache_dump() {
+        use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
+        use fvm_shared::version::NetworkVersion;
+        use fvm_shared::IPLD_RAW;
+        use multihash_codetable::Code;
+
+        use crate::engine::{EngineConfig, EnginePool};
+        use crate::machine::NetworkConfig;
+
+        let new_pool = || {
+            EnginePool::new(EngineConfig::from(&NetworkConfig::new(NetworkVersion::V21))).unwrap()
+        };
+        // (module (func (param i32) (result i32) i32.const 0))
+        let module = b"\0asm\x01\0\0\0\
+            \x01\x06\x01\x60\x01\x7f\x01\x7f\
+            \x03\x02\x01\0\
+            \x0a\x06\x01\x04\0\x41\0\x0b";
+        let bs = MemoryBlockstore::default();
+        let cid = bs
+            .put(Code::Blake2b256, &Block::new(IPLD_RAW, module))
+            .unwrap();
+
+        let pool = new_pool();
+        let size = pool.acquire().preload(&cid, &bs).unwrap();
+        let dump = pool.export_cache().unwrap();
+
+        // The imported module is used without looking up (or compiling) the code.
+        let other = new_pool();
+        assert_eq!(unsafe { other.import_cache(&dump) }.unwrap(), 1);
+        assert_eq!(
+            other
+                .acquire()
+                .preload(&cid, &MemoryBlockstore::default())
+                .unwrap(),
+            size
+        );
+        assert_eq!(unsafe { other.import_cache(&dump) }.unwrap(), 0);
+
+        // Corrupted dumps are rejected.
+        let mut corrupted = dump.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        let err = unsafe { new_pool().import_cache(&corrupted) }.unwrap_err();
+        assert!(err.to_string().contains("digest"), "{err}");
+        assert!(unsafe { new_pool().import_cache(&dump[..dump.len() / 2]) }.is_err());
+
+        // Dumps exported with a different configuration, or gas instrumentation, are rejected.
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.max_wasm_stack += 1;
+        let err = unsafe {
+            EnginePool::new(EngineConfig::from(&nc))
+                .unwrap()
+                .import_cache(&dump)
+        }
+        .unwrap_err();
+        assert!(err.to_string().contains("configuration"), "{err}");
+        let mut ec = EngineConfig::from(&NetworkConfig::new(NetworkVersion::V21));
+        let mut prices = ec.wasm_prices.clone();
+        prices.host_call_cost += crate::gas::Gas::new(1);
+        ec.wasm_prices = Box::leak(Box::new(prices));
+        let err = unsafe { EnginePool::new(ec).unwrap().import_cache(&dump) }.unwrap_err();
+        assert!(err.to_string().contains("configuration"), "{err}");
+
+        // But the concurrency doesn't matter.
+        let mut ec = EngineConfig::from(&NetworkConfig::new(NetworkVersion::V21));
+        ec.concurrency = 2;
+        assert_eq!(
+            unsafe { EnginePool::new(ec).unwrap().import_cache(&dump) }.unwrap(),
+            1
+        );
+    }
 }
@@ -0,0 +1,220 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_shared::version::NetworkVersion;
+use wasmparser::{ExternalKind, Parser, Payload};
+use wasmtime::Module;
+
+use super::{Engine, MAX_TABLE_ELEMENTS};
+use crate::call_manager::INVOKE_FUNC_NAME;
+use crate::Kernel;
+
+/// A reason an actor's wasm module would be rejected (or fail to instantiate) on-chain.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Diagnostic {
+    /// The module isn't valid wasm, or uses a wasm feature the FVM doesn't support (e.g., SIMD,
+    /// threads, multiple return values, or floats if disallowed by the
+    /// [`FloatPolicy`][super::FloatPolicy]).
+    #[error("invalid wasm: {0}")]
+    InvalidWasm(String),
+    /// The module imports something not provided by the kernel (or its host modules).
+    #[error("failed to link: {0}")]
+    Link(String),
+    /// The module doesn't export a required item.
+    #[error("missing export {0:?}")]
+    MissingExport(&'static str),
+    /// Stack limit or gas metering instrumentation failed.
+    #[error("failed to instrument: {0}")]
+    Instrumentation(String),
+    /// The module (or its instrumented form) failed to compile.
+    #[error("failed to compile: {0}")]
+    Compilation(String),
+    /// The module defines more than one memory.
+    #[error("module defines {0} memories, at most 1 is allowed")]
+    TooManyMemories(usize),
+    /// The module's initial memory exceeds the per-instance memory limit.
+    #[error("initial memory of {size} bytes exceeds the limit of {limit} bytes")]
+    MemoryTooLarge { size: u64, limit: u64 },
+    /// The module defines more than one table.
+    #[error("module defines {0} tables, at most 1 is allowed")]
+    TooManyTables(usize),
+    /// The module's initial table exceeds the maximum number of table elements.
+    #[error("initial table of {size} elements exceeds the limit of {limit} elements")]
+    TableTooLarge { size: u64, limit: u32 },
+}
+
+/// The result of [`Engine::preflight_validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Size of the module as submitted, in bytes.
+    pub code_size: usize,
+    /// Size of the module after instrumentation, in bytes, if instrumentation succeeded.
+    pub instrumented_size: Option<usize>,
+    /// Every problem found with the module. Empty if the module is deployable.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    /// Returns true if no problems were found.
+    pub fn is_valid(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// The memories, tables, and exports declared by a module.
+#[derive(Default)]
+struct ModuleShape<'a> {
+    memories: Vec<u64>,
+    tables: Vec<u64>,
+    exports: Vec<(&'a str, ExternalKind)>,
+}
+
+impl<'a> ModuleShape<'a> {
+    fn parse(wasm: &'a [u8]) -> wasmparser::Result<Self> {
+        let mut shape = Self::default();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::MemorySection(r) => {
+                    for m in r {
+                        shape.memories.push(m?.initial);
+                    }
+                }
+                Payload::TableSection(r) => {
+                    for t in r {
+                        shape.tables.push(t?.ty.initial);
+                    }
+                }
+                Payload::ExportSection(r) => {
+                    for e in r {
+                        let e = e?;
+                        shape.exports.push((e.name, e.kind));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(shape)
+    }
+
+    fn exports(&self, name: &str, kind: ExternalKind) -> bool {
+        self.exports.contains(&(name, kind))
+    }
+}
+
+impl Engine {
+    /// Check whether `wasm` would be accepted as actor code by this engine and kernel `K` at the
+    /// given network version, without installing it.
+    ///
+    /// This applies the same validation, limits, and instrumentation as loading the code for
+    /// execution, along with the linking and export checks performed when the actor is invoked,
+    /// reporting every problem found rather than stopping at the first. Imports are only checked
+    /// if the module is within the engine's limits, as it can't be compiled otherwise.
+    pub fn preflight_validate<K: Kernel>(
+        &self,
+        wasm: &[u8],
+        network_version: NetworkVersion,
+    ) -> ValidationReport {
+        let mut report = ValidationReport {
+            code_size: wasm.len(),
+            instrumented_size: None,
+            diagnostics: Vec::new(),
+        };
+
+        // Nothing else can be checked if the module isn't valid wasm in the first place.
+        if let Err(e) = self.validate(wasm) {
+            report
+                .diagnostics
+                .push(Diagnostic::InvalidWasm(e.to_string()));
+            return report;
+        }
+        let shape = match ModuleShape::parse(wasm) {
+            Ok(s) => s,
+            Err(e) => {
+                report
+                    .diagnostics
+                    .push(Diagnostic::InvalidWasm(e.to_string()));
+                return report;
+            }
+        };
+
+        // These limits are otherwise only enforced when compiling or instantiating the module.
+        if shape.memories.len() > 1 {
+            report
+                .diagnostics
+                .push(Diagnostic::TooManyMemories(shape.memories.len()));
+        }
+        let memory_limit = self.inner.config.max_inst_memory_bytes;
+        let page_size = wasmtime_environ::Memory::DEFAULT_PAGE_SIZE as u64;
+        for &pages in &shape.memories {
+            let size = pages.saturating_mul(page_size);
+            if size > memory_limit {
+                report.diagnostics.push(Diagnostic::MemoryTooLarge {
+                    size,
+                    limit: memory_limit,
+                });
+            }
+        }
+        if shape.tables.len() > 1 {
+            report
+                .diagnostics
+                .push(Diagnostic::TooManyTables(shape.tables.len()));
+        }
+        for &size in &shape.tables {
+            if size > MAX_TABLE_ELEMENTS as u64 {
+                report.diagnostics.push(Diagnostic::TableTooLarge {
+                    size,
+                    limit: MAX_TABLE_ELEMENTS,
+                });
+            }
+        }
+
+        if !shape.exports("memory", ExternalKind::Memory) {
+            report.diagnostics.push(Diagnostic::MissingExport("memory"));
+        }
+        if !shape.exports(INVOKE_FUNC_NAME, ExternalKind::Func) {
+            report
+                .diagnostics
+                .push(Diagnostic::MissingExport(INVOKE_FUNC_NAME));
+        }
+
+        if report.is_valid() {
+            // Link against the uninstrumented module: the gas counter the instrumentation imports
+            // is always provided by the engine.
+            let linked = Module::from_binary(&self.inner.engine, wasm)
+                .map_err(|e| Diagnostic::Compilation(format!("{e:#}")))
+                .and_then(|module| {
+                    let linker = self
+                        .inner
+                        .new_linker::<K>(network_version)
+                        .map_err(|e| Diagnostic::Link(format!("{e:#}")))?;
+                    linker
+                        .0
+                        .instantiate_pre(&module)
+                        .map_err(|e| Diagnostic::Link(format!("{e:#}")))
+                });
+            if let Err(d) = linked {
+                report.diagnostics.push(d);
+            }
+        }
+
+        let instrumented = match self.instrument(wasm) {
+            Ok(w) => w,
+            Err(e) => {
+                report
+                    .diagnostics
+                    .push(Diagnostic::Instrumentation(format!("{e:#}")));
+                return report;
+            }
+        };
+        report.instrumented_size = Some(instrumented.len());
+        if report.is_valid() {
+            if let Err(e) = Module::from_binary(&self.inner.engine, &instrumented) {
+                report
+                    .diagnostics
+                    .push(Diagnostic::Compilation(format!("{e:#}")));
+            }
+        }
+
+        report
+    }
+}
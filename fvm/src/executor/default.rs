@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::ops::{Deref, DerefMut};
 use std::result::Result as StdResult;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
 use cid::Cid;
@@ -15,14 +17,16 @@ use fvm_shared::receipt::Receipt;
 use fvm_shared::{ActorID, IPLD_RAW, METHOD_SEND};
 use num_traits::Zero;
 
-use super::{ApplyFailure, ApplyKind, ApplyRet, Executor};
-use crate::call_manager::{backtrace, Backtrace, CallManager, Entrypoint, InvocationResult};
+use super::{ApplyFailure, ApplyKind, ApplyRet, ExecutionTimeout, Executor, FeeDetails};
+use crate::call_manager::{
+    backtrace, ActorLog, Artifact, Backtrace, CallManager, Entrypoint, InvocationResult,
+};
 use crate::eam_actor::EAM_ACTOR_ID;
 use crate::engine::EnginePool;
 use crate::gas::{Gas, GasCharge, GasOutputs};
 use crate::kernel::{Block, ClassifyResult, Context as _, ExecutionError, Kernel};
-use crate::machine::{Machine, BURNT_FUNDS_ACTOR_ID, REWARD_ACTOR_ID};
-use crate::trace::ExecutionTrace;
+use crate::machine::{Machine, MachineHooks, BURNT_FUNDS_ACTOR_ID, REWARD_ACTOR_ID};
+use crate::trace::{CallFrameSummary, ExecutionTrace};
 
 /// The default [`Executor`].
 ///
@@ -35,6 +39,7 @@ pub struct DefaultExecutor<K: Kernel> {
     engine_pool: EnginePool,
     // If the inner value is `None` it means the machine got poisoned and is unusable.
     machine: Option<<K::CallManager as CallManager>::Machine>,
+    execution_timeout: Option<Duration>,
 }
 
 impl<K: Kernel> Deref for DefaultExecutor<K> {
@@ -64,20 +69,97 @@ where
         apply_kind: ApplyKind,
         raw_length: usize,
     ) -> anyhow::Result<ApplyRet> {
+        if apply_kind != ApplyKind::DryRun {
+            return self.apply_message(msg, apply_kind, raw_length);
+        }
+
+        // Apply the message as an explicit message, then revert everything it did to the state
+        // tree, whether it succeeded or not.
+        self.state_tree_mut().begin_transaction();
+        let ret = self.apply_message(msg, ApplyKind::Explicit, raw_length);
+        self.state_tree_mut()
+            .end_transaction(true)
+            .context("failed to revert a dry-run message")?;
+        let mut ret = ret?;
+        ret.dry_run = true;
+        Ok(ret)
+    }
+
+    /// Flush the state-tree to the underlying blockstore.
+    fn flush(&mut self) -> anyhow::Result<Cid> {
+        let k = (**self).flush()?;
+        Ok(k)
+    }
+
+    fn hooks(&self) -> Option<Arc<dyn MachineHooks>> {
+        self.context().hooks.clone()
+    }
+}
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Applies a message, committing its effects to the state tree. `apply_kind` must be either
+    /// [`ApplyKind::Explicit`] or [`ApplyKind::Implicit`].
+    ///
+    /// If the message times out, everything it did is reverted, including the gas precharged
+    /// from the sender and the bump of its nonce.
+    fn apply_message(
+        &mut self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        if self.context().trusted_writes {
+            return Err(anyhow!(
+                "cannot apply messages with trusted blockstore writes enabled"
+            ));
+        }
+
+        self.state_tree_mut().begin_transaction();
+        let ret = self.apply_message_inner(msg, apply_kind, raw_length);
+        let timed_out = matches!(&ret, Err(err) if err.is::<ExecutionTimeout>());
+        self.state_tree_mut()
+            .end_transaction(timed_out)
+            .context("failed to end the message's transaction")?;
+        ret
+    }
+
+    fn apply_message_inner(
+        &mut self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        let witness = self.context().witness;
+        if witness {
+            self.record_witness();
+        }
+
         // Validate if the message was correct, charge for it, and extract some preliminary data.
         let (sender_id, gas_cost, inclusion_cost) =
             match self.preflight_message(&msg, apply_kind, raw_length)? {
                 Ok(res) => res,
-                Err(apply_ret) => return Ok(apply_ret),
+                Err(mut apply_ret) => {
+                    if witness {
+                        apply_ret.witness = self.take_witness()?;
+                    }
+                    return Ok(apply_ret);
+                }
             };
 
         struct MachineExecRet {
             result: crate::kernel::Result<InvocationResult>,
             gas_used: u64,
+            gas_credit: u64,
             backtrace: Backtrace,
             exec_trace: ExecutionTrace,
+            call_frames: Vec<CallFrameSummary>,
             events_root: Option<Cid>,
             events: Vec<StampedEvent>, // TODO consider removing if nothing in the client ends up using it.
+            artifacts: Vec<Artifact>,
+            logs: Vec<ActorLog>,
         }
 
         // Pre-resolve the message receiver's address, if known.
@@ -96,7 +178,10 @@ where
 
         // Acquire an engine from the pool. This may block if there are concurrently executing
         // messages inside other executors sharing the same pool.
-        let engine = self.engine_pool.acquire();
+        let mut engine = self.engine_pool.acquire();
+        if let Some(timeout) = self.execution_timeout {
+            engine.set_deadline(Some(Instant::now() + timeout))?;
+        }
 
         // Apply the message.
         let ret = self.map_machine(|machine| {
@@ -164,10 +249,14 @@ where
                 Ok(MachineExecRet {
                     result,
                     gas_used: res.gas_used,
+                    gas_credit: res.gas_credit,
                     backtrace: res.backtrace,
                     exec_trace: res.exec_trace,
+                    call_frames: res.call_frames,
                     events_root: res.events_root,
                     events: res.events,
+                    artifacts: res.artifacts,
+                    logs: res.logs,
                 }),
                 machine,
             )
@@ -176,12 +265,34 @@ where
         let MachineExecRet {
             result: res,
             gas_used,
+            mut gas_credit,
             mut backtrace,
             exec_trace,
+            call_frames,
             events_root,
             events,
+            artifacts,
+            mut logs,
         } = ret;
 
+        // Timeouts are non-deterministic, so we can't produce a receipt. The caller reverts the
+        // message's effects.
+        if let Err(ExecutionError::Fatal(err)) = &res {
+            if err.is::<ExecutionTimeout>() {
+                return Err(ExecutionTimeout.into());
+            }
+        }
+
+        // Hand the actor logs to the sink, if any. Otherwise, they're returned with the receipt.
+        if let Some(sink) = &self.context().actor_log_sink {
+            if !logs.is_empty() {
+                let cid = msg.cid();
+                for log in logs.drain(..) {
+                    sink.log(&cid, &log);
+                }
+            }
+        }
+
         // Extract the exit code and build the result of the message application.
         let receipt = match res {
             Ok(InvocationResult { exit_code, value }) => {
@@ -243,6 +354,7 @@ where
                     self.context().epoch,
                 ));
                 backtrace.set_cause(backtrace::Cause::from_fatal(err));
+                gas_credit = 0;
                 Receipt {
                     exit_code: ExitCode::SYS_ASSERTION_FAILED,
                     return_data: Default::default(),
@@ -258,8 +370,8 @@ where
             Some(ApplyFailure::MessageBacktrace(backtrace))
         };
 
-        match apply_kind {
-            ApplyKind::Explicit => self.finish_message(
+        let mut ret = match apply_kind {
+            ApplyKind::Explicit | ApplyKind::DryRun => self.finish_message(
                 sender_id,
                 msg,
                 receipt,
@@ -267,8 +379,8 @@ where
                 gas_cost,
                 exec_trace,
                 events,
-            ),
-            ApplyKind::Implicit => Ok(ApplyRet {
+            )?,
+            ApplyKind::Implicit => ApplyRet {
                 msg_receipt: receipt,
                 penalty: TokenAmount::zero(),
                 miner_tip: TokenAmount::zero(),
@@ -277,24 +389,28 @@ where
                 refund: TokenAmount::zero(),
                 gas_refund: 0,
                 gas_burned: 0,
+                gas_credit: 0,
                 failure_info,
                 exec_trace,
+                call_frames: Vec::new(),
                 events,
-            }),
+                artifacts: Vec::new(),
+                logs: Vec::new(),
+                witness: Vec::new(),
+                dry_run: false,
+                fee_details: None,
+            },
+        };
+        ret.gas_credit = gas_credit;
+        ret.call_frames = call_frames;
+        ret.artifacts = artifacts;
+        ret.logs = logs;
+        if witness {
+            ret.witness = self.take_witness()?;
         }
+        Ok(ret)
     }
 
-    /// Flush the state-tree to the underlying blockstore.
-    fn flush(&mut self) -> anyhow::Result<Cid> {
-        let k = (**self).flush()?;
-        Ok(k)
-    }
-}
-
-impl<K> DefaultExecutor<K>
-where
-    K: Kernel,
-{
     /// Create a new [`DefaultExecutor`] for executing messages on the [`Machine`].
     pub fn new(
         engine_pool: EnginePool,
@@ -303,6 +419,17 @@ where
         // Skip preloading all builtin actors when testing.
         #[cfg(not(any(test, feature = "testing")))]
         {
+            // Pin the hottest actors, so they're never evicted.
+            let manifest = machine.builtin_actors();
+            engine_pool.pin(
+                machine.blockstore(),
+                machine
+                    .context()
+                    .pinned_actors
+                    .iter()
+                    .filter_map(|name| manifest.code_by_name(name)),
+            )?;
+
             // Preload any uncached modules.
             // This interface works for now because we know all actor CIDs
             // ahead of time, but with user-supplied code, we won't have that
@@ -315,9 +442,26 @@ where
         Ok(Self {
             engine_pool,
             machine: Some(machine),
+            execution_timeout: None,
         })
     }
 
+    /// Abort the execution of each message with an [`ExecutionTimeout`] error if it takes longer
+    /// than `timeout` (or never, if `None`). This is useful when executing untrusted messages
+    /// off-chain, e.g., for gas estimation.
+    ///
+    /// Fails if the engine pool wasn't created with execution timeouts enabled (see
+    /// [`NetworkConfig::enable_execution_timeouts`][crate::machine::NetworkConfig::enable_execution_timeouts]).
+    pub fn set_execution_timeout(&mut self, timeout: Option<Duration>) -> anyhow::Result<()> {
+        if timeout.is_some() && !self.engine_pool.is_interruptible() {
+            return Err(anyhow!(
+                "execution timeouts are not enabled for this engine pool"
+            ));
+        }
+        self.execution_timeout = timeout;
+        Ok(())
+    }
+
     /// Consume consumes the executor and returns the Machine. If the Machine had
     /// been poisoned during execution, the Option will be None.
     pub fn into_machine(self) -> Option<<K::CallManager as CallManager>::Machine> {
@@ -346,7 +490,7 @@ where
                 GasCharge::new("none", Gas::zero(), Gas::zero()),
                 Default::default(),
             ),
-            ApplyKind::Explicit => {
+            ApplyKind::Explicit | ApplyKind::DryRun => {
                 let inclusion_cost = pl.on_chain_message(raw_length);
                 let inclusion_total = inclusion_cost.total().round_up();
 
@@ -517,6 +661,7 @@ where
             // Sanity check. This could be a fatal error.
             return Err(anyhow!("Gas handling math is wrong"));
         }
+        let fee_details = FeeDetails::new(sender_id, &msg, &self.context().base_fee);
         Ok(ApplyRet {
             msg_receipt: receipt,
             penalty: miner_penalty,
@@ -526,9 +671,16 @@ where
             refund,
             gas_refund,
             gas_burned,
+            gas_credit: 0,
             failure_info,
             exec_trace,
+            call_frames: Vec::new(),
             events,
+            artifacts: Vec::new(),
+            logs: Vec::new(),
+            witness: Vec::new(),
+            dry_run: false,
+            fee_details: Some(fee_details),
         })
     }
 
@@ -0,0 +1,293 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! The implicit messages applied by the system at the end of every tipset: one reward message per
+//! block, followed by the cron tick.
+
+use anyhow::{anyhow, Context};
+use fvm_ipld_encoding::{to_vec, RawBytes};
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::reward::AwardBlockRewardParams;
+use fvm_shared::well_known::WellKnownActor;
+use fvm_shared::{MethodNum, BLOCK_GAS_LIMIT};
+
+use super::{ApplyKind, ApplyRet, Executor};
+use crate::machine::MachineHooks;
+
+/// The reward actor's `AwardBlockReward` method.
+pub const AWARD_BLOCK_REWARD_METHOD: MethodNum = 2;
+
+/// The cron actor's `EpochTick` method.
+pub const EPOCH_TICK_METHOD: MethodNum = 2;
+
+/// The gas limit of reward messages.
+pub const REWARD_MESSAGE_GAS_LIMIT: u64 = 1 << 30;
+
+/// The gas limit of cron messages, large enough to never run out.
+pub const CRON_MESSAGE_GAS_LIMIT: u64 = BLOCK_GAS_LIMIT * 10000;
+
+/// The implicit work to do at the end of a tipset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpochInfo {
+    /// The tipset's epoch.
+    pub epoch: ChainEpoch,
+    /// The reward for each of the tipset's blocks, in block order. Empty for null rounds, which
+    /// only run cron.
+    pub rewards: Vec<AwardBlockRewardParams>,
+}
+
+/// The results of [`Executor::apply_implicit_tipset`].
+#[derive(Clone, Debug)]
+pub struct ImplicitTipsetRet {
+    /// The result of each reward message, in block order.
+    pub rewards: Vec<ApplyRet>,
+    /// The result of the cron message.
+    pub cron: ApplyRet,
+    /// The result of each message returned by the [`MachineHooks`], in the order in which they
+    /// were applied.
+    pub hooks: Vec<ApplyRet>,
+}
+
+/// Builds the implicit message paying a block's reward at the given epoch.
+pub fn reward_message(
+    epoch: ChainEpoch,
+    reward: &AwardBlockRewardParams,
+) -> anyhow::Result<Message> {
+    Ok(Message {
+        version: 0,
+        from: WellKnownActor::System.address(),
+        to: WellKnownActor::Reward.address(),
+        sequence: epoch as u64,
+        value: TokenAmount::default(),
+        method_num: AWARD_BLOCK_REWARD_METHOD,
+        params: RawBytes::serialize(reward).context("failed to serialize reward params")?,
+        gas_limit: REWARD_MESSAGE_GAS_LIMIT,
+        gas_fee_cap: TokenAmount::default(),
+        gas_premium: TokenAmount::default(),
+    })
+}
+
+/// Builds the implicit message running cron at the given epoch.
+pub fn cron_message(epoch: ChainEpoch) -> Message {
+    Message {
+        version: 0,
+        from: WellKnownActor::System.address(),
+        to: WellKnownActor::Cron.address(),
+        sequence: epoch as u64,
+        value: TokenAmount::default(),
+        method_num: EPOCH_TICK_METHOD,
+        params: RawBytes::default(),
+        gas_limit: CRON_MESSAGE_GAS_LIMIT,
+        gas_fee_cap: TokenAmount::default(),
+        gas_premium: TokenAmount::default(),
+    }
+}
+
+/// Applies the implicit messages for the end of a tipset. See [`Executor::apply_implicit_tipset`].
+pub(super) fn apply_implicit_tipset<E: Executor + ?Sized>(
+    exec: &mut E,
+    epoch_info: &EpochInfo,
+) -> anyhow::Result<ImplicitTipsetRet> {
+    let hooks = exec.hooks();
+    apply_tipset_messages(epoch_info, hooks.as_deref(), |msg| {
+        apply_implicit(exec, msg)
+    })
+}
+
+/// Applies the implicit messages for the end of a tipset, and those returned by the hooks, with
+/// `apply`.
+fn apply_tipset_messages(
+    epoch_info: &EpochInfo,
+    hooks: Option<&dyn MachineHooks>,
+    mut apply: impl FnMut(Message) -> anyhow::Result<ApplyRet>,
+) -> anyhow::Result<ImplicitTipsetRet> {
+    let mut hook_rets = Vec::new();
+    if let Some(hooks) = hooks {
+        let msgs = hooks
+            .on_epoch_start(epoch_info)
+            .context("epoch start hook failed")?;
+        apply_hook_messages(&mut apply, msgs, &mut hook_rets)?;
+    }
+
+    let mut rewards = Vec::with_capacity(epoch_info.rewards.len());
+    for reward in &epoch_info.rewards {
+        let msg = reward_message(epoch_info.epoch, reward)?;
+        let ret = apply(msg)?;
+        if !ret.msg_receipt.exit_code.is_success() {
+            return Err(anyhow!(
+                "reward message for miner {} failed with exit code {}",
+                reward.miner,
+                ret.msg_receipt.exit_code
+            ));
+        }
+        rewards.push(ret);
+    }
+
+    let cron = apply(cron_message(epoch_info.epoch))?;
+    if !cron.msg_receipt.exit_code.is_success() {
+        return Err(anyhow!(
+            "cron message failed with exit code {}",
+            cron.msg_receipt.exit_code
+        ));
+    }
+
+    let mut ret = ImplicitTipsetRet {
+        rewards,
+        cron,
+        hooks: hook_rets,
+    };
+    if let Some(hooks) = hooks {
+        let msgs = hooks
+            .on_epoch_end(epoch_info, &ret)
+            .context("epoch end hook failed")?;
+        apply_hook_messages(&mut apply, msgs, &mut ret.hooks)?;
+    }
+    Ok(ret)
+}
+
+/// Applies the implicit messages returned by a hook, which must all succeed.
+fn apply_hook_messages(
+    apply: &mut impl FnMut(Message) -> anyhow::Result<ApplyRet>,
+    msgs: Vec<Message>,
+    rets: &mut Vec<ApplyRet>,
+) -> anyhow::Result<()> {
+    for msg in msgs {
+        let (to, method) = (msg.to, msg.method_num);
+        let ret = apply(msg)?;
+        if !ret.msg_receipt.exit_code.is_success() {
+            return Err(anyhow!(
+                "hook message to {} (method {}) failed with exit code {}",
+                to,
+                method,
+                ret.msg_receipt.exit_code
+            ));
+        }
+        rets.push(ret);
+    }
+    Ok(())
+}
+
+fn apply_implicit<E: Executor + ?Sized>(exec: &mut E, msg: Message) -> anyhow::Result<ApplyRet> {
+    let raw_length = to_vec(&msg)?.len();
+    exec.execute_message(msg, ApplyKind::Implicit, raw_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::from_slice;
+    use fvm_shared::address::Address;
+    use fvm_shared::error::ExitCode;
+
+    use super::*;
+
+    #[test]
+    fn implicit_messages() {
+        let reward = AwardBlockRewardParams {
+            miner: Address::new_id(1000),
+            penalty: TokenAmount::from_atto(1),
+            gas_reward: TokenAmount::from_atto(2),
+            win_count: 1,
+        };
+        let msg = reward_message(10, &reward).unwrap();
+        assert_eq!(msg.from, WellKnownActor::System.address());
+        assert_eq!(msg.to, WellKnownActor::Reward.address());
+        assert_eq!(msg.sequence, 10);
+        assert_eq!(
+            from_slice::<AwardBlockRewardParams>(msg.params.bytes()).unwrap(),
+            reward
+        );
+
+        let msg = cron_message(10);
+        assert_eq!(msg.to, WellKnownActor::Cron.address());
+        assert_eq!(msg.method_num, EPOCH_TICK_METHOD);
+        assert_eq!(msg.sequence, 10);
+        assert!(msg.params.is_empty());
+    }
+
+    /// Runs an extra implicit message (to the given method of the system actor) at the start and
+    /// end of every epoch.
+    #[derive(Debug)]
+    struct ExtraMessages;
+
+    impl MachineHooks for ExtraMessages {
+        fn on_epoch_start(&self, epoch_info: &EpochInfo) -> anyhow::Result<Vec<Message>> {
+            Ok(vec![hook_message(epoch_info.epoch, 100)])
+        }
+
+        fn on_epoch_end(
+            &self,
+            epoch_info: &EpochInfo,
+            ret: &ImplicitTipsetRet,
+        ) -> anyhow::Result<Vec<Message>> {
+            assert_eq!(ret.rewards.len(), epoch_info.rewards.len());
+            assert_eq!(ret.hooks.len(), 1);
+            Ok(vec![hook_message(epoch_info.epoch, 101)])
+        }
+    }
+
+    fn hook_message(epoch: ChainEpoch, method_num: MethodNum) -> Message {
+        Message {
+            to: WellKnownActor::System.address(),
+            method_num,
+            ..cron_message(epoch)
+        }
+    }
+
+    fn ret_with(code: ExitCode) -> ApplyRet {
+        ApplyRet::prevalidation_fail(code, "", TokenAmount::default())
+    }
+
+    #[test]
+    fn hooks() {
+        let epoch_info = EpochInfo {
+            epoch: 10,
+            rewards: vec![AwardBlockRewardParams {
+                miner: Address::new_id(1000),
+                penalty: TokenAmount::default(),
+                gas_reward: TokenAmount::default(),
+                win_count: 1,
+            }],
+        };
+
+        let mut applied = Vec::new();
+        let ret = apply_tipset_messages(&epoch_info, Some(&ExtraMessages), |msg| {
+            applied.push((msg.to, msg.method_num));
+            Ok(ret_with(ExitCode::OK))
+        })
+        .unwrap();
+        assert_eq!(
+            applied,
+            [
+                (WellKnownActor::System.address(), 100),
+                (WellKnownActor::Reward.address(), AWARD_BLOCK_REWARD_METHOD),
+                (WellKnownActor::Cron.address(), EPOCH_TICK_METHOD),
+                (WellKnownActor::System.address(), 101),
+            ]
+        );
+        assert_eq!(ret.rewards.len(), 1);
+        assert_eq!(ret.hooks.len(), 2);
+
+        // Without hooks, only the reward and cron messages are applied.
+        let mut applied = 0;
+        let ret = apply_tipset_messages(&epoch_info, None, |_| {
+            applied += 1;
+            Ok(ret_with(ExitCode::OK))
+        })
+        .unwrap();
+        assert_eq!(applied, 2);
+        assert!(ret.hooks.is_empty());
+
+        // Hook messages must succeed.
+        let err = apply_tipset_messages(&epoch_info, Some(&ExtraMessages), |msg| {
+            Ok(ret_with(if msg.method_num == 100 {
+                ExitCode::USR_ILLEGAL_STATE
+            } else {
+                ExitCode::OK
+            }))
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("hook message"), "{err}");
+    }
+}
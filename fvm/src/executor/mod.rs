@@ -1,23 +1,35 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 mod default;
+mod implicit;
 mod threaded;
 
 use std::fmt::Display;
+use std::sync::Arc;
 
+use anyhow::Context;
 use cid::Cid;
 pub use default::DefaultExecutor;
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::RawBytes;
-use fvm_shared::econ::TokenAmount;
+use fvm_shared::econ::{compute_miner_tip, TokenAmount};
 use fvm_shared::error::ExitCode;
 use fvm_shared::event::StampedEvent;
 use fvm_shared::message::Message;
 use fvm_shared::receipt::Receipt;
+use fvm_shared::ActorID;
+pub use implicit::{
+    cron_message, reward_message, EpochInfo, ImplicitTipsetRet, AWARD_BLOCK_REWARD_METHOD,
+    CRON_MESSAGE_GAS_LIMIT, EPOCH_TICK_METHOD, REWARD_MESSAGE_GAS_LIMIT,
+};
 use num_traits::Zero;
 pub use threaded::ThreadedExecutor;
 
-use crate::call_manager::Backtrace;
-use crate::trace::ExecutionTrace;
+use crate::blockstore::DiscardBlockstore;
+use crate::call_manager::{ActorLog, Artifact, Backtrace};
+use crate::machine::MachineHooks;
+use crate::trace::{CallFrameSummary, ExecutionTrace};
 use crate::Kernel;
 
 /// An executor executes messages on the underlying machine/kernel. It's responsible for:
@@ -44,8 +56,38 @@ pub trait Executor {
 
     /// Flushes the state-tree, returning the new root CID.
     fn flush(&mut self) -> anyhow::Result<Cid>;
+
+    /// Applies the implicit messages that end a tipset, in the canonical order: one reward message
+    /// per block (in block order), then the cron tick. Call this once for every epoch, after
+    /// applying the tipset's explicit messages, including null rounds (with no rewards).
+    ///
+    /// Fails if any of these messages fails, as the chain can't progress past such a tipset.
+    ///
+    /// Calls the [`MachineHooks`] returned by [`Executor::hooks`] (if any) before the reward
+    /// messages and after the cron tick, applying the implicit messages they return.
+    fn apply_implicit_tipset(
+        &mut self,
+        epoch_info: &EpochInfo,
+    ) -> anyhow::Result<ImplicitTipsetRet> {
+        implicit::apply_implicit_tipset(self, epoch_info)
+    }
+
+    /// Returns the machine's epoch-boundary hooks (see
+    /// [`MachineContext::hooks`](crate::machine::MachineContext::hooks)), if any.
+    fn hooks(&self) -> Option<Arc<dyn MachineHooks>> {
+        None
+    }
 }
 
+/// Returned by [`DefaultExecutor::execute_message`] when a message runs past the executor's
+/// execution timeout (see [`DefaultExecutor::set_execution_timeout`]).
+///
+/// Unlike other failures, no receipt is produced, and the message's effects on the state
+/// (including the gas precharged from the sender) are reverted, as if it had never been applied.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("message execution timed out")]
+pub struct ExecutionTimeout;
+
 /// A description of some failure encountered when applying a message.
 #[derive(Debug, Clone)]
 pub enum ApplyFailure {
@@ -86,13 +128,69 @@ pub struct ApplyRet {
     pub refund: TokenAmount,
     pub gas_refund: u64,
     pub gas_burned: u64,
+    /// Gas credited back to the message (e.g., for deleting state), already deducted from the
+    /// receipt's `gas_used`.
+    pub gas_credit: u64,
 
     /// Additional failure information for debugging, if any.
     pub failure_info: Option<ApplyFailure>,
     /// Execution trace information, for debugging.
     pub exec_trace: ExecutionTrace,
+    /// A summary of each call frame (caller, callee, method, gas used, events emitted, and blocks
+    /// written), in the order in which they were entered (only when
+    /// [`MachineContext::tracing`](crate::machine::MachineContext::tracing) is enabled).
+    pub call_frames: Vec<CallFrameSummary>,
     /// Events generated while applying the message.
     pub events: Vec<StampedEvent>,
+    /// Debug artifacts stored by actors while applying the message (in debug mode only).
+    pub artifacts: Vec<Artifact>,
+    /// Debug logs emitted by actors while applying the message (in debug mode only), unless
+    /// they're sent to [`MachineContext::actor_log_sink`](crate::machine::MachineContext::actor_log_sink)
+    /// instead. Logs past a per-message quota of 16MiB are dropped.
+    pub logs: Vec<ActorLog>,
+    /// The CIDs of the state blocks read while applying the message, in the order in which they
+    /// were first read (only when [`MachineContext::witness`](crate::machine::MachineContext::witness)
+    /// is enabled).
+    pub witness: Vec<Cid>,
+    /// Whether the message was applied as a dry run ([`ApplyKind::DryRun`]), in which case its
+    /// effects on the state tree were reverted and the receipt must not be included on chain.
+    pub dry_run: bool,
+    /// The nonce and fees of the message, for fee market analytics and replacement policies (only
+    /// for explicit and dry-run messages that passed validation). Not part of the receipt stored
+    /// on chain.
+    pub fee_details: Option<FeeDetails>,
+}
+
+/// The nonce and fees of an applied message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeDetails {
+    /// The message's sender.
+    pub origin: ActorID,
+    /// The origin's nonce consumed by the message (i.e., the message's sequence).
+    pub origin_nonce: u64,
+    /// The base fee at which the message was applied.
+    pub base_fee: TokenAmount,
+    /// The message's gas fee cap.
+    pub gas_fee_cap: TokenAmount,
+    /// The gas premium offered by the message.
+    pub gas_premium: TokenAmount,
+    /// The premium paid to the miner per unit of gas (limit): the offered premium, capped such
+    /// that it doesn't exceed the fee cap in addition to the base fee.
+    pub effective_premium: TokenAmount,
+}
+
+impl FeeDetails {
+    /// Computes the fee details of a message sent by `origin` and applied at `base_fee`.
+    pub fn new(origin: ActorID, msg: &Message, base_fee: &TokenAmount) -> Self {
+        FeeDetails {
+            origin,
+            origin_nonce: msg.sequence,
+            base_fee: base_fee.clone(),
+            gas_fee_cap: msg.gas_fee_cap.clone(),
+            gas_premium: msg.gas_premium.clone(),
+            effective_premium: compute_miner_tip(base_fee, &msg.gas_fee_cap, &msg.gas_premium),
+        }
+    }
 }
 
 impl ApplyRet {
@@ -116,21 +214,143 @@ impl ApplyRet {
             refund: TokenAmount::zero(),
             gas_refund: 0,
             gas_burned: 0,
+            gas_credit: 0,
             failure_info: Some(ApplyFailure::PreValidation(message.into())),
             exec_trace: vec![],
+            call_frames: vec![],
             events: vec![],
+            artifacts: vec![],
+            logs: vec![],
+            witness: vec![],
+            dry_run: false,
+            fee_details: None,
         }
     }
+
+    /// Returns the artifact with the given name most recently stored by the given actor, if any.
+    pub fn artifact(&self, actor: ActorID, name: &str) -> Option<&Artifact> {
+        self.artifacts
+            .iter()
+            .rev()
+            .find(|a| a.actor == actor && a.name == name)
+    }
 }
 
 /// The kind of message being applied:
 ///
 /// 1. Explicit messages may only come from account actors and charge the sending account for gas
-/// consumed.
+///    consumed.
 /// 2. Implicit messages may come from any actor, ignore the nonce, and charge no gas (but still
-/// account for it).
+///    account for it).
+/// 3. Dry-run messages are validated, executed and charged like explicit messages, but all their
+///    effects on the state tree are reverted afterwards. The result (including events, traces and
+///    gas) is returned as usual, with [`ApplyRet::dry_run`] set. This is meant for off-chain "call"
+///    and gas estimation endpoints.
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub enum ApplyKind {
     Explicit,
     Implicit,
+    DryRun,
+}
+
+/// The bit width of the AMT of a message's events, whose root is committed to in the message's
+/// receipt (see [`Receipt::events_root`]).
+pub const EVENTS_AMT_BITWIDTH: u32 = 5;
+
+/// Computes the root of the AMT of a message's events, exactly as committed to in the message's
+/// receipt (see [`Receipt::events_root`]). Returns `None` if the message emitted no events.
+///
+/// The events are stored in emission order (i.e., in the order of [`ApplyRet::events`]), CBOR
+/// encoded, in an AMT with a bit width of [`EVENTS_AMT_BITWIDTH`].
+pub fn compute_events_root(events: &[StampedEvent]) -> anyhow::Result<Option<Cid>> {
+    build_events_amt(DiscardBlockstore, events)
+}
+
+/// Like [`compute_events_root`], but also writes the events AMT to the given blockstore so it can
+/// be served to clients.
+pub fn build_events_amt<BS: Blockstore>(
+    store: BS,
+    events: &[StampedEvent],
+) -> anyhow::Result<Option<Cid>> {
+    if events.is_empty() {
+        return Ok(None);
+    }
+    let root = Amt::new_from_iter_with_bit_width(store, EVENTS_AMT_BITWIDTH, events.iter())
+        .context("failed to construct events AMT")?;
+    Ok(Some(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::address::Address;
+    use fvm_shared::event::{ActorEvent, Entry, Flags};
+    use fvm_shared::IPLD_RAW;
+
+    use super::*;
+
+    fn event(emitter: ActorID, value: &[u8]) -> StampedEvent {
+        StampedEvent::new(
+            emitter,
+            ActorEvent::from(vec![Entry {
+                flags: Flags::FLAG_INDEXED_ALL,
+                key: "k".into(),
+                codec: IPLD_RAW,
+                value: value.to_vec(),
+            }]),
+        )
+    }
+
+    #[test]
+    fn events_root_vectors() {
+        assert_eq!(compute_events_root(&[]).unwrap(), None);
+
+        let events = vec![event(1000, b"foo"), event(1001, b"bar")];
+        let root = compute_events_root(&events).unwrap().unwrap();
+        assert_eq!(
+            root.to_string(),
+            "bafy2bzaceboqtuiupyptccx2rswau3yxuzjwsb2mxe3l57h57di6bh3k4qbju"
+        );
+
+        // Order matters.
+        let reversed: Vec<_> = events.iter().cloned().rev().collect();
+        assert_ne!(compute_events_root(&reversed).unwrap(), Some(root));
+
+        // The AMT can be loaded back from a store.
+        let store = MemoryBlockstore::new();
+        assert_eq!(build_events_amt(&store, &events).unwrap(), Some(root));
+        let amt: Amt<StampedEvent, _> = Amt::load(&root, &store).unwrap();
+        assert_eq!(amt.count(), 2);
+        assert_eq!(amt.get(1).unwrap(), Some(&events[1]));
+    }
+
+    #[test]
+    fn fee_details() {
+        let msg = Message {
+            version: 0,
+            from: Address::new_id(1000),
+            to: Address::new_id(1001),
+            sequence: 7,
+            value: TokenAmount::default(),
+            method_num: 0,
+            params: RawBytes::default(),
+            gas_limit: 1_000_000,
+            gas_fee_cap: TokenAmount::from_atto(150),
+            gas_premium: TokenAmount::from_atto(20),
+        };
+        let details = |base_fee| FeeDetails::new(1000, &msg, &TokenAmount::from_atto(base_fee));
+
+        let d = details(100);
+        assert_eq!(d.origin, 1000);
+        assert_eq!(d.origin_nonce, 7);
+        assert_eq!(d.base_fee, TokenAmount::from_atto(100));
+        assert_eq!(d.gas_fee_cap, TokenAmount::from_atto(150));
+        assert_eq!(d.gas_premium, TokenAmount::from_atto(20));
+        assert_eq!(d.effective_premium, TokenAmount::from_atto(20));
+
+        // The premium is capped by the fee cap...
+        assert_eq!(details(140).effective_premium, TokenAmount::from_atto(10));
+        // ...and nothing is left for it once the base fee exceeds the fee cap.
+        assert_eq!(details(200).effective_premium, TokenAmount::from_atto(0));
+    }
 }
@@ -1,11 +1,14 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::sync::Arc;
+
 use anyhow::anyhow;
 use cid::Cid;
 use fvm_shared::message::Message;
 use lazy_static::lazy_static;
 
 use super::{ApplyKind, ApplyRet, Executor};
+use crate::machine::MachineHooks;
 
 lazy_static! {
     static ref EXEC_POOL: yastl::Pool = yastl::Pool::with_config(
@@ -50,4 +53,8 @@ where
     fn flush(&mut self) -> anyhow::Result<Cid> {
         self.0.flush()
     }
+
+    fn hooks(&self) -> Option<Arc<dyn MachineHooks>> {
+        self.0.hooks()
+    }
 }
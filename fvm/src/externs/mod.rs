@@ -2,10 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 //! This module contains the logic to invoke the node by traversing Boundary A.
 
+use anyhow::anyhow;
 use cid::Cid;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::consensus::ConsensusFault;
 
+use crate::gas::estimator::TipsetFees;
+
 pub trait Externs: Rand + Consensus + Chain {}
 
 /// Consensus related methods.
@@ -21,12 +24,14 @@ pub trait Consensus {
 
 /// Randomness provider trait
 pub trait Rand {
-    /// Gets 32 bytes of randomness for ChainRand paramaterized by the DomainSeparationTag,
-    /// ChainEpoch, Entropy from the ticket chain.
+    /// Gets 32 bytes of base randomness from the ticket chain at the given epoch. Actors derive
+    /// randomness for specific purposes from it with
+    /// [`draw_randomness`](fvm_shared::randomness::draw_randomness).
     fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]>;
 
-    /// Gets 32 bytes of randomness for ChainRand paramaterized by the DomainSeparationTag,
-    /// ChainEpoch, Entropy from the latest beacon entry.
+    /// Gets 32 bytes of base randomness from the latest beacon entry at the given epoch. Actors
+    /// derive randomness for specific purposes from it with
+    /// [`draw_randomness`](fvm_shared::randomness::draw_randomness).
     fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]>;
 }
 
@@ -34,4 +39,12 @@ pub trait Rand {
 pub trait Chain {
     /// Gets the CID for a given tipset.
     fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid>;
+
+    /// Gets the fees of up to `count` non-null tipsets up to and including the one at `epoch`,
+    /// most recent first. Only used for fee estimation (see [`crate::gas::estimator`]), so clients
+    /// that don't estimate fees needn't implement it.
+    fn get_fee_history(&self, epoch: ChainEpoch, count: u32) -> anyhow::Result<Vec<TipsetFees>> {
+        let _ = (epoch, count);
+        Err(anyhow!("fee history is not supported by this client"))
+    }
 }
@@ -59,3 +59,23 @@ impl GasCharge {
         self.compute_gas + self.other_gas
     }
 }
+
+/// A gas credit (a "negative" charge) in the VM, e.g. for deleting state. Credits don't change the
+/// gas available during execution. Instead, they're refunded at the end of the message, up to a cap
+/// set by the price list (see [`PriceList::gas_credit_refund`](super::PriceList::gas_credit_refund)).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "testing", derive(PartialEq, Eq))]
+pub struct GasCredit {
+    pub name: Cow<'static, str>,
+    /// Gas credited.
+    pub amount: Gas,
+}
+
+impl GasCredit {
+    pub fn new(name: impl Into<Cow<'static, str>>, amount: Gas) -> Self {
+        Self {
+            name: name.into(),
+            amount,
+        }
+    }
+}
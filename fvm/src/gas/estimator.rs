@@ -0,0 +1,253 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Fee estimation from recent chain history.
+//!
+//! The client supplies recent base fees and included message premiums through
+//! [`Chain::get_fee_history`], and the [`FeeEstimator`] turns them into fee suggestions following
+//! the Filecoin gas market rules (as implemented by Lotus), so that wallets and nodes share a
+//! single implementation.
+
+use anyhow::anyhow;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::BLOCK_GAS_LIMIT;
+use num_traits::Zero;
+
+use crate::externs::Chain;
+
+/// The gas each block targets. The base fee rises when blocks use more, and falls when they use
+/// less.
+pub const BLOCK_GAS_TARGET: u64 = BLOCK_GAS_LIMIT / 2;
+
+/// The base fee may change by at most `1/BASE_FEE_MAX_CHANGE_DENOM` from one epoch to the next.
+pub const BASE_FEE_MAX_CHANGE_DENOM: u64 = 8;
+
+/// The base fee never drops below this amount (in attoFIL).
+pub const MINIMUM_BASE_FEE: u64 = 100;
+
+/// The smallest gas premium (in attoFIL) ever suggested.
+pub const MINIMUM_GAS_PREMIUM: u64 = 100_000;
+
+/// Fee data for a single (non-null) tipset, as reported by the client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TipsetFees {
+    /// The tipset's epoch.
+    pub epoch: ChainEpoch,
+    /// The base fee messages in this tipset paid.
+    pub base_fee: TokenAmount,
+    /// The number of blocks in the tipset.
+    pub blocks: u64,
+    /// The sum of the gas limits of the messages included in the tipset.
+    pub gas_limit_used: u64,
+    /// The premium and gas limit of every message included in the tipset.
+    pub premiums: Vec<(TokenAmount, u64)>,
+}
+
+/// Suggested fees for a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeSuggestion {
+    /// The projected base fee of the next tipset.
+    pub base_fee: TokenAmount,
+    /// The suggested gas premium.
+    pub gas_premium: TokenAmount,
+    /// The suggested gas fee cap: enough to cover the premium after the base fee has risen at the
+    /// maximum rate for [`FeeEstimator::max_queue_epochs`].
+    pub gas_fee_cap: TokenAmount,
+}
+
+/// Computes the base fee of the tipset following one with the given base fee, number of blocks,
+/// and total gas limit of included messages.
+pub fn next_base_fee(base_fee: &TokenAmount, gas_limit_used: u64, blocks: u64) -> TokenAmount {
+    if blocks == 0 {
+        return base_fee.clone();
+    }
+    let target = BLOCK_GAS_TARGET as i64;
+    let delta = ((gas_limit_used / blocks) as i64 - target).clamp(-target, target);
+    let change = (base_fee * delta)
+        .div_floor(target)
+        .div_floor(BASE_FEE_MAX_CHANGE_DENOM);
+    let next = base_fee + change;
+    let minimum = TokenAmount::from_atto(MINIMUM_BASE_FEE);
+    if next < minimum {
+        minimum
+    } else {
+        next
+    }
+}
+
+/// Computes the fee cap needed to cover `gas_premium` on top of `base_fee` after the base fee has
+/// risen at the maximum rate for `epochs` epochs.
+pub fn gas_fee_cap(base_fee: &TokenAmount, gas_premium: &TokenAmount, epochs: u32) -> TokenAmount {
+    let denom = BigInt::from(BASE_FEE_MAX_CHANGE_DENOM);
+    let num = &denom + 1u32;
+    let future = (base_fee * num.pow(epochs)).div_ceil(denom.pow(epochs));
+    future + gas_premium
+}
+
+/// Suggests fees from recent chain history.
+#[derive(Clone, Debug)]
+pub struct FeeEstimator {
+    /// The number of epochs within which the message should be included. Twice as many tipsets
+    /// are sampled when computing the premium, and smaller values yield higher premiums.
+    pub inclusion_epochs: u32,
+    /// The number of epochs the fee cap must survive maximal base fee increases for.
+    pub max_queue_epochs: u32,
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        Self {
+            inclusion_epochs: 10,
+            max_queue_epochs: 20,
+        }
+    }
+}
+
+impl FeeEstimator {
+    /// The number of tipsets of history [`FeeEstimator::suggest`] uses.
+    pub fn lookback(&self) -> u32 {
+        self.inclusion_epochs.max(1) * 2
+    }
+
+    /// Suggests fees for a message sent on top of the tipset at `epoch`, fetching the history from
+    /// the client.
+    pub fn suggest_from_chain(
+        &self,
+        chain: &impl Chain,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<FeeSuggestion> {
+        let history = chain.get_fee_history(epoch, self.lookback())?;
+        self.suggest(&history)
+    }
+
+    /// Suggests fees from `history`, the fees of the most recent tipsets, most recent first.
+    pub fn suggest(&self, history: &[TipsetFees]) -> anyhow::Result<FeeSuggestion> {
+        let head = history
+            .first()
+            .ok_or_else(|| anyhow!("no fee history to estimate from"))?;
+        let history = &history[..history.len().min(self.lookback() as usize)];
+
+        let base_fee = next_base_fee(&head.base_fee, head.gas_limit_used, head.blocks);
+        let gas_premium = self.gas_premium(history);
+        let gas_fee_cap = gas_fee_cap(&base_fee, &gas_premium, self.max_queue_epochs);
+        Ok(FeeSuggestion {
+            base_fee,
+            gas_premium,
+            gas_fee_cap,
+        })
+    }
+
+    /// Picks the premium that would have placed a message in the top half of the gas targeted by
+    /// the sampled blocks (plus a 5% margin), scaled up when inclusion is wanted within one or two
+    /// epochs.
+    fn gas_premium(&self, history: &[TipsetFees]) -> TokenAmount {
+        let blocks: u64 = history.iter().map(|ts| ts.blocks).sum();
+        let mut premiums: Vec<_> = history.iter().flat_map(|ts| &ts.premiums).collect();
+        premiums.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let mut at = (BLOCK_GAS_TARGET * blocks / 2) as i128;
+        at += (BLOCK_GAS_TARGET * blocks / (2 * 20)) as i128;
+        let (mut prev1, mut prev2) = (TokenAmount::zero(), TokenAmount::zero());
+        for (premium, limit) in premiums {
+            prev2 = std::mem::replace(&mut prev1, premium.clone());
+            at -= *limit as i128;
+            if at < 0 {
+                break;
+            }
+        }
+        let mut premium = if prev2.is_zero() {
+            prev1
+        } else {
+            (prev1 + prev2).div_floor(2)
+        };
+
+        let minimum = TokenAmount::from_atto(MINIMUM_GAS_PREMIUM);
+        if premium < minimum {
+            premium = minimum;
+        }
+        match self.inclusion_epochs {
+            0 | 1 => premium * 2,
+            2 => (premium * 3).div_floor(2),
+            _ => premium,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atto(x: u64) -> TokenAmount {
+        TokenAmount::from_atto(x)
+    }
+
+    fn tipset(epoch: ChainEpoch, base_fee: u64, premiums: &[(u64, u64)]) -> TipsetFees {
+        TipsetFees {
+            epoch,
+            base_fee: atto(base_fee),
+            blocks: 1,
+            gas_limit_used: premiums.iter().map(|(_, l)| l).sum(),
+            premiums: premiums.iter().map(|&(p, l)| (atto(p), l)).collect(),
+        }
+    }
+
+    #[test]
+    fn base_fee_update() {
+        let base = atto(800_000);
+        // At target, the base fee doesn't move.
+        assert_eq!(next_base_fee(&base, BLOCK_GAS_TARGET, 1), base);
+        // Full blocks raise it by 1/8th, empty blocks lower it by 1/8th.
+        assert_eq!(next_base_fee(&base, BLOCK_GAS_LIMIT, 1), atto(900_000));
+        assert_eq!(next_base_fee(&base, 0, 1), atto(700_000));
+        // Usage is averaged over the blocks, and capped.
+        assert_eq!(next_base_fee(&base, BLOCK_GAS_LIMIT * 2, 4), base);
+        assert_eq!(next_base_fee(&base, BLOCK_GAS_LIMIT * 4, 1), atto(900_000));
+        // It never drops below the minimum.
+        assert_eq!(next_base_fee(&atto(101), 0, 1), atto(MINIMUM_BASE_FEE));
+    }
+
+    #[test]
+    fn fee_cap() {
+        assert_eq!(gas_fee_cap(&atto(800), &atto(5), 0), atto(805));
+        assert_eq!(gas_fee_cap(&atto(800), &atto(5), 1), atto(905));
+        assert_eq!(gas_fee_cap(&atto(800), &atto(5), 2), atto(1018));
+    }
+
+    #[test]
+    fn suggest() {
+        let estimator = FeeEstimator::default();
+        assert!(estimator.suggest(&[]).is_err());
+
+        // Quiet chain: the minimum premium is suggested.
+        let quiet = [tipset(2, 1000, &[]), tipset(1, 1000, &[])];
+        let s = estimator.suggest(&quiet).unwrap();
+        assert_eq!(s.base_fee, atto(875));
+        assert_eq!(s.gas_premium, atto(MINIMUM_GAS_PREMIUM));
+        assert_eq!(
+            s.gas_fee_cap,
+            gas_fee_cap(&s.base_fee, &s.gas_premium, estimator.max_queue_epochs)
+        );
+
+        // Busy chain: the premium that buys into the top half of the targeted gas is suggested.
+        let busy = [
+            tipset(
+                2,
+                1000,
+                &[(300_000, BLOCK_GAS_TARGET), (200_000, BLOCK_GAS_TARGET)],
+            ),
+            tipset(1, 1000, &[(500_000, BLOCK_GAS_TARGET / 2)]),
+        ];
+        let s = estimator.suggest(&busy).unwrap();
+        assert_eq!(s.base_fee, atto(1125));
+        assert_eq!(s.gas_premium, atto(400_000));
+
+        // Wanting faster inclusion raises the premium.
+        let fast = FeeEstimator {
+            inclusion_epochs: 1,
+            ..Default::default()
+        };
+        assert_eq!(fast.suggest(&busy).unwrap().gas_premium, atto(800_000));
+    }
+}
@@ -9,13 +9,17 @@ use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 use anyhow::Context;
 use num_traits::Zero;
 
-pub use self::charge::GasCharge;
+pub use self::charge::{GasCharge, GasCredit};
 pub use self::outputs::GasOutputs;
-pub use self::price_list::{price_list_by_network_version, PriceList, WasmGasPrices};
+pub use self::price_list::{
+    price_list_by_network_version, try_price_list_by_network_version, PriceList, WasmGasPrices,
+};
 pub use self::timer::{GasDuration, GasInstant, GasTimer};
 use crate::kernel::{ClassifyResult, ExecutionError, Result};
+use crate::trace::ExecutionEvent;
 
 mod charge;
+pub mod estimator;
 mod outputs;
 mod price_list;
 mod timer;
@@ -168,7 +172,11 @@ pub struct GasTracker {
     gas_limit: Gas,
     gas_used: Cell<Gas>,
     gas_snapshots: Vec<GasSnapshot>,
-    trace: Option<RefCell<Vec<GasCharge>>>,
+    /// Gas credited so far, refunded (up to a cap) at the end of the message.
+    gas_credited: Cell<Gas>,
+    /// The gas credited at the start of each open transaction, restored on revert.
+    credit_snapshots: Vec<Gas>,
+    trace: Option<RefCell<Vec<ExecutionEvent>>>,
 }
 
 impl GasTracker {
@@ -185,6 +193,8 @@ impl GasTracker {
             gas_limit,
             gas_used: Cell::new(gas_used),
             gas_snapshots: Vec::new(),
+            gas_credited: Cell::new(Gas::zero()),
+            credit_snapshots: Vec::new(),
             trace: enable_tracing.then_some(Default::default()),
         }
     }
@@ -210,7 +220,7 @@ impl GasTracker {
         if let Some(trace) = &self.trace {
             let mut charge = GasCharge::new(name.to_owned(), to_use, Gas::zero());
             let timer = GasTimer::new(&mut charge.elapsed);
-            trace.borrow_mut().push(charge);
+            trace.borrow_mut().push(ExecutionEvent::GasCharge(charge));
             res.map(|_| timer)
         } else {
             res.map(|_| GasTimer::empty())
@@ -224,13 +234,43 @@ impl GasTracker {
         let res = self.charge_gas_inner(to_use);
         if let Some(trace) = &self.trace {
             let timer = GasTimer::new(&mut charge.elapsed);
-            trace.borrow_mut().push(charge);
+            trace.borrow_mut().push(ExecutionEvent::GasCharge(charge));
             res.map(|_| timer)
         } else {
             res.map(|_| GasTimer::empty())
         }
     }
 
+    /// Credits gas back to the message, e.g. for deleting state. Credits don't make more gas
+    /// available during execution. They're refunded at the end of the message, up to the cap set
+    /// by the price list, and are discarded along with the state changes of reverted transactions.
+    pub fn credit_gas(&self, credit: GasCredit) {
+        log::trace!("crediting gas: {} {}", &credit.name, credit.amount);
+        self.gas_credited
+            .set(self.gas_credited.get() + credit.amount);
+        if let Some(trace) = &self.trace {
+            trace.borrow_mut().push(ExecutionEvent::GasCredit(credit));
+        }
+    }
+
+    /// Begin a transaction, recording the gas credited so far. Transactions stack.
+    pub fn begin_transaction(&mut self) {
+        self.credit_snapshots.push(self.gas_credited.get());
+    }
+
+    /// End a transaction, discarding the gas credited within it if reverting.
+    pub fn end_transaction(&mut self, revert: bool) -> Result<()> {
+        let credited = self
+            .credit_snapshots
+            .pop()
+            .context("no gas credit snapshots to pop")
+            .or_fatal()?;
+        if revert {
+            *self.gas_credited.get_mut() = credited;
+        }
+        Ok(())
+    }
+
     /// Push a new gas limit.
     pub fn push_limit(&mut self, new_limit: Gas) {
         self.gas_snapshots.push(GasSnapshot {
@@ -269,7 +309,13 @@ impl GasTracker {
         self.gas_limit - self.gas_used.get()
     }
 
-    pub fn drain_trace(&self) -> impl Iterator<Item = GasCharge> + '_ {
+    /// Getter for gas credited, before applying any cap.
+    pub fn gas_credited(&self) -> Gas {
+        self.gas_credited.get()
+    }
+
+    /// Drains the traced gas charges and credits, in order.
+    pub fn drain_trace(&self) -> impl Iterator<Item = ExecutionEvent> + '_ {
         self.trace
             .as_ref()
             .map(|v| v.take().into_iter())
@@ -308,6 +354,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn gas_credits() -> Result<()> {
+        let mut t = GasTracker::new(Gas::new(20), Gas::zero(), true);
+        t.credit_gas(GasCredit::new("a", Gas::new(3)));
+
+        t.begin_transaction();
+        t.credit_gas(GasCredit::new("b", Gas::new(5)));
+        t.end_transaction(false)?;
+        assert_eq!(t.gas_credited(), Gas::new(8));
+
+        t.begin_transaction();
+        t.credit_gas(GasCredit::new("c", Gas::new(7)));
+        t.end_transaction(true)?;
+        assert_eq!(t.gas_credited(), Gas::new(8));
+        assert!(t.end_transaction(false).is_err());
+
+        // Credits don't make more gas available.
+        assert_eq!(t.gas_available(), Gas::new(20));
+        // But they are traced, even if reverted.
+        let names: Vec<_> = t
+            .drain_trace()
+            .map(|e| match e {
+                ExecutionEvent::GasCredit(c) => c.name,
+                _ => panic!("unexpected trace event"),
+            })
+            .collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        Ok(())
+    }
+
     #[test]
     fn milligas_to_gas_round() {
         assert_eq!(milligas_to_gas(100, false), 0);
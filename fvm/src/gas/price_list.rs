@@ -20,7 +20,7 @@ use fvm_wasm_instrument::gas_metering::{InstructionCost, Operator, Rules};
 use lazy_static::lazy_static;
 use num_traits::Zero;
 
-use super::GasCharge;
+use super::{GasCharge, GasCredit};
 use crate::gas::Gas;
 use crate::kernel::SupportedHashes;
 
@@ -143,12 +143,25 @@ lazy_static! {
                 Ripemd160 => ScalingCost {
                     flat: Gas::zero(),
                     scale: Gas::new(35)
+                },
+                Blake3_256 => ScalingCost {
+                    flat: Gas::zero(),
+                    scale: Gas::new(6)
+                },
+                Sha3_512 => ScalingCost {
+                    flat: Gas::zero(),
+                    scale: Gas::new(62)
                 }
             }
         },
 
         compute_unsealed_sector_cid_base: Gas::new(98647),
         verify_seal_base: Gas::new(2000), // TODO revisit potential removal of this
+        // The benchmarked cost of verifying a single seal, which the builtin miner actor prepays
+        // (as `GAS_ON_SUBMIT_VERIFY_SEAL`) for every seal deferred to the power actor's batch
+        // verification in cron. Seals in a batch are verified in parallel, so this is an upper
+        // bound.
+        verify_seal_batch_per: Gas::new(34721049),
 
         verify_aggregate_seal_per: [
             (
@@ -275,6 +288,13 @@ lazy_static! {
 
         install_wasm_per_byte_cost: Zero::zero(),
 
+        // Validating, instrumenting, and compiling a module takes roughly 50ns/byte, plus a fixed
+        // ~100us of setup.
+        install_code: ScalingCost {
+            flat: Gas::new(1_000_000),
+            scale: Gas::new(500),
+        },
+
         wasm_rules: WasmGasPrices{
             // Use the default instruction cost of 4 everywhere.
             instruction_default: Gas::new(4),
@@ -312,6 +332,10 @@ lazy_static! {
         ipld_cbor_scan_per_field: Gas::new(35),
         ipld_link_tracked: Gas::new(300),
         ipld_link_checked: Gas::new(300),
+
+        // No credits yet.
+        storage_deletion_credit: ScalingCost::zero(),
+        gas_credit_cap_divisor: 5,
     };
 }
 
@@ -423,6 +447,8 @@ pub struct PriceList {
 
     pub(crate) compute_unsealed_sector_cid_base: Gas,
     pub(crate) verify_seal_base: Gas,
+    /// Gas cost per seal verified with [`FilecoinKernel::verify_seal_batch`][crate::kernel::filecoin::FilecoinKernel::verify_seal_batch].
+    pub(crate) verify_seal_batch_per: Gas,
     pub(crate) verify_aggregate_seal_per: HashMap<RegisteredSealProof, Gas>,
     pub(crate) verify_aggregate_seal_steps: HashMap<RegisteredSealProof, StepCost>,
 
@@ -473,6 +499,9 @@ pub struct PriceList {
     /// Gas cost of compiling a Wasm module during install.
     pub(crate) install_wasm_per_byte_cost: Gas,
 
+    /// Gas cost of validating and compiling actor code installed from raw bytecode.
+    pub(crate) install_code: ScalingCost,
+
     /// Actor IDs that can be updated for free.
     pub(crate) preloaded_actors: Vec<ActorID>,
 
@@ -487,6 +516,12 @@ pub struct PriceList {
 
     /// Gas cost for checking if CID is reachable.
     pub(crate) ipld_link_checked: Gas,
+
+    /// Gas credited for deleting state, per byte deleted.
+    pub(crate) storage_deletion_credit: ScalingCost,
+    /// Gas credits may refund at most `1/gas_credit_cap_divisor` of the gas used by a message.
+    /// Zero disables refunds entirely.
+    pub(crate) gas_credit_cap_divisor: u64,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -678,6 +713,16 @@ impl PriceList {
     pub fn on_verify_seal(&self, _info: &SealVerifyInfo) -> GasCharge {
         GasCharge::new("OnVerifySeal", self.verify_seal_base, Zero::zero())
     }
+
+    /// Returns gas required for verifying a batch of seals, charged up-front for the whole batch.
+    #[inline]
+    pub fn on_verify_seal_batch(&self, infos: &[SealVerifyInfo]) -> GasCharge {
+        GasCharge::new(
+            "OnVerifySealBatch",
+            self.verify_seal_batch_per * infos.len(),
+            Zero::zero(),
+        )
+    }
     #[inline]
     pub fn on_verify_aggregate_seals(
         &self,
@@ -743,6 +788,17 @@ impl PriceList {
         GasCharge::new("OnVerifyPost", gas_used, Zero::zero())
     }
 
+    /// Returns gas required for verifying a batch of PoSts, charged up-front for the whole batch:
+    /// the sum of the costs of verifying each PoSt individually.
+    #[inline]
+    pub fn on_verify_post_batch(&self, infos: &[WindowPoStVerifyInfo]) -> GasCharge {
+        let gas_used = infos
+            .iter()
+            .map(|info| self.on_verify_post(info).compute_gas)
+            .fold(Gas::zero(), |total, gas| total + gas);
+        GasCharge::new("OnVerifyPostBatch", gas_used, Zero::zero())
+    }
+
     /// Returns gas required for verifying consensus fault.
     #[inline]
     pub fn on_verify_consensus_fault(
@@ -956,6 +1012,17 @@ impl PriceList {
         )
     }
 
+    /// Returns the gas required for installing actor code from raw bytecode: validating and
+    /// compiling the code, and committing it to the state blockstore.
+    pub fn on_install_code(&self, code_size: usize) -> GasCharge {
+        let link = self.on_block_link(SupportedHashes::Blake2b256, code_size);
+        GasCharge::new(
+            "OnInstallCode",
+            link.compute_gas + self.install_code.apply(code_size),
+            link.other_gas,
+        )
+    }
+
     #[inline]
     pub fn on_actor_event(&self, entries: usize, keysize: usize, valuesize: usize) -> GasCharge {
         // Here we estimate per-event overhead given the constraints on event values.
@@ -996,17 +1063,45 @@ impl PriceList {
     pub fn on_set_root(&self) -> GasCharge {
         GasCharge::new("OnActorSetRoot", self.ipld_link_checked, Gas::zero())
     }
+
+    /// Returns the gas credited for deleting `size` bytes of state.
+    #[inline]
+    pub fn on_state_delete(&self, size: usize) -> GasCredit {
+        GasCredit::new("OnStateDelete", self.storage_deletion_credit.apply(size))
+    }
+
+    /// Returns the gas refunded to a message that used `gas_used` gas (before refunds) and was
+    /// credited `gas_credited` gas, capped at `1/gas_credit_cap_divisor` of the gas used.
+    pub fn gas_credit_refund(&self, gas_used: Gas, gas_credited: Gas) -> Gas {
+        match self.gas_credit_cap_divisor {
+            0 => Gas::zero(),
+            divisor => gas_credited.min(Gas::from_milligas(gas_used.as_milligas() / divisor)),
+        }
+    }
 }
 
 /// Returns gas price list by NetworkVersion for gas consumption.
+///
+/// Panics if the network version isn't supported (see [`try_price_list_by_network_version`]).
 pub fn price_list_by_network_version(network_version: NetworkVersion) -> &'static PriceList {
+    try_price_list_by_network_version(network_version)
+        .unwrap_or_else(|| panic!("network version {network_version} not supported"))
+}
+
+/// Returns gas price list by NetworkVersion for gas consumption, or `None` if the network version
+/// isn't supported.
+pub fn try_price_list_by_network_version(
+    network_version: NetworkVersion,
+) -> Option<&'static PriceList> {
     match network_version {
         NetworkVersion::V21
         | NetworkVersion::V22
         | NetworkVersion::V23
         | NetworkVersion::V24
-        | NetworkVersion::V25 => &WATERMELON_PRICES,
-        _ => panic!("network version {nv} not supported", nv = network_version),
+        | NetworkVersion::V25 => Some(&WATERMELON_PRICES),
+        #[cfg(feature = "nv26-dev")]
+        NetworkVersion::V26 => Some(&WATERMELON_PRICES),
+        _ => None,
     }
 }
 
@@ -1368,3 +1463,26 @@ fn test_step_cost_zero() {
     assert_eq!(costs.lookup(0), Gas::new(1));
     assert_eq!(costs.lookup(10), Gas::new(1));
 }
+
+#[test]
+fn test_gas_credit_refund() {
+    // No credits are granted yet.
+    assert!(WATERMELON_PRICES.on_state_delete(1000).amount.is_zero());
+
+    let mut prices = WATERMELON_PRICES.clone();
+    prices.storage_deletion_credit = ScalingCost {
+        flat: Gas::zero(),
+        scale: Gas::new(10),
+    };
+    let credit = prices.on_state_delete(100).amount;
+    assert_eq!(credit, Gas::new(1000));
+    // Capped at a fifth of the gas used.
+    assert_eq!(prices.gas_credit_refund(Gas::new(10000), credit), credit);
+    assert_eq!(
+        prices.gas_credit_refund(Gas::new(1000), credit),
+        Gas::new(200)
+    );
+
+    prices.gas_credit_cap_divisor = 0;
+    assert!(prices.gas_credit_refund(Gas::new(10000), credit).is_zero());
+}
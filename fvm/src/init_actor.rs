@@ -22,11 +22,12 @@ use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_encoding::CborStore;
 use fvm_ipld_hamt::Hamt;
 use fvm_shared::address::{Address, Payload};
+use fvm_shared::well_known::WellKnownActor;
 use fvm_shared::{ActorID, HAMT_BIT_WIDTH};
 
 use crate::state_tree::{ActorState, StateTree};
 
-pub const INIT_ACTOR_ID: ActorID = 1;
+pub const INIT_ACTOR_ID: ActorID = WellKnownActor::Init.id();
 
 use crate::kernel::{ClassifyResult, Result};
 
@@ -102,6 +103,45 @@ impl State {
         Ok(id)
     }
 
+    /// Returns true if the supplied code CID has been recorded as installed (with
+    /// [`State::record_installed_code`]).
+    #[cfg(feature = "m2-native")]
+    pub fn is_code_installed<B>(&self, store: B, code_cid: &Cid) -> Result<bool>
+    where
+        B: Blockstore,
+    {
+        let installed: Vec<Cid> = store
+            .get_cbor(&self.installed_actors)
+            .context("failed to load installed actors")
+            .or_fatal()?
+            .context("installed actors not found")
+            .or_fatal()?;
+        Ok(installed.contains(code_cid))
+    }
+
+    /// Records the supplied code CID (as returned by the `install_code` syscall) as installed.
+    /// Returns false if it was already recorded.
+    #[cfg(feature = "m2-native")]
+    pub fn record_installed_code<B>(&mut self, store: B, code_cid: Cid) -> Result<bool>
+    where
+        B: Blockstore,
+    {
+        let mut installed: Vec<Cid> = store
+            .get_cbor(&self.installed_actors)
+            .context("failed to load installed actors")
+            .or_fatal()?
+            .context("installed actors not found")
+            .or_fatal()?;
+        if installed.contains(&code_cid) {
+            return Ok(false);
+        }
+        installed.push(code_cid);
+        self.installed_actors = store
+            .put_cbor(&installed, multihash_codetable::Code::Blake2b256)
+            .or_fatal()?;
+        Ok(true)
+    }
+
     /// ResolveAddress resolves an address to an ID-address, if possible.
     /// If the provided address is an ID address, it is returned as-is.
     /// This means that mapped ID-addresses (which should only appear as values,
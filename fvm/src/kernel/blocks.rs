@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::collections::HashSet;
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
@@ -11,10 +12,52 @@ use crate::syscall_error;
 
 /// A registry of open blocks (per-kernel). Think "file descriptor" table. At the moment, there's no
 /// way to close/remove a block from this table.
-#[derive(Default)]
+///
+/// The registry enforces a quota on the number of blocks it holds and on their total size (see
+/// [`NetworkConfig::max_open_blocks`](crate::machine::NetworkConfig::max_open_blocks) and
+/// [`NetworkConfig::max_open_block_bytes`](crate::machine::NetworkConfig::max_open_block_bytes)).
+/// The quota is a [`BlockLimits`] shared by the registries of every invocation in a message.
 pub struct BlockRegistry {
     blocks: Vec<Block>,
     reachable: HashSet<Cid>,
+    limits: Rc<BlockLimits>,
+}
+
+/// The block quota of a message. Every block added to a registry sharing these limits counts
+/// towards them, until the end of the message.
+#[derive(Debug)]
+pub(crate) struct BlockLimits {
+    max_blocks: u32,
+    max_bytes: u64,
+    /// The number of blocks added so far.
+    blocks: Cell<u32>,
+    /// The total size of the blocks added so far.
+    bytes: Cell<u64>,
+}
+
+impl BlockLimits {
+    /// Allows at most `max_blocks` blocks (capped at an internal maximum), totalling at most
+    /// `max_bytes` bytes.
+    pub(crate) fn new(max_blocks: u32, max_bytes: u64) -> Self {
+        Self {
+            max_blocks: max_blocks.min(MAX_BLOCKS),
+            max_bytes,
+            blocks: Cell::new(0),
+            bytes: Cell::new(0),
+        }
+    }
+
+    fn remaining(&self) -> BlockQuota {
+        BlockQuota {
+            blocks_remaining: self.max_blocks.saturating_sub(self.blocks.get()),
+            bytes_remaining: self.max_bytes.saturating_sub(self.bytes.get()),
+        }
+    }
+
+    fn record(&self, size: u32) {
+        self.blocks.set(self.blocks.get().saturating_add(1));
+        self.bytes.set(self.bytes.get().saturating_add(size as u64));
+    }
 }
 
 /// Blocks in the block registry are addressed by an ordinal, starting from 1 (`FIRST_ID`).
@@ -31,6 +74,13 @@ pub struct BlockStat {
     pub size: u32,
 }
 
+/// The remaining capacity of a [`BlockRegistry`] (and of every other registry sharing its quota).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlockQuota {
+    pub blocks_remaining: u32,
+    pub bytes_remaining: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct Block(Rc<BlockInner>);
 #[derive(Debug)]
@@ -89,9 +139,27 @@ impl From<&Block> for IpldBlock {
     }
 }
 
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        Self::with_limits(MAX_BLOCKS, u64::MAX)
+    }
+}
+
 impl BlockRegistry {
-    pub(crate) fn new() -> Self {
-        Self::default()
+    /// Creates a registry that holds at most `max_blocks` blocks (capped at an internal maximum),
+    /// totalling at most `max_bytes` bytes.
+    pub(crate) fn with_limits(max_blocks: u32, max_bytes: u64) -> Self {
+        Self::with_shared_limits(Rc::new(BlockLimits::new(max_blocks, max_bytes)))
+    }
+
+    /// Creates a registry whose blocks count towards `limits`, along with the blocks of every
+    /// other registry sharing them.
+    pub(crate) fn with_shared_limits(limits: Rc<BlockLimits>) -> Self {
+        Self {
+            blocks: Vec::new(),
+            reachable: HashSet::new(),
+            limits,
+        }
     }
 }
 
@@ -126,7 +194,9 @@ impl BlockRegistry {
     /// Adds a new block to the registry, and returns a handle to refer to it.
     fn put_inner(&mut self, block: Block, check_reachable: bool) -> Result<BlockId> {
         if self.is_full() {
-            return Err(syscall_error!(LimitExceeded; "too many blocks").into());
+            return Err(
+                syscall_error!(LimitExceeded; "block quota exceeded: too many blocks").into(),
+            );
         }
 
         // We expect the caller to have already charged for gas.
@@ -141,6 +211,7 @@ impl BlockRegistry {
         }
 
         let id = FIRST_ID + self.blocks.len() as u32;
+        self.limits.record(block.size());
         self.blocks.push(block);
         Ok(id)
     }
@@ -172,6 +243,131 @@ impl BlockRegistry {
     }
 
     pub fn is_full(&self) -> bool {
-        self.blocks.len() as u32 == MAX_BLOCKS
+        self.quota().blocks_remaining == 0
+    }
+
+    /// Returns the number of blocks, and bytes of block data, that may still be added to the
+    /// registry (or to any other registry sharing its quota).
+    pub fn quota(&self) -> BlockQuota {
+        self.limits.remaining()
+    }
+
+    /// Checks that a block of `size` bytes fits within the registry's quota. Call this before
+    /// opening or creating a block on behalf of an actor.
+    ///
+    /// Blocks added by the system (parameters, return values, etc.) aren't checked against the
+    /// byte quota, but still count towards it.
+    pub fn check_quota(&self, size: usize) -> Result<()> {
+        let quota = self.quota();
+        if quota.blocks_remaining == 0 {
+            return Err(
+                syscall_error!(LimitExceeded; "block quota exceeded: too many blocks").into(),
+            );
+        }
+        if size as u64 > quota.bytes_remaining {
+            return Err(syscall_error!(
+                LimitExceeded;
+                "block quota exceeded: block of {size} bytes exceeds the remaining {} bytes",
+                quota.bytes_remaining
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::error::ErrorNumber;
+
+    use super::*;
+    use crate::kernel::ExecutionError;
+
+    fn assert_quota_exceeded(res: Result<impl std::fmt::Debug>) {
+        match res {
+            Err(ExecutionError::Syscall(e)) => assert_eq!(e.1, ErrorNumber::LimitExceeded),
+            other => panic!("expected a quota error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn quota() {
+        let mut br = BlockRegistry::with_limits(2, 100);
+        assert_eq!(
+            br.quota(),
+            BlockQuota {
+                blocks_remaining: 2,
+                bytes_remaining: 100
+            }
+        );
+
+        br.check_quota(60).unwrap();
+        br.put_reachable(Block::new(0x55, vec![0u8; 60], Vec::new()))
+            .unwrap();
+        assert_eq!(
+            br.quota(),
+            BlockQuota {
+                blocks_remaining: 1,
+                bytes_remaining: 40
+            }
+        );
+
+        // The block doesn't fit.
+        assert_quota_exceeded(br.check_quota(41));
+        br.check_quota(40).unwrap();
+
+        // System blocks aren't rejected for their size, but count towards the quota.
+        br.put_reachable(Block::new(0x55, vec![0u8; 60], Vec::new()))
+            .unwrap();
+        assert_eq!(
+            br.quota(),
+            BlockQuota {
+                blocks_remaining: 0,
+                bytes_remaining: 0
+            }
+        );
+        assert!(br.is_full());
+
+        // The registry is full.
+        assert_quota_exceeded(br.check_quota(0));
+        assert_quota_exceeded(br.put_reachable(Block::new(0x55, Vec::new(), Vec::new())));
+    }
+
+    #[test]
+    fn shared_quota() {
+        let limits = Rc::new(BlockLimits::new(3, 100));
+        let mut caller = BlockRegistry::with_shared_limits(limits.clone());
+        caller
+            .put_reachable(Block::new(0x55, vec![0u8; 60], Vec::new()))
+            .unwrap();
+
+        // A callee only gets what the caller left over...
+        let mut callee = BlockRegistry::with_shared_limits(limits.clone());
+        assert_eq!(
+            callee.quota(),
+            BlockQuota {
+                blocks_remaining: 2,
+                bytes_remaining: 40
+            }
+        );
+        assert_quota_exceeded(callee.check_quota(41));
+        callee
+            .put_reachable(Block::new(0x55, vec![0u8; 30], Vec::new()))
+            .unwrap();
+        drop(callee);
+
+        // ...and the blocks it opened still count once it returns.
+        assert_eq!(
+            caller.quota(),
+            BlockQuota {
+                blocks_remaining: 1,
+                bytes_remaining: 10
+            }
+        );
+        let mut next = BlockRegistry::with_shared_limits(limits);
+        next.put_reachable(Block::new(0x55, Vec::new(), Vec::new()))
+            .unwrap();
+        assert!(caller.is_full());
+        assert_quota_exceeded(caller.check_quota(0));
     }
 }
@@ -12,15 +12,17 @@ use fvm_shared::error::ErrorNumber;
 use fvm_shared::event::{ActorEvent, Entry, Flags};
 use fvm_shared::sys::out::vm::ContextFlags;
 use fvm_shared::upgrade::UpgradeInfo;
+use fvm_shared::version::NetworkVersion;
 use multihash_codetable::MultihashDigest;
 
-use super::blocks::{Block, BlockRegistry};
+use super::blocks::{Block, BlockQuota, BlockRegistry};
 use super::error::Result;
 use super::hash::SupportedHashes;
+use super::read_only::{MutatingOp, Writable};
 use super::*;
 use crate::call_manager::{
-    CallManager, Entrypoint, InvocationResult, INVOKE_FUNC_NAME, NO_DATA_BLOCK_ID,
-    UPGRADE_FUNC_NAME,
+    ActorLog, Artifact, CallManager, Entrypoint, InvocationResult, INVOKE_FUNC_NAME,
+    NO_DATA_BLOCK_ID, UPGRADE_FUNC_NAME,
 };
 use crate::externs::{Chain, Rand};
 use crate::gas::GasTimer;
@@ -32,6 +34,30 @@ use crate::{ipld, syscall_error};
 const BLAKE2B_256: u64 = 0xb220;
 const ENV_ARTIFACT_DIR: &str = "FVM_STORE_ARTIFACT_DIR";
 const MAX_ARTIFACT_NAME_LEN: usize = 256;
+const MAX_ARTIFACT_SIZE: usize = 16 << 20;
+
+/// The first network version at which actor code may be installed with
+/// [`ActorOps::install_code`].
+const MIN_CODE_INSTALL_VERSION: NetworkVersion = NetworkVersion::V26;
+
+/// The first network version at which blocks exceeding the block quota are rejected before they're
+/// scanned for links and charged for, rather than when they're added to the block registry.
+const MIN_EARLY_BLOCK_QUOTA_VERSION: NetworkVersion = NetworkVersion::V26;
+
+/// The first network version at which the [`SupportedHashes::Blake3_256`] and
+/// [`SupportedHashes::Sha3_512`] hash functions may be used with [`CryptoOps::hash`].
+const MIN_EXTENDED_HASHES_VERSION: NetworkVersion = NetworkVersion::V26;
+
+/// The first network version at which actors may send with [`SendFlags::NO_REENTRY`].
+const MIN_NO_REENTRY_VERSION: NetworkVersion = NetworkVersion::V26;
+
+/// The first network version at which [`NetworkOps::tipset_cid`] refuses to look back more than
+/// [`MAX_TIPSET_CID_LOOKBACK`] epochs.
+const MIN_TIPSET_CID_LOOKBACK_VERSION: NetworkVersion = NetworkVersion::V26;
+
+/// The maximum number of epochs [`NetworkOps::tipset_cid`] may look back (chain finality), from
+/// nv26.
+pub const MAX_TIPSET_CID_LOOKBACK: ChainEpoch = 900;
 
 #[cfg(feature = "testing")]
 const TEST_ACTOR_ALLOWED_TO_CALL_CREATE_ACTOR: ActorID = 98;
@@ -45,6 +71,8 @@ pub struct DefaultKernel<C> {
     pub method: MethodNum,
     pub value_received: TokenAmount,
     pub read_only: bool,
+    /// The index of this invocation within the message (see [`CallManager::invocation_count`]).
+    pub invocation: u64,
 
     /// The call manager for this call stack. If this kernel calls another actor, it will
     /// temporarily "give" the call manager to the other kernel before re-attaching it.
@@ -82,6 +110,8 @@ where
         read_only: bool,
     ) -> Self {
         DefaultKernel {
+            // The call manager counts this invocation before constructing its kernel.
+            invocation: mgr.invocation_count(),
             call_manager: mgr,
             blocks,
             caller,
@@ -117,6 +147,30 @@ where
     fn get_self(&self) -> Result<Option<ActorState>> {
         self.call_manager.get_actor(self.actor_id)
     }
+
+    /// Records a debug log emitted by this actor, attributed to this call.
+    fn record_log(
+        &mut self,
+        level: Option<LogLevel>,
+        message: String,
+        fields: Vec<(String, String)>,
+    ) {
+        let depth = self.call_manager.get_call_stack().len().saturating_sub(1) as u32;
+        self.call_manager.record_log(ActorLog {
+            actor: self.actor_id,
+            depth,
+            method: self.method,
+            level,
+            message,
+            fields,
+        });
+    }
+
+    /// Whether blocks are checked against the block quota before they're scanned and charged for
+    /// (see [`MIN_EARLY_BLOCK_QUOTA_VERSION`]).
+    fn early_block_quota_check(&self) -> bool {
+        self.call_manager.context().network.network_version >= MIN_EARLY_BLOCK_QUOTA_VERSION
+    }
 }
 
 impl<K> SendOps<K> for DefaultKernel<K::CallManager>
@@ -135,8 +189,13 @@ where
         let from = self.actor_id;
         let read_only = self.read_only || flags.read_only();
 
-        if read_only && !value.is_zero() {
-            return Err(syscall_error!(ReadOnly; "cannot transfer value when read-only").into());
+        if !value.is_zero() {
+            if flags.read_only() {
+                return Err(
+                    syscall_error!(ReadOnly; "cannot transfer value in a read-only send").into(),
+                );
+            }
+            self.writable(MutatingOp::TransferValue)?;
         }
 
         // Load parameters.
@@ -151,7 +210,19 @@ where
             return Err(syscall_error!(LimitExceeded; "cannot store return block").into());
         }
 
+        let no_reentry = flags.no_reentry();
+        if no_reentry
+            && self.call_manager.context().network.network_version < MIN_NO_REENTRY_VERSION
+        {
+            return Err(
+                syscall_error!(IllegalArgument; "invalid send flags: {}", flags.bits()).into(),
+            );
+        }
+
         // Send.
+        if no_reentry {
+            self.call_manager.acquire_reentry_guard(from);
+        }
         let result = self.call_manager.with_transaction(|cm| {
             cm.call_actor::<K>(
                 from,
@@ -162,7 +233,11 @@ where
                 gas_limit,
                 read_only,
             )
-        })?;
+        });
+        if no_reentry {
+            self.call_manager.release_reentry_guard(from);
+        }
+        let result = result?;
 
         // Store result and return.
         Ok(match result {
@@ -203,12 +278,19 @@ where
     K: Kernel,
 {
     fn upgrade_actor(&mut self, new_code_cid: Cid, params_id: BlockId) -> Result<CallResult> {
-        if self.read_only {
-            return Err(
-                syscall_error!(ReadOnly, "upgrade_actor cannot be called while read-only").into(),
-            );
-        }
+        self.writable(MutatingOp::UpgradeActor)?
+            .upgrade_actor::<K>(new_code_cid, params_id)
+    }
+}
 
+impl<C> Writable<'_, C>
+where
+    C: CallManager,
+{
+    fn upgrade_actor<K>(mut self, new_code_cid: Cid, params_id: BlockId) -> Result<CallResult>
+    where
+        K: Kernel<CallManager = C>,
+    {
         // check if this actor is already on the call stack
         //
         // We first find the first position of this actor on the call stack, and then make sure that
@@ -240,9 +322,10 @@ where
             return Err(syscall_error!(LimitExceeded; "cannot store return block").into());
         }
 
+        let (caller, actor_id) = (self.caller, self.actor_id);
         let result = self.call_manager.with_transaction(|cm| {
             let state = cm
-                .get_actor(self.actor_id)?
+                .get_actor(actor_id)?
                 .ok_or_else(|| syscall_error!(IllegalOperation; "actor deleted"))?;
 
             // store the code cid of the calling actor before running the upgrade entrypoint
@@ -252,7 +335,7 @@ where
 
             // update the code cid of the actor to new_code_cid
             cm.set_actor(
-                self.actor_id,
+                actor_id,
                 ActorState::new(
                     new_code_cid,
                     state.state,
@@ -264,8 +347,8 @@ where
 
             // run the upgrade entrypoint
             let result = cm.call_actor::<K>(
-                self.caller,
-                Address::new_id(self.actor_id),
+                caller,
+                Address::new_id(actor_id),
                 Entrypoint::Upgrade(UpgradeInfo { old_code_cid: code }),
                 params,
                 &TokenAmount::from_whole(0),
@@ -317,12 +400,29 @@ where
     }
 
     fn set_root(&mut self, new: Cid) -> Result<()> {
-        if self.read_only {
-            return Err(
-                syscall_error!(ReadOnly; "cannot update the state-root while read-only").into(),
-            );
-        }
+        self.writable(MutatingOp::SetRoot)?.set_root(new)
+    }
 
+    fn current_balance(&self) -> Result<TokenAmount> {
+        let t = self
+            .call_manager
+            .charge_gas(self.call_manager.price_list().on_self_balance())?;
+
+        // If the actor doesn't exist, it has zero balance.
+        t.record(Ok(self.get_self()?.map(|a| a.balance).unwrap_or_default()))
+    }
+
+    fn self_destruct(&mut self, burn_unspent: bool) -> Result<()> {
+        self.writable(MutatingOp::SelfDestruct)?
+            .self_destruct(burn_unspent)
+    }
+}
+
+impl<C> Writable<'_, C>
+where
+    C: CallManager,
+{
+    fn set_root(mut self, new: Cid) -> Result<()> {
         let _ = self
             .call_manager
             .charge_gas(self.call_manager.price_list().on_set_root())?;
@@ -336,24 +436,12 @@ where
             .get_actor(self.actor_id)?
             .ok_or_else(|| syscall_error!(IllegalOperation; "actor deleted"))?;
         state.state = new;
-        self.call_manager.set_actor(self.actor_id, state)?;
+        let actor_id = self.actor_id;
+        self.call_manager.set_actor(actor_id, state)?;
         Ok(())
     }
 
-    fn current_balance(&self) -> Result<TokenAmount> {
-        let t = self
-            .call_manager
-            .charge_gas(self.call_manager.price_list().on_self_balance())?;
-
-        // If the actor doesn't exist, it has zero balance.
-        t.record(Ok(self.get_self()?.map(|a| a.balance).unwrap_or_default()))
-    }
-
-    fn self_destruct(&mut self, burn_unspent: bool) -> Result<()> {
-        if self.read_only {
-            return Err(syscall_error!(ReadOnly; "cannot self-destruct when read-only").into());
-        }
-
+    fn self_destruct(mut self, burn_unspent: bool) -> Result<()> {
         // Idempotent: If the actor doesn't exist, this won't actually do anything. The current
         // balance will be zero, and `delete_actor_id` will be a no-op.
         let t = self
@@ -367,6 +455,7 @@ where
         //    fail is for the caller to run out of gas.
         // 2. If we ever decide to allow code on method 0, allowing transfers here would be
         //    unfortunate.
+        let actor_id = self.actor_id;
         let balance = self.current_balance()?;
         if !balance.is_zero() {
             if !burn_unspent {
@@ -375,12 +464,12 @@ where
                 );
             }
             self.call_manager
-                .transfer(self.actor_id, BURNT_FUNDS_ACTOR_ID, &balance)
+                .transfer(actor_id, BURNT_FUNDS_ACTOR_ID, &balance)
                 .or_fatal()?;
         }
 
         // Delete the executing actor.
-        t.record(self.call_manager.delete_actor(self.actor_id))
+        t.record(self.call_manager.delete_actor(actor_id))
     }
 }
 
@@ -409,6 +498,11 @@ where
 
         t.stop();
 
+        let early_quota_check = self.early_block_quota_check();
+        if early_quota_check {
+            self.blocks.check_quota(data.len())?;
+        }
+
         // This can fail because we can run out of gas.
         let children = ipld::scan_for_reachable_links(
             cid.codec(),
@@ -423,6 +517,10 @@ where
                 .on_block_open(data.len(), children.len()),
         )?;
 
+        if !early_quota_check {
+            self.blocks.check_quota(data.len())?;
+        }
+
         let block = Block::new(cid.codec(), data, children);
         let stat = block.stat();
         let id = self.blocks.put_reachable(block)?;
@@ -439,6 +537,11 @@ where
             return Err(syscall_error!(IllegalCodec; "codec {} not allowed", codec).into());
         }
 
+        let early_quota_check = self.early_block_quota_check();
+        if early_quota_check {
+            self.blocks.check_quota(data.len())?;
+        }
+
         let children = ipld::scan_for_reachable_links(
             codec,
             data,
@@ -452,6 +555,10 @@ where
                 .on_block_create(data.len(), children.len()),
         )?;
 
+        if !early_quota_check {
+            self.blocks.check_quota(data.len())?;
+        }
+
         let blk = Block::new(codec, data, children);
 
         t.record(Ok(self.blocks.put_check_reachable(blk)?))
@@ -484,6 +591,7 @@ where
             // probably abort the entire block.
             .or_fatal()?;
         self.blocks.mark_reachable(&k);
+        self.call_manager.record_block_write();
 
         t.stop_with(start);
         Ok(k)
@@ -531,6 +639,15 @@ where
 
         t.record(Ok(self.blocks.stat(id)?))
     }
+
+    fn block_quota(&self) -> Result<BlockQuota> {
+        // Like stat, this is a constant-time lookup in the block registry.
+        let t = self
+            .call_manager
+            .charge_gas(self.call_manager.price_list().on_block_stat())?;
+
+        t.record(Ok(self.blocks.quota()))
+    }
 }
 
 impl<C> MessageOps for DefaultKernel<C>
@@ -618,9 +735,13 @@ where
 
         // Verify signature, catching errors. Signature verification can include some complicated
         // math.
-        t.record(catch_and_log_panic("verifying signature", || {
-            Ok(signature::verify(sig_type, signature, plaintext, &signing_addr).is_ok())
-        }))
+        let backend = &self.call_manager.context().signature_backend;
+        t.record(catch_and_log_panic(
+            "verifying signature",
+            panic::AssertUnwindSafe(|| {
+                Ok(backend.verify_signature(sig_type, signature, plaintext, &signing_addr))
+            }),
+        ))
     }
 
     fn verify_bls_aggregate(
@@ -667,10 +788,11 @@ where
             );
         }
 
-        t.record(
-            signature::ops::verify_bls_aggregate(aggregate_sig, pub_keys, &plaintexts)
-                .or(Ok(false)),
-        )
+        t.record(Ok(self
+            .call_manager
+            .context()
+            .signature_backend
+            .verify_bls_aggregate(aggregate_sig, pub_keys, &plaintexts)))
     }
 
     fn recover_secp_public_key(
@@ -683,8 +805,10 @@ where
             .charge_gas(self.call_manager.price_list().on_recover_secp_public_key())?;
 
         t.record(
-            signature::ops::recover_secp_public_key(hash, signature)
-                .map(|pubkey| pubkey.serialize())
+            self.call_manager
+                .context()
+                .signature_backend
+                .recover_secp_public_key(hash, signature)
                 .map_err(|e| {
                     syscall_error!(IllegalArgument; "public key recovery failed: {}", e).into()
                 }),
@@ -694,6 +818,13 @@ where
     fn hash(&self, code: u64, data: &[u8]) -> Result<Multihash> {
         let hasher = SupportedHashes::try_from(code)
             .map_err(|err| syscall_error!(IllegalArgument; "unsupported hash code {}", err.0))?;
+        if matches!(
+            hasher,
+            SupportedHashes::Blake3_256 | SupportedHashes::Sha3_512
+        ) && self.call_manager.context().network.network_version < MIN_EXTENDED_HASHES_VERSION
+        {
+            return Err(syscall_error!(IllegalArgument; "unsupported hash code {}", code).into());
+        }
 
         let t = self.call_manager.charge_gas(
             self.call_manager
@@ -756,6 +887,18 @@ where
             Equal => return Err(syscall_error!(IllegalArgument; "cannot lookup the tipset cid for the current epoch").into()),
             Greater => {}
         }
+        if offset > MAX_TIPSET_CID_LOOKBACK
+            && self.call_manager.context().network.network_version
+                >= MIN_TIPSET_CID_LOOKBACK_VERSION
+        {
+            return Err(syscall_error!(
+                LimitExceeded;
+                "epoch {} is more than {} epochs ago",
+                epoch,
+                MAX_TIPSET_CID_LOOKBACK
+            )
+            .into());
+        }
 
         self.call_manager
             .charge_gas(self.call_manager.price_list().on_tipset_cid(offset))?;
@@ -816,6 +959,68 @@ where
     }
 }
 
+impl<C> Writable<'_, C>
+where
+    C: CallManager,
+{
+    #[cfg(not(feature = "m2-native"))]
+    fn install_code(self, _code: &[u8]) -> Result<Cid> {
+        // Installed code is recorded in the init actor's state, which only tracks it with M2
+        // native.
+        Err(syscall_error!(IllegalOperation; "installing actor code requires M2 native").into())
+    }
+
+    #[cfg(feature = "m2-native")]
+    fn install_code(mut self, code: &[u8]) -> Result<Cid> {
+        use fvm_ipld_encoding::CborStore;
+
+        // Charge for storing and compiling the code up-front.
+        let t = self
+            .call_manager
+            .charge_gas(self.call_manager.price_list().on_install_code(code.len()))?;
+
+        // Only check that the code compiles: the engine compiles it again, from the blockstore,
+        // when it's first instantiated.
+        self.call_manager
+            .engine()
+            .check_bytecode(code)
+            .context("failed to install actor code")
+            .or_illegal_argument()?;
+        let code_cid = Cid::new_v1(IPLD_RAW, multihash_codetable::Code::Blake2b256.digest(code));
+        self.call_manager
+            .blockstore()
+            .put_keyed(&code_cid, code)
+            .or_fatal()?;
+
+        // Record the code in the init actor's state. This is what makes it available to new
+        // actors, and what keeps the code block reachable from the state root. Like any other
+        // state change, it's dropped if the message (or the calling frame) reverts.
+        let mut init_actor = self
+            .call_manager
+            .get_actor(INIT_ACTOR_ID)?
+            .context("init actor not found")
+            .or_fatal()?;
+        let mut state: crate::init_actor::State = self
+            .call_manager
+            .blockstore()
+            .get_cbor(&init_actor.state)
+            .or_fatal()?
+            .context("init actor state not found")
+            .or_fatal()?;
+        if state.record_installed_code(self.call_manager.blockstore(), code_cid)? {
+            init_actor.state = self
+                .call_manager
+                .blockstore()
+                .put_cbor(&state, multihash_codetable::Code::Blake2b256)
+                .or_fatal()?;
+            self.call_manager.set_actor(INIT_ACTOR_ID, init_actor)?;
+        }
+
+        t.stop();
+        Ok(code_cid)
+    }
+}
+
 impl<C> ActorOps for DefaultKernel<C>
 where
     C: CallManager,
@@ -868,13 +1073,8 @@ where
             .into());
         }
 
-        if self.read_only {
-            return Err(
-                syscall_error!(ReadOnly, "create_actor cannot be called while read-only").into(),
-            );
-        }
-
-        self.call_manager
+        self.writable(MutatingOp::CreateActor)?
+            .call_manager
             .create_actor(code_id, actor_id, delegated_address)
     }
 
@@ -895,6 +1095,35 @@ where
         Ok(())
     }
 
+    fn install_code(&mut self, code: &[u8]) -> Result<Cid> {
+        let network_version = self.call_manager.context().network.network_version;
+        if network_version < MIN_CODE_INSTALL_VERSION {
+            return Err(syscall_error!(
+                IllegalOperation,
+                "installing actor code is not supported at network version {}",
+                network_version
+            )
+            .into());
+        }
+
+        let is_allowed_to_install_code = self.actor_id == INIT_ACTOR_ID;
+
+        #[cfg(feature = "testing")]
+        let is_allowed_to_install_code =
+            is_allowed_to_install_code || self.actor_id == TEST_ACTOR_ALLOWED_TO_CALL_CREATE_ACTOR;
+
+        if !is_allowed_to_install_code {
+            return Err(syscall_error!(
+                Forbidden,
+                "install_code is restricted to InitActor. Called by {}",
+                self.actor_id
+            )
+            .into());
+        }
+
+        self.writable(MutatingOp::InstallCode)?.install_code(code)
+    }
+
     fn get_builtin_actor_type(&self, code_cid: &Cid) -> Result<u32> {
         let t = self
             .call_manager
@@ -952,14 +1181,21 @@ where
     C: CallManager,
 {
     fn log(&mut self, msg: String) {
+        self.record_log(None, msg.clone(), Vec::new());
         self.call_manager.log(msg)
     }
 
+    fn log_structured(&mut self, level: LogLevel, msg: String, fields: Vec<(String, String)>) {
+        self.record_log(Some(level), msg.clone(), fields.clone());
+        self.call_manager
+            .log_structured(self.actor_id, level, msg, fields)
+    }
+
     fn debug_enabled(&self) -> bool {
         self.call_manager.context().actor_debugging
     }
 
-    fn store_artifact(&self, name: &str, data: &[u8]) -> Result<()> {
+    fn store_artifact(&mut self, name: &str, data: &[u8]) -> Result<()> {
         // Ensure well formed artifact name
         {
             if name.len() > MAX_ARTIFACT_NAME_LEN {
@@ -980,7 +1216,24 @@ where
         }
         .or_error(fvm_shared::error::ErrorNumber::IllegalArgument)?;
 
-        // Write to disk
+        if data.len() > MAX_ARTIFACT_SIZE {
+            log::error!(
+                "dropping debug artifact {}: exceeds the maximum size of {} bytes",
+                name,
+                MAX_ARTIFACT_SIZE
+            );
+            return Ok(());
+        }
+
+        // Keep the artifact around so it can be retrieved after execution.
+        self.call_manager.store_artifact(Artifact {
+            actor: self.actor_id,
+            invocation: self.invocation,
+            name: name.to_owned(),
+            data: data.to_vec(),
+        });
+
+        // And write it to disk, if requested.
         if let Ok(dir) = std::env::var(ENV_ARTIFACT_DIR).as_deref() {
             let dir: PathBuf = [
                 dir,
@@ -988,7 +1241,7 @@ where
                 &self.call_manager.origin().to_string(),
                 &self.call_manager.nonce().to_string(),
                 &self.actor_id.to_string(),
-                &self.call_manager.invocation_count().to_string(),
+                &self.invocation.to_string(),
             ]
             .iter()
             .collect();
@@ -1000,11 +1253,6 @@ where
             } else {
                 log::info!("wrote artifact: {} to {:?}", name, dir);
             }
-        } else {
-            log::error!(
-                "store_artifact was ignored, env var {} was not set",
-                ENV_ARTIFACT_DIR
-            )
         }
         Ok(())
     }
@@ -1020,14 +1268,21 @@ where
         event_keys: &[u8],
         event_values: &[u8],
     ) -> Result<()> {
-        const MAX_NR_ENTRIES: usize = 255;
-        const MAX_KEY_LEN: usize = 31;
-        const MAX_TOTAL_VALUES_LEN: usize = 8 << 10;
-
-        if self.read_only {
-            return Err(syscall_error!(ReadOnly; "cannot emit events while read-only").into());
-        }
+        self.writable(MutatingOp::EmitEvent)?
+            .emit_event(event_headers, event_keys, event_values)
+    }
+}
 
+impl<C> Writable<'_, C>
+where
+    C: CallManager,
+{
+    fn emit_event(
+        mut self,
+        event_headers: &[fvm_shared::sys::EventEntry],
+        event_keys: &[u8],
+        event_values: &[u8],
+    ) -> Result<()> {
         let t = self
             .call_manager
             .charge_gas(self.call_manager.price_list().on_actor_event(
@@ -1036,12 +1291,23 @@ where
                 event_values.len(),
             ))?;
 
-        if event_headers.len() > MAX_NR_ENTRIES {
-            return Err(syscall_error!(LimitExceeded; "event exceeded max entries: {} > {MAX_NR_ENTRIES}", event_headers.len()).into());
+        let limits = self.call_manager.context().network.event_limits;
+        if event_headers.len() > limits.max_entries {
+            return Err(syscall_error!(LimitExceeded;
+                "event has {} entries, exceeding the max_entries limit of {}",
+                event_headers.len(),
+                limits.max_entries
+            )
+            .into());
         }
 
-        if event_values.len() > MAX_TOTAL_VALUES_LEN {
-            return Err(syscall_error!(LimitExceeded; "total event value lengths exceeded the max size: {} > {MAX_TOTAL_VALUES_LEN}", event_values.len()).into());
+        if event_values.len() > limits.max_total_values_len {
+            return Err(syscall_error!(LimitExceeded;
+                "event values total {} bytes, exceeding the max_total_values_len limit of {}",
+                event_values.len(),
+                limits.max_total_values_len
+            )
+            .into());
         }
 
         // We validate utf8 all at once for better performance.
@@ -1063,14 +1329,19 @@ where
                 );
             }
 
-            if header.key_len > MAX_KEY_LEN as u32 {
-                let tmp = header.key_len;
-                return Err(syscall_error!(LimitExceeded; "event key exceeded max size: {} > {MAX_KEY_LEN}", tmp).into());
+            let key_len = header.key_len;
+            if key_len as usize > limits.max_key_len {
+                return Err(syscall_error!(LimitExceeded;
+                    "event key is {} bytes, exceeding the max_key_len limit of {}",
+                    key_len,
+                    limits.max_key_len
+                )
+                .into());
             }
 
             // We check this here purely to detect/prevent integer overflows below. That's why we
             // return IllegalArgument, not LimitExceeded.
-            if header.val_len > MAX_TOTAL_VALUES_LEN as u32 {
+            if header.val_len as usize > limits.max_total_values_len {
                 return Err(
                     syscall_error!(IllegalArgument; "event entry value out of range").into(),
                 );
@@ -4,17 +4,22 @@ use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::panic::{self, UnwindSafe};
 
+use ambassador::delegatable_trait;
+use anyhow::Context as _;
 use filecoin_proofs_api::{self as proofs, ProverId, PublicReplicaInfo, SectorId};
 
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::bytes_32;
+use fvm_ipld_encoding::de::DeserializeOwned;
 use fvm_shared::commcid;
 use fvm_shared::consensus::ConsensusFault;
-use fvm_shared::piece::{zero_piece_commitment, PaddedPieceSize, PieceInfo};
+use fvm_shared::piece::{required_padding, zero_piece_commitment, PaddedPieceSize, PieceInfo};
 use fvm_shared::randomness::Randomness;
 use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredPoStProof, RegisteredSealProof, ReplicaUpdateInfo,
-    SealVerifyInfo, SectorInfo, WindowPoStVerifyInfo,
+    SealVerifyInfo, SectorInfo, StoragePower, WindowPoStVerifyInfo,
 };
+use fvm_shared::well_known::WellKnownActor;
 use lazy_static::lazy_static;
 use rayon::iter::{
     IndexedParallelIterator, IntoParallelRefIterator, ParallelDrainRange, ParallelIterator,
@@ -25,6 +30,8 @@ use super::{ClassifyResult, Context};
 use crate::call_manager::CallManager;
 use crate::externs::Consensus;
 use crate::machine::Machine;
+use crate::power_actor;
+use crate::reward_actor;
 use crate::{syscall_error, DefaultKernel, Kernel};
 
 use super::prelude::*;
@@ -35,16 +42,22 @@ lazy_static! {
         .unwrap_or(8);
 }
 
+/// Filecoin-specific kernel operations (proofs, consensus faults, circulating supply, network
+/// aggregates).
+///
+/// Custom kernels wrapping [`DefaultFilecoinKernel`] can delegate this trait with
+/// `#[delegate(FilecoinKernel, where = "C: CallManager")]`.
+#[delegatable_trait]
 pub trait FilecoinKernel: Kernel {
     /// Computes an unsealed sector CID (CommD) from its constituent piece CIDs (CommPs) and sizes.
     fn compute_unsealed_sector_cid(
         &self,
-        proof_type: RegisteredSealProof,
-        pieces: &[PieceInfo],
+        proof_type: fvm_shared::sector::RegisteredSealProof,
+        pieces: &[fvm_shared::piece::PieceInfo],
     ) -> Result<cid::Cid>;
 
     /// Verifies a window proof of spacetime.
-    fn verify_post(&self, verify_info: &WindowPoStVerifyInfo) -> Result<bool>;
+    fn verify_post(&self, verify_info: &fvm_shared::sector::WindowPoStVerifyInfo) -> Result<bool>;
 
     /// Verifies that two block headers provide proof of a consensus fault:
     /// - both headers mined by the same actor
@@ -61,21 +74,43 @@ pub trait FilecoinKernel: Kernel {
         h1: &[u8],
         h2: &[u8],
         extra: &[u8],
-    ) -> Result<Option<ConsensusFault>>;
+    ) -> Result<Option<fvm_shared::consensus::ConsensusFault>>;
 
     /// Verifies a batch of seals. This is a privledged syscall, may _only_ be called by the
     /// power actor during cron.
     ///
     /// Gas: This syscall intentionally _does not_ charge any gas (as said gas would be charged to
     /// cron). Instead, gas is pre-paid by the storage provider on pre-commit.
-    fn batch_verify_seals(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>>;
+    fn batch_verify_seals(&self, vis: &[fvm_shared::sector::SealVerifyInfo]) -> Result<Vec<bool>>;
+
+    /// Verifies a batch of seals, reporting the result of each verification individually (a
+    /// malformed or invalid proof fails only its own verification). Unlike
+    /// [`FilecoinKernel::batch_verify_seals`], this may be called by any actor.
+    ///
+    /// Gas: charged up-front for the whole batch.
+    fn verify_seal_batch(&self, vis: &[fvm_shared::sector::SealVerifyInfo]) -> Result<Vec<bool>>;
+
+    /// Verifies a batch of window proofs of spacetime, reporting the result of each verification
+    /// individually (a malformed or invalid proof fails only its own verification).
+    ///
+    /// Gas: charged up-front for the whole batch.
+    fn verify_post_batch(
+        &self,
+        infos: &[fvm_shared::sector::WindowPoStVerifyInfo],
+    ) -> Result<Vec<bool>>;
 
     /// Verify aggregate seals verifies an aggregated batch of prove-commits.
-    fn verify_aggregate_seals(&self, aggregate: &AggregateSealVerifyProofAndInfos) -> Result<bool>;
+    fn verify_aggregate_seals(
+        &self,
+        aggregate: &fvm_shared::sector::AggregateSealVerifyProofAndInfos,
+    ) -> Result<bool>;
 
     /// Verify replica update verifies a snap deal: an upgrade from a CC sector to a sector with
     /// deals.
-    fn verify_replica_update(&self, replica: &ReplicaUpdateInfo) -> Result<bool>;
+    fn verify_replica_update(
+        &self,
+        replica: &fvm_shared::sector::ReplicaUpdateInfo,
+    ) -> Result<bool>;
 
     /// Returns the total token supply in circulation at the beginning of the current epoch.
     /// The circulating supply is the sum of:
@@ -86,6 +121,28 @@ pub trait FilecoinKernel: Kernel {
     /// - pledge collateral locked in storage miner actors (recorded in the storage power actor)
     /// - deal collateral locked by the storage market actor
     fn total_fil_circ_supply(&self) -> Result<TokenAmount>;
+
+    /// Returns the network's total power and total pledge collateral, as recorded in the storage
+    /// power actor's state, and the current epoch's block reward, as recorded in the reward
+    /// actor's state. These are read from the current state tree.
+    ///
+    /// This is charged like looking up both actors and opening their state blocks. Fails with
+    /// `IllegalOperation` if either actor doesn't exist, isn't running the builtin actor's code, or
+    /// has a state this FVM can't decode.
+    fn network_summary(&self) -> Result<NetworkSummary>;
+}
+
+/// Network-wide aggregates returned by [`FilecoinKernel::network_summary`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NetworkSummary {
+    /// The network's total raw byte power.
+    pub raw_byte_power: StoragePower,
+    /// The network's total quality-adjusted power.
+    pub quality_adj_power: StoragePower,
+    /// The total pledge collateral locked by storage providers.
+    pub total_pledge_collateral: TokenAmount,
+    /// The block reward paid out at the current epoch.
+    pub this_epoch_reward: TokenAmount,
 }
 
 #[derive(Delegate)]
@@ -176,38 +233,12 @@ where
             items.push((vi, t));
         }
         log::debug!("batch verify seals start");
-        let out = items.par_drain(..)
+        let out = items
+            .par_drain(..)
             .with_min_len(vis.len() / *AVAILABLE_PARALLELISM)
             .map(|(seal, timer)| {
                 let start = GasTimer::start();
-                let verify_seal_result = std::panic::catch_unwind(|| verify_seal(seal));
-                let ok = match verify_seal_result {
-                    Ok(res) => {
-                        match res {
-                            Ok(correct) => {
-                                if !correct {
-                                    log::debug!(
-                                        "seal verify in batch failed (miner: {}) (err: Invalid Seal proof)",
-                                        seal.sector_id.miner
-                                    );
-                                }
-                                correct // all ok
-                            }
-                            Err(err) => {
-                                log::debug!(
-                                    "seal verify in batch failed (miner: {}) (err: {})",
-                                    seal.sector_id.miner,
-                                    err
-                                );
-                                false
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("seal verify internal fail (miner: {}) (err: {:?})", seal.sector_id.miner, e);
-                        false
-                    }
-                };
+                let ok = verify_seal_in_batch(seal);
                 timer.stop_with(start);
                 ok
             })
@@ -216,6 +247,32 @@ where
         Ok(out)
     }
 
+    fn verify_seal_batch(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
+        let t = self
+            .0
+            .call_manager
+            .charge_gas(self.0.call_manager.price_list().on_verify_seal_batch(vis))?;
+        let out = vis
+            .par_iter()
+            .with_min_len(vis.len() / *AVAILABLE_PARALLELISM)
+            .map(verify_seal_in_batch)
+            .collect();
+        t.record(Ok(out))
+    }
+
+    fn verify_post_batch(&self, infos: &[WindowPoStVerifyInfo]) -> Result<Vec<bool>> {
+        let t = self
+            .0
+            .call_manager
+            .charge_gas(self.0.call_manager.price_list().on_verify_post_batch(infos))?;
+        let out = infos
+            .par_iter()
+            .with_min_len(infos.len() / *AVAILABLE_PARALLELISM)
+            .map(verify_post_in_batch)
+            .collect();
+        t.record(Ok(out))
+    }
+
     fn verify_aggregate_seals(&self, aggregate: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
         let t = self.0.call_manager.charge_gas(
             self.0
@@ -243,6 +300,63 @@ where
     fn total_fil_circ_supply(&self) -> Result<TokenAmount> {
         Ok(self.0.machine().context().circ_supply.clone())
     }
+
+    fn network_summary(&self) -> Result<NetworkSummary> {
+        let power: power_actor::State = self.read_actor_state(WellKnownActor::StoragePower)?;
+        let reward: reward_actor::State = self.read_actor_state(WellKnownActor::Reward)?;
+        Ok(NetworkSummary {
+            raw_byte_power: power.total_raw_byte_power,
+            quality_adj_power: power.total_quality_adj_power,
+            total_pledge_collateral: power.total_pledge_collateral,
+            this_epoch_reward: reward.this_epoch_reward,
+        })
+    }
+}
+
+impl<C> DefaultFilecoinKernel<C>
+where
+    C: CallManager,
+{
+    /// Reads and decodes a builtin actor's state, charging for the actor lookup and the state
+    /// block's read as if the calling actor had opened the block itself. Fails with
+    /// `IllegalOperation` if the actor doesn't exist, isn't running the builtin actor's code, or
+    /// its state can't be decoded (e.g., because a later actors version changed its layout).
+    fn read_actor_state<T: DeserializeOwned>(&self, actor: WellKnownActor) -> Result<T> {
+        let id = actor.id();
+        let name = actor.name();
+        let cm = &self.0.call_manager;
+        let actor_state = cm
+            .get_actor(id)?
+            .ok_or_else(|| syscall_error!(IllegalOperation; "builtin actor {id} does not exist"))?;
+        let builtin_code = cm
+            .machine()
+            .builtin_actors()
+            .code_by_name(actor.code_name());
+        if builtin_code != Some(&actor_state.code) {
+            return Err(syscall_error!(
+                IllegalOperation;
+                "actor {id} is not the builtin {name} actor"
+            )
+            .into());
+        }
+
+        let t = cm.charge_gas(cm.price_list().on_block_open_base())?;
+        let data = cm
+            .blockstore()
+            .get(&actor_state.state)
+            .with_context(|| format!("failed to read the state of actor {id}"))
+            .or_fatal()?
+            .ok_or_else(|| syscall_error!(IllegalOperation; "missing state for actor {id}"))?;
+        t.stop();
+
+        // The state's links aren't made reachable, so only charge for reading the block.
+        let t = cm.charge_gas(cm.price_list().on_block_open(data.len(), 0))?;
+        let state = fvm_ipld_encoding::from_slice(&data).map_err(
+            |e| syscall_error!(IllegalOperation; "failed to decode the {name} actor's state: {e}"),
+        )?;
+        t.stop();
+        Ok(state)
+    }
 }
 
 impl<C> Kernel for DefaultFilecoinKernel<C>
@@ -355,7 +469,8 @@ fn to_fil_public_replica_infos(
         .iter()
         .map::<core::result::Result<(SectorId, PublicReplicaInfo), String>, _>(
             |sector_info: &SectorInfo| {
-                let commr = commcid::cid_to_replica_commitment_v1(&sector_info.sealed_cid)?;
+                let commr = commcid::cid_to_replica_commitment_v1(&sector_info.sealed_cid)
+                    .map_err(|e| e.to_string())?;
                 if !check_valid_proof_type(typ, sector_info.proof) {
                     return Err("invalid proof type".to_string());
                 }
@@ -369,11 +484,9 @@ fn to_fil_public_replica_infos(
 }
 
 fn check_valid_proof_type(post_type: RegisteredPoStProof, seal_type: RegisteredSealProof) -> bool {
-    if let Ok(proof_type_v1p1) = seal_type.registered_window_post_proof() {
-        proof_type_v1p1 == post_type
-    } else {
-        false
-    }
+    seal_type
+        .info()
+        .is_some_and(|info| info.window_post_proof() == post_type)
 }
 
 fn prover_id_from_u64(id: u64) -> ProverId {
@@ -383,26 +496,49 @@ fn prover_id_from_u64(id: u64) -> ProverId {
     prover_id
 }
 
-fn get_required_padding(
-    old_length: PaddedPieceSize,
-    new_piece_length: PaddedPieceSize,
-) -> (Vec<PaddedPieceSize>, PaddedPieceSize) {
-    let mut sum = 0;
-
-    let mut to_fill = 0u64.wrapping_sub(old_length.0) % new_piece_length.0;
-    let n = to_fill.count_ones();
-    let mut pad_pieces = Vec::with_capacity(n as usize);
-    for _ in 0..n {
-        let next = to_fill.trailing_zeros();
-        let p_size = 1 << next;
-        to_fill ^= p_size;
-
-        let padded = PaddedPieceSize(p_size);
-        pad_pieces.push(padded);
-        sum += padded.0;
+/// Verifies a seal as part of a batch, logging (rather than returning) any failure.
+fn verify_seal_in_batch(seal: &SealVerifyInfo) -> bool {
+    match panic::catch_unwind(|| verify_seal(seal)) {
+        Ok(Ok(true)) => true,
+        Ok(Ok(false)) => {
+            log::debug!(
+                "seal verify in batch failed (miner: {}) (err: Invalid Seal proof)",
+                seal.sector_id.miner
+            );
+            false
+        }
+        Ok(Err(err)) => {
+            log::debug!(
+                "seal verify in batch failed (miner: {}) (err: {})",
+                seal.sector_id.miner,
+                err
+            );
+            false
+        }
+        Err(e) => {
+            log::error!(
+                "seal verify internal fail (miner: {}) (err: {:?})",
+                seal.sector_id.miner,
+                e
+            );
+            false
+        }
     }
+}
 
-    (pad_pieces, PaddedPieceSize(sum))
+/// Verifies a PoSt as part of a batch, logging (rather than returning) any failure.
+fn verify_post_in_batch(info: &WindowPoStVerifyInfo) -> bool {
+    match catch_and_log_panic("verifying post", || verify_post(info)) {
+        Ok(valid) => valid,
+        Err(err) => {
+            log::debug!(
+                "post verify in batch failed (miner: {}) (err: {})",
+                info.prover,
+                err
+            );
+            false
+        }
+    }
 }
 
 fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
@@ -460,7 +596,7 @@ fn verify_aggregate_seals(aggregate: &AggregateSealVerifyProofAndInfos) -> Resul
                 sector_id: SectorId::from(info.sector_number),
             })
         })
-        .collect::<core::result::Result<Vec<_>, &'static str>>()
+        .collect::<core::result::Result<Vec<_>, commcid::Error>>()
         .or_illegal_argument()?;
 
     let inp: Vec<Vec<_>> = inputs
@@ -547,14 +683,14 @@ fn compute_unsealed_sector_cid(
             }
         };
         for p in pieces {
-            let (ps, _) = get_required_padding(sum, p.size);
+            let (ps, _) = required_padding(sum, p.size);
             pad_to(ps, &mut all_pieces, &mut sum);
 
             all_pieces.push(proofs::PieceInfo::try_from(p).or_illegal_argument()?);
             sum.0 += p.size.0;
         }
 
-        let (ps, _) = get_required_padding(sum, pssize);
+        let (ps, _) = required_padding(sum, pssize);
         pad_to(ps, &mut all_pieces, &mut sum);
     }
 
@@ -1,7 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use multihash_codetable::{
-    Blake2b256, Blake2b512, Keccak256, MultihashDigest, Ripemd160, Sha2_256,
+    Blake2b256, Blake2b512, Blake3_256, Keccak256, MultihashDigest, Ripemd160, Sha2_256, Sha3_512,
 };
 
 #[derive(Clone, Copy, Debug, Eq, MultihashDigest, PartialEq, Hash)]
@@ -20,4 +20,8 @@ pub enum SupportedHashes {
     Keccak256,
     #[mh(code = 0x1053, hasher = Ripemd160)]
     Ripemd160,
+    #[mh(code = 0x1e, hasher = Blake3_256)]
+    Blake3_256,
+    #[mh(code = 0x14, hasher = Sha3_512)]
+    Sha3_512,
 }
@@ -14,8 +14,10 @@ mod hash;
 
 pub mod default;
 pub mod filecoin;
+pub mod read_only;
 
-pub use blocks::{Block, BlockId, BlockRegistry, BlockStat};
+pub(crate) use blocks::BlockLimits;
+pub use blocks::{Block, BlockId, BlockQuota, BlockRegistry, BlockStat};
 pub use error::{ClassifyResult, Context, ExecutionError, Result, SyscallError};
 pub use hash::SupportedHashes;
 
@@ -32,6 +34,18 @@ pub struct CallResult {
 ///
 /// Actors may call into the kernel via the syscalls defined in the [`syscalls`][crate::syscalls]
 /// module.
+///
+/// # Custom kernels
+///
+/// To add syscalls, wrap an existing kernel (usually
+/// [`DefaultFilecoinKernel`][filecoin::DefaultFilecoinKernel]) in a newtype, delegate the `*Ops`
+/// traits to it with `#[derive(Delegate)]` (see the [`prelude`]), forward the handful of
+/// [`Kernel`] methods, and implement [`SyscallHandler`] by first linking the wrapped kernel's
+/// syscalls and then your own with [`Linker::link_syscall`]. See `examples/custom-kernel` in the
+/// repository for a complete example.
+///
+/// The `*Ops` traits are object safe; [`Kernel`] itself is not, as it must be constructible by the
+/// call manager.
 pub trait Kernel: SyscallHandler<Self> + 'static {
     /// The [`Kernel`]'s [`CallManager`] is
     type CallManager: CallManager;
@@ -77,7 +91,10 @@ pub trait Kernel: SyscallHandler<Self> + 'static {
     fn charge_gas(&self, name: &str, compute: Gas) -> Result<GasTimer>;
 }
 
+/// Links a kernel's syscalls into a [`Linker`]. Kernels extending another kernel should link the
+/// inner kernel's syscalls first, then register their own.
 pub trait SyscallHandler<K>: Sized {
+    /// Register all syscalls supported by this kernel.
     fn link_syscalls(linker: &mut Linker<K>) -> anyhow::Result<()>;
 }
 
@@ -87,7 +104,8 @@ pub trait NetworkOps {
     /// Network information (epoch, version, etc.).
     fn network_context(&self) -> Result<NetworkContext>;
 
-    /// The CID of the tipset at the specified epoch.
+    /// The CID of the tipset at the specified epoch. From nv26, fails with `LimitExceeded` if the
+    /// epoch is more than [`default::MAX_TIPSET_CID_LOOKBACK`] epochs ago.
     fn tipset_cid(&self, epoch: ChainEpoch) -> Result<Cid>;
 }
 
@@ -108,6 +126,11 @@ pub trait SendOps<K: Kernel = Self> {
     /// kernel specifying its Self.
     /// This method is part of the Kernel trait so it can refer to the Self::CallManager
     /// associated type necessary to constrain K.
+    ///
+    /// The parameters may be any block open in this kernel's registry, including the actor's own
+    /// parameters or the return value of a previous send, so that actors can forward blocks
+    /// unchanged. Blocks are passed to the callee by reference, without copying them, and their
+    /// size isn't charged again: it was already charged when the block was opened or created.
     fn send(
         &mut self,
         recipient: &Address,
@@ -157,6 +180,10 @@ pub trait IpldBlockOps {
     ///
     /// This method will fail if the block handle is invalid.
     fn block_stat(&self, id: BlockId) -> Result<BlockStat>;
+
+    /// Returns the number of blocks, and bytes of block data, that may still be opened or created
+    /// during this message, by this invocation or any other.
+    fn block_quota(&self) -> Result<BlockQuota>;
 }
 
 /// Actor state access and manipulation.
@@ -210,6 +237,13 @@ pub trait ActorOps {
 
     fn install_actor(&mut self, code_cid: Cid) -> Result<()>;
 
+    /// Validates and stores the supplied actor bytecode, and records it as installed in the init
+    /// actor's state, returning its code CID (the blake2b-256 `IPLD_RAW` CID of the bytecode). No
+    /// actor is created, and the init actor (the only caller) must re-read its state afterwards.
+    ///
+    /// Only supported with M2 native, as that's when the init actor tracks installed code.
+    fn install_code(&mut self, code: &[u8]) -> Result<Cid>;
+
     /// Returns the actor's "type" (if builitin) or 0 (if not).
     fn get_builtin_actor_type(&self, code_cid: &Cid) -> Result<u32>;
 
@@ -287,12 +321,15 @@ pub trait DebugOps {
     /// Log a message.
     fn log(&mut self, msg: String);
 
+    /// Log a structured record, consisting of a message and a list of key-value pairs.
+    fn log_structured(&mut self, level: LogLevel, msg: String, fields: Vec<(String, String)>);
+
     /// Returns whether debug mode is enabled.
     fn debug_enabled(&self) -> bool;
 
     /// Store an artifact.
     /// Returns error on malformed name, returns Ok and logs the error on system/os errors.
-    fn store_artifact(&self, name: &str, data: &[u8]) -> Result<()>;
+    fn store_artifact(&mut self, name: &str, data: &[u8]) -> Result<()>;
 }
 
 /// Eventing APIs.
@@ -320,8 +357,11 @@ pub mod prelude {
         ActorOps, CryptoOps, DebugOps, EventOps, IpldBlockOps, MessageOps, NetworkOps,
         RandomnessOps, SelfOps, SendOps, UpgradeOps,
     };
-    pub use super::{Block, BlockId, BlockRegistry, BlockStat, CallResult, Kernel, SyscallHandler};
+    pub use super::{
+        Block, BlockId, BlockQuota, BlockRegistry, BlockStat, CallResult, Kernel, SyscallHandler,
+    };
     pub use crate::gas::{Gas, GasTimer, PriceList};
+    pub use crate::kernel::filecoin::NetworkSummary;
     pub use ambassador::Delegate;
     pub use fvm_shared::address::Address;
     pub use fvm_shared::clock::ChainEpoch;
@@ -333,7 +373,7 @@ pub mod prelude {
     pub use fvm_shared::randomness::RANDOMNESS_LENGTH;
     pub use fvm_shared::sys::out::network::NetworkContext;
     pub use fvm_shared::sys::out::vm::MessageContext;
-    pub use fvm_shared::sys::SendFlags;
+    pub use fvm_shared::sys::{LogLevel, SendFlags};
     pub use fvm_shared::version::NetworkVersion;
     pub use fvm_shared::{ActorID, MethodNum};
 
@@ -342,3 +382,16 @@ pub mod prelude {
 }
 
 use prelude::*;
+
+// Keep the kernel operation traits object safe so custom kernels can forward to them dynamically.
+static_assertions::assert_obj_safe!(
+    NetworkOps,
+    MessageOps,
+    IpldBlockOps,
+    SelfOps,
+    ActorOps,
+    CryptoOps,
+    RandomnessOps,
+    DebugOps,
+    EventOps,
+);
@@ -0,0 +1,69 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Enforcement of read-only invocations.
+//!
+//! Every operation that mutates the state, the event log, or the installed code is implemented on
+//! [`Writable`] rather than directly on [`DefaultKernel`]. A `Writable` can only be obtained from
+//! [`DefaultKernel::writable`], which refuses to hand one out while the kernel is read-only, so a
+//! mutating operation can't be reached without passing through that single check.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use super::default::DefaultKernel;
+use super::error::Result;
+use crate::syscall_error;
+
+/// An operation forbidden in read-only invocations.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MutatingOp {
+    SetRoot,
+    SelfDestruct,
+    CreateActor,
+    InstallCode,
+    UpgradeActor,
+    EmitEvent,
+    TransferValue,
+}
+
+impl fmt::Display for MutatingOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MutatingOp::SetRoot => "update the state-root",
+            MutatingOp::SelfDestruct => "self-destruct",
+            MutatingOp::CreateActor => "create actors",
+            MutatingOp::InstallCode => "install code",
+            MutatingOp::UpgradeActor => "upgrade actors",
+            MutatingOp::EmitEvent => "emit events",
+            MutatingOp::TransferValue => "transfer value",
+        })
+    }
+}
+
+/// Write access to a [`DefaultKernel`], proof that it isn't read-only.
+pub struct Writable<'a, C>(&'a mut DefaultKernel<C>);
+
+impl<C> DefaultKernel<C> {
+    /// Returns write access to the kernel in order to perform `op`, or a `ReadOnly` syscall error
+    /// if the kernel is read-only.
+    pub fn writable(&mut self, op: MutatingOp) -> Result<Writable<'_, C>> {
+        if self.read_only {
+            return Err(syscall_error!(ReadOnly; "cannot {op} while read-only").into());
+        }
+        Ok(Writable(self))
+    }
+}
+
+impl<C> Deref for Writable<'_, C> {
+    type Target = DefaultKernel<C>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<C> DerefMut for Writable<'_, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
@@ -21,6 +21,7 @@ pub mod machine;
 pub mod syscalls;
 
 pub mod gas;
+pub mod state_diff;
 pub mod state_tree;
 
 mod blockstore;
@@ -39,9 +40,11 @@ pub mod init_actor;
 #[cfg(feature = "testing")]
 pub mod system_actor;
 
-mod eam_actor;
+pub mod eam_actor;
 mod history_map;
 mod ipld;
+mod power_actor;
+mod reward_actor;
 pub mod trace;
 
 #[cfg(test)]
@@ -58,7 +61,7 @@ mod test {
     use crate::executor;
     use crate::externs::{Chain, Consensus, Externs, Rand};
     use crate::kernel::filecoin::DefaultFilecoinKernel;
-    use crate::machine::{DefaultMachine, Manifest, NetworkConfig};
+    use crate::machine::{DefaultMachine, MachineContext, Manifest, NetworkConfig};
     use crate::state_tree::StateTree;
 
     struct DummyExterns;
@@ -131,4 +134,689 @@ mod test {
             Box::new(machine),
         );
     }
+
+    #[test]
+    fn test_read_only_constructor() {
+        use crate::machine::Machine;
+
+        let bs = std::rc::Rc::new(MemoryBlockstore::default());
+        let mut st = StateTree::new(bs.clone(), StateTreeVersion::V5).unwrap();
+        let root = st.flush().unwrap();
+
+        let manifest_cid = bs
+            .put_cbor(&Manifest::DUMMY_CODES, Code::Blake2b256)
+            .unwrap();
+        let actors_cid = bs.put_cbor(&(1, manifest_cid), Code::Blake2b256).unwrap();
+
+        let mc = NetworkConfig::new(fvm_shared::version::NetworkVersion::V21)
+            .override_actors(actors_cid)
+            .for_epoch(0, 0, root);
+
+        let mut machine = DefaultMachine::new_read_only(&mc, bs.clone(), DummyExterns).unwrap();
+        assert!(machine.blockstore().is_read_only());
+
+        // Flushing an unmodified state tree is fine.
+        assert_eq!(machine.flush().unwrap(), root);
+
+        // But persisting any modification fails.
+        machine.state_tree_mut().set_actor(
+            1000,
+            crate::state_tree::ActorState::new_empty(fvm_shared::EMPTY_ARR_CID, None),
+        );
+        assert!(machine.flush().is_err());
+    }
+
+    /// Builds an actor whose `invoke` function has the given body.
+    fn test_actor(body: &[u8]) -> Vec<u8> {
+        #[rustfmt::skip]
+        let mut wasm = vec![
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            // (type (func (param i32) (result i32)))
+            0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+            0x03, 0x02, 0x01, 0x00,
+            // (memory 1)
+            0x05, 0x03, 0x01, 0x00, 0x01,
+            // (export "memory" (memory 0)) (export "invoke" (func 0))
+            0x07, 0x13, 0x02,
+            0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+            0x06, b'i', b'n', b'v', b'o', b'k', b'e', 0x00, 0x00,
+        ];
+        wasm.extend([0x0a, body.len() as u8 + 2, 0x01, body.len() as u8]);
+        wasm.extend_from_slice(body);
+        wasm
+    }
+
+    type TestKernel =
+        DefaultFilecoinKernel<DefaultCallManager<DefaultMachine<MemoryBlockstore, DummyExterns>>>;
+    type TestExecutor = executor::DefaultExecutor<TestKernel>;
+
+    /// Builds an executor over a fresh machine, with an actor with the given code at ID 1000, and
+    /// an account with 1 FIL at ID 100. The engine pool is passed to `setup` before any actor is
+    /// instantiated. Returns the executor and the initial state root.
+    fn test_executor(
+        wasm: &[u8],
+        configure: impl FnOnce(&mut NetworkConfig),
+        setup: impl FnOnce(&EnginePool) -> anyhow::Result<()>,
+        timeout: Option<std::time::Duration>,
+    ) -> anyhow::Result<(TestExecutor, Cid)> {
+        test_executor_with_context(wasm, configure, |_| (), setup, timeout)
+    }
+
+    /// Like [`test_executor`], additionally passing the machine context to `configure_machine`.
+    fn test_executor_with_context(
+        wasm: &[u8],
+        configure: impl FnOnce(&mut NetworkConfig),
+        configure_machine: impl FnOnce(&mut MachineContext),
+        setup: impl FnOnce(&EnginePool) -> anyhow::Result<()>,
+        timeout: Option<std::time::Duration>,
+    ) -> anyhow::Result<(TestExecutor, Cid)> {
+        use fvm_ipld_blockstore::{Block, Blockstore};
+        use fvm_shared::econ::TokenAmount;
+        use fvm_shared::state::ActorState;
+        use fvm_shared::IPLD_RAW;
+
+        let bs = MemoryBlockstore::default();
+        let code = bs.put(Code::Blake2b256, &Block::new(IPLD_RAW, wasm))?;
+        let mut st = StateTree::new(bs, StateTreeVersion::V5)?;
+        st.set_actor(
+            1000,
+            ActorState::new(code, fvm_shared::EMPTY_ARR_CID, Default::default(), 0, None),
+        );
+        st.set_actor(
+            100,
+            ActorState::new(
+                *Manifest::dummy().get_account_code(),
+                fvm_shared::EMPTY_ARR_CID,
+                TokenAmount::from_whole(1),
+                0,
+                None,
+            ),
+        );
+        let root = st.flush()?;
+        let bs = st.into_store();
+
+        let manifest_cid = bs.put_cbor(&Manifest::DUMMY_CODES, Code::Blake2b256)?;
+        let actors_cid = bs.put_cbor(&(1, manifest_cid), Code::Blake2b256)?;
+
+        let mut nc = NetworkConfig::new(fvm_shared::version::NetworkVersion::V21);
+        nc.override_actors(actors_cid);
+        configure(&mut nc);
+        let mut mc = nc.for_epoch(0, 0, root);
+        configure_machine(&mut mc);
+
+        let engine = EnginePool::new((&mc.network).into())?;
+        setup(&engine)?;
+        engine.acquire().preload_all(&bs, &[code])?;
+        let machine = DefaultMachine::new(&mc, bs, DummyExterns)?;
+        let mut executor = TestExecutor::new(engine, machine)?;
+        executor.set_execution_timeout(timeout)?;
+        Ok((executor, root))
+    }
+
+    /// Returns a message from the account to the test actor.
+    fn test_message() -> fvm_shared::message::Message {
+        fvm_shared::message::Message {
+            version: 0,
+            from: fvm_shared::address::Address::new_id(100),
+            to: fvm_shared::address::Address::new_id(1000),
+            sequence: 0,
+            value: Default::default(),
+            method_num: 1,
+            params: Default::default(),
+            gas_limit: fvm_shared::BLOCK_GAS_LIMIT,
+            gas_fee_cap: Default::default(),
+            gas_premium: Default::default(),
+        }
+    }
+
+    /// Invokes an actor with the given code in a fresh machine, as an implicit message.
+    fn call_test_actor(
+        wasm: &[u8],
+        configure: impl FnOnce(&mut NetworkConfig),
+        timeout: Option<std::time::Duration>,
+    ) -> anyhow::Result<executor::ApplyRet> {
+        use crate::executor::{ApplyKind, Executor};
+
+        let (mut executor, _) = test_executor(wasm, configure, |_| Ok(()), timeout)?;
+        executor.execute_message(test_message(), ApplyKind::Implicit, 0)
+    }
+
+    #[test]
+    fn test_execution_timeout() {
+        use crate::executor::{ApplyKind, ExecutionTimeout, Executor};
+
+        // (loop (br 0)) (i32.const 1)
+        let wasm = test_actor(&[0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x41, 0x01, 0x0b]);
+        let (mut executor, root) = test_executor(
+            &wasm,
+            |nc| {
+                nc.enable_execution_timeouts();
+            },
+            |_| Ok(()),
+            Some(std::time::Duration::from_millis(50)),
+        )
+        .unwrap();
+        let mut msg = test_message();
+        msg.gas_fee_cap = fvm_shared::econ::TokenAmount::from_atto(1);
+        let err = executor
+            .execute_message(msg, ApplyKind::Explicit, 100)
+            .unwrap_err();
+        assert!(err.is::<ExecutionTimeout>(), "unexpected error: {err:#}");
+
+        // Nothing was applied: neither the gas precharge, nor the bump of the sender's nonce.
+        assert_eq!(executor.flush().unwrap(), root);
+    }
+
+    #[test]
+    fn test_trusted_writes() {
+        use crate::executor::{ApplyKind, Executor};
+
+        // (i32.const 0)
+        let wasm = test_actor(&[0x00, 0x41, 0x00, 0x0b]);
+        let (mut executor, _) = test_executor_with_context(
+            &wasm,
+            |_| (),
+            |mc| {
+                mc.enable_trusted_writes();
+            },
+            |_| Ok(()),
+            None,
+        )
+        .unwrap();
+
+        // No message may be applied with trusted writes, not even implicit ones.
+        for kind in [ApplyKind::Explicit, ApplyKind::Implicit] {
+            let err = executor
+                .execute_message(test_message(), kind, 100)
+                .unwrap_err();
+            assert!(
+                err.to_string().contains("trusted blockstore writes"),
+                "unexpected error: {err:#}"
+            );
+        }
+    }
+
+    /// Builds an actor importing `debug.log`, with "hello" at offset 0 of its memory, whose
+    /// `invoke` function has the given body.
+    fn debug_log_actor(body: &[u8]) -> Vec<u8> {
+        #[rustfmt::skip]
+        let mut wasm = vec![
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            // (type (func (param i32) (result i32))) (type (func (param i32 i32) (result i32)))
+            0x01, 0x0c, 0x02,
+            0x60, 0x01, 0x7f, 0x01, 0x7f,
+            0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7f,
+            // (import "debug" "log" (func 0 (type 1)))
+            0x02, 0x0d, 0x01,
+            0x05, b'd', b'e', b'b', b'u', b'g', 0x03, b'l', b'o', b'g', 0x00, 0x01,
+            0x03, 0x02, 0x01, 0x00,
+            // (memory 1)
+            0x05, 0x03, 0x01, 0x00, 0x01,
+            // (export "memory" (memory 0)) (export "invoke" (func 1))
+            0x07, 0x13, 0x02,
+            0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+            0x06, b'i', b'n', b'v', b'o', b'k', b'e', 0x00, 0x01,
+        ];
+        wasm.extend([0x0a, body.len() as u8 + 2, 0x01, body.len() as u8]);
+        wasm.extend_from_slice(body);
+        // (data (i32.const 0) "hello")
+        wasm.extend([0x0b, 0x0b, 0x01, 0x00, 0x41, 0x00, 0x0b, 0x05]);
+        wasm.extend_from_slice(b"hello");
+        wasm
+    }
+
+    #[test]
+    fn test_actor_logs() {
+        use crate::call_manager::ActorLog;
+
+        // (drop (call 0 (i32.const 0) (i32.const 5))) (i32.const 0)
+        #[rustfmt::skip]
+        let wasm = debug_log_actor(&[
+            0x00,
+            0x41, 0x00, 0x41, 0x05, 0x10, 0x00, 0x1a,
+            0x41, 0x00, 0x0b,
+        ]);
+
+        // Logs are only recorded in debug mode.
+        let ret = call_test_actor(&wasm, |_| {}, None).unwrap();
+        assert_eq!(ret.msg_receipt.exit_code, fvm_shared::error::ExitCode::OK);
+        assert!(ret.logs.is_empty());
+
+        let ret = call_test_actor(
+            &wasm,
+            |nc| {
+                nc.enable_actor_debugging();
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(ret.msg_receipt.exit_code, fvm_shared::error::ExitCode::OK);
+        assert_eq!(
+            ret.logs,
+            vec![ActorLog {
+                actor: 1000,
+                depth: 0,
+                method: 1,
+                level: None,
+                message: "hello".into(),
+                fields: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_actor_logs_quota() {
+        // Logs the whole memory (64KiB) 300 times:
+        //
+        // (loop
+        //   (drop (call 0 (i32.const 0) (i32.const 65536)))
+        //   (br_if 0 (i32.lt_u (local.tee 0 (i32.add (local.get 0) (i32.const 1)))
+        //                      (i32.const 300))))
+        // (i32.const 0)
+        #[rustfmt::skip]
+        let wasm = debug_log_actor(&[
+            0x01, 0x01, 0x7f,
+            0x03, 0x40,
+            0x41, 0x00, 0x41, 0x80, 0x80, 0x04, 0x10, 0x00, 0x1a,
+            0x20, 0x00, 0x41, 0x01, 0x6a, 0x22, 0x00,
+            0x41, 0xac, 0x02, 0x49, 0x0d, 0x00,
+            0x0b,
+            0x41, 0x00, 0x0b,
+        ]);
+
+        let ret = call_test_actor(
+            &wasm,
+            |nc| {
+                nc.enable_actor_debugging();
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(ret.msg_receipt.exit_code, fvm_shared::error::ExitCode::OK);
+        // Only the first 16MiB are kept.
+        assert_eq!(ret.logs.len(), 256);
+    }
+
+    /// Configures the network for actors importing `debug.log_structured`, which is only linked
+    /// from nv26.
+    fn structured_logs_config(nc: &mut NetworkConfig) {
+        nc.network_version = fvm_shared::version::NetworkVersion::V26;
+    }
+
+    /// Builds an actor importing `debug.log_structured`, with "hello" at offset 0 of its memory and
+    /// the fields `[["k", "v"]]` (CBOR) at offset 8, whose `invoke` function has the given body.
+    fn structured_log_actor(body: &[u8]) -> Vec<u8> {
+        #[rustfmt::skip]
+        let mut wasm = vec![
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            // (type (func (param i32) (result i32)))
+            // (type (func (param i32 i32 i32 i32 i32) (result i32)))
+            0x01, 0x0f, 0x02,
+            0x60, 0x01, 0x7f, 0x01, 0x7f,
+            0x60, 0x05, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x01, 0x7f,
+            // (import "debug" "log_structured" (func 0 (type 1)))
+            0x02, 0x18, 0x01,
+            0x05, b'd', b'e', b'b', b'u', b'g',
+            0x0e, b'l', b'o', b'g', b'_', b's', b't', b'r', b'u', b'c', b't', b'u', b'r', b'e',
+            b'd', 0x00, 0x01,
+            0x03, 0x02, 0x01, 0x00,
+            // (memory 1)
+            0x05, 0x03, 0x01, 0x00, 0x01,
+            // (export "memory" (memory 0)) (export "invoke" (func 1))
+            0x07, 0x13, 0x02,
+            0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+            0x06, b'i', b'n', b'v', b'o', b'k', b'e', 0x00, 0x01,
+        ];
+        wasm.extend([0x0a, body.len() as u8 + 2, 0x01, body.len() as u8]);
+        wasm.extend_from_slice(body);
+        // (data (i32.const 0) "hello") (data (i32.const 8) "\81\82\61k\61v")
+        wasm.extend([0x0b, 0x16, 0x02, 0x00, 0x41, 0x00, 0x0b, 0x05]);
+        wasm.extend_from_slice(b"hello");
+        wasm.extend([
+            0x00, 0x41, 0x08, 0x0b, 0x06, 0x81, 0x82, 0x61, b'k', 0x61, b'v',
+        ]);
+        wasm
+    }
+
+    #[test]
+    fn test_actor_structured_logs() {
+        use fvm_shared::sys::LogLevel;
+
+        use crate::call_manager::ActorLog;
+        use crate::executor::{ApplyKind, Executor};
+        use crate::trace::ExecutionEvent;
+
+        // (drop (call 0 (i32.const 3) (i32.const 0) (i32.const 5) (i32.const 8) (i32.const 6)))
+        // (i32.const 0)
+        #[rustfmt::skip]
+        let wasm = structured_log_actor(&[
+            0x00,
+            0x41, 0x03, 0x41, 0x00, 0x41, 0x05, 0x41, 0x08, 0x41, 0x06, 0x10, 0x00, 0x1a,
+            0x41, 0x00, 0x0b,
+        ]);
+
+        // Logs are only recorded in debug mode.
+        let ret = call_test_actor(&wasm, structured_logs_config, None).unwrap();
+        assert_eq!(ret.msg_receipt.exit_code, fvm_shared::error::ExitCode::OK);
+        assert!(ret.logs.is_empty());
+
+        let (mut executor, _) = test_executor_with_context(
+            &wasm,
+            |nc| {
+                structured_logs_config(nc);
+                nc.enable_actor_debugging();
+            },
+            |mc| {
+                mc.enable_tracing();
+            },
+            |_| Ok(()),
+            None,
+        )
+        .unwrap();
+        let ret = executor
+            .execute_message(test_message(), ApplyKind::Implicit, 0)
+            .unwrap();
+        assert_eq!(ret.msg_receipt.exit_code, fvm_shared::error::ExitCode::OK);
+        let fields = vec![("k".to_owned(), "v".to_owned())];
+        assert_eq!(
+            ret.logs,
+            vec![ActorLog {
+                actor: 1000,
+                depth: 0,
+                method: 1,
+                level: Some(LogLevel::Info),
+                message: "hello".into(),
+                fields: fields.clone(),
+            }]
+        );
+        let traced: Vec<_> = ret
+            .exec_trace
+            .iter()
+            .filter_map(|e| match e {
+                ExecutionEvent::StructuredLog {
+                    actor,
+                    level,
+                    message,
+                    fields,
+                } => Some((*actor, *level, message.as_str(), fields)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(traced, vec![(1000, LogLevel::Info, "hello", &fields)]);
+    }
+
+    #[test]
+    fn test_actor_structured_logs_invalid_level() {
+        // Traps unless logging at level 9 fails:
+        //
+        // (if (i32.eqz (call 0 (i32.const 9) (i32.const 0) (i32.const 5) (i32.const 8)
+        //                      (i32.const 6)))
+        //   (then unreachable))
+        // (i32.const 0)
+        #[rustfmt::skip]
+        let wasm = structured_log_actor(&[
+            0x00,
+            0x41, 0x09, 0x41, 0x00, 0x41, 0x05, 0x41, 0x08, 0x41, 0x06, 0x10, 0x00,
+            0x45, 0x04, 0x40, 0x00, 0x0b,
+            0x41, 0x00, 0x0b,
+        ]);
+
+        let ret = call_test_actor(
+            &wasm,
+            |nc| {
+                structured_logs_config(nc);
+                nc.enable_actor_debugging();
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(ret.msg_receipt.exit_code, fvm_shared::error::ExitCode::OK);
+        assert!(ret.logs.is_empty());
+    }
+
+    #[test]
+    fn test_float_policy() {
+        use crate::engine::FloatPolicy;
+
+        // Traps unless 0/0 produces the canonical NaN:
+        //
+        // (if (i32.ne (i32.reinterpret_f32 (f32.div (f32.const 0) (f32.const 0)))
+        //             (i32.const 0x7fc00000))
+        //   (then unreachable))
+        // (i32.const 0)
+        #[rustfmt::skip]
+        let wasm = test_actor(&[
+            0x00,
+            0x43, 0x00, 0x00, 0x00, 0x00,
+            0x43, 0x00, 0x00, 0x00, 0x00,
+            0x95, 0xbc,
+            0x41, 0x80, 0x80, 0x80, 0xfe, 0x07,
+            0x47, 0x04, 0x40, 0x00, 0x0b,
+            0x41, 0x00, 0x0b,
+        ]);
+
+        let ret = call_test_actor(&wasm, |_| {}, None).unwrap();
+        assert_eq!(ret.msg_receipt.exit_code, fvm_shared::error::ExitCode::OK);
+
+        let err = call_test_actor(
+            &wasm,
+            |nc| {
+                nc.float_policy(FloatPolicy::Disallow);
+            },
+            None,
+        )
+        .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("floating-point"),
+            "unexpected error: {err:#}"
+        );
+    }
+
+    #[test]
+    fn test_host_modules() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        use crate::engine::Diagnostic;
+        use crate::executor::{ApplyKind, Executor};
+        use crate::syscalls::Context;
+
+        // Calls `my_chain.record(21)`, trapping if it fails.
+        #[rustfmt::skip]
+        let wasm = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            // (type (func (param i32) (result i32)))
+            0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+            // (import "my_chain" "record" (func 0 (type 0)))
+            0x02, 0x13, 0x01,
+            0x08, b'm', b'y', b'_', b'c', b'h', b'a', b'i', b'n',
+            0x06, b'r', b'e', b'c', b'o', b'r', b'd', 0x00, 0x00,
+            0x03, 0x02, 0x01, 0x00,
+            // (memory 1)
+            0x05, 0x03, 0x01, 0x00, 0x01,
+            // (export "memory" (memory 0)) (export "invoke" (func 1))
+            0x07, 0x13, 0x02,
+            0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+            0x06, b'i', b'n', b'v', b'o', b'k', b'e', 0x00, 0x01,
+            // (if (call 0 (i32.const 21)) (then unreachable)) (i32.const 0)
+            0x0a, 0x0e, 0x01, 0x0c, 0x00,
+            0x41, 0x15, 0x10, 0x00, 0x04, 0x40, 0x00, 0x0b, 0x41, 0x00, 0x0b,
+        ];
+
+        let recorded = Arc::new(AtomicU32::new(0));
+        let register = |engine: &EnginePool| {
+            let recorded = recorded.clone();
+            engine.register_syscalls::<TestKernel>("my_chain", move |ns| {
+                let recorded = recorded.clone();
+                ns.link_syscall(
+                    "record",
+                    move |_: Context<'_, TestKernel>, value: u32| -> crate::kernel::Result<()> {
+                        recorded.store(value, Ordering::SeqCst);
+                        Ok(())
+                    },
+                )?;
+                Ok(())
+            })
+        };
+
+        // The engine's linker is cached, so it must link the actor more than once.
+        let (mut executor, _) = test_executor(&wasm, |_| {}, register, None).unwrap();
+        for _ in 0..2 {
+            recorded.store(0, Ordering::SeqCst);
+            let ret = executor
+                .execute_message(test_message(), ApplyKind::Implicit, 0)
+                .unwrap();
+            assert_eq!(ret.msg_receipt.exit_code, fvm_shared::error::ExitCode::OK);
+            assert_eq!(recorded.load(Ordering::SeqCst), 21);
+        }
+
+        // Without the host module, the actor doesn't link.
+        let engine =
+            EnginePool::new((&NetworkConfig::new(fvm_shared::version::NetworkVersion::V21)).into())
+                .unwrap();
+        let report = engine
+            .acquire()
+            .preflight_validate::<TestKernel>(&wasm, fvm_shared::version::NetworkVersion::V21);
+        assert!(
+            matches!(&report.diagnostics[..], [Diagnostic::Link(_)]),
+            "{report:?}"
+        );
+        register(&engine).unwrap();
+        let report = engine
+            .acquire()
+            .preflight_validate::<TestKernel>(&wasm, fvm_shared::version::NetworkVersion::V21);
+        assert!(report.is_valid(), "{report:?}");
+
+        // Namespaces are registered once, and built-in namespaces can't be used.
+        assert!(register(&engine).is_err());
+        assert!(engine
+            .register_syscalls::<TestKernel>("ipld", |_| Ok(()))
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "m2-native")]
+    fn test_installed_code() {
+        use fvm_ipld_blockstore::Blockstore;
+        use fvm_shared::address::Address;
+        use fvm_shared::econ::TokenAmount;
+        use fvm_shared::error::{ErrorNumber, ExitCode};
+        use fvm_shared::state::ActorState;
+        use fvm_shared::version::NetworkVersion;
+        use num_traits::Zero;
+
+        use crate::call_manager::CallManager;
+        use crate::executor::{ApplyKind, Executor};
+        use crate::init_actor::{State as InitActorState, INIT_ACTOR_ID};
+        use crate::kernel::{ActorOps, BlockRegistry, ExecutionError, Kernel, SyscallError};
+        use crate::machine::Machine;
+
+        let bs = MemoryBlockstore::default();
+        let manifest = Manifest::dummy();
+        let init_state = bs
+            .put_cbor(&InitActorState::new_test(&bs), Code::Blake2b256)
+            .unwrap();
+        let mut st = StateTree::new(bs, StateTreeVersion::V5).unwrap();
+        st.set_actor(
+            INIT_ACTOR_ID,
+            ActorState::new(*manifest.get_init_code(), init_state, Zero::zero(), 0, None),
+        );
+        st.set_actor(
+            100,
+            ActorState::new(
+                *manifest.get_account_code(),
+                fvm_shared::EMPTY_ARR_CID,
+                TokenAmount::from_whole(1),
+                0,
+                None,
+            ),
+        );
+        let root = st.flush().unwrap();
+        let bs = st.into_store();
+        let manifest_cid = bs
+            .put_cbor(&Manifest::DUMMY_CODES, Code::Blake2b256)
+            .unwrap();
+        let actors_cid = bs.put_cbor(&(1, manifest_cid), Code::Blake2b256).unwrap();
+        let mut nc = NetworkConfig::new(NetworkVersion::V26);
+        nc.override_actors(actors_cid);
+
+        // Install some code in a message that reverts, then other code in one that commits, and
+        // create an actor with the latter. Only installed code may be used to create actors.
+        let engine = EnginePool::new((&nc).into()).unwrap();
+        let mut machine = DefaultMachine::new(&nc.for_epoch(0, 0, root), bs, DummyExterns).unwrap();
+        let mut installed = Vec::new();
+        for (wasm, revert) in [
+            // (i32.const 1)
+            (test_actor(&[0x00, 0x41, 0x01, 0x0b]), true),
+            // (i32.const 0)
+            (test_actor(&[0x00, 0x41, 0x00, 0x0b]), false),
+        ] {
+            machine.state_tree_mut().begin_transaction();
+            let call_manager = DefaultCallManager::new(
+                machine,
+                engine.acquire(),
+                fvm_shared::BLOCK_GAS_LIMIT,
+                100,
+                Address::new_id(100),
+                Some(INIT_ACTOR_ID),
+                Address::new_id(INIT_ACTOR_ID),
+                0,
+                Zero::zero(),
+            );
+            let mut kernel = TestKernel::new(
+                call_manager,
+                BlockRegistry::default(),
+                100,
+                INIT_ACTOR_ID,
+                0,
+                Zero::zero(),
+                false,
+            );
+            installed.push(kernel.install_code(&wasm).unwrap());
+            let (mut call_manager, _) = kernel.into_inner();
+            if !revert {
+                let res = call_manager.create_actor(installed[0], 1001, None);
+                assert!(
+                    matches!(
+                        res,
+                        Err(ExecutionError::Syscall(SyscallError(
+                            _,
+                            ErrorNumber::IllegalArgument
+                        )))
+                    ),
+                    "{res:?}"
+                );
+                call_manager.create_actor(installed[1], 1000, None).unwrap();
+            }
+            let (res, m) = call_manager.finish();
+            res.unwrap();
+            machine = m;
+            machine.state_tree_mut().end_transaction(revert).unwrap();
+        }
+        let [reverted, committed] = installed[..] else {
+            unreachable!()
+        };
+
+        // Only the committed code is recorded in the state, and survives flushing.
+        let (init_state, _) = InitActorState::load(machine.state_tree()).unwrap();
+        assert!(init_state
+            .is_code_installed(machine.blockstore(), &committed)
+            .unwrap());
+        assert!(!init_state
+            .is_code_installed(machine.blockstore(), &reverted)
+            .unwrap());
+        let root = machine.flush().unwrap();
+        let bs = machine.into_store().into_inner();
+        assert!(bs.has(&committed).unwrap());
+        assert!(!bs.has(&reverted).unwrap());
+
+        // A fresh engine compiles the installed code from the flushed state on demand.
+        let machine = DefaultMachine::new(&nc.for_epoch(1, 0, root), bs, DummyExterns).unwrap();
+        let mut executor =
+            TestExecutor::new(EnginePool::new((&nc).into()).unwrap(), machine).unwrap();
+        let ret = executor
+            .execute_message(test_message(), ApplyKind::Implicit, 0)
+            .unwrap();
+        assert_eq!(ret.msg_receipt.exit_code, ExitCode::OK);
+    }
 }
@@ -62,4 +62,14 @@ impl<M: Machine> Machine for Box<M> {
     fn new_limiter(&self) -> Self::Limiter {
         (**self).new_limiter()
     }
+
+    #[inline(always)]
+    fn record_witness(&self) {
+        (**self).record_witness()
+    }
+
+    #[inline(always)]
+    fn take_witness(&self) -> Result<Vec<Cid>> {
+        (**self).take_witness()
+    }
 }
@@ -1,16 +1,14 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-use std::ops::RangeInclusive;
 
 use anyhow::{anyhow, Context as _};
 use cid::Cid;
 use fvm_ipld_blockstore::{Block, Blockstore, Buffered};
 use fvm_ipld_encoding::{CborStore, DAG_CBOR};
-use fvm_shared::version::NetworkVersion;
 use log::debug;
 use multihash_codetable::Code::Blake2b256;
 
-use super::{Machine, MachineContext};
+use super::{Machine, MachineContext, SUPPORTED_NETWORK_VERSIONS};
 use crate::blockstore::BufferedBlockstore;
 use crate::externs::Externs;
 use crate::kernel::{ClassifyResult, Result};
@@ -51,15 +49,43 @@ where
     /// * `blockstore`: The underlying [blockstore][`Blockstore`] for reading/writing state.
     /// * `externs`: Client-provided ["external"][`Externs`] methods for accessing chain state.
     pub fn new(context: &MachineContext, blockstore: B, externs: E) -> anyhow::Result<Self> {
-        const SUPPORTED_VERSIONS: RangeInclusive<NetworkVersion> =
-            NetworkVersion::V21..=NetworkVersion::V25;
+        Self::new_inner(context, blockstore, externs, false)
+    }
+
+    /// Create a new read-only [`DefaultMachine`] for querying the state at the context's initial
+    /// state root.
+    ///
+    /// Unlike [`DefaultMachine::new`], the machine won't buffer writes and won't write anything to
+    /// the blockstore: any attempt to write a new block (e.g., by flushing a modified state tree)
+    /// will fail with an error. Messages may still be executed (e.g., to call read-only methods), but
+    /// their effects can never be persisted.
+    ///
+    /// The blockstore must already contain the empty array block (written by
+    /// [`DefaultMachine::new`]).
+    pub fn new_read_only(
+        context: &MachineContext,
+        blockstore: B,
+        externs: E,
+    ) -> anyhow::Result<Self> {
+        Self::new_inner(context, blockstore, externs, true)
+    }
 
+    fn new_inner(
+        context: &MachineContext,
+        blockstore: B,
+        externs: E,
+        read_only: bool,
+    ) -> anyhow::Result<Self> {
         debug!(
-            "initializing a new machine, epoch={}, base_fee={}, nv={:?}, root={}",
-            context.epoch, &context.base_fee, context.network_version, context.initial_state_root
+            "initializing a new machine, read_only={}, epoch={}, base_fee={}, nv={:?}, root={}",
+            read_only,
+            context.epoch,
+            &context.base_fee,
+            context.network_version,
+            context.initial_state_root
         );
 
-        if !SUPPORTED_VERSIONS.contains(&context.network_version) {
+        if !SUPPORTED_NETWORK_VERSIONS.contains(&context.network_version) {
             return Err(anyhow!(
                 "unsupported network version: {}",
                 context.network_version
@@ -77,11 +103,24 @@ where
             ));
         }
 
-        put_empty_blocks(&blockstore)?;
-
         // Create a new state tree from the supplied root.
         let state_tree = {
-            let bstore = BufferedBlockstore::new(blockstore);
+            let bstore = if read_only {
+                if !blockstore
+                    .has(&fvm_shared::EMPTY_ARR_CID)
+                    .context("failed to check for the empty array block")?
+                {
+                    return Err(anyhow!(
+                        "read-only blockstore doesn't have the empty array block"
+                    ));
+                }
+                BufferedBlockstore::read_only(blockstore)
+            } else {
+                put_empty_blocks(&blockstore)?;
+                let mut bstore = BufferedBlockstore::new(blockstore);
+                bstore.set_trusted_writes(context.trusted_writes);
+                bstore
+            };
             StateTree::new_from_root(bstore, &context.initial_state_root)?
         };
 
@@ -174,6 +213,17 @@ where
     fn new_limiter(&self) -> Self::Limiter {
         DefaultMemoryLimiter::for_network(&self.context().network)
     }
+
+    fn record_witness(&self) {
+        self.state_tree.record_witness();
+        self.blockstore().record_witness()
+    }
+
+    fn take_witness(&self) -> Result<Vec<Cid>> {
+        // Re-read the state served from the state tree's caches so the blockstore records it.
+        self.state_tree.read_witness()?;
+        Ok(self.blockstore().take_witness())
+    }
 }
 
 /// DagCBOR-encoded empty array. This is the default state object, so it always has to exist.
@@ -6,15 +6,21 @@ use anyhow::{anyhow, Context};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::CborStore;
+use fvm_shared::well_known::WellKnownActor;
+use serde::ser::SerializeSeq;
+use serde::{Serialize, Serializer};
 
 const ACCOUNT_ACTOR_NAME: &str = "account";
-const INIT_ACTOR_NAME: &str = "init";
-const SYSTEM_ACTOR_NAME: &str = "system";
+const INIT_ACTOR_NAME: &str = WellKnownActor::Init.code_name();
+const SYSTEM_ACTOR_NAME: &str = WellKnownActor::System.code_name();
 const PLACEHOLDER_ACTOR_NAME: &str = "placeholder";
-const EAM_ACTOR_NAME: &str = "eam";
+const EAM_ACTOR_NAME: &str = WellKnownActor::Eam.code_name();
 const ETHACCOUNT_ACTOR_NAME: &str = "ethaccount";
 
 /// A mapping of builtin actor CIDs to their respective types.
+///
+/// The manifest serializes to the same format it's loaded from (a list of name/code CID pairs), so
+/// external tools can store or export it as they found it on-chain.
 pub struct Manifest {
     account_code: Cid,
     placeholder_code: Cid,
@@ -23,8 +29,11 @@ pub struct Manifest {
     eam_code: Cid,
     ethaccount_code: Cid,
 
+    /// Actor type names and code CIDs, in manifest order (i.e., by ID, starting at 1).
+    entries: Vec<(String, Cid)>,
     by_id: HashMap<u32, Cid>,
     by_code: HashMap<Cid, u32>,
+    by_name: HashMap<String, Cid>,
 }
 
 /// Create an "id CID" (for testing).
@@ -62,6 +71,8 @@ impl Manifest {
         ("cron", id_cid(b"fil/test/cron")),
         ("account", id_cid(b"fil/test/account")),
         ("placeholder", id_cid(b"fil/test/placeholder")),
+        ("storagepower", id_cid(b"fil/test/storagepower")),
+        ("reward", id_cid(b"fil/test/reward")),
     ];
 
     #[cfg(any(feature = "testing", test))]
@@ -90,6 +101,7 @@ impl Manifest {
         let mut by_name = HashMap::new();
         let mut by_id = HashMap::new();
         let mut by_code = HashMap::new();
+        let mut entries = Vec::new();
 
         // Actors are indexed sequentially, starting at 1, in the order in which they appear in the
         // manifest. 0 is reserved for "everything else" (i.e., not a builtin actor).
@@ -97,7 +109,8 @@ impl Manifest {
             let name = name.into();
             by_id.insert(id, code_cid);
             by_code.insert(code_cid, id);
-            by_name.insert(name, code_cid);
+            by_name.insert(name.clone(), code_cid);
+            entries.push((name, code_cid));
         }
 
         let account_code = *by_name
@@ -131,8 +144,10 @@ impl Manifest {
             placeholder_code,
             eam_code,
             ethaccount_code,
+            entries,
             by_id,
             by_code,
+            by_name,
         })
     }
 
@@ -146,6 +161,27 @@ impl Manifest {
         self.by_code.get(code).copied().unwrap_or(0)
     }
 
+    /// Returns the code CID of the builtin actor with the given type name (e.g., "account").
+    pub fn code_by_name(&self, name: &str) -> Option<&Cid> {
+        self.by_name.get(name)
+    }
+
+    /// Returns the type name (e.g., "account") of the builtin actor with the given code CID, if
+    /// any.
+    pub fn name_by_code(&self, code: &Cid) -> Option<&str> {
+        let id = self.by_code.get(code)?;
+        self.entries
+            .get(*id as usize - 1)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Iterates over the type names and code CIDs of the builtin actors, in manifest order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Cid)> {
+        self.entries
+            .iter()
+            .map(|(name, code)| (name.as_str(), code))
+    }
+
     /// Returns true id the passed code CID is the account actor.
     pub fn is_account_actor(&self, cid: &Cid) -> bool {
         &self.account_code == cid
@@ -161,6 +197,7 @@ impl Manifest {
         &self.ethaccount_code == cid
     }
 
+    /// Iterates over the code CIDs of the builtin actors, in no particular order.
     pub fn builtin_actor_codes(&self) -> impl Iterator<Item = &Cid> {
         self.by_id.values()
     }
@@ -195,3 +232,55 @@ impl Manifest {
         &self.ethaccount_code
     }
 }
+
+impl Serialize for Manifest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.entries.len()))?;
+        for entry in &self.entries {
+            seq.serialize_element(entry)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use multihash_codetable::Code;
+
+    use super::*;
+
+    #[test]
+    fn lookups() {
+        let manifest = Manifest::dummy();
+        assert!(manifest.iter().eq(Manifest::DUMMY_CODES
+            .iter()
+            .map(|(name, code)| (*name, code))));
+
+        for (id, (name, code)) in (1..).zip(Manifest::DUMMY_CODES) {
+            assert_eq!(manifest.code_by_name(name), Some(code));
+            assert_eq!(manifest.name_by_code(code), Some(*name));
+            assert_eq!(manifest.id_by_code(code), id);
+            assert_eq!(manifest.code_by_id(id), Some(code));
+        }
+        assert_eq!(manifest.code_by_name("miner"), None);
+        assert_eq!(manifest.name_by_code(&Cid::default()), None);
+    }
+
+    #[test]
+    fn serialization() {
+        let bs = MemoryBlockstore::default();
+        let manifest = Manifest::dummy();
+        let root = bs.put_cbor(&manifest, Code::Blake2b256).unwrap();
+        assert_eq!(
+            root,
+            bs.put_cbor(&Manifest::DUMMY_CODES, Code::Blake2b256)
+                .unwrap()
+        );
+        let loaded = Manifest::load(&bs, &root, 1).unwrap();
+        assert!(loaded.iter().eq(manifest.iter()));
+    }
+}
@@ -1,16 +1,25 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
 use cid::Cid;
 use derive_more::{Deref, DerefMut};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
 use fvm_shared::version::NetworkVersion;
+use fvm_shared::well_known::WellKnownActor;
 use fvm_shared::ActorID;
 use num_traits::Zero;
 
+use crate::call_manager::ActorLog;
+use crate::engine::FloatPolicy;
+use crate::executor::{EpochInfo, ImplicitTipsetRet};
 use crate::externs::Externs;
-use crate::gas::{price_list_by_network_version, PriceList};
+use crate::gas::{try_price_list_by_network_version, PriceList};
 use crate::kernel::Result;
 use crate::state_tree::StateTree;
 
@@ -28,10 +37,28 @@ use self::limiter::MemoryLimiter;
 
 mod boxed;
 
-pub const REWARD_ACTOR_ID: ActorID = 2;
+pub mod signature;
+
+use self::signature::{DefaultSignatureBackend, SignatureBackend};
+
+/// The network versions at which this FVM can execute messages, each with its own price list and
+/// set of available syscalls. Older network versions must be executed by older FVM releases: this
+/// build doesn't carry their price lists or syscall semantics, so it can't replay archival chain
+/// segments from before these versions.
+#[cfg(not(feature = "nv26-dev"))]
+pub const SUPPORTED_NETWORK_VERSIONS: RangeInclusive<NetworkVersion> =
+    NetworkVersion::V21..=NetworkVersion::V25;
+#[cfg(feature = "nv26-dev")]
+pub const SUPPORTED_NETWORK_VERSIONS: RangeInclusive<NetworkVersion> =
+    NetworkVersion::V21..=NetworkVersion::V26;
+
+/// The builtin actors pinned by default (see [`NetworkConfig::pinned_actors`]).
+pub const DEFAULT_PINNED_ACTORS: &[&str] = &["system", "init", "account", "evm"];
+
+pub const REWARD_ACTOR_ID: ActorID = WellKnownActor::Reward.id();
 
 /// Distinguished Account actor that is the destination of all burnt funds.
-pub const BURNT_FUNDS_ACTOR_ID: ActorID = 99;
+pub const BURNT_FUNDS_ACTOR_ID: ActorID = WellKnownActor::BurntFunds.id();
 
 /// The Machine is the top-level object of the FVM.
 ///
@@ -81,6 +108,19 @@ pub trait Machine: 'static {
 
     /// Creates a new limiter to track the resources of a message execution.
     fn new_limiter(&self) -> Self::Limiter;
+
+    /// Starts recording the CIDs of the state blocks read from the blockstore, discarding any
+    /// previous recording. See [`MachineContext::witness`].
+    ///
+    /// Machines that can't record the blocks they read may leave this unimplemented, in which case
+    /// [`Machine::take_witness`] always returns an empty witness.
+    fn record_witness(&self) {}
+
+    /// Stops recording and returns the CIDs of the state blocks read since
+    /// [`Machine::record_witness`] was called, including those proving state served from caches.
+    fn take_witness(&self) -> Result<Vec<Cid>> {
+        Ok(Vec::new())
+    }
 }
 
 /// Network-level settings. Except when testing locally, changing any of these likely requires a
@@ -121,6 +161,19 @@ pub struct NetworkConfig {
     /// DEFAULT: 1MiB
     pub max_block_size: usize,
 
+    /// The maximum number of blocks that may be opened (including blocks created by actors,
+    /// parameters, and return values) during a single message, across all of its invocations.
+    ///
+    /// DEFAULT: 2^31-1
+    pub max_open_blocks: u32,
+
+    /// The maximum total size, in bytes, of the blocks that may be opened (including blocks created
+    /// by actors, parameters, and return values) during a single message, across all of its
+    /// invocations.
+    ///
+    /// DEFAULT: unlimited
+    pub max_open_block_bytes: u64,
+
     /// An override for builtin-actors. If specified, this should be the CID of a builtin-actors
     /// "manifest".
     ///
@@ -132,6 +185,26 @@ pub struct NetworkConfig {
     /// DEFAULT: `false`
     pub actor_debugging: bool,
 
+    /// Allow message execution to be interrupted after a wall-clock timeout (see
+    /// [`DefaultExecutor::set_execution_timeout`][crate::executor::DefaultExecutor::set_execution_timeout]).
+    /// This slows down all actor code slightly and makes execution non-deterministic, so it should
+    /// only be enabled when executing messages off-chain (e.g., for gas estimation).
+    ///
+    /// DEFAULT: `false`
+    pub execution_timeouts: bool,
+
+    /// How actor code may use floating point numbers. This is consensus-critical: it determines
+    /// which actor code can be deployed and executed.
+    ///
+    /// DEFAULT: [`FloatPolicy::Canonicalize`]
+    pub float_policy: FloatPolicy,
+
+    /// Limits on the size of actor events. The gas charged for events is determined by the
+    /// [`price_list`](Self::price_list).
+    ///
+    /// DEFAULT: [`EventLimits::default`]
+    pub event_limits: EventLimits,
+
     /// The price list.
     ///
     /// DEFAULT: The price-list for the current network version.
@@ -139,12 +212,32 @@ pub struct NetworkConfig {
 
     /// Actor redirects for debug execution
     pub actor_redirect: Vec<(Cid, Cid)>,
+
+    /// The builtin actors (by name in the manifest) whose modules are compiled and pinned when
+    /// the [`DefaultExecutor`][crate::executor::DefaultExecutor] is constructed (see
+    /// [`EnginePool::pin`][crate::engine::EnginePool::pin]). Actors missing from the manifest are
+    /// ignored. This only affects performance.
+    ///
+    /// DEFAULT: [`DEFAULT_PINNED_ACTORS`]
+    pub pinned_actors: Vec<String>,
 }
 
 impl NetworkConfig {
     /// Create a new network config for the given network version.
+    ///
+    /// Panics if the network version isn't supported (see [`NetworkConfig::try_new`]).
     pub fn new(network_version: NetworkVersion) -> Self {
-        NetworkConfig {
+        Self::try_new(network_version)
+            .unwrap_or_else(|| panic!("network version {network_version} not supported"))
+    }
+
+    /// Create a new network config for the given network version, or return `None` if this FVM
+    /// can't execute messages at that network version (see [`SUPPORTED_NETWORK_VERSIONS`]).
+    pub fn try_new(network_version: NetworkVersion) -> Option<Self> {
+        if !SUPPORTED_NETWORK_VERSIONS.contains(&network_version) {
+            return None;
+        }
+        Some(NetworkConfig {
             chain_id: ChainID::from(0u64),
             network_version,
             max_call_depth: 1024,
@@ -152,11 +245,20 @@ impl NetworkConfig {
             max_inst_memory_bytes: 512 * (1 << 20),
             max_memory_bytes: 2 * (1 << 30),
             actor_debugging: false,
+            execution_timeouts: false,
+            float_policy: FloatPolicy::default(),
+            event_limits: EventLimits::default(),
             builtin_actors_override: None,
-            price_list: price_list_by_network_version(network_version),
+            price_list: try_price_list_by_network_version(network_version)?,
             actor_redirect: vec![],
+            pinned_actors: DEFAULT_PINNED_ACTORS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
             max_block_size: 1 << 20,
-        }
+            max_open_blocks: i32::MAX as u32,
+            max_open_block_bytes: u64::MAX,
+        })
     }
 
     /// Enable actor debugging. This is a consensus-critical option (affects gas usage) so it should
@@ -166,6 +268,26 @@ impl NetworkConfig {
         self
     }
 
+    /// Allow message execution to be interrupted after a wall-clock timeout. This makes execution
+    /// non-deterministic, so it should only be enabled when executing messages off-chain.
+    pub fn enable_execution_timeouts(&mut self) -> &mut Self {
+        self.execution_timeouts = true;
+        self
+    }
+
+    /// Set how actor code may use floating point numbers.
+    pub fn float_policy(&mut self, policy: FloatPolicy) -> &mut Self {
+        self.float_policy = policy;
+        self
+    }
+
+    /// Set the limits on the size of actor events. This is consensus-critical, so the defaults
+    /// should only be changed for local testing or as a network-wide parameter.
+    pub fn event_limits(&mut self, limits: EventLimits) -> &mut Self {
+        self.event_limits = limits;
+        self
+    }
+
     /// Override actors with the specific manifest. This is primarily useful for testing, or
     /// networks prior to NV16 (where the actor's "manifest" isn't specified on-chain).
     pub fn override_actors(&mut self, manifest: Cid) -> &mut Self {
@@ -179,6 +301,12 @@ impl NetworkConfig {
         self
     }
 
+    /// Set the builtin actors whose modules are pinned (see [`NetworkConfig::pinned_actors`]).
+    pub fn pin_actors<S: Into<String>>(&mut self, names: impl IntoIterator<Item = S>) -> &mut Self {
+        self.pinned_actors = names.into_iter().map(Into::into).collect();
+        self
+    }
+
     /// Create a ['MachineContext'] for a given epoch, timestamp, and initial state.
     pub fn for_epoch(
         &self,
@@ -194,6 +322,11 @@ impl NetworkConfig {
             initial_state_root: initial_state,
             circ_supply: fvm_shared::TOTAL_FILECOIN.clone(),
             tracing: false,
+            witness: false,
+            trusted_writes: false,
+            signature_backend: Arc::new(DefaultSignatureBackend),
+            actor_log_sink: None,
+            hooks: None,
         }
     }
 
@@ -204,6 +337,86 @@ impl NetworkConfig {
     }
 }
 
+/// Limits on the size of the events an actor may emit. Events exceeding these limits are rejected
+/// with [`ErrorNumber::LimitExceeded`](fvm_shared::error::ErrorNumber::LimitExceeded), and an error
+/// message naming the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventLimits {
+    /// The maximum number of entries in a single event.
+    ///
+    /// DEFAULT: 255
+    pub max_entries: usize,
+    /// The maximum length of an entry's key, in bytes.
+    ///
+    /// DEFAULT: 31
+    pub max_key_len: usize,
+    /// The maximum total length of an event's entry values, in bytes.
+    ///
+    /// DEFAULT: 8KiB
+    pub max_total_values_len: usize,
+}
+
+impl Default for EventLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 255,
+            max_key_len: 31,
+            max_total_values_len: 8 << 10,
+        }
+    }
+}
+
+/// Receives the debug logs emitted by actors (see [`MachineContext::actor_log_sink`]).
+pub trait ActorLogSink: Debug + Send + Sync {
+    /// Receives a log emitted while applying the message with the given CID. Logs are delivered
+    /// in the order in which they were emitted, once the message has been applied, including logs
+    /// emitted by calls that were later reverted.
+    fn log(&self, message: &Cid, log: &ActorLog);
+}
+
+/// Protocol-side effects at tipset boundaries (see [`MachineContext::hooks`]), e.g., to run
+/// network-specific implicit messages or to record metrics. Hooks are called by
+/// [`Executor::apply_implicit_tipset`](crate::executor::Executor::apply_implicit_tipset), and may
+/// return implicit messages to apply at that point, which must succeed like the cron message.
+///
+/// Consensus-critical if the hooks return messages: every node must apply the same ones.
+pub trait MachineHooks: Debug + Send + Sync {
+    /// Called before the tipset's reward messages are applied. Returns implicit messages to apply
+    /// first.
+    fn on_epoch_start(&self, epoch_info: &EpochInfo) -> anyhow::Result<Vec<Message>> {
+        let _ = epoch_info;
+        Ok(Vec::new())
+    }
+
+    /// Called once cron has run, with the results so far. Returns implicit messages to apply last.
+    fn on_epoch_end(
+        &self,
+        epoch_info: &EpochInfo,
+        ret: &ImplicitTipsetRet,
+    ) -> anyhow::Result<Vec<Message>> {
+        let _ = (epoch_info, ret);
+        Ok(Vec::new())
+    }
+}
+
+/// An [`ActorLogSink`] forwarding actor logs to the host's [`log`] stream (under the `fvm::actor`
+/// target), along with their attribution.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HostLogSink;
+
+impl ActorLogSink for HostLogSink {
+    fn log(&self, message: &Cid, log: &ActorLog) {
+        use fvm_shared::sys::LogLevel;
+        let level = match log.level {
+            Some(LogLevel::Error) => log::Level::Error,
+            Some(LogLevel::Warn) => log::Level::Warn,
+            Some(LogLevel::Info) => log::Level::Info,
+            Some(LogLevel::Debug) | None => log::Level::Debug,
+        };
+        log::log!(target: "fvm::actor", level, "message {}: {}", message, log);
+    }
+}
+
 /// Per-epoch machine context.
 #[derive(Clone, Debug, Deref, DerefMut)]
 pub struct MachineContext {
@@ -240,6 +453,49 @@ pub struct MachineContext {
     /// Whether or not to produce execution traces in the returned result.
     /// Not consensus-critical, but has a performance impact.
     pub tracing: bool,
+
+    /// Whether or not to record the CIDs of the state blocks read while applying each message, and
+    /// return them in [`ApplyRet::witness`](crate::executor::ApplyRet::witness).
+    ///
+    /// Blocks already loaded into the FVM's in-memory caches by earlier messages (e.g., actors
+    /// loaded from the state tree) aren't read again and so won't be recorded. To get a complete
+    /// witness for a message, apply it on a fresh machine. The root of the state tree, which is
+    /// loaded when the machine is constructed, is never included.
+    ///
+    /// Not consensus-critical, but has a performance impact.
+    pub witness: bool,
+
+    /// Whether the blocks flushed from the machine's write buffer are trusted to match their CIDs,
+    /// letting the underlying blockstore skip re-hashing them (see
+    /// [`Blockstore::put_many_keyed_unchecked`]). This is meant for state migrations and snapshot
+    /// tooling, which write large numbers of known-good blocks.
+    ///
+    /// Never enable this for consensus execution: the executor refuses to apply any message
+    /// (implicit messages included) while it's enabled.
+    ///
+    /// Default: false.
+    pub trusted_writes: bool,
+
+    /// The implementation of the signature operations exposed to actors.
+    ///
+    /// Consensus-critical: a custom backend must behave exactly like the default one (see
+    /// [`signature::test_vectors::check`]).
+    ///
+    /// Default: [`DefaultSignatureBackend`].
+    pub signature_backend: Arc<dyn SignatureBackend>,
+
+    /// If set, receives the debug logs emitted by actors (when
+    /// [`NetworkConfig::actor_debugging`] is enabled). Otherwise, they're returned in
+    /// [`ApplyRet::logs`](crate::executor::ApplyRet::logs).
+    ///
+    /// Default: `None`.
+    pub actor_log_sink: Option<Arc<dyn ActorLogSink>>,
+
+    /// If set, called at tipset boundaries when applying implicit messages with
+    /// [`Executor::apply_implicit_tipset`](crate::executor::Executor::apply_implicit_tipset).
+    ///
+    /// Default: `None`.
+    pub hooks: Option<Arc<dyn MachineHooks>>,
 }
 
 impl MachineContext {
@@ -266,4 +522,89 @@ impl MachineContext {
         self.tracing = true;
         self
     }
+
+    /// Enable witness generation. [`MachineContext::witness`].
+    pub fn enable_witness(&mut self) -> &mut Self {
+        self.witness = true;
+        self
+    }
+
+    /// Enable trusted blockstore writes. See [`MachineContext::trusted_writes`].
+    pub fn enable_trusted_writes(&mut self) -> &mut Self {
+        self.trusted_writes = true;
+        self
+    }
+
+    /// Set [`MachineContext::signature_backend`].
+    pub fn set_signature_backend(&mut self, backend: impl SignatureBackend + 'static) -> &mut Self {
+        self.signature_backend = Arc::new(backend);
+        self
+    }
+
+    /// Set [`MachineContext::actor_log_sink`].
+    pub fn set_actor_log_sink(&mut self, sink: impl ActorLogSink + 'static) -> &mut Self {
+        self.actor_log_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Set [`MachineContext::hooks`].
+    pub fn set_hooks(&mut self, hooks: impl MachineHooks + 'static) -> &mut Self {
+        self.hooks = Some(Arc::new(hooks));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct CollectingSink(std::sync::Mutex<Vec<String>>);
+
+    impl ActorLogSink for CollectingSink {
+        fn log(&self, message: &Cid, log: &ActorLog) {
+            self.0.lock().unwrap().push(format!("{message}: {log}"));
+        }
+    }
+
+    #[test]
+    fn actor_log_sink() {
+        let mut mc = NetworkConfig::new(NetworkVersion::V21).for_epoch(10, 0, Cid::default());
+        assert!(mc.actor_log_sink.is_none());
+        mc.set_actor_log_sink(CollectingSink::default());
+
+        let log = ActorLog {
+            actor: 1000,
+            depth: 1,
+            method: 2,
+            level: Some(fvm_shared::sys::LogLevel::Info),
+            message: "transfer".into(),
+            fields: vec![("amount".into(), "10".into())],
+        };
+        let sink = mc.actor_log_sink.as_ref().unwrap();
+        sink.log(&Cid::default(), &log);
+        // Also exercise the host sink, which must not panic without a logger.
+        HostLogSink.log(&Cid::default(), &log);
+
+        let sink = format!("{sink:?}");
+        assert!(
+            sink.contains("actor 1000 (depth 1, method 2): transfer amount=10"),
+            "{sink}"
+        );
+    }
+
+    #[test]
+    fn supported_network_versions() {
+        for nv in 0..=30u32 {
+            let nv = NetworkVersion::from(nv);
+            let nc = NetworkConfig::try_new(nv);
+            assert_eq!(nc.is_some(), SUPPORTED_NETWORK_VERSIONS.contains(&nv));
+            assert_eq!(
+                nc.map(|nc| nc.price_list as *const PriceList),
+                try_price_list_by_network_version(nv).map(|p| p as *const PriceList)
+            );
+        }
+    }
 }
@@ -0,0 +1,288 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Pluggable signature verification.
+//!
+//! The kernel verifies signatures through the [`SignatureBackend`] configured in the
+//! [`MachineContext`](super::MachineContext), which defaults to [`DefaultSignatureBackend`] (the
+//! implementations bundled with `fvm_shared`). Embedders may supply their own backend, e.g., to use
+//! hardware acceleration. Signature verification is consensus-critical, so a backend must behave
+//! _exactly_ like the default one; [`test_vectors::check`] helps verify this.
+
+use std::fmt::Debug;
+
+use fvm_shared::address::Address;
+use fvm_shared::crypto::signature::{
+    self, SignatureType, BLS_PUB_LEN, BLS_SIG_LEN, SECP_PUB_LEN, SECP_SIG_LEN,
+    SECP_SIG_MESSAGE_HASH_SIZE,
+};
+
+/// Implementations of the signature operations exposed to actors. Every method defaults to the
+/// bundled implementation, so backends only need to override the operations they accelerate.
+pub trait SignatureBackend: Debug + Send + Sync {
+    /// Returns true if `signature` is a valid signature of the given type over `plaintext` by
+    /// `signer`, a secp256k1 or BLS key address.
+    fn verify_signature(
+        &self,
+        sig_type: SignatureType,
+        signature: &[u8],
+        plaintext: &[u8],
+        signer: &Address,
+    ) -> bool {
+        signature::verify(sig_type, signature, plaintext, signer).is_ok()
+    }
+
+    /// Returns true if `aggregate_sig` is a valid aggregate BLS signature over the plaintexts, each
+    /// signed by the public key at the same position. Invalid signatures and public keys are
+    /// rejected, and an empty aggregate is valid.
+    fn verify_bls_aggregate(
+        &self,
+        aggregate_sig: &[u8; BLS_SIG_LEN],
+        pub_keys: &[[u8; BLS_PUB_LEN]],
+        plaintexts: &[&[u8]],
+    ) -> bool {
+        signature::ops::verify_bls_aggregate(aggregate_sig, pub_keys, plaintexts).unwrap_or(false)
+    }
+
+    /// Recovers the (uncompressed) public key that produced the secp256k1 `signature` over `hash`.
+    fn recover_secp_public_key(
+        &self,
+        hash: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+        signature: &[u8; SECP_SIG_LEN],
+    ) -> Result<[u8; SECP_PUB_LEN], signature::Error> {
+        signature::ops::recover_secp_public_key(hash, signature).map(|key| key.serialize())
+    }
+}
+
+/// The signature verification bundled with `fvm_shared`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultSignatureBackend;
+
+impl SignatureBackend for DefaultSignatureBackend {}
+
+/// Test vectors shared by all signature backends.
+pub mod test_vectors {
+    use multihash_codetable::MultihashDigest;
+
+    use super::*;
+    use crate::kernel::SupportedHashes;
+
+    const PLAINTEXT_1: &[u8] = b"fvm signature test vector 1";
+    const PLAINTEXT_2: &[u8] = b"fvm signature test vector 2";
+
+    /// The public key of the secp256k1 secret key `[0x11; 32]`.
+    const SECP_PUB: &str = "044f355bdcb7cc0af728ef3cceb9615d90684bb5b2ca5f859ab0f0b704075871aa385b6b1b8ead809ca67454d9683fcf2ba03456d6fe2c4abe2b07f0fbdbb2f1c1";
+    /// The secp256k1 signature of the blake2b-256 hash of `PLAINTEXT_1`.
+    const SECP_SIG: &str = "70a76af0b7525b531c0b04f976f84b6732f078ffa4eef219bd5272125dc5ec65283298880e935eada03caa45b5eb2e7757e28a9f2ef54ada3dd98c99facb025700";
+    const BLS_PUB_1: &str = "b62661801d595c2a34c81a5fae07db9c94c39153d712adb92531a45608ba5f5de18b44f24db8daa16dfad9a80b1ce789";
+    const BLS_PUB_2: &str = "89fc9508674cfb318cc6c212a0ce57bb136ed6b673cc9a48a2e33ed8abaf2901c029d96d1408ba1943f533dafe4a46e1";
+    /// The BLS signature of `PLAINTEXT_1` by the key of `BLS_PUB_1`.
+    const BLS_SIG_1: &str = "ab8b6e9f078ce0aafd9253f01e741cc3c762c972e537d13a137d92eb4113699bcb528baf4421353074b580355c5dc8630f1504aa95e1d6889ba105cd4f65b40a1d59b67b87b2386bb642d50dc0fb0d22c410725a62f8531df6c2d7544ac66a68";
+    /// The aggregate of `BLS_SIG_1` and the signature of `PLAINTEXT_2` by the key of `BLS_PUB_2`.
+    const BLS_AGG: &str = "b52547175d779a499cccf6547be111c660772c4e1378ec204b381593d6ffddccee421054d53221844a0ec70fadcf48f70127d27837e0d6491368b82079704b970b50977d8cd61679060da19004e3a64d4f560a4a95cb3a5b6de3d71928cf90de";
+
+    fn unhex<const N: usize>(s: &str) -> [u8; N] {
+        let bytes: Vec<u8> = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("invalid hex"))
+            .collect();
+        bytes.try_into().expect("wrong length")
+    }
+
+    fn corrupt<const N: usize>(mut bytes: [u8; N], i: usize) -> [u8; N] {
+        bytes[i] ^= 1;
+        bytes
+    }
+
+    /// Checks that `backend` agrees with the expected results of the shared test vectors, returning
+    /// the name of the first vector it disagrees on.
+    pub fn check(backend: &dyn SignatureBackend) -> Result<(), String> {
+        let secp_pub: [u8; SECP_PUB_LEN] = unhex(SECP_PUB);
+        let secp_sig: [u8; SECP_SIG_LEN] = unhex(SECP_SIG);
+        let secp_addr = Address::new_secp256k1(&secp_pub).unwrap();
+        let bls_pub_1: [u8; BLS_PUB_LEN] = unhex(BLS_PUB_1);
+        let bls_pub_2: [u8; BLS_PUB_LEN] = unhex(BLS_PUB_2);
+        let bls_sig_1: [u8; BLS_SIG_LEN] = unhex(BLS_SIG_1);
+        let bls_agg: [u8; BLS_SIG_LEN] = unhex(BLS_AGG);
+        let bls_addr_1 = Address::new_bls(&bls_pub_1).unwrap();
+        let bls_addr_2 = Address::new_bls(&bls_pub_2).unwrap();
+        let hash: [u8; SECP_SIG_MESSAGE_HASH_SIZE] = SupportedHashes::Blake2b256
+            .digest(PLAINTEXT_1)
+            .digest()
+            .try_into()
+            .unwrap();
+
+        let verify = |sig_type, sig: &[u8], plaintext, signer| {
+            backend.verify_signature(sig_type, sig, plaintext, signer)
+        };
+        let cases: Vec<(&str, bool, bool)> = vec![
+            (
+                "secp256k1 valid",
+                verify(SignatureType::Secp256k1, &secp_sig, PLAINTEXT_1, &secp_addr),
+                true,
+            ),
+            (
+                "secp256k1 wrong plaintext",
+                verify(SignatureType::Secp256k1, &secp_sig, PLAINTEXT_2, &secp_addr),
+                false,
+            ),
+            (
+                "secp256k1 corrupted signature",
+                verify(
+                    SignatureType::Secp256k1,
+                    &corrupt(secp_sig, 10),
+                    PLAINTEXT_1,
+                    &secp_addr,
+                ),
+                false,
+            ),
+            (
+                "secp256k1 truncated signature",
+                verify(
+                    SignatureType::Secp256k1,
+                    &secp_sig[..64],
+                    PLAINTEXT_1,
+                    &secp_addr,
+                ),
+                false,
+            ),
+            (
+                "secp256k1 signature against a BLS address",
+                verify(
+                    SignatureType::Secp256k1,
+                    &secp_sig,
+                    PLAINTEXT_1,
+                    &bls_addr_1,
+                ),
+                false,
+            ),
+            (
+                "BLS valid",
+                verify(SignatureType::BLS, &bls_sig_1, PLAINTEXT_1, &bls_addr_1),
+                true,
+            ),
+            (
+                "BLS wrong plaintext",
+                verify(SignatureType::BLS, &bls_sig_1, PLAINTEXT_2, &bls_addr_1),
+                false,
+            ),
+            (
+                "BLS wrong signer",
+                verify(SignatureType::BLS, &bls_sig_1, PLAINTEXT_1, &bls_addr_2),
+                false,
+            ),
+            (
+                "BLS invalid signature",
+                verify(
+                    SignatureType::BLS,
+                    &[0; BLS_SIG_LEN],
+                    PLAINTEXT_1,
+                    &bls_addr_1,
+                ),
+                false,
+            ),
+            (
+                "BLS aggregate valid",
+                backend.verify_bls_aggregate(
+                    &bls_agg,
+                    &[bls_pub_1, bls_pub_2],
+                    &[PLAINTEXT_1, PLAINTEXT_2],
+                ),
+                true,
+            ),
+            (
+                "BLS aggregate swapped plaintexts",
+                backend.verify_bls_aggregate(
+                    &bls_agg,
+                    &[bls_pub_1, bls_pub_2],
+                    &[PLAINTEXT_2, PLAINTEXT_1],
+                ),
+                false,
+            ),
+            (
+                "BLS aggregate missing signer",
+                backend.verify_bls_aggregate(&bls_agg, &[bls_pub_1], &[PLAINTEXT_1]),
+                false,
+            ),
+            (
+                "BLS aggregate of one",
+                backend.verify_bls_aggregate(&bls_sig_1, &[bls_pub_1], &[PLAINTEXT_1]),
+                true,
+            ),
+            (
+                "BLS aggregate empty",
+                backend.verify_bls_aggregate(&bls_agg, &[], &[]),
+                true,
+            ),
+            (
+                "BLS aggregate invalid public key",
+                backend.verify_bls_aggregate(&bls_agg, &[[0; BLS_PUB_LEN]], &[PLAINTEXT_1]),
+                false,
+            ),
+            (
+                "secp256k1 recovery",
+                backend.recover_secp_public_key(&hash, &secp_sig) == Ok(secp_pub),
+                true,
+            ),
+            (
+                "secp256k1 recovery of another hash",
+                backend.recover_secp_public_key(&corrupt(hash, 0), &secp_sig) == Ok(secp_pub),
+                false,
+            ),
+            (
+                "secp256k1 recovery with an invalid recovery ID",
+                backend
+                    .recover_secp_public_key(&hash, &{
+                        let mut sig = secp_sig;
+                        sig[64] = 4;
+                        sig
+                    })
+                    .is_ok(),
+                false,
+            ),
+        ];
+
+        match cases
+            .into_iter()
+            .find(|(_, actual, expected)| actual != expected)
+        {
+            Some((name, actual, expected)) => Err(format!(
+                "test vector '{name}' failed: expected {expected}, got {actual}"
+            )),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn default_backend() {
+            check(&DefaultSignatureBackend).unwrap();
+        }
+
+        #[test]
+        fn detects_divergence() {
+            #[derive(Debug)]
+            struct Permissive;
+            impl SignatureBackend for Permissive {
+                fn verify_signature(
+                    &self,
+                    _: SignatureType,
+                    _: &[u8],
+                    _: &[u8],
+                    _: &Address,
+                ) -> bool {
+                    true
+                }
+            }
+            assert_eq!(
+                check(&Permissive),
+                Err(
+                    "test vector 'secp256k1 wrong plaintext' failed: expected false, got true"
+                        .into()
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! This module reads the network totals recorded in the storage power actor's state, for the
+//! `network::summary` syscall. It does not contain the logic of the power actor: that lives
+//! on-chain as a WASM actor.
+//!
+//! ## Version compatibility
+//!
+//! Only the leading fields of the state are decoded, and any later fields are skipped. Their
+//! layout hasn't changed since the first FVM network version (nv16).
+
+use std::fmt;
+
+use fvm_ipld_encoding::de::{self, Deserialize, Deserializer, IgnoredAny, SeqAccess, Visitor};
+use fvm_shared::bigint::bigint_ser::BigIntDe;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::sector::StoragePower;
+use fvm_shared::well_known::WellKnownActor;
+use fvm_shared::ActorID;
+
+pub const POWER_ACTOR_ID: ActorID = WellKnownActor::StoragePower.id();
+
+/// The network totals recorded in the power actor's state, as of the last cron tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub total_raw_byte_power: StoragePower,
+    pub total_quality_adj_power: StoragePower,
+    pub total_pledge_collateral: TokenAmount,
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StateVisitor;
+
+        impl<'de> Visitor<'de> for StateVisitor {
+            type Value = State;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("the storage power actor's state")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<State, A::Error> {
+                let missing = || de::Error::custom("truncated storage power actor state");
+                let BigIntDe(total_raw_byte_power) = seq.next_element()?.ok_or_else(missing)?;
+                let _total_bytes_committed: IgnoredAny = seq.next_element()?.ok_or_else(missing)?;
+                let BigIntDe(total_quality_adj_power) = seq.next_element()?.ok_or_else(missing)?;
+                let _total_qa_bytes_committed: IgnoredAny =
+                    seq.next_element()?.ok_or_else(missing)?;
+                let total_pledge_collateral = seq.next_element()?.ok_or_else(missing)?;
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(State {
+                    total_raw_byte_power,
+                    total_quality_adj_power,
+                    total_pledge_collateral,
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(StateVisitor)
+    }
+}
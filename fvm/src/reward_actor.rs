@@ -0,0 +1,54 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! This module reads the current block reward recorded in the reward actor's state, for the
+//! `network::summary` syscall. It does not contain the logic of the reward actor: that lives
+//! on-chain as a WASM actor.
+//!
+//! ## Version compatibility
+//!
+//! Only the leading fields of the state are decoded, and any later fields are skipped. Their
+//! layout hasn't changed since the first FVM network version (nv16).
+
+use std::fmt;
+
+use fvm_ipld_encoding::de::{self, Deserialize, Deserializer, IgnoredAny, SeqAccess, Visitor};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::well_known::WellKnownActor;
+use fvm_shared::ActorID;
+
+pub const REWARD_ACTOR_ID: ActorID = WellKnownActor::Reward.id();
+
+/// The block reward recorded in the reward actor's state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// The reward paid out to the blocks of the current epoch.
+    pub this_epoch_reward: TokenAmount,
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StateVisitor;
+
+        impl<'de> Visitor<'de> for StateVisitor {
+            type Value = State;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("the reward actor's state")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<State, A::Error> {
+                let missing = || de::Error::custom("truncated reward actor state");
+                // Skip the cumulative baseline and realized power, the effective network time,
+                // and the effective baseline power.
+                for _ in 0..4 {
+                    let _: IgnoredAny = seq.next_element()?.ok_or_else(missing)?;
+                }
+                let this_epoch_reward = seq.next_element()?.ok_or_else(missing)?;
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(State { this_epoch_reward })
+            }
+        }
+
+        deserializer.deserialize_seq(StateVisitor)
+    }
+}
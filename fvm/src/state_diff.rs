@@ -0,0 +1,403 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Human-readable diffs between two state trees.
+//!
+//! This module is intended for debugging and testing (e.g., explaining state-root mismatches in
+//! test vectors). It walks both state trees in full, so it should not be used on large (e.g.,
+//! mainnet) state trees.
+//!
+//! For the built-in actors the FVM knows about (system, init, and account), the actor states are
+//! decoded and diffed field by field. For other built-in actors, the top-level fields of the
+//! state object are diffed by index. User actors are only diffed at the state-root level.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::Context as _;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::ActorID;
+use ipld_core::ipld::Ipld;
+
+use crate::kernel::Context as _;
+use crate::machine::Manifest;
+use crate::state_tree::{ActorState, StateTree};
+use crate::system_actor::{self, SYSTEM_ACTOR_ID};
+
+/// The difference between two state trees, keyed by actor ID.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StateDiff {
+    pub actors: BTreeMap<ActorID, ActorDiff>,
+}
+
+/// The difference in a single actor between two state trees.
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum ActorDiff {
+    /// The actor only exists in the "after" state tree.
+    Created(ActorState),
+    /// The actor only exists in the "before" state tree.
+    Deleted(ActorState),
+    /// The actor exists in both state trees, but differs.
+    Modified {
+        before: ActorState,
+        after: ActorState,
+        /// Field-level differences between the actor's states. Empty if the state root didn't
+        /// change, or if the actor's state couldn't be decoded.
+        fields: Vec<FieldDiff>,
+    },
+}
+
+/// The difference in a single top-level field of an actor's state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldDiff {
+    /// The field's name, or its index if the name is unknown.
+    pub field: String,
+    /// The field's value before, if present.
+    pub before: Option<Ipld>,
+    /// The field's value after, if present.
+    pub after: Option<Ipld>,
+}
+
+impl StateDiff {
+    /// Computes the difference between the state trees rooted at `before` and `after`. Both state
+    /// trees (and the states of any modified built-in actors) must be present in the blockstore.
+    pub fn compute<B: Blockstore>(bs: &B, before: &Cid, after: &Cid) -> anyhow::Result<Self> {
+        if before == after {
+            return Ok(Self::default());
+        }
+
+        let before_st =
+            StateTree::new_from_root(bs, before).context("failed to load 'before' state tree")?;
+        let after_st =
+            StateTree::new_from_root(bs, after).context("failed to load 'after' state tree")?;
+
+        // Try to find the builtin actor manifest so we know which actors we can decode.
+        let manifest = load_manifest(&after_st).or_else(|| load_manifest(&before_st));
+
+        let mut before_actors = collect_actors(&before_st)?;
+        let mut actors = BTreeMap::new();
+        for (id, after) in collect_actors(&after_st)? {
+            let diff = match before_actors.remove(&id) {
+                None => ActorDiff::Created(after),
+                Some(before) if before == after => continue,
+                Some(before) => {
+                    let fields = diff_fields(bs, manifest.as_ref(), &before, &after)
+                        .with_context(|| format!("failed to diff state of actor {}", id))?;
+                    ActorDiff::Modified {
+                        before,
+                        after,
+                        fields,
+                    }
+                }
+            };
+            actors.insert(id, diff);
+        }
+        actors.extend(
+            before_actors
+                .into_iter()
+                .map(|(id, before)| (id, ActorDiff::Deleted(before))),
+        );
+
+        Ok(Self { actors })
+    }
+
+    /// Returns true if the state trees are identical.
+    pub fn is_empty(&self) -> bool {
+        self.actors.is_empty()
+    }
+}
+
+impl ActorDiff {
+    /// The actor's state before, if it existed.
+    pub fn before(&self) -> Option<&ActorState> {
+        match self {
+            ActorDiff::Created(_) => None,
+            ActorDiff::Deleted(before) | ActorDiff::Modified { before, .. } => Some(before),
+        }
+    }
+
+    /// The actor's state after, if it exists.
+    pub fn after(&self) -> Option<&ActorState> {
+        match self {
+            ActorDiff::Deleted(_) => None,
+            ActorDiff::Created(after) | ActorDiff::Modified { after, .. } => Some(after),
+        }
+    }
+
+    /// The change in the actor's balance. A missing actor is treated as having a zero balance.
+    pub fn balance_delta(&self) -> TokenAmount {
+        let balance = |a: Option<&ActorState>| a.map(|a| a.balance.clone()).unwrap_or_default();
+        balance(self.after()) - balance(self.before())
+    }
+
+    /// The change in the actor's nonce. A missing actor is treated as having a zero nonce.
+    pub fn nonce_delta(&self) -> i128 {
+        let nonce = |a: Option<&ActorState>| a.map(|a| a.sequence as i128).unwrap_or_default();
+        nonce(self.after()) - nonce(self.before())
+    }
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for (id, diff) in &self.actors {
+            write!(f, "actor {}: {}", id, diff)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ActorDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActorDiff::Created(after) => {
+                writeln!(f, "created")?;
+                writeln!(f, "  code: {}", after.code)?;
+                writeln!(f, "  state: {}", after.state)?;
+                writeln!(f, "  nonce: {}", after.sequence)?;
+                writeln!(f, "  balance: {}", after.balance)?;
+                if let Some(addr) = &after.delegated_address {
+                    writeln!(f, "  delegated address: {}", addr)?;
+                }
+                Ok(())
+            }
+            ActorDiff::Deleted(before) => {
+                writeln!(f, "deleted")?;
+                writeln!(f, "  nonce: {}", before.sequence)?;
+                writeln!(f, "  balance: {}", before.balance)
+            }
+            ActorDiff::Modified {
+                before,
+                after,
+                fields,
+            } => {
+                writeln!(f, "modified")?;
+                if before.code != after.code {
+                    writeln!(f, "  code: {} -> {}", before.code, after.code)?;
+                }
+                if before.sequence != after.sequence {
+                    writeln!(
+                        f,
+                        "  nonce: {} -> {} ({:+})",
+                        before.sequence,
+                        after.sequence,
+                        self.nonce_delta()
+                    )?;
+                }
+                if before.balance != after.balance {
+                    let delta = self.balance_delta();
+                    let sign = if delta.is_positive() { "+" } else { "" };
+                    writeln!(
+                        f,
+                        "  balance: {} -> {} ({}{})",
+                        before.balance, after.balance, sign, delta
+                    )?;
+                }
+                if before.delegated_address != after.delegated_address {
+                    writeln!(
+                        f,
+                        "  delegated address: {:?} -> {:?}",
+                        before.delegated_address, after.delegated_address
+                    )?;
+                }
+                if before.state != after.state {
+                    writeln!(f, "  state: {} -> {}", before.state, after.state)?;
+                    for field in fields {
+                        writeln!(f, "    {}", field)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: &Option<Ipld>| match v {
+            Some(v) => format!("{:?}", v),
+            None => "<missing>".into(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.field,
+            show(&self.before),
+            show(&self.after)
+        )
+    }
+}
+
+/// Loads the builtin actor manifest referenced by the system actor, if possible.
+fn load_manifest<B: Blockstore>(st: &StateTree<B>) -> Option<Manifest> {
+    let actor = st.get_actor(SYSTEM_ACTOR_ID).ok()??;
+    let state: system_actor::State = st.store().get_cbor(&actor.state).ok()??;
+    Manifest::load(st.store(), &state.builtin_actors, 1).ok()
+}
+
+fn collect_actors<B: Blockstore>(
+    st: &StateTree<B>,
+) -> anyhow::Result<BTreeMap<ActorID, ActorState>> {
+    let mut actors = BTreeMap::new();
+    st.for_each(|addr, actor| {
+        let id = addr.id().context("state tree contains a non-ID address")?;
+        actors.insert(id, actor.clone());
+        Ok(())
+    })?;
+    Ok(actors)
+}
+
+/// Returns the names of the top-level state fields of the builtin actors the FVM knows about.
+fn known_fields(manifest: &Manifest, code: &Cid) -> Option<&'static [&'static str]> {
+    if code == manifest.get_system_code() {
+        Some(&["builtin_actors"])
+    } else if code == manifest.get_init_code() {
+        #[cfg(feature = "m2-native")]
+        const INIT_FIELDS: &[&str] =
+            &["address_map", "next_id", "network_name", "installed_actors"];
+        #[cfg(not(feature = "m2-native"))]
+        const INIT_FIELDS: &[&str] = &["address_map", "next_id", "network_name"];
+        Some(INIT_FIELDS)
+    } else if manifest.is_account_actor(code) {
+        Some(&["address"])
+    } else {
+        None
+    }
+}
+
+/// Diffs the top-level fields of a builtin actor's state. Returns an empty diff if the state didn't
+/// change, the actor isn't a builtin actor, or the actor's code changed.
+fn diff_fields<B: Blockstore>(
+    bs: &B,
+    manifest: Option<&Manifest>,
+    before: &ActorState,
+    after: &ActorState,
+) -> anyhow::Result<Vec<FieldDiff>> {
+    let manifest = match manifest {
+        Some(m) if before.state != after.state && before.code == after.code => m,
+        _ => return Ok(Vec::new()),
+    };
+    if manifest.id_by_code(&after.code) == 0 {
+        return Ok(Vec::new());
+    }
+
+    let load = |c: &Cid| -> anyhow::Result<Vec<Ipld>> {
+        match bs.get_cbor(c)? {
+            Some(Ipld::List(fields)) => Ok(fields),
+            Some(_) => Ok(Vec::new()),
+            None => Err(anyhow::anyhow!("actor state {} not found", c)),
+        }
+    };
+    let before_fields = load(&before.state)?;
+    let after_fields = load(&after.state)?;
+    let names = known_fields(manifest, &after.code).unwrap_or_default();
+
+    let len = before_fields.len().max(after_fields.len());
+    Ok((0..len)
+        .filter_map(|i| {
+            let (b, a) = (before_fields.get(i), after_fields.get(i));
+            (b != a).then(|| FieldDiff {
+                field: names
+                    .get(i)
+                    .map(|n| n.to_string())
+                    .unwrap_or_else(|| i.to_string()),
+                before: b.cloned(),
+                after: a.cloned(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::address::Address;
+    use fvm_shared::state::StateTreeVersion;
+    use multihash_codetable::Code;
+
+    use super::*;
+    use crate::account_actor;
+
+    #[test]
+    fn test_state_diff() {
+        let bs = MemoryBlockstore::default();
+        let manifest_cid = bs
+            .put_cbor(&Manifest::DUMMY_CODES, Code::Blake2b256)
+            .unwrap();
+        let manifest = Manifest::dummy();
+
+        let mut st = StateTree::new(&bs, StateTreeVersion::V5).unwrap();
+        let system_state = bs
+            .put_cbor(
+                &system_actor::State {
+                    builtin_actors: manifest_cid,
+                },
+                Code::Blake2b256,
+            )
+            .unwrap();
+        st.set_actor(
+            SYSTEM_ACTOR_ID,
+            ActorState::new_empty(*manifest.get_system_code(), None),
+        );
+        st.mutate_actor(SYSTEM_ACTOR_ID, |a| {
+            a.state = system_state;
+            Ok(())
+        })
+        .unwrap();
+
+        let account_state = |addr| {
+            bs.put_cbor(&account_actor::State { address: addr }, Code::Blake2b256)
+                .unwrap()
+        };
+        let mut account = ActorState::new(
+            *manifest.get_account_code(),
+            account_state(Address::new_secp256k1(&[1; 65]).unwrap()),
+            TokenAmount::from_whole(100),
+            0,
+            None,
+        );
+        st.set_actor(100, account.clone());
+        st.set_actor(101, ActorState::new_empty(Cid::default(), None));
+        let before = st.flush().unwrap();
+
+        assert!(StateDiff::compute(&bs, &before, &before)
+            .unwrap()
+            .is_empty());
+
+        account.balance = TokenAmount::from_whole(40);
+        account.sequence = 1;
+        account.state = account_state(Address::new_secp256k1(&[2; 65]).unwrap());
+        st.set_actor(100, account.clone());
+        st.delete_actor(101);
+        st.set_actor(102, ActorState::new_empty(Cid::default(), None));
+        let after = st.flush().unwrap();
+
+        let diff = StateDiff::compute(&bs, &before, &after).unwrap();
+        assert_eq!(diff.actors.len(), 3);
+        assert!(matches!(diff.actors[&101], ActorDiff::Deleted(_)));
+        assert!(matches!(diff.actors[&102], ActorDiff::Created(_)));
+
+        let modified = &diff.actors[&100];
+        assert_eq!(modified.balance_delta(), TokenAmount::from_whole(-60));
+        assert_eq!(modified.nonce_delta(), 1);
+        match modified {
+            ActorDiff::Modified { fields, .. } => {
+                assert_eq!(fields.len(), 1);
+                assert_eq!(fields[0].field, "address");
+            }
+            _ => panic!("expected a modified actor"),
+        }
+
+        let rendered = diff.to_string();
+        assert!(rendered.contains("actor 100: modified"));
+        assert!(rendered.contains("nonce: 0 -> 1 (+1)"));
+        assert!(rendered.contains("balance: 100.0 -> 40.0 (-60.0)"));
+        assert!(rendered.contains("actor 101: deleted"));
+        assert!(rendered.contains("actor 102: created"));
+    }
+}
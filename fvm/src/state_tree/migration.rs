@@ -0,0 +1,306 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Migration of state trees from older versions to the current one.
+//!
+//! State trees V3 and V4 share the layout of the current (V5) tree, a HAMT of actors with a bit
+//! width of 5 wrapped in a versioned [`StateRoot`], but their actors have no delegated address. The
+//! migration rewrites each actor in the current layout, without a delegated address, and verifies
+//! that the resulting tree holds exactly the same actors. It only migrates the state tree itself:
+//! the states of the (builtin) actors are left as they are.
+//!
+//! Older trees (V0 to V2) use a different HAMT format, and can't be migrated.
+
+use anyhow::{anyhow, bail, Context as _};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::CborStore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::state::{StateInfo0, StateRoot};
+use fvm_shared::HAMT_BIT_WIDTH;
+use multihash_codetable::Code;
+
+use super::{ActorState, StateTreeVersion};
+
+/// The number of actors between two progress reports.
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+/// The layout of actors in V3 and V4 state trees.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq, Eq)]
+struct ActorStateV4 {
+    code: Cid,
+    state: Cid,
+    sequence: u64,
+    balance: TokenAmount,
+}
+
+impl From<ActorStateV4> for ActorState {
+    fn from(actor: ActorStateV4) -> Self {
+        ActorState::new(actor.code, actor.state, actor.balance, actor.sequence, None)
+    }
+}
+
+/// The progress of a state tree migration, reported every few thousand actors and at the end of
+/// each phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationProgress {
+    /// This many actors have been rewritten so far.
+    Migrated(u64),
+    /// This many actors of the migrated tree have been verified so far.
+    Verified(u64),
+}
+
+/// Migrates the state tree at `root` to the current state tree version (V5), verifies the result
+/// (see [`verify_migration`]), and returns its root. Trees already at the current version are
+/// returned as they are.
+pub fn migrate_state_tree<S: Blockstore>(
+    store: &S,
+    root: &Cid,
+    mut progress: impl FnMut(MigrationProgress),
+) -> anyhow::Result<Cid> {
+    let state_root = load_root(store, root)?;
+    match state_root.version {
+        StateTreeVersion::V5 => return Ok(*root),
+        StateTreeVersion::V3 | StateTreeVersion::V4 => {}
+        version => bail!("cannot migrate state tree version {version:?}"),
+    }
+
+    let old =
+        Hamt::<_, ActorStateV4>::load_with_bit_width(&state_root.actors, store, HAMT_BIT_WIDTH)
+            .context("failed to load state tree")?;
+    let mut new = Hamt::<_, ActorState>::new_with_bit_width(store, HAMT_BIT_WIDTH);
+
+    let mut count = 0;
+    for entry in &old {
+        let (key, actor) = entry.context("failed to read state tree")?;
+        new.set(key.clone(), actor.clone().into())
+            .context("failed to write migrated actor")?;
+        count += 1;
+        if count % PROGRESS_INTERVAL == 0 {
+            progress(MigrationProgress::Migrated(count));
+        }
+    }
+    progress(MigrationProgress::Migrated(count));
+
+    let actors = new.flush().context("failed to flush migrated state tree")?;
+    let info = store
+        .put_cbor(&StateInfo0::default(), Code::Blake2b256)
+        .context("failed to put state info")?;
+    let new_root = store
+        .put_cbor(
+            &StateRoot {
+                version: StateTreeVersion::V5,
+                actors,
+                info,
+            },
+            Code::Blake2b256,
+        )
+        .context("failed to put state root")?;
+
+    verify_migration(store, root, &new_root, progress)?;
+    Ok(new_root)
+}
+
+/// Verifies that the V5 state tree at `new_root` holds exactly the actors of the V3 or V4 state
+/// tree at `old_root`, without delegated addresses. Returns the number of actors.
+pub fn verify_migration<S: Blockstore>(
+    store: &S,
+    old_root: &Cid,
+    new_root: &Cid,
+    mut progress: impl FnMut(MigrationProgress),
+) -> anyhow::Result<u64> {
+    let old_root = load_root(store, old_root)?;
+    if !matches!(
+        old_root.version,
+        StateTreeVersion::V3 | StateTreeVersion::V4
+    ) {
+        bail!(
+            "expected a V3 or V4 state tree, found version {:?}",
+            old_root.version
+        );
+    }
+    let new_root = load_root(store, new_root)?;
+    if new_root.version != StateTreeVersion::V5 {
+        bail!(
+            "expected a V5 state tree, found version {:?}",
+            new_root.version
+        );
+    }
+
+    let old = Hamt::<_, ActorStateV4>::load_with_bit_width(&old_root.actors, store, HAMT_BIT_WIDTH)
+        .context("failed to load original state tree")?;
+    let new = Hamt::<_, ActorState>::load_with_bit_width(&new_root.actors, store, HAMT_BIT_WIDTH)
+        .context("failed to load migrated state tree")?;
+
+    let mut count = 0;
+    for entry in &old {
+        let (key, actor) = entry.context("failed to read original state tree")?;
+        let migrated = new
+            .get(key)
+            .context("failed to read migrated state tree")?
+            .ok_or_else(|| anyhow!("actor {} missing from migrated state tree", address(key)))?;
+        if *migrated != ActorState::from(actor.clone()) {
+            bail!("actor {} differs in migrated state tree", address(key));
+        }
+        count += 1;
+        if count % PROGRESS_INTERVAL == 0 {
+            progress(MigrationProgress::Verified(count));
+        }
+    }
+
+    // Every actor of the original tree is in the migrated tree, so the trees are equal if they
+    // have as many actors.
+    let mut new_count = 0;
+    for entry in &new {
+        entry.context("failed to read migrated state tree")?;
+        new_count += 1;
+    }
+    if new_count != count {
+        bail!("migrated state tree has {new_count} actors, expected {count}");
+    }
+    progress(MigrationProgress::Verified(count));
+
+    Ok(count)
+}
+
+fn load_root<S: Blockstore>(store: &S, root: &Cid) -> anyhow::Result<StateRoot> {
+    store
+        .get_cbor(root)
+        .with_context(|| format!("failed to load state tree {root}"))?
+        .ok_or_else(|| anyhow!("failed to find state tree {root}"))
+}
+
+/// Formats a state tree key (an address) for error messages.
+fn address(key: &BytesKey) -> String {
+    match fvm_shared::address::Address::from_bytes(key) {
+        Ok(addr) => addr.to_string(),
+        Err(_) => format!("{key:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::address::Address;
+    use multihash_codetable::MultihashDigest;
+
+    use super::*;
+    use crate::state_tree::StateTree;
+
+    fn cid(data: &[u8]) -> Cid {
+        Cid::new_v1(fvm_ipld_encoding::DAG_CBOR, Code::Blake2b256.digest(data))
+    }
+
+    fn actor(i: u64) -> ActorStateV4 {
+        ActorStateV4 {
+            code: cid(b"code"),
+            state: cid(&i.to_be_bytes()),
+            sequence: i,
+            balance: TokenAmount::from_atto(i * 100),
+        }
+    }
+
+    fn v4_tree(store: &MemoryBlockstore, version: StateTreeVersion, actors: u64) -> Cid {
+        let mut hamt = Hamt::<_, ActorStateV4>::new_with_bit_width(store, HAMT_BIT_WIDTH);
+        for i in 0..actors {
+            hamt.set(Address::new_id(i).to_bytes().into(), actor(i))
+                .unwrap();
+        }
+        let actors = hamt.flush().unwrap();
+        let info = store
+            .put_cbor(&StateInfo0::default(), Code::Blake2b256)
+            .unwrap();
+        store
+            .put_cbor(
+                &StateRoot {
+                    version,
+                    actors,
+                    info,
+                },
+                Code::Blake2b256,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn migrate() {
+        let store = MemoryBlockstore::default();
+        let old_root = v4_tree(&store, StateTreeVersion::V4, 25_000);
+
+        let mut reports = Vec::new();
+        let new_root = migrate_state_tree(&store, &old_root, |p| reports.push(p)).unwrap();
+        assert_eq!(
+            reports,
+            [
+                MigrationProgress::Migrated(10_000),
+                MigrationProgress::Migrated(20_000),
+                MigrationProgress::Migrated(25_000),
+                MigrationProgress::Verified(10_000),
+                MigrationProgress::Verified(20_000),
+                MigrationProgress::Verified(25_000),
+            ]
+        );
+
+        let tree = StateTree::new_from_root(&store, &new_root).unwrap();
+        for i in [0, 1, 12_345, 24_999] {
+            assert_eq!(tree.get_actor(i).unwrap(), Some(actor(i).into()));
+        }
+        assert_eq!(tree.get_actor(25_000).unwrap(), None);
+
+        // Already migrated.
+        assert_eq!(
+            migrate_state_tree(&store, &new_root, |_| panic!("nothing to migrate")).unwrap(),
+            new_root
+        );
+    }
+
+    #[test]
+    fn migrate_v3() {
+        let store = MemoryBlockstore::default();
+        let old_root = v4_tree(&store, StateTreeVersion::V3, 3);
+        let new_root = migrate_state_tree(&store, &old_root, |_| {}).unwrap();
+        let tree = StateTree::new_from_root(&store, &new_root).unwrap();
+        assert_eq!(tree.get_actor(2).unwrap(), Some(actor(2).into()));
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        let store = MemoryBlockstore::default();
+        let old_root = v4_tree(&store, StateTreeVersion::V2, 3);
+        assert!(migrate_state_tree(&store, &old_root, |_| {}).is_err());
+    }
+
+    #[test]
+    fn verify() {
+        let store = MemoryBlockstore::default();
+        let old_root = v4_tree(&store, StateTreeVersion::V4, 10);
+        let new_root = migrate_state_tree(&store, &old_root, |_| {}).unwrap();
+        assert_eq!(
+            verify_migration(&store, &old_root, &new_root, |_| {}).unwrap(),
+            10
+        );
+
+        // Missing, modified, and extra actors are all detected.
+        let mut tree = StateTree::new_from_root(&store, &new_root).unwrap();
+        tree.delete_actor(3);
+        let missing = tree.flush().unwrap();
+        let err = verify_migration(&store, &old_root, &missing, |_| {}).unwrap_err();
+        assert!(err.to_string().contains("missing"), "{err}");
+
+        let mut tree = StateTree::new_from_root(&store, &new_root).unwrap();
+        tree.set_actor(
+            3,
+            ActorState::new(cid(b"code"), cid(b"other"), TokenAmount::default(), 3, None),
+        );
+        let modified = tree.flush().unwrap();
+        let err = verify_migration(&store, &old_root, &modified, |_| {}).unwrap_err();
+        assert!(err.to_string().contains("differs"), "{err}");
+
+        let mut tree = StateTree::new_from_root(&store, &new_root).unwrap();
+        tree.set_actor(10, actor(10).into());
+        let extra = tree.flush().unwrap();
+        let err = verify_migration(&store, &old_root, &extra, |_| {}).unwrap_err();
+        assert!(err.to_string().contains("has 11 actors"), "{err}");
+    }
+}
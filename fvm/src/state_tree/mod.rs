@@ -0,0 +1,648 @@
+// Copyright 2021-2023 Protocol Labs
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Context as _};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_ipld_hamt::Hamt;
+use fvm_shared::address::{Address, Payload};
+use fvm_shared::state::{StateInfo0, StateRoot};
+use fvm_shared::{ActorID, HAMT_BIT_WIDTH};
+
+pub use fvm_shared::state::{ActorState, StateTreeVersion};
+
+mod migration;
+pub use migration::{migrate_state_tree, verify_migration, MigrationProgress};
+
+use crate::history_map::HistoryMap;
+use crate::init_actor::State as InitActorState;
+use crate::kernel::{ClassifyResult, ExecutionError, Result};
+
+/// State tree implementation using hamt. This structure is not threadsafe and should only be used
+/// in sync contexts.
+pub struct StateTree<S> {
+    hamt: Hamt<S, ActorState>,
+    /// The root of the actors HAMT as of the last flush, if any.
+    actors_root: Option<Cid>,
+
+    version: StateTreeVersion,
+    info: Option<Cid>,
+
+    /// An actor-state cache that internally keeps an undo history.
+    actor_cache: RefCell<HistoryMap<ActorID, ActorCacheEntry>>,
+    /// An actor-address cache that internally keeps an undo history.
+    resolve_cache: RefCell<HistoryMap<Address, ActorID>>,
+    /// A delegated (f4) address cache that internally keeps an undo history. Kept apart from the
+    /// resolve cache as delegated addresses are resolved on the hot path of message execution.
+    delegated_cache: RefCell<HistoryMap<Address, ActorID>>,
+    /// Snapshot layers. Each layer contains points in the actor/resolve cache histories to which
+    /// said caches will be reverted on revert.
+    layers: Vec<StateSnapLayer>,
+    /// The actors and addresses looked up since [`StateTree::record_witness`] was called.
+    witness: RefCell<Option<WitnessKeys>>,
+}
+
+/// The keys looked up while recording a witness.
+#[derive(Default)]
+struct WitnessKeys {
+    actors: BTreeSet<ActorID>,
+    addresses: BTreeSet<Address>,
+}
+
+/// An entry in the actor cache.
+#[derive(Eq, PartialEq)]
+struct ActorCacheEntry {
+    /// True if this is a change that should be flushed.
+    dirty: bool,
+    /// The cached actor, or None if the actor doesn't exist and/or has been deleted.
+    actor: Option<ActorState>,
+}
+
+/// State snap shot layer.
+struct StateSnapLayer {
+    /// The actor-cache height at which this snapshot was taken.
+    actor_cache_height: usize,
+    /// The resolve-cache height at which this snapshot was taken.
+    resolve_cache_height: usize,
+    /// The delegated-cache height at which this snapshot was taken.
+    delegated_cache_height: usize,
+}
+
+impl<S> StateTree<S>
+where
+    S: Blockstore,
+{
+    pub fn new(store: S, version: StateTreeVersion) -> Result<Self> {
+        let info = match version {
+            StateTreeVersion::V0
+            | StateTreeVersion::V1
+            | StateTreeVersion::V2
+            | StateTreeVersion::V3
+            | StateTreeVersion::V4 => {
+                return Err(ExecutionError::Fatal(anyhow!(
+                    "unsupported state tree version: {:?}",
+                    version
+                )))
+            }
+            StateTreeVersion::V5 => {
+                let cid = store
+                    .put_cbor(
+                        &StateInfo0::default(),
+                        multihash_codetable::Code::Blake2b256,
+                    )
+                    .context("failed to put state info")
+                    .or_fatal()?;
+                Some(cid)
+            }
+        };
+
+        // Both V3 and V4 use bitwidt=5.
+        let hamt = Hamt::new_with_bit_width(store, HAMT_BIT_WIDTH);
+        Ok(Self {
+            hamt,
+            actors_root: None,
+            version,
+            info,
+            actor_cache: Default::default(),
+            resolve_cache: Default::default(),
+            delegated_cache: Default::default(),
+            layers: Vec::new(),
+            witness: Default::default(),
+        })
+    }
+
+    /// Constructor for a hamt state tree given an IPLD store
+    pub fn new_from_root(store: S, c: &Cid) -> Result<Self> {
+        // Try to load state root, if versioned
+        let (version, info, actors) = match store.get_cbor(c) {
+            Ok(Some(StateRoot {
+                version,
+                info,
+                actors,
+            })) => (version, Some(info), actors),
+            Ok(None) => {
+                return Err(ExecutionError::Fatal(anyhow!(
+                    "failed to find state tree {}",
+                    c
+                )))
+            }
+            Err(e) => {
+                return Err(ExecutionError::Fatal(anyhow!(
+                    "failed to load state tree {}: {}",
+                    c,
+                    e
+                )))
+            }
+        };
+
+        match version {
+            StateTreeVersion::V0
+            | StateTreeVersion::V1
+            | StateTreeVersion::V2
+            | StateTreeVersion::V3
+            | StateTreeVersion::V4 => Err(ExecutionError::Fatal(anyhow!(
+                "unsupported state tree version: {:?}",
+                version
+            ))),
+
+            StateTreeVersion::V5 => {
+                let hamt = Hamt::load_with_bit_width(&actors, store, HAMT_BIT_WIDTH)
+                    .context("failed to load state tree")
+                    .or_fatal()?;
+
+                Ok(Self {
+                    hamt,
+                    actors_root: Some(actors),
+                    version,
+                    info,
+                    actor_cache: Default::default(),
+                    resolve_cache: Default::default(),
+                    delegated_cache: Default::default(),
+                    layers: Vec::new(),
+                    witness: Default::default(),
+                })
+            }
+        }
+    }
+
+    /// Retrieve store reference to modify db.
+    pub fn store(&self) -> &S {
+        self.hamt.store()
+    }
+
+    /// Get actor state from an address. Will be resolved to ID address.
+    #[cfg(feature = "testing")]
+    pub fn get_actor_by_address(&self, addr: &Address) -> Result<Option<ActorState>> {
+        let id = match self.lookup_id(addr)? {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        self.get_actor(id)
+    }
+
+    /// Get actor state from an actor ID.
+    pub fn get_actor(&self, id: ActorID) -> Result<Option<ActorState>> {
+        if let Some(witness) = &mut *self.witness.borrow_mut() {
+            witness.actors.insert(id);
+        }
+        self.actor_cache
+            .borrow_mut()
+            .get_or_try_insert_with(id, || {
+                // It's not cached/dirty, so we look it up and cache it.
+                let key = Address::new_id(id).to_bytes();
+                Ok(ActorCacheEntry {
+                    dirty: false,
+                    actor: self
+                        .hamt
+                        .get(&key)
+                        .with_context(|| format!("failed to lookup actor {}", id))
+                        .or_fatal()?
+                        .cloned(),
+                })
+            })
+            .map(|ActorCacheEntry { actor, .. }| actor.clone())
+    }
+
+    /// Set actor state with an actor ID.
+    pub fn set_actor(&mut self, id: ActorID, actor: ActorState) {
+        self.actor_cache.borrow_mut().insert(
+            id,
+            ActorCacheEntry {
+                actor: Some(actor),
+                dirty: true,
+            },
+        )
+    }
+
+    /// Get an ID address from any Address
+    ///
+    /// ID addresses resolve to their ID without checking that the actor exists. All other
+    /// addresses resolve through the init actor's address map, and resolve to `None` until
+    /// assigned (see [`ImplicitCreation`](fvm_shared::address::ImplicitCreation)).
+    pub fn lookup_id(&self, addr: &Address) -> Result<Option<ActorID>> {
+        match addr.payload() {
+            &Payload::ID(id) => Ok(Some(id)),
+            Payload::Delegated(_) => self.lookup_delegated_id(addr),
+            _ => self.resolve_through_init(&self.resolve_cache, addr),
+        }
+    }
+
+    /// Resolve a delegated (f4) address to the ID of the actor it was assigned to. Returns None if
+    /// the address isn't a delegated address, or hasn't been assigned.
+    pub fn lookup_delegated_id(&self, addr: &Address) -> Result<Option<ActorID>> {
+        if !matches!(addr.payload(), Payload::Delegated(_)) {
+            return Ok(None);
+        }
+        self.resolve_through_init(&self.delegated_cache, addr)
+    }
+
+    /// Get the delegated (f4) address of an actor, if it exists and has one.
+    pub fn lookup_delegated_address(&self, id: ActorID) -> Result<Option<Address>> {
+        let addr = match self.get_actor(id)?.and_then(|act| act.delegated_address) {
+            Some(addr) => addr,
+            None => return Ok(None),
+        };
+        // An actor's delegated address always resolves back to it, so remember that.
+        self.delegated_cache.borrow_mut().insert(addr, id);
+        Ok(Some(addr))
+    }
+
+    /// Resolve an address through the init actor, consulting and filling the given cache.
+    fn resolve_through_init(
+        &self,
+        cache: &RefCell<HistoryMap<Address, ActorID>>,
+        addr: &Address,
+    ) -> Result<Option<ActorID>> {
+        if let Some(witness) = &mut *self.witness.borrow_mut() {
+            witness.addresses.insert(*addr);
+        }
+        if let Some(&id) = cache.borrow().get(addr) {
+            return Ok(Some(id));
+        }
+
+        let (state, _) = InitActorState::load(self)?;
+
+        let id = match state.resolve_address(self.store(), addr)? {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        cache.borrow_mut().insert(*addr, id);
+
+        Ok(Some(id))
+    }
+
+    /// Delete actor identified by the supplied ID.
+    pub fn delete_actor(&mut self, id: ActorID) {
+        // Record that we've deleted the actor.
+        self.actor_cache.borrow_mut().insert(
+            id,
+            ActorCacheEntry {
+                dirty: true,
+                actor: None,
+            },
+        );
+    }
+
+    /// Mutate and set actor state identified by the supplied ID. Returns a fatal error if the actor
+    /// doesn't exist.
+    pub fn mutate_actor<F>(&mut self, id: ActorID, mutate: F) -> Result<()>
+    where
+        F: FnOnce(&mut ActorState) -> Result<()>,
+    {
+        self.maybe_mutate_actor_id(id, mutate).and_then(|found| {
+            if found {
+                Ok(())
+            } else {
+                Err(anyhow!("failed to lookup actor {}", id)).or_fatal()
+            }
+        })
+    }
+
+    /// Try to mutate the actor state identified by the supplied ID, returning false if the actor
+    /// doesn't exist.
+    pub fn maybe_mutate_actor_id<F>(&mut self, id: ActorID, mutate: F) -> Result<bool>
+    where
+        F: FnOnce(&mut ActorState) -> Result<()>,
+    {
+        // Retrieve actor state from address
+        let mut act = match self.get_actor(id)? {
+            Some(act) => act,
+            None => return Ok(false),
+        };
+
+        // Apply function of actor state
+        mutate(&mut act)?;
+        // Set the actor
+        self.set_actor(id, act);
+        Ok(true)
+    }
+
+    /// Register a new address through the init actor.
+    pub fn register_new_address(&mut self, addr: &Address) -> Result<ActorID> {
+        let (mut state, mut actor) = InitActorState::load(self)?;
+
+        let new_id = state.map_address_to_new_id(self.store(), addr)?;
+
+        // Set state for init actor in store and update root Cid
+        actor.state = self
+            .store()
+            .put_cbor(&state, multihash_codetable::Code::Blake2b256)
+            .or_fatal()?;
+
+        self.set_actor(crate::init_actor::INIT_ACTOR_ID, actor);
+        if let Payload::Delegated(_) = addr.payload() {
+            self.delegated_cache.borrow_mut().insert(*addr, new_id);
+        } else {
+            self.resolve_cache.borrow_mut().insert(*addr, new_id);
+        }
+
+        Ok(new_id)
+    }
+
+    /// Begin a new state transaction. Transactions stack.
+    pub fn begin_transaction(&mut self) {
+        self.layers.push(StateSnapLayer {
+            actor_cache_height: self.actor_cache.get_mut().history_len(),
+            resolve_cache_height: self.resolve_cache.get_mut().history_len(),
+            delegated_cache_height: self.delegated_cache.get_mut().history_len(),
+        })
+    }
+
+    /// End a transaction, reverting if requested.
+    pub fn end_transaction(&mut self, revert: bool) -> Result<()> {
+        let layer = self
+            .layers
+            .pop()
+            .context("state snapshots empty")
+            .or_fatal()?;
+        if revert {
+            self.actor_cache
+                .get_mut()
+                .rollback(layer.actor_cache_height);
+            self.resolve_cache
+                .get_mut()
+                .rollback(layer.resolve_cache_height);
+            self.delegated_cache
+                .get_mut()
+                .rollback(layer.delegated_cache_height);
+        }
+        // When we end the last transaction, discard the undo history.
+        if !self.in_transaction() {
+            self.actor_cache.get_mut().discard_history();
+            self.resolve_cache.get_mut().discard_history();
+            self.delegated_cache.get_mut().discard_history();
+        }
+        Ok(())
+    }
+
+    /// Returns true if we're inside of a transaction.
+    pub fn in_transaction(&self) -> bool {
+        !self.layers.is_empty()
+    }
+
+    /// Flush state tree and return Cid root.
+    pub fn flush(&mut self) -> Result<Cid> {
+        if self.in_transaction() {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "cannot flush while inside of a transaction",
+            )));
+        }
+        for (&id, entry) in self.actor_cache.get_mut().iter_mut() {
+            if !entry.dirty {
+                continue;
+            }
+            entry.dirty = false;
+            let addr = Address::new_id(id);
+            match entry.actor {
+                None => {
+                    self.hamt.delete(&addr.to_bytes()).or_fatal()?;
+                }
+                Some(ref state) => {
+                    self.hamt
+                        .set(addr.to_bytes().into(), state.clone())
+                        .or_fatal()?;
+                }
+            }
+        }
+
+        let root = self.hamt.flush().or_fatal()?;
+        self.actors_root = Some(root);
+
+        match self.version {
+            StateTreeVersion::V0 => Ok(root),
+            _ => {
+                let cid = self
+                    .info
+                    .expect("malformed state tree, version 1+ require info");
+                let obj = &StateRoot {
+                    version: self.version,
+                    actors: root,
+                    info: cid,
+                };
+                let root = self
+                    .store()
+                    .put_cbor(obj, multihash_codetable::Code::Blake2b256)
+                    .or_fatal()?;
+                Ok(root)
+            }
+        }
+    }
+
+    /// Starts recording the actors and addresses looked up, discarding any previous recording. See
+    /// [`StateTree::read_witness`].
+    pub fn record_witness(&self) {
+        *self.witness.borrow_mut() = Some(WitnessKeys::default());
+    }
+
+    /// Stops recording and re-reads, from the blockstore, the blocks proving each actor and
+    /// address looked up since [`StateTree::record_witness`] was called.
+    ///
+    /// Lookups served from this state tree's caches (or from the HAMT's in-memory nodes) never
+    /// reach the blockstore. Re-reading them here lets a blockstore recording the blocks read
+    /// include them in its witness. Actors are proven against the actors HAMT as of the last
+    /// flush.
+    pub fn read_witness(&self) -> Result<()> {
+        let Some(keys) = self.witness.borrow_mut().take() else {
+            return Ok(());
+        };
+
+        if let Some(root) = &self.actors_root {
+            let hamt =
+                Hamt::<_, ActorState>::load_with_bit_width(root, self.store(), HAMT_BIT_WIDTH)
+                    .context("failed to load state tree")
+                    .or_fatal()?;
+            for id in keys.actors {
+                hamt.get(&Address::new_id(id).to_bytes())
+                    .with_context(|| format!("failed to lookup actor {}", id))
+                    .or_fatal()?;
+            }
+        }
+
+        if !keys.addresses.is_empty() {
+            let (state, _) = InitActorState::load(self)?;
+            for addr in keys.addresses {
+                state.resolve_address(self.store(), &addr)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Consumes this StateTree and returns the Blockstore it owns via the HAMT.
+    pub fn into_store(self) -> S {
+        self.hamt.into_store()
+    }
+
+    pub fn for_each<F>(&self, mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(Address, &ActorState) -> anyhow::Result<()>,
+    {
+        self.hamt.for_each(|k, v| {
+            let addr = Address::from_bytes(&k.0)?;
+            f(addr, v)
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::econ::TokenAmount;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use super::*;
+    use crate::init_actor::INIT_ACTOR_ID;
+
+    fn code_cid() -> Cid {
+        Cid::new_v1(
+            fvm_ipld_encoding::DAG_CBOR,
+            Code::Blake2b256.digest(b"code"),
+        )
+    }
+
+    #[test]
+    fn delegated_lookup() {
+        let store = MemoryBlockstore::default();
+        let init_state = store
+            .put_cbor(&InitActorState::new_test(&store), Code::Blake2b256)
+            .unwrap();
+        let mut tree = StateTree::new(store, StateTreeVersion::V5).unwrap();
+        tree.set_actor(
+            INIT_ACTOR_ID,
+            ActorState::new(code_cid(), init_state, TokenAmount::default(), 0, None),
+        );
+
+        let f4 = Address::new_delegated(10, &[0xab; 20]).unwrap();
+        assert_eq!(tree.lookup_delegated_id(&f4).unwrap(), None);
+
+        tree.begin_transaction();
+        let id = tree.register_new_address(&f4).unwrap();
+        tree.set_actor(id, ActorState::new_empty(code_cid(), Some(f4)));
+        assert_eq!(tree.lookup_delegated_id(&f4).unwrap(), Some(id));
+        assert_eq!(tree.lookup_id(&f4).unwrap(), Some(id));
+        assert_eq!(tree.lookup_delegated_address(id).unwrap(), Some(f4));
+        tree.end_transaction(true).unwrap();
+
+        // Reverting forgets both the mapping and the actor.
+        assert_eq!(tree.lookup_delegated_id(&f4).unwrap(), None);
+        assert_eq!(tree.lookup_delegated_address(id).unwrap(), None);
+
+        let id = tree.register_new_address(&f4).unwrap();
+        tree.set_actor(id, ActorState::new_empty(code_cid(), Some(f4)));
+        let root = tree.flush().unwrap();
+
+        // Resolves from a fresh tree, without any cached state.
+        let tree = StateTree::new_from_root(tree.into_store(), &root).unwrap();
+        assert_eq!(tree.lookup_delegated_address(id).unwrap(), Some(f4));
+        assert_eq!(tree.lookup_delegated_id(&f4).unwrap(), Some(id));
+        let tree = StateTree::new_from_root(tree.into_store(), &root).unwrap();
+        assert_eq!(tree.lookup_id(&f4).unwrap(), Some(id));
+
+        // Other addresses aren't delegated addresses.
+        assert_eq!(
+            tree.lookup_delegated_id(&Address::new_id(id)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn address_lookup() {
+        let store = MemoryBlockstore::default();
+        let init_state = store
+            .put_cbor(&InitActorState::new_test(&store), Code::Blake2b256)
+            .unwrap();
+        let mut tree = StateTree::new(store, StateTreeVersion::V5).unwrap();
+        tree.set_actor(
+            INIT_ACTOR_ID,
+            ActorState::new(code_cid(), init_state, TokenAmount::default(), 0, None),
+        );
+
+        // ID addresses resolve to themselves, whether or not the actor exists.
+        assert_eq!(tree.lookup_id(&Address::new_id(1234)).unwrap(), Some(1234));
+        assert!(tree.get_actor(1234).unwrap().is_none());
+
+        let key = Address::new_secp256k1(&[4; 65]).unwrap();
+        let robust = Address::new_actor(b"robust");
+        let f4 = Address::new_delegated(10, &[0xab; 20]).unwrap();
+        for addr in [key, robust, f4] {
+            assert_eq!(tree.lookup_id(&addr).unwrap(), None, "{addr}");
+        }
+
+        // Assigning an address doesn't create the actor, nor assign the actor's other addresses.
+        let key_id = tree.register_new_address(&key).unwrap();
+        assert_eq!(tree.lookup_id(&key).unwrap(), Some(key_id));
+        assert_eq!(tree.lookup_id(&robust).unwrap(), None);
+        assert!(tree.get_actor(key_id).unwrap().is_none());
+        assert_eq!(tree.lookup_delegated_address(key_id).unwrap(), None);
+
+        // A placeholder's delegated address resolves both ways; an account created for a key
+        // address has no delegated address.
+        let f4_id = tree.register_new_address(&f4).unwrap();
+        assert_ne!(f4_id, key_id);
+        tree.set_actor(f4_id, ActorState::new_empty(code_cid(), Some(f4)));
+        tree.set_actor(key_id, ActorState::new_empty(code_cid(), None));
+        assert_eq!(tree.lookup_id(&f4).unwrap(), Some(f4_id));
+        assert_eq!(tree.lookup_delegated_address(f4_id).unwrap(), Some(f4));
+        assert_eq!(tree.lookup_delegated_address(key_id).unwrap(), None);
+
+        // Resolution survives a flush.
+        let root = tree.flush().unwrap();
+        let tree = StateTree::new_from_root(tree.into_store(), &root).unwrap();
+        assert_eq!(tree.lookup_id(&key).unwrap(), Some(key_id));
+        assert_eq!(tree.lookup_id(&f4).unwrap(), Some(f4_id));
+        assert_eq!(tree.lookup_id(&robust).unwrap(), None);
+    }
+
+    #[test]
+    fn witness_covers_cached_lookups() {
+        use crate::blockstore::BufferedBlockstore;
+
+        let store = BufferedBlockstore::new(MemoryBlockstore::default());
+        let init_state = store
+            .put_cbor(&InitActorState::new_test(&store), Code::Blake2b256)
+            .unwrap();
+        let mut tree = StateTree::new(store, StateTreeVersion::V5).unwrap();
+        tree.set_actor(
+            INIT_ACTOR_ID,
+            ActorState::new(code_cid(), init_state, TokenAmount::default(), 0, None),
+        );
+        let key = Address::new_secp256k1(&[4; 65]).unwrap();
+        let id = tree.register_new_address(&key).unwrap();
+        tree.set_actor(id, ActorState::new_empty(code_cid(), None));
+        let root = tree.flush().unwrap();
+
+        let tree = StateTree::new_from_root(tree.into_store(), &root).unwrap();
+        let StateRoot { actors, .. } = tree.store().get_cbor(&root).unwrap().unwrap();
+        let (InitActorState { address_map, .. }, init_actor) = InitActorState::load(&tree).unwrap();
+
+        // Warm the caches.
+        assert_eq!(tree.lookup_id(&key).unwrap(), Some(id));
+        assert!(tree.get_actor(id).unwrap().is_some());
+
+        // Cached lookups don't reach the blockstore...
+        tree.record_witness();
+        tree.store().record_witness();
+        assert_eq!(tree.lookup_id(&key).unwrap(), Some(id));
+        assert!(tree.get_actor(id).unwrap().is_some());
+        assert!(tree.store().take_witness().is_empty());
+
+        // ...but are re-read into the witness.
+        tree.store().record_witness();
+        tree.read_witness().unwrap();
+        let witness = tree.store().take_witness();
+        for cid in [actors, init_actor.state, address_map] {
+            assert!(witness.contains(&cid), "{cid} missing from {witness:?}");
+        }
+
+        // Nothing is re-read once recording stops.
+        tree.store().record_witness();
+        tree.read_witness().unwrap();
+        assert!(tree.store().take_witness().is_empty());
+    }
+}
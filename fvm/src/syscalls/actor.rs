@@ -171,6 +171,20 @@ pub fn install_actor(
     context.kernel.install_actor(typ)
 }
 
+pub fn install_code(
+    context: Context<'_, impl ActorOps>,
+    code_off: u32,
+    code_len: u32,
+    obuf_off: u32, // Cid
+    obuf_len: u32,
+) -> Result<u32> {
+    context.memory.check_bounds(obuf_off, obuf_len)?;
+
+    let code = context.memory.try_slice(code_off, code_len)?;
+    let k = context.kernel.install_code(code)?;
+    context.memory.write_cid(&k, obuf_off, obuf_len)
+}
+
 pub fn balance_of(context: Context<'_, impl ActorOps>, actor_id: u64) -> Result<sys::TokenAmount> {
     let balance = context.kernel.balance_of(actor_id)?;
     balance
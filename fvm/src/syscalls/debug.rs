@@ -1,6 +1,10 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::from_slice;
+use fvm_shared::sys::LogLevel;
+
 use crate::kernel::{ClassifyResult, DebugOps, Result};
+use crate::syscall_error;
 use crate::syscalls::context::Context;
 
 pub fn log(context: Context<'_, impl DebugOps>, msg_off: u32, msg_len: u32) -> Result<()> {
@@ -15,6 +19,29 @@ pub fn log(context: Context<'_, impl DebugOps>, msg_off: u32, msg_len: u32) -> R
     Ok(())
 }
 
+pub fn log_structured(
+    context: Context<'_, impl DebugOps>,
+    level: u32,
+    msg_off: u32,
+    msg_len: u32,
+    fields_off: u32,
+    fields_len: u32,
+) -> Result<()> {
+    // No-op if disabled.
+    if !context.kernel.debug_enabled() {
+        return Ok(());
+    }
+
+    let level = LogLevel::try_from(level)
+        .map_err(|l| syscall_error!(IllegalArgument; "invalid log level {}", l))?;
+    let msg = context.memory.try_slice(msg_off, msg_len)?;
+    let msg = String::from_utf8(msg.to_owned()).or_illegal_argument()?;
+    let fields = context.memory.try_slice(fields_off, fields_len)?;
+    let fields: Vec<(String, String)> = from_slice(fields).or_illegal_argument()?;
+    context.kernel.log_structured(level, msg, fields);
+    Ok(())
+}
+
 pub fn enabled(context: Context<'_, impl DebugOps>) -> Result<i32> {
     Ok(if context.kernel.debug_enabled() {
         0
@@ -194,23 +194,97 @@ pub fn batch_verify_seals(
     // Execute.
     let result = context.kernel.batch_verify_seals(&batch)?;
 
+    // Return.
+    write_batch_results(output, &result)
+}
+
+/// Verify a batch of seals encoded as a CBOR array of `SealVerifyInfo`, charging gas for the whole
+/// batch up-front.
+///
+/// When successful, this method will write a single byte back into the array at `result_off` for
+/// each result: 0 for failed, 1 for success.
+pub fn verify_seal_batch(
+    context: Context<'_, impl FilecoinKernel>,
+    batch_off: u32,
+    batch_len: u32,
+    result_off: u32,
+) -> Result<()> {
+    let batch = context
+        .memory
+        .read_cbor::<Vec<SealVerifyInfo>>(batch_off, batch_len)?;
+    let output = context
+        .memory
+        .try_slice_mut(result_off, batch.len() as u32)?;
+
+    let result = context.kernel.verify_seal_batch(&batch)?;
+    write_batch_results(output, &result)
+}
+
+/// Verify a batch of window PoSts encoded as a CBOR array of `WindowPoStVerifyInfo`, charging gas
+/// for the whole batch up-front.
+///
+/// When successful, this method will write a single byte back into the array at `result_off` for
+/// each result: 0 for failed, 1 for success.
+pub fn verify_post_batch(
+    context: Context<'_, impl FilecoinKernel>,
+    batch_off: u32,
+    batch_len: u32,
+    result_off: u32,
+) -> Result<()> {
+    let batch = context
+        .memory
+        .read_cbor::<Vec<WindowPoStVerifyInfo>>(batch_off, batch_len)?;
+    let output = context
+        .memory
+        .try_slice_mut(result_off, batch.len() as u32)?;
+
+    let result = context.kernel.verify_post_batch(&batch)?;
+    write_batch_results(output, &result)
+}
+
+/// Writes one byte per batch verification result into `output`.
+fn write_batch_results(output: &mut [u8], result: &[bool]) -> Result<()> {
     // Sanity check that we got the correct number of results.
-    if result.len() != batch.len() {
+    if result.len() != output.len() {
         return Err(anyhow!(
             "expected one result per input: {} != {}",
-            batch.len(),
+            output.len(),
             result.len()
         ))
         .or_fatal();
     }
 
-    // Return.
     unsafe {
-        output.copy_from_slice(&*(&*result as *const [bool] as *const [u8]));
+        output.copy_from_slice(&*(result as *const [bool] as *const [u8]));
     }
     Ok(())
 }
 
+/// Returns the network's power, pledge and reward aggregates.
+pub fn network_summary(
+    context: Context<'_, impl FilecoinKernel>,
+) -> Result<sys::out::network::NetworkSummary> {
+    let summary = context.kernel.network_summary()?;
+    Ok(sys::out::network::NetworkSummary {
+        raw_byte_power: (&summary.raw_byte_power)
+            .try_into()
+            .context("raw byte power exceeds u128 limit")
+            .or_fatal()?,
+        quality_adj_power: (&summary.quality_adj_power)
+            .try_into()
+            .context("quality-adjusted power exceeds u128 limit")
+            .or_fatal()?,
+        total_pledge_collateral: (&summary.total_pledge_collateral)
+            .try_into()
+            .context("total pledge collateral exceeds u128 limit")
+            .or_fatal()?,
+        this_epoch_reward: (&summary.this_epoch_reward)
+            .try_into()
+            .context("epoch reward exceeds u128 limit")
+            .or_fatal()?,
+    })
+}
+
 /// Returns the network circulating supply.
 pub fn total_fil_circ_supply(
     context: Context<'_, impl FilecoinKernel>,
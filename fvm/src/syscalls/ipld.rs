@@ -69,3 +69,13 @@ pub fn block_stat(
             size: stat.size,
         })
 }
+
+pub fn block_quota(context: Context<'_, impl IpldBlockOps>) -> Result<sys::out::ipld::IpldQuota> {
+    context
+        .kernel
+        .block_quota()
+        .map(|quota| sys::out::ipld::IpldQuota {
+            bytes_remaining: quota.bytes_remaining,
+            blocks_remaining: quota.blocks_remaining,
+        })
+}
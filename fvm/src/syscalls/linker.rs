@@ -1,9 +1,11 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::HashSet;
 use std::mem;
 
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::sys::SyscallSafe;
+use fvm_shared::version::NetworkVersion;
 use wasmtime::{Caller, WasmTy};
 
 use super::context::Memory;
@@ -12,10 +14,37 @@ use super::{charge_for_exec, charge_syscall_gas, update_gas_available, Context,
 use crate::call_manager::backtrace;
 use crate::kernel::{self, ExecutionError, Kernel, SyscallError};
 
+/// Syscall modules (namespaces) reserved for the FVM's built-in syscalls. Additional host modules
+/// registered through [`Linker::namespace`] may not use these names.
+pub const RESERVED_NAMESPACES: &[&str] = &[
+    "vm", "network", "ipld", "self", "actor", "crypto", "event", "rand", "gas", "send", "debug",
+];
+
 /// A "linker" for exposing syscalls to wasm modules.
-pub struct Linker<K>(pub(crate) wasmtime::Linker<InvocationData<K>>);
+pub struct Linker<K>(
+    pub(crate) wasmtime::Linker<InvocationData<K>>,
+    /// The modules syscalls have been linked into.
+    HashSet<&'static str>,
+    /// The network version actors instantiated with this linker will run at.
+    NetworkVersion,
+);
 
 impl<K> Linker<K> {
+    pub(crate) fn new(engine: &wasmtime::Engine, network_version: NetworkVersion) -> Self {
+        Linker(
+            wasmtime::Linker::new(engine),
+            HashSet::new(),
+            network_version,
+        )
+    }
+
+    /// The network version actors instantiated with this linker will run at. Syscalls introduced
+    /// in a given network version should only be linked from that version onwards, so that actors
+    /// importing them are rejected before it.
+    pub fn network_version(&self) -> NetworkVersion {
+        self.2
+    }
+
     /// Link a syscall.
     ///
     /// # Example
@@ -37,6 +66,59 @@ impl<K> Linker<K> {
         syscall: impl Syscall<K, Args, Ret>,
     ) -> anyhow::Result<&mut Self> {
         syscall.link(self, module, name)?;
+        self.1.insert(module);
+        Ok(self)
+    }
+
+    /// Returns true if any syscall has been linked into the given module.
+    pub fn has_module(&self, module: &str) -> bool {
+        self.1.contains(module)
+    }
+
+    /// Returns a handle for linking syscalls into a custom (non-reserved) module.
+    ///
+    /// Syscalls linked through the returned [`Namespace`] are wrapped exactly like the built-in
+    /// syscalls: execution gas is charged before the call, the syscall base fee is charged, and
+    /// the wasm gas counter is updated on return.
+    pub fn namespace(&mut self, module: &'static str) -> anyhow::Result<Namespace<'_, K>> {
+        check_namespace(module)?;
+        Ok(Namespace {
+            linker: self,
+            module,
+        })
+    }
+}
+
+/// Fails if the given syscall module name is empty or reserved for built-in syscalls.
+pub(crate) fn check_namespace(module: &str) -> anyhow::Result<()> {
+    if module.is_empty() {
+        anyhow::bail!("syscall namespace must not be empty");
+    }
+    if RESERVED_NAMESPACES.contains(&module) {
+        anyhow::bail!("syscall namespace {module:?} is reserved for built-in syscalls");
+    }
+    Ok(())
+}
+
+/// A [`Linker`] scoped to a single custom syscall module. See [`Linker::namespace`].
+pub struct Namespace<'a, K> {
+    linker: &'a mut Linker<K>,
+    module: &'static str,
+}
+
+impl<K> Namespace<'_, K> {
+    /// The module name syscalls are linked under.
+    pub fn module(&self) -> &'static str {
+        self.module
+    }
+
+    /// Link a syscall into this namespace.
+    pub fn link_syscall<Args, Ret>(
+        &mut self,
+        name: &'static str,
+        syscall: impl Syscall<K, Args, Ret>,
+    ) -> anyhow::Result<&mut Self> {
+        self.linker.link_syscall(self.module, name, syscall)?;
         Ok(self)
     }
 }
@@ -162,7 +244,7 @@ macro_rules! impl_syscall {
                     // If we're returning a zero-sized "value", we return no value therefore and expect no out pointer.
                     linker.0.func_wrap(module, name, move |mut caller: Caller<'_, InvocationData<K>> $(, $t: $t)*| {
                         charge_for_exec(&mut caller)?;
-                        charge_syscall_gas(&mut caller)?;
+                        let t = charge_syscall_gas(&mut caller)?;
 
                         let (mut memory, data) = memory_and_data(&mut caller);
 
@@ -184,6 +266,7 @@ macro_rules! impl_syscall {
                             ControlFlow::Abort(abort) => Err(abort.into()),
                         };
 
+                        t.stop(&data.kernel);
                         update_gas_available(&mut caller)?;
 
                         result
@@ -192,7 +275,7 @@ macro_rules! impl_syscall {
                     // If we're returning an actual value, we need to write it back into the wasm module's memory.
                     linker.0.func_wrap(module, name, move |mut caller: Caller<'_, InvocationData<K>>, ret: u32 $(, $t: $t)*| {
                         charge_for_exec(&mut caller)?;
-                        charge_syscall_gas(&mut caller)?;
+                        let t = charge_syscall_gas(&mut caller)?;
 
                         let (mut memory, data) = memory_and_data(&mut caller);
 
@@ -225,6 +308,7 @@ macro_rules! impl_syscall {
                             ControlFlow::Abort(abort) => Err(abort.into()),
                         };
 
+                        t.stop(&data.kernel);
                         update_gas_available(&mut caller)?;
 
                         result
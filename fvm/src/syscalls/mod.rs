@@ -1,6 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use anyhow::{anyhow, Context as _};
+use fvm_shared::version::NetworkVersion;
 use num_traits::Zero;
 use wasmtime::{AsContext, AsContextMut, ExternType, Global, Module, Val};
 
@@ -34,7 +35,8 @@ mod vm;
 
 pub use context::{Context, Memory};
 pub use error::Abort;
-pub use linker::{ControlFlow, Linker};
+pub(crate) use linker::check_namespace;
+pub use linker::{ControlFlow, Linker, Namespace, RESERVED_NAMESPACES};
 
 pub use linker::{IntoControlFlow, Syscall};
 
@@ -171,14 +173,41 @@ pub(crate) fn charge_for_exec<K: Kernel>(
     Ok(())
 }
 
-pub(crate) fn charge_syscall_gas(
-    ctx: &mut impl AsContext<Data = InvocationData<impl Kernel>>,
-) -> Result<(), Abort> {
+/// Charge the fixed cost of a syscall.
+///
+/// The returned timer should be stopped once the syscall returns, recording the time spent
+/// dispatching and executing it.
+pub(crate) fn charge_syscall_gas<K: Kernel>(
+    ctx: &mut impl AsContext<Data = InvocationData<K>>,
+) -> Result<SyscallTimer, Abort> {
     let data = ctx.as_context().data();
-    data.kernel
+    let timer = data
+        .kernel
         .charge_gas("OnSyscall", data.wasm_prices.host_call_cost)
         .map_err(Abort::from_error_as_fatal)?;
-    Ok(())
+    Ok(SyscallTimer {
+        timer,
+        gas_available: data.kernel.gas_available(),
+    })
+}
+
+/// Times the `OnSyscall` charge of a syscall.
+pub(crate) struct SyscallTimer {
+    timer: GasTimer,
+    /// The gas available right after charging for the syscall.
+    gas_available: Gas,
+}
+
+impl SyscallTimer {
+    /// Record the time spent in the syscall, unless it charged any more gas. The time of a syscall
+    /// which did would include the time of its nested charges (e.g. hashing, or entire sends),
+    /// which are recorded on the charges themselves, so we leave the `OnSyscall` time unset rather
+    /// than counting it twice.
+    pub(crate) fn stop(self, kernel: &impl Kernel) {
+        if kernel.gas_available() == self.gas_available {
+            self.timer.stop()
+        }
+    }
 }
 
 /// Charge for the initial memory and tables before a Wasm module is instantiated.
@@ -279,6 +308,10 @@ where
         linker.link_syscall("ipld", "block_read", ipld::block_read)?;
         linker.link_syscall("ipld", "block_stat", ipld::block_stat)?;
         linker.link_syscall("ipld", "block_link", ipld::block_link)?;
+        // The block quota is only queryable from nv26.
+        if linker.network_version() >= NetworkVersion::V26 {
+            linker.link_syscall("ipld", "block_quota", ipld::block_quota)?;
+        }
 
         linker.link_syscall("self", "root", sself::root)?;
         linker.link_syscall("self", "set_root", sself::set_root)?;
@@ -314,6 +347,7 @@ where
         // Only wire this syscall when M2 native is enabled.
         if cfg!(feature = "m2-native") {
             linker.link_syscall("actor", "install_actor", actor::install_actor)?;
+            linker.link_syscall("actor", "install_code", actor::install_code)?;
         }
         #[cfg(feature = "verify-signature")]
         linker.link_syscall("crypto", "verify_signature", crypto::verify_signature)?;
@@ -341,6 +375,10 @@ where
         linker.link_syscall("send", "send", send::send)?;
 
         linker.link_syscall("debug", "log", debug::log)?;
+        // Structured logging is only available from nv26.
+        if linker.network_version() >= NetworkVersion::V26 {
+            linker.link_syscall("debug", "log_structured", debug::log_structured)?;
+        }
         linker.link_syscall("debug", "enabled", debug::enabled)?;
         linker.link_syscall("debug", "store_artifact", debug::store_artifact)?;
 
@@ -372,6 +410,10 @@ where
             "total_fil_circ_supply",
             filecoin::total_fil_circ_supply,
         )?;
+        // The network summary is only available from nv26.
+        if linker.network_version() >= NetworkVersion::V26 {
+            linker.link_syscall("network", "summary", filecoin::network_summary)?;
+        }
 
         // Now bind the crypto syscalls.
         linker.link_syscall(
@@ -396,6 +438,11 @@ where
             filecoin::verify_replica_update,
         )?;
         linker.link_syscall("crypto", "batch_verify_seals", filecoin::batch_verify_seals)?;
+        // Batch proof verification is only available from nv26.
+        if linker.network_version() >= NetworkVersion::V26 {
+            linker.link_syscall("crypto", "verify_seal_batch", filecoin::verify_seal_batch)?;
+            linker.link_syscall("crypto", "verify_post_batch", filecoin::verify_post_batch)?;
+        }
 
         Ok(())
     }
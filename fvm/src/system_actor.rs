@@ -5,12 +5,13 @@ use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
 use fvm_ipld_encoding::CborStore;
+use fvm_shared::well_known::WellKnownActor;
 use fvm_shared::ActorID;
 
 use crate::kernel::{ClassifyResult, Result};
 use crate::state_tree::{ActorState, StateTree};
 
-pub const SYSTEM_ACTOR_ID: ActorID = 0;
+pub const SYSTEM_ACTOR_ID: ActorID = WellKnownActor::System.id();
 
 #[derive(Default, Deserialize_tuple, Serialize_tuple)]
 pub struct State {
@@ -0,0 +1,282 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Exporters converting execution traces into formats understood by standard profiling tools:
+//!
+//! - [`to_chrome_trace`] produces Chrome trace event JSON, which can be loaded into
+//!   `chrome://tracing` or [Perfetto](https://ui.perfetto.dev).
+//! - [`to_folded_stacks`] produces the "folded stacks" format consumed by `flamegraph.pl` and
+//!   `inferno-flamegraph`.
+//!
+//! Both exporters only consider the call hierarchy (calls and their returns) and gas charges, plus
+//! gas credits in Chrome traces (as instant events); all other events are ignored.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::ExecutionEvent;
+use crate::gas::GasCharge;
+
+/// The quantity used to measure the "duration" of each gas charge.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Timeline {
+    /// The charged gas, in milligas.
+    #[default]
+    Gas,
+    /// The measured execution time, in nanoseconds. Charges without timing information (e.g., if
+    /// the trace was collected without timing gas charges) are treated as taking no time.
+    Time,
+}
+
+impl Timeline {
+    fn measure(self, charge: &GasCharge) -> u64 {
+        match self {
+            Timeline::Gas => charge.total().as_milligas(),
+            Timeline::Time => charge
+                .elapsed
+                .get()
+                .map(|d| d.as_nanos().try_into().unwrap_or(u64::MAX))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Converts an execution trace into Chrome trace event JSON.
+///
+/// Each call is recorded as a slice spanning all the gas charges made while it was on the stack,
+/// and each gas charge is recorded as a slice of its own. With [`Timeline::Gas`], one microsecond
+/// on the timeline corresponds to one unit of gas.
+pub fn to_chrome_trace(trace: &[ExecutionEvent], timeline: Timeline) -> String {
+    let mut events = Vec::new();
+    let mut open_calls = 0usize;
+    // The current position on the timeline, in milligas or nanoseconds (both 1/1000 of the
+    // microseconds used by the trace format).
+    let mut cursor = 0u64;
+    let ts = |v: u64| v as f64 / 1000.0;
+
+    for event in trace {
+        match event {
+            ExecutionEvent::GasCharge(charge) => {
+                let dur = timeline.measure(charge);
+                events.push(format!(
+                    r#"{{"name":{},"cat":"gas","ph":"X","ts":{},"dur":{},"pid":1,"tid":1,"args":{{"compute_gas":{},"other_gas":{}}}}}"#,
+                    json_string(&charge.name),
+                    ts(cursor),
+                    ts(dur),
+                    charge.compute_gas,
+                    charge.other_gas,
+                ));
+                cursor += dur;
+            }
+            ExecutionEvent::GasCredit(credit) => {
+                events.push(format!(
+                    r#"{{"name":{},"cat":"gas_credit","ph":"i","s":"t","ts":{},"pid":1,"tid":1,"args":{{"amount":{}}}}}"#,
+                    json_string(&credit.name),
+                    ts(cursor),
+                    credit.amount,
+                ));
+            }
+            ExecutionEvent::Call {
+                from,
+                to,
+                method,
+                value,
+                gas_limit,
+                read_only,
+                ..
+            } => {
+                open_calls += 1;
+                events.push(format!(
+                    r#"{{"name":{},"cat":"call","ph":"B","ts":{},"pid":1,"tid":1,"args":{{"from":{},"to":{},"method":{},"value":{},"gas_limit":{},"read_only":{}}}}}"#,
+                    json_string(&call_name(&to.to_string(), *method)),
+                    ts(cursor),
+                    from,
+                    json_string(&to.to_string()),
+                    method,
+                    json_string(&value.to_string()),
+                    gas_limit,
+                    read_only,
+                ));
+            }
+            ExecutionEvent::CallReturn(exit_code, _) if open_calls > 0 => {
+                open_calls -= 1;
+                events.push(format!(
+                    r#"{{"ph":"E","ts":{},"pid":1,"tid":1,"args":{{"exit_code":{}}}}}"#,
+                    ts(cursor),
+                    exit_code.value(),
+                ));
+            }
+            ExecutionEvent::CallError(err) if open_calls > 0 => {
+                open_calls -= 1;
+                events.push(format!(
+                    r#"{{"ph":"E","ts":{},"pid":1,"tid":1,"args":{{"error":{}}}}}"#,
+                    ts(cursor),
+                    json_string(&err.0),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    // Close any calls left open (e.g., if the trace was truncated).
+    for _ in 0..open_calls {
+        events.push(format!(
+            r#"{{"ph":"E","ts":{},"pid":1,"tid":1}}"#,
+            ts(cursor)
+        ));
+    }
+
+    format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+}
+
+/// Converts an execution trace into the folded stacks format, one line per unique stack:
+///
+/// ```text
+/// f01000::2;f01001::3;wasm_exec 12345
+/// ```
+///
+/// Each stack consists of the calls (`<receiver>::<method>`) on the call stack at the time of a
+/// gas charge, followed by the gas charge's name. The weight of each stack is the sum of the gas
+/// charges' durations, as measured by the given [`Timeline`].
+pub fn to_folded_stacks(trace: &[ExecutionEvent], timeline: Timeline) -> String {
+    let mut stack: Vec<String> = Vec::new();
+    let mut weights: BTreeMap<String, u64> = BTreeMap::new();
+
+    for event in trace {
+        match event {
+            ExecutionEvent::GasCharge(charge) => {
+                let weight = timeline.measure(charge);
+                if weight == 0 {
+                    continue;
+                }
+                let mut key = stack.join(";");
+                if !key.is_empty() {
+                    key.push(';');
+                }
+                key.push_str(&folded_frame(&charge.name));
+                *weights.entry(key).or_default() += weight;
+            }
+            ExecutionEvent::Call { to, method, .. } => {
+                stack.push(folded_frame(&call_name(&to.to_string(), *method)));
+            }
+            ExecutionEvent::CallReturn(..) | ExecutionEvent::CallError(_) => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = String::new();
+    for (stack, weight) in weights {
+        writeln!(out, "{} {}", stack, weight).unwrap();
+    }
+    out
+}
+
+fn call_name(to: &str, method: u64) -> String {
+    format!("{}::{}", to, method)
+}
+
+/// Sanitizes a frame name for the folded stacks format, where semicolons separate frames and
+/// whitespace separates the stack from its weight.
+fn folded_frame(name: &str) -> String {
+    name.replace(|c: char| c == ';' || c.is_whitespace(), "_")
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::error::ExitCode;
+
+    use super::*;
+    use crate::gas::{Gas, GasCredit};
+
+    fn charge(name: &'static str, gas: u64, micros: u64) -> ExecutionEvent {
+        let mut charge = GasCharge::new(name, Gas::new(gas), Gas::from_milligas(0));
+        charge.elapsed = Duration::from_micros(micros).into();
+        ExecutionEvent::GasCharge(charge)
+    }
+
+    fn call(to: u64, method: u64) -> ExecutionEvent {
+        ExecutionEvent::Call {
+            from: 100,
+            to: Address::new_id(to),
+            method,
+            params: None,
+            value: TokenAmount::from_atto(0),
+            gas_limit: 1000,
+            read_only: false,
+        }
+    }
+
+    fn test_trace() -> Vec<ExecutionEvent> {
+        vec![
+            charge("OnChainMessage", 10, 1),
+            call(1000, 2),
+            charge("wasm_exec", 5, 2),
+            call(1001, 3),
+            charge("wasm_exec", 7, 3),
+            ExecutionEvent::CallReturn(ExitCode::OK, None),
+            charge("wasm_exec", 1, 4),
+            ExecutionEvent::GasCredit(GasCredit::new("OnStateDelete", Gas::new(2))),
+            ExecutionEvent::CallReturn(ExitCode::OK, None),
+        ]
+    }
+
+    #[test]
+    fn folded_stacks() {
+        assert_eq!(
+            to_folded_stacks(&test_trace(), Timeline::Gas),
+            "OnChainMessage 10000\n\
+             f01000::2;f01001::3;wasm_exec 7000\n\
+             f01000::2;wasm_exec 6000\n"
+        );
+        assert_eq!(
+            to_folded_stacks(&test_trace(), Timeline::Time),
+            "OnChainMessage 1000\n\
+             f01000::2;f01001::3;wasm_exec 3000\n\
+             f01000::2;wasm_exec 6000\n"
+        );
+    }
+
+    #[test]
+    fn chrome_trace() {
+        let json = to_chrome_trace(&test_trace(), Timeline::Gas);
+        assert!(json.starts_with("{\"traceEvents\":["));
+        assert_eq!(json.matches(r#""ph":"B""#).count(), 2);
+        assert_eq!(json.matches(r#""ph":"E""#).count(), 2);
+        assert_eq!(json.matches(r#""ph":"X""#).count(), 4);
+        assert!(json.contains(
+            r#"{"name":"OnStateDelete","cat":"gas_credit","ph":"i","s":"t","ts":23,"pid":1,"tid":1,"args":{"amount":2.000}}"#
+        ));
+        // The nested call starts after 10 + 5 gas.
+        assert!(json.contains(r#"{"name":"f01001::3","cat":"call","ph":"B","ts":15,"#));
+        // And the last charge ends after 23 gas.
+        assert!(json.contains(r#""ph":"E","ts":23,"#));
+    }
+
+    #[test]
+    fn json_escaping() {
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), r#""a\"b\\c\n\u0001""#);
+    }
+}
@@ -1,15 +1,19 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use cid::Cid;
 use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
 use fvm_shared::state::ActorState;
+use fvm_shared::sys::LogLevel;
 use fvm_shared::{ActorID, MethodNum};
 
-use crate::gas::GasCharge;
+use crate::gas::{Gas, GasCharge, GasCredit};
 use crate::kernel::SyscallError;
 
+pub mod export;
+
 /// Execution Trace, only for informational and debugging purposes.
 pub type ExecutionTrace = Vec<ExecutionEvent>;
 
@@ -20,6 +24,9 @@ pub type ExecutionTrace = Vec<ExecutionEvent>;
 #[non_exhaustive]
 pub enum ExecutionEvent {
     GasCharge(GasCharge),
+    /// Gas credited back to the message (e.g., for deleting state). The credit is refunded at the
+    /// end of the message, up to a cap, unless the call that earned it is reverted.
+    GasCredit(GasCredit),
     /// Emitted on each send call regardless whether we actually end up invoking the
     /// actor or not (e.g. if we don't have enough gas or if the actor does not exist)
     Call {
@@ -39,4 +46,43 @@ pub enum ExecutionEvent {
         state: ActorState,
     },
     Log(String),
+    /// A structured log record emitted by an actor (in debug mode).
+    StructuredLog {
+        actor: ActorID,
+        level: LogLevel,
+        message: String,
+        fields: Vec<(String, String)>,
+    },
+    /// Emitted when an invocation of an actor successfully returns (committing its changes) after
+    /// changing that actor's state root. Dropped from the trace if a calling actor then fails,
+    /// reverting the change.
+    StateRootChange {
+        actor: ActorID,
+        old_root: Cid,
+        new_root: Cid,
+    },
+}
+
+/// A summary of a single call frame (an invocation of an actor method through a send), recorded
+/// when tracing. Frames are listed in the order in which they were entered, so each frame is
+/// followed by its subcalls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallFrameSummary {
+    /// The depth of the call, from 0 for the message's top-level call.
+    pub depth: u32,
+    pub caller: ActorID,
+    pub callee: Address,
+    pub method: MethodNum,
+    /// The call's exit code, or `None` if it failed with a syscall error (e.g., because the callee
+    /// doesn't exist) or a fatal error.
+    pub exit_code: Option<ExitCode>,
+    /// The gas used by the call, including its subcalls.
+    pub gas_used: Gas,
+    /// The gas used by the call itself, excluding its subcalls.
+    pub self_gas_used: Gas,
+    /// The number of events emitted by the callee in this call (not by its subcalls), including
+    /// events that were later discarded because this call or one of its callers reverted.
+    pub events_emitted: u64,
+    /// The number of blocks written by the callee in this call (not by its subcalls).
+    pub blocks_written: u64,
 }
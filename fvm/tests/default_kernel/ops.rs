@@ -431,6 +431,631 @@ mod ipld {
 
         Ok(())
     }
+
+    #[test]
+    fn quota() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        let before = kern.block_quota()?;
+        kern.block_create(IPLD_RAW, "foo".as_bytes())?;
+        let after = kern.block_quota()?;
+
+        assert_eq!(after.blocks_remaining, before.blocks_remaining - 1);
+        assert_eq!(after.bytes_remaining, before.bytes_remaining - 3);
+
+        let (call_manager, _) = kern.into_inner();
+        let price_list = call_manager.machine.context().price_list;
+        assert_eq!(
+            call_manager.gas_tracker.gas_used(),
+            price_list.on_block_create(3, 0).total()
+                + price_list.on_block_stat().total()
+                + price_list.on_block_stat().total(),
+            "querying the quota should be priced like a block stat"
+        );
+        Ok(())
+    }
+}
+
+mod actor {
+    #[cfg(feature = "m2-native")]
+    use cid::Cid;
+    use fvm::engine::EnginePool;
+    use fvm::init_actor::INIT_ACTOR_ID;
+    use fvm::kernel::ActorOps;
+    #[cfg(feature = "m2-native")]
+    use fvm::machine::Machine;
+    #[cfg(feature = "m2-native")]
+    use fvm_ipld_blockstore::Blockstore;
+    #[cfg(feature = "m2-native")]
+    use fvm_ipld_encoding::IPLD_RAW;
+    use fvm_shared::version::NetworkVersion;
+    #[cfg(feature = "m2-native")]
+    use multihash_codetable::MultihashDigest;
+    use num_traits::Zero;
+
+    use super::*;
+
+    /// A kernel for the init actor, with an engine, at the given network version.
+    fn init_kernel(network_version: NetworkVersion) -> anyhow::Result<TestingKernel> {
+        let (mut call_manager, _) = dummy::DummyCallManager::new_stub();
+        call_manager.machine.ctx.network.network_version = network_version;
+        call_manager.engine =
+            Some(EnginePool::new((&call_manager.machine.ctx.network).into())?.acquire());
+        #[cfg(feature = "m2-native")]
+        {
+            use fvm::state_tree::ActorState;
+            use fvm_ipld_encoding::CborStore;
+
+            let store = call_manager.machine.state_tree.store();
+            let state =
+                store.put_cbor(&fvm::init_actor::State::new_test(store), Code::Blake2b256)?;
+            let code = *call_manager.machine.builtin_actors.get_init_code();
+            call_manager.machine.state_tree.set_actor(
+                INIT_ACTOR_ID,
+                ActorState::new(code, state, Zero::zero(), 0, None),
+            );
+        }
+        Ok(TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            INIT_ACTOR_ID,
+            0,
+            Zero::zero(),
+            false,
+        ))
+    }
+
+    #[test]
+    #[cfg(not(feature = "m2-native"))]
+    fn install_code_requires_m2_native() -> anyhow::Result<()> {
+        // Installed code is recorded in the init actor's state, which only tracks it with M2
+        // native.
+        let mut kern = init_kernel(NetworkVersion::V26)?;
+        expect_syscall_err!(IllegalOperation, kern.install_code(b"\0asm\x01\0\0\0"));
+        let (call_manager, _) = kern.into_inner();
+        assert!(call_manager.gas_tracker.gas_used().is_zero());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "m2-native")]
+    fn install_code() -> anyhow::Result<()> {
+        let mut kern = init_kernel(NetworkVersion::V26)?;
+
+        // A module with a single empty function.
+        let code: &[u8] = &[
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+            0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section: () -> ()
+            0x03, 0x02, 0x01, 0x00, // function section
+            0x0a, 0x04, 0x01, 0x02, 0x00, 0x0b, // code section
+        ];
+        let code_cid = kern.install_code(code)?;
+        assert_eq!(
+            code_cid,
+            Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(code))
+        );
+
+        let (call_manager, _) = kern.into_inner();
+        assert_eq!(
+            call_manager.machine.blockstore().get(&code_cid)?.as_deref(),
+            Some(code),
+            "the code should be stored under its CID"
+        );
+        let (state, _) = fvm::init_actor::State::load(call_manager.machine.state_tree())?;
+        assert!(
+            state.is_code_installed(call_manager.machine.blockstore(), &code_cid)?,
+            "the code should be recorded in the init actor's state"
+        );
+        assert_eq!(
+            call_manager.gas_tracker.gas_used(),
+            call_manager
+                .machine
+                .context()
+                .price_list
+                .on_install_code(code.len())
+                .total()
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "m2-native")]
+    fn install_code_invalid() -> anyhow::Result<()> {
+        let mut kern = init_kernel(NetworkVersion::V26)?;
+
+        // Code the engine can't compile is rejected, and not stored.
+        expect_syscall_err!(IllegalArgument, kern.install_code(b"not wasm"));
+        let (call_manager, _) = kern.into_inner();
+        let code_cid = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"not wasm"));
+        assert!(!call_manager.machine.blockstore().has(&code_cid)?);
+        Ok(())
+    }
+
+    #[test]
+    fn install_code_unsupported() -> anyhow::Result<()> {
+        // Installing code isn't supported before nv26, even by the init actor.
+        for nv in [NetworkVersion::V21, NetworkVersion::V25] {
+            let mut kern = init_kernel(nv)?;
+            expect_syscall_err!(IllegalOperation, kern.install_code(b"\0asm\x01\0\0\0"));
+
+            let (call_manager, _) = kern.into_inner();
+            assert!(
+                call_manager.gas_tracker.gas_used().is_zero(),
+                "rejected installs should not charge gas"
+            );
+        }
+        Ok(())
+    }
+}
+
+mod debug {
+    use fvm::kernel::{BlockRegistry, DebugOps};
+    use num_traits::Zero;
+
+    use super::*;
+
+    #[test]
+    fn artifact_invocation() -> anyhow::Result<()> {
+        let (call_manager, test_data) = dummy::DummyCallManager::new_stub();
+
+        // This kernel runs the message's second invocation.
+        test_data.borrow_mut().invocation_count = 2;
+        let mut kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            0,
+            0,
+            Zero::zero(),
+            false,
+        );
+
+        // The actor makes more calls before storing an artifact.
+        test_data.borrow_mut().invocation_count = 5;
+        kern.store_artifact("artifact", b"data")?;
+
+        let test_data = test_data.borrow();
+        let artifacts = &test_data.artifacts;
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(
+            artifacts[0].invocation, 2,
+            "artifacts should be attributed to the invocation that stored them"
+        );
+        assert_eq!(artifacts[0].data, b"data");
+
+        Ok(())
+    }
+}
+
+mod network {
+    use fvm::kernel::default::MAX_TIPSET_CID_LOOKBACK;
+    use fvm::kernel::{BlockRegistry, NetworkOps};
+    use fvm_shared::version::NetworkVersion;
+    use num_traits::Zero;
+
+    use super::*;
+
+    #[test]
+    fn tipset_cid_lookback() -> anyhow::Result<()> {
+        const EPOCH: i64 = 2000;
+
+        for nv in [NetworkVersion::V25, NetworkVersion::V26] {
+            let (mut call_manager, _) = dummy::DummyCallManager::new_stub();
+            call_manager.machine.ctx.epoch = EPOCH;
+            call_manager.machine.ctx.network.network_version = nv;
+            let kern = TestingKernel::new(
+                call_manager,
+                BlockRegistry::default(),
+                0,
+                0,
+                0,
+                Zero::zero(),
+                false,
+            );
+
+            kern.tipset_cid(EPOCH - 1)?;
+            kern.tipset_cid(EPOCH - MAX_TIPSET_CID_LOOKBACK)?;
+            expect_syscall_err!(IllegalArgument, kern.tipset_cid(EPOCH));
+            expect_syscall_err!(IllegalArgument, kern.tipset_cid(-1));
+
+            // The lookback is only bounded from nv26.
+            let res = kern.tipset_cid(EPOCH - MAX_TIPSET_CID_LOOKBACK - 1);
+            if nv >= NetworkVersion::V26 {
+                expect_syscall_err!(LimitExceeded, res);
+            } else {
+                res?;
+            }
+        }
+        Ok(())
+    }
+}
+
+mod read_only {
+    use cid::Cid;
+    use fvm::call_manager::NO_DATA_BLOCK_ID;
+    use fvm::init_actor::INIT_ACTOR_ID;
+    use fvm::kernel::{ActorOps, EventOps, SelfOps, SendOps, UpgradeOps};
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::sys::SendFlags;
+    use fvm_shared::version::NetworkVersion;
+    use num_traits::Zero;
+
+    use super::*;
+
+    /// A read-only kernel for the init actor, at a network version supporting every syscall.
+    fn read_only_kernel() -> TestingKernel {
+        let (mut call_manager, _) = dummy::DummyCallManager::new_stub();
+        call_manager.machine.ctx.network.network_version = NetworkVersion::V26;
+        TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            INIT_ACTOR_ID,
+            0,
+            Zero::zero(),
+            true,
+        )
+    }
+
+    #[test]
+    fn mutating_syscalls() -> anyhow::Result<()> {
+        let mut kern = read_only_kernel();
+        let cid = Cid::default();
+        let value = TokenAmount::from_atto(1);
+
+        expect_syscall_err!(ReadOnly, kern.set_root(cid));
+        expect_syscall_err!(ReadOnly, kern.self_destruct(true));
+        expect_syscall_err!(ReadOnly, kern.create_actor(cid, 100, None));
+        expect_syscall_err!(ReadOnly, kern.install_code(b"\0asm\x01\0\0\0"));
+        expect_syscall_err!(
+            ReadOnly,
+            UpgradeOps::<TestingKernel>::upgrade_actor(&mut kern, cid, NO_DATA_BLOCK_ID)
+                .map(|_| ())
+        );
+        expect_syscall_err!(ReadOnly, kern.emit_event(&[], &[], &[]));
+        expect_syscall_err!(
+            ReadOnly,
+            SendOps::<TestingKernel>::send(
+                &mut kern,
+                &Address::new_id(100),
+                0,
+                NO_DATA_BLOCK_ID,
+                &value,
+                None,
+                SendFlags::empty(),
+            )
+            .map(|_| ())
+        );
+
+        let (call_manager, _) = kern.into_inner();
+        assert!(
+            call_manager.gas_tracker.gas_used().is_zero(),
+            "rejected syscalls should not charge gas"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_only_send_with_value() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        // Even a writable kernel can't transfer value in a read-only send.
+        expect_syscall_err!(
+            ReadOnly,
+            SendOps::<TestingKernel>::send(
+                &mut kern,
+                &Address::new_id(100),
+                0,
+                NO_DATA_BLOCK_ID,
+                &TokenAmount::from_atto(1),
+                None,
+                SendFlags::READ_ONLY,
+            )
+            .map(|_| ())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn writable() -> anyhow::Result<()> {
+        let mut kern = read_only_kernel();
+        let err = kern
+            .writable(fvm::kernel::read_only::MutatingOp::SetRoot)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("update the state-root"), "{err}");
+
+        let (mut kern, _) = build_inspecting_test()?;
+        assert!(kern
+            .writable(fvm::kernel::read_only::MutatingOp::SetRoot)
+            .is_ok());
+        Ok(())
+    }
+}
+
+mod crypto {
+    use fvm::kernel::{CryptoOps, SupportedHashes};
+
+    use super::*;
+
+    #[test]
+    fn extended_hashes_unsupported() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+
+        // Blake3 and sha3-512 aren't supported at the stub network version.
+        for hasher in [SupportedHashes::Blake3_256, SupportedHashes::Sha3_512] {
+            expect_syscall_err!(IllegalArgument, kern.hash(hasher.into(), b"foo"));
+        }
+        assert!(kern.hash(SupportedHashes::Sha2_256.into(), b"foo").is_ok());
+        Ok(())
+    }
+}
+
+mod event {
+    use fvm::kernel::EventOps;
+    use fvm::machine::EventLimits;
+    use fvm_shared::event::Flags;
+    use fvm_shared::sys::EventEntry;
+    use fvm_shared::IPLD_RAW;
+
+    use super::*;
+
+    fn entry(key_len: usize, val_len: usize) -> EventEntry {
+        EventEntry {
+            flags: Flags::FLAG_INDEXED_ALL,
+            codec: IPLD_RAW,
+            key_len: key_len as u32,
+            val_len: val_len as u32,
+        }
+    }
+
+    fn kernel_with_limits(limits: EventLimits) -> TestingKernel {
+        let (mut call_manager, _) = dummy::DummyCallManager::new_stub();
+        call_manager.machine.ctx.network.event_limits(limits);
+        TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            0,
+            0,
+            Zero::zero(),
+            false,
+        )
+    }
+
+    #[test]
+    fn limits() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+        let key = "k".repeat(32);
+        let value = vec![0u8; 9 << 10];
+
+        // Within the default limits.
+        kern.emit_event(
+            &[entry(31, 8 << 10)],
+            &key.as_bytes()[..31],
+            &value[..8 << 10],
+        )?;
+
+        // Each default limit is enforced, and named in the error.
+        let err = kern
+            .emit_event(&[entry(32, 1)], key.as_bytes(), &value[..1])
+            .unwrap_err();
+        assert!(err.to_string().contains("max_key_len"), "{err}");
+        let err = kern
+            .emit_event(&[entry(1, 9 << 10)], &key.as_bytes()[..1], &value)
+            .unwrap_err();
+        assert!(err.to_string().contains("max_total_values_len"), "{err}");
+        let entries: Vec<_> = (0..256).map(|_| entry(0, 0)).collect();
+        let err = kern.emit_event(&entries, &[], &[]).unwrap_err();
+        assert!(err.to_string().contains("max_entries"), "{err}");
+        expect_syscall_err!(LimitExceeded, kern.emit_event(&entries, &[], &[]));
+
+        // The limits can be raised...
+        let mut kern = kernel_with_limits(EventLimits {
+            max_entries: 256,
+            max_key_len: 32,
+            max_total_values_len: 9 << 10,
+        });
+        kern.emit_event(&entries, &[], &[])?;
+        kern.emit_event(&[entry(32, 9 << 10)], key.as_bytes(), &value)?;
+
+        // ...and lowered.
+        let mut kern = kernel_with_limits(EventLimits {
+            max_entries: 1,
+            ..Default::default()
+        });
+        expect_syscall_err!(
+            LimitExceeded,
+            kern.emit_event(&[entry(0, 0), entry(0, 0)], &[], &[])
+        );
+        Ok(())
+    }
+}
+
+mod filecoin {
+    use cid::Cid;
+    use fvm::kernel::filecoin::{DefaultFilecoinKernel, FilecoinKernel, NetworkSummary};
+    use fvm::machine::{Machine, Manifest};
+    use fvm::state_tree::ActorState;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_shared::bigint::bigint_ser::BigIntSer;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::randomness::Randomness;
+    use fvm_shared::sector::{
+        PoStProof, RegisteredPoStProof, RegisteredSealProof, SealVerifyInfo, SectorID,
+        StoragePower, WindowPoStVerifyInfo,
+    };
+    use fvm_shared::well_known::WellKnownActor;
+    use serde::Serialize;
+
+    use super::*;
+
+    /// Builds a kernel with the power and reward actors set to the given states, running the
+    /// builtin actors' code unless `code` is given.
+    fn kernel_with_states(
+        power_state: &impl Serialize,
+        reward_state: &impl Serialize,
+        code: Option<Cid>,
+    ) -> anyhow::Result<DefaultFilecoinKernel<DummyCallManager>> {
+        let (mut kern, _) = build_inspecting_test()?;
+        let machine = &mut kern.call_manager.machine;
+        let power_root = machine
+            .blockstore()
+            .put_cbor(power_state, Code::Blake2b256)?;
+        let reward_root = machine
+            .blockstore()
+            .put_cbor(reward_state, Code::Blake2b256)?;
+        for (actor, root) in [
+            (WellKnownActor::StoragePower, power_root),
+            (WellKnownActor::Reward, reward_root),
+        ] {
+            let code = match code {
+                Some(code) => code,
+                None => *machine
+                    .builtin_actors()
+                    .code_by_name(actor.code_name())
+                    .unwrap(),
+            };
+            machine.state_tree_mut().set_actor(
+                actor.id(),
+                ActorState::new(code, root, TokenAmount::default(), 0, None),
+            );
+        }
+        Ok(DefaultFilecoinKernel(kern))
+    }
+
+    #[test]
+    fn network_summary() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+
+        // The power and reward actors don't exist.
+        expect_syscall_err!(
+            IllegalOperation,
+            DefaultFilecoinKernel(kern).network_summary()
+        );
+
+        let summary = NetworkSummary {
+            raw_byte_power: StoragePower::from(1u128 << 64),
+            quality_adj_power: StoragePower::from(10u128 << 64),
+            total_pledge_collateral: TokenAmount::from_whole(1_000_000),
+            this_epoch_reward: TokenAmount::from_whole(100),
+        };
+        let zero = StoragePower::default();
+        let zero_fil = TokenAmount::default();
+
+        // The leading fields of the power and reward actors' states, followed by a field the
+        // FVM doesn't read.
+        let power_state = (
+            BigIntSer(&summary.raw_byte_power),
+            BigIntSer(&zero),
+            BigIntSer(&summary.quality_adj_power),
+            BigIntSer(&zero),
+            &summary.total_pledge_collateral,
+            BigIntSer(&zero),
+        );
+        let reward_state = (
+            BigIntSer(&zero),
+            BigIntSer(&zero),
+            0i64,
+            BigIntSer(&zero),
+            &summary.this_epoch_reward,
+            &zero_fil,
+        );
+
+        let kern = kernel_with_states(&power_state, &reward_state, None)?;
+        assert_eq!(kern.network_summary()?, summary);
+
+        // Actors that aren't running the builtin actors' code aren't read...
+        let system_code = *Manifest::dummy().get_system_code();
+        let kern = kernel_with_states(&power_state, &reward_state, Some(system_code))?;
+        expect_syscall_err!(IllegalOperation, kern.network_summary());
+
+        // ...and states the FVM can't decode (e.g., from a later actors version) are reported to
+        // the caller, not as a fatal error.
+        let kern = kernel_with_states(&(BigIntSer(&zero),), &reward_state, None)?;
+        expect_syscall_err!(IllegalOperation, kern.network_summary());
+        Ok(())
+    }
+
+    fn seal(miner: u64) -> SealVerifyInfo {
+        SealVerifyInfo {
+            registered_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+            sector_id: SectorID { miner, number: 1 },
+            deal_ids: Vec::new(),
+            randomness: Randomness(vec![0; 32]),
+            interactive_randomness: Randomness(vec![0; 32]),
+            proof: vec![0; 1920],
+            // Not a replica commitment.
+            sealed_cid: Cid::default(),
+            unsealed_cid: Cid::default(),
+        }
+    }
+
+    #[test]
+    fn verify_seal_batch() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+        let kern = DefaultFilecoinKernel(kern);
+
+        // Empty batches are free.
+        assert_eq!(kern.verify_seal_batch(&[])?, Vec::<bool>::new());
+        assert!(kern.0.call_manager.gas_tracker.gas_used().is_zero());
+
+        // Invalid seals fail their own verification, and the whole batch is charged up-front.
+        let seals = [seal(1), seal(2), seal(3)];
+        assert_eq!(kern.verify_seal_batch(&seals)?, vec![false; 3]);
+        let price_list = kern.0.call_manager.machine.context().price_list;
+        assert_eq!(
+            kern.0.call_manager.gas_tracker.gas_used(),
+            price_list.on_verify_seal_batch(&seals).total()
+        );
+        assert_eq!(
+            price_list.on_verify_seal_batch(&seals).total(),
+            price_list.on_verify_seal_batch(&seals[..1]).total() * 3u32
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn verify_post_batch() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+        let kern = DefaultFilecoinKernel(kern);
+
+        assert_eq!(kern.verify_post_batch(&[])?, Vec::<bool>::new());
+        assert!(kern.0.call_manager.gas_tracker.gas_used().is_zero());
+
+        let proof = |post_proof| PoStProof {
+            post_proof,
+            proof_bytes: vec![0; 192],
+        };
+        let posts = [
+            // No proofs at all.
+            WindowPoStVerifyInfo {
+                prover: 1,
+                ..Default::default()
+            },
+            // Mixed proof types.
+            WindowPoStVerifyInfo {
+                proofs: vec![
+                    proof(RegisteredPoStProof::StackedDRGWindow32GiBV1P1),
+                    proof(RegisteredPoStProof::StackedDRGWindow64GiBV1P1),
+                ],
+                prover: 2,
+                ..Default::default()
+            },
+        ];
+        assert_eq!(kern.verify_post_batch(&posts)?, vec![false; 2]);
+
+        // Charged as verifying each PoSt individually.
+        let price_list = kern.0.call_manager.machine.context().price_list;
+        assert_eq!(
+            kern.0.call_manager.gas_tracker.gas_used(),
+            price_list.on_verify_post(&posts[0]).total()
+                + price_list.on_verify_post(&posts[1]).total()
+        );
+        Ok(())
+    }
 }
 
 mod gas {
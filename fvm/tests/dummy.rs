@@ -6,7 +6,9 @@ use std::rc::Rc;
 
 use anyhow::Context;
 use cid::Cid;
-use fvm::call_manager::{Backtrace, CallManager, Entrypoint, FinishRet, InvocationResult};
+use fvm::call_manager::{
+    Artifact, Backtrace, CallManager, Entrypoint, FinishRet, InvocationResult,
+};
 use fvm::engine::Engine;
 use fvm::externs::{Chain, Consensus, Externs, Rand};
 use fvm::gas::{Gas, GasCharge, GasTimer, GasTracker};
@@ -192,21 +194,26 @@ pub struct DummyCallManager {
     pub origin_address: Address,
     pub nonce: u64,
     pub test_data: Rc<RefCell<TestData>>,
+    /// The engine, if any: stub call managers have none.
+    pub engine: Option<Engine>,
     limits: DummyLimiter,
 }
 
 /// Information to be read by external tests
+#[derive(Default)]
 pub struct TestData {
     pub charge_gas_calls: usize,
+    /// The invocation count reported by the call manager.
+    pub invocation_count: u64,
+    /// The artifacts stored through the call manager.
+    pub artifacts: Vec<Artifact>,
 }
 
 const BLOCK_GAS_LIMIT: Gas = Gas::new(fvm_shared::BLOCK_GAS_LIMIT);
 
 impl DummyCallManager {
     pub fn new_stub() -> (Self, Rc<RefCell<TestData>>) {
-        let rc = Rc::new(RefCell::new(TestData {
-            charge_gas_calls: 0,
-        }));
+        let rc = Rc::new(RefCell::new(TestData::default()));
         let cell_ref = rc.clone();
         (
             Self {
@@ -215,6 +222,7 @@ impl DummyCallManager {
                 origin: 0,
                 nonce: 0,
                 test_data: rc,
+                engine: None,
                 limits: DummyLimiter::default(),
                 origin_address: Address::new_id(0),
                 gas_premium: TokenAmount::zero(),
@@ -224,9 +232,7 @@ impl DummyCallManager {
     }
 
     pub fn new_with_gas(gas_tracker: GasTracker) -> (Self, Rc<RefCell<TestData>>) {
-        let rc = Rc::new(RefCell::new(TestData {
-            charge_gas_calls: 0,
-        }));
+        let rc = Rc::new(RefCell::new(TestData::default()));
         let cell_ref = rc.clone();
         (
             Self {
@@ -235,6 +241,7 @@ impl DummyCallManager {
                 origin: 0,
                 nonce: 0,
                 test_data: rc,
+                engine: None,
                 limits: DummyLimiter::default(),
                 origin_address: Address::new_id(0),
                 gas_premium: TokenAmount::zero(),
@@ -249,7 +256,7 @@ impl CallManager for DummyCallManager {
 
     fn new(
         machine: Self::Machine,
-        _engine: Engine,
+        engine: Engine,
         _gas_limit: u64,
         origin: ActorID,
         origin_address: Address,
@@ -258,9 +265,7 @@ impl CallManager for DummyCallManager {
         nonce: u64,
         gas_premium: TokenAmount,
     ) -> Self {
-        let rc = Rc::new(RefCell::new(TestData {
-            charge_gas_calls: 0,
-        }));
+        let rc = Rc::new(RefCell::new(TestData::default()));
         let limits = machine.new_limiter();
         Self {
             machine,
@@ -270,6 +275,7 @@ impl CallManager for DummyCallManager {
             origin_address,
             nonce,
             test_data: rc,
+            engine: Some(engine),
             limits,
         }
     }
@@ -300,13 +306,17 @@ impl CallManager for DummyCallManager {
         (
             Ok(FinishRet {
                 gas_used: 0,
+                gas_credit: 0,
                 backtrace: Backtrace {
                     frames: Vec::new(),
                     cause: None,
                 },
                 exec_trace: Vec::new(),
+                call_frames: Vec::new(),
                 events: Vec::new(),
                 events_root: None,
+                artifacts: Vec::new(),
+                logs: Vec::new(),
             }),
             self.machine,
         )
@@ -321,7 +331,9 @@ impl CallManager for DummyCallManager {
     }
 
     fn engine(&self) -> &Engine {
-        todo!()
+        self.engine
+            .as_ref()
+            .expect("the call manager has no engine")
     }
 
     fn gas_tracker(&self) -> &GasTracker {
@@ -363,15 +375,21 @@ impl CallManager for DummyCallManager {
     }
 
     fn invocation_count(&self) -> u64 {
-        todo!()
+        RefCell::borrow(&self.test_data).invocation_count
     }
 
     fn limiter_mut(&mut self) -> &mut <Self::Machine as Machine>::Limiter {
         &mut self.limits
     }
 
-    fn append_event(&mut self, _evt: StampedEvent) {
-        todo!()
+    fn append_event(&mut self, _evt: StampedEvent) {}
+
+    fn acquire_reentry_guard(&mut self, _actor: ActorID) {}
+
+    fn release_reentry_guard(&mut self, _actor: ActorID) {}
+
+    fn store_artifact(&mut self, artifact: Artifact) {
+        self.test_data.borrow_mut().artifacts.push(artifact);
     }
 
     fn resolve_address(&self, address: &Address) -> fvm::kernel::Result<Option<ActorID>> {
@@ -2,5 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 mod default_kernel;
 mod dummy;
+mod preflight;
 
 use dummy::*;
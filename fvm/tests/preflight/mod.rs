@@ -0,0 +1,142 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm::engine::{Diagnostic, EngineConfig, EnginePool};
+use fvm::kernel::default::DefaultKernel;
+use fvm::kernel::filecoin::DefaultFilecoinKernel;
+use fvm::machine::NetworkConfig;
+use fvm_shared::version::NetworkVersion;
+
+use super::*;
+
+type TestingKernel = DefaultKernel<DummyCallManager>;
+
+fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+    let mut s = vec![id, contents.len() as u8];
+    s.extend_from_slice(contents);
+    s
+}
+
+/// Builds a module with a single `(func (param i32) (result i32))`, a memory of `pages` pages
+/// (LEB128 encoded), and optionally an unknown import and the exports actors must provide.
+fn module(pages: &[u8], import: bool, exports: bool) -> Vec<u8> {
+    let mut m = b"\0asm\x01\0\0\0".to_vec();
+    // Type: (i32) -> i32
+    m.extend(section(1, &[1, 0x60, 1, 0x7f, 1, 0x7f]));
+    if import {
+        // (import "env" "foo" (func (type 0)))
+        m.extend(section(
+            2,
+            &[1, 3, b'e', b'n', b'v', 3, b'f', b'o', b'o', 0, 0],
+        ));
+    }
+    m.extend(section(3, &[1, 0]));
+    let mut memory = vec![1, 0];
+    memory.extend_from_slice(pages);
+    m.extend(section(5, &memory));
+    if exports {
+        let func = import as u8;
+        let mut e = vec![2];
+        e.extend_from_slice(&[6, b'm', b'e', b'm', b'o', b'r', b'y', 2, 0]);
+        e.extend_from_slice(&[6, b'i', b'n', b'v', b'o', b'k', b'e', 0, func]);
+        m.extend(section(7, &e));
+    }
+    // Body: (i32.const 0)
+    m.extend(section(10, &[1, 4, 0, 0x41, 0, 0x0b]));
+    m
+}
+
+/// Builds a valid actor module importing the syscall `module::name`, which takes `params` `i32`s.
+fn module_importing(module: &str, name: &str, params: u8) -> Vec<u8> {
+    let mut m = b"\0asm\x01\0\0\0".to_vec();
+    // Types: (i32) -> i32, (i32, ...) -> i32
+    let mut types = vec![2, 0x60, 1, 0x7f, 1, 0x7f, 0x60, params];
+    types.extend(std::iter::repeat(0x7f).take(params as usize));
+    types.extend_from_slice(&[1, 0x7f]);
+    m.extend(section(1, &types));
+    let mut import = vec![1, module.len() as u8];
+    import.extend_from_slice(module.as_bytes());
+    import.push(name.len() as u8);
+    import.extend_from_slice(name.as_bytes());
+    import.extend_from_slice(&[0, 1]);
+    m.extend(section(2, &import));
+    m.extend(section(3, &[1, 0]));
+    m.extend(section(5, &[1, 0, 1]));
+    let mut e = vec![2];
+    e.extend_from_slice(&[6, b'm', b'e', b'm', b'o', b'r', b'y', 2, 0]);
+    e.extend_from_slice(&[6, b'i', b'n', b'v', b'o', b'k', b'e', 0, 1]);
+    m.extend(section(7, &e));
+    // Body: (i32.const 0)
+    m.extend(section(10, &[1, 4, 0, 0x41, 0, 0x0b]));
+    m
+}
+
+#[test]
+fn preflight_validate() {
+    let engine = EnginePool::new(EngineConfig::from(&NetworkConfig::new(NetworkVersion::V21)))
+        .unwrap()
+        .acquire();
+
+    let valid = module(&[1], false, true);
+    let report = engine.preflight_validate::<TestingKernel>(&valid, NetworkVersion::V21);
+    assert!(report.is_valid(), "{:?}", report.diagnostics);
+    assert_eq!(report.code_size, valid.len());
+    assert!(report.instrumented_size.unwrap() > valid.len());
+
+    let report = engine.preflight_validate::<TestingKernel>(b"not wasm", NetworkVersion::V21);
+    assert!(matches!(
+        &report.diagnostics[..],
+        [Diagnostic::InvalidWasm(_)]
+    ));
+    assert_eq!(report.instrumented_size, None);
+
+    let report =
+        engine.preflight_validate::<TestingKernel>(&module(&[1], true, true), NetworkVersion::V21);
+    assert!(matches!(&report.diagnostics[..], [Diagnostic::Link(_)]));
+
+    // 10000 pages exceeds the default 512MiB limit.
+    let report = engine.preflight_validate::<TestingKernel>(
+        &module(&[0x90, 0x4e], false, false),
+        NetworkVersion::V21,
+    );
+    assert_eq!(
+        report.diagnostics,
+        [
+            Diagnostic::MemoryTooLarge {
+                size: 10000 << 16,
+                limit: 512 << 20,
+            },
+            Diagnostic::MissingExport("memory"),
+            Diagnostic::MissingExport("invoke"),
+        ]
+    );
+}
+
+#[test]
+fn preflight_validate_network_version() {
+    type FilecoinKernel = DefaultFilecoinKernel<DummyCallManager>;
+
+    let engine = EnginePool::new(EngineConfig::from(&NetworkConfig::new(NetworkVersion::V21)))
+        .unwrap()
+        .acquire();
+
+    // Syscalls introduced in nv26 are only linked from nv26.
+    for (module, name, params) in [
+        ("crypto", "verify_seal_batch", 3),
+        ("crypto", "verify_post_batch", 3),
+        ("network", "summary", 1),
+        ("ipld", "block_quota", 1),
+        ("debug", "log_structured", 5),
+    ] {
+        let wasm = module_importing(module, name, params);
+        for nv in [NetworkVersion::V21, NetworkVersion::V25] {
+            let report = engine.preflight_validate::<FilecoinKernel>(&wasm, nv);
+            assert!(
+                matches!(&report.diagnostics[..], [Diagnostic::Link(_)]),
+                "{name} at nv{nv}: {:?}",
+                report.diagnostics
+            );
+        }
+        let report = engine.preflight_validate::<FilecoinKernel>(&wasm, NetworkVersion::V26);
+        assert!(report.is_valid(), "{name}: {:?}", report.diagnostics);
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Representative AMT workloads, run against several bit widths so that their relative
+//! performance can be compared.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const ITEM_COUNT: usize = 1000;
+const CHURN_ROUNDS: usize = 20;
+const CHURN_BATCH: usize = 10;
+/// Random indices are drawn from `0..SPARSE_RANGE`.
+const SPARSE_RANGE: u64 = 1 << 24;
+
+const BIT_WIDTHS: &[u32] = &[3, 5, 8];
+
+/// Deterministic pseudo-random indices.
+fn random_indices(n: usize, seed: u64) -> Vec<u64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n).map(|_| rng.gen_range(0..SPARSE_RANGE)).collect()
+}
+
+/// Appends to the end of the array, as queues and event logs do.
+fn sequential_append(c: &mut Criterion) {
+    let mut group = c.benchmark_group("AMT sequential append and flush");
+    for &bit_width in BIT_WIDTHS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(bit_width),
+            &bit_width,
+            |b, &bit_width| {
+                b.iter(|| {
+                    let db = MemoryBlockstore::default();
+                    let mut a = Amt::new_with_bit_width(&db, bit_width);
+                    for i in 0..ITEM_COUNT as u64 {
+                        a.set(black_box(a.count()), black_box(i)).unwrap();
+                    }
+                    a.flush().unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn random_insert(c: &mut Criterion) {
+    let indices = random_indices(ITEM_COUNT, 1);
+    let mut group = c.benchmark_group("AMT sparse random insert and flush");
+    for &bit_width in BIT_WIDTHS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(bit_width),
+            &bit_width,
+            |b, &bit_width| {
+                b.iter(|| {
+                    let db = MemoryBlockstore::default();
+                    let mut a = Amt::new_with_bit_width(&db, bit_width);
+                    for (v, &i) in indices.iter().enumerate() {
+                        a.set(black_box(i), black_box(v as u64)).unwrap();
+                    }
+                    a.flush().unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Repeatedly loads the array, appends a few entries and deletes as many of the oldest ones, and
+/// flushes it again, as actors do with queues from one message to the next.
+fn flush_churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("AMT flush-heavy churn");
+    for &bit_width in BIT_WIDTHS {
+        let db = MemoryBlockstore::default();
+        let root = Amt::new_from_iter_with_bit_width(&db, bit_width, 0..ITEM_COUNT as u64).unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(bit_width), &root, |b, root| {
+            b.iter(|| {
+                let mut cid = *root;
+                for round in 0..CHURN_ROUNDS {
+                    let mut a = Amt::<u64, _>::load(&cid, &db).unwrap();
+                    let batch = (round * CHURN_BATCH) as u64..((round + 1) * CHURN_BATCH) as u64;
+                    for i in batch.clone() {
+                        a.set(black_box(ITEM_COUNT as u64 + i), i).unwrap();
+                    }
+                    for i in batch {
+                        a.delete(black_box(i)).unwrap();
+                    }
+                    cid = a.flush().unwrap();
+                }
+                cid
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sequential_append, random_insert, flush_churn);
+criterion_main!(benches);
@@ -0,0 +1,192 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! An append-only log backed by an AMT.
+
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::ser::Serialize;
+use fvm_ipld_encoding::tuple::*;
+
+use crate::{Amt, Error, MAX_INDEX};
+
+/// The persisted form of a [`Journal`]: store this in actor state and reload it with
+/// [`Journal::load`].
+#[derive(Serialize_tuple, Deserialize_tuple, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct JournalRoot {
+    /// The root of the AMT holding the retained entries.
+    pub amt: Cid,
+    /// The index of the oldest retained entry (equal to `next` if the journal is empty).
+    pub first: u64,
+    /// The index the next appended entry will be assigned.
+    pub next: u64,
+}
+
+/// An append-only log of values with monotonically increasing indexes, such as an actor's
+/// proposal or checkpoint history.
+///
+/// Entries are assigned consecutive indexes starting at 0 and are never modified. Old entries can
+/// be pruned from the front, but indexes are never reused: an index, once assigned, refers to the
+/// same entry (or to nothing, once pruned) forever.
+///
+/// Usage:
+/// ```
+/// use fvm_ipld_amt::Journal;
+///
+/// let db = fvm_ipld_blockstore::MemoryBlockstore::default();
+/// let mut journal = Journal::new(&db);
+///
+/// assert_eq!(journal.append("foo".to_owned()).unwrap(), 0);
+/// assert_eq!(journal.append("bar".to_owned()).unwrap(), 1);
+/// assert_eq!(journal.prune_before(1, 10).unwrap(), 1);
+/// assert_eq!(journal.get(0).unwrap(), None);
+///
+/// let root = journal.flush().unwrap();
+/// let journal = Journal::<String, _>::load(&root, &db).unwrap();
+/// assert_eq!(journal.get(1).unwrap(), Some(&"bar".to_owned()));
+/// ```
+#[derive(Debug)]
+pub struct Journal<V, BS> {
+    amt: Amt<V, BS>,
+    first: u64,
+    next: u64,
+}
+
+impl<V, BS> Journal<V, BS> {
+    /// Constructs an empty journal.
+    pub fn new(block_store: BS) -> Self {
+        Self {
+            amt: Amt::new(block_store),
+            first: 0,
+            next: 0,
+        }
+    }
+
+    /// Constructs an empty journal, backed by an AMT with the given bit width.
+    pub fn new_with_bit_width(block_store: BS, bit_width: u32) -> Self {
+        Self {
+            amt: Amt::new_with_bit_width(block_store, bit_width),
+            first: 0,
+            next: 0,
+        }
+    }
+
+    /// Returns the index of the oldest retained entry.
+    pub fn first_index(&self) -> u64 {
+        self.first
+    }
+
+    /// Returns the index the next appended entry will be assigned.
+    pub fn next_index(&self) -> u64 {
+        self.next
+    }
+
+    /// Returns the number of retained entries.
+    pub fn len(&self) -> u64 {
+        self.next - self.first
+    }
+
+    /// Returns true if no entries are retained.
+    pub fn is_empty(&self) -> bool {
+        self.first == self.next
+    }
+}
+
+impl<V, BS> Journal<V, BS>
+where
+    V: DeserializeOwned + Serialize,
+    BS: Blockstore,
+{
+    /// Loads a journal from its persisted root.
+    pub fn load(root: &JournalRoot, block_store: BS) -> Result<Self, Error> {
+        if root.first > root.next {
+            return Err(format!(
+                "invalid journal: first index {} is after next index {}",
+                root.first, root.next
+            )
+            .into());
+        }
+        let amt = Amt::load(&root.amt, block_store)?;
+        if amt.count() != root.next - root.first {
+            return Err(format!(
+                "invalid journal: expected {} entries, found {}",
+                root.next - root.first,
+                amt.count()
+            )
+            .into());
+        }
+        Ok(Self {
+            amt,
+            first: root.first,
+            next: root.next,
+        })
+    }
+
+    /// Appends a value to the journal, returning its index.
+    pub fn append(&mut self, value: V) -> Result<u64, Error> {
+        let idx = self.next;
+        if idx > MAX_INDEX {
+            return Err(Error::OutOfRange(idx));
+        }
+        self.amt.set(idx, value)?;
+        self.next += 1;
+        Ok(idx)
+    }
+
+    /// Returns the entry at the given index, or `None` if it has been pruned or not yet appended.
+    pub fn get(&self, idx: u64) -> Result<Option<&V>, Error> {
+        if idx < self.first || idx >= self.next {
+            return Ok(None);
+        }
+        self.amt.get(idx)
+    }
+
+    /// Returns the most recently appended entry, if it's still retained.
+    pub fn last(&self) -> Result<Option<&V>, Error> {
+        match self.next.checked_sub(1) {
+            Some(idx) => self.get(idx),
+            None => Ok(None),
+        }
+    }
+
+    /// Prunes entries with indexes below `before`, oldest first, removing at most `limit` entries
+    /// so the cost of a single call is bounded. Returns the number of entries removed; call again
+    /// to continue pruning if that's `limit`.
+    pub fn prune_before(&mut self, before: u64, limit: u64) -> Result<u64, Error> {
+        let end = before.min(self.next).min(self.first.saturating_add(limit));
+        if end <= self.first {
+            return Ok(0);
+        }
+        for idx in self.first..end {
+            self.amt.delete(idx)?;
+        }
+        let removed = end - self.first;
+        self.first = end;
+        Ok(removed)
+    }
+
+    /// Prunes the oldest entries so that at most `retain` remain, removing at most `limit` entries.
+    /// Returns the number of entries removed.
+    pub fn truncate_front(&mut self, retain: u64, limit: u64) -> Result<u64, Error> {
+        self.prune_before(self.next.saturating_sub(retain), limit)
+    }
+
+    /// Iterates over the retained entries and their indexes, oldest first.
+    pub fn for_each<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(u64, &V) -> anyhow::Result<()>,
+    {
+        self.amt.for_each_ranged(Some(self.first), None, f)?;
+        Ok(())
+    }
+
+    /// Flushes the backing AMT, returning the journal's root.
+    pub fn flush(&mut self) -> Result<JournalRoot, Error> {
+        Ok(JournalRoot {
+            amt: self.amt.flush()?,
+            first: self.first,
+            next: self.next,
+        })
+    }
+}
@@ -11,6 +11,7 @@ mod amt;
 mod diff;
 mod error;
 mod iter;
+mod journal;
 mod node;
 mod root;
 mod value_mut;
@@ -19,6 +20,7 @@ pub(crate) use self::amt::AmtImpl;
 pub use self::amt::{Amt, Amtv0};
 pub use self::diff::{diff, Change, ChangeType};
 pub use self::error::Error;
+pub use self::journal::{Journal, JournalRoot};
 pub(crate) use self::node::Node;
 pub use self::value_mut::ValueMut;
 
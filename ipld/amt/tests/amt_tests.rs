@@ -4,7 +4,7 @@
 
 use fvm_ipld_amt::{Amt, Amtv0, Error, MAX_INDEX};
 use fvm_ipld_blockstore::tracking::{BSStats, TrackingBlockstore};
-use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_blockstore::{BlockCache, Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::de::DeserializeOwned;
 use fvm_ipld_encoding::ser::Serialize;
 use fvm_ipld_encoding::BytesDe;
@@ -630,3 +630,23 @@ fn new_from_iter() {
     let expected: Vec<_> = data.into_iter().enumerate().collect();
     assert_eq!(expected, restored);
 }
+
+#[test]
+fn shared_block_cache() {
+    let mem = MemoryBlockstore::default();
+    let data: Vec<String> = (0..1000).map(|i| format!("thing{i}")).collect();
+    let k = Amt::<&str, _>::new_from_iter(&mem, data.iter().map(|s| &**s)).unwrap();
+
+    // AMTs loaded over stores sharing a cache only read each node from the store once.
+    let db = TrackingBlockstore::new(&mem);
+    let cache = BlockCache::new(1 << 20);
+    let first: Amt<String, _> = Amt::load(&k, cache.wrap(&db)).unwrap();
+    first.for_each(|_, _| Ok(())).unwrap();
+    let reads = db.stats.borrow().r;
+    assert_eq!(cache.stats().misses, reads);
+
+    let second: Amt<String, _> = Amt::load(&k, cache.wrap(&db)).unwrap();
+    second.for_each(|_, _| Ok(())).unwrap();
+    assert_eq!(db.stats.borrow().r, reads);
+    assert_eq!(cache.stats().hits, reads);
+}
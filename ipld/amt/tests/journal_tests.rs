@@ -0,0 +1,72 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_ipld_amt::{Journal, JournalRoot};
+use fvm_ipld_blockstore::MemoryBlockstore;
+
+#[test]
+fn append_and_prune() {
+    let db = MemoryBlockstore::default();
+    let mut journal = Journal::new_with_bit_width(&db, 2);
+    for i in 0..20u64 {
+        assert_eq!(journal.append(i * 10).unwrap(), i);
+    }
+    assert_eq!(journal.len(), 20);
+    assert_eq!(journal.last().unwrap(), Some(&190));
+
+    // Pruning is bounded by the limit, and resumes where it left off.
+    assert_eq!(journal.prune_before(15, 4).unwrap(), 4);
+    assert_eq!(journal.first_index(), 4);
+    assert_eq!(journal.prune_before(15, 100).unwrap(), 11);
+    assert_eq!(journal.prune_before(15, 100).unwrap(), 0);
+    assert_eq!(journal.get(14).unwrap(), None);
+    assert_eq!(journal.get(15).unwrap(), Some(&150));
+    assert_eq!(journal.get(20).unwrap(), None);
+
+    // Indexes are never reused after pruning.
+    assert_eq!(journal.truncate_front(2, 100).unwrap(), 3);
+    assert_eq!(journal.append(200).unwrap(), 20);
+
+    let mut entries = Vec::new();
+    journal
+        .for_each(|i, v| {
+            entries.push((i, *v));
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(entries, [(18, 180), (19, 190), (20, 200)]);
+
+    // Pruning everything leaves an empty journal that keeps counting.
+    assert_eq!(journal.prune_before(u64::MAX, u64::MAX).unwrap(), 3);
+    assert!(journal.is_empty());
+    assert_eq!(journal.last().unwrap(), None);
+    assert_eq!(journal.append(210).unwrap(), 21);
+}
+
+#[test]
+fn flush_and_load() {
+    let db = MemoryBlockstore::default();
+    let mut journal = Journal::new(&db);
+    for i in 0..10u64 {
+        journal.append(i.to_string()).unwrap();
+    }
+    journal.prune_before(3, 10).unwrap();
+    let root = journal.flush().unwrap();
+    assert_eq!((root.first, root.next), (3, 10));
+
+    let mut loaded = Journal::<String, _>::load(&root, &db).unwrap();
+    assert_eq!(loaded.len(), 7);
+    assert_eq!(loaded.get(2).unwrap(), None);
+    assert_eq!(loaded.get(3).unwrap(), Some(&"3".to_owned()));
+    assert_eq!(loaded.append("10".to_owned()).unwrap(), 10);
+
+    // Roots whose counters don't match the AMT are rejected.
+    let bad = JournalRoot { first: 2, ..root };
+    assert!(Journal::<String, _>::load(&bad, &db).is_err());
+    let bad = JournalRoot {
+        first: 11,
+        next: 10,
+        ..root
+    };
+    assert!(Journal::<String, _>::load(&bad, &db).is_err());
+}
@@ -0,0 +1,103 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Differential tests checking the AMT against a `BTreeMap` model of a sparse array.
+
+use std::collections::BTreeMap;
+
+use fvm_ipld_amt::Amt;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use quickcheck::{Arbitrary, Gen};
+use quickcheck_macros::quickcheck;
+
+#[derive(Clone, Debug)]
+struct BitWidth(u32);
+
+impl Arbitrary for BitWidth {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self(*g.choose(&[1, 2, 3, 5, 8]).unwrap())
+    }
+}
+
+/// An index that's usually small (to induce overlapping operations), but occasionally large (to
+/// grow and shrink the tree's height).
+#[derive(Clone, Debug)]
+struct Index(u64);
+
+impl Arbitrary for Index {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let i = u64::arbitrary(g);
+        Self(if u8::arbitrary(g) % 8 == 0 {
+            i % (1 << 20)
+        } else {
+            i % 64
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Operation {
+    Set(Index, i64),
+    Delete(Index),
+    Flush,
+}
+
+impl Arbitrary for Operation {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match u8::arbitrary(g) % 8 {
+            0 => Operation::Flush,
+            1..=3 => Operation::Delete(Index::arbitrary(g)),
+            _ => Operation::Set(Index::arbitrary(g), i64::arbitrary(g)),
+        }
+    }
+}
+
+fn assert_matches_model(amt: &Amt<i64, &MemoryBlockstore>, model: &BTreeMap<u64, i64>) {
+    assert_eq!(amt.count(), model.len() as u64);
+    let entries: Vec<_> = amt
+        .iter()
+        .map(|r| r.map(|(k, v)| (k, *v)))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let expected: Vec<_> = model.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(entries, expected);
+}
+
+/// Applies random operations to both an AMT and a model, checking that they agree after every
+/// step, and that the final tree is identical to one built directly from the model's contents.
+#[quickcheck]
+fn prop_matches_model(BitWidth(bit_width): BitWidth, ops: Vec<Operation>) {
+    let store = MemoryBlockstore::default();
+    let mut amt: Amt<i64, _> = Amt::new_with_bit_width(&store, bit_width);
+    let mut model = BTreeMap::new();
+
+    for op in ops {
+        match op {
+            Operation::Set(Index(i), v) => {
+                amt.set(i, v).unwrap();
+                model.insert(i, v);
+                assert_eq!(amt.get(i).unwrap(), Some(&v));
+            }
+            Operation::Delete(Index(i)) => {
+                assert_eq!(amt.delete(i).unwrap(), model.remove(&i));
+                assert_eq!(amt.get(i).unwrap(), None);
+            }
+            Operation::Flush => {
+                let cid = amt.flush().unwrap();
+                amt = Amt::load(&cid, &store).unwrap();
+            }
+        }
+    }
+    assert_matches_model(&amt, &model);
+
+    let cid = amt.flush().unwrap();
+    let reloaded = Amt::load(&cid, &store).unwrap();
+    assert_matches_model(&reloaded, &model);
+
+    // The structure is canonical: it doesn't depend on the history of operations.
+    let mut fresh: Amt<i64, _> = Amt::new_with_bit_width(&store, bit_width);
+    for (i, v) in &model {
+        fresh.set(*i, *v).unwrap();
+    }
+    assert_eq!(fresh.flush().unwrap(), cid);
+}
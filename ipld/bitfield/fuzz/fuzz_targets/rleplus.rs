@@ -0,0 +1,20 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+#![no_main]
+use fvm_ipld_bitfield::{BitField, DecodeMode};
+use libfuzzer_sys::fuzz_target;
+
+// Keep in sync with `ipld/bitfield/tests/rleplus_corpus.rs`, which replays the corpus.
+fuzz_target!(|data: &[u8]| {
+    let lenient = BitField::from_bytes_with_mode(data, DecodeMode::Lenient);
+    if let Ok(bf) = BitField::from_bytes(data) {
+        // The strict encoding is unique, and accepted by the lenient decoder.
+        assert_eq!(bf.to_bytes(), data);
+        assert_eq!(lenient.as_ref(), Ok(&bf));
+    }
+    if let Ok(bf) = lenient {
+        // Lenient decoding normalizes to a strictly valid bitfield.
+        assert_eq!(BitField::from_bytes(&bf.to_bytes()), Ok(bf));
+    }
+});
@@ -17,7 +17,7 @@ use std::ops::Range;
 
 use iter::{ranges_from_bits, RangeIterator};
 pub(crate) use range::RangeSize;
-pub use rleplus::Error;
+pub use rleplus::{DecodeMode, Error};
 use thiserror::Error;
 pub use unvalidated::{UnvalidatedBitField, Validate};
 
@@ -25,12 +25,21 @@ pub use unvalidated::{UnvalidatedBitField, Validate};
 /// a slice of runs, a bitfield of this size should not exceed 2MiB of memory.
 ///
 /// This bitfield can fit at least 3072 sparse elements.
-pub(crate) const MAX_ENCODED_SIZE: usize = 32 << 10;
+pub const MAX_ENCODED_SIZE: usize = 32 << 10;
 
 #[derive(Clone, Error, Debug)]
 #[error("bitfields may not include u64::MAX")]
 pub struct OutOfRangeError;
 
+#[derive(Clone, Error, Debug, PartialEq, Eq)]
+#[error("buffer too small for encoded bitfield: need {needed} bytes, have {available}")]
+pub struct BufferTooSmallError {
+    /// The length of the encoded bitfield.
+    pub needed: usize,
+    /// The length of the buffer.
+    pub available: usize,
+}
+
 impl From<OutOfRangeError> for Error {
     fn from(_: OutOfRangeError) -> Self {
         Error::RLEOverflow
@@ -413,6 +422,71 @@ pub mod json {
         }
     }
 
+    /// Wrapper for serializing a bitfield to JSON as a base64 string of its RLE+ encoding, the
+    /// representation Lotus uses for raw bitfield bytes.
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    #[serde(transparent)]
+    pub struct BitFieldBase64(#[serde(with = "base64")] pub BitField);
+
+    /// Wrapper for serializing a bitfield reference to JSON as a base64 string of its RLE+
+    /// encoding.
+    #[derive(Serialize)]
+    #[serde(transparent)]
+    pub struct BitFieldBase64Ref<'a>(#[serde(with = "base64")] pub &'a BitField);
+
+    impl From<BitFieldBase64> for BitField {
+        fn from(wrapper: BitFieldBase64) -> Self {
+            wrapper.0
+        }
+    }
+
+    impl From<BitField> for BitFieldBase64 {
+        fn from(wrapper: BitField) -> Self {
+            BitFieldBase64(wrapper)
+        }
+    }
+
+    /// Serde helpers for (de)serializing bitfields as base64 strings of their RLE+ encoding. Use
+    /// with `#[serde(with = "fvm_ipld_bitfield::json::base64")]`.
+    pub mod base64 {
+        use data_encoding::BASE64;
+        use serde::{de, ser, Deserialize, Deserializer, Serializer};
+
+        use crate::{BitField, MAX_ENCODED_SIZE};
+
+        pub fn serialize<S>(m: &BitField, serializer: S) -> std::result::Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let bytes = m.to_bytes();
+            if bytes.len() > MAX_ENCODED_SIZE {
+                return Err(ser::Error::custom(format!(
+                    "encoded bitfield was too large {}",
+                    bytes.len()
+                )));
+            }
+            serializer.serialize_str(&BASE64.encode(&bytes))
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<BitField, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            // Go encodes nil byte slices as null.
+            let encoded: Option<String> = Deserialize::deserialize(deserializer)?;
+            let bytes = BASE64
+                .decode(encoded.unwrap_or_default().as_bytes())
+                .map_err(de::Error::custom)?;
+            if bytes.len() > MAX_ENCODED_SIZE {
+                return Err(de::Error::custom(format!(
+                    "encoded bitfield was too large {}",
+                    bytes.len()
+                )));
+            }
+            BitField::from_bytes(&bytes).map_err(de::Error::custom)
+        }
+    }
+
     fn serialize<S>(m: &BitField, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -474,6 +548,24 @@ pub mod json {
         assert_eq!(bf, bitfield);
     }
 
+    #[test]
+    fn base64_serialization() {
+        let bf = BitFieldBase64(bitfield![0, 0, 1, 1, 1, 1, 0, 0, 0, 1, 1]);
+        let j = serde_json::to_string(&bf).unwrap();
+        assert_eq!(j, "\"UCRHAQ==\"");
+        assert_eq!(serde_json::to_string(&BitFieldBase64Ref(&bf.0)).unwrap(), j);
+        let bitfield: BitFieldBase64 = serde_json::from_str(&j).unwrap();
+        assert_eq!(bf, bitfield);
+
+        let empty = BitFieldBase64(bitfield![]);
+        assert_eq!(serde_json::to_string(&empty).unwrap(), "\"\"");
+        let bitfield: BitFieldBase64 = serde_json::from_str("null").unwrap();
+        assert_eq!(empty, bitfield);
+
+        // Non-minimal encodings are rejected.
+        assert!(serde_json::from_str::<BitFieldBase64>("\"UCRHAQA=\"").is_err());
+    }
+
     #[test]
     fn serialization_with_single_unut() {
         let bf = BitFieldJson(bitfield![]);
@@ -2,14 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use thiserror::Error;
 
+/// RLE+ decoding error
 #[derive(PartialEq, Eq, Clone, Debug, Error)]
 pub enum Error {
+    /// A run is encoded in a larger block than necessary, or has a length of zero.
     #[error("bitfield not minimally encoded")]
     NotMinimal,
+    /// The encoding ends with zero bits, either as zero bytes or as a final run of zeros.
+    #[error("bitfield has trailing zero bits")]
+    TrailingBits,
+    /// The header specifies a version other than 0.
     #[error("bitfield specifies an unsupported version")]
     UnsupportedVersion,
+    /// The total length of the runs overflows a u64.
     #[error("bitfield overflows 2^63-1")]
     RLEOverflow,
+    /// A long block's varint is truncated, overflows a u64, or (in strict mode) isn't minimally
+    /// encoded.
     #[error("invalid varint")]
     InvalidVarint,
 }
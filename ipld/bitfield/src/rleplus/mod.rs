@@ -67,6 +67,7 @@ mod reader;
 mod writer;
 
 use std::borrow::Cow;
+use std::ops::Range;
 
 #[cfg(feature = "enable-arbitrary")]
 use arbitrary::{size_hint, Arbitrary, Unstructured};
@@ -75,9 +76,23 @@ use fvm_ipld_encoding::strict_bytes;
 pub use reader::BitReader;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub use writer::BitWriter;
+use writer::{BitCounter, BitSink, SliceBitWriter};
 
 use super::BitField;
-use crate::{RangeSize, MAX_ENCODED_SIZE};
+use crate::{BufferTooSmallError, RangeSize, MAX_ENCODED_SIZE};
+
+/// How strictly to check RLE+ encodings when decoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Only accept the unique, minimal encoding of each bitfield. This is the mode used when
+    /// deserializing bitfields, and must be used whenever the result affects consensus.
+    #[default]
+    Strict,
+    /// Also accept encodings that aren't minimal: runs encoded in larger blocks than necessary,
+    /// zero-length runs, non-minimal varints, and trailing zero bits. Encodings that are
+    /// malformed (unsupported versions, invalid varints, overflowing runs) are still rejected.
+    Lenient,
+}
 
 impl Serialize for BitField {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
@@ -154,9 +169,14 @@ impl<'a> Arbitrary<'a> for BitField {
 }
 
 impl BitField {
-    /// Decodes RLE+ encoded bytes into a bit field.
+    /// Decodes RLE+ encoded bytes into a bit field, rejecting encodings that aren't minimal.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        let mut reader = BitReader::new(bytes)?;
+        Self::from_bytes_with_mode(bytes, DecodeMode::Strict)
+    }
+
+    /// Decodes RLE+ encoded bytes into a bit field with the given decoding mode.
+    pub fn from_bytes_with_mode(bytes: &[u8], mode: DecodeMode) -> Result<Self, Error> {
+        let mut reader = BitReader::new(bytes, mode)?;
 
         let version = reader.read(2);
         if version != 0 {
@@ -164,7 +184,7 @@ impl BitField {
         }
 
         let mut next_value = reader.read(1) == 1;
-        let mut ranges = Vec::new();
+        let mut ranges: Vec<Range<u64>> = Vec::new();
         let mut index = 0u64;
         let mut total_len: u64 = 0;
 
@@ -178,8 +198,13 @@ impl BitField {
             index += len;
             let end = index;
 
-            if next_value {
-                ranges.push(start..end);
+            // Zero-length runs can only appear in lenient mode, in which case we skip empty runs
+            // of ones and merge the runs of ones around empty runs of zeros.
+            if next_value && start < end {
+                match ranges.last_mut() {
+                    Some(last) if last.end == start => last.end = end,
+                    _ => ranges.push(start..end),
+                }
             }
 
             next_value = !next_value;
@@ -187,8 +212,8 @@ impl BitField {
 
         // next_value equal true means we just read a run of zeros
         // which means that there is a trailing run of zeros
-        if next_value {
-            return Err(Error::NotMinimal);
+        if next_value && mode == DecodeMode::Strict {
+            return Err(Error::TrailingBits);
         }
 
         Ok(Self {
@@ -199,14 +224,45 @@ impl BitField {
 
     /// Turns a bit field into its RLE+ encoded form.
     pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        self.encode(&mut writer);
+        writer.finish()
+    }
+
+    /// Returns the length of the RLE+ encoded form of the bit field, without encoding it.
+    ///
+    /// Use this to check the encoding against [`MAX_ENCODED_SIZE`] before serializing, or to
+    /// allocate a buffer for [`write_to`](Self::write_to).
+    pub fn encoded_len(&self) -> usize {
+        let mut counter = BitCounter::default();
+        self.encode(&mut counter);
+        counter.len()
+    }
+
+    /// Writes the RLE+ encoded form of the bit field into `buf`, returning the number of bytes
+    /// written (see [`encoded_len`](Self::encoded_len)). Fails without writing anything if `buf`
+    /// is too small.
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<usize, BufferTooSmallError> {
+        let needed = self.encoded_len();
+        if buf.len() < needed {
+            return Err(BufferTooSmallError {
+                needed,
+                available: buf.len(),
+            });
+        }
+        let mut writer = SliceBitWriter::new(buf);
+        self.encode(&mut writer);
+        Ok(writer.finish())
+    }
+
+    fn encode(&self, writer: &mut impl BitSink) {
         let mut iter = self.ranges();
 
         let first_range = match iter.next() {
             Some(range) => range,
-            None => return Default::default(),
+            None => return,
         };
 
-        let mut writer = BitWriter::new();
         writer.write(0, 2); // version 00
 
         if first_range.start == 0 {
@@ -226,8 +282,6 @@ impl BitField {
             writer.write_len(range.size()); // ones
             index = range.end;
         }
-
-        writer.finish()
     }
 }
 
@@ -237,8 +291,9 @@ mod tests {
     use rand_xorshift::XorShiftRng;
 
     use super::super::{bitfield, ranges_from_bits};
-    use super::{BitField, BitWriter, Error};
+    use super::{BitField, BitSink, BitWriter, DecodeMode, Error};
     use crate::iter::Ranges;
+    use crate::BufferTooSmallError;
 
     #[test]
     fn test() {
@@ -318,7 +373,7 @@ mod tests {
                     1, 0, 1, // 5 - 1
                     0, 0, 0, 0, 0, 0, 0, 0,
                 ],
-                Err(Error::NotMinimal),
+                Err(Error::TrailingBits),
             ),
             // a valid varint
             (
@@ -428,7 +483,7 @@ mod tests {
                     0, // starts with 0
                     1, // run of one
                 ],
-                Err(Error::NotMinimal),
+                Err(Error::TrailingBits),
             ),
             (
                 vec![
@@ -437,7 +492,7 @@ mod tests {
                     0, 1, // fits into 4 bits
                     0, 0, 1, 0,
                 ],
-                Err(Error::NotMinimal),
+                Err(Error::TrailingBits),
             ),
             (
                 vec![
@@ -447,7 +502,7 @@ mod tests {
                     0, 0, 1, 0, // 2
                     1, // trailing run of zeros
                 ],
-                Err(Error::NotMinimal),
+                Err(Error::TrailingBits),
             ),
             (
                 vec![
@@ -458,7 +513,7 @@ mod tests {
                     0, 0, 1, 0, // 2
                     0, 1, 0, 0, 1, 0, // 2 trailing zeros
                 ],
-                Err(Error::NotMinimal),
+                Err(Error::TrailingBits),
             ),
         ]
         .into_iter()
@@ -473,6 +528,95 @@ mod tests {
         }
     }
 
+    #[test]
+    fn lenient() {
+        for (i, (bits, expected)) in [
+            // block_long that could have fit on block_single.
+            (
+                vec![
+                    0, 0, // version
+                    1, // starts with 1
+                    0, 0, // fits into a varint
+                    1, 0, 0, 0, 0, 0, 0, 0, // 1 - 1
+                ],
+                Ok(bitfield![1]),
+            ),
+            // Zero-length run of zeros between runs of ones.
+            (
+                vec![
+                    0, 0, // version
+                    1, // starts with 1
+                    1, // 1 - 1
+                    0, 1, // fits into 4 bits
+                    0, 0, 0, 0, // 0 - 0
+                    1, // 1 - 1
+                ],
+                Ok(bitfield![1, 1]),
+            ),
+            // Zero-length run of ones.
+            (
+                vec![
+                    0, 0, // version
+                    0, // starts with 0
+                    1, // 1 - 0
+                    0, 1, // fits into 4 bits
+                    0, 0, 0, 0, // 0 - 1
+                    1, // 1 - 0
+                    1, // 1 - 1
+                ],
+                Ok(bitfield![0, 0, 1]),
+            ),
+            // Non-minimal varint and trailing zeros.
+            (
+                vec![
+                    0, 0, // version
+                    1, // starts with 1
+                    0, 0, // fits into a varint
+                    1, 0, 0, 0, 1, 0, 0, 1, // 17 - 1
+                    0, 0, 0, 0, 0, 0, 0, 0, // (non-minimal)
+                    1, // 1 - 0
+                    0, 0, 0, 0, 0, 0, 0, 0,
+                ],
+                Ok(BitField::from_ranges(Ranges::new(std::iter::once(0..17)))),
+            ),
+            // Malformed encodings are still rejected.
+            (
+                vec![
+                    1, 0, // incorrect version
+                    1, // starts with 1
+                ],
+                Err(Error::UnsupportedVersion),
+            ),
+            (
+                vec![
+                    0, 0, // version
+                    1, // starts with 1
+                    0, 0, // fits into a varint
+                    0, 0, 0, 0, 0, 0, 0, 1, // overflows u64
+                    0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0,
+                    0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0,
+                    0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+                ],
+                Err(Error::InvalidVarint),
+            ),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut writer = BitWriter::new();
+            for bit in bits {
+                writer.write(bit, 1);
+            }
+            let bytes = writer.finish_test();
+            let res = BitField::from_bytes_with_mode(&bytes, DecodeMode::Lenient);
+            assert_eq!(res, expected, "test {} failed", i);
+            if let Ok(bf) = res {
+                assert!(BitField::from_bytes(&bytes).is_err(), "test {} failed", i);
+                assert_eq!(BitField::from_bytes(&bf.to_bytes()), Ok(bf));
+            }
+        }
+    }
+
     #[test]
     fn roundtrip() {
         let mut rng = XorShiftRng::seed_from_u64(1);
@@ -487,6 +631,41 @@ mod tests {
             assert_eq!(bf.ranges().collect::<Vec<_>>(), ranges);
         }
     }
+
+    #[test]
+    fn encoded_len_and_write_to() {
+        let mut rng = XorShiftRng::seed_from_u64(2);
+
+        for _i in 0..1000 {
+            let len: u64 = rng.gen_range(0..1000);
+            let bits: Vec<_> = (0..len).filter(|_| rng.gen_range(0..8) == 0).collect();
+            let bf = BitField::from_ranges(ranges_from_bits(bits));
+
+            let bytes = bf.to_bytes();
+            assert_eq!(bf.encoded_len(), bytes.len());
+
+            // An exactly sized buffer is enough.
+            let mut buf = vec![0xff; bytes.len()];
+            assert_eq!(bf.write_to(&mut buf), Ok(bytes.len()));
+            assert_eq!(buf, bytes);
+
+            let mut buf = vec![0xff; bytes.len() + 8];
+            assert_eq!(bf.write_to(&mut buf), Ok(bytes.len()));
+            assert_eq!(buf[..bytes.len()], bytes);
+
+            if !bytes.is_empty() {
+                let mut buf = vec![0xff; bytes.len() - 1];
+                assert_eq!(
+                    bf.write_to(&mut buf),
+                    Err(BufferTooSmallError {
+                        needed: bytes.len(),
+                        available: bytes.len() - 1
+                    })
+                );
+                assert!(buf.iter().all(|&b| b == 0xff));
+            }
+        }
+    }
     #[test]
     fn iter_last() {
         // Create RLE with 2**64-2 set bits- tests timeout on the `let max` line with last
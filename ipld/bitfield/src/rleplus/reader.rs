@@ -2,7 +2,7 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use super::Error;
+use super::{DecodeMode, Error};
 
 // Unlike the multiformats "uvarint", we allow 10 bytes here so we can encode a full uint64.
 const VARINT_MAX_BYTES: usize = 10;
@@ -19,15 +19,18 @@ pub struct BitReader<'a> {
 
     /// The number of bits in `bits` from bytes that came before `next_byte` (at least 8, at most 15).
     num_bits: u32,
+
+    /// Whether to reject encodings that aren't minimal.
+    mode: DecodeMode,
 }
 
 impl<'a> BitReader<'a> {
-    /// Creates a new `BitReader`.
-    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+    /// Creates a new `BitReader`. In strict mode, encodings that aren't minimal are rejected.
+    pub fn new(bytes: &'a [u8], mode: DecodeMode) -> Result<Self, Error> {
         // There are infinite implicit "0"s, so we don't expect any trailing zeros in the actual
         // data.
-        if bytes.last() == Some(&0) {
-            return Err(Error::NotMinimal);
+        if mode == DecodeMode::Strict && bytes.last() == Some(&0) {
+            return Err(Error::TrailingBits);
         }
         let mut bits = 0u64;
         for i in 0..2 {
@@ -41,6 +44,7 @@ impl<'a> BitReader<'a> {
             bytes,
             bits,
             num_bits: 16,
+            mode,
         })
     }
 
@@ -105,7 +109,10 @@ impl<'a> BitReader<'a> {
             if byte & 0x80 == 0 {
                 // 1. We only allow the 9th byte to be 1 (overflows u64).
                 // 2. The last byte cannot be 0 (not minimally encoded).
-                if (i == 9 && byte > 1) || (byte == 0 && i != 0) {
+                if i == 9 && byte > 1 {
+                    break;
+                }
+                if byte == 0 && i != 0 && self.mode == DecodeMode::Strict {
                     break;
                 }
                 return Ok(len);
@@ -132,7 +139,7 @@ impl<'a> BitReader<'a> {
             // Block Short (prefix 01)
             let val = ((peek6 >> 2) & 0x0f) as u64;
             self.drop(6);
-            if val < 2 {
+            if val < 2 && self.mode == DecodeMode::Strict {
                 return Err(Error::NotMinimal);
             }
             val
@@ -140,7 +147,7 @@ impl<'a> BitReader<'a> {
             // Block Long (prefix 00)
             self.drop(2);
             let val = self.read_varint()?;
-            if val < 16 {
+            if val < 16 && self.mode == DecodeMode::Strict {
                 return Err(Error::NotMinimal);
             }
             val
@@ -158,11 +165,12 @@ impl<'a> BitReader<'a> {
 #[cfg(test)]
 mod tests {
     use super::BitReader;
+    use crate::DecodeMode;
 
     #[test]
     fn read() {
         let bytes = &[0b1011_1110, 0b0111_0010, 0b0010_1010];
-        let mut reader = BitReader::new(bytes).unwrap();
+        let mut reader = BitReader::new(bytes, DecodeMode::Strict).unwrap();
 
         assert_eq!(reader.read(0), 0);
         assert_eq!(reader.read(1), 0);
@@ -181,7 +189,7 @@ mod tests {
     #[test]
     fn read_len() {
         let bytes = &[0b0001_0101, 0b1101_0111, 0b0110_0111, 0b00110010];
-        let mut reader = BitReader::new(bytes).unwrap();
+        let mut reader = BitReader::new(bytes, DecodeMode::Strict).unwrap();
 
         assert_eq!(reader.read_len().unwrap(), Some(1)); // prefix: 1
         assert_eq!(reader.read_len().unwrap(), Some(2)); // prefix: 01, value: 0100 (LSB to MSB)
@@ -195,7 +203,7 @@ mod tests {
     #[test]
     #[should_panic(expected = "assertion failed")]
     fn too_many_bits_at_once() {
-        let mut reader = BitReader::new(&[]).unwrap();
+        let mut reader = BitReader::new(&[], DecodeMode::Strict).unwrap();
         reader.read(16);
     }
 
@@ -204,7 +212,7 @@ mod tests {
         use rand::{Rng, SeedableRng};
         use rand_xorshift::XorShiftRng;
 
-        use super::super::BitWriter;
+        use super::super::{BitSink, BitWriter};
 
         let mut rng = XorShiftRng::seed_from_u64(5);
 
@@ -220,7 +228,7 @@ mod tests {
             }
 
             let bytes = writer.finish();
-            let mut reader = BitReader::new(&bytes).unwrap();
+            let mut reader = BitReader::new(&bytes, DecodeMode::Strict).unwrap();
 
             for &len in &lengths {
                 assert_eq!(reader.read_len().unwrap(), Some(len));
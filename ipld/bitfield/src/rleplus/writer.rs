@@ -2,41 +2,13 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-#[derive(Default, Clone, Debug)]
-/// A `BitWriter` allows for efficiently writing bits to a byte buffer, up to a byte at a time.
-pub struct BitWriter {
-    /// The buffer that is written to.
-    bytes: Vec<u8>,
-    /// The most recently written bits. Whenever this exceeds 8 bits, one byte is written to `bytes`.
-    bits: u16,
-    /// The number of bits currently stored in `bits`.
-    num_bits: u32,
-}
+/// A destination for the bits of an RLE+ encoding.
+pub(crate) trait BitSink {
+    /// Writes a given number of bits from `byte`.
+    fn write(&mut self, byte: u8, num_bits: u32);
 
-impl BitWriter {
-    /// Creates a new `BitWriter`.
-    pub fn new() -> Self {
-        Default::default()
-    }
-
-    /// Writes a given number of bits from `byte` to the buffer.
-    pub fn write(&mut self, byte: u8, num_bits: u32) {
-        debug_assert!(num_bits <= 8);
-        debug_assert!(8 - byte.leading_zeros() <= num_bits);
-
-        self.bits |= (byte as u16) << self.num_bits;
-        self.num_bits += num_bits;
-
-        // when we have a full byte in `self.bits`, we write it to `self.bytes`
-        if self.num_bits >= 8 {
-            self.bytes.push(self.bits as u8);
-            self.bits >>= 8;
-            self.num_bits -= 8;
-        }
-    }
-
-    /// Writes a given length to the buffer according to RLE+ encoding.
-    pub fn write_len(&mut self, len: u64) {
+    /// Writes a given length according to RLE+ encoding.
+    fn write_len(&mut self, len: u64) {
         debug_assert!(len > 0);
 
         if len == 1 {
@@ -56,6 +28,24 @@ impl BitWriter {
             }
         }
     }
+}
+
+#[derive(Default, Clone, Debug)]
+/// A `BitWriter` allows for efficiently writing bits to a byte buffer, up to a byte at a time.
+pub struct BitWriter {
+    /// The buffer that is written to.
+    bytes: Vec<u8>,
+    /// The most recently written bits. Whenever this exceeds 8 bits, one byte is written to `bytes`.
+    bits: u16,
+    /// The number of bits currently stored in `bits`.
+    num_bits: u32,
+}
+
+impl BitWriter {
+    /// Creates a new `BitWriter`.
+    pub fn new() -> Self {
+        Default::default()
+    }
 
     /// Writes any remaining bits to the buffer and returns it.
     pub fn finish(mut self) -> Vec<u8> {
@@ -84,9 +74,118 @@ impl BitWriter {
     }
 }
 
+impl BitSink for BitWriter {
+    fn write(&mut self, byte: u8, num_bits: u32) {
+        debug_assert!(num_bits <= 8);
+        debug_assert!(8 - byte.leading_zeros() <= num_bits);
+
+        self.bits |= (byte as u16) << self.num_bits;
+        self.num_bits += num_bits;
+
+        // when we have a full byte in `self.bits`, we write it to `self.bytes`
+        if self.num_bits >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.num_bits -= 8;
+        }
+    }
+}
+
+/// A [`BitSink`] writing into a caller-provided buffer, which must have room for the encoding
+/// up to its last set bit (see [`BitCounter`]). Like [`BitWriter::finish`], trailing zero bytes
+/// are dropped.
+pub(crate) struct SliceBitWriter<'a> {
+    /// The buffer that is written to.
+    buf: &'a mut [u8],
+    /// The number of bytes written so far, including the trailing zero bytes that may not fit
+    /// into `buf`.
+    pos: usize,
+    /// The length of the encoding up to the last non-zero byte.
+    len: usize,
+    /// The most recently written bits. Whenever this exceeds 8 bits, one byte is written to `buf`.
+    bits: u16,
+    /// The number of bits currently stored in `bits`.
+    num_bits: u32,
+}
+
+impl<'a> SliceBitWriter<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self {
+            buf,
+            pos: 0,
+            len: 0,
+            bits: 0,
+            num_bits: 0,
+        }
+    }
+
+    /// Writes any remaining bits to the buffer and returns the length of the encoding.
+    pub fn finish(mut self) -> usize {
+        if self.bits > 0 {
+            self.push(self.bits as u8);
+        }
+        self.len
+    }
+
+    fn push(&mut self, byte: u8) {
+        if byte != 0 {
+            self.buf[self.pos] = byte;
+            self.len = self.pos + 1;
+        } else if let Some(b) = self.buf.get_mut(self.pos) {
+            *b = 0;
+        }
+        self.pos += 1;
+    }
+}
+
+impl BitSink for SliceBitWriter<'_> {
+    fn write(&mut self, byte: u8, num_bits: u32) {
+        debug_assert!(num_bits <= 8);
+        debug_assert!(8 - byte.leading_zeros() <= num_bits);
+
+        self.bits |= (byte as u16) << self.num_bits;
+        self.num_bits += num_bits;
+
+        if self.num_bits >= 8 {
+            self.push(self.bits as u8);
+            self.bits >>= 8;
+            self.num_bits -= 8;
+        }
+    }
+}
+
+/// A [`BitSink`] that only measures the length of the encoding written to it, up to its last
+/// non-zero byte.
+#[derive(Default)]
+pub(crate) struct BitCounter {
+    /// The number of bits written so far.
+    num_bits: u64,
+    /// The number of bits written up to (and including) the last set bit.
+    end: u64,
+}
+
+impl BitCounter {
+    /// Returns the length of the encoding in bytes.
+    pub fn len(&self) -> usize {
+        self.end.div_ceil(8) as usize
+    }
+}
+
+impl BitSink for BitCounter {
+    fn write(&mut self, byte: u8, num_bits: u32) {
+        debug_assert!(num_bits <= 8);
+        debug_assert!(8 - byte.leading_zeros() <= num_bits);
+
+        if byte != 0 {
+            self.end = self.num_bits + (8 - byte.leading_zeros()) as u64;
+        }
+        self.num_bits += num_bits as u64;
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::BitWriter;
+    use super::{BitSink, BitWriter};
 
     #[test]
     fn write() {
@@ -0,0 +1,38 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Replays the `rleplus` fuzz target's corpus, so regressions found by fuzzing are caught by
+//! `cargo test`.
+
+use std::fs;
+use std::path::Path;
+
+use fvm_ipld_bitfield::{BitField, DecodeMode};
+
+// Keep in sync with `ipld/bitfield/fuzz/fuzz_targets/rleplus.rs`.
+fn check(data: &[u8]) {
+    let lenient = BitField::from_bytes_with_mode(data, DecodeMode::Lenient);
+    if let Ok(bf) = BitField::from_bytes(data) {
+        // The strict encoding is unique, and accepted by the lenient decoder.
+        assert_eq!(bf.to_bytes(), data);
+        assert_eq!(lenient.as_ref(), Ok(&bf));
+    }
+    if let Ok(bf) = lenient {
+        // Lenient decoding normalizes to a strictly valid bitfield.
+        assert_eq!(BitField::from_bytes(&bf.to_bytes()), Ok(bf));
+    }
+}
+
+#[test]
+fn rleplus_corpus() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/rleplus");
+    let mut count = 0;
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        let data = fs::read(&path).unwrap();
+        let res = std::panic::catch_unwind(|| check(&data));
+        assert!(res.is_ok(), "corpus entry {} failed", path.display());
+        count += 1;
+    }
+    assert!(count > 0, "empty corpus at {}", dir.display());
+}
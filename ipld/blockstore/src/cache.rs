@@ -0,0 +1,279 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Result;
+use cid::Cid;
+
+use super::{Blockstore, CacheStats, LruCache};
+
+/// A least-recently-used cache of blocks, keyed by CID and bounded by the total size of the
+/// cached blocks.
+///
+/// The cache is a cheaply clonable handle: clones share the same underlying cache, so a single
+/// cache can back several [`CachedBlockstore`]s (and therefore several AMTs loaded over the same
+/// store) at once, saving repeated reads of the nodes they have in common. HAMTs can share a cache
+/// of decoded nodes instead (`fvm_ipld_hamt::NodeCache`), which also saves decoding them.
+#[derive(Debug, Clone)]
+pub struct BlockCache {
+    lru: LruCache<Cid, Vec<u8>>,
+}
+
+impl BlockCache {
+    /// Creates an empty cache holding at most `max_bytes` bytes of blocks. Blocks larger than
+    /// `max_bytes` are never cached.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            lru: LruCache::new(max_bytes),
+        }
+    }
+
+    /// Wraps `store` in a [`CachedBlockstore`] backed by this cache.
+    pub fn wrap<BS: Blockstore>(&self, store: BS) -> CachedBlockstore<BS> {
+        CachedBlockstore::new(store, self.clone())
+    }
+
+    /// Returns the cached block with the given CID, if any, marking it as recently used.
+    pub fn get(&self, k: &Cid) -> Option<Vec<u8>> {
+        self.lru.get(k, Vec::clone)
+    }
+
+    /// Returns true if the block with the given CID is cached. This doesn't affect the
+    /// statistics, nor the eviction order.
+    pub fn contains(&self, k: &Cid) -> bool {
+        self.lru.contains(k)
+    }
+
+    /// Caches a block, evicting the least recently used blocks as necessary.
+    pub fn insert(&self, k: &Cid, block: &[u8]) {
+        self.lru.insert_with(*k, block.len(), || block.to_vec())
+    }
+
+    /// Returns the hit statistics accumulated since the cache was created (or last cleared).
+    pub fn stats(&self) -> CacheStats {
+        self.lru.stats()
+    }
+
+    /// Returns the total size of the cached blocks, in bytes.
+    pub fn size(&self) -> usize {
+        self.lru.size()
+    }
+
+    /// Returns the number of cached blocks.
+    pub fn len(&self) -> usize {
+        self.lru.len()
+    }
+
+    /// Returns true if no blocks are cached.
+    pub fn is_empty(&self) -> bool {
+        self.lru.is_empty()
+    }
+
+    /// Evicts all blocks and resets the statistics.
+    pub fn clear(&self) {
+        self.lru.clear()
+    }
+}
+
+/// A blockstore that serves reads from a (possibly shared) [`BlockCache`], falling back to the
+/// wrapped store on misses. Blocks read from, or written to, the wrapped store are cached.
+#[derive(Debug, Clone)]
+pub struct CachedBlockstore<BS> {
+    store: BS,
+    cache: BlockCache,
+}
+
+impl<BS> CachedBlockstore<BS>
+where
+    BS: Blockstore,
+{
+    pub fn new(store: BS, cache: BlockCache) -> Self {
+        Self { store, cache }
+    }
+
+    /// Returns a reference to the cache.
+    pub fn cache(&self) -> &BlockCache {
+        &self.cache
+    }
+
+    /// Returns a reference to the wrapped store.
+    pub fn store(&self) -> &BS {
+        &self.store
+    }
+
+    /// Consumes the blockstore, returning the wrapped store.
+    pub fn into_inner(self) -> BS {
+        self.store
+    }
+}
+
+impl<BS> Blockstore for CachedBlockstore<BS>
+where
+    BS: Blockstore,
+{
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        if let Some(data) = self.cache.get(k) {
+            return Ok(Some(data));
+        }
+        let data = self.store.get(k)?;
+        if let Some(data) = &data {
+            self.cache.insert(k, data);
+        }
+        Ok(data)
+    }
+
+    fn has(&self, k: &Cid) -> Result<bool> {
+        Ok(self.cache.contains(k) || self.store.has(k)?)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        self.store.put_keyed(k, block)?;
+        self.cache.insert(k, block);
+        Ok(())
+    }
+
+    fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        // Only cache the blocks once they've been written.
+        let blocks: Vec<_> = blocks.into_iter().collect();
+        self.store
+            .put_many_keyed(blocks.iter().map(|(k, b)| (*k, b.as_ref())))?;
+        for (k, b) in &blocks {
+            self.cache.insert(k, b.as_ref());
+        }
+        Ok(())
+    }
+
+    fn put_many_keyed_unchecked<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        let blocks: Vec<_> = blocks.into_iter().collect();
+        self.store
+            .put_many_keyed_unchecked(blocks.iter().map(|(k, b)| (*k, b.as_ref())))?;
+        for (k, b) in &blocks {
+            self.cache.insert(k, b.as_ref());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use multihash_codetable::Code;
+
+    use super::*;
+    use crate::{Block, MemoryBlockstore};
+
+    fn cid(data: &[u8]) -> Cid {
+        Block::new(0x55, data).cid(Code::Blake2b256)
+    }
+
+    #[test]
+    fn lru_eviction() {
+        let cache = BlockCache::new(8);
+        let (a, b, c) = (cid(b"aaaa"), cid(b"bbbb"), cid(b"cccc"));
+        cache.insert(&a, b"aaaa");
+        cache.insert(&b, b"bbbb");
+        assert_eq!(cache.size(), 8);
+
+        // Touch `a` so `b` is evicted first.
+        assert_eq!(cache.get(&a).as_deref(), Some(&b"aaaa"[..]));
+        cache.insert(&c, b"cccc");
+        assert!(cache.contains(&a));
+        assert!(!cache.contains(&b));
+        assert!(cache.contains(&c));
+        assert_eq!(cache.len(), 2);
+
+        // Blocks larger than the cache are ignored.
+        let big = cid(b"too big to cache");
+        cache.insert(&big, b"too big to cache");
+        assert!(!cache.contains(&big));
+
+        assert_eq!(cache.get(&b), None);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn shared_cache() {
+        let store = MemoryBlockstore::default();
+        let k = store
+            .put(Code::Blake2b256, &Block::new(0x55, &b"foobar"[..]))
+            .unwrap();
+
+        let cache = BlockCache::new(1 << 10);
+        let first = cache.wrap(&store);
+        let second = cache.wrap(&store);
+
+        assert_eq!(first.get(&k).unwrap().as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(second.get(&k).unwrap().as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 1);
+
+        // Writes go through to the store, and are cached.
+        let other = second
+            .put(Code::Blake2b256, &Block::new(0x55, &b"other"[..]))
+            .unwrap();
+        assert!(store.has(&other).unwrap());
+        assert_eq!(first.get(&other).unwrap().as_deref(), Some(&b"other"[..]));
+        assert_eq!(cache.stats().hits, 2);
+    }
+
+    #[test]
+    fn forwards_unchecked_writes() {
+        /// Counts the blocks written through the unchecked path.
+        #[derive(Default)]
+        struct Store {
+            mem: MemoryBlockstore,
+            unchecked: std::cell::Cell<usize>,
+        }
+
+        impl Blockstore for Store {
+            fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+                self.mem.get(k)
+            }
+
+            fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+                self.mem.put_keyed(k, block)
+            }
+
+            fn put_many_keyed_unchecked<D, I>(&self, blocks: I) -> Result<()>
+            where
+                D: AsRef<[u8]>,
+                I: IntoIterator<Item = (Cid, D)>,
+            {
+                for (k, b) in blocks {
+                    self.unchecked.set(self.unchecked.get() + 1);
+                    self.mem.put_keyed(&k, b.as_ref())?;
+                }
+                Ok(())
+            }
+        }
+
+        let store = Store::default();
+        let cache = BlockCache::new(1 << 10);
+        let cached = cache.wrap(&store);
+        let (a, b) = (cid(b"aaaa"), cid(b"bbbb"));
+        cached
+            .put_many_keyed_unchecked([(a, &b"aaaa"[..]), (b, &b"bbbb"[..])])
+            .unwrap();
+        assert_eq!(store.unchecked.get(), 2);
+        assert!(store.mem.has(&a).unwrap());
+
+        // The written blocks are cached.
+        assert!(cache.contains(&a));
+        assert!(cache.contains(&b));
+    }
+}
@@ -11,6 +11,15 @@ pub mod tracking;
 mod memory;
 pub use memory::MemoryBlockstore;
 
+mod lru;
+pub use lru::{CacheStats, LruCache};
+
+mod cache;
+pub use cache::{BlockCache, CachedBlockstore};
+
+mod read_through;
+pub use read_through::{AsyncBlockFetcher, BlockFetcher, BlockingFetcher, ReadThroughBlockstore};
+
 mod block;
 pub use block::*;
 
@@ -82,6 +91,22 @@ pub trait Blockstore {
         }
         Ok(())
     }
+
+    /// Bulk-put pre-keyed blocks whose keys are trusted to match their contents.
+    ///
+    /// Blockstores that re-hash blocks on write to check their keys may skip that check here. Only
+    /// use this for blocks known to be well-formed (e.g., blocks written by state migrations or
+    /// imported from verified snapshots), never for blocks written during consensus execution.
+    ///
+    /// By default, this defers to put_many_keyed.
+    fn put_many_keyed_unchecked<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        self.put_many_keyed(blocks)
+    }
 }
 
 pub trait Buffered: Blockstore {
@@ -130,6 +155,15 @@ macro_rules! impl_blockstore {
                 {
                     (**self).put_many_keyed(blocks)
                 }
+
+                fn put_many_keyed_unchecked<D, I>(&self, blocks: I) -> Result<()>
+                where
+                    Self: Sized,
+                    D: AsRef<[u8]>,
+                    I: IntoIterator<Item = (Cid, D)>,
+                {
+                    (**self).put_many_keyed_unchecked(blocks)
+                }
             }
         )+
     }
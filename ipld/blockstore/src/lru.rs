@@ -0,0 +1,238 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Hit statistics of an [`LruCache`] (and of the caches built on it).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of lookups served from the cache.
+    pub hits: usize,
+    /// Number of lookups that missed the cache.
+    pub misses: usize,
+    /// Number of entries evicted to stay within the byte limit.
+    pub evictions: usize,
+}
+
+struct Entry<V> {
+    value: V,
+    size: usize,
+    last_used: u64,
+}
+
+struct CacheInner<K, V> {
+    max_bytes: usize,
+    bytes: usize,
+    tick: u64,
+    entries: HashMap<K, Entry<V>>,
+    /// Cached keys ordered by last use, least recently used first.
+    lru: BTreeMap<u64, K>,
+    stats: CacheStats,
+}
+
+impl<K, V> CacheInner<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    fn touch(&mut self, k: &K) -> Option<&Entry<V>> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(k)?;
+        self.lru.remove(&entry.last_used);
+        self.lru.insert(self.tick, k.clone());
+        entry.last_used = self.tick;
+        Some(entry)
+    }
+
+    fn insert(&mut self, k: K, size: usize, value: impl FnOnce() -> V) {
+        if size > self.max_bytes || self.touch(&k).is_some() {
+            return;
+        }
+        while self.bytes + size > self.max_bytes {
+            let Some((_, evicted)) = self.lru.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&evicted) {
+                self.bytes -= entry.size;
+                self.stats.evictions += 1;
+            }
+        }
+        self.bytes += size;
+        self.lru.insert(self.tick, k.clone());
+        self.entries.insert(
+            k,
+            Entry {
+                value: value(),
+                size,
+                last_used: self.tick,
+            },
+        );
+    }
+}
+
+/// A least-recently-used cache, bounded by the total size of the cached values (as given on
+/// insertion) rather than by their number.
+///
+/// The cache is a cheaply clonable handle: clones share the same underlying cache. This is the
+/// core of [`BlockCache`](crate::BlockCache), and of the node caches of the IPLD data structures
+/// built on this crate.
+pub struct LruCache<K, V> {
+    inner: Arc<Mutex<CacheInner<K, V>>>,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Creates an empty cache holding at most `max_bytes` bytes of values. Values larger than
+    /// `max_bytes` are never cached.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CacheInner {
+                max_bytes,
+                bytes: 0,
+                tick: 0,
+                entries: HashMap::new(),
+                lru: BTreeMap::new(),
+                stats: CacheStats::default(),
+            })),
+        }
+    }
+
+    /// Looks up the value cached under `k`, marking it as recently used, and returns the result of
+    /// `f` applied to it (usually a copy).
+    pub fn get<R>(&self, k: &K, f: impl FnOnce(&V) -> R) -> Option<R> {
+        let mut inner = self.lock();
+        let res = inner.touch(k).map(|e| f(&e.value));
+        if res.is_some() {
+            inner.stats.hits += 1;
+        } else {
+            inner.stats.misses += 1;
+        }
+        res
+    }
+
+    /// Returns true if a value is cached under `k`. This doesn't affect the statistics, nor the
+    /// eviction order.
+    pub fn contains(&self, k: &K) -> bool {
+        self.lock().entries.contains_key(k)
+    }
+
+    /// Caches the value returned by `value`, of the given `size`, under `k`, evicting the least
+    /// recently used values as necessary. If a value is already cached under `k`, it's marked as
+    /// recently used instead, and `value` isn't called.
+    pub fn insert_with(&self, k: K, size: usize, value: impl FnOnce() -> V) {
+        self.lock().insert(k, size, value)
+    }
+
+    /// Returns the hit statistics accumulated since the cache was created (or last cleared).
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats
+    }
+
+    /// Returns the total size of the cached values, in bytes.
+    pub fn size(&self) -> usize {
+        self.lock().bytes
+    }
+
+    /// Returns the number of cached values.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns true if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Evicts everything and resets the statistics.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.bytes = 0;
+        inner.entries.clear();
+        inner.lru.clear();
+        inner.stats = CacheStats::default();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheInner<K, V>> {
+        self.inner.lock().expect("cache poisoned")
+    }
+}
+
+impl<K, V> Clone for LruCache<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for LruCache<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LruCache")
+            .field("size", &self.size())
+            .field("len", &self.len())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eviction() {
+        let cache = LruCache::new(8);
+        cache.insert_with("a", 4, || 1);
+        cache.insert_with("b", 4, || 2);
+        assert_eq!(cache.size(), 8);
+
+        // Touch `a` so `b` is evicted first.
+        assert_eq!(cache.get(&"a", |v| *v), Some(1));
+        cache.insert_with("c", 4, || 3);
+        assert!(cache.contains(&"a"));
+        assert!(!cache.contains(&"b"));
+        assert!(cache.contains(&"c"));
+        assert_eq!(cache.len(), 2);
+
+        // Re-inserting only marks the value as recently used.
+        cache.insert_with("a", 4, || unreachable!());
+        cache.insert_with("d", 4, || 4);
+        assert!(cache.contains(&"a"));
+        assert!(!cache.contains(&"c"));
+
+        // Values larger than the cache are ignored.
+        cache.insert_with("big", 9, || 5);
+        assert!(!cache.contains(&"big"));
+
+        assert_eq!(cache.get(&"b", |v| *v), None);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 2,
+            }
+        );
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.size(), 0);
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn shared() {
+        let cache = LruCache::new(8);
+        let other = cache.clone();
+        other.insert_with("a", 4, || 1);
+        assert_eq!(cache.get(&"a", |v| *v), Some(1));
+        assert_eq!(other.stats().hits, 1);
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use multihash_codetable::{Code, MultihashDigest};
+
+use super::Blockstore;
+
+/// The multihash code of the identity "hash".
+const IDENTITY_HASH: u64 = 0x0;
+
+/// A source of blocks that aren't available locally, e.g. a remote node or a network of peers.
+pub trait BlockFetcher {
+    /// Fetches the block with the given CID, returning `None` if the block couldn't be found.
+    ///
+    /// Fetched blocks don't need to be verified, the [`ReadThroughBlockstore`] checks them against
+    /// their CIDs.
+    fn fetch(&self, k: &Cid) -> Result<Option<Vec<u8>>>;
+}
+
+impl<F> BlockFetcher for F
+where
+    F: Fn(&Cid) -> Result<Option<Vec<u8>>>,
+{
+    fn fetch(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        self(k)
+    }
+}
+
+/// An asynchronous [`BlockFetcher`]. Wrap it in a [`BlockingFetcher`] to use it with a
+/// [`ReadThroughBlockstore`].
+pub trait AsyncBlockFetcher {
+    /// Fetches the block with the given CID, returning `None` if the block couldn't be found.
+    fn fetch(&self, k: &Cid) -> impl Future<Output = Result<Option<Vec<u8>>>>;
+}
+
+/// Adapts an [`AsyncBlockFetcher`] into a [`BlockFetcher`] by blocking the current thread until
+/// each fetch completes.
+///
+/// The futures are driven by a minimal executor that parks the current thread while waiting, so
+/// they must not depend on a specific async runtime. Fetchers built on such a runtime should
+/// implement [`BlockFetcher`] directly using that runtime's `block_on`.
+#[derive(Debug)]
+pub struct BlockingFetcher<F> {
+    inner: F,
+}
+
+impl<F> BlockingFetcher<F>
+where
+    F: AsyncBlockFetcher,
+{
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+
+    /// Consumes the adapter, returning the wrapped fetcher.
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F> BlockFetcher for BlockingFetcher<F>
+where
+    F: AsyncBlockFetcher,
+{
+    fn fetch(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        block_on(self.inner.fetch(k))
+    }
+}
+
+/// Wakes a thread parked in [`block_on`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls the future to completion on the current thread.
+fn block_on<T>(fut: impl Future<Output = T>) -> T {
+    let mut fut = pin!(fut);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(res) => return res,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// A blockstore that reads through to a remote [`BlockFetcher`] when a block is missing from the
+/// local store, writing fetched blocks to the local store.
+///
+/// This lets light clients execute messages against partial state, resolving misses on demand.
+/// Writes only go to the local store.
+#[derive(Debug)]
+pub struct ReadThroughBlockstore<L, R> {
+    local: L,
+    remote: R,
+}
+
+impl<L, R> ReadThroughBlockstore<L, R>
+where
+    L: Blockstore,
+    R: BlockFetcher,
+{
+    pub fn new(local: L, remote: R) -> Self {
+        Self { local, remote }
+    }
+
+    /// Returns a reference to the local store.
+    pub fn local(&self) -> &L {
+        &self.local
+    }
+
+    /// Returns a reference to the remote fetcher.
+    pub fn remote(&self) -> &R {
+        &self.remote
+    }
+
+    /// Consumes the store, returning the local store and the remote fetcher.
+    pub fn into_inner(self) -> (L, R) {
+        (self.local, self.remote)
+    }
+
+    /// Fetches a block from the remote, checks it against its CID, and writes it to the local store.
+    fn fetch(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        let Some(data) = self.remote.fetch(k)? else {
+            return Ok(None);
+        };
+        verify(k, &data)?;
+        self.local.put_keyed(k, &data)?;
+        Ok(Some(data))
+    }
+}
+
+/// Checks that `data` hashes to the multihash in `k`.
+fn verify(k: &Cid, data: &[u8]) -> Result<()> {
+    let valid = if k.hash().code() == IDENTITY_HASH {
+        k.hash().digest() == data
+    } else {
+        let code = Code::try_from(k.hash().code())
+            .map_err(|_| anyhow!("cannot verify block {k}: unsupported hash function"))?;
+        code.digest(data) == *k.hash()
+    };
+    if !valid {
+        return Err(anyhow!("fetched block doesn't match its CID {k}"));
+    }
+    Ok(())
+}
+
+impl<L, R> Blockstore for ReadThroughBlockstore<L, R>
+where
+    L: Blockstore,
+    R: BlockFetcher,
+{
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        match self.local.get(k)? {
+            Some(data) => Ok(Some(data)),
+            None => self.fetch(k),
+        }
+    }
+
+    fn has(&self, k: &Cid) -> Result<bool> {
+        Ok(self.local.has(k)? || self.fetch(k)?.is_some())
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        self.local.put_keyed(k, block)
+    }
+
+    fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        self.local.put_many_keyed(blocks)
+    }
+
+    fn put_many_keyed_unchecked<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        self.local.put_many_keyed_unchecked(blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{Block, MemoryBlockstore};
+
+    #[test]
+    fn read_through() {
+        let remote = MemoryBlockstore::default();
+        let block = Block::new(0x55, &b"foobar"[..]);
+        let k = remote.put(Code::Blake2b256, &block).unwrap();
+        let missing = Block::new(0x55, &b"missing"[..]).cid(Code::Blake2b256);
+
+        let fetches = Cell::new(0);
+        let fetcher = |k: &Cid| {
+            fetches.set(fetches.get() + 1);
+            remote.get(k)
+        };
+        let store = ReadThroughBlockstore::new(MemoryBlockstore::default(), fetcher);
+
+        // Misses are fetched and cached locally.
+        assert_eq!(store.get(&k).unwrap().as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(fetches.get(), 1);
+        assert!(store.local().has(&k).unwrap());
+        assert!(store.has(&k).unwrap());
+        assert_eq!(store.get(&k).unwrap().as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(fetches.get(), 1);
+
+        // Blocks missing remotely are missing.
+        assert_eq!(store.get(&missing).unwrap(), None);
+        assert!(!store.has(&missing).unwrap());
+
+        // Writes only go to the local store.
+        let other = store
+            .put(Code::Blake2b256, &Block::new(0x55, &b"other"[..]))
+            .unwrap();
+        assert!(store.local().has(&other).unwrap());
+        assert!(!remote.has(&other).unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_blocks() {
+        let k = Block::new(0x55, &b"foobar"[..]).cid(Code::Blake2b256);
+        let store = ReadThroughBlockstore::new(MemoryBlockstore::default(), |_: &Cid| {
+            Ok(Some(b"not foobar".to_vec()))
+        });
+        assert!(store.get(&k).is_err());
+        assert!(!store.local().has(&k).unwrap());
+    }
+
+    #[test]
+    fn blocking_fetcher() {
+        struct Remote(MemoryBlockstore);
+
+        impl AsyncBlockFetcher for Remote {
+            async fn fetch(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+                self.0.get(k)
+            }
+        }
+
+        let remote = MemoryBlockstore::default();
+        let k = remote
+            .put(Code::Blake2b256, &Block::new(0x55, &b"foobar"[..]))
+            .unwrap();
+
+        let store = ReadThroughBlockstore::new(
+            MemoryBlockstore::default(),
+            BlockingFetcher::new(Remote(remote)),
+        );
+        assert_eq!(store.get(&k).unwrap().as_deref(), Some(&b"foobar"[..]));
+    }
+}
@@ -107,6 +107,21 @@ where
             }))?;
         Ok(())
     }
+
+    fn put_many_keyed_unchecked<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        let mut stats = self.stats.borrow_mut();
+        self.base
+            .put_many_keyed_unchecked(blocks.into_iter().inspect(|(_, b)| {
+                stats.w += 1;
+                stats.bw += b.as_ref().len();
+            }))?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
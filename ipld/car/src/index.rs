@@ -0,0 +1,112 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::HashMap;
+
+use cid::Cid;
+use futures::{AsyncRead, AsyncWrite};
+use fvm_ipld_encoding::{from_slice, to_vec};
+
+use super::error::Error;
+use super::util::{ld_read, ld_write};
+
+/// The version of the side-car index format written by [`CarIndex::write_async`].
+const INDEX_VERSION: u64 = 1;
+
+/// An index of the blocks in a CARv1 file, mapping each CID to the offset of its section (the
+/// varint length prefix) in the file. It can be written to, and read from, a side-car file
+/// (conventionally named `<file>.carindex`) to allow random access to the blocks in the CAR with
+/// [`CarReader::seek_block`](crate::CarReader::seek_block).
+///
+/// The index is stored as a sequence of varint length-prefixed sections: a CBOR encoded version
+/// number, followed by one section per block containing the block's CID and its offset (as a
+/// big-endian u64), ordered by offset.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CarIndex {
+    offsets: HashMap<Cid, u64>,
+}
+
+impl CarIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the offset of the section containing the block with the given CID. If the CID is
+    /// already indexed (i.e., the block is duplicated in the CAR), the first offset is kept.
+    pub fn insert(&mut self, cid: Cid, offset: u64) {
+        self.offsets.entry(cid).or_insert(offset);
+    }
+
+    /// Returns the offset of the section containing the block with the given CID, if indexed.
+    pub fn get(&self, cid: &Cid) -> Option<u64> {
+        self.offsets.get(cid).copied()
+    }
+
+    /// Returns true if the block with the given CID is indexed.
+    pub fn contains(&self, cid: &Cid) -> bool {
+        self.offsets.contains_key(cid)
+    }
+
+    /// Returns the number of indexed blocks.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns true if no blocks are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Iterates over the indexed CIDs and their offsets, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Cid, u64)> + '_ {
+        self.offsets.iter().map(|(k, v)| (k, *v))
+    }
+
+    /// Writes the index to the given writer.
+    pub async fn write_async<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: AsyncWrite + Send + Unpin,
+    {
+        ld_write(writer, &to_vec(&INDEX_VERSION)?).await?;
+
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by_key(|(_, offset)| *offset);
+        for (cid, offset) in entries {
+            ld_write(
+                writer,
+                &[cid.to_bytes(), offset.to_be_bytes().to_vec()].concat(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Reads an index written by [`CarIndex::write_async`].
+    pub async fn read_async<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let version: u64 = ld_read(reader)
+            .await?
+            .ok_or_else(|| Error::ParsingError("empty CAR index".to_owned()))
+            .and_then(|buf| from_slice(&buf).map_err(|e| Error::ParsingError(e.to_string())))?;
+        if version != INDEX_VERSION {
+            return Err(Error::InvalidFile(format!(
+                "CAR index version must be {INDEX_VERSION}, found {version}"
+            )));
+        }
+
+        let mut index = Self::new();
+        while let Some(buf) = ld_read(reader).await? {
+            let mut cursor = std::io::Cursor::new(&buf);
+            let cid = Cid::read_bytes(&mut cursor)?;
+            let offset = buf[cursor.position() as usize..]
+                .try_into()
+                .map(u64::from_be_bytes)
+                .map_err(|_| Error::ParsingError(format!("invalid CAR index entry for {cid}")))?;
+            index.insert(cid, offset);
+        }
+        Ok(index)
+    }
+}
@@ -3,15 +3,19 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 mod error;
+mod index;
 mod util;
 
+use std::io::SeekFrom;
+
 use cid::Cid;
 pub use error::*;
-use futures::{AsyncRead, AsyncWrite, Stream, StreamExt};
+use futures::{AsyncRead, AsyncSeek, AsyncSeekExt, AsyncWrite, Stream, StreamExt};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{from_slice, to_vec};
+pub use index::CarIndex;
 use serde::{Deserialize, Serialize};
-use util::{ld_read, ld_write, read_node};
+use util::{ld_len, ld_read, ld_write, read_node};
 
 /// CAR file header
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -47,6 +51,34 @@ impl CarHeader {
 
         Ok(())
     }
+
+    /// Writes header and stream of data to writer in Car format, returning an index of the
+    /// written blocks (see [`CarIndex`]).
+    pub async fn write_stream_async_indexed<W, S>(
+        &self,
+        writer: &mut W,
+        stream: &mut S,
+    ) -> Result<CarIndex, Error>
+    where
+        W: AsyncWrite + Send + Unpin,
+        S: Stream<Item = (Cid, Vec<u8>)> + Unpin,
+    {
+        // Write header bytes
+        let header_bytes = to_vec(self)?;
+        ld_write(writer, &header_bytes).await?;
+        let mut offset = ld_len(header_bytes.len());
+
+        // Write all key values from the stream, recording where each one starts.
+        let mut index = CarIndex::new();
+        while let Some((cid, bytes)) = stream.next().await {
+            let section = [cid.to_bytes(), bytes].concat();
+            ld_write(writer, &section).await?;
+            index.insert(cid, offset);
+            offset += ld_len(section.len());
+        }
+
+        Ok(index)
+    }
 }
 
 impl From<Vec<Cid>> for CarHeader {
@@ -60,6 +92,12 @@ pub struct CarReader<R> {
     pub reader: R,
     pub header: CarHeader,
     pub validate: bool,
+    /// The offset of the first block in the CAR.
+    data_offset: u64,
+    /// The offset of the next section to be read.
+    position: u64,
+    /// The index used for random access, if known.
+    index: Option<CarIndex>,
 }
 
 impl<R> CarReader<R>
@@ -78,10 +116,14 @@ where
         if header.version != 1 {
             return Err(Error::InvalidFile("CAR file version must be 1".to_owned()));
         }
+        let data_offset = ld_len(buf.len());
         Ok(CarReader {
             reader,
             header,
             validate: true,
+            data_offset,
+            position: data_offset,
+            index: None,
         })
     }
 
@@ -97,6 +139,7 @@ where
         use multihash_codetable::{Code, MultihashDigest};
         // Read node -> cid, bytes
         if let Some((cid, data)) = read_node(&mut self.reader).await? {
+            self.position += ld_len(cid.encoded_len() + data.len());
             if self.validate {
                 match cid.hash().code() {
                     0x0 => {
@@ -142,6 +185,71 @@ where
             .map_err(|e| Error::Other(e.to_string()))?;
         Ok(self.header.roots)
     }
+
+    /// Returns the offset, in the CAR, of the next block to be read.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Reads the remaining blocks in the CAR, returning an index of them (see [`CarIndex`]).
+    pub async fn read_index(&mut self) -> Result<CarIndex, Error> {
+        let mut index = CarIndex::new();
+        loop {
+            let offset = self.position;
+            match self.next_block().await? {
+                Some(block) => index.insert(block.cid, offset),
+                None => break,
+            }
+        }
+        Ok(index)
+    }
+
+    /// Sets the index used by [`CarReader::seek_block`], e.g. one previously written to a side-car
+    /// file with [`CarIndex::write_async`].
+    pub fn with_index(mut self, index: CarIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Returns the index used by [`CarReader::seek_block`], if set or built.
+    pub fn index(&self) -> Option<&CarIndex> {
+        self.index.as_ref()
+    }
+}
+
+impl<R> CarReader<R>
+where
+    R: AsyncRead + AsyncSeek + Send + Unpin,
+{
+    /// Reads the block with the given CID, returning `None` if the CAR doesn't contain it.
+    ///
+    /// This uses the reader's index (see [`CarReader::with_index`]) to seek directly to the block.
+    /// If no index has been set, one is first built by scanning the whole CAR. Subsequent calls to
+    /// [`CarReader::next_block`] continue from the block after the one returned.
+    pub async fn seek_block(&mut self, cid: &Cid) -> Result<Option<Block>, Error> {
+        if self.index.is_none() {
+            self.seek(self.data_offset).await?;
+            self.index = Some(self.read_index().await?);
+        }
+        let Some(offset) = self.index.as_ref().and_then(|index| index.get(cid)) else {
+            return Ok(None);
+        };
+
+        self.seek(offset).await?;
+        match self.next_block().await? {
+            Some(block) if block.cid == *cid => Ok(Some(block)),
+            _ => Err(Error::InvalidFile(format!(
+                "CAR index doesn't match the CAR: no block {} at offset {}",
+                cid, offset
+            ))),
+        }
+    }
+
+    async fn seek(&mut self, offset: u64) -> Result<(), Error> {
+        self.reader.seek(SeekFrom::Start(offset)).await?;
+        self.position = offset;
+        Ok(())
+    }
 }
 
 /// IPLD Block
@@ -229,4 +337,59 @@ mod tests {
 
         assert_eq!(bs.get(&cid).unwrap(), Some(b"test".to_vec()));
     }
+
+    #[async_std::test]
+    async fn car_index() {
+        let blocks: Vec<_> = (0..10u8)
+            .map(|i| {
+                let data = vec![i; 10 * i as usize];
+                (Cid::new_v1(DAG_CBOR, Blake2b256.digest(&data)), data)
+            })
+            .collect();
+        let header = CarHeader::from(vec![blocks[0].0]);
+
+        let mut buffer = Vec::new();
+        let index = header
+            .write_stream_async_indexed(
+                &mut buffer,
+                &mut futures::stream::iter(blocks.iter().cloned()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(index.len(), blocks.len());
+
+        // Reading the CAR produces the same index.
+        let mut reader = CarReader::new(Cursor::new(&buffer)).await.unwrap();
+        assert_eq!(reader.read_index().await.unwrap(), index);
+
+        // The index survives a roundtrip through a side-car file.
+        let mut index_file = Vec::new();
+        index.write_async(&mut index_file).await.unwrap();
+        let read = CarIndex::read_async(&mut Cursor::new(&index_file))
+            .await
+            .unwrap();
+        assert_eq!(read, index);
+
+        // Seek to blocks with and without a known index.
+        for mut reader in [
+            CarReader::new(Cursor::new(&buffer))
+                .await
+                .unwrap()
+                .with_index(read),
+            CarReader::new(Cursor::new(&buffer)).await.unwrap(),
+        ] {
+            for (cid, data) in blocks.iter().rev() {
+                let block = reader.seek_block(cid).await.unwrap().unwrap();
+                assert_eq!(&block.cid, cid);
+                assert_eq!(&block.data, data);
+            }
+            let missing = Cid::new_v1(DAG_CBOR, Blake2b256.digest(b"missing"));
+            assert!(reader.seek_block(&missing).await.unwrap().is_none());
+
+            // Reading continues after the last block found.
+            reader.seek_block(&blocks[8].0).await.unwrap();
+            assert_eq!(reader.next_block().await.unwrap().unwrap().cid, blocks[9].0);
+            assert!(reader.next_block().await.unwrap().is_none());
+        }
+    }
 }
@@ -55,6 +55,12 @@ where
     Ok(())
 }
 
+/// Returns the length of a varint length-prefixed section holding `len` bytes.
+pub(crate) fn ld_len(len: usize) -> u64 {
+    let mut buff = unsigned_varint::encode::usize_buffer();
+    (unsigned_varint::encode::usize(len, &mut buff).len() + len) as u64
+}
+
 pub(crate) async fn read_node<R>(buf_reader: &mut R) -> Result<Option<(Cid, Vec<u8>)>, Error>
 where
     R: AsyncRead + Send + Unpin,
@@ -82,5 +88,7 @@ mod tests {
         let mut reader = Cursor::new(&buffer);
         let read = ld_read(&mut reader).await.unwrap();
         assert_eq!(read, Some(b"test bytes".to_vec()));
+        assert_eq!(ld_len(b"test bytes".len()), buffer.len() as u64);
+        assert_eq!(ld_len(300), 302);
     }
 }
@@ -7,6 +7,7 @@
 /// 1. Refuses to decode strings/arrays into "bytes", only accepting "bytes" (hence the "strict"
 ///    part).
 /// 2. Can decode to/from byte arrays.
+/// 3. Can decode to borrowed byte slices without copying, refusing inputs it can't borrow from.
 pub mod strict_bytes {
     use std::borrow::Cow;
     use std::fmt;
@@ -111,6 +112,31 @@ pub mod strict_bytes {
         }
     }
 
+    impl<'de: 'a, 'a> Deserialize<'de> for &'a [u8] {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct SliceVisitor;
+
+            impl<'de> Visitor<'de> for SliceVisitor {
+                type Value = &'de [u8];
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("a borrowed byte array")
+                }
+
+                fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+                where
+                    E: Error,
+                {
+                    Ok(v)
+                }
+            }
+            deserializer.deserialize_bytes(SliceVisitor)
+        }
+    }
+
     impl<'de, const L: usize> Deserialize<'de> for [u8; L] {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
@@ -175,6 +201,13 @@ pub use strict_bytes::ByteBuf as BytesDe;
 #[serde(transparent)]
 pub struct BytesSer<'a>(#[serde(with = "strict_bytes")] pub &'a [u8]);
 
+/// Wrapper for serializing and deserializing a slice of bytes borrowed from the input, without
+/// copying it. Deserialization fails if the bytes can't be borrowed (e.g., when decoding from a
+/// reader).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Eq, PartialEq, Clone, Copy)]
+#[serde(transparent)]
+pub struct BytesRef<'a>(#[serde(borrow, with = "strict_bytes")] pub &'a [u8]);
+
 pub fn bytes_32(buf: &[u8]) -> [u8; 32] {
     let mut array = [0; 32];
     array.copy_from_slice(buf.as_ref());
@@ -188,7 +221,7 @@ pub use strict_bytes as serde_bytes;
 mod test {
     use serde::{Deserialize, Serialize};
 
-    use crate::{from_slice, strict_bytes, to_vec, BytesDe, BytesSer};
+    use crate::{from_slice, strict_bytes, to_vec, BytesDe, BytesRef, BytesSer};
 
     #[test]
     fn round_trip() {
@@ -209,6 +242,21 @@ mod test {
         assert_eq!(input, result);
     }
 
+    #[test]
+    fn borrowed() {
+        let buf = &[1u8, 2, 3, 4][..];
+        let serialized = to_vec(&BytesRef(buf)).unwrap();
+        assert_eq!(serialized, to_vec(&BytesSer(buf)).unwrap());
+
+        let result: BytesRef = from_slice(&serialized).unwrap();
+        assert_eq!(buf, result.0);
+        // The bytes are borrowed from the input rather than copied.
+        assert_eq!(result.0.as_ptr(), serialized[1..].as_ptr());
+
+        from_slice::<BytesRef>(&to_vec(&"abcde").unwrap())
+            .expect_err("can't decode string into bytes");
+    }
+
     #[test]
     fn from_string_fails() {
         let serialized = to_vec(&"abcde").unwrap();
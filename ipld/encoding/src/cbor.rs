@@ -85,8 +85,9 @@ impl RawBytes {
         &self.bytes
     }
 
-    /// Deserializes the serialized bytes into a defined type.
-    pub fn deserialize<O: de::DeserializeOwned>(&self) -> Result<O, Error> {
+    /// Deserializes the serialized bytes into a defined type. The result may borrow from these
+    /// bytes (e.g., through [`BytesRef`](crate::BytesRef)) instead of copying them.
+    pub fn deserialize<'de, O: de::Deserialize<'de>>(&'de self) -> Result<O, Error> {
         from_slice(&self.bytes)
     }
 }
@@ -103,7 +104,18 @@ impl Debug for RawBytes {
 
 #[cfg(test)]
 mod test {
-    use crate::RawBytes;
+    use crate::{BytesRef, RawBytes};
+
+    #[test]
+    fn deserialize_borrowed() {
+        let payload = vec![7u8; 1024];
+        let raw = RawBytes::serialize((BytesRef(&payload), 1u64)).unwrap();
+
+        let (BytesRef(borrowed), n): (BytesRef, u64) = raw.deserialize().unwrap();
+        assert_eq!(borrowed, payload);
+        assert_eq!(n, 1);
+        assert!(raw.bytes().as_ptr_range().contains(&borrowed.as_ptr()));
+    }
 
     #[test]
     fn debug_hex() {
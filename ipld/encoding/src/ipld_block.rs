@@ -34,12 +34,14 @@ impl Debug for IpldBlock {
 }
 
 impl IpldBlock {
+    /// Deserializes the block according to its codec. The result may borrow from the block's data
+    /// (e.g., through [`BytesRef`](crate::BytesRef)) instead of copying it.
     pub fn deserialize<'de, T>(&'de self) -> Result<T, Error>
     where
         T: serde::Deserialize<'de>,
     {
         match self.codec {
-            IPLD_RAW => T::deserialize(value::BytesDeserializer::<value::Error>::new(
+            IPLD_RAW => T::deserialize(value::BorrowedBytesDeserializer::<value::Error>::new(
                 self.data.as_slice(),
             ))
             .map_err(|e| Error {
@@ -92,6 +94,21 @@ impl From<RawBytes> for Option<IpldBlock> {
 #[cfg(test)]
 mod test {
     use super::IpldBlock;
+    use crate::{BytesRef, CBOR, IPLD_RAW};
+
+    #[test]
+    fn deserialize_borrowed() {
+        let payload = vec![7u8; 1024];
+        for codec in [IPLD_RAW, CBOR] {
+            let block = IpldBlock::serialize(codec, &BytesRef(&payload)).unwrap();
+            let BytesRef(borrowed) = block.deserialize().unwrap();
+            assert_eq!(borrowed, payload);
+            assert!(block.data.as_ptr_range().contains(&borrowed.as_ptr()));
+
+            let owned: Vec<u8> = block.deserialize::<crate::BytesDe>().unwrap().into_vec();
+            assert_eq!(owned, payload);
+        }
+    }
 
     #[test]
     fn debug_hex() {
@@ -0,0 +1,145 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Representative HAMT workloads, run against several [`Config`]s so that their relative
+//! performance can be compared.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_hamt::{BytesKey, Config, Hamt};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const ITEM_COUNT: usize = 1000;
+const CHURN_ROUNDS: usize = 20;
+const CHURN_BATCH: usize = 10;
+
+const CONFIGS: &[(&str, Config)] = &[
+    (
+        "default",
+        Config {
+            bit_width: 8,
+            min_data_depth: 0,
+            max_array_width: 3,
+            max_key_len: None,
+            max_depth: None,
+        },
+    ),
+    (
+        "bw5",
+        Config {
+            bit_width: 5,
+            min_data_depth: 0,
+            max_array_width: 3,
+            max_key_len: None,
+            max_depth: None,
+        },
+    ),
+    (
+        "bw5-depth2",
+        Config {
+            bit_width: 5,
+            min_data_depth: 2,
+            max_array_width: 1,
+            max_key_len: None,
+            max_depth: None,
+        },
+    ),
+    (
+        "bw3",
+        Config {
+            bit_width: 3,
+            min_data_depth: 0,
+            max_array_width: 3,
+            max_key_len: None,
+            max_depth: None,
+        },
+    ),
+];
+
+/// Deterministic pseudo-random 32 byte keys.
+fn random_keys(n: usize, seed: u64) -> Vec<BytesKey> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n)
+        .map(|_| rng.gen::<[u8; 32]>().to_vec().into())
+        .collect()
+}
+
+fn random_insert(c: &mut Criterion) {
+    let keys = random_keys(ITEM_COUNT, 1);
+    let mut group = c.benchmark_group("HAMT random insert and flush");
+    for (name, conf) in CONFIGS {
+        group.bench_with_input(BenchmarkId::from_parameter(name), conf, |b, conf| {
+            b.iter(|| {
+                let db = MemoryBlockstore::default();
+                let mut a = Hamt::<_, u64>::new_with_config(&db, conf.clone());
+                for (i, k) in keys.iter().enumerate() {
+                    a.set(black_box(k.clone()), black_box(i as u64)).unwrap();
+                }
+                a.flush().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn random_get(c: &mut Criterion) {
+    let keys = random_keys(ITEM_COUNT, 1);
+    let mut group = c.benchmark_group("HAMT random get after load");
+    for (name, conf) in CONFIGS {
+        let db = MemoryBlockstore::default();
+        let mut a = Hamt::<_, u64>::new_with_config(&db, conf.clone());
+        for (i, k) in keys.iter().enumerate() {
+            a.set(k.clone(), i as u64).unwrap();
+        }
+        let cid = a.flush().unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), conf, |b, conf| {
+            b.iter(|| {
+                let a = Hamt::<_, u64>::load_with_config(&cid, &db, conf.clone()).unwrap();
+                for k in keys.iter().step_by(10) {
+                    black_box(a.get(k).unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Repeatedly loads the map, inserts and deletes a few keys, and flushes it again, as actors do
+/// from one message to the next.
+fn flush_churn(c: &mut Criterion) {
+    let keys = random_keys(ITEM_COUNT, 1);
+    let new_keys = random_keys(CHURN_ROUNDS * CHURN_BATCH, 2);
+    let mut group = c.benchmark_group("HAMT flush-heavy churn");
+    for (name, conf) in CONFIGS {
+        let db = MemoryBlockstore::default();
+        let mut a = Hamt::<_, u64>::new_with_config(&db, conf.clone());
+        for (i, k) in keys.iter().enumerate() {
+            a.set(k.clone(), i as u64).unwrap();
+        }
+        let root = a.flush().unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), conf, |b, conf| {
+            b.iter(|| {
+                let mut cid = root;
+                for round in 0..CHURN_ROUNDS {
+                    let mut a = Hamt::<_, u64>::load_with_config(&cid, &db, conf.clone()).unwrap();
+                    let batch = round * CHURN_BATCH..(round + 1) * CHURN_BATCH;
+                    for (k, i) in new_keys[batch.clone()].iter().zip(batch.clone()) {
+                        a.set(black_box(k.clone()), i as u64).unwrap();
+                    }
+                    for k in &keys[batch] {
+                        a.delete(black_box(k)).unwrap();
+                    }
+                    cid = a.flush().unwrap();
+                }
+                cid
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, random_insert, random_get, flush_churn);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt;
+
+use cid::Cid;
+use forest_hash_utils::BytesKey;
+use fvm_ipld_blockstore::{CacheStats, LruCache};
+
+use crate::node::Node;
+use crate::pointer::version;
+use crate::{Config, Sha256};
+
+/// Cached nodes are keyed by depth as well as by CID, as whether a node is valid depends on its
+/// depth.
+type Key = (Cid, u32);
+
+/// Copies a node, without the nodes cached in its links.
+type CopyFn<K, V, H, Ver> = fn(&Node<K, V, H, Ver>) -> Node<K, V, H, Ver>;
+
+/// A least-recently-used cache of decoded HAMT nodes, bounded by the total encoded size of the
+/// cached nodes.
+///
+/// The cache is a cheaply clonable handle: clones share the same underlying cache, so a single
+/// cache can back several HAMTs (see `Hamt::load_with_cache`), saving repeated reads and decoding
+/// of the nodes they have in common. All the HAMTs sharing a cache use its [`Config`].
+pub struct NodeCache<V, K = BytesKey, H = Sha256, Ver = version::V3> {
+    conf: Config,
+    lru: LruCache<Key, Node<K, V, H, Ver>>,
+    /// Copies cached nodes, so nodes can be cached whatever the bounds on the HAMT's keys and
+    /// values.
+    copy: CopyFn<K, V, H, Ver>,
+}
+
+impl<V, K, H, Ver> NodeCache<V, K, H, Ver> {
+    /// Creates an empty cache for HAMTs with the given configuration, holding at most
+    /// `max_bytes` bytes of (encoded) nodes. Nodes larger than `max_bytes` are never cached.
+    pub fn new(conf: Config, max_bytes: usize) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        Self {
+            conf,
+            lru: LruCache::new(max_bytes),
+            copy: Node::copy,
+        }
+    }
+
+    /// Returns the configuration of the HAMTs backed by this cache.
+    pub fn config(&self) -> &Config {
+        &self.conf
+    }
+
+    /// Returns the hit statistics accumulated since the cache was created (or last cleared).
+    pub fn stats(&self) -> CacheStats {
+        self.lru.stats()
+    }
+
+    /// Returns the total encoded size of the cached nodes, in bytes.
+    pub fn size(&self) -> usize {
+        self.lru.size()
+    }
+
+    /// Returns the number of cached nodes.
+    pub fn len(&self) -> usize {
+        self.lru.len()
+    }
+
+    /// Returns true if no nodes are cached.
+    pub fn is_empty(&self) -> bool {
+        self.lru.is_empty()
+    }
+
+    /// Evicts all nodes and resets the statistics.
+    pub fn clear(&self) {
+        self.lru.clear()
+    }
+
+    /// Returns a copy of the cached node with the given CID at the given depth, if any, marking it
+    /// as recently used.
+    pub(crate) fn get(&self, cid: &Cid, depth: u32) -> Option<Node<K, V, H, Ver>> {
+        self.lru.get(&(*cid, depth), self.copy)
+    }
+
+    /// Caches a copy of a node freshly loaded at the given depth, evicting the least recently used
+    /// nodes as necessary.
+    pub(crate) fn insert(&self, cid: &Cid, depth: u32, node: &Node<K, V, H, Ver>, size: usize) {
+        self.lru
+            .insert_with((*cid, depth), size, || (self.copy)(node))
+    }
+}
+
+impl<V, K, H, Ver> Clone for NodeCache<V, K, H, Ver> {
+    fn clone(&self) -> Self {
+        Self {
+            conf: self.conf.clone(),
+            lru: self.lru.clone(),
+            copy: self.copy,
+        }
+    }
+}
+
+impl<V, K, H, Ver> fmt::Debug for NodeCache<V, K, H, Ver> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeCache")
+            .field("conf", &self.conf)
+            .field("size", &self.size())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
@@ -10,9 +10,13 @@ use thiserror::Error;
 /// HAMT Error
 #[derive(Debug, Error)]
 pub enum Error {
-    /// Maximum depth error
+    /// Maximum depth error, either from running out of hash bits or exceeding
+    /// [`Config::max_depth`](crate::Config::max_depth).
     #[error("Maximum depth reached")]
     MaxDepth,
+    /// A key exceeds [`Config::max_key_len`](crate::Config::max_key_len).
+    #[error("Key length {len} exceeds the maximum of {max}")]
+    KeyTooLong { len: usize, max: usize },
     /// Hash bits does not support greater than 8 bit width
     #[error("HashBits does not support retrieving more than 8 bits")]
     InvalidHashBitLen,
@@ -16,7 +16,7 @@ use serde::{Serialize, Serializer};
 use crate::iter::IterImpl;
 use crate::node::Node;
 use crate::pointer::version::Version;
-use crate::{pointer::version, Config, Error, Hash, HashAlgorithm, Sha256};
+use crate::{pointer::version, Config, Error, Hash, HashAlgorithm, NodeCache, Sha256};
 
 /// Implementation of the HAMT data structure for IPLD.
 ///
@@ -47,6 +47,8 @@ pub struct HamtImpl<BS, V, K = BytesKey, H = Sha256, Ver = version::V3> {
     hash: PhantomData<H>,
     /// Remember the last flushed CID until it changes.
     flushed_cid: Option<Cid>,
+    /// The cache nodes are loaded through, if any.
+    cache: Option<NodeCache<V, K, H, Ver>>,
 }
 
 impl<BS, V, K, H, Ver> Serialize for HamtImpl<BS, V, K, H, Ver>
@@ -92,6 +94,19 @@ where
             conf,
             hash: Default::default(),
             flushed_cid: None,
+            cache: None,
+        }
+    }
+
+    /// Construct an empty hamt loading its nodes through the given cache (and using its config).
+    pub fn new_with_cache(store: BS, cache: NodeCache<V, K, H, Ver>) -> Self {
+        Self {
+            root: Node::default(),
+            store,
+            conf: cache.config().clone(),
+            hash: Default::default(),
+            flushed_cid: None,
+            cache: Some(cache),
         }
     }
 
@@ -115,11 +130,31 @@ where
     /// Lazily instantiate a hamt from this root Cid with a specified parameters.
     pub fn load_with_config(cid: &Cid, store: BS, conf: Config) -> Result<Self, Error> {
         Ok(Self {
-            root: Node::load(&conf, &store, cid, 0)?,
+            root: Node::load(&conf, &store, None, cid, 0)?,
+            store,
+            conf,
+            hash: Default::default(),
+            flushed_cid: Some(*cid),
+            cache: None,
+        })
+    }
+
+    /// Lazily instantiate a hamt from this root Cid, loading its nodes through the given cache
+    /// (and using its config). HAMTs sharing a cache share the nodes they have in common, rather
+    /// than each loading and decoding them.
+    pub fn load_with_cache(
+        cid: &Cid,
+        store: BS,
+        cache: NodeCache<V, K, H, Ver>,
+    ) -> Result<Self, Error> {
+        let conf = cache.config().clone();
+        Ok(Self {
+            root: Node::load(&conf, &store, Some(&cache), cid, 0)?,
             store,
             conf,
             hash: Default::default(),
             flushed_cid: Some(*cid),
+            cache: Some(cache),
         })
     }
     /// Lazily instantiate a hamt from this root Cid with a specified bit width.
@@ -136,7 +171,7 @@ where
 
     /// Sets the root based on the Cid of the root node using the Hamt store
     pub fn set_root(&mut self, cid: &Cid) -> Result<(), Error> {
-        self.root = Node::load(&self.conf, &self.store, cid, 0)?;
+        self.root = Node::load(&self.conf, &self.store, self.cache.as_ref(), cid, 0)?;
         self.flushed_cid = Some(*cid);
 
         Ok(())
@@ -173,9 +208,14 @@ where
     where
         V: PartialEq,
     {
-        let (old, modified) = self
-            .root
-            .set(key, value, self.store.borrow(), &self.conf, true)?;
+        let (old, modified) = self.root.set(
+            key,
+            value,
+            self.store.borrow(),
+            self.cache.as_ref(),
+            &self.conf,
+            true,
+        )?;
 
         if modified {
             self.flushed_cid = None;
@@ -216,7 +256,14 @@ where
     {
         let set = self
             .root
-            .set(key, value, self.store.borrow(), &self.conf, false)
+            .set(
+                key,
+                value,
+                self.store.borrow(),
+                self.cache.as_ref(),
+                &self.conf,
+                false,
+            )
             .map(|(_, set)| set)?;
 
         if set {
@@ -252,7 +299,10 @@ where
         Q: Hash + Eq + ?Sized,
         V: DeserializeOwned,
     {
-        match self.root.get(k, self.store.borrow(), &self.conf)? {
+        match self
+            .root
+            .get(k, self.store.borrow(), self.cache.as_ref(), &self.conf)?
+        {
             Some(v) => Ok(Some(v)),
             None => Ok(None),
         }
@@ -283,7 +333,10 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        Ok(self.root.get(k, self.store.borrow(), &self.conf)?.is_some())
+        Ok(self
+            .root
+            .get(k, self.store.borrow(), self.cache.as_ref(), &self.conf)?
+            .is_some())
     }
 
     /// Removes a key from the HAMT, returning the value at the key if the key
@@ -311,7 +364,9 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let deleted = self.root.remove_entry(k, self.store.borrow(), &self.conf)?;
+        let deleted =
+            self.root
+                .remove_entry(k, self.store.borrow(), self.cache.as_ref(), &self.conf)?;
 
         if deleted.is_some() {
             self.flushed_cid = None;
@@ -482,7 +537,7 @@ where
     /// # anyhow::Ok(())
     /// ```
     pub fn iter(&self) -> IterImpl<BS, V, K, H, Ver> {
-        IterImpl::new(&self.store, &self.root, &self.conf)
+        IterImpl::new(&self.store, self.cache.as_ref(), &self.root, &self.conf)
     }
 
     /// Iterate over the HAMT starting at the given key. This can be used to implement "ranged"
@@ -527,7 +582,13 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        IterImpl::new_from(&self.store, &self.root, key, &self.conf)
+        IterImpl::new_from(
+            &self.store,
+            self.cache.as_ref(),
+            &self.root,
+            key,
+            &self.conf,
+        )
     }
 }
 
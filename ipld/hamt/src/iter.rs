@@ -11,11 +11,12 @@ use crate::hash_bits::HashBits;
 use crate::node::Node;
 use crate::pointer::version::Version;
 use crate::pointer::{version, Pointer};
-use crate::{Config, Error, Hash, HashAlgorithm, KeyValuePair, Sha256};
+use crate::{Config, Error, Hash, HashAlgorithm, KeyValuePair, NodeCache, Sha256};
 
 #[doc(hidden)]
 pub struct IterImpl<'a, BS, V, K = BytesKey, H = Sha256, Ver = version::V3> {
     store: &'a BS,
+    cache: Option<&'a NodeCache<V, K, H, Ver>>,
     conf: &'a Config,
     stack: Vec<std::slice::Iter<'a, Pointer<K, V, H, Ver>>>,
     current: std::slice::Iter<'a, KeyValuePair<K, V>>,
@@ -34,10 +35,16 @@ where
     Ver: Version,
     BS: Blockstore,
 {
-    pub(crate) fn new(store: &'a BS, root: &'a Node<K, V, H, Ver>, conf: &'a Config) -> Self {
+    pub(crate) fn new(
+        store: &'a BS,
+        cache: Option<&'a NodeCache<V, K, H, Ver>>,
+        root: &'a Node<K, V, H, Ver>,
+        conf: &'a Config,
+    ) -> Self {
         Self {
             conf,
             store,
+            cache,
             stack: vec![root.pointers.iter()],
             current: [].iter(),
         }
@@ -45,6 +52,7 @@ where
 
     pub(crate) fn new_from<Q>(
         store: &'a BS,
+        cache: Option<&'a NodeCache<V, K, H, Ver>>,
         root: &'a Node<K, V, H, Ver>,
         key: &Q,
         conf: &'a Config,
@@ -63,8 +71,8 @@ where
             stack.push(node.pointers[node.index_for_bit_pos(idx)..].iter());
             node = match stack.last_mut().unwrap().next() {
                 Some(p) => match p {
-                    Pointer::Link { cid, cache } => cache.get_or_try_init(|| {
-                        Node::load(conf, store, cid, stack.len() as u32).map(Box::new)
+                    Pointer::Link { cid, cache: node } => node.get_or_try_init(|| {
+                        Node::load(conf, store, cache, cid, stack.len() as u32).map(Box::new)
                     })?,
                     Pointer::Dirty(node) => node,
                     Pointer::Values(values) => {
@@ -72,6 +80,7 @@ where
                             Some(offset) => Ok(Self {
                                 conf,
                                 store,
+                                cache,
                                 stack,
                                 current: values[offset..].iter(),
                             }),
@@ -106,8 +115,14 @@ where
             match next {
                 Pointer::Link { cid, cache } => {
                     let node = match cache.get_or_try_init(|| {
-                        Node::load(self.conf, &self.store, cid, self.stack.len() as u32)
-                            .map(Box::new)
+                        Node::load(
+                            self.conf,
+                            &self.store,
+                            self.cache,
+                            cid,
+                            self.stack.len() as u32,
+                        )
+                        .map(Box::new)
                     }) {
                         Ok(node) => node,
                         Err(e) => return Some(Err(e)),
@@ -11,6 +11,7 @@
 //! The Hamt is a data structure that mimmics a HashMap which has the features of being sharded, persisted, and indexable by a Cid. The Hamt supports a variable bit width to adjust the amount of possible pointers that can exist at each height of the tree. Hamt can be modified at any point, but the underlying values are only persisted to the store when the [flush](struct.Hamt.html#method.flush) is called.
 
 mod bitfield;
+mod cache;
 mod error;
 mod hamt;
 mod hash;
@@ -23,6 +24,7 @@ mod pointer;
 pub use forest_hash_utils::{BytesKey, Hash};
 use serde::{Deserialize, Serialize};
 
+pub use self::cache::NodeCache;
 pub use self::error::Error;
 pub use self::hamt::{Hamt, Hamtv0};
 pub use self::hash_algorithm::*;
@@ -60,6 +62,24 @@ pub struct Config {
 
     /// Maximum number of key-value pairs in a bucket before it's pushed down.
     pub max_array_width: usize,
+
+    /// The maximum length of a key, measured as the length of its DAG-CBOR encoding.
+    ///
+    /// When set, inserting a longer key fails with [`Error::KeyTooLong`], and so does loading a
+    /// node containing one. Set this when loading untrusted roots, to bound the work a single
+    /// lookup can cause.
+    ///
+    /// Default: `None` (unbounded).
+    pub max_key_len: Option<usize>,
+
+    /// The maximum depth of a node, the root being at depth 0.
+    ///
+    /// When set, loading a deeper node, or inserting a key that would require one, fails with
+    /// [`Error::MaxDepth`]. Otherwise, the depth is only bounded by the length of the hashed keys
+    /// (256 bits, consumed `bit_width` bits per level).
+    ///
+    /// Default: `None`.
+    pub max_depth: Option<u32>,
 }
 
 impl Default for Config {
@@ -69,13 +89,15 @@ impl Default for Config {
             bit_width: DEFAULT_BIT_WIDTH,
             min_data_depth: 0,
             max_array_width: 3,
+            max_key_len: None,
+            max_depth: None,
         }
     }
 }
 
 type HashedKey = [u8; 32];
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 struct KeyValuePair<K, V>(K, V);
 
 impl<K, V> KeyValuePair<K, V> {
@@ -8,7 +8,8 @@ use std::marker::PhantomData;
 
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use fvm_ipld_encoding::{CborStore, CBOR, DAG_CBOR};
+use ipld_core::ipld::Ipld;
 use multihash_codetable::Code;
 use once_cell::unsync::OnceCell;
 use serde::de::DeserializeOwned;
@@ -19,7 +20,7 @@ use super::hash_bits::HashBits;
 use super::pointer::Pointer;
 use super::{Error, Hash, HashAlgorithm, KeyValuePair};
 use crate::pointer::version::{self, Version};
-use crate::Config;
+use crate::{Config, NodeCache};
 
 /// Node in Hamt tree which contains bitfield of set indexes and pointers to nodes
 #[derive(Debug)]
@@ -59,21 +60,69 @@ impl<K, V, H, Ver> Default for Node<K, V, H, Ver> {
     }
 }
 
+impl<K: Clone, V: Clone, H, Ver> Node<K, V, H, Ver> {
+    /// Copies the node, without the nodes cached in its links.
+    pub(crate) fn copy(&self) -> Self {
+        Node {
+            bitfield: self.bitfield,
+            pointers: self
+                .pointers
+                .iter()
+                .map(|p| match p {
+                    Pointer::Values(kvs) => Pointer::Values(kvs.clone()),
+                    Pointer::Link { cid, .. } => Pointer::Link {
+                        cid: *cid,
+                        cache: Default::default(),
+                    },
+                    Pointer::Dirty(node) => Pointer::Dirty(Box::new(node.copy())),
+                })
+                .collect(),
+            hash: Default::default(),
+        }
+    }
+}
+
 impl<K, V, H, Ver> Node<K, V, H, Ver>
 where
     K: PartialOrd + DeserializeOwned,
     V: DeserializeOwned,
     Ver: Version,
 {
+    /// Loads the node with the given CID at the given depth, through the cache if any.
     pub fn load(
         conf: &Config,
         store: &impl Blockstore,
+        cache: Option<&NodeCache<V, K, H, Ver>>,
         k: &Cid,
         depth: u32,
     ) -> Result<Self, Error> {
-        let (bitfield, pointers): (Bitfield, Vec<Pointer<K, V, H, Ver>>) = store
-            .get_cbor(k)?
-            .ok_or_else(|| Error::CidNotFound(k.to_string()))?;
+        let Some(cache) = cache else {
+            return Self::decode(conf, store, k, depth).map(|(node, _)| node);
+        };
+        if let Some(node) = cache.get(k, depth) {
+            return Ok(node);
+        }
+        let (node, size) = Self::decode(conf, store, k, depth)?;
+        cache.insert(k, depth, &node, size);
+        Ok(node)
+    }
+
+    /// Loads and validates the node with the given CID at the given depth, returning it along
+    /// with its encoded size.
+    fn decode(
+        conf: &Config,
+        store: &impl Blockstore,
+        k: &Cid,
+        depth: u32,
+    ) -> Result<(Self, usize), Error> {
+        check_depth(conf, depth)?;
+
+        let block = get_node_block(store, k)?;
+        let (bitfield, pointers): (Bitfield, Vec<Pointer<K, V, H, Ver>>) =
+            fvm_ipld_encoding::from_slice(&block)?;
+        if let Some(max) = conf.max_key_len {
+            check_encoded_key_lens(&block, max)?;
+        }
 
         if pointers.len() > 1 << conf.bit_width {
             return Err(Error::Dynamic(anyhow::anyhow!(
@@ -139,11 +188,12 @@ where
             }
         }
 
-        Ok(Node {
+        let node = Node {
             bitfield,
             pointers,
             hash: Default::default(),
-        })
+        };
+        Ok((node, block.len()))
     }
 }
 
@@ -159,12 +209,19 @@ where
         key: K,
         value: V,
         store: &S,
+        node_cache: Option<&NodeCache<V, K, H, Ver>>,
         conf: &Config,
         overwrite: bool,
     ) -> Result<(Option<V>, bool), Error>
     where
         V: PartialEq,
     {
+        if let Some(max) = conf.max_key_len {
+            let len = fvm_ipld_encoding::to_vec(&key)?.len();
+            if len > max {
+                return Err(Error::KeyTooLong { len, max });
+            }
+        }
         let hash = H::hash(&key);
         self.modify_value(
             &mut HashBits::new(&hash),
@@ -173,6 +230,7 @@ where
             key,
             value,
             store,
+            node_cache,
             overwrite,
         )
     }
@@ -182,13 +240,16 @@ where
         &self,
         k: &Q,
         store: &S,
+        node_cache: Option<&NodeCache<V, K, H, Ver>>,
         conf: &Config,
     ) -> Result<Option<&V>, Error>
     where
         K: Borrow<Q>,
         Q: Eq + Hash + ?Sized,
     {
-        Ok(self.search(k, store, conf)?.map(|kv| kv.value()))
+        Ok(self
+            .search(k, store, node_cache, conf)?
+            .map(|kv| kv.value()))
     }
 
     #[inline]
@@ -196,6 +257,7 @@ where
         &mut self,
         k: &Q,
         store: &S,
+        node_cache: Option<&NodeCache<V, K, H, Ver>>,
         conf: &Config,
     ) -> Result<Option<(K, V)>, Error>
     where
@@ -204,7 +266,7 @@ where
         S: Blockstore,
     {
         let hash = H::hash(k);
-        self.rm_value(&mut HashBits::new(&hash), conf, 0, k, store)
+        self.rm_value(&mut HashBits::new(&hash), conf, 0, k, store, node_cache)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -216,6 +278,7 @@ where
         &self,
         q: &Q,
         store: &S,
+        node_cache: Option<&NodeCache<V, K, H, Ver>>,
         conf: &Config,
     ) -> Result<Option<&KeyValuePair<K, V>>, Error>
     where
@@ -223,7 +286,7 @@ where
         Q: Eq + Hash + ?Sized,
     {
         let hash = H::hash(q);
-        self.get_value(&mut HashBits::new(&hash), conf, 0, q, store)
+        self.get_value(&mut HashBits::new(&hash), conf, 0, q, store, node_cache)
     }
 
     fn get_value<Q, S: Blockstore>(
@@ -233,6 +296,7 @@ where
         depth: u32,
         key: &Q,
         store: &S,
+        node_cache: Option<&NodeCache<V, K, H, Ver>>,
     ) -> Result<Option<&KeyValuePair<K, V>>, Error>
     where
         K: Borrow<Q>,
@@ -248,16 +312,16 @@ where
         let child = self.get_child(cindex);
 
         let node = match child {
-            Pointer::Link { cid, cache } => {
-                cache.get_or_try_init(|| Node::load(conf, store, cid, depth + 1).map(Box::new))?
-            }
+            Pointer::Link { cid, cache } => cache.get_or_try_init(|| {
+                Node::load(conf, store, node_cache, cid, depth + 1).map(Box::new)
+            })?,
             Pointer::Dirty(node) => node,
             Pointer::Values(vals) => {
                 return Ok(vals.iter().find(|kv| key.eq(kv.key().borrow())));
             }
         };
 
-        node.get_value(hashed_key, conf, depth + 1, key, store)
+        node.get_value(hashed_key, conf, depth + 1, key, store, node_cache)
     }
 
     /// Internal method to modify values.
@@ -274,6 +338,7 @@ where
         key: K,
         value: V,
         store: &S,
+        node_cache: Option<&NodeCache<V, K, H, Ver>>,
         overwrite: bool,
     ) -> Result<(Option<V>, bool), Error>
     where
@@ -287,8 +352,18 @@ where
                 self.insert_child(idx, key, value);
             } else {
                 // Need to insert some empty nodes reserved for links.
+                check_depth(conf, depth + 1)?;
                 let mut sub = Node::<K, V, H, Ver>::default();
-                sub.modify_value(hashed_key, conf, depth + 1, key, value, store, overwrite)?;
+                sub.modify_value(
+                    hashed_key,
+                    conf,
+                    depth + 1,
+                    key,
+                    value,
+                    store,
+                    node_cache,
+                    overwrite,
+                )?;
                 self.insert_child_dirty(idx, Box::new(sub));
             }
             return Ok((None, true));
@@ -299,7 +374,9 @@ where
 
         match child {
             Pointer::Link { cid, cache } => {
-                cache.get_or_try_init(|| Node::load(conf, store, cid, depth + 1).map(Box::new))?;
+                cache.get_or_try_init(|| {
+                    Node::load(conf, store, node_cache, cid, depth + 1).map(Box::new)
+                })?;
                 let child_node = cache.get_mut().expect("filled line above");
 
                 let (old, modified) = child_node.modify_value(
@@ -309,6 +386,7 @@ where
                     key,
                     value,
                     store,
+                    node_cache,
                     overwrite,
                 )?;
                 if modified {
@@ -316,9 +394,16 @@ where
                 }
                 Ok((old, modified))
             }
-            Pointer::Dirty(node) => {
-                node.modify_value(hashed_key, conf, depth + 1, key, value, store, overwrite)
-            }
+            Pointer::Dirty(node) => node.modify_value(
+                hashed_key,
+                conf,
+                depth + 1,
+                key,
+                value,
+                store,
+                node_cache,
+                overwrite,
+            ),
             Pointer::Values(vals) => {
                 // Update, if the key already exists.
                 if let Some(i) = vals.iter().position(|p| p.key() == &key) {
@@ -342,6 +427,7 @@ where
 
                 // If the array is full, create a subshard and insert everything
                 if vals.len() >= conf.max_array_width {
+                    check_depth(conf, depth + 1)?;
                     let kvs = std::mem::take(vals);
                     let hashed_kvs = kvs.into_iter().map(|KeyValuePair(k, v)| {
                         let hash = H::hash(&k);
@@ -357,6 +443,7 @@ where
                         key,
                         value,
                         store,
+                        node_cache,
                         overwrite,
                     )?;
 
@@ -368,6 +455,7 @@ where
                             k,
                             v,
                             store,
+                            node_cache,
                             overwrite,
                         )?;
                     }
@@ -397,6 +485,7 @@ where
         depth: u32,
         key: &Q,
         store: &S,
+        node_cache: Option<&NodeCache<V, K, H, Ver>>,
     ) -> Result<Option<(K, V)>, Error>
     where
         K: Borrow<Q>,
@@ -414,10 +503,13 @@ where
 
         match child {
             Pointer::Link { cid, cache } => {
-                cache.get_or_try_init(|| Node::load(conf, store, cid, depth + 1).map(Box::new))?;
+                cache.get_or_try_init(|| {
+                    Node::load(conf, store, node_cache, cid, depth + 1).map(Box::new)
+                })?;
                 let child_node = cache.get_mut().expect("filled line above");
 
-                let deleted = child_node.rm_value(hashed_key, conf, depth + 1, key, store)?;
+                let deleted =
+                    child_node.rm_value(hashed_key, conf, depth + 1, key, store, node_cache)?;
 
                 if deleted.is_some() {
                     *child = Pointer::Dirty(std::mem::take(child_node));
@@ -430,7 +522,7 @@ where
             }
             Pointer::Dirty(node) => {
                 // Delete value and return deleted value
-                let deleted = node.rm_value(hashed_key, conf, depth + 1, key, store)?;
+                let deleted = node.rm_value(hashed_key, conf, depth + 1, key, store, node_cache)?;
 
                 if deleted.is_some() && Self::clean(child, conf, depth)? {
                     self.rm_child(cindex, idx);
@@ -518,6 +610,57 @@ where
     }
 }
 
+/// Reads a node's encoded block. Nodes are decoded from the raw block (rather than with
+/// [`CborStore::get_cbor`]) so that their size and keys can be checked, so this applies the same
+/// codec check as `get_cbor`, whether or not any limits are configured.
+fn get_node_block(store: &impl Blockstore, k: &Cid) -> Result<Vec<u8>, Error> {
+    if !matches!(k.codec(), CBOR | DAG_CBOR) {
+        return Err(Error::Dynamic(anyhow::anyhow!(
+            "{} is not CBOR or DagCBOR",
+            k.codec()
+        )));
+    }
+    store
+        .get(k)?
+        .ok_or_else(|| Error::CidNotFound(k.to_string()))
+}
+
+/// Fails with [`Error::MaxDepth`] if a node at `depth` would exceed [`Config::max_depth`].
+fn check_depth(conf: &Config, depth: u32) -> Result<(), Error> {
+    match conf.max_depth {
+        Some(max) if depth > max => Err(Error::MaxDepth),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the encoded length of the keys in an encoded node (of either version), which has
+/// already been decoded successfully.
+fn check_encoded_key_lens(block: &[u8], max: usize) -> Result<(), Error> {
+    let (_, pointers): (Ipld, Vec<Ipld>) = fvm_ipld_encoding::from_slice(block)?;
+    for pointer in &pointers {
+        let kvs = match pointer {
+            Ipld::List(kvs) => kvs,
+            // Version 0 buckets.
+            Ipld::Map(m) => match m.get("1") {
+                Some(Ipld::List(kvs)) => kvs,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        for kv in kvs {
+            if let Ipld::List(kv) = kv {
+                if let Some(key) = kv.first() {
+                    let len = fvm_ipld_encoding::to_vec(key)?.len();
+                    if len > max {
+                        return Err(Error::KeyTooLong { len, max });
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 impl<K, V, H, Ver> Node<K, V, H, Ver> {
     pub(crate) fn index_for_bit_pos(&self, bp: u8) -> usize {
         let mask = Bitfield::zero().set_bits_le(bp);
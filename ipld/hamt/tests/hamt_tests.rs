@@ -7,13 +7,13 @@ use std::fmt::Display;
 
 use cid::Cid;
 use fvm_ipld_blockstore::tracking::{BSStats, TrackingBlockstore};
-use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_blockstore::{Blockstore, CacheStats, MemoryBlockstore};
 use fvm_ipld_encoding::de::DeserializeOwned;
 use fvm_ipld_encoding::strict_bytes::ByteBuf;
 use fvm_ipld_encoding::CborStore;
 #[cfg(feature = "identity")]
 use fvm_ipld_hamt::Identity;
-use fvm_ipld_hamt::{BytesKey, Config, Error, Hamt, Hash};
+use fvm_ipld_hamt::{BytesKey, Config, Error, Hamt, Hash, NodeCache};
 use multihash_codetable::Code;
 use quickcheck::Arbitrary;
 use rand::seq::SliceRandom;
@@ -643,10 +643,75 @@ fn add_and_remove_keys(
     assert_eq!(*store.stats.borrow(), stats);
 }
 
+#[test]
+fn shared_node_cache() {
+    let store = TrackingBlockstore::new(MemoryBlockstore::default());
+    let conf = Config {
+        bit_width: 2,
+        ..Default::default()
+    };
+    let mut hamt: Hamt<_, u64, u64> = Hamt::new_with_config(&store, conf.clone());
+    for i in 0..100 {
+        hamt.set(i, i).unwrap();
+    }
+    let root = hamt.flush().unwrap();
+
+    // Two maps loaded from the same root share the nodes they decoded through the cache.
+    let cache = NodeCache::new(conf, 1 << 20);
+    let reads = store.stats.borrow().r;
+    let first: Hamt<_, u64, u64> = Hamt::load_with_cache(&root, &store, cache.clone()).unwrap();
+    assert_eq!(first.get(&42).unwrap(), Some(&42));
+    let misses = cache.stats().misses;
+    assert_eq!(cache.stats().hits, 0);
+    assert_eq!(store.stats.borrow().r, reads + misses);
+
+    let mut second: Hamt<_, u64, u64> =
+        Hamt::load_with_cache(&root, &store, cache.clone()).unwrap();
+    assert_eq!(second.get(&42).unwrap(), Some(&42));
+    assert_eq!(cache.stats().misses, misses);
+    assert_eq!(cache.stats().hits, misses);
+    assert_eq!(store.stats.borrow().r, reads + misses);
+
+    // Modifying a map doesn't affect the cached nodes.
+    second.set(42, 0).unwrap();
+    let third: Hamt<_, u64, u64> = Hamt::load_with_cache(&root, &store, cache.clone()).unwrap();
+    assert_eq!(third.get(&42).unwrap(), Some(&42));
+    assert_eq!(store.stats.borrow().r, reads + misses);
+}
+
+#[test]
+fn node_cache_eviction() {
+    let store = MemoryBlockstore::default();
+    let conf = Config {
+        bit_width: 2,
+        ..Default::default()
+    };
+    let mut hamt: Hamt<_, u64, u64> = Hamt::new_with_config(&store, conf.clone());
+    for i in 0..100 {
+        hamt.set(i, i).unwrap();
+    }
+    let root = hamt.flush().unwrap();
+
+    // The cache stays within its byte limit, evicting the least recently used nodes.
+    let cache = NodeCache::new(conf, 256);
+    let hamt: Hamt<_, u64, u64> = Hamt::load_with_cache(&root, &store, cache.clone()).unwrap();
+    for i in 0..100 {
+        assert_eq!(hamt.get(&i).unwrap(), Some(&i));
+    }
+    assert!(cache.size() <= 256);
+    assert!(cache.stats().evictions > 0);
+    assert_eq!(cache.stats().evictions + cache.len(), cache.stats().misses);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.stats(), CacheStats::default());
+}
+
 #[test]
 #[cfg(feature = "identity")]
 fn canonical_structure() {
-    // Champ mutation semantics test
+    // Champ mutation semantics test. The CIDs match those of go-hamt-ipld's
+    // TestCanonicalStructure, checking both implementations build the same trees.
     #[rustfmt::skip]
     add_and_remove_keys(
         8,
@@ -808,6 +873,105 @@ fn max_array_width_reduces_root_size() {
     test_reduced_root_size(factory1, factory2);
 }
 
+#[test]
+fn max_key_len() {
+    let store = MemoryBlockstore::default();
+    let conf = Config {
+        max_key_len: Some(10),
+        ..Default::default()
+    };
+
+    // 9 bytes of key, plus a 1 byte header.
+    let mut hamt: Hamt<_, u8> = Hamt::new_with_config(&store, conf.clone());
+    hamt.set(tstring("123456789"), 1).unwrap();
+    assert!(matches!(
+        hamt.set(tstring("1234567890"), 2),
+        Err(Error::KeyTooLong { len: 11, max: 10 })
+    ));
+    assert_eq!(hamt.get(&tstring("123456789")).unwrap(), Some(&1));
+
+    // Roots with longer keys are rejected on load.
+    let mut unbounded: Hamt<_, u8> = Hamt::new_with_config(&store, Config::default());
+    unbounded.set(tstring("1234567890"), 2).unwrap();
+    let root = unbounded.flush().unwrap();
+    assert!(matches!(
+        Hamt::<_, u8>::load_with_config(&root, &store, conf),
+        Err(Error::KeyTooLong { len: 11, max: 10 })
+    ));
+}
+
+#[test]
+fn max_depth() {
+    let store = MemoryBlockstore::default();
+    let conf = Config {
+        bit_width: 1,
+        max_array_width: 1,
+        max_depth: Some(2),
+        ..Default::default()
+    };
+
+    // With a bit width of 1 and single-entry buckets, a handful of keys can't fit in 3 levels.
+    let mut unbounded: Hamt<_, u8> = Hamt::new_with_config(
+        &store,
+        Config {
+            max_depth: None,
+            ..conf
+        },
+    );
+    let mut hamt: Hamt<_, u8> = Hamt::new_with_config(&store, conf.clone());
+    let mut overflowed = None;
+    for i in 0..16u8 {
+        unbounded.set(tstring(i), i).unwrap();
+        match hamt.set(tstring(i), i) {
+            Ok(_) => {}
+            Err(Error::MaxDepth) => {
+                overflowed = Some(i);
+                break;
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+    let overflowed = overflowed.expect("expected the depth limit to be reached");
+
+    // The keys inserted before the limit was reached are unaffected.
+    for i in 0..overflowed {
+        assert_eq!(hamt.get(&tstring(i)).unwrap(), Some(&i));
+    }
+
+    // Deeper trees are rejected when traversed.
+    let root = unbounded.flush().unwrap();
+    let loaded: Hamt<_, u8> = Hamt::load_with_config(&root, &store, conf).unwrap();
+    assert!(matches!(
+        loaded.for_each(|_, _| Ok(())),
+        Err(Error::MaxDepth)
+    ));
+}
+
+#[test]
+fn load_non_cbor_root() {
+    let store = MemoryBlockstore::default();
+    let mut hamt: Hamt<_, u8> = Hamt::new(&store);
+    hamt.set(tstring(1), 1).unwrap();
+    let root = hamt.flush().unwrap();
+
+    // Nodes are only loaded from CBOR or DAG-CBOR blocks, with or without limits, as when they
+    // were loaded with `CborStore::get_cbor`.
+    let raw = Cid::new_v1(fvm_ipld_encoding::IPLD_RAW, *root.hash());
+    store
+        .put_keyed(&raw, &store.get(&root).unwrap().unwrap())
+        .unwrap();
+    for conf in [
+        Config::default(),
+        Config {
+            max_key_len: Some(10),
+            max_depth: Some(2),
+            ..Default::default()
+        },
+    ] {
+        assert!(Hamt::<_, u8>::load_with_config(&raw, &store, conf).is_err());
+    }
+}
+
 /// List of key value pairs with unique keys.
 ///
 /// Uniqueness is used so insert order doesn't cause overwrites.
@@ -1186,7 +1350,8 @@ test_hamt_mod!(
         conf: Config {
             bit_width: 1,
             min_data_depth: 0,
-            max_array_width: 3
+            max_array_width: 3,
+            ..Default::default()
         },
     }
 );
@@ -1197,7 +1362,8 @@ test_hamt_mod!(
         conf: Config {
             bit_width: 4,
             min_data_depth: 2,
-            max_array_width: 1
+            max_array_width: 1,
+            ..Default::default()
         },
     }
 );
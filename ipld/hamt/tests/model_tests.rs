@@ -0,0 +1,103 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Differential tests checking the HAMT against a `BTreeMap` model.
+
+use std::collections::BTreeMap;
+
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_hamt::{Config, Hamt};
+use quickcheck::{Arbitrary, Gen};
+use quickcheck_macros::quickcheck;
+
+#[derive(Clone, Debug)]
+struct ArbitraryConfig(Config);
+
+impl Arbitrary for ArbitraryConfig {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self(Config {
+            bit_width: *g.choose(&[1, 2, 5, 8]).unwrap(),
+            min_data_depth: u32::arbitrary(g) % 3,
+            max_array_width: usize::arbitrary(g) % 4 + 1,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Operation {
+    Set(u8, i64),
+    SetIfAbsent(u8, i64),
+    Delete(u8),
+    Flush,
+}
+
+impl Arbitrary for Operation {
+    fn arbitrary(g: &mut Gen) -> Self {
+        // Keys are drawn from a small range to induce lots of overlapping operations.
+        let key = u8::arbitrary(g) % 64;
+        match u8::arbitrary(g) % 8 {
+            0 => Operation::Flush,
+            1 => Operation::SetIfAbsent(key, i64::arbitrary(g)),
+            2..=4 => Operation::Delete(key),
+            _ => Operation::Set(key, i64::arbitrary(g)),
+        }
+    }
+}
+
+fn assert_matches_model(hamt: &Hamt<&MemoryBlockstore, i64, u8>, model: &BTreeMap<u8, i64>) {
+    assert_eq!(hamt.is_empty(), model.is_empty());
+    let mut entries = BTreeMap::new();
+    hamt.for_each(|k, v| {
+        assert!(entries.insert(*k, *v).is_none(), "duplicate key {k}");
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(&entries, model);
+}
+
+/// Applies random operations to both a HAMT and a model, checking that they agree after every
+/// step, and that the final tree is identical to one built directly from the model's contents.
+#[quickcheck]
+fn prop_matches_model(ArbitraryConfig(conf): ArbitraryConfig, ops: Vec<Operation>) {
+    let store = MemoryBlockstore::default();
+    let mut hamt: Hamt<_, i64, u8> = Hamt::new_with_config(&store, conf.clone());
+    let mut model = BTreeMap::new();
+
+    for op in ops {
+        match op {
+            Operation::Set(k, v) => {
+                assert_eq!(hamt.set(k, v).unwrap(), model.insert(k, v));
+            }
+            Operation::SetIfAbsent(k, v) => {
+                let absent = !model.contains_key(&k);
+                if absent {
+                    model.insert(k, v);
+                }
+                assert_eq!(hamt.set_if_absent(k, v).unwrap(), absent);
+            }
+            Operation::Delete(k) => {
+                assert_eq!(hamt.delete(&k).unwrap(), model.remove(&k).map(|v| (k, v)));
+            }
+            Operation::Flush => {
+                let cid = hamt.flush().unwrap();
+                hamt = Hamt::load_with_config(&cid, &store, conf.clone()).unwrap();
+            }
+        }
+        for (k, v) in &model {
+            assert_eq!(hamt.get(k).unwrap(), Some(v));
+        }
+    }
+    assert_matches_model(&hamt, &model);
+
+    let cid = hamt.flush().unwrap();
+    let reloaded = Hamt::load_with_config(&cid, &store, conf.clone()).unwrap();
+    assert_matches_model(&reloaded, &model);
+
+    // The structure is canonical: it doesn't depend on the history of operations.
+    let mut fresh: Hamt<_, i64, u8> = Hamt::new_with_config(&store, conf);
+    for (k, v) in &model {
+        fresh.set(*k, *v).unwrap();
+    }
+    assert_eq!(fresh.flush().unwrap(), cid);
+}
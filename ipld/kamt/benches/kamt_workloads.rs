@@ -0,0 +1,163 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Representative KAMT workloads, shaped like EVM contract storage, run against several
+//! [`Config`]s so that their relative performance can be compared.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_ipld_kamt::id::Identity;
+use fvm_ipld_kamt::{Config, Kamt};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Storage slots holding a contract's fixed-layout state variables.
+const SEQUENTIAL_SLOTS: usize = 64;
+/// Storage slots of mapping entries, whose keys are keccak-256 hashes.
+const MAPPING_SLOTS: usize = 1000;
+const CHURN_ROUNDS: usize = 20;
+const CHURN_BATCH: usize = 10;
+
+const CONFIGS: &[(&str, Config)] = &[
+    (
+        "evm",
+        Config {
+            bit_width: 5,
+            min_data_depth: 0,
+            max_array_width: 1,
+        },
+    ),
+    (
+        "evm-width3",
+        Config {
+            bit_width: 5,
+            min_data_depth: 0,
+            max_array_width: 3,
+        },
+    ),
+    (
+        "evm-depth2",
+        Config {
+            bit_width: 5,
+            min_data_depth: 2,
+            max_array_width: 1,
+        },
+    ),
+    (
+        "default",
+        Config {
+            bit_width: 8,
+            min_data_depth: 0,
+            max_array_width: 3,
+        },
+    ),
+];
+
+type Slot = [u8; 32];
+type Storage<'a> = Kamt<&'a MemoryBlockstore, Slot, RawBytes, Identity>;
+
+/// Deterministic pseudo-random slots, standing in for hashed mapping keys.
+fn mapping_slots(n: usize, seed: u64) -> Vec<Slot> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n).map(|_| rng.gen()).collect()
+}
+
+/// Small, big-endian slot numbers, as used by state variables and fixed-size arrays.
+fn sequential_slots(n: usize) -> Vec<Slot> {
+    (0..n)
+        .map(|i| {
+            let mut slot = [0u8; 32];
+            slot[24..].copy_from_slice(&(i as u64).to_be_bytes());
+            slot
+        })
+        .collect()
+}
+
+fn word(i: usize) -> RawBytes {
+    RawBytes::new((i as u64 + 1).to_be_bytes().to_vec())
+}
+
+fn evm_slots() -> Vec<Slot> {
+    let mut slots = sequential_slots(SEQUENTIAL_SLOTS);
+    slots.extend(mapping_slots(MAPPING_SLOTS, 1));
+    slots
+}
+
+fn populate<'a>(db: &'a MemoryBlockstore, conf: &Config, slots: &[Slot]) -> Storage<'a> {
+    let mut a = Storage::new_with_config(db, conf.clone());
+    for (i, slot) in slots.iter().enumerate() {
+        a.set(*slot, word(i)).unwrap();
+    }
+    a
+}
+
+fn storage_insert(c: &mut Criterion) {
+    let slots = evm_slots();
+    let mut group = c.benchmark_group("KAMT EVM storage insert and flush");
+    for (name, conf) in CONFIGS {
+        group.bench_with_input(BenchmarkId::from_parameter(name), conf, |b, conf| {
+            b.iter(|| {
+                let db = MemoryBlockstore::default();
+                let mut a = populate(&db, conf, black_box(&slots));
+                a.flush().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn storage_get(c: &mut Criterion) {
+    let slots = evm_slots();
+    let mut group = c.benchmark_group("KAMT EVM storage get after load");
+    for (name, conf) in CONFIGS {
+        let db = MemoryBlockstore::default();
+        let cid = populate(&db, conf, &slots).flush().unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), conf, |b, conf| {
+            b.iter(|| {
+                let a = Storage::load_with_config(&cid, &db, conf.clone()).unwrap();
+                for slot in slots.iter().step_by(10) {
+                    black_box(a.get(slot).unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Repeatedly loads the storage, overwrites, clears, and adds a few slots, and flushes it again,
+/// as contract invocations do.
+fn flush_churn(c: &mut Criterion) {
+    let slots = evm_slots();
+    let new_slots = mapping_slots(CHURN_ROUNDS * CHURN_BATCH, 2);
+    let mut group = c.benchmark_group("KAMT EVM storage flush-heavy churn");
+    for (name, conf) in CONFIGS {
+        let db = MemoryBlockstore::default();
+        let root = populate(&db, conf, &slots).flush().unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), conf, |b, conf| {
+            b.iter(|| {
+                let mut cid = root;
+                for round in 0..CHURN_ROUNDS {
+                    let mut a = Storage::load_with_config(&cid, &db, conf.clone()).unwrap();
+                    // Update a state variable.
+                    a.set(slots[round % SEQUENTIAL_SLOTS], word(round)).unwrap();
+                    let batch = round * CHURN_BATCH..(round + 1) * CHURN_BATCH;
+                    for (slot, i) in new_slots[batch.clone()].iter().zip(batch.clone()) {
+                        a.set(black_box(*slot), word(i)).unwrap();
+                    }
+                    for slot in &slots[SEQUENTIAL_SLOTS..][batch] {
+                        a.delete(black_box(slot)).unwrap();
+                    }
+                    cid = a.flush().unwrap();
+                }
+                cid
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, storage_insert, storage_get, flush_churn);
+criterion_main!(benches);
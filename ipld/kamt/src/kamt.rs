@@ -13,7 +13,7 @@ use serde::{Serialize, Serializer};
 
 use crate::iter::Iter;
 use crate::node::Node;
-use crate::{AsHashedKey, Config, Error};
+use crate::{AsHashedKey, Config, Error, PruneStats};
 
 /// Implementation of the KAMT data structure for IPLD.
 ///
@@ -39,6 +39,8 @@ pub struct Kamt<BS, K, V, H, const N: usize = 32> {
     conf: Config,
     /// Remember the last flushed CID until it changes.
     flushed_cid: Option<Cid>,
+    /// Statistics about deletes since the KAMT was created or loaded.
+    prune_stats: PruneStats,
 }
 
 impl<BS, K, V, H, const N: usize> Serialize for Kamt<BS, K, V, H, N>
@@ -79,6 +81,7 @@ where
             store,
             conf,
             flushed_cid: None,
+            prune_stats: PruneStats::default(),
         }
     }
 
@@ -95,6 +98,7 @@ where
             store,
             conf,
             flushed_cid: Some(*cid),
+            prune_stats: PruneStats::default(),
         })
     }
 
@@ -317,15 +321,46 @@ where
         Q: PartialEq,
         H: AsHashedKey<Q, N>,
     {
-        let deleted = self.root.remove_entry(k, self.store.borrow(), &self.conf)?;
+        let deleted =
+            self.root
+                .remove_entry(k, self.store.borrow(), &self.conf, &mut self.prune_stats)?;
 
         if deleted.is_some() {
             self.flushed_cid = None;
+            self.prune_stats.deleted += 1;
         }
 
         Ok(deleted)
     }
 
+    /// Returns statistics about the entries deleted from the Kamt, and the nodes pruned from it as
+    /// a result, since it was created or loaded (or since [`Kamt::take_prune_stats`] was last
+    /// called).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fvm_ipld_kamt::{Config, Kamt, PruneStats};
+    /// use fvm_ipld_kamt::id::Identity;
+    ///
+    /// let store = fvm_ipld_blockstore::MemoryBlockstore::default();
+    /// let conf = Config { bit_width: 5, min_data_depth: 0, max_array_width: 1 };
+    ///
+    /// let mut map: Kamt<_, u32, _, Identity> = Kamt::new_with_config(store, conf);
+    /// map.set(1, "a".to_string()).unwrap();
+    /// map.set(2, "b".to_string()).unwrap();
+    /// map.delete(&2).unwrap();
+    /// assert_eq!(map.prune_stats(), PruneStats { deleted: 1, pruned_nodes: 1 });
+    /// ```
+    pub fn prune_stats(&self) -> PruneStats {
+        self.prune_stats
+    }
+
+    /// Returns the statistics reported by [`Kamt::prune_stats`], and resets them.
+    pub fn take_prune_stats(&mut self) -> PruneStats {
+        std::mem::take(&mut self.prune_stats)
+    }
+
     /// Iterates over each KV in the Kamt and runs a function on the values.
     ///
     /// This function will constrain all values to be of the same type
@@ -33,6 +33,19 @@ pub use self::kamt::Kamt;
 #[deprecated]
 const DEFAULT_BIT_WIDTH: u32 = 8;
 
+/// Statistics about the entries deleted from a KAMT, and the nodes pruned from it as a result.
+///
+/// Deletes keep the KAMT in its canonical form: nodes left with too few entries are merged into
+/// their parents, and nodes left with a single child are replaced by a link to that child. Each
+/// such node is no longer written on flush, and is counted here.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PruneStats {
+    /// The number of entries deleted.
+    pub deleted: u64,
+    /// The number of nodes pruned.
+    pub pruned_nodes: u64,
+}
+
 /// Configuration options for a KAMT instance.
 #[derive(Debug, Clone)]
 pub struct Config {
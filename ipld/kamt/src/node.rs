@@ -18,7 +18,7 @@ use super::hash_bits::HashBits;
 use super::pointer::Pointer;
 use super::{Error, KeyValuePair};
 use crate::ext::Extension;
-use crate::{AsHashedKey, Config, HashedKey};
+use crate::{AsHashedKey, Config, HashedKey, PruneStats};
 
 /// Node in Kamt tree which contains bitfield of set indexes and pointers to nodes
 #[derive(Debug)]
@@ -237,6 +237,7 @@ where
         key: &Q,
         store: &S,
         conf: &Config,
+        stats: &mut PruneStats,
     ) -> Result<Option<V>, Error>
     where
         K: Borrow<Q>,
@@ -249,6 +250,7 @@ where
             0,
             key,
             store,
+            stats,
         )
     }
 
@@ -500,6 +502,7 @@ where
         depth: u32,
         key: &Q,
         store: &S,
+        stats: &mut PruneStats,
     ) -> Result<Option<V>, Error>
     where
         K: Borrow<Q>,
@@ -523,15 +526,21 @@ where
                     })?;
                     let child_node = cache.get_mut().expect("filled line above");
 
-                    let deleted =
-                        child_node.rm_value(hashed_key, conf, depth + 1 + skipped, key, store)?;
+                    let deleted = child_node.rm_value(
+                        hashed_key,
+                        conf,
+                        depth + 1 + skipped,
+                        key,
+                        store,
+                        stats,
+                    )?;
 
                     if deleted.is_some() {
                         *child = Pointer::Dirty {
                             node: std::mem::take(child_node),
                             ext: std::mem::take(ext),
                         };
-                        if Self::clean(child, conf, depth)? {
+                        if Self::clean(child, conf, depth, stats)? {
                             self.rm_child(cindex, idx);
                         }
                     }
@@ -544,10 +553,16 @@ where
                 match match_extension(conf, hashed_key, ext)? {
                     ExtensionMatch::Full { skipped } => {
                         // Delete value and return deleted value
-                        let deleted =
-                            node.rm_value(hashed_key, conf, depth + 1 + skipped, key, store)?;
+                        let deleted = node.rm_value(
+                            hashed_key,
+                            conf,
+                            depth + 1 + skipped,
+                            key,
+                            store,
+                            stats,
+                        )?;
 
-                        if deleted.is_some() && Self::clean(child, conf, depth)? {
+                        if deleted.is_some() && Self::clean(child, conf, depth, stats)? {
                             self.rm_child(cindex, idx);
                         }
 
@@ -653,10 +668,23 @@ where
     ///
     /// Returns true if the child pointer is completely empty and can be removed,
     /// which can happen if we artificially inserted nodes during insertion.
-    fn clean(child: &mut Pointer<K, V, H, N>, conf: &Config, depth: u32) -> Result<bool, Error> {
+    ///
+    /// Either way, the pruned node is counted in `stats`.
+    fn clean(
+        child: &mut Pointer<K, V, H, N>,
+        conf: &Config,
+        depth: u32,
+        stats: &mut PruneStats,
+    ) -> Result<bool, Error> {
         match child.clean(conf, depth) {
-            Ok(()) => Ok(false),
-            Err(Error::ZeroPointers) if depth < conf.min_data_depth => Ok(true),
+            Ok(pruned) => {
+                stats.pruned_nodes += pruned as u64;
+                Ok(false)
+            }
+            Err(Error::ZeroPointers) if depth < conf.min_data_depth => {
+                stats.pruned_nodes += 1;
+                Ok(true)
+            }
             Err(err) => Err(err),
         }
     }
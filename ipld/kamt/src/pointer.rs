@@ -123,8 +123,9 @@ where
     }
 
     /// Internal method to cleanup children, to ensure consistent tree representation
-    /// after deletes.
-    pub(crate) fn clean(&mut self, conf: &Config, depth: u32) -> Result<(), Error> {
+    /// after deletes. Returns true if the node this pointer pointed at was pruned (merged into
+    /// this pointer).
+    pub(crate) fn clean(&mut self, conf: &Config, depth: u32) -> Result<bool, Error> {
         match self {
             Pointer::Dirty { node: n, ext: ext1 } => match n.pointers.len() {
                 0 => Err(Error::ZeroPointers),
@@ -134,7 +135,7 @@ where
                     // The rest of the rules would either move key-value pairs up, or undo a split.
                     // But if we use extensions and minimum data depth, splits will only happen after
                     // the minimum data depth as well, and these don't need undoing. So we can skip.
-                    Ok(())
+                    Ok(false)
                 }
                 1 => {
                     // Node has only one pointer, swap with parent node
@@ -174,7 +175,7 @@ where
                             }
                         }
                     }
-                    Ok(())
+                    Ok(true)
                 }
                 w if 2 <= w && w <= conf.max_array_width => {
                     // If more child values than max width, nothing to change.
@@ -183,11 +184,11 @@ where
                         if let Pointer::Values(vals) = c {
                             children_len += vals.len();
                         } else {
-                            return Ok(());
+                            return Ok(false);
                         }
                     }
                     if children_len > conf.max_array_width {
-                        return Ok(());
+                        return Ok(false);
                     }
 
                     // Collect values from child nodes to collapse.
@@ -212,9 +213,9 @@ where
 
                     // Replace link node with child values
                     *self = Pointer::Values(child_vals);
-                    Ok(())
+                    Ok(true)
                 }
-                _ => Ok(()),
+                _ => Ok(false),
             },
             _ => unreachable!("clean is only called on dirty pointer"),
         }
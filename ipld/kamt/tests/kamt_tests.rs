@@ -11,7 +11,7 @@ use fvm_ipld_encoding::de::DeserializeOwned;
 use fvm_ipld_encoding::BytesDe;
 use fvm_ipld_encoding::CborStore;
 use fvm_ipld_kamt::id::Identity;
-use fvm_ipld_kamt::{Config, Error, HashedKey, Kamt};
+use fvm_ipld_kamt::{Config, Error, HashedKey, Kamt, PruneStats};
 use multihash_codetable::Code;
 use quickcheck::Arbitrary;
 use rand::seq::SliceRandom;
@@ -76,6 +76,36 @@ fn test_n_keys(factory: KamtFactory) {
     }
 }
 
+fn prune_on_delete(factory: KamtFactory) {
+    let store = MemoryBlockstore::default();
+    let key = |j: u8| {
+        let mut k = [0; 32];
+        k[31] = j;
+        k
+    };
+    let mut kamt: HKamt<_, _, _> = factory.new(&store);
+    for j in 0u8..=255 {
+        kamt.set(key(j), j).unwrap();
+    }
+    let root = kamt.flush().unwrap();
+
+    // Clearing most of the storage prunes the nodes that held it...
+    let mut kamt: HKamt<_, u8, [u8; 32]> = factory.load(&root, &store).unwrap();
+    for j in 1u8..=255 {
+        assert_eq!(kamt.delete(&key(j)).unwrap(), Some(j));
+    }
+    assert_eq!(kamt.delete(&key(1)).unwrap(), None);
+    let stats = kamt.take_prune_stats();
+    assert_eq!(stats.deleted, 255);
+    assert!(stats.pruned_nodes > 0);
+    assert_eq!(kamt.prune_stats(), PruneStats::default());
+
+    // ...leaving the same tree as if it had never been there.
+    let mut expected: HKamt<_, _, _> = factory.new(&store);
+    expected.set(key(0), 0).unwrap();
+    assert_eq!(kamt.flush().unwrap(), expected.flush().unwrap());
+}
+
 fn test_load(factory: KamtFactory) {
     let store = MemoryBlockstore::default();
 
@@ -370,6 +400,11 @@ macro_rules! test_kamt_mod {
                 super::test_load($factory)
             }
 
+            #[test]
+            fn prune_on_delete() {
+                super::prune_on_delete($factory)
+            }
+
             #[test]
             fn test_set_if_absent() {
                 super::test_set_if_absent($factory)
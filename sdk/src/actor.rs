@@ -134,6 +134,23 @@ pub fn install_actor(code_cid: &Cid) -> SyscallResult<()> {
     unsafe { sys::actor::install_actor(cid.as_ptr()) }
 }
 
+/// Validates and stores the supplied actor bytecode, recording it as installed in the init actor's
+/// state, and returns its code CID.
+/// Note: this is a privileged syscall, restricted to the init actor.
+#[cfg(feature = "m2-native")]
+pub fn install_code(code: &[u8]) -> SyscallResult<Cid> {
+    let mut buf = [0u8; MAX_CID_LEN];
+    unsafe {
+        let len = sys::actor::install_code(
+            code.as_ptr(),
+            code.len() as u32,
+            buf.as_mut_ptr(),
+            MAX_CID_LEN as u32,
+        )?;
+        Ok(Cid::read_bytes(&buf[..len as usize]).expect("invalid cid returned"))
+    }
+}
+
 /// Determines whether the supplied CodeCID belongs to a built-in actor type,
 /// and to which.
 pub fn get_builtin_actor_type(code_cid: &Cid) -> Option<i32> {
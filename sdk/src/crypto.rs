@@ -5,7 +5,9 @@ use fvm_ipld_encoding::to_vec;
 use fvm_shared::address::Address;
 use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::crypto::{
+    eth::{self, Eip712Domain},
     hash::SupportedHashes,
+    mac::{self, BLAKE2B_BLOCK_LEN, SHA256_BLOCK_LEN},
     signature::{
         Signature, BLS_PUB_LEN, BLS_SIG_LEN, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
     },
@@ -21,6 +23,8 @@ use num_traits::FromPrimitive;
 
 use crate::{status_code_to_bool, sys, SyscallResult};
 
+pub use fvm_shared::crypto::mac::constant_time_eq;
+
 #[cfg(feature = "verify-signature")]
 /// Verifies that a signature is valid for an address and plaintext.
 ///
@@ -163,6 +167,79 @@ pub fn hash_blake2b(data: &[u8]) -> [u8; 32] {
     ret
 }
 
+/// Hashes input data using keccak with 256 bit output.
+pub fn hash_keccak256(data: &[u8]) -> [u8; 32] {
+    let mut ret = [0u8; 32];
+    hash_into(SupportedHashes::Keccak256, data, &mut ret);
+    ret
+}
+
+/// Computes the digest an Ethereum wallet signs for `message` with `personal_sign`. See
+/// [`fvm_shared::crypto::eth::personal_message_hash`].
+pub fn eth_personal_message_hash(message: &[u8]) -> [u8; 32] {
+    eth::personal_message_hash(hash_keccak256, message)
+}
+
+/// Computes the digest an Ethereum wallet signs for EIP-712 typed data in the given `domain`,
+/// given the message's struct hash (see [`eip712_hash_struct`]).
+pub fn eip712_typed_data_hash(domain: &Eip712Domain, struct_hash: &[u8; 32]) -> [u8; 32] {
+    let separator = domain.separator(hash_keccak256);
+    eth::typed_data_hash(hash_keccak256, &separator, struct_hash)
+}
+
+/// Computes the EIP-712 `hashStruct` of a struct given its encoded type and encoded member values.
+/// See [`fvm_shared::crypto::eth::hash_struct`].
+pub fn eip712_hash_struct(encoded_type: &str, values: &[[u8; 32]]) -> [u8; 32] {
+    eth::hash_struct(hash_keccak256, encoded_type, values)
+}
+
+/// Hashes input data using sha2 with 256 bit output.
+pub fn hash_sha256(data: &[u8]) -> [u8; 32] {
+    let mut ret = [0u8; 32];
+    hash_into(SupportedHashes::Sha2_256, data, &mut ret);
+    ret
+}
+
+/// Computes the HMAC-SHA256 of `message` under `key`. See [`fvm_shared::crypto::mac::hmac`].
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    mac::hmac::<SHA256_BLOCK_LEN, 32>(hash_sha256, key, message)
+}
+
+/// Computes the HMAC of `message` under `key`, using blake2b with 256 bit output.
+pub fn hmac_blake2b(key: &[u8], message: &[u8]) -> [u8; 32] {
+    mac::hmac::<BLAKE2B_BLOCK_LEN, 32>(hash_blake2b, key, message)
+}
+
+/// Checks a HMAC-SHA256 `tag` of `message` under `key`, in constant time (see
+/// [`constant_time_eq`]).
+pub fn verify_hmac_sha256(key: &[u8], message: &[u8], tag: &[u8]) -> bool {
+    constant_time_eq(&hmac_sha256(key, message), tag)
+}
+
+/// Checks a blake2b HMAC `tag` of `message` under `key` (see [`hmac_blake2b`]), in constant time
+/// (see [`constant_time_eq`]).
+pub fn verify_hmac_blake2b(key: &[u8], message: &[u8], tag: &[u8]) -> bool {
+    constant_time_eq(&hmac_blake2b(key, message), tag)
+}
+
+/// Hashes input data using blake3 with 256 bit output.
+///
+/// Panics if blake3 isn't supported by the current network version (prior to nv26).
+pub fn hash_blake3(data: &[u8]) -> [u8; 32] {
+    let mut ret = [0u8; 32];
+    hash_into(SupportedHashes::Blake3_256, data, &mut ret);
+    ret
+}
+
+/// Hashes input data using sha3 with 512 bit output.
+///
+/// Panics if sha3-512 isn't supported by the current network version (prior to nv26).
+pub fn hash_sha3_512(data: &[u8]) -> [u8; 64] {
+    let mut ret = [0u8; 64];
+    hash_into(SupportedHashes::Sha3_512, data, &mut ret);
+    ret
+}
+
 /// Hashes input data using one of the supported functions.
 /// hashes longer than 64 bytes will be truncated.
 pub fn hash_owned(hasher: SupportedHashes, data: &[u8]) -> Vec<u8> {
@@ -303,3 +380,36 @@ pub fn batch_verify_seals(batch: &[SealVerifyInfo]) -> SyscallResult<Vec<bool>>
         result
     })
 }
+
+/// Verifies a batch of seals, returning the result of each verification in input order. Unlike
+/// [`batch_verify_seals`], this may be called by any actor, and is charged for up-front.
+pub fn verify_seal_batch(batch: &[SealVerifyInfo]) -> SyscallResult<Vec<bool>> {
+    let encoded = to_vec(batch).expect("failed to marshal batch seal verification input");
+
+    Ok(unsafe {
+        let mut result: Vec<bool> = Vec::with_capacity(batch.len());
+        sys::crypto::verify_seal_batch(
+            encoded.as_ptr(),
+            encoded.len() as u32,
+            result.as_mut_ptr() as *mut u8,
+        )?;
+        result.set_len(batch.len());
+        result
+    })
+}
+
+/// Verifies a batch of window PoSts, returning the result of each verification in input order.
+pub fn verify_post_batch(batch: &[WindowPoStVerifyInfo]) -> SyscallResult<Vec<bool>> {
+    let encoded = to_vec(batch).expect("failed to marshal batch PoSt verification input");
+
+    Ok(unsafe {
+        let mut result: Vec<bool> = Vec::with_capacity(batch.len());
+        sys::crypto::verify_post_batch(
+            encoded.as_ptr(),
+            encoded.len() as u32,
+            result.as_mut_ptr() as *mut u8,
+        )?;
+        result.set_len(batch.len());
+        result
+    })
+}
@@ -3,6 +3,8 @@
 use lazy_static::lazy_static;
 use log::LevelFilter;
 
+pub use fvm_shared::sys::LogLevel;
+
 use crate::sys;
 
 lazy_static! {
@@ -17,6 +19,31 @@ pub fn log(msg: String) {
         sys::debug::log(msg.as_ptr(), msg.len() as u32).unwrap();
     }
 }
+/// Logs a structured record on the node, consisting of a message and a list of key-value pairs.
+/// The node records the emitting actor along with the record.
+///
+/// This is usually called through the [`log_debug!`](crate::log_debug!),
+/// [`log_info!`](crate::log_info!), [`log_warn!`](crate::log_warn!), and
+/// [`log_error!`](crate::log_error!) macros.
+///
+/// The syscall is only available from nv26: actors using it fail to link at earlier versions.
+pub fn log_structured(level: LogLevel, msg: &str, fields: &[(&str, String)]) {
+    if !enabled() {
+        return;
+    }
+    let fields = fvm_ipld_encoding::to_vec(fields).expect("failed to encode log fields");
+    unsafe {
+        sys::debug::log_structured(
+            level as u32,
+            msg.as_ptr(),
+            msg.len() as u32,
+            fields.as_ptr(),
+            fields.len() as u32,
+        )
+        .unwrap();
+    }
+}
+
 /// Initialize logging if debugging is enabled.
 #[inline(always)]
 pub fn init_logging() {
@@ -64,3 +91,55 @@ impl log::Log for Logger {
 
     fn flush(&self) {}
 }
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_structured {
+    ($level:expr, $msg:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::debug::enabled() {
+            $crate::debug::log_structured(
+                $level,
+                ::core::convert::AsRef::<str>::as_ref(&$msg),
+                &[$((::core::stringify!($key), ::std::format!("{}", $value))),*],
+            )
+        }
+    };
+}
+
+/// Logs a structured record at the debug level. See [`log_info!`](crate::log_info!).
+#[macro_export]
+macro_rules! log_debug {
+    ($($args:tt)*) => {
+        $crate::__log_structured!($crate::debug::LogLevel::Debug, $($args)*)
+    };
+}
+
+/// Logs a structured record on the node (if debugging is enabled), consisting of a message and a
+/// list of `key = value` pairs. The values are formatted with [`Display`](std::fmt::Display) and
+/// are only evaluated when debugging is enabled.
+///
+/// ```ignore
+/// fvm_sdk::log_info!("transferred funds", to = recipient, amount = amount);
+/// ```
+#[macro_export]
+macro_rules! log_info {
+    ($($args:tt)*) => {
+        $crate::__log_structured!($crate::debug::LogLevel::Info, $($args)*)
+    };
+}
+
+/// Logs a structured record at the warning level. See [`log_info!`](crate::log_info!).
+#[macro_export]
+macro_rules! log_warn {
+    ($($args:tt)*) => {
+        $crate::__log_structured!($crate::debug::LogLevel::Warn, $($args)*)
+    };
+}
+
+/// Logs a structured record at the error level. See [`log_info!`](crate::log_info!).
+#[macro_export]
+macro_rules! log_error {
+    ($($args:tt)*) => {
+        $crate::__log_structured!($crate::debug::LogLevel::Error, $($args)*)
+    };
+}
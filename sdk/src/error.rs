@@ -1,5 +1,6 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_shared::error::{ErrorNumber, ExitCode};
 use thiserror::Error;
 
 #[derive(Copy, Clone, Debug, Error, Eq, PartialEq)]
@@ -22,6 +23,22 @@ pub enum ActorDeleteError {
     UnspentFunds,
 }
 
+#[derive(Copy, Clone, Debug, Error, Eq, PartialEq)]
+pub enum SelfDestructError {
+    #[error("cannot self-destruct when read-only")]
+    ReadOnly,
+    #[error("beneficiary does not exist")]
+    BeneficiaryNotFound,
+    #[error("beneficiary is the actor being destroyed")]
+    BeneficiaryIsSelf,
+    #[error("failed to send the remaining balance to the beneficiary: {0}")]
+    SendFailed(ErrorNumber),
+    #[error("beneficiary rejected the remaining balance with exit code {0}")]
+    Rejected(ExitCode),
+    #[error("actor received funds while sending its balance to the beneficiary")]
+    UnspentFunds,
+}
+
 #[derive(Copy, Clone, Debug, Error, Eq, PartialEq)]
 pub enum EpochBoundsError {
     #[error("the requested epoch isn't valid")]
@@ -68,6 +68,170 @@ pub fn get(cid: &Cid) -> SyscallResult<Vec<u8>> {
     }
 }
 
+/// Get a block, reading it into the supplied buffer instead of allocating. Returns the size of the
+/// block (the number of bytes written to the start of `buf`).
+///
+/// This may be called on the same CIDs as [`get`]. Fails with [`ErrorNumber::BufferTooSmall`] if
+/// the block doesn't fit in `buf`; use a [`BlockReader`] to read large blocks piece by piece.
+pub fn get_into(cid: &Cid, buf: &mut [u8]) -> SyscallResult<usize> {
+    let mut reader = BlockReader::open(cid)?;
+    let size = reader.size() as usize;
+    if size > buf.len() {
+        return Err(ErrorNumber::BufferTooSmall);
+    }
+    let read = reader.read(&mut buf[..size])?;
+    assert_eq!(read, size, "expected to read the block exactly");
+    Ok(size)
+}
+
+/// Incrementally reads a block, so that actors processing large blocks can bound their memory
+/// usage.
+///
+/// Use [`BlockReader::read`] to read into a caller-provided buffer, or [`BlockReader::chunks`] to
+/// iterate over the block in fixed-size chunks.
+#[derive(Debug)]
+pub struct BlockReader {
+    source: BlockSource,
+    codec: u64,
+    size: u32,
+    offset: u32,
+}
+
+#[derive(Debug)]
+enum BlockSource {
+    /// A block opened in (or created by) the FVM.
+    Handle(fvm_shared::sys::BlockId),
+    /// The digest of an identity-hashed CID.
+    Inline(Vec<u8>),
+}
+
+impl BlockReader {
+    /// Opens the block referenced by `cid` for reading. This may be called on the same CIDs as
+    /// [`get`].
+    pub fn open(cid: &Cid) -> SyscallResult<Self> {
+        // Identity-hashed blocks are inlined in the CID; there's nothing to open.
+        if cid.hash().code() == fvm_shared::IDENTITY_HASH {
+            let data = cid.hash().digest();
+            return Ok(BlockReader {
+                codec: cid.codec(),
+                size: data.len() as u32,
+                source: BlockSource::Inline(data.into()),
+                offset: 0,
+            });
+        }
+
+        let mut cid_buf = [0u8; MAX_CID_LEN];
+        cid.write_bytes(&mut cid_buf[..])
+            .expect("CID encoding should not fail");
+        let fvm_shared::sys::out::ipld::IpldOpen { codec, id, size } =
+            unsafe { sys::ipld::block_open(cid_buf.as_mut_ptr())? };
+        Ok(BlockReader {
+            source: BlockSource::Handle(id),
+            codec,
+            size,
+            offset: 0,
+        })
+    }
+
+    /// Reads the block referenced by the given BlockId, e.g. the parameters of a message.
+    pub fn from_block(id: fvm_shared::sys::BlockId) -> SyscallResult<Self> {
+        // The "empty" block can't be stat-ed.
+        if id == UNIT {
+            return Ok(BlockReader {
+                source: BlockSource::Inline(Vec::new()),
+                codec: 0,
+                size: 0,
+                offset: 0,
+            });
+        }
+
+        let fvm_shared::sys::out::ipld::IpldStat { codec, size } =
+            unsafe { sys::ipld::block_stat(id)? };
+        Ok(BlockReader {
+            source: BlockSource::Handle(id),
+            codec,
+            size,
+            offset: 0,
+        })
+    }
+
+    /// The codec of the block.
+    pub fn codec(&self) -> u64 {
+        self.codec
+    }
+
+    /// The total size of the block.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// The number of bytes left to read.
+    pub fn remaining(&self) -> u32 {
+        self.size - self.offset
+    }
+
+    /// Reads the next bytes of the block into `buf`, returning the number of bytes read. Returns
+    /// zero once the whole block has been read.
+    pub fn read(&mut self, buf: &mut [u8]) -> SyscallResult<usize> {
+        let len = self
+            .remaining()
+            .min(buf.len().try_into().unwrap_or(u32::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        match &self.source {
+            BlockSource::Handle(id) => {
+                let remaining =
+                    unsafe { sys::ipld::block_read(*id, self.offset, buf.as_mut_ptr(), len)? };
+                assert!(remaining >= 0, "block shorter than expected");
+            }
+            BlockSource::Inline(data) => {
+                let start = self.offset as usize;
+                buf[..len as usize].copy_from_slice(&data[start..start + len as usize]);
+            }
+        }
+        self.offset += len;
+        Ok(len as usize)
+    }
+
+    /// Returns an iterator over the rest of the block, in chunks of `chunk_size` bytes (the last
+    /// chunk may be shorter).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunks(self, chunk_size: usize) -> BlockChunks {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        BlockChunks {
+            reader: self,
+            chunk_size,
+        }
+    }
+}
+
+/// An iterator over a block in fixed-size chunks, created by [`BlockReader::chunks`].
+#[derive(Debug)]
+pub struct BlockChunks {
+    reader: BlockReader,
+    chunk_size: usize,
+}
+
+impl Iterator for BlockChunks {
+    type Item = SyscallResult<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.chunk_size.min(self.reader.remaining() as usize);
+        if len == 0 {
+            return None;
+        }
+        let mut chunk = vec![0u8; len];
+        Some(self.reader.read(&mut chunk).map(|n| {
+            chunk.truncate(n);
+            chunk
+        }))
+    }
+}
+
 /// Gets the data of the block referenced by BlockId. If the caller knows the size, this function
 /// will read the block in a single syscall. Otherwise, any block over 1KiB will take two syscalls.
 pub fn get_block(id: fvm_shared::sys::BlockId, size_hint: Option<u32>) -> SyscallResult<Vec<u8>> {
@@ -101,6 +265,15 @@ pub fn get_block(id: fvm_shared::sys::BlockId, size_hint: Option<u32>) -> Syscal
     Ok(buf)
 }
 
+/// Returns the number of blocks, and bytes of block data, that may still be opened or created
+/// during the current message (the quota is shared with the actor's callers and callees). Actors
+/// handling large amounts of state can use this to size their batches.
+///
+/// The syscall is only available from nv26: actors using it fail to link at earlier versions.
+pub fn quota() -> fvm_shared::sys::out::ipld::IpldQuota {
+    unsafe { sys::ipld::block_quota() }.expect("failed to query the block quota")
+}
+
 /// Writes the supplied block and returns the BlockId.
 pub fn put_block(
     codec: fvm_shared::sys::Codec,
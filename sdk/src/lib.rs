@@ -7,6 +7,7 @@ pub mod error;
 pub mod event;
 pub mod gas;
 pub mod ipld;
+pub mod lock;
 pub mod message;
 pub mod network;
 pub mod rand;
@@ -0,0 +1,238 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! A reentrancy lock kept in the actor's own state.
+//!
+//! An actor that calls other actors may be re-entered (e.g., through a callback, or by cron) before
+//! the outer call returns. To guard a non-reentrant section, the actor reserves a field of its state
+//! for a [`LockState`], exposes it through [`Lockable`], and wraps the section in [`with_lock`] (or
+//! [`acquire`] and [`StateLock::release`]).
+//!
+//! Acquiring the lock immediately commits it to the actor's state root, so any re-entrant call
+//! observes it and fails with [`LockError::Locked`]. If the actor aborts, the runtime reverts its
+//! state, releasing the lock with it. If the actor instead returns while still holding the lock,
+//! dropping the [`StateLock`] poisons it: all further attempts to acquire it fail with
+//! [`LockError::Poisoned`] until the actor calls [`clear_poison`].
+use std::marker::PhantomData;
+
+use fvm_ipld_encoding::de::{Deserialize, DeserializeOwned, Deserializer, Error as _};
+use fvm_ipld_encoding::ser::{Serialize, Serializer};
+use fvm_ipld_encoding::{from_slice, to_vec, DAG_CBOR};
+use fvm_shared::crypto::hash::SupportedHashes;
+use thiserror::Error;
+
+use crate::error::{StateReadError, StateUpdateError};
+use crate::{ipld, sself};
+
+/// The state of a reentrancy lock, stored in the actor's state (as a single integer).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum LockState {
+    #[default]
+    Unlocked,
+    Locked,
+    /// The lock was not released before the actor returned.
+    Poisoned,
+}
+
+impl LockState {
+    /// Takes the lock, failing if it's already held or poisoned.
+    pub fn acquire(&mut self) -> Result<(), LockError> {
+        match self {
+            LockState::Unlocked => {
+                *self = LockState::Locked;
+                Ok(())
+            }
+            LockState::Locked => Err(LockError::Locked),
+            LockState::Poisoned => Err(LockError::Poisoned),
+        }
+    }
+
+    /// Releases the lock, failing if it isn't held.
+    pub fn release(&mut self) -> Result<(), LockError> {
+        match self {
+            LockState::Locked => {
+                *self = LockState::Unlocked;
+                Ok(())
+            }
+            LockState::Unlocked => Err(LockError::NotLocked),
+            LockState::Poisoned => Err(LockError::Poisoned),
+        }
+    }
+
+    /// Poisons the lock if it's held.
+    pub fn poison(&mut self) {
+        if *self == LockState::Locked {
+            *self = LockState::Poisoned;
+        }
+    }
+
+    /// Clears the poison from the lock, leaving it unlocked. Does nothing if the lock isn't
+    /// poisoned.
+    pub fn clear_poison(&mut self) {
+        if *self == LockState::Poisoned {
+            *self = LockState::Unlocked;
+        }
+    }
+}
+
+impl Serialize for LockState {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let v: u8 = match self {
+            LockState::Unlocked => 0,
+            LockState::Locked => 1,
+            LockState::Poisoned => 2,
+        };
+        v.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LockState {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match u8::deserialize(deserializer)? {
+            0 => Ok(LockState::Unlocked),
+            1 => Ok(LockState::Locked),
+            2 => Ok(LockState::Poisoned),
+            v => Err(D::Error::custom(format!("invalid lock state {v}"))),
+        }
+    }
+}
+
+/// An actor's state, holding a reentrancy lock in a field reserved for it.
+pub trait Lockable: Serialize + DeserializeOwned {
+    /// Returns the state's reentrancy lock.
+    fn lock_state(&mut self) -> &mut LockState;
+}
+
+#[derive(Copy, Clone, Debug, Error, Eq, PartialEq)]
+pub enum LockError {
+    #[error("the lock is already held")]
+    Locked,
+    #[error("the lock is poisoned")]
+    Poisoned,
+    #[error("the lock isn't held")]
+    NotLocked,
+    #[error("failed to read the actor's state: {0}")]
+    StateRead(#[from] StateReadError),
+    #[error("failed to update the actor's state: {0}")]
+    StateUpdate(#[from] StateUpdateError),
+}
+
+/// A held reentrancy lock. Release it with [`StateLock::release`]; dropping it without doing so
+/// poisons the lock.
+#[must_use = "dropping the lock without releasing it poisons it"]
+pub struct StateLock<S: Lockable> {
+    released: bool,
+    _state: PhantomData<fn() -> S>,
+}
+
+impl<S: Lockable> StateLock<S> {
+    /// Releases the lock in the actor's current state.
+    pub fn release(mut self) -> Result<(), LockError> {
+        self.released = true;
+        update::<S, _>(LockState::release)
+    }
+}
+
+impl<S: Lockable> Drop for StateLock<S> {
+    fn drop(&mut self) {
+        if !self.released {
+            // There's nothing more we can do if this fails (e.g., because the actor was deleted).
+            let _ = update::<S, _>(|lock| {
+                lock.poison();
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Acquires the reentrancy lock in the actor's current state, committing it to the state root.
+pub fn acquire<S: Lockable>() -> Result<StateLock<S>, LockError> {
+    update::<S, _>(LockState::acquire)?;
+    Ok(StateLock {
+        released: false,
+        _state: PhantomData,
+    })
+}
+
+/// Runs `f` while holding the reentrancy lock in the actor's state, releasing it afterwards.
+pub fn with_lock<S, T, F>(f: F) -> Result<T, LockError>
+where
+    S: Lockable,
+    F: FnOnce() -> T,
+{
+    let lock = acquire::<S>()?;
+    let ret = f();
+    lock.release()?;
+    Ok(ret)
+}
+
+/// Clears the poison from the reentrancy lock in the actor's current state.
+pub fn clear_poison<S: Lockable>() -> Result<(), LockError> {
+    update::<S, _>(|lock| {
+        lock.clear_poison();
+        Ok(())
+    })
+}
+
+/// Loads the actor's state, applies `f` to its lock and, if that succeeds, writes the state back.
+fn update<S, F>(f: F) -> Result<(), LockError>
+where
+    S: Lockable,
+    F: FnOnce(&mut LockState) -> Result<(), LockError>,
+{
+    let root = sself::root()?;
+    let data = ipld::get(&root).expect("failed to load the actor's state");
+    let mut state: S = from_slice(&data).expect("failed to decode the actor's state");
+
+    f(state.lock_state())?;
+
+    let data = to_vec(&state).expect("failed to encode the actor's state");
+    let root = ipld::put(SupportedHashes::Blake2b256 as u64, 32, DAG_CBOR, &data)
+        .expect("failed to store the actor's state");
+    sself::set_root(&root)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions() {
+        let mut lock = LockState::default();
+        assert_eq!(lock.release(), Err(LockError::NotLocked));
+        lock.acquire().unwrap();
+        assert_eq!(lock, LockState::Locked);
+        assert_eq!(lock.acquire(), Err(LockError::Locked));
+        lock.release().unwrap();
+        assert_eq!(lock, LockState::Unlocked);
+
+        // Poisoning only affects held locks.
+        lock.poison();
+        assert_eq!(lock, LockState::Unlocked);
+        lock.acquire().unwrap();
+        lock.poison();
+        assert_eq!(lock.acquire(), Err(LockError::Poisoned));
+        assert_eq!(lock.release(), Err(LockError::Poisoned));
+        lock.clear_poison();
+        lock.acquire().unwrap();
+    }
+
+    #[test]
+    fn encoding() {
+        for (lock, byte) in [
+            (LockState::Unlocked, 0x00),
+            (LockState::Locked, 0x01),
+            (LockState::Poisoned, 0x02),
+        ] {
+            assert_eq!(to_vec(&lock).unwrap(), [byte]);
+            assert_eq!(from_slice::<LockState>(&[byte]).unwrap(), lock);
+        }
+        assert!(from_slice::<LockState>(&[0x03]).is_err());
+    }
+}
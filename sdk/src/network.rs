@@ -5,6 +5,7 @@ use fvm_shared::chainid::ChainID;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
+use fvm_shared::sector::StoragePower;
 use fvm_shared::sys::out::network::NetworkContext;
 use fvm_shared::version::NetworkVersion;
 use fvm_shared::MAX_CID_LEN;
@@ -20,14 +21,22 @@ lazy_static::lazy_static! {
     };
 }
 
+/// Returns the ID of the chain the actor is executing on.
 pub fn chain_id() -> ChainID {
     NETWORK_CONTEXT.chain_id.into()
 }
 
-pub fn curr_epoch() -> ChainEpoch {
+/// Returns the epoch at which the current message is being executed.
+pub fn current_epoch() -> ChainEpoch {
     NETWORK_CONTEXT.epoch
 }
 
+#[deprecated(note = "use `current_epoch` instead")]
+pub fn curr_epoch() -> ChainEpoch {
+    current_epoch()
+}
+
+/// Returns the network version at the current epoch.
 pub fn version() -> NetworkVersion {
     NETWORK_CONTEXT.network_version
 }
@@ -44,13 +53,49 @@ pub fn total_fil_circ_supply() -> TokenAmount {
     }
 }
 
+/// Network-wide aggregates, as of the parent state of the current tipset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkSummary {
+    /// The network's total raw byte power.
+    pub raw_byte_power: StoragePower,
+    /// The network's total quality-adjusted power.
+    pub quality_adj_power: StoragePower,
+    /// The total pledge collateral locked by storage providers.
+    pub total_pledge_collateral: TokenAmount,
+    /// The block reward paid out at the current epoch.
+    pub this_epoch_reward: TokenAmount,
+}
+
+/// Returns the network's total power, total pledge collateral, and the current epoch's block
+/// reward, as recorded in the storage power and reward actors' state, or `None` if either actor
+/// doesn't exist or its state can't be decoded (e.g., in test networks without them).
+///
+/// The syscall is only available from nv26: actors using it fail to link at earlier versions.
+pub fn summary() -> Option<NetworkSummary> {
+    unsafe {
+        match sys::network::summary() {
+            Ok(s) => Some(NetworkSummary {
+                raw_byte_power: s.raw_byte_power.into(),
+                quality_adj_power: s.quality_adj_power.into(),
+                total_pledge_collateral: s.total_pledge_collateral.into(),
+                this_epoch_reward: s.this_epoch_reward.into(),
+            }),
+            Err(ErrorNumber::IllegalOperation) => None,
+            Err(other) => panic!("unexpected network summary failure: {}", other),
+        }
+    }
+}
+
 /// Returns the current block time in seconds since the EPOCH.
 pub fn tipset_timestamp() -> u64 {
     NETWORK_CONTEXT.timestamp
 }
 
-/// Returns the tipset CID of the specified epoch, if available. Allows querying from now up to
-/// finality (900 epochs).
+/// Returns the tipset CID of the specified epoch, if available. Allows querying any epoch before
+/// the current one, up to finality (900 epochs) from nv26.
+///
+/// Returns [`EpochBoundsError::Invalid`] if the epoch is negative, current, or in the future, and
+/// (from nv26) [`EpochBoundsError::ExceedsLookback`] if it's more than 900 epochs ago.
 pub fn tipset_cid(epoch: ChainEpoch) -> Result<Cid, EpochBoundsError> {
     let mut buf = [0u8; MAX_CID_LEN];
 
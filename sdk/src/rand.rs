@@ -1,7 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use fvm_shared::clock::ChainEpoch;
-use fvm_shared::randomness::RANDOMNESS_LENGTH;
+use fvm_shared::randomness::{draw_randomness_with, DomainSeparationTag, RANDOMNESS_LENGTH};
 
 use crate::{sys, SyscallResult};
 
@@ -20,3 +20,37 @@ pub fn get_chain_randomness(round: ChainEpoch) -> SyscallResult<[u8; RANDOMNESS_
 pub fn get_beacon_randomness(round: ChainEpoch) -> SyscallResult<[u8; RANDOMNESS_LENGTH]> {
     unsafe { sys::rand::get_beacon_randomness(round) }
 }
+
+/// Draws randomness from the ticket chain for the purpose `pers`, mixing in the `entropy`. See
+/// [`fvm_shared::randomness::draw_randomness`].
+pub fn draw_chain_randomness(
+    pers: DomainSeparationTag,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> SyscallResult<[u8; RANDOMNESS_LENGTH]> {
+    let rbase = get_chain_randomness(round)?;
+    Ok(draw_randomness_with(
+        crate::crypto::hash_blake2b,
+        &rbase,
+        pers,
+        round,
+        entropy,
+    ))
+}
+
+/// Draws randomness from the beacon system for the purpose `pers`, mixing in the `entropy`. See
+/// [`fvm_shared::randomness::draw_randomness`].
+pub fn draw_beacon_randomness(
+    pers: DomainSeparationTag,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> SyscallResult<[u8; RANDOMNESS_LENGTH]> {
+    let rbase = get_beacon_randomness(round)?;
+    Ok(draw_randomness_with(
+        crate::crypto::hash_blake2b,
+        &rbase,
+        pers,
+        round,
+        entropy,
+    ))
+}
@@ -6,12 +6,15 @@ use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
-use fvm_shared::sys::SendFlags;
+use fvm_shared::sys::{BlockId, SendFlags};
 use fvm_shared::{MethodNum, Response};
 
 use crate::{build_response, sys, SyscallResult, NO_DATA_BLOCK_ID};
 
 /// Sends a message to another actor.
+///
+/// If `gas_limit` is specified, the callee may use at most that much gas (see [`send_with_gas`]).
+/// Otherwise, it may use all the gas remaining.
 pub fn send(
     to: &Address,
     method: MethodNum,
@@ -20,20 +23,62 @@ pub fn send(
     gas_limit: Option<u64>,
     flags: SendFlags,
 ) -> SyscallResult<Response> {
+    let value = convert_value(value)?;
+    // Insert parameters as a block. Nil parameters is represented as the
+    // NO_DATA_BLOCK_ID block ID in the FFI interface.
+    let params_id = match params {
+        Some(p) => unsafe {
+            sys::ipld::block_create(p.codec, p.data.as_ptr(), p.data.len() as u32)?
+        },
+        None => NO_DATA_BLOCK_ID,
+    };
+    build_response(send_raw(to, method, params_id, value, gas_limit, flags)?)
+}
+
+/// Sends a message to another actor, passing an already open block as the parameters (or
+/// [`NO_DATA_BLOCK_ID`] for none).
+///
+/// Unlike [`send`], this neither copies the parameters into a new block nor reads the return value
+/// back into the actor's memory. Proxies can use it to forward their own parameters (the block ID
+/// passed to `invoke`) unchanged, and return the callee's return value by returning its block ID
+/// (`return_id`) from `invoke`. This saves the gas charged to copy these blocks in and out. The
+/// return value can still be read with [`crate::ipld::get_block`].
+pub fn send_block(
+    to: &Address,
+    method: MethodNum,
+    params_id: BlockId,
+    value: TokenAmount,
+    gas_limit: Option<u64>,
+    flags: SendFlags,
+) -> SyscallResult<fvm_shared::sys::out::send::Send> {
+    send_raw(
+        to,
+        method,
+        params_id,
+        convert_value(value)?,
+        gas_limit,
+        flags,
+    )
+}
+
+/// Converts a value to send into its syscall representation. Values that don't fit can't be
+/// covered by the actor's balance either.
+fn convert_value(value: TokenAmount) -> SyscallResult<sys::TokenAmount> {
+    value.try_into().map_err(|_| ErrorNumber::InsufficientFunds)
+}
+
+/// Performs the send syscall, with the value already converted.
+fn send_raw(
+    to: &Address,
+    method: MethodNum,
+    params_id: BlockId,
+    value: sys::TokenAmount,
+    gas_limit: Option<u64>,
+    flags: SendFlags,
+) -> SyscallResult<fvm_shared::sys::out::send::Send> {
     let recipient = to.to_bytes();
-    let value: sys::TokenAmount = value
-        .try_into()
-        .map_err(|_| ErrorNumber::InsufficientFunds)?;
     unsafe {
-        // Insert parameters as a block. Nil parameters is represented as the
-        // NO_DATA_BLOCK_ID block ID in the FFI interface.
-        let params_id = match params {
-            Some(p) => sys::ipld::block_create(p.codec, p.data.as_ptr(), p.data.len() as u32)?,
-            None => NO_DATA_BLOCK_ID,
-        };
-
-        // Perform the syscall to send the message.
-        let send = sys::send::send(
+        sys::send::send(
             recipient.as_ptr(),
             recipient.len() as u32,
             method,
@@ -42,8 +87,35 @@ pub fn send(
             value.lo,
             gas_limit.unwrap_or(u64::MAX),
             flags,
-        )?;
-
-        build_response(send)
+        )
     }
 }
+
+/// Sends a message to another actor, letting the callee use at most `gas_limit` gas (or the gas
+/// remaining, if less).
+///
+/// If the callee runs out of its gas limit, the call is aborted and its state changes are reverted,
+/// but the caller keeps running: the returned response carries the
+/// [`SYS_OUT_OF_GAS`](fvm_shared::error::ExitCode::SYS_OUT_OF_GAS) exit code, and the gas used by
+/// the callee (up to the limit) is still charged. This lets actors call untrusted code without
+/// risking running out of gas themselves (a "try call"), as long as they keep enough gas in reserve
+/// to handle the failure. However, if the message as a whole runs out of gas, the caller is aborted
+/// as usual.
+///
+/// A gas limit of 0 doesn't let the callee run at all.
+pub fn send_with_gas(
+    to: &Address,
+    method: MethodNum,
+    params: Option<IpldBlock>,
+    value: TokenAmount,
+    gas_limit: u64,
+) -> SyscallResult<Response> {
+    send(
+        to,
+        method,
+        params,
+        value,
+        Some(gas_limit),
+        SendFlags::default(),
+    )
+}
@@ -1,12 +1,15 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use cid::Cid;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
-use fvm_shared::MAX_CID_LEN;
+use fvm_shared::sys::SendFlags;
+use fvm_shared::{MethodNum, MAX_CID_LEN, METHOD_SEND};
 
-use crate::error::{ActorDeleteError, StateReadError, StateUpdateError};
-use crate::sys;
+use crate::error::{ActorDeleteError, SelfDestructError, StateReadError, StateUpdateError};
+use crate::{actor, message, send, sys, vm};
 
 /// Get the IPLD root CID. Fails if the actor doesn't have state (before the first call to
 /// `set_root` and after actor deletion).
@@ -52,7 +55,11 @@ pub fn current_balance() -> TokenAmount {
     }
 }
 
-/// Destroys the calling actor, burning any remaining balance.
+/// Destroys the calling actor. If `burn_funds` is true, any remaining balance is burnt. Otherwise,
+/// this fails if the actor has any remaining balance.
+///
+/// Unlike earlier FVM versions, this doesn't take a beneficiary: to hand the remaining balance over
+/// to another actor, use [`self_destruct_to`].
 pub fn self_destruct(burn_funds: bool) -> Result<(), ActorDeleteError> {
     unsafe {
         sys::sself::self_destruct(burn_funds).map_err(|e| match e {
@@ -62,3 +69,61 @@ pub fn self_destruct(burn_funds: bool) -> Result<(), ActorDeleteError> {
         })
     }
 }
+
+/// Sends the calling actor's remaining balance to `beneficiary`, then destroys the calling actor.
+///
+/// Fails without sending anything if the actor is executing in read-only mode, if the beneficiary
+/// doesn't exist yet (sending funds to an unused address would create a new actor there), or if
+/// the beneficiary is the calling actor itself (the funds would be burnt). See
+/// [`self_destruct_with`] to also hand data over to the beneficiary.
+pub fn self_destruct_to(beneficiary: &Address) -> Result<(), SelfDestructError> {
+    self_destruct_with(beneficiary, METHOD_SEND, None)
+}
+
+/// Like [`self_destruct_to`], but invokes `method` on the beneficiary with `params` when sending it
+/// the remaining balance (even if that balance is zero, unless `method` is [`METHOD_SEND`] and
+/// there are no parameters).
+///
+/// The parameters can link to blocks in the calling actor's state (e.g., its state root), handing
+/// them over to the beneficiary: the beneficiary must link them from its own state before
+/// returning, as they become unreachable once the calling actor is destroyed.
+///
+/// Fails without destroying the actor if the beneficiary aborts, or if the actor receives funds
+/// while the beneficiary runs.
+pub fn self_destruct_with(
+    beneficiary: &Address,
+    method: MethodNum,
+    params: Option<IpldBlock>,
+) -> Result<(), SelfDestructError> {
+    if vm::read_only() {
+        return Err(SelfDestructError::ReadOnly);
+    }
+    let id = actor::resolve_address(beneficiary).ok_or(SelfDestructError::BeneficiaryNotFound)?;
+    if id == message::receiver() {
+        return Err(SelfDestructError::BeneficiaryIsSelf);
+    }
+    if actor::get_actor_code_cid(beneficiary).is_none() {
+        return Err(SelfDestructError::BeneficiaryNotFound);
+    }
+
+    let balance = current_balance();
+    if !balance.is_zero() || method != METHOD_SEND || params.is_some() {
+        let ret = send::send(
+            beneficiary,
+            method,
+            params,
+            balance,
+            None,
+            SendFlags::default(),
+        )
+        .map_err(SelfDestructError::SendFailed)?;
+        if !ret.exit_code.is_success() {
+            return Err(SelfDestructError::Rejected(ret.exit_code));
+        }
+    }
+
+    self_destruct(false).map_err(|e| match e {
+        ActorDeleteError::ReadOnly => SelfDestructError::ReadOnly,
+        ActorDeleteError::UnspentFunds => SelfDestructError::UnspentFunds,
+    })
+}
@@ -172,6 +172,39 @@ super::fvm_syscalls! {
     #[cfg(feature = "m2-native")]
     pub fn install_actor(cid_off: *const u8) -> Result<()>;
 
+    /// Validates and stores the supplied actor bytecode, writing its code CID into the output
+    /// buffer. The code is recorded as installed in the init actor's state, so the init actor
+    /// must re-read its state afterwards.
+    ///
+    /// **Privileged:** May only be called by the init actor.
+    ///
+    /// # Arguments
+    ///
+    /// - `code_off` and `code_len` specify the location and length of the wasm bytecode.
+    /// - `obuf_off` and `obuf_len` specify the location and length of the output buffer in which
+    ///   to write the code CID.
+    ///
+    /// # Returns
+    ///
+    /// The length of the code CID.
+    ///
+    /// # Errors
+    ///
+    /// | Error                 | Reason                                                       |
+    /// |-----------------------|--------------------------------------------------------------|
+    /// | [`IllegalArgument`]   | the code is invalid, or the buffers are out of bounds.       |
+    /// | [`IllegalOperation`]  | installing code isn't supported at this network version.     |
+    /// | [`Forbidden`]         | the caller is not the init actor.                            |
+    /// | [`ReadOnly`]          | the actor is executing in read-only mode.                    |
+    /// | [`BufferTooSmall`]    | the output buffer isn't large enough to fit the CID.         |
+    #[cfg(feature = "m2-native")]
+    pub fn install_code(
+        code_off: *const u8,
+        code_len: u32,
+        obuf_off: *mut u8,
+        obuf_len: u32,
+    ) -> Result<u32>;
+
     /// Gets the balance of the specified actor.
     ///
     /// # Arguments
@@ -247,4 +247,42 @@ super::fvm_syscalls! {
     /// |---------------------|--------------------------|
     /// | [`IllegalArgument`] | an argument is malformed |
     pub fn batch_verify_seals(batch_off: *const u8, batch_len: u32, result_off: *const u8) -> Result<()>;
+
+    /// Verifies a batch of sector seal proofs, charging gas for the whole batch up-front. Unlike
+    /// [`batch_verify_seals`], this may be called by any actor.
+    ///
+    /// # Arguments
+    ///
+    /// - `batch_off` and `batch_len` specify the location and length of a cbor-encoded list of
+    ///   [`SealVerifyInfo`][fvm_shared::sector::SealVerifyInfo] in tuple representation.
+    /// - `results_off` specifies the location of a length `L` byte buffer where the results of the
+    ///   verification will be written, where `L` is the number of proofs in the batch. For each
+    ///   proof in the input list (in input order), a 1 or 0 byte will be written on success or
+    ///   failure, respectively. A malformed proof fails only its own verification.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                       |
+    /// |---------------------|------------------------------|
+    /// | [`IllegalArgument`] | the batch couldn't be parsed |
+    pub fn verify_seal_batch(batch_off: *const u8, batch_len: u32, result_off: *const u8) -> Result<()>;
+
+    /// Verifies a batch of window proofs of spacetime, charging gas for the whole batch up-front.
+    ///
+    /// # Arguments
+    ///
+    /// - `batch_off` and `batch_len` specify the location and length of a cbor-encoded list of
+    ///   [`WindowPoStVerifyInfo`][fvm_shared::sector::WindowPoStVerifyInfo] in tuple
+    ///   representation.
+    /// - `results_off` specifies the location of a length `L` byte buffer where the results of the
+    ///   verification will be written, where `L` is the number of proofs in the batch. For each
+    ///   proof in the input list (in input order), a 1 or 0 byte will be written on success or
+    ///   failure, respectively. A malformed proof fails only its own verification.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                       |
+    /// |---------------------|------------------------------|
+    /// | [`IllegalArgument`] | the batch couldn't be parsed |
+    pub fn verify_post_batch(batch_off: *const u8, batch_len: u32, result_off: *const u8) -> Result<()>;
 }
@@ -2,6 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 //! Syscalls for debugging.
 
+// for documentation links
+#[cfg(doc)]
+use crate::sys::ErrorNumber::*;
+
 super::fvm_syscalls! {
     module = "debug";
 
@@ -12,6 +16,28 @@ super::fvm_syscalls! {
     /// Logs a message on the node.
     pub fn log(message: *const u8, message_len: u32) -> Result<()>;
 
+    /// Logs a structured record on the node.
+    ///
+    /// # Arguments
+    ///
+    /// - `level` is the record's [`LogLevel`](fvm_shared::sys::LogLevel).
+    /// - `message` and `message_len` specify the location and length of the UTF-8 message.
+    /// - `fields` and `fields_len` specify the location and length of the record's key-value
+    ///   pairs, encoded as a CBOR list of `(string, string)` tuples.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                                     |
+    /// |---------------------|------------------------------------------------------------|
+    /// | [`IllegalArgument`] | invalid level, message or fields, or out-of-bounds buffers |
+    pub fn log_structured(
+        level: u32,
+        message: *const u8,
+        message_len: u32,
+        fields: *const u8,
+        fields_len: u32,
+    ) -> Result<()>;
+
     /// Save data as a debug artifact on the node.
     pub fn store_artifact(name_off: *const u8, name_len: u32, data_off: *const u8, data_len: u32) -> Result<()>;
 }
@@ -32,6 +32,7 @@ super::fvm_syscalls! {
     /// |---------------------|---------------------------------------------|
     /// | [`NotFound`]        | the target block isn't in the reachable set |
     /// | [`IllegalArgument`] | there's something wrong with the CID        |
+    /// | [`LimitExceeded`]   | the block doesn't fit in the block quota    |
     pub fn block_open(cid: *const u8) -> Result<IpldOpen>;
 
     /// Creates a new block, returning the block's ID. The block's children must be in the reachable
@@ -46,7 +47,7 @@ super::fvm_syscalls! {
     ///
     /// | Error               | Reason                                                  |
     /// |---------------------|---------------------------------------------------------|
-    /// | [`LimitExceeded`]   | the block is too big                                    |
+    /// | [`LimitExceeded`]   | the block is too big, or doesn't fit in the block quota |
     /// | [`NotFound`]        | one of the blocks's children isn't in the reachable set |
     /// | [`IllegalCodec`]    | the passed codec isn't supported                        |
     /// | [`Serialization`]   | the passed block doesn't match the passed codec         |
@@ -91,6 +92,15 @@ super::fvm_syscalls! {
     /// | [`InvalidHandle`] | if the handle isn't known. |
     pub fn block_stat(id: u32) -> Result<IpldStat>;
 
+    /// Returns the number of blocks, and bytes of block data, that may still be opened or created
+    /// during the current message before [`block_open`] and [`block_create`] fail with
+    /// [`LimitExceeded`]. The quota is shared with the actor's callers and callees.
+    ///
+    /// # Errors
+    ///
+    /// None.
+    pub fn block_quota() -> Result<IpldQuota>;
+
     /// Computes the given block's CID, writing the resulting CID into `cid`.
     ///
     /// The returned CID is added to the reachable set.
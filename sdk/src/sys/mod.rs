@@ -54,7 +54,7 @@
 #[doc(inline)]
 pub use fvm_shared::error::ErrorNumber;
 #[doc(inline)]
-pub use fvm_shared::sys::TokenAmount;
+pub use fvm_shared::sys::{SyscallSafe, TokenAmount};
 
 pub mod actor;
 pub mod crypto;
@@ -116,7 +116,7 @@ macro_rules! fvm_syscalls {
             if code == 0 {
                 Ok(())
             } else {
-                Err(num_traits::FromPrimitive::from_u32(code)
+                Err($crate::sys::__private::FromPrimitive::from_u32(code)
                     .expect("syscall returned unrecognized exit code"))
             }
         }
@@ -142,7 +142,7 @@ macro_rules! fvm_syscalls {
             if code == 0 {
                 Ok(ret.assume_init())
             } else {
-                Err(num_traits::FromPrimitive::from_u32(code)
+                Err($crate::sys::__private::FromPrimitive::from_u32(code)
                     .expect("syscall returned unrecognized exit code"))
             }
         }
@@ -181,3 +181,96 @@ macro_rules! fvm_syscalls {
 }
 
 pub use fvm_syscalls;
+
+/// Declare custom syscalls (e.g., syscalls provided by a custom kernel or host module) and generate
+/// safe wrappers for them.
+///
+/// For each declared function, this macro generates the `unsafe` wasm import (see
+/// [`fvm_syscalls`]) along with a safe wrapper of the same name. Parameters and return values must
+/// be [`SyscallSafe`] values, so pointers can't be passed. Errors are returned as
+/// [`ErrorNumber`]s unless an error type is specified, in which case it must implement
+/// `From<ErrorNumber>`.
+///
+/// The module and function names must match the names the kernel links the syscall under.
+///
+/// ```ignore
+/// fvm_sdk::declare_syscall! {
+///     module = "my_custom_kernel";
+///
+///     /// Doubles the input.
+///     pub fn my_custom_syscall(doubleme: i32) -> Result<i32>;
+///
+///     /// Returns nothing, mapping failures into `MyError`.
+///     pub fn poke(arg: u64) -> Result<(), MyError>;
+/// }
+///
+/// let doubled: fvm_sdk::SyscallResult<i32> = my_custom_syscall(21);
+/// ```
+#[macro_export]
+macro_rules! declare_syscall {
+    // Returns no values.
+    (module = $module:literal; $(#[$attrs:meta])* $v:vis fn $name:ident($($args:ident : $args_ty:ty),*$(,)?) -> Result<()>; $($rest:tt)*) => {
+        $crate::declare_syscall! {
+            @wrap module = $module; $(#[$attrs])* $v fn $name($($args : $args_ty),*) -> Result<(), $crate::sys::ErrorNumber> => [Result<()>];
+        }
+        $crate::declare_syscall! {
+            module = $module; $($rest)*
+        }
+    };
+    // Returns no values, custom error type.
+    (module = $module:literal; $(#[$attrs:meta])* $v:vis fn $name:ident($($args:ident : $args_ty:ty),*$(,)?) -> Result<(), $err:ty>; $($rest:tt)*) => {
+        $crate::declare_syscall! {
+            @wrap module = $module; $(#[$attrs])* $v fn $name($($args : $args_ty),*) -> Result<(), $err> => [Result<()>];
+        }
+        $crate::declare_syscall! {
+            module = $module; $($rest)*
+        }
+    };
+    // Returns a value.
+    (module = $module:literal; $(#[$attrs:meta])* $v:vis fn $name:ident($($args:ident : $args_ty:ty),*$(,)?) -> Result<$ret:ty>; $($rest:tt)*) => {
+        $crate::declare_syscall! {
+            @wrap module = $module; $(#[$attrs])* $v fn $name($($args : $args_ty),*) -> Result<$ret, $crate::sys::ErrorNumber> => [Result<$ret>];
+        }
+        $crate::declare_syscall! {
+            module = $module; $($rest)*
+        }
+    };
+    // Returns a value, custom error type.
+    (module = $module:literal; $(#[$attrs:meta])* $v:vis fn $name:ident($($args:ident : $args_ty:ty),*$(,)?) -> Result<$ret:ty, $err:ty>; $($rest:tt)*) => {
+        $crate::declare_syscall! {
+            @wrap module = $module; $(#[$attrs])* $v fn $name($($args : $args_ty),*) -> Result<$ret, $err> => [Result<$ret>];
+        }
+        $crate::declare_syscall! {
+            module = $module; $($rest)*
+        }
+    };
+    // Generates the safe wrapper around the raw syscall.
+    (@wrap module = $module:literal; $(#[$attrs:meta])* $v:vis fn $name:ident($($args:ident : $args_ty:ty),*) -> Result<$ret:ty, $err:ty> => [$($raw_ret:tt)*];) => {
+        $(#[$attrs])*
+        #[allow(clippy::too_many_arguments)]
+        $v fn $name($($args : $args_ty),*) -> ::core::result::Result<$ret, $err> {
+            // Parameters and return values must be plain values for this wrapper to be safe.
+            fn assert_syscall_safe<T: $crate::sys::SyscallSafe>() {}
+            $(assert_syscall_safe::<$args_ty>();)*
+            assert_syscall_safe::<$ret>();
+
+            $crate::sys::fvm_syscalls! {
+                module = $module;
+                fn $name($($args : $args_ty),*) -> $($raw_ret)*;
+            }
+
+            // SAFETY: all parameters are passed by value and the return value (if any) is
+            // written by the FVM into memory we own.
+            unsafe { $name($($args),*) }.map_err(::core::convert::Into::into)
+        }
+    };
+    // Base case.
+    (module = $module:literal;) => {};
+}
+
+pub use declare_syscall;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use num_traits::FromPrimitive;
+}
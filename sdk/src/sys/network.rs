@@ -4,7 +4,7 @@
 
 // for documentation links
 #[doc(inline)]
-pub use fvm_shared::sys::out::network::NetworkContext;
+pub use fvm_shared::sys::out::network::{NetworkContext, NetworkSummary};
 
 #[cfg(doc)]
 use crate::sys::ErrorNumber::*;
@@ -19,6 +19,17 @@ super::fvm_syscalls! {
     /// None
     pub fn total_fil_circ_supply() -> Result<super::TokenAmount>;
 
+    /// Gets the network's total raw byte and quality-adjusted power, the total pledge collateral,
+    /// and the current epoch's block reward, as recorded in the storage power and reward actors'
+    /// state.
+    ///
+    /// # Errors
+    ///
+    /// | Error                | Reason                                                              |
+    /// |----------------------|---------------------------------------------------------------------|
+    /// | [`IllegalOperation`] | the storage power or reward actor doesn't exist or can't be decoded |
+    pub fn summary() -> Result<NetworkSummary>;
+
     /// Retrieves a tipset's CID within the last finality, if available
     ///
     /// # Arguments
@@ -33,10 +44,10 @@ super::fvm_syscalls! {
     ///
     /// # Errors
     ///
-    /// | Error               | Reason                                       |
-    /// |---------------------|----------------------------------------------|
-    /// | [`IllegalArgument`] | specified epoch is negative or in the future |
-    /// | [`LimitExceeded`]   | specified epoch exceeds finality             |
+    /// | Error               | Reason                                               |
+    /// |---------------------|------------------------------------------------------|
+    /// | [`IllegalArgument`] | specified epoch is negative or in the future         |
+    /// | [`LimitExceeded`]   | specified epoch exceeds finality (900 epochs, nv26+) |
     pub fn tipset_cid(
         epoch: i64,
         ret_off: *mut u8,
@@ -45,6 +45,7 @@ super::fvm_syscalls! {
     /// | [`LimitExceeded`]     | recursion limit reached.                             |
     /// | [`IllegalArgument`]   | invalid recipient address buffer.                    |
     /// | [`ReadOnly`]          | the send would mutate state in read-only mode.       |
+    /// | [`Forbidden`]         | the send would re-enter an actor forbidding it.      |
     pub fn send(
         recipient_off: *const u8,
         recipient_len: u32,
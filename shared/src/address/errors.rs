@@ -8,7 +8,7 @@ use data_encoding::DecodeError;
 use thiserror::Error;
 use unsigned_varint::decode::Error as VarintError;
 
-use super::{BLS_PUB_LEN, SECP_PUB_LEN};
+use super::{BLS_PUB_LEN, ETH_ADDRESS_LEN, SECP_PUB_LEN};
 
 /// Address error
 #[derive(Debug, PartialEq, Eq, Error)]
@@ -35,6 +35,15 @@ pub enum Error {
     NonIDAddress,
     #[error("Cannot get delegated address from non delegate address")]
     NonDelegatedAddress,
+    #[error("Not an f410 address (outside of the EAM's namespace)")]
+    NonEamAddress,
+    #[error(
+        "Invalid Ethereum address length, wanted: {} got: {0}",
+        ETH_ADDRESS_LEN
+    )]
+    InvalidEthAddressLength(usize),
+    #[error("ID-masked Ethereum addresses have no f410 address")]
+    IdMaskedEthAddress,
 }
 
 impl From<num::ParseIntError> for Error {
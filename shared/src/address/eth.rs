@@ -0,0 +1,251 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Ethereum addresses and their f410 encoding.
+//!
+//! The Ethereum Address Manager (EAM) assigns f4 addresses in its own namespace (`f410...`), whose
+//! subaddress is always a 20 byte Ethereum address. On the Ethereum side, actors without such an
+//! address are referred to by "ID-masked" addresses (`0xff`, 11 zero bytes, then the big-endian
+//! actor ID); these are never valid f410 subaddresses.
+//!
+//! Like [`crate::crypto::eth`], the checksum helpers take a keccak-256 implementation rather than
+//! depending on one.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Address, Error, Payload};
+use crate::well_known::WellKnownActor;
+use crate::ActorID;
+
+/// The namespace of f410 addresses, the ID of the EAM actor.
+pub const EAM_NAMESPACE: ActorID = WellKnownActor::Eam.id();
+
+/// The length of an Ethereum address, and of f410 subaddresses.
+pub const ETH_ADDRESS_LEN: usize = 20;
+
+/// The first 12 bytes of an ID-masked Ethereum address.
+const ID_MASK_PREFIX: [u8; 12] = [0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// A 20 byte Ethereum address.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EthAddress(pub [u8; ETH_ADDRESS_LEN]);
+
+impl EthAddress {
+    /// Returns the ID-masked Ethereum address of the given actor.
+    pub const fn from_id(id: ActorID) -> Self {
+        let mut bytes = [0u8; ETH_ADDRESS_LEN];
+        bytes[0] = 0xff;
+        let id = id.to_be_bytes();
+        let mut i = 0;
+        while i < id.len() {
+            bytes[12 + i] = id[i];
+            i += 1;
+        }
+        Self(bytes)
+    }
+
+    /// Returns true if this is an ID-masked address.
+    pub fn is_id_masked(&self) -> bool {
+        self.0[..12] == ID_MASK_PREFIX
+    }
+
+    /// Returns the actor ID of an ID-masked address, or `None` for any other address.
+    pub fn as_id(&self) -> Option<ActorID> {
+        if !self.is_id_masked() {
+            return None;
+        }
+        Some(ActorID::from_be_bytes(self.0[12..].try_into().unwrap()))
+    }
+
+    /// Extracts the Ethereum address from an f410 address. Fails if the address isn't in the EAM's
+    /// namespace, if its subaddress isn't exactly 20 bytes, or if it's ID-masked.
+    pub fn from_f410(addr: &Address) -> Result<Self, Error> {
+        let delegated = match addr.payload() {
+            Payload::Delegated(delegated) => delegated,
+            _ => return Err(Error::NonDelegatedAddress),
+        };
+        if delegated.namespace() != EAM_NAMESPACE {
+            return Err(Error::NonEamAddress);
+        }
+        let subaddress = delegated.subaddress();
+        let eth = Self(
+            subaddress
+                .try_into()
+                .map_err(|_| Error::InvalidEthAddressLength(subaddress.len()))?,
+        );
+        if eth.is_id_masked() {
+            return Err(Error::IdMaskedEthAddress);
+        }
+        Ok(eth)
+    }
+
+    /// Returns the f410 address of this Ethereum address. Fails if it's ID-masked: use
+    /// [`Address::new_id`] with the ID returned by [`EthAddress::as_id`] instead.
+    pub fn to_f410(&self) -> Result<Address, Error> {
+        if self.is_id_masked() {
+            return Err(Error::IdMaskedEthAddress);
+        }
+        Address::new_delegated(EAM_NAMESPACE, &self.0)
+    }
+
+    /// Formats the address as a mixed-case checksummed `0x` string (EIP-55).
+    pub fn to_checksum_string(&self, keccak256: impl FnOnce(&[u8]) -> [u8; 32]) -> String {
+        let lower = data_encoding::HEXLOWER.encode(&self.0);
+        let hash = keccak256(lower.as_bytes());
+        let mut out = String::with_capacity(2 + lower.len());
+        out.push_str("0x");
+        for (i, c) in lower.chars().enumerate() {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0xf;
+            out.push(if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            });
+        }
+        out
+    }
+
+    /// Parses a `0x` string, verifying its EIP-55 checksum if it's mixed-case. All-lowercase and
+    /// all-uppercase strings carry no checksum, and are accepted as they are.
+    pub fn parse_checksummed(
+        s: &str,
+        keccak256: impl FnOnce(&[u8]) -> [u8; 32],
+    ) -> Result<Self, Error> {
+        let addr: Self = s.parse()?;
+        let digits = &s[2..];
+        let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase())
+            && digits.chars().any(|c| c.is_ascii_uppercase());
+        if mixed_case && addr.to_checksum_string(keccak256) != s {
+            return Err(Error::InvalidChecksum);
+        }
+        Ok(addr)
+    }
+}
+
+/// Formats the address as a lowercase `0x` string. See [`EthAddress::to_checksum_string`] for the
+/// checksummed format.
+impl fmt::Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", data_encoding::HEXLOWER.encode(&self.0))
+    }
+}
+
+/// Parses a `0x` string of any case, without verifying its checksum. See
+/// [`EthAddress::parse_checksummed`].
+impl FromStr for EthAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let digits = s.strip_prefix("0x").ok_or(Error::InvalidPayload)?;
+        if digits.len() != 2 * ETH_ADDRESS_LEN {
+            return Err(Error::InvalidEthAddressLength(digits.len() / 2));
+        }
+        let bytes = data_encoding::HEXLOWER_PERMISSIVE
+            .decode(digits.as_bytes())
+            .map_err(|_| Error::InvalidPayload)?;
+        Ok(Self(bytes.try_into().unwrap()))
+    }
+}
+
+impl TryFrom<&Address> for EthAddress {
+    type Error = Error;
+
+    fn try_from(addr: &Address) -> Result<Self, Error> {
+        Self::from_f410(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::eth::keccak256;
+
+    // The examples from EIP-55.
+    const CHECKSUMMED: &[&str] = &[
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn checksum() {
+        for s in CHECKSUMMED {
+            let addr: EthAddress = s.parse().unwrap();
+            assert_eq!(addr.to_string(), s.to_lowercase());
+            assert_eq!(addr.to_checksum_string(keccak256), *s);
+            assert_eq!(EthAddress::parse_checksummed(s, keccak256), Ok(addr));
+            assert_eq!(
+                EthAddress::parse_checksummed(&s.to_lowercase(), keccak256),
+                Ok(addr)
+            );
+        }
+
+        // Flip the case of one letter.
+        let bad = CHECKSUMMED[0].replacen('a', "A", 1);
+        assert_eq!(
+            EthAddress::parse_checksummed(&bad, keccak256),
+            Err(Error::InvalidChecksum)
+        );
+
+        assert_eq!(
+            "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse::<EthAddress>(),
+            Err(Error::InvalidPayload)
+        );
+        assert_eq!(
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA".parse::<EthAddress>(),
+            Err(Error::InvalidEthAddressLength(19))
+        );
+        assert_eq!(
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAzz".parse::<EthAddress>(),
+            Err(Error::InvalidPayload)
+        );
+    }
+
+    #[test]
+    fn f410() {
+        let eth: EthAddress = CHECKSUMMED[0].parse().unwrap();
+        let addr = eth.to_f410().unwrap();
+        assert!(addr.to_string()[1..].starts_with("410f"), "{addr}");
+        assert_eq!(EthAddress::from_f410(&addr), Ok(eth));
+        assert_eq!(EthAddress::try_from(&addr), Ok(eth));
+
+        assert_eq!(
+            EthAddress::from_f410(&Address::new_id(1234)),
+            Err(Error::NonDelegatedAddress)
+        );
+        assert_eq!(
+            EthAddress::from_f410(&Address::new_delegated(32, &eth.0).unwrap()),
+            Err(Error::NonEamAddress)
+        );
+        assert_eq!(
+            EthAddress::from_f410(&Address::new_delegated(EAM_NAMESPACE, &eth.0[1..]).unwrap()),
+            Err(Error::InvalidEthAddressLength(19))
+        );
+        assert_eq!(
+            EthAddress::from_f410(&Address::new_delegated(EAM_NAMESPACE, &[0; 32]).unwrap()),
+            Err(Error::InvalidEthAddressLength(32))
+        );
+    }
+
+    #[test]
+    fn id_masked() {
+        let masked = EthAddress::from_id(1234);
+        assert_eq!(
+            masked.to_string(),
+            "0xff000000000000000000000000000000000004d2"
+        );
+        assert!(masked.is_id_masked());
+        assert_eq!(masked.as_id(), Some(1234));
+        assert_eq!(masked.to_f410(), Err(Error::IdMaskedEthAddress));
+        assert_eq!(
+            EthAddress::from_f410(&Address::new_delegated(EAM_NAMESPACE, &masked.0).unwrap()),
+            Err(Error::IdMaskedEthAddress)
+        );
+
+        let eth: EthAddress = CHECKSUMMED[0].parse().unwrap();
+        assert!(!eth.is_id_masked());
+        assert_eq!(eth.as_id(), None);
+    }
+}
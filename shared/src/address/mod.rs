@@ -3,9 +3,12 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 mod errors;
+mod eth;
 mod network;
 mod payload;
+mod predict;
 mod protocol;
+mod resolution;
 
 use std::borrow::Cow;
 use std::fmt;
@@ -18,9 +21,12 @@ use fvm_ipld_encoding::strict_bytes;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 pub use self::errors::Error;
+pub use self::eth::{EthAddress, EAM_NAMESPACE, ETH_ADDRESS_LEN};
 pub use self::network::{current_network, set_current_network, Network};
 pub use self::payload::{DelegatedAddress, Payload};
+pub use self::predict::predict_actor_address;
 pub use self::protocol::Protocol;
+pub use self::resolution::ImplicitCreation;
 use crate::ActorID;
 
 /// defines the encoder for base32 encoding with the provided string with no padding
@@ -166,6 +172,9 @@ impl Address {
     }
 
     /// Get ID of the address. ID protocol only.
+    ///
+    /// The FVM resolves ID addresses to their ID without checking that the actor exists, see
+    /// [`ImplicitCreation`].
     pub fn id(&self) -> Result<u64, Error> {
         match self.payload {
             Payload::ID(id) => Ok(id),
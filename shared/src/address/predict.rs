@@ -0,0 +1,48 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_ipld_encoding::to_vec;
+
+use super::Address;
+
+/// Predicts the robust (f2) address assigned to the `actors_created`th actor (starting at 0)
+/// created while executing the message with the given origin address and nonce (e.g., by the init
+/// actor's `Exec` method).
+///
+/// This matches the FVM's derivation exactly, so the origin address must be the message's `from`
+/// address exactly as it appears in the message.
+pub fn predict_actor_address(origin: &Address, nonce: u64, actors_created: u64) -> Address {
+    let mut b = to_vec(origin).expect("failed to serialize address");
+    b.extend_from_slice(&nonce.to_be_bytes());
+    b.extend_from_slice(&actors_created.to_be_bytes());
+    Address::new_actor(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actor_address() {
+        let origin = Address::new_id(1234);
+        let first = predict_actor_address(&origin, 5, 0);
+
+        // The f2 address hashes the CBOR-encoded origin, then the nonce and the number of actors
+        // created so far (both big-endian).
+        #[rustfmt::skip]
+        let preimage = [
+            0x43, 0x00, 0xd2, 0x09,
+            0, 0, 0, 0, 0, 0, 0, 5,
+            0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert_eq!(first, Address::new_actor(&preimage));
+        // Skip the network prefix, as tests may change the current network.
+        assert_eq!(
+            &first.to_string()[1..],
+            "2ohwnpmk7e2pfgyv367n3v4yp5cfo3dbufrassmq"
+        );
+
+        assert_ne!(first, predict_actor_address(&origin, 5, 1));
+        assert_ne!(first, predict_actor_address(&origin, 6, 0));
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{Address, Payload, EAM_NAMESPACE};
+
+/// What the FVM does when a message is sent to an address that isn't assigned to an actor, as
+/// returned by [`Address::implicit_creation`].
+///
+/// The FVM resolves addresses to actor IDs as follows:
+///
+/// - ID (f0) addresses always resolve to their ID, whether or not an actor exists with that ID.
+///   Sending to the ID of a missing actor fails with `NotFound` when the receiver is loaded.
+/// - Key (f1, f3), robust (f2) and delegated (f4) addresses are resolved through the init actor's
+///   address map. An actor's delegated address is also recorded in its state, so it can be looked
+///   up from the actor's ID (the `actor::lookup_delegated_address` syscall).
+///
+/// Only sends to non-ID addresses that don't resolve may implicitly create actors. They never do
+/// in read-only calls: those fail with `ReadOnly` instead, unless the address is classified as
+/// [`ImplicitCreation::None`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImplicitCreation {
+    /// An account actor is created (charging for its creation) and its constructor is invoked by
+    /// the system actor. Applies to secp256k1 (f1) and BLS (f3) addresses, except the BLS zero
+    /// address.
+    Account,
+    /// A placeholder actor, with the address as its delegated address, is created without
+    /// invoking any constructor. Applies to delegated (f4) addresses in the EAM namespace; the EAM
+    /// may later deploy an EVM actor over the placeholder, keeping its ID and balance.
+    Placeholder,
+    /// The send fails with `IllegalArgument`: the BLS zero address is a well-known burn address
+    /// and can't be assigned to an actor.
+    Forbidden,
+    /// The send fails with `NotFound`. Applies to ID addresses (which always resolve, so this only
+    /// describes sends to missing actors), robust (f2) addresses, which are only assigned when an
+    /// actor is created through the init actor, and delegated addresses in other namespaces.
+    None,
+}
+
+impl Address {
+    /// Returns what the FVM does when a message is sent to this address while it isn't assigned
+    /// to an actor.
+    pub fn implicit_creation(&self) -> ImplicitCreation {
+        match self.payload() {
+            _ if self.is_bls_zero_address() => ImplicitCreation::Forbidden,
+            Payload::Secp256k1(_) | Payload::BLS(_) => ImplicitCreation::Account,
+            Payload::Delegated(da) if da.namespace() == EAM_NAMESPACE => {
+                ImplicitCreation::Placeholder
+            }
+            Payload::ID(_) | Payload::Actor(_) | Payload::Delegated(_) => ImplicitCreation::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::BLS_PUB_LEN;
+
+    #[test]
+    fn implicit_creation() {
+        let mut bls_zero = [0u8; BLS_PUB_LEN];
+        bls_zero[0] = 192;
+        let cases = [
+            (Address::new_id(0), ImplicitCreation::None),
+            (Address::new_id(1234), ImplicitCreation::None),
+            (
+                Address::new_secp256k1(&[4; 65]).unwrap(),
+                ImplicitCreation::Account,
+            ),
+            (
+                Address::new_bls(&[1; BLS_PUB_LEN]).unwrap(),
+                ImplicitCreation::Account,
+            ),
+            (
+                Address::new_bls(&bls_zero).unwrap(),
+                ImplicitCreation::Forbidden,
+            ),
+            (Address::new_actor(b"actor"), ImplicitCreation::None),
+            (
+                Address::new_delegated(EAM_NAMESPACE, &[0xab; 20]).unwrap(),
+                ImplicitCreation::Placeholder,
+            ),
+            // The EAM namespace doesn't constrain the subaddress's length here; the EAM rejects
+            // such addresses when deploying over placeholders.
+            (
+                Address::new_delegated(EAM_NAMESPACE, &[]).unwrap(),
+                ImplicitCreation::Placeholder,
+            ),
+            (
+                Address::new_delegated(EAM_NAMESPACE + 1, &[0xab; 20]).unwrap(),
+                ImplicitCreation::None,
+            ),
+            (
+                Address::new_delegated(1000, &[0xab; 20]).unwrap(),
+                ImplicitCreation::None,
+            ),
+        ];
+        for (addr, expected) in cases {
+            assert_eq!(addr.implicit_creation(), expected, "{addr}");
+        }
+    }
+}
@@ -0,0 +1,25 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use cid::Version;
+use thiserror::Error;
+
+use super::CommitmentKind;
+
+/// Commitment CID error
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum Error {
+    #[error("Invalid CID version {0:?}, expected a CIDv1 commitment")]
+    InvalidVersion(Version),
+    #[error("Invalid codec {0:#x}, expected sealed or unsealed commitment codec")]
+    InvalidCodec(u64),
+    #[error("Incorrect hash function {hash:#x} for {kind} commitment")]
+    InvalidHashFunction { kind: CommitmentKind, hash: u64 },
+    #[error("Commitments must be 32 bytes long, got {0}")]
+    InvalidLength(usize),
+    #[error("Expected {expected} commitment, got {actual}")]
+    UnexpectedKind {
+        expected: CommitmentKind,
+        actual: CommitmentKind,
+    },
+}
@@ -2,8 +2,13 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::fmt;
+
 use cid::multihash::Multihash;
-use cid::Cid;
+use cid::{Cid, Version};
+
+mod errors;
+pub use self::errors::Error;
 
 pub const FIL_COMMITMENT_SEALED: u64 = 0xf102;
 pub const FIL_COMMITMENT_UNSEALED: u64 = 0xf101;
@@ -16,14 +21,59 @@ pub const SHA2_256_TRUNC254_PADDED: u64 = 0x1012;
 
 pub type Commitment = [u8; 32];
 
+/// The kind of a commitment, determined by its CID's codec.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CommitmentKind {
+    /// A replica commitment (CommR), identifying a sealed sector.
+    Sealed,
+    /// A data (CommD) or piece (CommP) commitment, identifying unsealed data.
+    Unsealed,
+}
+
+impl CommitmentKind {
+    /// Returns the commitment kind with the given codec, if any.
+    pub fn from_codec(codec: u64) -> Option<Self> {
+        match codec {
+            FIL_COMMITMENT_SEALED => Some(CommitmentKind::Sealed),
+            FIL_COMMITMENT_UNSEALED => Some(CommitmentKind::Unsealed),
+            _ => None,
+        }
+    }
+
+    /// The CID codec of commitments of this kind.
+    pub fn codec(self) -> u64 {
+        match self {
+            CommitmentKind::Sealed => FIL_COMMITMENT_SEALED,
+            CommitmentKind::Unsealed => FIL_COMMITMENT_UNSEALED,
+        }
+    }
+
+    /// The multihash code of commitments of this kind.
+    pub fn hash_code(self) -> u64 {
+        match self {
+            CommitmentKind::Sealed => POSEIDON_BLS12_381_A1_FC1,
+            CommitmentKind::Unsealed => SHA2_256_TRUNC254_PADDED,
+        }
+    }
+}
+
+impl fmt::Display for CommitmentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitmentKind::Sealed => f.write_str("sealed"),
+            CommitmentKind::Unsealed => f.write_str("unsealed"),
+        }
+    }
+}
+
 /// CommitmentToCID converts a raw commitment hash to a CID
 /// by adding:
 /// - the given filecoin codec type
 /// - the given filecoin e
-pub fn commitment_to_cid(mc: u64, mh: u64, commitment: &Commitment) -> Result<Cid, &'static str> {
+pub fn commitment_to_cid(mc: u64, mh: u64, commitment: &Commitment) -> Result<Cid, Error> {
     validate_filecoin_cid_segments(mc, mh, commitment)?;
 
-    let mh = Multihash::wrap(mh, commitment).map_err(|_| "failed to wrap commitment cid")?;
+    let mh = Multihash::wrap(mh, commitment).map_err(|_| Error::InvalidLength(commitment.len()))?;
 
     Ok(Cid::new_v1(mc, mh))
 }
@@ -31,8 +81,8 @@ pub fn commitment_to_cid(mc: u64, mh: u64, commitment: &Commitment) -> Result<Ci
 /// CIDToCommitment extracts the raw commitment bytes, the FilMultiCodec and
 /// FilMultiHash from a CID, after validating that the codec and hash type are
 /// consistent
-pub fn cid_to_commitment(c: &Cid) -> Result<(u64, u64, Commitment), &'static str> {
-    validate_filecoin_cid_segments(c.codec(), c.hash().code(), c.hash().digest())?;
+pub fn cid_to_commitment(c: &Cid) -> Result<(u64, u64, Commitment), Error> {
+    validate_commitment_cid(c)?;
 
     let mut comm = Commitment::default();
     comm.copy_from_slice(c.hash().digest());
@@ -44,78 +94,102 @@ pub fn cid_to_commitment(c: &Cid) -> Result<(u64, u64, Commitment), &'static str
 /// by adding:
 /// - codec: cid.FilCommitmentUnsealed
 /// - hash type: multihash.Sha2256Truncated256Padded
-pub fn data_commitment_v1_to_cid(comm_d: &Commitment) -> Result<Cid, &'static str> {
+pub fn data_commitment_v1_to_cid(comm_d: &Commitment) -> Result<Cid, Error> {
     commitment_to_cid(FIL_COMMITMENT_UNSEALED, SHA2_256_TRUNC254_PADDED, comm_d)
 }
 
 /// cid_to_data_commitment_v1 extracts the raw data commitment from a CID
 /// assuming that it has the correct hashing function and
 /// serialization types
-pub fn cid_to_data_commitment_v1(c: &Cid) -> Result<Commitment, &'static str> {
-    let (codec, _, comm_d) = cid_to_commitment(c)?;
-
-    if codec != FIL_COMMITMENT_UNSEALED {
-        return Err("data commitment codec must be Unsealed");
-    }
-
-    Ok(comm_d)
+pub fn cid_to_data_commitment_v1(c: &Cid) -> Result<Commitment, Error> {
+    cid_to_commitment_of_kind(c, CommitmentKind::Unsealed)
 }
 
 /// ReplicaCommitmentV1ToCID converts a raw data commitment to a CID
 /// by adding:
 /// - codec: cid.FilCommitmentSealed
 /// - hash type: multihash.PoseidonBls12381A1Fc1
-pub fn replica_commitment_v1_to_cid(comm_r: &Commitment) -> Result<Cid, &'static str> {
+pub fn replica_commitment_v1_to_cid(comm_r: &Commitment) -> Result<Cid, Error> {
     commitment_to_cid(FIL_COMMITMENT_SEALED, POSEIDON_BLS12_381_A1_FC1, comm_r)
 }
 
 /// cid_to_replica_commitment_v1 extracts the raw replica commitment from a CID
 /// assuming that it has the correct hashing function and
 /// serialization types
-pub fn cid_to_replica_commitment_v1(c: &Cid) -> Result<Commitment, &'static str> {
-    let (codec, _, comm_r) = cid_to_commitment(c)?;
+pub fn cid_to_replica_commitment_v1(c: &Cid) -> Result<Commitment, Error> {
+    cid_to_commitment_of_kind(c, CommitmentKind::Sealed)
+}
+
+/// Validates that a CID is a well-formed commitment CID: a CIDv1 with a commitment codec, the hash
+/// function matching the codec, and a 32 byte digest. Returns the kind of the commitment.
+pub fn validate_commitment_cid(c: &Cid) -> Result<CommitmentKind, Error> {
+    if c.version() != Version::V1 {
+        return Err(Error::InvalidVersion(c.version()));
+    }
+    validate_filecoin_cid_segments(c.codec(), c.hash().code(), c.hash().digest())
+}
+
+/// Validates that a CID is a well-formed replica commitment (CommR) CID, i.e. a sealed sector CID.
+pub fn validate_sealed_cid(c: &Cid) -> Result<(), Error> {
+    validate_commitment_cid_of_kind(c, CommitmentKind::Sealed)
+}
+
+/// Validates that a CID is a well-formed data commitment (CommD) CID, i.e. an unsealed sector CID.
+pub fn validate_unsealed_cid(c: &Cid) -> Result<(), Error> {
+    validate_commitment_cid_of_kind(c, CommitmentKind::Unsealed)
+}
+
+/// Validates that a CID is a well-formed piece commitment (CommP) CID. Piece CIDs are encoded the
+/// same way as unsealed sector CIDs.
+pub fn validate_piece_cid(c: &Cid) -> Result<(), Error> {
+    validate_commitment_cid_of_kind(c, CommitmentKind::Unsealed)
+}
 
-    if codec != FIL_COMMITMENT_SEALED {
-        return Err("data commitment codec must be Sealed");
+fn validate_commitment_cid_of_kind(c: &Cid, expected: CommitmentKind) -> Result<(), Error> {
+    let actual = validate_commitment_cid(c)?;
+    if actual != expected {
+        return Err(Error::UnexpectedKind { expected, actual });
     }
+    Ok(())
+}
+
+fn cid_to_commitment_of_kind(c: &Cid, kind: CommitmentKind) -> Result<Commitment, Error> {
+    validate_commitment_cid_of_kind(c, kind)?;
 
-    Ok(comm_r)
+    let mut comm = Commitment::default();
+    comm.copy_from_slice(c.hash().digest());
+    Ok(comm)
 }
 
 /// ValidateFilecoinCidSegments returns an error if the provided CID parts
 /// conflict with each other.
-fn validate_filecoin_cid_segments(mc: u64, mh: u64, comm_x: &[u8]) -> Result<(), &'static str> {
-    match mc {
-        FIL_COMMITMENT_UNSEALED => {
-            if mh != SHA2_256_TRUNC254_PADDED {
-                return Err("Incorrect hash function for unsealed commitment");
-            }
-        }
-        FIL_COMMITMENT_SEALED => {
-            if mh != POSEIDON_BLS12_381_A1_FC1 {
-                return Err("Incorrect hash function for sealed commitment");
-            }
-        }
-        _ => return Err("Invalid Codec, expected sealed or unsealed commitment codec"),
+fn validate_filecoin_cid_segments(
+    mc: u64,
+    mh: u64,
+    comm_x: &[u8],
+) -> Result<CommitmentKind, Error> {
+    let kind = CommitmentKind::from_codec(mc).ok_or(Error::InvalidCodec(mc))?;
+    if mh != kind.hash_code() {
+        return Err(Error::InvalidHashFunction { kind, hash: mh });
     }
 
     if comm_x.len() != 32 {
-        Err("commitments must be 32 bytes long")
+        Err(Error::InvalidLength(comm_x.len()))
     } else {
-        Ok(())
+        Ok(kind)
     }
 }
 
 /// piece_commitment_v1_to_cid converts a comm_p to a CID
 /// -- it is just a helper function that is equivalent to
 /// data_commitment_v1_to_cid.
-pub fn piece_commitment_v1_to_cid(comm_p: &Commitment) -> Result<Cid, &'static str> {
+pub fn piece_commitment_v1_to_cid(comm_p: &Commitment) -> Result<Cid, Error> {
     data_commitment_v1_to_cid(comm_p)
 }
 
 /// cid_to_piece_commitment_v1 converts a CID to a comm_p
 /// -- it is just a helper function that is equivalent to
 /// cid_to_data_commitment_v1.
-pub fn cid_to_piece_commitment_v1(c: &Cid) -> Result<Commitment, &'static str> {
+pub fn cid_to_piece_commitment_v1(c: &Cid) -> Result<Commitment, Error> {
     cid_to_data_commitment_v1(c)
 }
@@ -0,0 +1,230 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Digests of Ethereum-style signed messages: personal messages (as signed by `personal_sign`,
+//! EIP-191 version `0x45`) and EIP-712 typed data (as signed by `eth_signTypedData_v4`).
+//!
+//! These helpers take a keccak-256 implementation (e.g., the hashing syscall inside actors, see
+//! `fvm_sdk::crypto`) rather than depending on one.
+
+/// The prefix of a personal message, followed by the message's length in decimal.
+pub const PERSONAL_MESSAGE_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n";
+
+/// The prefix of an EIP-712 typed data digest's preimage.
+pub const TYPED_DATA_PREFIX: [u8; 2] = [0x19, 0x01];
+
+/// Computes the digest signed by `personal_sign` for `message`:
+/// `keccak256("\x19Ethereum Signed Message:\n" || len(message) || message)`.
+pub fn personal_message_hash(
+    keccak256: impl FnOnce(&[u8]) -> [u8; 32],
+    message: &[u8],
+) -> [u8; 32] {
+    let len = message.len().to_string();
+    let mut data = Vec::with_capacity(PERSONAL_MESSAGE_PREFIX.len() + len.len() + message.len());
+    data.extend_from_slice(PERSONAL_MESSAGE_PREFIX);
+    data.extend_from_slice(len.as_bytes());
+    data.extend_from_slice(message);
+    keccak256(&data)
+}
+
+/// Computes the EIP-712 digest of a message given its domain separator (see
+/// [`Eip712Domain::separator`]) and struct hash (see [`hash_struct`]):
+/// `keccak256("\x19\x01" || domain_separator || struct_hash)`.
+pub fn typed_data_hash(
+    keccak256: impl FnOnce(&[u8]) -> [u8; 32],
+    domain_separator: &[u8; 32],
+    struct_hash: &[u8; 32],
+) -> [u8; 32] {
+    let mut data = [0u8; 66];
+    data[..2].copy_from_slice(&TYPED_DATA_PREFIX);
+    data[2..34].copy_from_slice(domain_separator);
+    data[34..].copy_from_slice(struct_hash);
+    keccak256(&data)
+}
+
+/// Computes the EIP-712 `hashStruct` of a struct, given its encoded type (e.g.,
+/// `"Mail(Person from,Person to,string contents)Person(string name,address wallet)"`) and its
+/// encoded member values in order.
+///
+/// Atomic values are encoded with [`encode_uint`], [`encode_address`], and [`encode_bool`];
+/// `string` and `bytes` values are encoded as their keccak-256 hash; struct values are encoded as
+/// their `hashStruct`.
+pub fn hash_struct(
+    keccak256: impl Fn(&[u8]) -> [u8; 32],
+    encoded_type: &str,
+    values: &[[u8; 32]],
+) -> [u8; 32] {
+    let mut data = Vec::with_capacity(32 * (values.len() + 1));
+    data.extend_from_slice(&keccak256(encoded_type.as_bytes()));
+    for value in values {
+        data.extend_from_slice(value);
+    }
+    keccak256(&data)
+}
+
+/// Encodes an unsigned integer (`uint8` through `uint256`) EIP-712 value.
+pub fn encode_uint(value: u128) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[16..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+/// Encodes an `address` EIP-712 value.
+pub fn encode_address(address: &[u8; 20]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[12..].copy_from_slice(address);
+    out
+}
+
+/// Encodes a `bool` EIP-712 value.
+pub fn encode_bool(value: bool) -> [u8; 32] {
+    encode_uint(value as u128)
+}
+
+/// An EIP-712 domain. Only the fields that are set are part of the domain's type.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Eip712Domain {
+    /// The name of the signing domain (e.g., the dApp or protocol).
+    pub name: Option<String>,
+    /// The major version of the signing domain.
+    pub version: Option<String>,
+    /// The EIP-155 chain ID (e.g., 314 for Filecoin mainnet).
+    pub chain_id: Option<u64>,
+    /// The address of the contract (actor) that will verify the signature.
+    pub verifying_contract: Option<[u8; 20]>,
+    /// A disambiguating salt.
+    pub salt: Option<[u8; 32]>,
+}
+
+impl Eip712Domain {
+    /// Returns the encoded `EIP712Domain` type of this domain.
+    pub fn encoded_type(&self) -> String {
+        let fields = [
+            (self.name.is_some(), "string name"),
+            (self.version.is_some(), "string version"),
+            (self.chain_id.is_some(), "uint256 chainId"),
+            (
+                self.verifying_contract.is_some(),
+                "address verifyingContract",
+            ),
+            (self.salt.is_some(), "bytes32 salt"),
+        ];
+        let fields: Vec<_> = fields
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, field)| *field)
+            .collect();
+        format!("EIP712Domain({})", fields.join(","))
+    }
+
+    /// Computes the domain separator, the `hashStruct` of this domain.
+    pub fn separator(&self, keccak256: impl Fn(&[u8]) -> [u8; 32]) -> [u8; 32] {
+        let mut values = Vec::with_capacity(5);
+        if let Some(name) = &self.name {
+            values.push(keccak256(name.as_bytes()));
+        }
+        if let Some(version) = &self.version {
+            values.push(keccak256(version.as_bytes()));
+        }
+        if let Some(chain_id) = self.chain_id {
+            values.push(encode_uint(chain_id.into()));
+        }
+        if let Some(contract) = &self.verifying_contract {
+            values.push(encode_address(contract));
+        }
+        if let Some(salt) = self.salt {
+            values.push(salt);
+        }
+        hash_struct(keccak256, &self.encoded_type(), &values)
+    }
+}
+
+/// A keccak-256 implementation for this crate's tests.
+#[cfg(test)]
+pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
+    use multihash_codetable::{Code, MultihashDigest};
+    Code::Keccak256.digest(data).digest().try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        data_encoding::HEXLOWER_PERMISSIVE
+            .decode(s.as_bytes())
+            .unwrap()
+    }
+
+    fn address(s: &str) -> [u8; 20] {
+        hex(s).try_into().unwrap()
+    }
+
+    #[test]
+    fn personal_message() {
+        assert_eq!(
+            personal_message_hash(keccak256, b"hello world").to_vec(),
+            hex("d9eba16ed0ecae432b71fe008c98cc872bb4cc214d3220a36f365326cf807d68")
+        );
+    }
+
+    // The example from EIP-712.
+    #[test]
+    fn typed_data() {
+        let domain = Eip712Domain {
+            name: Some("Ether Mail".into()),
+            version: Some("1".into()),
+            chain_id: Some(1),
+            verifying_contract: Some(address("CcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC")),
+            salt: None,
+        };
+        assert_eq!(
+            domain.encoded_type(),
+            "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"
+        );
+        let separator = domain.separator(keccak256);
+        assert_eq!(
+            separator.to_vec(),
+            hex("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+
+        const PERSON: &str = "Person(string name,address wallet)";
+        const MAIL: &str =
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)";
+        let person = |name: &str, wallet: &str| {
+            hash_struct(
+                keccak256,
+                PERSON,
+                &[keccak256(name.as_bytes()), encode_address(&address(wallet))],
+            )
+        };
+        let mail = hash_struct(
+            keccak256,
+            MAIL,
+            &[
+                person("Cow", "CD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"),
+                person("Bob", "bBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"),
+                keccak256(b"Hello, Bob!"),
+            ],
+        );
+        assert_eq!(
+            mail.to_vec(),
+            hex("c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e")
+        );
+        assert_eq!(
+            typed_data_hash(keccak256, &separator, &mail).to_vec(),
+            hex("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+    }
+
+    #[test]
+    fn encoding() {
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(encode_uint(1), one);
+        assert_eq!(encode_bool(true), one);
+        assert_eq!(encode_bool(false), [0u8; 32]);
+        assert_eq!(encode_address(&[0xff; 20])[..12], [0u8; 12]);
+        assert_eq!(Eip712Domain::default().encoded_type(), "EIP712Domain()");
+    }
+}
@@ -8,6 +8,8 @@ pub enum SupportedHashes {
     Blake2b512 = 0xb240,
     Keccak256 = 0x1b,
     Ripemd160 = 0x1053,
+    Blake3_256 = 0x1e,
+    Sha3_512 = 0x14,
 }
 
 impl From<SupportedHashes> for u64 {
@@ -0,0 +1,142 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Message authentication codes (HMAC, RFC 2104) and constant-time comparisons.
+//!
+//! Comparing secrets (MACs, commitment openings, preimages) with `==` returns at the first
+//! differing byte, so the gas used reveals the length of the matching prefix. Use
+//! [`constant_time_eq`] instead, which always reads every byte and doesn't branch on their values.
+//!
+//! Like [`eth`](super::eth), these helpers take a hash implementation (e.g., the hashing syscall
+//! inside actors, see `fvm_sdk::crypto`) rather than depending on one.
+
+use std::hint::black_box;
+
+/// The block size of SHA-256, in bytes.
+pub const SHA256_BLOCK_LEN: usize = 64;
+/// The block size of BLAKE2b, in bytes.
+pub const BLAKE2B_BLOCK_LEN: usize = 128;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// Returns whether two byte strings are equal, in time (and gas) depending only on their lengths.
+///
+/// The lengths are not considered secret: strings of different lengths are unequal without
+/// comparing their contents. Otherwise, every byte is compared, and the result is computed without
+/// branching on the bytes' values.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    // Keep the optimizer from turning the loop into an early-exit comparison.
+    black_box(diff) == 0
+}
+
+/// Computes the HMAC of `message` under `key` with the given hash function, whose block size is
+/// `BLOCK` bytes (e.g., [`SHA256_BLOCK_LEN`] or [`BLAKE2B_BLOCK_LEN`]):
+/// `H((K' ^ opad) || H((K' ^ ipad) || message))`, where `K'` is the key (or its hash, if longer
+/// than a block) padded with zeros to a block. The digest must not be longer than a block.
+pub fn hmac<const BLOCK: usize, const OUT: usize>(
+    hash: impl Fn(&[u8]) -> [u8; OUT],
+    key: &[u8],
+    message: &[u8],
+) -> [u8; OUT] {
+    let mut block_key = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block_key[..OUT].copy_from_slice(&hash(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Vec::with_capacity(BLOCK + message.len());
+    inner.extend(block_key.iter().map(|b| b ^ IPAD));
+    inner.extend_from_slice(message);
+    let inner = hash(&inner);
+
+    let mut outer = Vec::with_capacity(BLOCK + OUT);
+    outer.extend(block_key.iter().map(|b| b ^ OPAD));
+    outer.extend_from_slice(&inner);
+    hash(&outer)
+}
+
+#[cfg(test)]
+mod tests {
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use super::*;
+
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        Code::Sha2_256.digest(data).digest().try_into().unwrap()
+    }
+
+    fn blake2b(data: &[u8]) -> [u8; 32] {
+        blake2b_simd::Params::new()
+            .hash_length(32)
+            .hash(data)
+            .as_bytes()
+            .try_into()
+            .unwrap()
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        data_encoding::HEXLOWER_PERMISSIVE
+            .decode(s.as_bytes())
+            .unwrap()
+    }
+
+    #[test]
+    fn eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"Secret"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+    }
+
+    #[test]
+    fn hmac_sha256() {
+        // RFC 4231, test cases 1, 2 and 6.
+        let cases = [
+            (
+                vec![0x0b; 20],
+                b"Hi There".to_vec(),
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe".to_vec(),
+                b"what do ya want for nothing?".to_vec(),
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                vec![0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+        ];
+        for (key, message, expected) in cases {
+            let mac = hmac::<SHA256_BLOCK_LEN, 32>(sha256, &key, &message);
+            assert_eq!(mac.to_vec(), hex(expected));
+        }
+    }
+
+    #[test]
+    fn hmac_long_key() {
+        // Keys longer than a block are hashed first.
+        let key = [0xaa; BLAKE2B_BLOCK_LEN + 1];
+        assert_eq!(
+            hmac::<BLAKE2B_BLOCK_LEN, 32>(blake2b, &key, b"message"),
+            hmac::<BLAKE2B_BLOCK_LEN, 32>(blake2b, &blake2b(&key), b"message"),
+        );
+        // Keys of a block or less are padded.
+        assert_eq!(
+            hmac::<BLAKE2B_BLOCK_LEN, 32>(blake2b, b"key", b"message"),
+            hmac::<BLAKE2B_BLOCK_LEN, 32>(blake2b, b"key\0\0", b"message"),
+        );
+        assert_ne!(
+            hmac::<BLAKE2B_BLOCK_LEN, 32>(blake2b, b"key", b"message"),
+            hmac::<SHA256_BLOCK_LEN, 32>(blake2b, b"key", b"message"),
+        );
+    }
+}
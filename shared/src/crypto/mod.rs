@@ -1,4 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+pub mod eth;
 pub mod hash;
+pub mod mac;
 pub mod signature;
+pub mod signing;
@@ -0,0 +1,182 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Signing bytes for payloads signed off-chain and verified by actors, such as payment channel
+//! vouchers and datacap removal proposals.
+//!
+//! [`legacy_signing_bytes`] reproduces the formats the builtin actors verify today. These make no
+//! domain-separation guarantees: the payment channel actor verifies a voucher's plain CBOR
+//! encoding (with its signature field cleared), which any other CBOR payload could collide with,
+//! and only the verified registry prefixes datacap removal proposals with `fil_removedatacap:`:
+//!
+//! ```text
+//! [legacy_prefix] || cbor(payload)
+//! ```
+//!
+//! Nor are these signatures bound to a network. [`chain_bound_signing_bytes`] tags every payload
+//! with its [`SigningDomain`], and commits to the chain ID:
+//!
+//! ```text
+//! domain_prefix || chain_id (8 bytes, big-endian) || cbor(payload)
+//! ```
+//!
+//! **Deployed actors reject chain-bound signatures**; only use them with actors that verify them
+//! explicitly.
+
+use fvm_ipld_encoding::{to_vec, Error};
+use serde::Serialize;
+
+use crate::chainid::ChainID;
+
+/// The domain of a signed payload, which its chain-bound signing bytes are tagged with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SigningDomain {
+    /// Payment channel vouchers.
+    PaychVoucher,
+    /// Proposals to remove datacap from a verified client.
+    DataCapRemoval,
+}
+
+impl SigningDomain {
+    /// Returns the prefix the builtin actors expect in front of this domain's payloads in its
+    /// legacy signing bytes, if any. Payment channel vouchers have none.
+    pub const fn legacy_prefix(self) -> Option<&'static [u8]> {
+        match self {
+            SigningDomain::PaychVoucher => None,
+            SigningDomain::DataCapRemoval => Some(b"fil_removedatacap:"),
+        }
+    }
+
+    /// Returns the prefix of this domain's chain-bound signing bytes. No chain-bound prefix is a
+    /// prefix of another, nor of a legacy prefix (or of a CBOR-encoded voucher).
+    pub const fn chain_bound_prefix(self) -> &'static [u8] {
+        match self {
+            SigningDomain::PaychVoucher => b"fil_chain_paychvoucher:",
+            SigningDomain::DataCapRemoval => b"fil_chain_removedatacap:",
+        }
+    }
+}
+
+/// Returns the legacy signing bytes for `payload`, as the builtin actors verify them: its CBOR
+/// encoding, behind the domain's [legacy prefix](SigningDomain::legacy_prefix) if it has one.
+/// These bytes aren't domain-separated, nor bound to a network. Payment channel vouchers must be
+/// passed with their signature field cleared.
+pub fn legacy_signing_bytes<T: Serialize + ?Sized>(
+    domain: SigningDomain,
+    payload: &T,
+) -> Result<Vec<u8>, Error> {
+    let prefix = domain.legacy_prefix().unwrap_or_default();
+    let payload = to_vec(payload)?;
+    let mut bytes = Vec::with_capacity(prefix.len() + payload.len());
+    bytes.extend_from_slice(prefix);
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// Returns the chain-bound signing bytes for `payload` in the given domain, on the network
+/// identified by `chain_id`. Deployed actors don't accept signatures over these bytes.
+pub fn chain_bound_signing_bytes<T: Serialize + ?Sized>(
+    domain: SigningDomain,
+    chain_id: ChainID,
+    payload: &T,
+) -> Result<Vec<u8>, Error> {
+    let prefix = domain.chain_bound_prefix();
+    let payload = to_vec(payload)?;
+    let mut bytes = Vec::with_capacity(prefix.len() + 8 + payload.len());
+    bytes.extend_from_slice(prefix);
+    bytes.extend_from_slice(&u64::from(chain_id).to_be_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// A payload signed in a fixed [`SigningDomain`].
+pub trait Signable: Serialize {
+    /// The domain of this payload type.
+    const DOMAIN: SigningDomain;
+
+    /// Returns the legacy signing bytes for this payload, as the builtin actors verify them. See
+    /// [`legacy_signing_bytes`].
+    fn legacy_signing_bytes(&self) -> Result<Vec<u8>, Error> {
+        legacy_signing_bytes(Self::DOMAIN, self)
+    }
+
+    /// Returns the chain-bound signing bytes for this payload, on the network identified by
+    /// `chain_id`. See [`chain_bound_signing_bytes`].
+    fn chain_bound_signing_bytes(&self, chain_id: ChainID) -> Result<Vec<u8>, Error> {
+        chain_bound_signing_bytes(Self::DOMAIN, chain_id, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::tuple::*;
+
+    use super::*;
+
+    #[derive(Serialize_tuple)]
+    struct Voucher {
+        lane: u64,
+        nonce: u64,
+    }
+
+    impl Signable for Voucher {
+        const DOMAIN: SigningDomain = SigningDomain::PaychVoucher;
+    }
+
+    #[test]
+    fn legacy_layout() {
+        // Vouchers are signed as plain CBOR.
+        let voucher = Voucher { lane: 1, nonce: 2 };
+        assert_eq!(voucher.legacy_signing_bytes().unwrap(), [0x82, 0x01, 0x02]);
+
+        // Datacap removal proposals are prefixed, without a chain ID.
+        assert_eq!(
+            legacy_signing_bytes(SigningDomain::DataCapRemoval, &voucher).unwrap(),
+            [&b"fil_removedatacap:"[..], &[0x82, 0x01, 0x02]].concat()
+        );
+    }
+
+    #[test]
+    fn chain_bound_layout() {
+        let voucher = Voucher { lane: 1, nonce: 2 };
+        let bytes = voucher
+            .chain_bound_signing_bytes(ChainID::from(314))
+            .unwrap();
+        assert_eq!(
+            bytes,
+            [
+                &b"fil_chain_paychvoucher:"[..],
+                &[0, 0, 0, 0, 0, 0, 0x01, 0x3a],
+                &[0x82, 0x01, 0x02],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn separation() {
+        let voucher = Voucher { lane: 1, nonce: 2 };
+        let mainnet = voucher
+            .chain_bound_signing_bytes(ChainID::from(314))
+            .unwrap();
+        let calibnet = voucher
+            .chain_bound_signing_bytes(ChainID::from(314159))
+            .unwrap();
+        let removal =
+            chain_bound_signing_bytes(SigningDomain::DataCapRemoval, ChainID::from(314), &voucher)
+                .unwrap();
+        assert_ne!(mainnet, calibnet);
+        assert_ne!(mainnet, removal);
+        assert_ne!(mainnet, voucher.legacy_signing_bytes().unwrap());
+
+        let domains = [SigningDomain::PaychVoucher, SigningDomain::DataCapRemoval];
+        for a in domains {
+            for b in domains {
+                let (pa, pb) = (a.chain_bound_prefix(), b.chain_bound_prefix());
+                assert!(a == b || !pa.starts_with(pb), "{a:?} {b:?}");
+                let legacy = b.legacy_prefix().unwrap_or_default();
+                assert!(!legacy.starts_with(pa), "{a:?} {b:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Message fee calculations. These are the exact functions used by the FVM executor when charging
+//! for a message, so wallets and gas estimators can use them to predict what a message will cost.
+
+use super::TokenAmount;
+
+/// The numerator of the fraction of gas used that may be "overestimated" without penalty.
+pub const GAS_OVERUSE_NUM: u64 = 11;
+/// The denominator of the fraction of gas used that may be "overestimated" without penalty.
+pub const GAS_OVERUSE_DENOM: u64 = 10;
+
+/// Returns the base fee a message actually pays per unit of gas: the network base fee, capped at
+/// the message's fee cap.
+pub fn effective_base_fee<'a>(
+    base_fee: &'a TokenAmount,
+    fee_cap: &'a TokenAmount,
+) -> &'a TokenAmount {
+    if base_fee > fee_cap {
+        fee_cap
+    } else {
+        base_fee
+    }
+}
+
+/// Returns the gas premium the block miner receives per unit of gas: the message's gas premium,
+/// capped such that the effective base fee plus the premium doesn't exceed the message's fee cap.
+///
+/// The total miner tip is this value multiplied by the message's gas _limit_.
+pub fn compute_miner_tip(
+    base_fee: &TokenAmount,
+    fee_cap: &TokenAmount,
+    gas_premium: &TokenAmount,
+) -> TokenAmount {
+    let base_fee_to_pay = effective_base_fee(base_fee, fee_cap);
+    if &(base_fee_to_pay + gas_premium) > fee_cap {
+        fee_cap - base_fee_to_pay
+    } else {
+        gas_premium.clone()
+    }
+}
+
+/// Computes the amount of gas to refund and the amount of gas to burn (in whole gas units) given
+/// the gas used and the gas limit of a message, returning `(refund, burn)`.
+///
+/// This burns (N-10)% (clamped at 0% and 100%) of the remaining gas where N is the overestimation
+/// percentage.
+pub fn compute_gas_overestimation_burn(gas_used: u64, gas_limit: u64) -> (u64, u64) {
+    if gas_used == 0 {
+        return (0, gas_limit);
+    }
+
+    // Convert to u128 to prevent overflow on multiply.
+    let gas_used = gas_used as u128;
+    let gas_limit = gas_limit as u128;
+
+    let over = gas_limit
+        .saturating_sub((GAS_OVERUSE_NUM as u128 * gas_used) / GAS_OVERUSE_DENOM as u128)
+        .min(gas_used);
+
+    // We handle the case where the gas used exceeds the gas limit, just in case.
+    let gas_remaining = gas_limit.saturating_sub(gas_used);
+
+    // This computes the fraction of the "remaining" gas to burn and will never be greater than 100%
+    // of the remaining gas.
+    let gas_to_burn = (gas_remaining * over) / gas_used;
+
+    // But... we use saturating sub, just in case.
+    let refund = gas_remaining.saturating_sub(gas_to_burn);
+
+    (refund as u64, gas_to_burn as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Adapted from lotus.
+    #[test]
+    fn overestimation_burn_test() {
+        fn do_test(used: u64, limit: u64, refund: u64, toburn: u64) {
+            let (computed_refund, computed_toburn) = compute_gas_overestimation_burn(used, limit);
+            assert_eq!(refund, computed_refund, "refund");
+            assert_eq!(toburn, computed_toburn, "burned");
+        }
+
+        do_test(100, 200, 10, 90);
+        do_test(100, 150, 30, 20);
+        do_test(1_000, 1_300, 240, 60);
+        do_test(500, 700, 140, 60);
+        do_test(200, 200, 0, 0);
+        do_test(20_000, 21_000, 1_000, 0);
+        do_test(0, 2_000, 0, 2_000);
+        do_test(500, 651, 121, 30);
+        do_test(500, 5_000, 0, 4_500);
+        do_test(7_499_000_000, 7_500_000_000, 1_000_000, 0);
+        do_test(7_500_000_000 / 2, 7_500_000_000, 375_000_000, 3_375_000_000);
+        do_test(1, 7_500_000_000, 0, 7_499_999_999);
+    }
+
+    #[test]
+    fn miner_tip_test() {
+        fn do_test(base_fee: u64, fee_cap: u64, premium: u64, tip: u64) {
+            assert_eq!(
+                compute_miner_tip(
+                    &TokenAmount::from_atto(base_fee),
+                    &TokenAmount::from_atto(fee_cap),
+                    &TokenAmount::from_atto(premium),
+                ),
+                TokenAmount::from_atto(tip)
+            );
+        }
+
+        // Fee cap covers the base fee and the full premium.
+        do_test(10, 11, 1, 1);
+        do_test(10, 100, 5, 5);
+        // Premium is capped by the fee cap.
+        do_test(10, 12, 5, 2);
+        do_test(10, 10, 1, 0);
+        // The base fee exceeds the fee cap, so there's nothing left for the miner.
+        do_test(10, 6, 1, 0);
+    }
+
+    #[test]
+    fn effective_base_fee_test() {
+        let ten = TokenAmount::from_atto(10);
+        let six = TokenAmount::from_atto(6);
+        assert_eq!(effective_base_fee(&ten, &six), &six);
+        assert_eq!(effective_base_fee(&six, &ten), &six);
+        assert_eq!(effective_base_fee(&ten, &ten), &ten);
+    }
+}
@@ -12,6 +12,9 @@ use serde::{Deserialize, Serialize, Serializer};
 
 use crate::bigint::bigint_ser;
 
+mod fees;
+pub use fees::*;
+
 /// A quantity of native tokens.
 /// A token amount is an integer, but has a human interpretation as a value with
 /// 18 decimal places.
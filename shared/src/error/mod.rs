@@ -7,6 +7,17 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// ExitCode defines the exit code from the VM invocation.
+///
+/// The exit code space is partitioned as follows:
+///
+/// | Range                 | Owner                                                       |
+/// |-----------------------|-------------------------------------------------------------|
+/// | `0..=15`              | The VM (see the `SYS_*` codes). Actors may not abort with these. |
+/// | `16..=31`             | Standard codes shared by all actors (see the `USR_*` codes). |
+/// | `32..=0xffff`         | Actor-specific codes, including those used by the built-in actors. |
+/// | `0x10000..=0x1ffff`   | User-defined codes (see [`ExitCode::user`]). Built-in actors never use these. |
+///
+/// Codes above `0x1ffff` are currently unassigned.
 #[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(transparent)]
 #[repr(transparent)]
@@ -33,6 +44,46 @@ impl ExitCode {
     pub fn is_system_error(self) -> bool {
         self.value < (Self::FIRST_USER_EXIT_CODE)
     }
+
+    /// Constructs an exit code in the user-defined range. User-defined exit codes are never used
+    /// by the VM or the built-in actors, so custom actors may assign them freely.
+    pub const fn user(code: u16) -> Self {
+        Self::new(Self::FIRST_USER_DEFINED_EXIT_CODE + code as u32)
+    }
+
+    /// Returns true if the exit code is in the user-defined range (see [`ExitCode::user`]).
+    pub fn is_user_defined(self) -> bool {
+        (Self::FIRST_USER_DEFINED_EXIT_CODE..=Self::LAST_USER_DEFINED_EXIT_CODE)
+            .contains(&self.value)
+    }
+
+    /// Returns the code passed to [`ExitCode::user`] if this exit code is user-defined.
+    pub fn user_code(self) -> Option<u16> {
+        self.is_user_defined()
+            .then(|| (self.value - Self::FIRST_USER_DEFINED_EXIT_CODE) as u16)
+    }
+
+    /// Maps a syscall error to the standard exit code an actor should abort with when it can't
+    /// otherwise handle the error. Errors without a standard counterpart map to
+    /// [`ExitCode::USR_UNSPECIFIED`].
+    pub fn from_error_number(err: ErrorNumber) -> Self {
+        match err {
+            ErrorNumber::IllegalArgument => Self::USR_ILLEGAL_ARGUMENT,
+            ErrorNumber::IllegalOperation => Self::USR_ILLEGAL_STATE,
+            ErrorNumber::InsufficientFunds => Self::USR_INSUFFICIENT_FUNDS,
+            ErrorNumber::NotFound => Self::USR_NOT_FOUND,
+            ErrorNumber::Serialization => Self::USR_SERIALIZATION,
+            ErrorNumber::Forbidden => Self::USR_FORBIDDEN,
+            ErrorNumber::ReadOnly => Self::USR_READ_ONLY,
+            _ => Self::USR_UNSPECIFIED,
+        }
+    }
+}
+
+impl From<ErrorNumber> for ExitCode {
+    fn from(err: ErrorNumber) -> Self {
+        Self::from_error_number(err)
+    }
 }
 
 impl From<u32> for ExitCode {
@@ -114,6 +165,14 @@ impl ExitCode {
     // pub const RESERVED_29: ExitCode = ExitCode::new(29);
     // pub const RESERVED_30: ExitCode = ExitCode::new(30);
     // pub const RESERVED_31: ExitCode = ExitCode::new(31);
+
+    /// The lowest actor-specific exit code. Codes from here up to (but excluding)
+    /// [`ExitCode::FIRST_USER_DEFINED_EXIT_CODE`] are defined by individual actors.
+    pub const FIRST_ACTOR_SPECIFIC_EXIT_CODE: u32 = 32;
+    /// The lowest user-defined exit code (i.e., `ExitCode::user(0)`).
+    pub const FIRST_USER_DEFINED_EXIT_CODE: u32 = 0x10000;
+    /// The highest user-defined exit code (i.e., `ExitCode::user(u16::MAX)`).
+    pub const LAST_USER_DEFINED_EXIT_CODE: u32 = 0x1ffff;
 }
 
 /// When a syscall fails, it returns an `ErrorNumber` to indicate why. The syscalls themselves
@@ -176,3 +235,55 @@ impl std::fmt::Display for ErrorNumber {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_exit_codes() {
+        assert_eq!(
+            ExitCode::user(0).value(),
+            ExitCode::FIRST_USER_DEFINED_EXIT_CODE
+        );
+        assert_eq!(
+            ExitCode::user(u16::MAX).value(),
+            ExitCode::LAST_USER_DEFINED_EXIT_CODE
+        );
+        assert_eq!(ExitCode::user(0x42).user_code(), Some(0x42));
+        assert!(ExitCode::user(0x42).is_user_defined());
+        assert!(!ExitCode::user(0x42).is_system_error());
+
+        for code in [
+            ExitCode::OK,
+            ExitCode::SYS_OUT_OF_GAS,
+            ExitCode::USR_NOT_PAYABLE,
+            ExitCode::new(ExitCode::FIRST_ACTOR_SPECIFIC_EXIT_CODE),
+            ExitCode::new(ExitCode::FIRST_USER_DEFINED_EXIT_CODE - 1),
+            ExitCode::new(ExitCode::LAST_USER_DEFINED_EXIT_CODE + 1),
+        ] {
+            assert!(!code.is_user_defined(), "{code} is not user-defined");
+            assert_eq!(code.user_code(), None);
+        }
+    }
+
+    #[test]
+    fn exit_code_from_error_number() {
+        assert_eq!(
+            ExitCode::from(ErrorNumber::IllegalArgument),
+            ExitCode::USR_ILLEGAL_ARGUMENT
+        );
+        assert_eq!(
+            ExitCode::from(ErrorNumber::NotFound),
+            ExitCode::USR_NOT_FOUND
+        );
+        assert_eq!(
+            ExitCode::from(ErrorNumber::ReadOnly),
+            ExitCode::USR_READ_ONLY
+        );
+        assert_eq!(
+            ExitCode::from(ErrorNumber::BufferTooSmall),
+            ExitCode::USR_UNSPECIFIED
+        );
+    }
+}
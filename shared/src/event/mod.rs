@@ -7,6 +7,10 @@ use serde_tuple::*;
 
 use crate::ActorID;
 
+mod value;
+
+pub use value::{EntryError, EntryValue, EventBuilder, EVENT_TYPE_KEY};
+
 /// Event with extra information stamped by the FVM. This is the structure that gets committed
 /// on-chain via the receipt.
 #[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
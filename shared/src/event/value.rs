@@ -0,0 +1,264 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Typed event entry values.
+//!
+//! Entries carry raw bytes tagged with a codec. By convention, the builtin actors encode typed
+//! values (numbers, token amounts, addresses, CIDs, strings) as DAG-CBOR, while Ethereum logs
+//! store their topics and data as raw bytes (`IPLD_RAW`). These helpers enforce the codec on both
+//! sides, so that emitters and indexers agree on the encoding.
+
+use cid::Cid;
+use fvm_ipld_encoding::DAG_CBOR;
+use thiserror::Error;
+
+use super::{ActorEvent, Entry, Flags};
+use crate::address::Address;
+use crate::bigint::bigint_ser::{BigIntDe, BigIntSer};
+use crate::bigint::BigInt;
+use crate::econ::TokenAmount;
+use crate::IPLD_RAW;
+
+/// The key of the entry holding an event's type, as emitted by the builtin actors.
+pub const EVENT_TYPE_KEY: &str = "$type";
+
+/// A value that can be stored in an event entry, encoded as DAG-CBOR.
+pub trait EntryValue: Sized {
+    /// Encodes the value.
+    fn encode_entry(&self) -> Vec<u8>;
+    /// Decodes a value encoded by [`encode_entry`](Self::encode_entry).
+    fn decode_entry(bytes: &[u8]) -> Result<Self, fvm_ipld_encoding::Error>;
+}
+
+macro_rules! impl_entry_value {
+    ($($t:ty),*) => {
+        $(
+            impl EntryValue for $t {
+                fn encode_entry(&self) -> Vec<u8> {
+                    fvm_ipld_encoding::to_vec(self).expect("failed to encode event value")
+                }
+
+                fn decode_entry(bytes: &[u8]) -> Result<Self, fvm_ipld_encoding::Error> {
+                    fvm_ipld_encoding::from_slice(bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_entry_value!(u64, i64, bool, String, Address, Cid, TokenAmount);
+
+impl EntryValue for BigInt {
+    fn encode_entry(&self) -> Vec<u8> {
+        fvm_ipld_encoding::to_vec(&BigIntSer(self)).expect("failed to encode event value")
+    }
+
+    fn decode_entry(bytes: &[u8]) -> Result<Self, fvm_ipld_encoding::Error> {
+        fvm_ipld_encoding::from_slice::<BigIntDe>(bytes).map(|v| v.0)
+    }
+}
+
+/// An error decoding an event entry.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EntryError {
+    #[error("event has no entry {0:?}")]
+    Missing(String),
+    #[error("entry {key:?} has codec {actual:#x}, expected {expected:#x}")]
+    WrongCodec {
+        key: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("failed to decode entry {key:?}: {reason}")]
+    Decode { key: String, reason: String },
+}
+
+impl Entry {
+    /// Creates an entry holding a DAG-CBOR encoded value.
+    pub fn typed<T: EntryValue>(flags: Flags, key: impl Into<String>, value: &T) -> Self {
+        Self {
+            flags,
+            key: key.into(),
+            codec: DAG_CBOR,
+            value: value.encode_entry(),
+        }
+    }
+
+    /// Creates an entry holding raw bytes (`IPLD_RAW`), like the EVM's log topics and data.
+    pub fn raw(flags: Flags, key: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            flags,
+            key: key.into(),
+            codec: IPLD_RAW,
+            value: value.into(),
+        }
+    }
+
+    /// Decodes the entry's value, which must be DAG-CBOR encoded.
+    pub fn decode<T: EntryValue>(&self) -> Result<T, EntryError> {
+        self.check_codec(DAG_CBOR)?;
+        T::decode_entry(&self.value).map_err(|e| EntryError::Decode {
+            key: self.key.clone(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// Returns the entry's value, which must be raw bytes (`IPLD_RAW`).
+    pub fn raw_value(&self) -> Result<&[u8], EntryError> {
+        self.check_codec(IPLD_RAW)?;
+        Ok(&self.value)
+    }
+
+    fn check_codec(&self, expected: u64) -> Result<(), EntryError> {
+        if self.codec != expected {
+            return Err(EntryError::WrongCodec {
+                key: self.key.clone(),
+                expected,
+                actual: self.codec,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl ActorEvent {
+    /// Returns the first entry with the given key, if any.
+    pub fn entry(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.key == key)
+    }
+
+    /// Decodes the value of the first entry with the given key (see [`Entry::decode`]).
+    pub fn decode_field<T: EntryValue>(&self, key: &str) -> Result<T, EntryError> {
+        self.entry(key)
+            .ok_or_else(|| EntryError::Missing(key.into()))?
+            .decode()
+    }
+
+    /// Decodes the event's type (the value of its [`EVENT_TYPE_KEY`] entry).
+    pub fn event_type(&self) -> Result<String, EntryError> {
+        self.decode_field(EVENT_TYPE_KEY)
+    }
+}
+
+/// Builds events following the builtin actors' conventions: a `$type` entry first, then one
+/// DAG-CBOR entry per field.
+#[derive(Debug, Default)]
+pub struct EventBuilder {
+    entries: Vec<Entry>,
+}
+
+impl EventBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the event's type, indexing both the key and the value.
+    pub fn typ(self, typ: &str) -> Self {
+        self.entry(Entry::typed(
+            Flags::FLAG_INDEXED_ALL,
+            EVENT_TYPE_KEY,
+            &typ.to_owned(),
+        ))
+    }
+
+    /// Adds a field, indexing its key only.
+    pub fn field<T: EntryValue>(self, key: &str, value: &T) -> Self {
+        self.entry(Entry::typed(Flags::FLAG_INDEXED_KEY, key, value))
+    }
+
+    /// Adds a field, indexing both its key and its value.
+    pub fn field_indexed<T: EntryValue>(self, key: &str, value: &T) -> Self {
+        self.entry(Entry::typed(Flags::FLAG_INDEXED_ALL, key, value))
+    }
+
+    /// Adds an arbitrary entry.
+    pub fn entry(mut self, entry: Entry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    pub fn build(self) -> ActorEvent {
+        self.entries.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let cid = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(b"foo"));
+        let big = BigInt::from(-1) << 100;
+        let event = EventBuilder::new()
+            .typ("sector-activated")
+            .field_indexed("miner", &1234u64)
+            .field("delta", &-5i64)
+            .field("power", &big)
+            .field("amount", &TokenAmount::from_atto(42))
+            .field("owner", &Address::new_id(100))
+            .field("unsealed-cid", &cid)
+            .field("verified", &true)
+            .entry(Entry::raw(Flags::FLAG_INDEXED_ALL, "d", b"data".to_vec()))
+            .build();
+
+        assert_eq!(event.entries.len(), 9);
+        assert_eq!(event.entries[0].key, EVENT_TYPE_KEY);
+        assert_eq!(event.entries[0].flags, Flags::FLAG_INDEXED_ALL);
+        assert_eq!(event.entries[2].flags, Flags::FLAG_INDEXED_KEY);
+        assert_eq!(event.event_type().unwrap(), "sector-activated");
+        assert_eq!(event.decode_field::<u64>("miner").unwrap(), 1234);
+        assert_eq!(event.decode_field::<i64>("delta").unwrap(), -5);
+        assert_eq!(event.decode_field::<BigInt>("power").unwrap(), big);
+        assert_eq!(
+            event.decode_field::<TokenAmount>("amount").unwrap(),
+            TokenAmount::from_atto(42)
+        );
+        assert_eq!(
+            event.decode_field::<Address>("owner").unwrap(),
+            Address::new_id(100)
+        );
+        assert_eq!(event.decode_field::<Cid>("unsealed-cid").unwrap(), cid);
+        assert!(event.decode_field::<bool>("verified").unwrap());
+        assert_eq!(event.entry("d").unwrap().raw_value().unwrap(), b"data");
+    }
+
+    #[test]
+    fn errors() {
+        let event = EventBuilder::new()
+            .field("miner", &1234u64)
+            .entry(Entry::raw(Flags::empty(), "d", b"data".to_vec()))
+            .build();
+
+        assert_eq!(
+            event.decode_field::<u64>("missing"),
+            Err(EntryError::Missing("missing".into()))
+        );
+        assert_eq!(
+            event.decode_field::<u64>("d"),
+            Err(EntryError::WrongCodec {
+                key: "d".into(),
+                expected: DAG_CBOR,
+                actual: IPLD_RAW,
+            })
+        );
+        assert_eq!(
+            event.entry("miner").unwrap().raw_value(),
+            Err(EntryError::WrongCodec {
+                key: "miner".into(),
+                expected: IPLD_RAW,
+                actual: DAG_CBOR,
+            })
+        );
+        assert!(matches!(
+            event.decode_field::<String>("miner"),
+            Err(EntryError::Decode { key, .. }) if key == "miner"
+        ));
+        assert!(matches!(
+            event.decode_field::<u64>("$type"),
+            Err(EntryError::Missing(_))
+        ));
+    }
+}
@@ -0,0 +1,599 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Lotus-compatible JSON representations of chain types.
+//!
+//! The serde implementations of the types in this crate produce their on-chain (tuple) CBOR
+//! encodings. This module provides the JSON encodings used by the Lotus RPC API instead:
+//!
+//! - Structs are objects with Lotus's (PascalCase) field names.
+//! - Addresses are strings, using the current network's prefix.
+//! - Token amounts are decimal strings of attoFIL.
+//! - Byte strings are base64-encoded, with empty byte strings encoded as `null`.
+//! - CIDs are links of the form `{"/": "<cid>"}`.
+//!
+//! Wrap a value in [`Json`] to (de)serialize it with this encoding, or annotate a field of any
+//! supported type with `#[serde(with = "fvm_shared::json")]`.
+
+use std::str::FromStr;
+
+use cid::Cid;
+use data_encoding::BASE64;
+use fvm_ipld_encoding::RawBytes;
+use num_bigint::BigInt;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::address::Address;
+use crate::crypto::signature::{Signature, SignatureType};
+use crate::econ::TokenAmount;
+use crate::error::ExitCode;
+use crate::event::{ActorEvent, Entry, Flags, StampedEvent};
+use crate::message::Message;
+use crate::receipt::Receipt;
+use crate::state::ActorState;
+use crate::{ActorID, MethodNum};
+
+/// A type with a Lotus-compatible JSON representation.
+pub trait LotusJson: Sized {
+    fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+    fn deserialize_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// Serializes a value as Lotus-compatible JSON. For use with `#[serde(with = "...")]`.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: LotusJson,
+    S: Serializer,
+{
+    value.serialize_json(serializer)
+}
+
+/// Deserializes a value from Lotus-compatible JSON. For use with `#[serde(with = "...")]`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: LotusJson,
+    D: Deserializer<'de>,
+{
+    T::deserialize_json(deserializer)
+}
+
+/// A wrapper (de)serializing the inner value as Lotus-compatible JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl<T: LotusJson> Serialize for Json<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_json(serializer)
+    }
+}
+
+impl<'de, T: LotusJson> Deserialize<'de> for Json<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_json(deserializer).map(Json)
+    }
+}
+
+impl<T: LotusJson> LotusJson for Option<T> {
+    fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Some(v) => v.serialize_json(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn deserialize_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<Json<T>>::deserialize(deserializer)?.map(|Json(v)| v))
+    }
+}
+
+/// Lists are encoded as arrays, and decoded from arrays or `null` (an empty list in Lotus).
+impl<T: LotusJson> LotusJson for Vec<T> {
+    fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(JsonRef))
+    }
+
+    fn deserialize_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<Vec<Json<T>>>::deserialize(deserializer)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|Json(v)| v)
+            .collect())
+    }
+}
+
+/// Serializes a borrowed value as Lotus-compatible JSON.
+struct JsonRef<'a, T>(&'a T);
+
+impl<T: LotusJson> Serialize for JsonRef<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_json(serializer)
+    }
+}
+
+impl LotusJson for Address {
+    fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+
+    fn deserialize_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Address::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+impl LotusJson for TokenAmount {
+    fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self.atto())
+    }
+
+    fn deserialize_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        BigInt::from_str(&s)
+            .map(TokenAmount::from_atto)
+            .map_err(D::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Link {
+    #[serde(rename = "/")]
+    cid: String,
+}
+
+impl LotusJson for Cid {
+    fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Link {
+            cid: self.to_string(),
+        }
+        .serialize(serializer)
+    }
+
+    fn deserialize_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Link { cid } = Link::deserialize(deserializer)?;
+        Cid::from_str(&cid).map_err(D::Error::custom)
+    }
+}
+
+impl LotusJson for RawBytes {
+    fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        base64::serialize(self.bytes(), serializer)
+    }
+
+    fn deserialize_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        base64::deserialize(deserializer).map(RawBytes::new)
+    }
+}
+
+/// Byte strings, base64-encoded.
+mod base64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if bytes.is_empty() {
+            serializer.serialize_none()
+        } else {
+            serializer.serialize_str(&BASE64.encode(bytes))
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(s) => BASE64.decode(s.as_bytes()).map_err(D::Error::custom),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Defines the [`LotusJson`] implementation of a type through a mirror struct with Lotus's field
+/// names, converting from and into the type.
+macro_rules! lotus_json_via {
+    ($ty:ty, $mirror:ty) => {
+        impl LotusJson for $ty {
+            fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                <$mirror>::from(self).serialize(serializer)
+            }
+
+            fn deserialize_json<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                <$mirror>::deserialize(deserializer).map(Into::into)
+            }
+        }
+    };
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MessageJson {
+    version: u64,
+    #[serde(with = "crate::json")]
+    to: Address,
+    #[serde(with = "crate::json")]
+    from: Address,
+    nonce: u64,
+    #[serde(with = "crate::json")]
+    value: TokenAmount,
+    gas_limit: u64,
+    #[serde(with = "crate::json")]
+    gas_fee_cap: TokenAmount,
+    #[serde(with = "crate::json")]
+    gas_premium: TokenAmount,
+    method: MethodNum,
+    #[serde(with = "crate::json")]
+    params: RawBytes,
+    /// Included for convenience when serializing, like Lotus does, and ignored when deserializing.
+    #[serde(rename = "CID", with = "crate::json", skip_deserializing)]
+    cid: Option<Cid>,
+}
+
+impl From<&Message> for MessageJson {
+    fn from(msg: &Message) -> Self {
+        MessageJson {
+            version: msg.version,
+            to: msg.to,
+            from: msg.from,
+            nonce: msg.sequence,
+            value: msg.value.clone(),
+            gas_limit: msg.gas_limit,
+            gas_fee_cap: msg.gas_fee_cap.clone(),
+            gas_premium: msg.gas_premium.clone(),
+            method: msg.method_num,
+            params: msg.params.clone(),
+            cid: Some(msg.cid()),
+        }
+    }
+}
+
+impl From<MessageJson> for Message {
+    fn from(msg: MessageJson) -> Self {
+        Message {
+            version: msg.version,
+            from: msg.from,
+            to: msg.to,
+            sequence: msg.nonce,
+            value: msg.value,
+            method_num: msg.method,
+            params: msg.params,
+            gas_limit: msg.gas_limit,
+            gas_fee_cap: msg.gas_fee_cap,
+            gas_premium: msg.gas_premium,
+        }
+    }
+}
+
+lotus_json_via!(Message, MessageJson);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ReceiptJson {
+    exit_code: ExitCode,
+    #[serde(with = "crate::json")]
+    r#return: RawBytes,
+    gas_used: u64,
+    #[serde(with = "crate::json", default)]
+    events_root: Option<Cid>,
+}
+
+impl From<&Receipt> for ReceiptJson {
+    fn from(rec: &Receipt) -> Self {
+        ReceiptJson {
+            exit_code: rec.exit_code,
+            r#return: rec.return_data.clone(),
+            gas_used: rec.gas_used,
+            events_root: rec.events_root,
+        }
+    }
+}
+
+impl From<ReceiptJson> for Receipt {
+    fn from(rec: ReceiptJson) -> Self {
+        Receipt {
+            exit_code: rec.exit_code,
+            return_data: rec.r#return,
+            gas_used: rec.gas_used,
+            events_root: rec.events_root,
+        }
+    }
+}
+
+lotus_json_via!(Receipt, ReceiptJson);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SignatureJson {
+    r#type: SignatureType,
+    #[serde(with = "base64")]
+    data: Vec<u8>,
+}
+
+impl From<&Signature> for SignatureJson {
+    fn from(sig: &Signature) -> Self {
+        SignatureJson {
+            r#type: sig.sig_type,
+            data: sig.bytes.clone(),
+        }
+    }
+}
+
+impl From<SignatureJson> for Signature {
+    fn from(sig: SignatureJson) -> Self {
+        Signature {
+            sig_type: sig.r#type,
+            bytes: sig.data,
+        }
+    }
+}
+
+lotus_json_via!(Signature, SignatureJson);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ActorStateJson {
+    #[serde(with = "crate::json")]
+    code: Cid,
+    #[serde(with = "crate::json")]
+    head: Cid,
+    nonce: u64,
+    #[serde(with = "crate::json")]
+    balance: TokenAmount,
+    #[serde(with = "crate::json", default)]
+    delegated_address: Option<Address>,
+}
+
+impl From<&ActorState> for ActorStateJson {
+    fn from(act: &ActorState) -> Self {
+        ActorStateJson {
+            code: act.code,
+            head: act.state,
+            nonce: act.sequence,
+            balance: act.balance.clone(),
+            delegated_address: act.delegated_address,
+        }
+    }
+}
+
+impl From<ActorStateJson> for ActorState {
+    fn from(act: ActorStateJson) -> Self {
+        ActorState {
+            code: act.code,
+            state: act.head,
+            sequence: act.nonce,
+            balance: act.balance,
+            delegated_address: act.delegated_address,
+        }
+    }
+}
+
+lotus_json_via!(ActorState, ActorStateJson);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EntryJson {
+    flags: u64,
+    key: String,
+    codec: u64,
+    #[serde(with = "base64")]
+    value: Vec<u8>,
+}
+
+impl From<&Entry> for EntryJson {
+    fn from(entry: &Entry) -> Self {
+        EntryJson {
+            flags: entry.flags.bits(),
+            key: entry.key.clone(),
+            codec: entry.codec,
+            value: entry.value.clone(),
+        }
+    }
+}
+
+impl From<EntryJson> for Entry {
+    fn from(entry: EntryJson) -> Self {
+        Entry {
+            flags: Flags::from_bits_retain(entry.flags),
+            key: entry.key,
+            codec: entry.codec,
+            value: entry.value,
+        }
+    }
+}
+
+lotus_json_via!(Entry, EntryJson);
+
+/// An actor event is encoded as the list of its entries.
+impl LotusJson for ActorEvent {
+    fn serialize_json<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.entries.serialize_json(serializer)
+    }
+
+    fn deserialize_json<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Entry>::deserialize_json(deserializer).map(ActorEvent::from)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StampedEventJson {
+    emitter: ActorID,
+    #[serde(with = "crate::json")]
+    entries: ActorEvent,
+}
+
+impl From<&StampedEvent> for StampedEventJson {
+    fn from(evt: &StampedEvent) -> Self {
+        StampedEventJson {
+            emitter: evt.emitter,
+            entries: evt.event.clone(),
+        }
+    }
+}
+
+impl From<StampedEventJson> for StampedEvent {
+    fn from(evt: StampedEventJson) -> Self {
+        StampedEvent::new(evt.emitter, evt.entries)
+    }
+}
+
+lotus_json_via!(StampedEvent, StampedEventJson);
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::{to_vec, DAG_CBOR};
+    use serde_json::json;
+
+    use super::*;
+
+    fn cid(data: &[u8]) -> Cid {
+        use multihash_codetable::{Code, MultihashDigest};
+        Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(data))
+    }
+
+    fn check<T: LotusJson + PartialEq + std::fmt::Debug + Clone>(
+        value: T,
+        expected: serde_json::Value,
+    ) {
+        let encoded = serde_json::to_value(Json(value.clone())).unwrap();
+        assert_eq!(encoded, expected);
+        let Json(decoded) = serde_json::from_value::<Json<T>>(encoded).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn message() {
+        let msg = Message {
+            version: 0,
+            from: Address::new_id(1001),
+            to: Address::new_id(1000),
+            sequence: 7,
+            value: TokenAmount::from_atto(1234),
+            method_num: 2,
+            params: RawBytes::new(vec![0x82, 0x01, 0x02]),
+            gas_limit: 10_000_000,
+            gas_fee_cap: TokenAmount::from_atto(200),
+            gas_premium: TokenAmount::from_atto(100),
+        };
+        let msg_cid = MessageJson::from(&msg).cid.unwrap();
+        assert_eq!(
+            msg_cid,
+            Cid::new_v1(
+                DAG_CBOR,
+                multihash_codetable::MultihashDigest::digest(
+                    &multihash_codetable::Code::Blake2b256,
+                    &to_vec(&msg).unwrap()
+                )
+            )
+        );
+        check(
+            msg,
+            json!({
+                "Version": 0,
+                "To": "f01000",
+                "From": "f01001",
+                "Nonce": 7,
+                "Value": "1234",
+                "GasLimit": 10000000,
+                "GasFeeCap": "200",
+                "GasPremium": "100",
+                "Method": 2,
+                "Params": "ggEC",
+                "CID": {"/": msg_cid.to_string()},
+            }),
+        );
+
+        // The CID is optional, and params may be null.
+        let Json(msg) = serde_json::from_value::<Json<Message>>(json!({
+            "Version": 0,
+            "To": "f01000",
+            "From": "f01001",
+            "Nonce": 0,
+            "Value": "0",
+            "GasLimit": 0,
+            "GasFeeCap": "0",
+            "GasPremium": "0",
+            "Method": 0,
+            "Params": null,
+        }))
+        .unwrap();
+        assert!(msg.params.is_empty());
+    }
+
+    #[test]
+    fn receipt() {
+        let root = cid(b"events");
+        check(
+            Receipt {
+                exit_code: ExitCode::USR_ILLEGAL_ARGUMENT,
+                return_data: RawBytes::new(vec![1, 2, 3]),
+                gas_used: 42,
+                events_root: Some(root),
+            },
+            json!({
+                "ExitCode": 16,
+                "Return": "AQID",
+                "GasUsed": 42,
+                "EventsRoot": {"/": root.to_string()},
+            }),
+        );
+        check(
+            Receipt {
+                exit_code: ExitCode::OK,
+                return_data: RawBytes::default(),
+                gas_used: 0,
+                events_root: None,
+            },
+            json!({"ExitCode": 0, "Return": null, "GasUsed": 0, "EventsRoot": null}),
+        );
+    }
+
+    #[test]
+    fn signature() {
+        check(
+            Signature {
+                sig_type: SignatureType::BLS,
+                bytes: vec![0xff; 3],
+            },
+            json!({"Type": 2, "Data": "////"}),
+        );
+    }
+
+    #[test]
+    fn actor_state() {
+        let f4 = Address::new_delegated(10, &[0xab; 20]).unwrap();
+        check(
+            ActorState::new(
+                cid(b"code"),
+                cid(b"state"),
+                TokenAmount::from_atto(5),
+                3,
+                Some(f4),
+            ),
+            json!({
+                "Code": {"/": cid(b"code").to_string()},
+                "Head": {"/": cid(b"state").to_string()},
+                "Nonce": 3,
+                "Balance": "5",
+                "DelegatedAddress": f4.to_string(),
+            }),
+        );
+    }
+
+    #[test]
+    fn event() {
+        check(
+            StampedEvent::new(
+                1000,
+                ActorEvent::from(vec![Entry {
+                    flags: Flags::FLAG_INDEXED_ALL,
+                    key: "t1".into(),
+                    codec: crate::IPLD_RAW,
+                    value: vec![0xde, 0xad],
+                }]),
+            ),
+            json!({
+                "Emitter": 1000,
+                "Entries": [{"Flags": 3, "Key": "t1", "Codec": 85, "Value": "3q0="}],
+            }),
+        );
+    }
+}
@@ -22,6 +22,8 @@ pub mod deal;
 pub mod econ;
 pub mod error;
 pub mod event;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod math;
 pub mod message;
 pub mod piece;
@@ -34,6 +36,7 @@ pub mod state;
 pub mod sys;
 pub mod upgrade;
 pub mod version;
+pub mod well_known;
 
 use cid::multihash::Multihash;
 use crypto::hash::SupportedHashes;
@@ -2,10 +2,71 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::bigint::{BigInt, ParseBigIntError};
+//! Fixed-point math in Q.128 format (a `BigInt` scaled by 2^128), as used by the reward and power
+//! calculations. Actors and client-side estimators should use these helpers rather than shifting
+//! by hand, so they round identically.
+//!
+//! All operations round down (towards negative infinity).
+
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::bigint::{BigInt, Integer, ParseBigIntError};
 
 pub const PRECISION: u64 = 128;
 
+/// Converts an integer to Q.128.
+pub fn q128_from_int(x: impl Into<BigInt>) -> BigInt {
+    x.into() << PRECISION
+}
+
+/// Converts a Q.128 number to an integer, rounding down.
+pub fn q128_to_int(x: &BigInt) -> BigInt {
+    x >> PRECISION
+}
+
+/// Converts a Q.128 number to a `u64`, rounding down, or returns `None` if the result is negative
+/// or doesn't fit.
+pub fn q128_checked_to_u64(x: &BigInt) -> Option<u64> {
+    q128_to_int(x).to_u64()
+}
+
+/// Converts a Q.128 number to a `u64`, rounding down and clamping the result to `0..=u64::MAX`.
+pub fn q128_saturating_to_u64(x: &BigInt) -> u64 {
+    if x.is_negative() {
+        0
+    } else {
+        q128_checked_to_u64(x).unwrap_or(u64::MAX)
+    }
+}
+
+/// Multiplies two Q.128 numbers, rounding down.
+pub fn q128_mul(a: &BigInt, b: &BigInt) -> BigInt {
+    (a * b) >> PRECISION
+}
+
+/// Divides two Q.128 numbers, rounding down.
+///
+/// # Panics
+///
+/// Panics if `b` is zero. See [`q128_checked_div`].
+pub fn q128_div(a: &BigInt, b: &BigInt) -> BigInt {
+    (a << PRECISION).div_floor(b)
+}
+
+/// Divides two Q.128 numbers, rounding down, or returns `None` if `b` is zero.
+pub fn q128_checked_div(a: &BigInt, b: &BigInt) -> Option<BigInt> {
+    (!b.is_zero()).then(|| q128_div(a, b))
+}
+
+/// Subtracts two Q.128 (or integer) numbers, clamping the result at zero.
+pub fn q128_saturating_sub(a: &BigInt, b: &BigInt) -> BigInt {
+    if a > b {
+        a - b
+    } else {
+        BigInt::zero()
+    }
+}
+
 /// polyval evaluates a polynomial given by coefficients `p` in Q.128 format
 /// at point `x` in Q.128 format. Output is in Q.128.
 /// Coefficients should be ordered from the highest order coefficient to the lowest.
@@ -13,7 +74,7 @@ pub fn poly_val(poly: &[BigInt], x: &BigInt) -> BigInt {
     let mut res = BigInt::default();
 
     for coeff in poly {
-        res = ((res * x) >> PRECISION) + coeff;
+        res = q128_mul(&res, x) + coeff;
     }
     res
 }
@@ -21,3 +82,76 @@ pub fn poly_val(poly: &[BigInt], x: &BigInt) -> BigInt {
 pub fn poly_parse(coefs: &[&str]) -> Result<Vec<BigInt>, ParseBigIntError> {
     coefs.iter().map(|c| c.parse()).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(num: i64, den: i64) -> BigInt {
+        q128_from_int(num) / den
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(q128_from_int(1), BigInt::from(1) << PRECISION);
+        assert_eq!(q128_from_int(-3), -(BigInt::from(3) << PRECISION));
+
+        for (x, int) in [
+            (q(5, 2), 2),
+            (q(-5, 2), -3),
+            (q(1, 3), 0),
+            (-q(1, 3), -1),
+            (q128_from_int(7), 7),
+            (BigInt::zero(), 0),
+        ] {
+            assert_eq!(q128_to_int(&x), BigInt::from(int), "{x}");
+        }
+
+        let max = q128_from_int(u64::MAX);
+        assert_eq!(q128_checked_to_u64(&q(7, 2)), Some(3));
+        assert_eq!(q128_checked_to_u64(&max), Some(u64::MAX));
+        assert_eq!(q128_checked_to_u64(&(&max << 1)), None);
+        assert_eq!(q128_checked_to_u64(&-q(1, 2)), None);
+        assert_eq!(q128_saturating_to_u64(&q(7, 2)), 3);
+        assert_eq!(q128_saturating_to_u64(&(&max << 1)), u64::MAX);
+        assert_eq!(q128_saturating_to_u64(&-q(1, 2)), 0);
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(q128_mul(&q(3, 2), &q(5, 2)), q(15, 4));
+        assert_eq!(q128_mul(&q(-3, 2), &q(5, 2)), q(-15, 4));
+        assert_eq!(q128_mul(&q128_from_int(6), &BigInt::zero()), BigInt::zero());
+        // The smallest representable value squared rounds down to zero, or to -1 ulp if negative.
+        let ulp = BigInt::from(1);
+        assert_eq!(q128_mul(&ulp, &ulp), BigInt::zero());
+        assert_eq!(q128_mul(&-&ulp, &ulp), -&ulp);
+
+        assert_eq!(q128_div(&q128_from_int(15), &q128_from_int(4)), q(15, 4));
+        assert_eq!(q128_div(&q128_from_int(1), &q128_from_int(3)), q(1, 3));
+        assert_eq!(
+            q128_div(&q128_from_int(-1), &q128_from_int(3)),
+            q(-1, 3) - 1
+        );
+        assert_eq!(q128_checked_div(&q(1, 2), &q(1, 4)), Some(q128_from_int(2)));
+        assert_eq!(q128_checked_div(&q(1, 2), &BigInt::zero()), None);
+
+        assert_eq!(q128_saturating_sub(&q(5, 2), &q(1, 2)), q128_from_int(2));
+        assert_eq!(q128_saturating_sub(&q(1, 2), &q(5, 2)), BigInt::zero());
+        assert_eq!(q128_saturating_sub(&q(1, 2), &q(1, 2)), BigInt::zero());
+    }
+
+    #[test]
+    fn polynomial() {
+        // 2x^2 - 3x + 1/2 at x = 3/2.
+        let poly = [q128_from_int(2), q128_from_int(-3), q(1, 2)];
+        assert_eq!(poly_val(&poly, &q(3, 2)), q(1, 2));
+        assert_eq!(poly_val(&poly, &BigInt::zero()), q(1, 2));
+        assert_eq!(poly_val(&[], &q(3, 2)), BigInt::zero());
+        assert_eq!(
+            poly_parse(&["1", "-2"]).unwrap(),
+            [BigInt::from(1), BigInt::from(-2)]
+        );
+        assert!(poly_parse(&["x"]).is_err());
+    }
+}
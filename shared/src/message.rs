@@ -3,11 +3,16 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use anyhow::anyhow;
+use cid::multihash::Multihash;
+use cid::Cid;
 use fvm_ipld_encoding::de::{Deserialize, Deserializer};
 use fvm_ipld_encoding::ser::{Serialize, Serializer};
-use fvm_ipld_encoding::RawBytes;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{to_vec, RawBytes, DAG_CBOR};
 
 use crate::address::Address;
+use crate::crypto::hash::SupportedHashes;
+use crate::crypto::signature::{Signature, SignatureType};
 use crate::econ::TokenAmount;
 use crate::MethodNum;
 
@@ -39,6 +44,42 @@ impl Message {
         }
         Ok(())
     }
+
+    /// Returns the message's CID: the blake2b-256 hash of its DAG-CBOR encoding. This is how the
+    /// chain identifies the message (and what its sender signs).
+    pub fn cid(&self) -> Cid {
+        dag_cbor_cid(self)
+    }
+}
+
+/// A message along with its sender's signature, as included in blocks.
+#[derive(PartialEq, Clone, Debug, Hash, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct SignedMessage {
+    pub message: Message,
+    pub signature: Signature,
+}
+
+impl SignedMessage {
+    /// Returns the signed message's CID.
+    ///
+    /// BLS-signed messages are identified by the CID of the unsigned [`Message`], because blocks
+    /// include them without their signatures (aggregating them instead). All other signed messages
+    /// are identified by the blake2b-256 hash of the DAG-CBOR encoding of the signed message.
+    pub fn cid(&self) -> Cid {
+        match self.signature.sig_type {
+            SignatureType::BLS => self.message.cid(),
+            _ => dag_cbor_cid(self),
+        }
+    }
+}
+
+/// Returns the CIDv1 of the DAG-CBOR encoding of `value`, hashed with blake2b-256.
+fn dag_cbor_cid<T: Serialize>(value: &T) -> Cid {
+    let data = to_vec(value).expect("failed to encode message");
+    let digest = blake2b_simd::Params::new().hash_length(32).hash(&data);
+    let mh = Multihash::wrap(SupportedHashes::Blake2b256 as u64, digest.as_bytes())
+        .expect("a 32 byte digest fits in a multihash");
+    Cid::new_v1(DAG_CBOR, mh)
 }
 
 impl Serialize for Message {
@@ -11,6 +11,8 @@ use serde_tuple::*;
 #[cfg(feature = "proofs")]
 pub use zero::zero_piece_commitment;
 
+use crate::sector::SectorSize;
+
 /// Size of a piece in bytes.
 #[derive(PartialEq, Debug, Eq, Clone, Copy)]
 pub struct UnpaddedPieceSize(pub u64);
@@ -21,6 +23,21 @@ impl UnpaddedPieceSize {
         PaddedPieceSize(self.0 + (self.0 / 127))
     }
 
+    /// Converts unpadded piece size into padded piece size, returning `None` on overflow.
+    pub fn checked_padded(self) -> Option<PaddedPieceSize> {
+        self.0.checked_add(self.0 / 127).map(PaddedPieceSize)
+    }
+
+    /// Returns the smallest valid unpadded piece size which can hold `bytes` bytes of data, or
+    /// `None` if there is no such piece size.
+    pub fn for_payload(bytes: u64) -> Option<Self> {
+        let mut padded = PaddedPieceSize(128);
+        while padded.unpadded().0 < bytes {
+            padded = PaddedPieceSize(padded.0.checked_mul(2)?);
+        }
+        Some(padded.unpadded())
+    }
+
     /// Validates piece size.
     pub fn validate(self) -> Result<(), &'static str> {
         if self.0 < 127 {
@@ -47,6 +64,11 @@ impl PaddedPieceSize {
         UnpaddedPieceSize(self.0 - (self.0 / 128))
     }
 
+    /// Adds two padded piece sizes, returning `None` on overflow.
+    pub fn checked_add(self, other: PaddedPieceSize) -> Option<PaddedPieceSize> {
+        self.0.checked_add(other.0).map(PaddedPieceSize)
+    }
+
     /// Validates piece size.
     pub fn validate(self) -> Result<(), &'static str> {
         if self.0 < 128 {
@@ -61,6 +83,69 @@ impl PaddedPieceSize {
     }
 }
 
+/// Sums padded piece sizes, returning `None` on overflow.
+pub fn sum_piece_sizes<I>(sizes: I) -> Option<PaddedPieceSize>
+where
+    I: IntoIterator<Item = PaddedPieceSize>,
+{
+    sizes
+        .into_iter()
+        .try_fold(PaddedPieceSize(0), PaddedPieceSize::checked_add)
+}
+
+/// Validates each of the padded piece sizes, and checks that together they fit into a sector of
+/// the given size. Returns the total size of the pieces.
+pub fn check_pieces_fit<I>(
+    sizes: I,
+    sector_size: SectorSize,
+) -> Result<PaddedPieceSize, &'static str>
+where
+    I: IntoIterator<Item = PaddedPieceSize>,
+{
+    let mut total = PaddedPieceSize(0);
+    for size in sizes {
+        size.validate()?;
+        total = total
+            .checked_add(size)
+            .ok_or("total piece size overflows")?;
+    }
+    if total.0 > sector_size as u64 {
+        return Err("pieces don't fit into the sector");
+    }
+    Ok(total)
+}
+
+/// Returns the padding pieces required to align a new piece of `new_piece_length` placed after
+/// `old_length` bytes of pieces, along with their total size.
+///
+/// Pieces must be aligned to their own size within a sector, so the gap is filled with the fewest
+/// power of two padding pieces, smallest first. A zero-sized piece has no alignment requirement and
+/// needs no padding.
+pub fn required_padding(
+    old_length: PaddedPieceSize,
+    new_piece_length: PaddedPieceSize,
+) -> (Vec<PaddedPieceSize>, PaddedPieceSize) {
+    let mut sum = 0;
+
+    let mut to_fill = 0u64
+        .wrapping_sub(old_length.0)
+        .checked_rem(new_piece_length.0)
+        .unwrap_or(0);
+    let n = to_fill.count_ones();
+    let mut pad_pieces = Vec::with_capacity(n as usize);
+    for _ in 0..n {
+        let next = to_fill.trailing_zeros();
+        let p_size = 1 << next;
+        to_fill ^= p_size;
+
+        let padded = PaddedPieceSize(p_size);
+        pad_pieces.push(padded);
+        sum += padded.0;
+    }
+
+    (pad_pieces, PaddedPieceSize(sum))
+}
+
 /// Piece information for part or a whole file.
 #[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
 pub struct PieceInfo {
@@ -75,7 +160,7 @@ use std::convert::TryFrom;
 
 #[cfg(feature = "proofs")]
 impl TryFrom<&PieceInfo> for filecoin_proofs_api::PieceInfo {
-    type Error = &'static str;
+    type Error = crate::commcid::Error;
 
     fn try_from(p: &PieceInfo) -> Result<Self, Self::Error> {
         Ok(Self {
@@ -119,4 +204,111 @@ mod tests {
             Err("unpadded piece size must be a power of 2 multiple of 127")
         );
     }
+
+    #[test]
+    fn checked_arithmetic() {
+        assert_eq!(
+            UnpaddedPieceSize(127).checked_padded(),
+            Some(PaddedPieceSize(128))
+        );
+        assert_eq!(UnpaddedPieceSize(u64::MAX).checked_padded(), None);
+        assert_eq!(
+            PaddedPieceSize(128).checked_add(PaddedPieceSize(256)),
+            Some(PaddedPieceSize(384))
+        );
+        assert_eq!(
+            PaddedPieceSize(u64::MAX).checked_add(PaddedPieceSize(1)),
+            None
+        );
+        assert_eq!(sum_piece_sizes([]), Some(PaddedPieceSize(0)));
+        assert_eq!(
+            sum_piece_sizes([PaddedPieceSize(128), PaddedPieceSize(1024)]),
+            Some(PaddedPieceSize(1152))
+        );
+        assert_eq!(
+            sum_piece_sizes([PaddedPieceSize(1 << 63), PaddedPieceSize(1 << 63)]),
+            None
+        );
+    }
+
+    #[test]
+    fn piece_size_for_payload() {
+        assert_eq!(
+            UnpaddedPieceSize::for_payload(0),
+            Some(UnpaddedPieceSize(127))
+        );
+        assert_eq!(
+            UnpaddedPieceSize::for_payload(127),
+            Some(UnpaddedPieceSize(127))
+        );
+        assert_eq!(
+            UnpaddedPieceSize::for_payload(128),
+            Some(UnpaddedPieceSize(254))
+        );
+        assert_eq!(
+            UnpaddedPieceSize::for_payload(1 << 20),
+            Some(UnpaddedPieceSize(127 << 14))
+        );
+        for bytes in [1, 500, 1 << 30, 1 << 40] {
+            let size = UnpaddedPieceSize::for_payload(bytes).unwrap();
+            size.validate().unwrap();
+            assert!(size.0 >= bytes);
+            assert!(size.0 / 2 < bytes || size.0 == 127);
+        }
+        assert_eq!(UnpaddedPieceSize::for_payload(u64::MAX), None);
+    }
+
+    #[test]
+    fn pieces_fit() {
+        let sizes = [PaddedPieceSize(1024), PaddedPieceSize(1024)];
+        assert_eq!(
+            check_pieces_fit(sizes, SectorSize::_2KiB),
+            Ok(PaddedPieceSize(2048))
+        );
+        let sizes = [PaddedPieceSize(2048), PaddedPieceSize(128)];
+        assert_eq!(
+            check_pieces_fit(sizes, SectorSize::_2KiB),
+            Err("pieces don't fit into the sector")
+        );
+        let sizes = [PaddedPieceSize(1000)];
+        assert_eq!(
+            check_pieces_fit(sizes, SectorSize::_2KiB),
+            Err("padded piece size must be a power of 2")
+        );
+        let sizes = [PaddedPieceSize(1 << 63), PaddedPieceSize(1 << 63)];
+        assert_eq!(
+            check_pieces_fit(sizes, SectorSize::_64GiB),
+            Err("total piece size overflows")
+        );
+    }
+
+    #[test]
+    fn padding() {
+        // Nothing to pad at the start of a sector, or when already aligned.
+        assert_eq!(
+            required_padding(PaddedPieceSize(0), PaddedPieceSize(1024)),
+            (vec![], PaddedPieceSize(0))
+        );
+        assert_eq!(
+            required_padding(PaddedPieceSize(1024), PaddedPieceSize(512)),
+            (vec![], PaddedPieceSize(0))
+        );
+        // 128 bytes in, a 1024 byte piece needs 128 + 256 + 512 bytes of padding.
+        assert_eq!(
+            required_padding(PaddedPieceSize(128), PaddedPieceSize(1024)),
+            (
+                vec![
+                    PaddedPieceSize(128),
+                    PaddedPieceSize(256),
+                    PaddedPieceSize(512)
+                ],
+                PaddedPieceSize(896)
+            )
+        );
+        // A zero-sized piece doesn't need to be aligned.
+        assert_eq!(
+            required_padding(PaddedPieceSize(128), PaddedPieceSize(0)),
+            (vec![], PaddedPieceSize(0))
+        );
+    }
 }
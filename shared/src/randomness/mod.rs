@@ -3,8 +3,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use fvm_ipld_encoding::{BytesDe, BytesSer};
+use num_derive::FromPrimitive;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::clock::ChainEpoch;
+
 // TODO: turn this back into a 32byte array once we no longer need go compat. It's a vec so that the
 // errors match.
 /// String of random bytes usually generated from a randomness beacon or from tickets on chain.
@@ -31,3 +34,120 @@ impl<'de> Deserialize<'de> for Randomness {
         Ok(Self(bytes.0))
     }
 }
+
+/// Specifies a domain for randomness generation, to make sure that randomness drawn for different
+/// purposes from the same base randomness is unrelated.
+#[derive(PartialEq, Eq, Copy, Clone, FromPrimitive, Debug, Hash)]
+#[repr(i64)]
+pub enum DomainSeparationTag {
+    TicketProduction = 1,
+    ElectionProofProduction = 2,
+    WinningPoStChallengeSeed = 3,
+    WindowedPoStChallengeSeed = 4,
+    SealRandomness = 5,
+    InteractiveSealChallengeSeed = 6,
+    WindowPoStDeadlineAssignment = 7,
+    MarketDealCronSeed = 8,
+    PoStChainCommit = 9,
+    EvmPrevRandao = 10,
+}
+
+/// Draws randomness from the base randomness `rbase` (as returned by the FVM's randomness
+/// syscalls), for the purpose `pers`, mixing in the epoch `round` and some `entropy`, hashing with
+/// blake2b-256.
+pub fn draw_randomness(
+    rbase: &[u8; RANDOMNESS_LENGTH],
+    pers: DomainSeparationTag,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> [u8; RANDOMNESS_LENGTH] {
+    draw_randomness_with(
+        |data| {
+            blake2b_simd::Params::new()
+                .hash_length(RANDOMNESS_LENGTH)
+                .to_state()
+                .update(data)
+                .finalize()
+                .as_bytes()
+                .try_into()
+                .expect("blake2b-256 digests are 32 bytes")
+        },
+        rbase,
+        pers,
+        round,
+        entropy,
+    )
+}
+
+/// Like [`draw_randomness`], but hashes with the supplied blake2b-256 implementation (e.g., the
+/// hashing syscall inside actors).
+pub fn draw_randomness_with(
+    hasher: impl FnOnce(&[u8]) -> [u8; RANDOMNESS_LENGTH],
+    rbase: &[u8; RANDOMNESS_LENGTH],
+    pers: DomainSeparationTag,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> [u8; RANDOMNESS_LENGTH] {
+    let mut data = Vec::with_capacity(8 + RANDOMNESS_LENGTH + 8 + entropy.len());
+    data.extend_from_slice(&(pers as i64).to_be_bytes());
+    data.extend_from_slice(rbase);
+    data.extend_from_slice(&round.to_be_bytes());
+    data.extend_from_slice(entropy);
+    hasher(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw() {
+        let rbase = [7u8; RANDOMNESS_LENGTH];
+        let r = draw_randomness(&rbase, DomainSeparationTag::SealRandomness, 100, b"entropy");
+
+        // The input is laid out as: tag, base randomness, round, entropy.
+        let mut expected_input = vec![0, 0, 0, 0, 0, 0, 0, 5];
+        expected_input.extend_from_slice(&rbase);
+        expected_input.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 100]);
+        expected_input.extend_from_slice(b"entropy");
+        let r_with = draw_randomness_with(
+            |data| {
+                assert_eq!(data, expected_input);
+                r
+            },
+            &rbase,
+            DomainSeparationTag::SealRandomness,
+            100,
+            b"entropy",
+        );
+        assert_eq!(r, r_with);
+
+        // Every input affects the output.
+        assert_ne!(
+            r,
+            draw_randomness(
+                &rbase,
+                DomainSeparationTag::TicketProduction,
+                100,
+                b"entropy"
+            )
+        );
+        assert_ne!(
+            r,
+            draw_randomness(&rbase, DomainSeparationTag::SealRandomness, 101, b"entropy")
+        );
+        assert_ne!(
+            r,
+            draw_randomness(&rbase, DomainSeparationTag::SealRandomness, 100, b"")
+        );
+        assert_ne!(
+            r,
+            draw_randomness(
+                &[8u8; 32],
+                DomainSeparationTag::SealRandomness,
+                100,
+                b"entropy"
+            )
+        );
+    }
+}
@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use serde_tuple::*;
 
+use crate::address::Address;
 use crate::bigint::bigint_ser;
+use crate::econ::TokenAmount;
 use crate::sector::StoragePower;
 use crate::smooth::FilterEstimate;
 
@@ -13,3 +15,17 @@ pub struct ThisEpochRewardReturn {
     #[serde(with = "bigint_ser")]
     pub this_epoch_baseline_power: StoragePower,
 }
+
+/// Parameters of the reward actor's `AwardBlockReward` method, invoked by the system actor once
+/// per block to pay the block's miner.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct AwardBlockRewardParams {
+    /// The miner that produced the block.
+    pub miner: Address,
+    /// The penalty to charge the miner (e.g., for including invalid messages).
+    pub penalty: TokenAmount,
+    /// The sum of the gas premiums of the messages included in the block.
+    pub gas_reward: TokenAmount,
+    /// The number of winning tickets the block's election proof carries.
+    pub win_count: i64,
+}
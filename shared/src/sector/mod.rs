@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 pub mod post;
+mod proof_registry;
 mod registered_proof;
 mod seal;
 
@@ -14,6 +15,7 @@ use num_bigint::BigInt;
 use num_derive::FromPrimitive;
 
 pub use self::post::*;
+pub use self::proof_registry::*;
 pub use self::registered_proof::*;
 pub use self::seal::*;
 use crate::ActorID;
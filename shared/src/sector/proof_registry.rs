@@ -0,0 +1,298 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! A registry of the supported proof types, their sector sizes, their corresponding PoSt proof
+//! types, and the network versions in which they may be used.
+
+use super::{RegisteredPoStProof, RegisteredSealProof, SectorSize};
+use crate::version::NetworkVersion;
+
+/// The kind of a proof of spacetime.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum PoStKind {
+    /// Proves storage when mining a block.
+    Winning,
+    /// Proves storage once per proving period.
+    Window,
+}
+
+/// The network versions in which a proof type may be used for new proofs.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct ProofAvailability {
+    /// The first network version in which the proof type may be used.
+    pub since: NetworkVersion,
+    /// The first network version in which the proof type may no longer be used, if any.
+    pub until: Option<NetworkVersion>,
+}
+
+impl ProofAvailability {
+    const fn since(since: NetworkVersion) -> Self {
+        ProofAvailability { since, until: None }
+    }
+
+    /// Returns true if the proof type may be used at the given network version.
+    pub fn contains(&self, nv: NetworkVersion) -> bool {
+        nv >= self.since && !matches!(self.until, Some(until) if nv >= until)
+    }
+}
+
+/// The registry entry of a seal proof type. The proof type's sector size and PoSt proof types
+/// are those of its [`RegisteredSealProof`] tables.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct SealProofInfo {
+    pub proof: RegisteredSealProof,
+    /// When new sectors may be sealed with this proof type.
+    pub availability: ProofAvailability,
+}
+
+impl SealProofInfo {
+    /// Returns the size of the sectors sealed with this proof type.
+    pub fn sector_size(&self) -> SectorSize {
+        self.proof.sector_size().expect("registered seal proof")
+    }
+
+    /// Returns the winning PoSt proof type for sectors sealed with this proof type.
+    pub fn winning_post_proof(&self) -> RegisteredPoStProof {
+        self.proof
+            .registered_winning_post_proof()
+            .expect("registered seal proof")
+    }
+
+    /// Returns the window PoSt proof type for sectors sealed with this proof type.
+    pub fn window_post_proof(&self) -> RegisteredPoStProof {
+        self.proof
+            .registered_window_post_proof()
+            .expect("registered seal proof")
+    }
+}
+
+/// The registry entry of a PoSt proof type.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct PoStProofInfo {
+    pub proof: RegisteredPoStProof,
+    pub kind: PoStKind,
+    /// When proofs of this type may be submitted.
+    pub availability: ProofAvailability,
+}
+
+impl PoStProofInfo {
+    /// Returns the size of the sectors proven with this proof type.
+    pub fn sector_size(&self) -> SectorSize {
+        self.proof.sector_size().expect("registered PoSt proof")
+    }
+}
+
+macro_rules! seal_proofs {
+    ($($availability:expr => [$($proof:ident),*];)*) => {
+        /// All the registered seal proof types.
+        pub const SEAL_PROOFS: &[SealProofInfo] = &[$($(SealProofInfo {
+            proof: RegisteredSealProof::$proof,
+            availability: $availability,
+        },)*)*];
+    };
+}
+
+macro_rules! post_proofs {
+    ($($kind:ident, $availability:expr => [$($proof:ident),*];)*) => {
+        /// All the registered PoSt proof types.
+        pub const POST_PROOFS: &[PoStProofInfo] = &[$($(PoStProofInfo {
+            proof: RegisteredPoStProof::$proof,
+            kind: PoStKind::$kind,
+            availability: $availability,
+        },)*)*];
+    };
+}
+
+/// The original seal proofs, replaced by the V1P1 proofs in network version 7.
+const SEAL_V1: ProofAvailability = ProofAvailability {
+    since: NetworkVersion::V0,
+    until: Some(NetworkVersion::V7),
+};
+const SEAL_V1P1: ProofAvailability = ProofAvailability::since(NetworkVersion::V7);
+/// Synthetic PoRep (FIP-0059).
+const SEAL_SYNTHETIC: ProofAvailability = ProofAvailability::since(NetworkVersion::V21);
+/// Non-interactive PoRep (FIP-0090).
+const SEAL_NI: ProofAvailability = ProofAvailability::since(NetworkVersion::V23);
+const WINNING_POST: ProofAvailability = ProofAvailability::since(NetworkVersion::V0);
+/// The V1P1 window PoSt proofs (FIP-0061).
+const WINDOW_POST_V1P1: ProofAvailability = ProofAvailability::since(NetworkVersion::V19);
+
+seal_proofs! {
+    SEAL_V1 => [
+        StackedDRG2KiBV1,
+        StackedDRG8MiBV1,
+        StackedDRG512MiBV1,
+        StackedDRG32GiBV1,
+        StackedDRG64GiBV1
+    ];
+    SEAL_V1P1 => [
+        StackedDRG2KiBV1P1,
+        StackedDRG8MiBV1P1,
+        StackedDRG512MiBV1P1,
+        StackedDRG32GiBV1P1,
+        StackedDRG64GiBV1P1
+    ];
+    SEAL_SYNTHETIC => [
+        StackedDRG2KiBV1P1_Feat_SyntheticPoRep,
+        StackedDRG8MiBV1P1_Feat_SyntheticPoRep,
+        StackedDRG512MiBV1P1_Feat_SyntheticPoRep,
+        StackedDRG32GiBV1P1_Feat_SyntheticPoRep,
+        StackedDRG64GiBV1P1_Feat_SyntheticPoRep
+    ];
+    SEAL_NI => [
+        StackedDRG2KiBV1P2_Feat_NiPoRep,
+        StackedDRG8MiBV1P2_Feat_NiPoRep,
+        StackedDRG512MiBV1P2_Feat_NiPoRep,
+        StackedDRG32GiBV1P2_Feat_NiPoRep,
+        StackedDRG64GiBV1P2_Feat_NiPoRep
+    ];
+}
+
+post_proofs! {
+    Winning, WINNING_POST => [
+        StackedDRGWinning2KiBV1,
+        StackedDRGWinning8MiBV1,
+        StackedDRGWinning512MiBV1,
+        StackedDRGWinning32GiBV1,
+        StackedDRGWinning64GiBV1
+    ];
+    Window, WINDOW_POST_V1P1 => [
+        StackedDRGWindow2KiBV1P1,
+        StackedDRGWindow8MiBV1P1,
+        StackedDRGWindow512MiBV1P1,
+        StackedDRGWindow32GiBV1P1,
+        StackedDRGWindow64GiBV1P1
+    ];
+}
+
+/// A proof type listed in the registry.
+pub trait RegisteredProof: Copy {
+    /// Returns when the proof type may be used, or `None` if it's not a registered proof type.
+    fn availability(self) -> Option<ProofAvailability>;
+}
+
+impl RegisteredProof for RegisteredSealProof {
+    fn availability(self) -> Option<ProofAvailability> {
+        self.info().map(|i| i.availability)
+    }
+}
+
+impl RegisteredProof for RegisteredPoStProof {
+    fn availability(self) -> Option<ProofAvailability> {
+        self.info().map(|i| i.availability)
+    }
+}
+
+/// Returns true if the proof type is registered, and may be used at the given network version.
+pub fn is_allowed(nv: NetworkVersion, proof: impl RegisteredProof) -> bool {
+    proof.availability().is_some_and(|a| a.contains(nv))
+}
+
+impl RegisteredSealProof {
+    /// Returns the registry entry of the proof type, or `None` if it's not a registered proof type.
+    pub fn info(self) -> Option<&'static SealProofInfo> {
+        SEAL_PROOFS.iter().find(|i| i.proof == self)
+    }
+
+    /// Returns true if the given PoSt proof type may be used to prove sectors sealed with this
+    /// proof type.
+    pub fn is_compatible_post(self, post: RegisteredPoStProof) -> bool {
+        self.info()
+            .is_some_and(|i| i.winning_post_proof() == post || i.window_post_proof() == post)
+    }
+}
+
+impl RegisteredPoStProof {
+    /// Returns the registry entry of the proof type, or `None` if it's not a registered proof type.
+    pub fn info(self) -> Option<&'static PoStProofInfo> {
+        POST_PROOFS.iter().find(|i| i.proof == self)
+    }
+
+    /// Returns whether this is a winning or window PoSt proof type.
+    pub fn kind(self) -> Option<PoStKind> {
+        self.info().map(|i| i.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_matches_proof_types() {
+        for info in SEAL_PROOFS {
+            let p = info.proof;
+            assert_eq!(p.info(), Some(info));
+            let (winning, window) = (info.winning_post_proof(), info.window_post_proof());
+            assert_eq!(winning.kind(), Some(PoStKind::Winning), "{p:?}");
+            assert_eq!(window.kind(), Some(PoStKind::Window), "{p:?}");
+            assert_eq!(winning.sector_size(), Ok(info.sector_size()), "{p:?}");
+            assert_eq!(window.sector_size(), Ok(info.sector_size()), "{p:?}");
+            assert!(p.is_compatible_post(winning));
+            assert!(p.is_compatible_post(window));
+        }
+        for info in POST_PROOFS {
+            assert_eq!(info.proof.info(), Some(info));
+        }
+
+        // Every valid i64 value is registered.
+        for i in 0..20 {
+            assert!(RegisteredSealProof::from(i).info().is_some(), "{i}");
+        }
+        for i in (0..5).chain(10..15) {
+            assert!(RegisteredPoStProof::from(i).info().is_some(), "{i}");
+        }
+        assert_eq!(SEAL_PROOFS.len(), 20);
+        assert_eq!(POST_PROOFS.len(), 10);
+    }
+
+    #[test]
+    fn allowed_proofs() {
+        use RegisteredSealProof::*;
+
+        assert!(is_allowed(NetworkVersion::V6, StackedDRG32GiBV1));
+        assert!(!is_allowed(NetworkVersion::V7, StackedDRG32GiBV1));
+        assert!(!is_allowed(NetworkVersion::V6, StackedDRG32GiBV1P1));
+        assert!(is_allowed(NetworkVersion::V7, StackedDRG32GiBV1P1));
+        assert!(!is_allowed(
+            NetworkVersion::V20,
+            StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+        ));
+        assert!(is_allowed(
+            NetworkVersion::V21,
+            StackedDRG32GiBV1P1_Feat_SyntheticPoRep
+        ));
+        assert!(!is_allowed(
+            NetworkVersion::V22,
+            StackedDRG32GiBV1P2_Feat_NiPoRep
+        ));
+        assert!(is_allowed(
+            NetworkVersion::V23,
+            StackedDRG32GiBV1P2_Feat_NiPoRep
+        ));
+        assert!(!is_allowed(NetworkVersion::V23, Invalid(42)));
+
+        assert!(is_allowed(
+            NetworkVersion::V0,
+            RegisteredPoStProof::StackedDRGWinning32GiBV1
+        ));
+        assert!(is_allowed(
+            NetworkVersion::V21,
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1
+        ));
+        assert!(!is_allowed(
+            NetworkVersion::V21,
+            RegisteredPoStProof::Invalid(5)
+        ));
+    }
+
+    #[test]
+    fn compatible_post() {
+        use RegisteredPoStProof::*;
+
+        let seal = RegisteredSealProof::StackedDRG32GiBV1P1_Feat_SyntheticPoRep;
+        assert!(seal.is_compatible_post(StackedDRGWinning32GiBV1));
+        assert!(seal.is_compatible_post(StackedDRGWindow32GiBV1P1));
+        assert!(!seal.is_compatible_post(StackedDRGWindow64GiBV1P1));
+        assert!(!RegisteredSealProof::Invalid(1).is_compatible_post(StackedDRGWindow32GiBV1P1));
+    }
+}
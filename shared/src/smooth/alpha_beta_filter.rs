@@ -6,7 +6,7 @@ use fvm_ipld_encoding::tuple::*;
 
 use crate::bigint::{bigint_ser, BigInt, Integer};
 use crate::clock::ChainEpoch;
-use crate::math::PRECISION;
+use crate::math::{q128_from_int, q128_mul, q128_to_int, PRECISION};
 
 #[derive(Default, Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq, Eq)]
 pub struct FilterEstimate {
@@ -20,14 +20,14 @@ impl FilterEstimate {
     /// Create a new filter estimate given two Q.0 format ints.
     pub fn new(position: BigInt, velocity: BigInt) -> Self {
         FilterEstimate {
-            position: position << PRECISION,
-            velocity: velocity << PRECISION,
+            position: q128_from_int(position),
+            velocity: q128_from_int(velocity),
         }
     }
 
     /// Returns the Q.0 position estimate of the filter
     pub fn estimate(&self) -> BigInt {
-        &self.position >> PRECISION
+        q128_to_int(&self.position)
     }
 
     /// Extrapolate filter "position" delta epochs in the future.
@@ -54,13 +54,13 @@ impl<'a, 'b, 'f> AlphaBetaFilter<'a, 'b, 'f> {
     }
 
     pub fn next_estimate(&self, obs: &BigInt, epoch_delta: ChainEpoch) -> FilterEstimate {
-        let delta_t = BigInt::from(epoch_delta) << PRECISION;
-        let delta_x = (&delta_t * &self.prev_est.velocity) >> PRECISION;
+        let delta_t = q128_from_int(epoch_delta);
+        let delta_x = q128_mul(&delta_t, &self.prev_est.velocity);
         let mut position = delta_x + &self.prev_est.position;
 
         let obs = obs << PRECISION;
         let residual = obs - &position;
-        let revision_x = (self.alpha * &residual) >> PRECISION;
+        let revision_x = q128_mul(self.alpha, &residual);
         position += &revision_x;
 
         let revision_v = residual * self.beta;
@@ -5,7 +5,7 @@
 use super::alpha_beta_filter::*;
 use crate::bigint::{BigInt, Integer};
 use crate::clock::ChainEpoch;
-use crate::math::{poly_parse, poly_val, PRECISION};
+use crate::math::{poly_parse, poly_val, q128_from_int, q128_mul, PRECISION};
 
 lazy_static! {
     pub static ref NUM: Vec<BigInt> = poly_parse(&[
@@ -45,19 +45,19 @@ pub fn extrapolated_cum_sum_of_ratio(
     est_num: &FilterEstimate,
     est_denom: &FilterEstimate,
 ) -> BigInt {
-    let delta_t = BigInt::from(delta) << PRECISION;
-    let t0 = BigInt::from(relative_start) << PRECISION;
+    let delta_t = q128_from_int(delta);
+    let t0 = q128_from_int(relative_start);
 
     let pos_1 = &est_num.position;
     let pos_2 = &est_denom.position;
     let velo_1 = &est_num.velocity;
     let velo_2 = &est_denom.velocity;
 
-    let squared_velo_2 = (velo_2 * velo_2) >> PRECISION;
+    let squared_velo_2 = q128_mul(velo_2, velo_2);
 
     if squared_velo_2 > *EPSILON {
-        let mut x2a = ((velo_2 * t0) >> PRECISION) + pos_2;
-        let mut x2b = ((velo_2 * &delta_t) >> PRECISION) + &x2a;
+        let mut x2a = q128_mul(velo_2, &t0) + pos_2;
+        let mut x2b = q128_mul(velo_2, &delta_t) + &x2a;
         x2a = ln(&x2a);
         x2b = ln(&x2b);
 
@@ -49,6 +49,34 @@ impl<'a> TryFrom<&'a crate::econ::TokenAmount> for TokenAmount {
     }
 }
 
+/// The storage power type used in syscalls. It can represent any amount of power (in bytes) from 0
+/// to `2^128-1` bytes.
+///
+/// Like [`TokenAmount`], this type is a tuple of `u64`s storing the "low" and "high" bits of a
+/// little-endian u128.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(packed, C)]
+pub struct StoragePower {
+    pub lo: u64,
+    pub hi: u64,
+}
+
+impl From<StoragePower> for crate::sector::StoragePower {
+    fn from(v: StoragePower) -> Self {
+        ((v.hi as u128) << 64 | (v.lo as u128)).into()
+    }
+}
+
+impl<'a> TryFrom<&'a crate::sector::StoragePower> for StoragePower {
+    type Error = TryFromBigIntError<()>;
+    fn try_from(v: &'a crate::sector::StoragePower) -> Result<Self, Self::Error> {
+        v.try_into().map(|v: u128| Self {
+            hi: (v >> u64::BITS) as u64,
+            lo: v as u64,
+        })
+    }
+}
+
 bitflags! {
     /// Flags passed to the send syscall.
     #[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
@@ -58,6 +86,11 @@ bitflags! {
     pub struct SendFlags: u64 {
         /// Send in "read-only" mode.
         const READ_ONLY = 0b00000001;
+        /// Reject calls back into the sender (with [`ErrorNumber::Forbidden`]) until this send
+        /// returns.
+        ///
+        /// [`ErrorNumber::Forbidden`]: crate::error::ErrorNumber::Forbidden
+        const NO_REENTRY = 0b00000010;
     }
 }
 
@@ -65,6 +98,44 @@ impl SendFlags {
     pub fn read_only(self) -> bool {
         self.intersects(Self::READ_ONLY)
     }
+
+    pub fn no_reentry(self) -> bool {
+        self.intersects(Self::NO_REENTRY)
+    }
+}
+
+/// The level of a structured log record emitted through the `debug::log_structured` syscall.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u32)]
+pub enum LogLevel {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+}
+
+impl TryFrom<u32> for LogLevel {
+    type Error = u32;
+    fn try_from(v: u32) -> Result<Self, u32> {
+        Ok(match v {
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            3 => LogLevel::Info,
+            4 => LogLevel::Debug,
+            _ => return Err(v),
+        })
+    }
+}
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+        })
+    }
 }
 
 /// A fixed sized struct for serializing an [event `Entry`](crate::event::Entry) separately from the
@@ -103,11 +174,14 @@ assert_syscall_safe! {
     i8, i16, i32, i64,
 
     TokenAmount,
+    StoragePower,
     out::ipld::IpldOpen,
     out::ipld::IpldStat,
+    out::ipld::IpldQuota,
     out::send::Send,
     out::crypto::VerifyConsensusFault,
     out::network::NetworkContext,
+    out::network::NetworkSummary,
     out::vm::MessageContext,
 }
 
@@ -31,6 +31,15 @@ pub mod ipld {
         pub codec: u64,
         pub size: u32,
     }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[repr(packed, C)]
+    pub struct IpldQuota {
+        /// The number of bytes of block data that may still be opened or created.
+        pub bytes_remaining: u64,
+        /// The number of blocks that may still be opened or created.
+        pub blocks_remaining: u32,
+    }
 }
 
 pub mod send {
@@ -107,7 +116,7 @@ pub mod vm {
 
 pub mod network {
     use crate::clock::ChainEpoch;
-    use crate::sys::TokenAmount;
+    use crate::sys::{StoragePower, TokenAmount};
     use crate::version::NetworkVersion;
 
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -124,4 +133,17 @@ pub mod network {
         /// The network version.
         pub network_version: NetworkVersion,
     }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[repr(packed, C)]
+    pub struct NetworkSummary {
+        /// The network's total raw byte power.
+        pub raw_byte_power: StoragePower,
+        /// The network's total quality-adjusted power.
+        pub quality_adj_power: StoragePower,
+        /// The total pledge collateral locked by storage providers.
+        pub total_pledge_collateral: TokenAmount,
+        /// The block reward paid out at the current epoch.
+        pub this_epoch_reward: TokenAmount,
+    }
 }
@@ -2,9 +2,182 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use cid::Cid;
 use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{from_slice, to_vec, RawBytes};
+use ipld_core::ipld::Ipld;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
 pub struct UpgradeInfo {
     // the old code cid we are upgrading from
     pub old_code_cid: Cid,
 }
+
+/// An actor state tagged with the version of its layout.
+///
+/// Actors that expect their state layout to change across code upgrades can store their state
+/// wrapped in this envelope, so the new code can tell which layout it's looking at (and migrate it
+/// if necessary) before decoding the payload.
+///
+/// The payload is embedded in the envelope as IPLD (not as opaque bytes), so the CIDs it links to
+/// are links of the envelope's block: they stay reachable, and are written and retained with it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct VersionedState {
+    /// The version of the payload's layout.
+    pub version: u64,
+    /// The state.
+    pub payload: Ipld,
+}
+
+/// A state type with a known layout version.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// The version of this type's layout. Must change whenever the layout does.
+    const VERSION: u64;
+}
+
+/// Versioned state error
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to encode or decode versioned state: {0}")]
+    Encoding(#[from] fvm_ipld_encoding::Error),
+    #[error("unexpected state version {actual}, expected {expected}")]
+    UnexpectedVersion { expected: u64, actual: u64 },
+}
+
+impl VersionedState {
+    /// Encodes `state` as the payload of an envelope with the given version.
+    pub fn new<T: Serialize + ?Sized>(version: u64, state: &T) -> Result<Self, Error> {
+        Ok(VersionedState {
+            version,
+            payload: from_slice(&to_vec(state)?)?,
+        })
+    }
+
+    /// Decodes the payload, without checking the version.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        Ok(from_slice(&to_vec(&self.payload)?)?)
+    }
+
+    /// Decodes the payload, failing with [`Error::UnexpectedVersion`] if the envelope wasn't
+    /// written with the current version of `T`.
+    pub fn decode_versioned<T: Versioned>(&self) -> Result<T, Error> {
+        if self.version != T::VERSION {
+            return Err(Error::UnexpectedVersion {
+                expected: T::VERSION,
+                actual: self.version,
+            });
+        }
+        self.decode()
+    }
+}
+
+/// Encodes `state` in a [`VersionedState`] envelope tagged with its current version.
+pub fn encode_versioned<T: Versioned>(state: &T) -> Result<RawBytes, Error> {
+    Ok(RawBytes::serialize(VersionedState::new(
+        T::VERSION,
+        state,
+    )?)?)
+}
+
+/// Decodes a [`VersionedState`] envelope written by [`encode_versioned`], failing if it was
+/// written with a version other than the current version of `T`.
+pub fn decode_versioned<T: Versioned>(bytes: &[u8]) -> Result<T, Error> {
+    fvm_ipld_encoding::from_slice::<VersionedState>(bytes)?.decode_versioned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+    struct StateV1 {
+        count: u64,
+    }
+
+    impl Versioned for StateV1 {
+        const VERSION: u64 = 1;
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+    struct StateV2 {
+        count: u64,
+        owner: u64,
+    }
+
+    impl Versioned for StateV2 {
+        const VERSION: u64 = 2;
+    }
+
+    #[test]
+    fn versioned_state_roundtrip() {
+        let state = StateV1 { count: 42 };
+        let bytes = encode_versioned(&state).unwrap();
+        assert_eq!(decode_versioned::<StateV1>(&bytes).unwrap(), state);
+
+        let envelope: VersionedState = fvm_ipld_encoding::from_slice(&bytes).unwrap();
+        assert_eq!(envelope.version, 1);
+        assert_eq!(envelope.decode::<StateV1>().unwrap(), state);
+    }
+
+    #[test]
+    fn versioned_state_migration() {
+        let bytes = encode_versioned(&StateV1 { count: 42 }).unwrap();
+        match decode_versioned::<StateV2>(&bytes) {
+            Err(Error::UnexpectedVersion {
+                expected: 2,
+                actual: 1,
+            }) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        // The new code can detect the old layout and convert it.
+        let envelope: VersionedState = fvm_ipld_encoding::from_slice(&bytes).unwrap();
+        let migrated = match envelope.version {
+            StateV1::VERSION => {
+                let old: StateV1 = envelope.decode().unwrap();
+                StateV2 {
+                    count: old.count,
+                    owner: 0,
+                }
+            }
+            StateV2::VERSION => envelope.decode_versioned().unwrap(),
+            v => panic!("unknown version {v}"),
+        };
+        assert_eq!(
+            migrated,
+            StateV2 {
+                count: 42,
+                owner: 0
+            }
+        );
+    }
+
+    #[test]
+    fn versioned_state_links() {
+        let link = Cid::default();
+        let envelope = VersionedState::new(1, &(42u64, link)).unwrap();
+        let bytes = to_vec(&envelope).unwrap();
+
+        // The link is a link of the envelope itself, not hidden inside a byte string.
+        let decoded: Ipld = from_slice(&bytes).unwrap();
+        assert_eq!(
+            decoded,
+            Ipld::List(vec![
+                Ipld::Integer(1),
+                Ipld::List(vec![Ipld::Integer(42), Ipld::Link(link)]),
+            ])
+        );
+        assert_eq!(envelope.decode::<(u64, Cid)>().unwrap(), (42, link));
+    }
+
+    #[test]
+    fn versioned_state_bad_payload() {
+        let envelope = VersionedState::new(1, &"not a state").unwrap();
+        let bytes = fvm_ipld_encoding::to_vec(&envelope).unwrap();
+        assert!(matches!(
+            decode_versioned::<StateV1>(&bytes),
+            Err(Error::Encoding(_))
+        ));
+    }
+}
@@ -7,7 +7,7 @@ use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 
 /// Specifies the network version
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Ord, PartialOrd, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 #[repr(transparent)]
 #[serde(transparent)]
 pub struct NetworkVersion(u32);
@@ -65,6 +65,8 @@ impl NetworkVersion {
     pub const V24: Self = Self(24);
     /// TBD (builtin-actors v16)
     pub const V25: Self = Self(25);
+    /// TBD (builtin-actors v17)
+    pub const V26: Self = Self(26);
 
     pub const MAX: Self = Self(u32::MAX);
 
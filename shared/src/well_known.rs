@@ -0,0 +1,156 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! The registry of well-known singleton actors, which live at fixed ID addresses on every network.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::address::Address;
+use crate::ActorID;
+
+/// A singleton actor at a fixed ID address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WellKnownActor {
+    System,
+    Init,
+    Reward,
+    Cron,
+    StoragePower,
+    StorageMarket,
+    VerifiedRegistry,
+    DataCap,
+    Eam,
+    /// The account actor that is the destination of all burnt funds.
+    BurntFunds,
+}
+
+/// Every well-known actor, in ID order.
+pub const WELL_KNOWN_ACTORS: [WellKnownActor; 10] = [
+    WellKnownActor::System,
+    WellKnownActor::Init,
+    WellKnownActor::Reward,
+    WellKnownActor::Cron,
+    WellKnownActor::StoragePower,
+    WellKnownActor::StorageMarket,
+    WellKnownActor::VerifiedRegistry,
+    WellKnownActor::DataCap,
+    WellKnownActor::Eam,
+    WellKnownActor::BurntFunds,
+];
+
+impl WellKnownActor {
+    /// Returns the actor's ID.
+    pub const fn id(self) -> ActorID {
+        match self {
+            WellKnownActor::System => 0,
+            WellKnownActor::Init => 1,
+            WellKnownActor::Reward => 2,
+            WellKnownActor::Cron => 3,
+            WellKnownActor::StoragePower => 4,
+            WellKnownActor::StorageMarket => 5,
+            WellKnownActor::VerifiedRegistry => 6,
+            WellKnownActor::DataCap => 7,
+            WellKnownActor::Eam => 10,
+            WellKnownActor::BurntFunds => 99,
+        }
+    }
+
+    /// Returns the actor's ID address.
+    pub const fn address(self) -> Address {
+        Address::new_id(self.id())
+    }
+
+    /// Returns the actor's name. Except for [`WellKnownActor::BurntFunds`], this is also the name
+    /// of the actor's code in the builtin actors manifest (see [`WellKnownActor::code_name`]).
+    pub const fn name(self) -> &'static str {
+        match self {
+            WellKnownActor::System => "system",
+            WellKnownActor::Init => "init",
+            WellKnownActor::Reward => "reward",
+            WellKnownActor::Cron => "cron",
+            WellKnownActor::StoragePower => "storagepower",
+            WellKnownActor::StorageMarket => "storagemarket",
+            WellKnownActor::VerifiedRegistry => "verifiedregistry",
+            WellKnownActor::DataCap => "datacap",
+            WellKnownActor::Eam => "eam",
+            WellKnownActor::BurntFunds => "burntfunds",
+        }
+    }
+
+    /// Returns the name of the actor's code in the builtin actors manifest.
+    pub const fn code_name(self) -> &'static str {
+        match self {
+            WellKnownActor::BurntFunds => "account",
+            other => other.name(),
+        }
+    }
+
+    /// Looks up a well-known actor by ID.
+    pub fn from_id(id: ActorID) -> Option<Self> {
+        WELL_KNOWN_ACTORS.into_iter().find(|a| a.id() == id)
+    }
+
+    /// Looks up a well-known actor by name (see [`WellKnownActor::name`]).
+    pub fn from_name(name: &str) -> Option<Self> {
+        WELL_KNOWN_ACTORS.into_iter().find(|a| a.name() == name)
+    }
+
+    /// Looks up a well-known actor by ID address. Returns `None` for non-ID addresses.
+    pub fn from_address(addr: &Address) -> Option<Self> {
+        addr.id().ok().and_then(Self::from_id)
+    }
+
+    /// Iterates over every well-known actor, in ID order.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        WELL_KNOWN_ACTORS.into_iter()
+    }
+}
+
+impl fmt::Display for WellKnownActor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for WellKnownActor {
+    type Err = UnknownActorName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s).ok_or_else(|| UnknownActorName(s.into()))
+    }
+}
+
+/// Returned when parsing an unknown well-known actor name.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown well-known actor {0:?}")]
+pub struct UnknownActorName(pub String);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn lookups_round_trip() {
+        for actor in WellKnownActor::iter() {
+            assert_eq!(WellKnownActor::from_id(actor.id()), Some(actor));
+            assert_eq!(WellKnownActor::from_name(actor.name()), Some(actor));
+            assert_eq!(WellKnownActor::from_address(&actor.address()), Some(actor));
+            assert_eq!(actor.to_string().parse(), Ok(actor));
+        }
+        assert_eq!(WellKnownActor::from_id(8), None);
+        assert_eq!(WellKnownActor::from_name("account"), None);
+        assert!("foo".parse::<WellKnownActor>().is_err());
+    }
+
+    #[test]
+    fn registry_is_consistent() {
+        let ids: HashSet<_> = WellKnownActor::iter().map(|a| a.id()).collect();
+        let names: HashSet<_> = WellKnownActor::iter().map(|a| a.name()).collect();
+        assert_eq!(ids.len(), WELL_KNOWN_ACTORS.len());
+        assert_eq!(names.len(), WELL_KNOWN_ACTORS.len());
+        assert!(WELL_KNOWN_ACTORS.windows(2).all(|w| w[0].id() < w[1].id()));
+    }
+}
@@ -5,6 +5,7 @@
 use cid::Cid;
 use fvm_shared::commcid::*;
 use multihash_codetable::{Code, Multihash, MultihashDigest};
+use quickcheck_macros::quickcheck;
 use rand::{thread_rng, Rng};
 
 fn rand_comm() -> Commitment {
@@ -104,3 +105,136 @@ fn symmetric_conversion() {
         (FIL_COMMITMENT_UNSEALED, SHA2_256_TRUNC254_PADDED, comm)
     );
 }
+
+#[test]
+fn typed_errors() {
+    let comm = rand_comm();
+
+    let mh = Multihash::wrap(SHA2_256_TRUNC254_PADDED, &comm).unwrap();
+    assert_eq!(
+        cid_to_replica_commitment_v1(&Cid::new_v1(FIL_COMMITMENT_UNSEALED, mh)),
+        Err(Error::UnexpectedKind {
+            expected: CommitmentKind::Sealed,
+            actual: CommitmentKind::Unsealed
+        })
+    );
+    assert_eq!(
+        validate_sealed_cid(&Cid::new_v1(FIL_COMMITMENT_SEALED, mh)),
+        Err(Error::InvalidHashFunction {
+            kind: CommitmentKind::Sealed,
+            hash: SHA2_256_TRUNC254_PADDED
+        })
+    );
+    assert_eq!(
+        validate_commitment_cid(&Cid::new_v1(0x55, mh)),
+        Err(Error::InvalidCodec(0x55))
+    );
+
+    // Truncated digests are rejected.
+    let mh = Multihash::wrap(SHA2_256_TRUNC254_PADDED, &comm[..31]).unwrap();
+    assert_eq!(
+        validate_unsealed_cid(&Cid::new_v1(FIL_COMMITMENT_UNSEALED, mh)),
+        Err(Error::InvalidLength(31))
+    );
+
+    // CIDv0 is never a commitment.
+    let c = Cid::new_v0(Code::Sha2_256.digest(&comm)).unwrap();
+    assert_eq!(
+        validate_commitment_cid(&c),
+        Err(Error::InvalidVersion(cid::Version::V0))
+    );
+
+    // The errors are human readable.
+    assert_eq!(
+        Error::InvalidCodec(0x55).to_string(),
+        "Invalid codec 0x55, expected sealed or unsealed commitment codec"
+    );
+}
+
+#[test]
+fn validation() {
+    let comm = rand_comm();
+
+    let comm_r = replica_commitment_v1_to_cid(&comm).unwrap();
+    let comm_d = data_commitment_v1_to_cid(&comm).unwrap();
+    let comm_p = piece_commitment_v1_to_cid(&comm).unwrap();
+
+    assert_eq!(validate_commitment_cid(&comm_r), Ok(CommitmentKind::Sealed));
+    assert_eq!(
+        validate_commitment_cid(&comm_d),
+        Ok(CommitmentKind::Unsealed)
+    );
+
+    assert!(validate_sealed_cid(&comm_r).is_ok());
+    assert!(validate_sealed_cid(&comm_d).is_err());
+    assert!(validate_unsealed_cid(&comm_d).is_ok());
+    assert!(validate_unsealed_cid(&comm_r).is_err());
+    assert!(validate_piece_cid(&comm_p).is_ok());
+    assert!(validate_piece_cid(&comm_r).is_err());
+}
+
+fn comm_from_words(words: (u64, u64, u64, u64)) -> Commitment {
+    let mut comm = Commitment::default();
+    for (chunk, w) in comm.chunks_mut(8).zip([words.0, words.1, words.2, words.3]) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    comm
+}
+
+#[quickcheck]
+fn prop_commitment_roundtrip(words: (u64, u64, u64, u64), sealed: bool) -> Result<(), String> {
+    let comm = comm_from_words(words);
+    let (cid, kind) = if sealed {
+        (
+            replica_commitment_v1_to_cid(&comm).map_err(|e| e.to_string())?,
+            CommitmentKind::Sealed,
+        )
+    } else {
+        (
+            data_commitment_v1_to_cid(&comm).map_err(|e| e.to_string())?,
+            CommitmentKind::Unsealed,
+        )
+    };
+
+    let decoded = cid_to_commitment(&cid).map_err(|e| e.to_string())?;
+    if decoded != (kind.codec(), kind.hash_code(), comm) {
+        return Err(format!("commitment differs after roundtrip: {decoded:?}"));
+    }
+    if validate_commitment_cid(&cid) != Ok(kind) {
+        return Err(format!("{cid} not validated as a {kind} commitment"));
+    }
+    Ok(())
+}
+
+#[quickcheck]
+fn prop_only_matching_segments_are_accepted(codec: u64, hash: u64) -> Result<(), String> {
+    // Bias the inputs towards the interesting values.
+    let codec = match codec % 3 {
+        0 => FIL_COMMITMENT_SEALED,
+        1 => FIL_COMMITMENT_UNSEALED,
+        _ => codec,
+    };
+    let hash = match hash % 3 {
+        0 => POSEIDON_BLS12_381_A1_FC1,
+        1 => SHA2_256_TRUNC254_PADDED,
+        _ => hash,
+    };
+    let valid = matches!(
+        (codec, hash),
+        (FIL_COMMITMENT_SEALED, POSEIDON_BLS12_381_A1_FC1)
+            | (FIL_COMMITMENT_UNSEALED, SHA2_256_TRUNC254_PADDED)
+    );
+
+    let comm = rand_comm();
+    if commitment_to_cid(codec, hash, &comm).is_ok() != valid {
+        return Err(format!(
+            "commitment_to_cid({codec:#x}, {hash:#x}) should be {valid}"
+        ));
+    }
+
+    let c = Cid::new_v1(codec, Multihash::wrap(hash, &comm).unwrap());
+    if validate_commitment_cid(&c).is_ok() != valid {
+        return Err(format!("validate_commitment_cid({c}) should be {valid}"));
+    }
+    Ok(())
+}
@@ -0,0 +1,326 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Golden CBOR encodings of the on-chain types defined in this crate.
+//!
+//! Each case checks that a fixed value encodes to the bytes committed in `tests/golden/<name>.hex`
+//! and that these bytes decode back to the same value. A failure here means the wire format
+//! changed, which is a consensus-breaking change. If the change is intended, regenerate the files
+//! with `UPDATE_GOLDEN=1 cargo test -p fvm_shared --test golden` and commit them.
+
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+use cid::Cid;
+use data_encoding::HEXLOWER;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::ser::Serialize;
+use fvm_ipld_encoding::{from_slice, to_vec, RawBytes, DAG_CBOR};
+use fvm_shared::address::Address;
+use fvm_shared::commcid::{data_commitment_v1_to_cid, replica_commitment_v1_to_cid, Commitment};
+use fvm_shared::crypto::signature::{Signature, SignatureType};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::event::{ActorEvent, Entry, Flags, StampedEvent};
+use fvm_shared::message::{Message, SignedMessage};
+use fvm_shared::piece::{PaddedPieceSize, PieceInfo};
+use fvm_shared::randomness::Randomness;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::sector::{
+    AggregateSealVerifyInfo, AggregateSealVerifyProofAndInfos, PoStProof, RegisteredAggregateProof,
+    RegisteredPoStProof, RegisteredSealProof, RegisteredUpdateProof, ReplicaUpdateInfo,
+    SealVerifyInfo, SectorID, SectorInfo, WindowPoStVerifyInfo,
+};
+use fvm_shared::state::ActorState;
+use fvm_shared::upgrade::{UpgradeInfo, VersionedState};
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::IPLD_RAW;
+use ipld_core::ipld::Ipld;
+use multihash_codetable::{Code, MultihashDigest};
+
+/// Checks `value` against its golden encoding, (re)writing it instead if `UPDATE_GOLDEN` is set.
+fn check<T>(name: &str, value: T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.hex"));
+    let encoded = to_vec(&value).unwrap();
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, HEXLOWER.encode(&encoded) + "\n").unwrap();
+    }
+
+    let golden = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+    let golden = HEXLOWER.decode(golden.trim().as_bytes()).unwrap();
+    assert_eq!(
+        HEXLOWER.encode(&encoded),
+        HEXLOWER.encode(&golden),
+        "encoding of {name} changed"
+    );
+    let decoded: T = from_slice(&golden).unwrap();
+    assert_eq!(decoded, value, "{name} doesn't round-trip");
+}
+
+fn cid(data: &[u8]) -> Cid {
+    Cid::new_v1(DAG_CBOR, Code::Sha2_256.digest(data))
+}
+
+fn commitment(data: &[u8]) -> Commitment {
+    Code::Sha2_256.digest(data).digest().try_into().unwrap()
+}
+
+fn sealed_cid(data: &[u8]) -> Cid {
+    replica_commitment_v1_to_cid(&commitment(data)).unwrap()
+}
+
+fn unsealed_cid(data: &[u8]) -> Cid {
+    data_commitment_v1_to_cid(&commitment(data)).unwrap()
+}
+
+#[test]
+fn address() {
+    check("address_id", Address::new_id(1024));
+    check(
+        "address_secp256k1",
+        Address::new_secp256k1(&[4; 65]).unwrap(),
+    );
+    check("address_actor", Address::new_actor(b"actor"));
+    check("address_bls", Address::new_bls(&[7; 48]).unwrap());
+    check(
+        "address_delegated",
+        Address::new_delegated(10, &[0xab; 20]).unwrap(),
+    );
+}
+
+#[test]
+fn econ() {
+    check(
+        "token_amount",
+        TokenAmount::from_atto(123_456_789_000_000u64),
+    );
+    check("token_amount_zero", TokenAmount::from_atto(0));
+    check("token_amount_negative", TokenAmount::from_atto(-42));
+}
+
+#[test]
+fn message() {
+    check(
+        "message",
+        Message {
+            version: 0,
+            from: Address::new_secp256k1(&[4; 65]).unwrap(),
+            to: Address::new_id(1024),
+            sequence: 7,
+            value: TokenAmount::from_whole(3),
+            method_num: 2,
+            params: RawBytes::new(vec![0x82, 0x01, 0x02]),
+            gas_limit: 10_000_000,
+            gas_fee_cap: TokenAmount::from_atto(200_000),
+            gas_premium: TokenAmount::from_atto(100_000),
+        },
+    );
+    check(
+        "signature_secp256k1",
+        Signature {
+            sig_type: SignatureType::Secp256k1,
+            bytes: vec![1; 65],
+        },
+    );
+    check(
+        "signature_bls",
+        Signature {
+            sig_type: SignatureType::BLS,
+            bytes: vec![2; 96],
+        },
+    );
+}
+
+#[test]
+fn message_cid() {
+    let message = Message {
+        version: 0,
+        from: Address::new_secp256k1(&[4; 65]).unwrap(),
+        to: Address::new_id(1024),
+        sequence: 7,
+        value: TokenAmount::from_whole(3),
+        method_num: 2,
+        params: RawBytes::new(vec![0x82, 0x01, 0x02]),
+        gas_limit: 10_000_000,
+        gas_fee_cap: TokenAmount::from_atto(200_000),
+        gas_premium: TokenAmount::from_atto(100_000),
+    };
+    let blake2b_cid = |data: &[u8]| Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(data));
+    assert_eq!(message.cid(), blake2b_cid(&to_vec(&message).unwrap()));
+
+    let secp = SignedMessage {
+        message: message.clone(),
+        signature: Signature {
+            sig_type: SignatureType::Secp256k1,
+            bytes: vec![1; 65],
+        },
+    };
+    check("signed_message_secp256k1", secp.clone());
+    assert_eq!(secp.cid(), blake2b_cid(&to_vec(&secp).unwrap()));
+    assert_ne!(secp.cid(), message.cid());
+
+    // BLS-signed messages are identified by the unsigned message.
+    let bls = SignedMessage {
+        message: message.clone(),
+        signature: Signature {
+            sig_type: SignatureType::BLS,
+            bytes: vec![2; 96],
+        },
+    };
+    check("signed_message_bls", bls.clone());
+    assert_eq!(bls.cid(), message.cid());
+}
+
+#[test]
+fn receipt() {
+    check(
+        "receipt",
+        Receipt {
+            exit_code: ExitCode::OK,
+            return_data: RawBytes::new(vec![0x43, 1, 2, 3]),
+            gas_used: 1_234_567,
+            events_root: Some(cid(b"events")),
+        },
+    );
+    check(
+        "receipt_failed",
+        Receipt {
+            exit_code: ExitCode::USR_ILLEGAL_ARGUMENT,
+            return_data: RawBytes::default(),
+            gas_used: 42,
+            events_root: None,
+        },
+    );
+    check("exit_code", ExitCode::SYS_OUT_OF_GAS);
+    check("network_version", NetworkVersion::V25);
+}
+
+#[test]
+fn event() {
+    check(
+        "stamped_event",
+        StampedEvent::new(
+            1024,
+            ActorEvent::from(vec![
+                Entry {
+                    flags: Flags::FLAG_INDEXED_ALL,
+                    key: "t1".into(),
+                    codec: IPLD_RAW,
+                    value: vec![0xde, 0xad],
+                },
+                Entry {
+                    flags: Flags::empty(),
+                    key: "d".into(),
+                    codec: IPLD_RAW,
+                    value: vec![],
+                },
+            ]),
+        ),
+    );
+}
+
+#[test]
+fn state() {
+    check(
+        "actor_state",
+        ActorState::new(
+            cid(b"code"),
+            cid(b"state"),
+            TokenAmount::from_whole(10),
+            3,
+            Some(Address::new_delegated(10, &[0xab; 20]).unwrap()),
+        ),
+    );
+    check(
+        "upgrade_info",
+        UpgradeInfo {
+            old_code_cid: cid(b"code"),
+        },
+    );
+    check(
+        "versioned_state",
+        VersionedState {
+            version: 2,
+            payload: Ipld::List(vec![Ipld::Integer(1), Ipld::Integer(2)]),
+        },
+    );
+}
+
+#[test]
+fn sector() {
+    let post_proof = PoStProof {
+        post_proof: RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        proof_bytes: vec![3; 192],
+    };
+    check("post_proof", post_proof.clone());
+    check(
+        "window_post_verify_info",
+        WindowPoStVerifyInfo {
+            randomness: Randomness(vec![5; 32]),
+            proofs: vec![post_proof],
+            challenged_sectors: vec![SectorInfo {
+                proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+                sector_number: 17,
+                sealed_cid: sealed_cid(b"sealed"),
+            }],
+            prover: 1000,
+        },
+    );
+    check(
+        "seal_verify_info",
+        SealVerifyInfo {
+            registered_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+            sector_id: SectorID {
+                miner: 1000,
+                number: 17,
+            },
+            deal_ids: vec![1, 2, 3],
+            randomness: Randomness(vec![6; 32]),
+            interactive_randomness: Randomness(vec![7; 32]),
+            proof: vec![8; 1920],
+            sealed_cid: sealed_cid(b"sealed"),
+            unsealed_cid: unsealed_cid(b"unsealed"),
+        },
+    );
+    check(
+        "aggregate_seal_verify_proof_and_infos",
+        AggregateSealVerifyProofAndInfos {
+            miner: 1000,
+            seal_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+            aggregate_proof: RegisteredAggregateProof::SnarkPackV2,
+            proof: vec![9; 64],
+            infos: vec![AggregateSealVerifyInfo {
+                sector_number: 17,
+                randomness: Randomness(vec![6; 32]),
+                interactive_randomness: Randomness(vec![7; 32]),
+                sealed_cid: sealed_cid(b"sealed"),
+                unsealed_cid: unsealed_cid(b"unsealed"),
+            }],
+        },
+    );
+    check(
+        "replica_update_info",
+        ReplicaUpdateInfo {
+            update_proof_type: RegisteredUpdateProof::StackedDRG32GiBV1,
+            old_sealed_cid: sealed_cid(b"old"),
+            new_sealed_cid: sealed_cid(b"new"),
+            new_unsealed_cid: unsealed_cid(b"unsealed"),
+            proof: vec![10; 64],
+        },
+    );
+    check(
+        "piece_info",
+        PieceInfo {
+            size: PaddedPieceSize(2048),
+            cid: unsealed_cid(b"piece"),
+        },
+    );
+}
@@ -0,0 +1,426 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Reproducible WASM builds of actor crates, for use in build scripts.
+//!
+//! [`ActorBuilder`] compiles actor packages to `wasm32-unknown-unknown` with the workspace's `wasm`
+//! profile and pinned compiler flags (ignoring `RUSTFLAGS`), removes dead code from the result
+//! (see [`eliminate_dead_code`]), checks that it fits on chain (see [`ActorBuilder::max_size`]) and
+//! that the FVM accepts it (with the `preflight` feature, see [`preflight`]), and writes a
+//! `wasm_bin.rs` file to `OUT_DIR` declaring, for each actor, its binary and its code CID:
+//!
+//! ```ignore
+//! pub mod wasm_bin {
+//!     include!(concat!(env!("OUT_DIR"), "/wasm_bin.rs"));
+//! }
+//! ```
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+
+use cid::Cid;
+use fvm_ipld_encoding::IPLD_RAW;
+use multihash_codetable::{Code, MultihashDigest};
+
+mod optimize;
+pub use optimize::{eliminate_dead_code, FunctionSize, SizeReport};
+
+#[cfg(feature = "preflight")]
+mod preflight;
+#[cfg(feature = "preflight")]
+pub use preflight::preflight;
+
+/// The target actors are compiled to.
+pub const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// The cargo profile actors are compiled with (defined by the workspace).
+pub const WASM_PROFILE: &str = "wasm";
+
+/// The default maximum size of an actor binary: the FVM's default maximum block size, as the
+/// binary is stored on chain as a single block.
+pub const DEFAULT_MAX_SIZE: usize = 1 << 20;
+
+/// An actor package to build.
+#[derive(Clone, Debug)]
+pub struct Actor {
+    binary_var: String,
+    package: String,
+    features: Vec<String>,
+    preflight: bool,
+    max_size: Option<usize>,
+}
+
+impl Actor {
+    /// An actor built from `package` and declared as `binary_var` (e.g., `HELLO_WORLD_ACTOR_BINARY`).
+    pub fn new(binary_var: impl Into<String>, package: impl Into<String>) -> Self {
+        Self {
+            binary_var: binary_var.into(),
+            package: package.into(),
+            features: Vec::new(),
+            preflight: true,
+            max_size: None,
+        }
+    }
+
+    /// Builds the actor with the given cargo features.
+    pub fn features(mut self, features: &[&str]) -> Self {
+        self.features = features.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    /// Skips the preflight check, e.g., for actors that need syscalls of a custom kernel.
+    pub fn skip_preflight(mut self) -> Self {
+        self.preflight = false;
+        self
+    }
+
+    /// Fails the build if the actor's binary is larger than `bytes`, instead of the builder's
+    /// limit (see [`ActorBuilder::max_size`]).
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// The name of the declared code CID: the binary's name with `_BINARY` replaced by
+    /// `_CODE_CID` (e.g., `HELLO_WORLD_ACTOR_CODE_CID`), or followed by `_CODE_CID` if it doesn't
+    /// contain `_BINARY`.
+    pub fn code_cid_var(&self) -> String {
+        if self.binary_var.contains("_BINARY") {
+            self.binary_var.replacen("_BINARY", "_CODE_CID", 1)
+        } else {
+            format!("{}_CODE_CID", self.binary_var)
+        }
+    }
+}
+
+/// A built actor.
+#[derive(Clone, Debug)]
+pub struct BuiltActor {
+    pub actor: Actor,
+    /// The path of the built binary.
+    pub path: PathBuf,
+    /// The actor's code CID (see [`code_cid`]).
+    pub code_cid: Cid,
+    /// The size of the binary, function by function. Also written next to the binary, with the
+    /// `.sizes.txt` extension.
+    pub size_report: SizeReport,
+}
+
+/// Builds actor packages of a workspace into `OUT_DIR`.
+#[derive(Clone, Debug)]
+pub struct ActorBuilder {
+    manifest_path: PathBuf,
+    out_dir: PathBuf,
+    actors: Vec<Actor>,
+    max_size: usize,
+}
+
+impl ActorBuilder {
+    /// A builder for the package whose build script is running (and its workspace).
+    pub fn from_env() -> Self {
+        let manifest_dir =
+            std::env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR unset");
+        let out_dir = std::env::var_os("OUT_DIR").expect("no OUT_DIR env var");
+        Self::new(Path::new(&manifest_dir).join("Cargo.toml"), out_dir)
+    }
+
+    /// A builder for the workspace of the given manifest, building into `out_dir`.
+    pub fn new(manifest_path: impl Into<PathBuf>, out_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            manifest_path: manifest_path.into(),
+            out_dir: out_dir.into(),
+            actors: Vec::new(),
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+
+    /// Adds an actor to build.
+    pub fn actor(mut self, actor: Actor) -> Self {
+        self.actors.push(actor);
+        self
+    }
+
+    /// Fails the build if an actor's binary is larger than `bytes` (after dead code elimination),
+    /// unless the actor sets its own limit. Defaults to [`DEFAULT_MAX_SIZE`].
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Builds the actors, removes their dead code, checks their size and (with the `preflight`
+    /// feature) that the FVM accepts them, and writes `wasm_bin.rs`.
+    ///
+    /// Actors with the same features are built by a single cargo invocation, in the order in
+    /// which they were first added.
+    pub fn build(self) -> Result<Vec<BuiltActor>, Box<dyn Error>> {
+        let cargo = std::env::var_os("CARGO").expect("no CARGO env var");
+        let bundle_dir = self.out_dir.join("bundle");
+        let rustflags = self.rustflags(&cargo)?;
+
+        let mut groups: Vec<(&[String], Vec<&Actor>)> = Vec::new();
+        for actor in &self.actors {
+            match groups
+                .iter_mut()
+                .find(|(f, _)| *f == actor.features.as_slice())
+            {
+                Some((_, actors)) => actors.push(actor),
+                None => groups.push((&actor.features, vec![actor])),
+            }
+        }
+
+        let mut built = Vec::with_capacity(self.actors.len());
+        for (features, actors) in groups {
+            let mut cmd = Command::new(&cargo);
+            cmd.arg("build")
+                .args(actors.iter().map(|a| format!("-p={}", a.package)))
+                .arg(format!("--target={WASM_TARGET}"))
+                .arg(format!("--profile={WASM_PROFILE}"))
+                .arg("--locked")
+                .arg(format!("--manifest-path={}", self.manifest_path.display()))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                // We are supposed to only generate artifacts under OUT_DIR,
+                // so set OUT_DIR as the target directory for this build.
+                .env("CARGO_TARGET_DIR", &bundle_dir)
+                // As we are being called inside a build-script, these env variables may be set by
+                // the outer build. We pin our own flags instead.
+                .env_remove("RUSTFLAGS")
+                .env("CARGO_ENCODED_RUSTFLAGS", rustflags.join("\x1f"));
+            if !features.is_empty() {
+                cmd.arg(format!("--features={}", features.join(",")));
+            }
+
+            // Print out the command line we're about to run.
+            println!("cargo:warning=cmd={:?}", &cmd);
+
+            // Launch the command.
+            let child = cmd.spawn().expect("failed to launch cargo build");
+            let result = wait_cmd_and_print_output(child)?;
+            if !result.success() {
+                return Err("actor build failed".into());
+            }
+
+            // Move the binaries before the next build overwrites them.
+            for actor in actors {
+                let bin = bundle_dir
+                    .join(WASM_TARGET)
+                    .join(WASM_PROFILE)
+                    .join(format!("{}.wasm", actor.package));
+                let path = bundle_dir.join(format!("{}.wasm", actor.binary_var));
+                let original = std::fs::read(&bin)?;
+                let wasm = eliminate_dead_code(&original)
+                    .map_err(|e| format!("failed to optimize actor {}: {e}", actor.binary_var))?;
+                std::fs::write(&path, &wasm)?;
+                std::fs::remove_file(bin)?;
+
+                let size_report = SizeReport::optimized(&original, &wasm)?;
+                std::fs::write(
+                    path.with_extension("sizes.txt"),
+                    format!(
+                        "{} ({} bytes before dead code elimination)\n{size_report}",
+                        actor.package,
+                        original.len()
+                    ),
+                )?;
+                let max_size = actor.max_size.unwrap_or(self.max_size);
+                if wasm.len() > max_size {
+                    return Err(too_large(actor, max_size, &size_report).into());
+                }
+
+                #[cfg(feature = "preflight")]
+                if actor.preflight {
+                    preflight(&wasm).map_err(|e| {
+                        format!("actor {} would be rejected: {e}", actor.binary_var)
+                    })?;
+                }
+
+                built.push(BuiltActor {
+                    actor: actor.clone(),
+                    path,
+                    code_cid: code_cid(&wasm),
+                    size_report,
+                });
+            }
+        }
+
+        std::fs::write(self.out_dir.join("wasm_bin.rs"), bindings(&built))?;
+        Ok(built)
+    }
+
+    /// Returns the pinned compiler flags, remapping the workspace and cargo home directories so
+    /// that their location doesn't end up in the binaries (e.g., in panic messages). Any
+    /// `RUSTFLAGS` set by the environment are ignored.
+    fn rustflags(&self, cargo: &std::ffi::OsStr) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut flags = Vec::new();
+
+        let output = Command::new(cargo)
+            .args(["locate-project", "--workspace", "--message-format=plain"])
+            .arg(format!("--manifest-path={}", self.manifest_path.display()))
+            .output()?;
+        if !output.status.success() {
+            return Err("failed to locate the workspace".into());
+        }
+        let workspace_manifest = PathBuf::from(String::from_utf8(output.stdout)?.trim());
+        if let Some(workspace) = workspace_manifest.parent() {
+            flags.push(format!(
+                "--remap-path-prefix={}=/workspace",
+                workspace.display()
+            ));
+        }
+
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")));
+        if let Some(cargo_home) = cargo_home {
+            flags.push(format!(
+                "--remap-path-prefix={}=/cargo",
+                cargo_home.display()
+            ));
+        }
+        Ok(flags)
+    }
+}
+
+/// Computes the code CID of an actor binary, as installed by the integration tests (the
+/// blake2b-256 hash of the raw binary).
+pub fn code_cid(wasm: &[u8]) -> Cid {
+    Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(wasm))
+}
+
+/// The error reported when an actor's binary exceeds its size limit, listing its largest functions.
+fn too_large(actor: &Actor, max_size: usize, report: &SizeReport) -> String {
+    let mut msg = format!(
+        "actor {} is {} bytes, over its limit of {max_size} bytes; largest functions:",
+        actor.binary_var, report.size
+    );
+    for f in report.functions.iter().take(10) {
+        match &f.name {
+            Some(name) => write!(msg, " {name} ({} bytes),", f.size),
+            None => write!(msg, " func[{}] ({} bytes),", f.index, f.size),
+        }
+        .unwrap();
+    }
+    msg.pop();
+    msg
+}
+
+/// Returns the contents of `wasm_bin.rs`.
+fn bindings(built: &[BuiltActor]) -> String {
+    let mut out = String::new();
+    for b in built {
+        let var = &b.actor.binary_var;
+        writeln!(
+            out,
+            "/// The `{}` actor.\npub const {var}: &[u8] = include_bytes!({:?});",
+            b.actor.package, b.path
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "/// The code CID of [`{var}`].\npub const {}: &str = {:?};",
+            b.actor.code_cid_var(),
+            b.code_cid.to_string()
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn wait_cmd_and_print_output(mut child: Child) -> Result<ExitStatus, Box<dyn Error>> {
+    // Pipe the output as cargo warnings. Unfortunately this is the only way to
+    // get cargo build to print the output.
+    let stdout = child.stdout.take().expect("no stdout");
+    let stderr = child.stderr.take().expect("no stderr");
+    let j1 = thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            println!("cargo:warning={:?}", line.unwrap());
+        }
+    });
+    let j2 = thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            println!("cargo:warning={:?}", line.unwrap());
+        }
+    });
+
+    j1.join().unwrap();
+    j2.join().unwrap();
+
+    let result = child.wait().expect("failed to wait for build to finish");
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_cid_vars() {
+        assert_eq!(
+            Actor::new("HELLO_WORLD_ACTOR_BINARY", "fil_hello_world_actor").code_cid_var(),
+            "HELLO_WORLD_ACTOR_CODE_CID"
+        );
+        assert_eq!(
+            Actor::new("SYSCALL_ACTOR_BINARY_FIP0079", "fil_syscall_actor").code_cid_var(),
+            "SYSCALL_ACTOR_CODE_CID_FIP0079"
+        );
+        assert_eq!(
+            Actor::new("ACTOR", "fil_actor").code_cid_var(),
+            "ACTOR_CODE_CID"
+        );
+    }
+
+    #[test]
+    fn generated_bindings() {
+        let code_cid = code_cid(b"\0asm");
+        assert_eq!(code_cid.codec(), IPLD_RAW);
+        assert_eq!(code_cid.hash().code(), u64::from(Code::Blake2b256));
+
+        let built = BuiltActor {
+            actor: Actor::new("HELLO_WORLD_ACTOR_BINARY", "fil_hello_world_actor"),
+            path: "/out/bundle/HELLO_WORLD_ACTOR_BINARY.wasm".into(),
+            code_cid,
+            size_report: SizeReport {
+                size: 4,
+                functions: Vec::new(),
+            },
+        };
+        assert_eq!(
+            bindings(&[built]),
+            format!(
+                "/// The `fil_hello_world_actor` actor.\n\
+                 pub const HELLO_WORLD_ACTOR_BINARY: &[u8] = \
+                 include_bytes!(\"/out/bundle/HELLO_WORLD_ACTOR_BINARY.wasm\");\n\
+                 /// The code CID of [`HELLO_WORLD_ACTOR_BINARY`].\n\
+                 pub const HELLO_WORLD_ACTOR_CODE_CID: &str = \"{code_cid}\";\n"
+            )
+        );
+    }
+
+    #[test]
+    fn size_limit_error() {
+        let report = SizeReport {
+            size: 2000,
+            functions: vec![
+                FunctionSize {
+                    index: 3,
+                    name: Some("invoke".into()),
+                    size: 1500,
+                },
+                FunctionSize {
+                    index: 4,
+                    name: None,
+                    size: 400,
+                },
+            ],
+        };
+        let actor = Actor::new("HELLO_WORLD_ACTOR_BINARY", "fil_hello_world_actor").max_size(1024);
+        assert_eq!(actor.max_size, Some(1024));
+        assert_eq!(
+            too_large(&actor, 1024, &report),
+            "actor HELLO_WORLD_ACTOR_BINARY is 2000 bytes, over its limit of 1024 bytes; \
+             largest functions: invoke (1500 bytes), func[4] (400 bytes)"
+        );
+    }
+}
@@ -0,0 +1,474 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt;
+
+use wasm_encoder::reencode::{utils, Reencode};
+use wasmparser::{
+    BinaryReaderError, ConstExpr, ElementItems, ExternalKind, KnownCustom, Name, Operator,
+    OperatorsReader, Parser, Payload, TypeRef,
+};
+
+/// Removes the functions of an actor binary that can't be reached from its exports, its start
+/// function, its tables or its globals, and strips its custom sections (names, debug info and
+/// producers), which the FVM ignores.
+///
+/// Functions referenced by element segments are kept: they may be called indirectly. Imports are
+/// kept as-is. The result is validated before being returned.
+pub fn eliminate_dead_code(wasm: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (imported, remap) = live_functions(wasm)?;
+    let mut module = wasm_encoder::Module::new();
+    DeadCodeEliminator {
+        imported,
+        remap,
+        next_body: 0,
+    }
+    .parse_core_module(&mut module, Parser::new(0), wasm)?;
+    let optimized = module.finish();
+    wasmparser::validate(&optimized)?;
+    Ok(optimized)
+}
+
+/// Returns the number of imported functions, and the new index of every function of the module
+/// (imports first), or `None` for dead functions. Imported functions always keep their index.
+fn live_functions(wasm: &[u8]) -> Result<(u32, Vec<Option<u32>>), BinaryReaderError> {
+    let mut imported = 0;
+    let mut defined = 0;
+    let mut roots = Vec::new();
+    let mut calls: Vec<Vec<u32>> = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    if matches!(import?.ty, TypeRef::Func(_)) {
+                        imported += 1;
+                    }
+                }
+            }
+            Payload::FunctionSection(functions) => defined = functions.count(),
+            Payload::ExportSection(exports) => {
+                for export in exports {
+                    let export = export?;
+                    if export.kind == ExternalKind::Func {
+                        roots.push(export.index);
+                    }
+                }
+            }
+            Payload::StartSection { func, .. } => roots.push(func),
+            Payload::GlobalSection(globals) => {
+                for global in globals {
+                    const_expr_refs(&global?.init_expr, &mut roots)?;
+                }
+            }
+            Payload::ElementSection(elements) => {
+                for element in elements {
+                    match element?.items {
+                        ElementItems::Functions(funcs) => {
+                            for func in funcs {
+                                roots.push(func?);
+                            }
+                        }
+                        ElementItems::Expressions(_, exprs) => {
+                            for expr in exprs {
+                                const_expr_refs(&expr?, &mut roots)?;
+                            }
+                        }
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut callees = Vec::new();
+                operator_refs(body.get_operators_reader()?, &mut callees)?;
+                calls.push(callees);
+            }
+            _ => {}
+        }
+    }
+
+    let mut live = vec![false; (imported + defined) as usize];
+    live[..imported as usize].fill(true);
+    while let Some(func) = roots.pop() {
+        match live.get_mut(func as usize) {
+            Some(l) if !*l => *l = true,
+            _ => continue,
+        }
+        if let Some(callees) = func
+            .checked_sub(imported)
+            .and_then(|i| calls.get(i as usize))
+        {
+            roots.extend(callees);
+        }
+    }
+
+    let mut next = 0;
+    let remap = live
+        .into_iter()
+        .map(|l| {
+            l.then(|| {
+                next += 1;
+                next - 1
+            })
+        })
+        .collect();
+    Ok((imported, remap))
+}
+
+/// Collects the functions referenced by a constant expression.
+fn const_expr_refs(expr: &ConstExpr, refs: &mut Vec<u32>) -> Result<(), BinaryReaderError> {
+    operator_refs(expr.get_operators_reader(), refs)
+}
+
+/// Collects the functions called or referenced by a sequence of instructions.
+fn operator_refs(reader: OperatorsReader, refs: &mut Vec<u32>) -> Result<(), BinaryReaderError> {
+    for op in reader {
+        match op? {
+            Operator::Call { function_index }
+            | Operator::ReturnCall { function_index }
+            | Operator::RefFunc { function_index } => refs.push(function_index),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Re-encodes a module without its dead functions and custom sections.
+struct DeadCodeEliminator {
+    /// See [`live_functions`].
+    remap: Vec<Option<u32>>,
+    imported: u32,
+    /// The index of the next function body, relative to the first defined function.
+    next_body: u32,
+}
+
+impl DeadCodeEliminator {
+    fn is_live(&self, defined: u32) -> bool {
+        self.remap[(self.imported + defined) as usize].is_some()
+    }
+}
+
+impl Reencode for DeadCodeEliminator {
+    type Error = Infallible;
+
+    fn function_index(&mut self, func: u32) -> u32 {
+        self.remap[func as usize].expect("live function references a dead function")
+    }
+
+    fn parse_function_section(
+        &mut self,
+        functions: &mut wasm_encoder::FunctionSection,
+        section: wasmparser::FunctionSectionReader<'_>,
+    ) -> Result<(), wasm_encoder::reencode::Error> {
+        for (i, ty) in section.into_iter().enumerate() {
+            let ty = ty?;
+            if self.is_live(i as u32) {
+                functions.function(self.type_index(ty));
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_function_body(
+        &mut self,
+        code: &mut wasm_encoder::CodeSection,
+        func: wasmparser::FunctionBody<'_>,
+    ) -> Result<(), wasm_encoder::reencode::Error> {
+        let body = self.next_body;
+        self.next_body += 1;
+        if self.is_live(body) {
+            utils::parse_function_body(self, code, func)?;
+        }
+        Ok(())
+    }
+
+    fn parse_custom_section(
+        &mut self,
+        _module: &mut wasm_encoder::Module,
+        _section: wasmparser::CustomSectionReader<'_>,
+    ) -> Result<(), wasm_encoder::reencode::Error> {
+        Ok(())
+    }
+}
+
+/// The size of an actor binary, function by function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeReport {
+    /// The size of the binary.
+    pub size: usize,
+    /// The binary's functions, largest first.
+    pub functions: Vec<FunctionSize>,
+}
+
+/// The size of a function body in an actor binary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionSize {
+    /// The index of the function (imports included).
+    pub index: u32,
+    /// The function's name, from the name section or else from its exports (if any).
+    pub name: Option<String>,
+    /// The size of the function's body, locals included.
+    pub size: usize,
+}
+
+impl SizeReport {
+    /// Measures the functions of an actor binary.
+    pub fn new(wasm: &[u8]) -> Result<Self, BinaryReaderError> {
+        Self::with_names(wasm, function_names(wasm)?)
+    }
+
+    /// Measures the functions of a binary produced by [`eliminate_dead_code`] from `original`.
+    /// Dead code elimination strips the name section, so functions are named after their
+    /// counterparts in the original binary.
+    pub fn optimized(original: &[u8], optimized: &[u8]) -> Result<Self, BinaryReaderError> {
+        let (_, remap) = live_functions(original)?;
+        let names = function_names(original)?
+            .into_iter()
+            .filter_map(|(index, name)| Some((remap.get(index as usize).copied().flatten()?, name)))
+            .collect();
+        Self::with_names(optimized, names)
+    }
+
+    /// Measures the functions of an actor binary, naming them with `names` (by index).
+    fn with_names(wasm: &[u8], mut names: HashMap<u32, String>) -> Result<Self, BinaryReaderError> {
+        let mut imported = 0;
+        let mut functions = Vec::new();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::ImportSection(imports) => {
+                    for import in imports {
+                        if matches!(import?.ty, TypeRef::Func(_)) {
+                            imported += 1;
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => functions.push(FunctionSize {
+                    index: imported + functions.len() as u32,
+                    name: None,
+                    size: body.range().len(),
+                }),
+                _ => {}
+            }
+        }
+        for f in &mut functions {
+            f.name = names.remove(&f.index);
+        }
+        functions.sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+        Ok(Self {
+            size: wasm.len(),
+            functions,
+        })
+    }
+
+    /// The total size of the function bodies.
+    pub fn code_size(&self) -> usize {
+        self.functions.iter().map(|f| f.size).sum()
+    }
+}
+
+/// Returns the names of a binary's functions (by index), from the name section or else from
+/// their exports.
+fn function_names(wasm: &[u8]) -> Result<HashMap<u32, String>, BinaryReaderError> {
+    let mut names = HashMap::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ExportSection(exports) => {
+                for export in exports {
+                    let export = export?;
+                    if export.kind == ExternalKind::Func {
+                        names.entry(export.index).or_insert(export.name.to_owned());
+                    }
+                }
+            }
+            Payload::CustomSection(section) => {
+                if let KnownCustom::Name(reader) = section.as_known() {
+                    for name in reader {
+                        if let Name::Function(map) = name? {
+                            for naming in map {
+                                let naming = naming?;
+                                names.insert(naming.index, naming.name.to_owned());
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(names)
+}
+
+impl fmt::Display for SizeReport {
+    /// One line per function, largest first: its size in bytes, then its name (or index).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} bytes, {} in {} functions",
+            self.size,
+            self.code_size(),
+            self.functions.len()
+        )?;
+        for func in &self.functions {
+            match &func.name {
+                Some(name) => writeln!(f, "{:>10} {name}", func.size)?,
+                None => writeln!(f, "{:>10} func[{}]", func.size, func.index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_encoder::{
+        CodeSection, CustomSection, ElementSection, Elements, EntityType, ExportKind,
+        ExportSection, Function, FunctionSection, ImportSection, Instruction, Module, NameMap,
+        NameSection, RefType, TableSection, TableType, TypeSection, ValType,
+    };
+
+    use super::*;
+
+    /// A module importing `env.ext` (function 0) and defining:
+    ///
+    /// 1. `invoke` (exported), calling 2.
+    /// 2. a helper, calling the import.
+    /// 3. a dead function, calling 2.
+    /// 4. a function only reachable through the table.
+    fn module() -> Vec<u8> {
+        module_with_names(None)
+    }
+
+    /// [`module`], with the given name section.
+    fn module_with_names(names: Option<&NameSection>) -> Vec<u8> {
+        let mut types = TypeSection::new();
+        types.function([], []);
+        let mut imports = ImportSection::new();
+        imports.import("env", "ext", EntityType::Function(0));
+        let mut functions = FunctionSection::new();
+        let mut code = CodeSection::new();
+        for callee in [Some(2), Some(0), Some(2), None] {
+            functions.function(0);
+            let mut f = Function::new([(1, ValType::I64)]);
+            if let Some(callee) = callee {
+                f.instruction(&Instruction::Call(callee));
+            }
+            f.instruction(&Instruction::End);
+            code.function(&f);
+        }
+        let mut tables = TableSection::new();
+        tables.table(TableType {
+            element_type: RefType::FUNCREF,
+            minimum: 1,
+            maximum: Some(1),
+            table64: false,
+            shared: false,
+        });
+        let mut exports = ExportSection::new();
+        exports.export("invoke", ExportKind::Func, 1);
+        let mut elements = ElementSection::new();
+        elements.active(
+            Some(0),
+            &wasm_encoder::ConstExpr::i32_const(0),
+            Elements::Functions(&[4]),
+        );
+
+        let mut module = Module::new();
+        module
+            .section(&types)
+            .section(&imports)
+            .section(&functions)
+            .section(&tables)
+            .section(&exports)
+            .section(&elements)
+            .section(&code)
+            .section(&CustomSection {
+                name: "producers".into(),
+                data: [0u8; 16].as_slice().into(),
+            });
+        if let Some(names) = names {
+            module.section(names);
+        }
+        module.finish()
+    }
+
+    #[test]
+    fn removes_dead_functions() {
+        let wasm = module();
+        assert_eq!(
+            live_functions(&wasm).unwrap(),
+            (1, vec![Some(0), Some(1), Some(2), None, Some(3)])
+        );
+
+        let optimized = eliminate_dead_code(&wasm).unwrap();
+        assert!(optimized.len() < wasm.len());
+        let report = SizeReport::new(&optimized).unwrap();
+        assert_eq!(report.size, optimized.len());
+        let mut indices: Vec<_> = report.functions.iter().map(|f| f.index).collect();
+        indices.sort();
+        assert_eq!(indices, [1, 2, 3]);
+
+        // Optimizing is idempotent.
+        assert_eq!(eliminate_dead_code(&optimized).unwrap(), optimized);
+    }
+
+    #[test]
+    fn size_report() {
+        let report = SizeReport::new(&module()).unwrap();
+        assert_eq!(report.functions.len(), 4);
+        assert_eq!(
+            report.code_size(),
+            report.functions.iter().map(|f| f.size).sum()
+        );
+        assert!(report.functions.windows(2).all(|w| w[0].size >= w[1].size));
+        let invoke = report.functions.iter().find(|f| f.index == 1).unwrap();
+        assert_eq!(invoke.name.as_deref(), Some("invoke"));
+        let lines: Vec<_> = report.to_string().lines().map(String::from).collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[1], format!("{:>10} invoke", invoke.size));
+        assert_eq!(
+            lines[2],
+            format!("{:>10} func[2]", report.functions[1].size)
+        );
+    }
+
+    #[test]
+    fn optimized_size_report() {
+        let mut map = NameMap::new();
+        for (index, name) in [
+            (0, "ext"),
+            (1, "run"),
+            (2, "helper"),
+            (3, "dead"),
+            (4, "indirect"),
+        ] {
+            map.append(index, name);
+        }
+        let mut names = NameSection::new();
+        names.functions(&map);
+        let wasm = module_with_names(Some(&names));
+        let optimized = eliminate_dead_code(&wasm).unwrap();
+
+        // The optimized binary has no name section, so only the exports are named.
+        let report = SizeReport::new(&optimized).unwrap();
+        let named: Vec<_> = report
+            .functions
+            .iter()
+            .filter_map(|f| f.name.as_deref())
+            .collect();
+        assert_eq!(named, ["invoke"]);
+
+        // Names are taken from the original binary, and follow the functions to their new index.
+        let report = SizeReport::optimized(&wasm, &optimized).unwrap();
+        assert_eq!(report.size, optimized.len());
+        let mut named: Vec<_> = report
+            .functions
+            .iter()
+            .map(|f| (f.index, f.name.as_deref()))
+            .collect();
+        named.sort();
+        assert_eq!(
+            named,
+            [(1, Some("run")), (2, Some("helper")), (3, Some("indirect"))]
+        );
+    }
+}
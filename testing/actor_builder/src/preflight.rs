@@ -0,0 +1,88 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use anyhow::bail;
+use cid::Cid;
+use fvm::call_manager::DefaultCallManager;
+use fvm::engine::{EngineConfig, EnginePool};
+use fvm::externs::{Chain, Consensus, Externs, Rand};
+use fvm::kernel::filecoin::DefaultFilecoinKernel;
+use fvm::machine::{DefaultMachine, NetworkConfig, SUPPORTED_NETWORK_VERSIONS};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::ConsensusFault;
+
+/// The kernel actors are checked against: the default kernel, with the FVM's `upgrade-actor`,
+/// `verify-signature` and `nv26-dev` features (test actors may use syscalls introduced in nv26).
+type PreflightKernel =
+    DefaultFilecoinKernel<DefaultCallManager<DefaultMachine<MemoryBlockstore, NoExterns>>>;
+
+/// Externs are never called: preflight checks don't execute the actor.
+struct NoExterns;
+
+impl Externs for NoExterns {}
+
+impl Rand for NoExterns {
+    fn get_chain_randomness(&self, _round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        bail!("no randomness during preflight checks")
+    }
+
+    fn get_beacon_randomness(&self, _round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        bail!("no randomness during preflight checks")
+    }
+}
+
+impl Consensus for NoExterns {
+    fn verify_consensus_fault(
+        &self,
+        _h1: &[u8],
+        _h2: &[u8],
+        _extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        bail!("no consensus faults during preflight checks")
+    }
+}
+
+impl Chain for NoExterns {
+    fn get_tipset_cid(&self, _epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        bail!("no tipsets during preflight checks")
+    }
+}
+
+/// Checks that the FVM (at the latest supported network version) would accept `wasm` as actor
+/// code, returning the problems found otherwise. See `fvm::engine::Engine::preflight_validate`.
+pub fn preflight(wasm: &[u8]) -> Result<(), String> {
+    let nc = NetworkConfig::new(*SUPPORTED_NETWORK_VERSIONS.end());
+    let engine = EnginePool::new(EngineConfig::from(&nc))
+        .map_err(|e| format!("failed to create engine: {e:#}"))?
+        .acquire();
+    let report = engine.preflight_validate::<PreflightKernel>(wasm, nc.network_version);
+    if report.is_valid() {
+        return Ok(());
+    }
+    Err(report
+        .diagnostics
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_modules() {
+        // (module (memory (export "memory") 1) (func (export "invoke") (param i32) (result i32) i32.const 0))
+        let valid = b"\0asm\x01\0\0\0\
+            \x01\x06\x01\x60\x01\x7f\x01\x7f\
+            \x03\x02\x01\0\
+            \x05\x03\x01\0\x01\
+            \x07\x13\x02\x06memory\x02\0\x06invoke\0\0\
+            \x0a\x06\x01\x04\0\x41\0\x0b";
+        assert_eq!(preflight(valid), Ok(()));
+
+        let err = preflight(b"not wasm").unwrap_err();
+        assert!(err.starts_with("invalid wasm"), "{err}");
+    }
+}
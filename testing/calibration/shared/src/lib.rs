@@ -25,6 +25,8 @@ pub enum Method {
     OnEvent,
     /// Read/write blocks with different numbers of CBOR fields & links.
     OnScanIpldLinks,
+    /// Call a syscall which doesn't do anything to measure the bare `OnSyscall` overhead.
+    OnSyscall,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -86,6 +88,11 @@ pub struct OnEventParams {
     pub seed: u64,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct OnSyscallParams {
+    pub iterations: usize,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct OnSendParams {
     pub iterations: usize,
@@ -101,6 +108,8 @@ impl OnHashingParams {
             h if h == SupportedHashes::Blake2b512 as u64 => Some(SupportedHashes::Blake2b512),
             h if h == SupportedHashes::Keccak256 as u64 => Some(SupportedHashes::Keccak256),
             h if h == SupportedHashes::Ripemd160 as u64 => Some(SupportedHashes::Ripemd160),
+            h if h == SupportedHashes::Blake3_256 as u64 => Some(SupportedHashes::Blake3_256),
+            h if h == SupportedHashes::Sha3_512 as u64 => Some(SupportedHashes::Sha3_512),
             _ => None,
         }
     }
@@ -66,6 +66,8 @@ fn bench_500_simple_state_access(
             })
             .unwrap(),
             epoch_offset: None,
+            receipt: None,
+            state_root: None,
         })
         .collect();
 
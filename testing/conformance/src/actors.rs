@@ -1,8 +1,10 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::HashMap;
 use std::io::Read;
 use std::sync::Mutex;
 
+use cid::Cid;
 use futures::executor::block_on;
 use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
 use fvm_ipld_car::load_car;
@@ -12,25 +14,56 @@ static V10_BUNDLE: &[u8] = include_bytes!("../actors/v10.tar.zst");
 static V11_BUNDLE: &[u8] = include_bytes!("../actors/v11.tar.zst");
 
 lazy_static! {
-    static ref ACTORS: Mutex<MemoryBlockstore> =
-        Mutex::new(load_bundles(&[V10_BUNDLE, V11_BUNDLE]).expect("failed to load bundles"));
+    static ref ACTORS: Mutex<Bundles> = Mutex::new(
+        load_bundles(&[("v10", V10_BUNDLE), ("v11", V11_BUNDLE)]).expect("failed to load bundles")
+    );
 }
 
-fn load_bundles(bundles: &[&[u8]]) -> anyhow::Result<MemoryBlockstore> {
+struct Bundles {
+    blockstore: MemoryBlockstore,
+    /// The manifest of each bundle, keyed by `<version>/<network>` (e.g., `v11/mainnet`).
+    manifests: HashMap<String, Cid>,
+}
+
+fn load_bundles(bundles: &[(&str, &[u8])]) -> anyhow::Result<Bundles> {
     let bs = MemoryBlockstore::new();
-    for bundle in bundles {
+    let mut manifests = HashMap::new();
+    for (version, bundle) in bundles {
         let mut reader = tar::Archive::new(zstd::Decoder::with_buffer(*bundle)?);
         for entry in reader.entries()? {
+            let mut entry = entry?;
+            let network = entry
+                .path()?
+                .to_string_lossy()
+                .trim_start_matches("builtin-actors-")
+                .trim_end_matches(".car")
+                .to_owned();
             // We need to read it to a vec first as we can't send it between threads (async issues).
             let mut car = Vec::new();
-            entry?.read_to_end(&mut car)?;
-            block_on(load_car(&bs, &*car))?;
+            entry.read_to_end(&mut car)?;
+            if let [manifest] = block_on(load_car(&bs, &*car))?[..] {
+                manifests.insert(format!("{version}/{network}"), manifest);
+            }
         }
     }
-    Ok(bs)
+    Ok(Bundles {
+        blockstore: bs,
+        manifests,
+    })
 }
 
 /// Load the bundled actors into the specified blockstore.
 pub fn load_actors(bs: &impl Blockstore) -> anyhow::Result<()> {
-    ACTORS.lock().unwrap().copy_to(bs)
+    ACTORS.lock().unwrap().blockstore.copy_to(bs)
+}
+
+/// Returns the manifest of a bundled actors release for a network (e.g., `("v11", "mainnet")`).
+pub fn bundle_manifest(version: &str, network: &str) -> Option<Cid> {
+    let key = format!("{version}/{network}");
+    ACTORS.lock().unwrap().manifests.get(&key).copied()
+}
+
+/// Returns whether a block is part of the bundled actors.
+pub fn is_bundled(cid: &Cid) -> bool {
+    ACTORS.lock().unwrap().blockstore.has(cid).unwrap_or(false)
 }
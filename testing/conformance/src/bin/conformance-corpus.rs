@@ -0,0 +1,45 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Manages the cache of downloaded test vector corpora (see `fvm_conformance_tests::corpus`).
+//!
+//! Usage:
+//! - `conformance-corpus list` prints the cached releases, as `<tag>@<sha256>`.
+//! - `conformance-corpus fetch <tag>@<sha256>` downloads and verifies a release (unless it's
+//!   already cached), then prints the path of its vectors.
+//!
+//! The cache directory can be set with `CONFORMANCE_CACHE_DIR`.
+
+use std::{env, process};
+
+use anyhow::anyhow;
+use fvm_conformance_tests::corpus::{CorpusCache, Release};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        println!("Error: {err:#}");
+        process::exit(1)
+    }
+}
+
+fn run(args: &[String]) -> anyhow::Result<()> {
+    let cache = CorpusCache::from_env();
+    match args {
+        [cmd] if cmd == "list" => {
+            for release in cache.list()? {
+                println!("{release}");
+            }
+        }
+        [cmd, release] if cmd == "fetch" => {
+            let release: Release = release.parse()?;
+            println!("{}", cache.fetch(&release)?.display());
+        }
+        _ => {
+            return Err(anyhow!(
+                "usage: conformance-corpus list | conformance-corpus fetch <tag>@<sha256>"
+            ))
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Runs test vectors at several network versions and prints a compatibility matrix for each
+//! variant.
+//!
+//! Usage: `conformance-matrix <vector file or directory> <network versions>`, where the network
+//! versions are comma separated (e.g., `21,22,23`). The first network version is the baseline the
+//! others are compared against.
+
+use std::path::{Path, PathBuf};
+use std::{env, process};
+
+use anyhow::{anyhow, Context};
+use fvm::engine::MultiEngine;
+use fvm_conformance_tests::driver::is_runnable;
+use fvm_conformance_tests::matrix::run_variant_matrix;
+use fvm_conformance_tests::vector::MessageVector;
+use fvm_shared::version::NetworkVersion;
+use walkdir::WalkDir;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let config = Config::build(args).unwrap_or_else(|err| {
+        println!("Invalid args: {err}");
+        process::exit(1)
+    });
+
+    match run(&config) {
+        Ok(true) => {}
+        Ok(false) => process::exit(2),
+        Err(err) => {
+            println!("Error running with {config:?}: {err:#}");
+            process::exit(1)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Config {
+    /// A test vector, or a directory of test vectors.
+    path: PathBuf,
+    /// The network versions to run the vectors at.
+    network_versions: Vec<NetworkVersion>,
+}
+
+impl Config {
+    pub fn build(args: Vec<String>) -> anyhow::Result<Self> {
+        if args.len() != 3 {
+            return Err(anyhow!("Expected 2 arguments; got {}", args.len() - 1));
+        }
+
+        let network_versions = args[2]
+            .split(',')
+            .map(|nv| {
+                nv.trim()
+                    .parse::<u32>()
+                    .map(NetworkVersion::from)
+                    .with_context(|| format!("invalid network version {nv:?}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            path: PathBuf::from(&args[1]),
+            network_versions,
+        })
+    }
+}
+
+/// Prints the matrix of every variant, returning false if any of them behaves differently at
+/// different network versions.
+fn run(config: &Config) -> anyhow::Result<bool> {
+    let engines = MultiEngine::new(1);
+    let mut uniform = true;
+    for entry in WalkDir::new(&config.path) {
+        let entry = entry?;
+        if entry.file_type().is_file() && is_runnable(&entry) {
+            uniform &= run_vector(entry.path(), &engines, &config.network_versions)
+                .with_context(|| format!("failed to run vector {}", entry.path().display()))?;
+        }
+    }
+    Ok(uniform)
+}
+
+fn run_vector(
+    path: &Path,
+    engines: &MultiEngine,
+    network_versions: &[NetworkVersion],
+) -> anyhow::Result<bool> {
+    let vector = MessageVector::from_file(path)?;
+    println!("vector {}", path.display());
+    if !vector.is_supported() {
+        println!("  skipped: selector not supported");
+        return Ok(true);
+    }
+
+    let (bs, _) = async_std::task::block_on(vector.seed_blockstore())?;
+    let mut uniform = true;
+    for variant in &vector.preconditions.variants {
+        let matrix = run_variant_matrix(&bs, &vector, variant, engines, network_versions)?;
+        uniform &= matrix.is_uniform();
+        print!("{matrix}");
+    }
+    Ok(uniform)
+}
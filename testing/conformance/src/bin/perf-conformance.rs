@@ -6,7 +6,7 @@ use std::env::var;
 use std::ffi::CString;
 use std::path::Path;
 
-use conformance_tests::vector::{MessageVector, Selector, Variant};
+use conformance_tests::vector::{MessageVector, Variant};
 use conformance_tests::vm::{TestKernel, TestMachine};
 use fvm::executor::{ApplyKind, DefaultExecutor, Executor};
 use fvm::machine::{Engine, EngineConfig};
@@ -34,9 +34,12 @@ fn main() {
             __itt_string_handle_create(handle_cstring.as_ptr()),
         )
     };
-    let skip = !vector.selector.as_ref().map_or(true, Selector::supported);
-    if skip {
-        println!("skipping because selector not supported");
+    if let Some(reason) = vector
+        .selector
+        .as_ref()
+        .and_then(|s| s.unsupported_reason())
+    {
+        println!("skipping because selector not supported: {reason}");
         return;
     }
     let engine = Engine::new(
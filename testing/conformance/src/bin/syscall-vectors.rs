@@ -0,0 +1,42 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Regenerates the golden syscall vectors (see `fvm_conformance_tests::syscall_vectors`), writing
+//! them to `syscall-vectors/` and removing the vectors of syscalls that are no longer covered.
+
+use std::collections::HashSet;
+use std::{fs, process};
+
+use anyhow::Context;
+use fvm::engine::MultiEngine;
+use fvm_conformance_tests::syscall_vectors::{generate, syscall_vectors, to_json, vectors_dir};
+use walkdir::WalkDir;
+
+fn main() {
+    if let Err(err) = run() {
+        println!("Error: {err:#}");
+        process::exit(1)
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let dir = vectors_dir();
+    let engines = MultiEngine::new(1);
+    let mut written = HashSet::new();
+    for vector in syscall_vectors() {
+        let json = generate(&vector, &engines).with_context(|| vector.id())?;
+        let path = dir.join(vector.path());
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, to_json(&json)?)?;
+        println!("wrote {}", path.display());
+        written.insert(path);
+    }
+    for entry in WalkDir::new(&dir) {
+        let path = entry?.into_path();
+        if path.is_file() && !written.contains(&path) {
+            fs::remove_file(&path)?;
+            println!("removed {}", path.display());
+        }
+    }
+    Ok(())
+}
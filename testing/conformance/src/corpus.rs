@@ -0,0 +1,375 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Management of downloaded test vector corpora.
+//!
+//! By default, the runner reads vectors from the `test-vectors` submodule. Alternatively, a
+//! [`CorpusCache`] can fetch a tagged release of the [fvm-test-vectors] repository, check it
+//! against a pinned SHA-256 digest, and unpack it into a local cache directory. Since a release is
+//! identified by its tag and digest, CI can cache the directory deterministically.
+//!
+//! Downloads shell out to `curl`.
+//!
+//! [fvm-test-vectors]: https://github.com/filecoin-project/fvm-test-vectors
+
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context};
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+use tar::EntryType;
+
+/// The repository the corpus is released from.
+pub const REPOSITORY: &str = "https://github.com/filecoin-project/fvm-test-vectors";
+
+/// The environment variable overriding the default cache directory.
+pub const CACHE_DIR_VAR: &str = "CONFORMANCE_CACHE_DIR";
+
+/// The environment variable selecting a release to run, as `<tag>@<sha256>`.
+pub const CORPUS_VAR: &str = "CONFORMANCE_CORPUS";
+
+/// The file recording the digest of an unpacked release, written once it's fully unpacked.
+const DIGEST_FILE: &str = ".sha256";
+
+/// A release of the corpus, pinned to the SHA-256 digest of its archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Release {
+    pub tag: String,
+    pub sha256: [u8; 32],
+}
+
+impl Release {
+    /// Creates a release from its tag and hex-encoded archive digest.
+    pub fn new(tag: &str, sha256: &str) -> anyhow::Result<Self> {
+        if tag.is_empty() || tag.starts_with('.') || tag.contains(['/', '\\']) {
+            bail!("invalid release tag {tag:?}");
+        }
+        let mut digest = [0u8; 32];
+        hex::decode_to_slice(sha256, &mut digest)
+            .with_context(|| format!("invalid sha256 digest {sha256:?}"))?;
+        Ok(Release {
+            tag: tag.to_owned(),
+            sha256: digest,
+        })
+    }
+
+    /// Returns the URL of the release's archive.
+    pub fn url(&self) -> String {
+        format!("{REPOSITORY}/archive/refs/tags/{}.tar.gz", self.tag)
+    }
+}
+
+impl FromStr for Release {
+    type Err = anyhow::Error;
+
+    /// Parses a release as `<tag>@<sha256>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tag, sha256) = s
+            .rsplit_once('@')
+            .ok_or_else(|| anyhow!("expected <tag>@<sha256>, got {s:?}"))?;
+        Release::new(tag, sha256)
+    }
+}
+
+impl fmt::Display for Release {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.tag, hex::encode(self.sha256))
+    }
+}
+
+/// A directory of unpacked releases, one subdirectory per tag.
+#[derive(Clone, Debug)]
+pub struct CorpusCache {
+    dir: PathBuf,
+}
+
+impl CorpusCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        CorpusCache { dir: dir.into() }
+    }
+
+    /// Returns the cache in the directory named by [`CACHE_DIR_VAR`], or in `corpora` under this
+    /// crate by default.
+    pub fn from_env() -> Self {
+        match std::env::var_os(CACHE_DIR_VAR) {
+            Some(dir) => CorpusCache::new(dir),
+            None => CorpusCache::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("corpora")),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Lists the releases in the cache, sorted by tag.
+    pub fn list(&self) -> anyhow::Result<Vec<Release>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).context("failed to read the corpus cache"),
+        };
+        let mut releases = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let Some(tag) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Ok(digest) = fs::read_to_string(path.join(DIGEST_FILE)) else {
+                continue;
+            };
+            releases.push(Release::new(tag, digest.trim())?);
+        }
+        releases.sort_by(|a, b| a.tag.cmp(&b.tag));
+        Ok(releases)
+    }
+
+    /// Returns the directory of the release's vectors, if the release is in the cache.
+    ///
+    /// Fails if a release with the same tag but a different digest is in the cache.
+    pub fn get(&self, release: &Release) -> anyhow::Result<Option<PathBuf>> {
+        let root = self.dir.join(&release.tag);
+        let digest = match fs::read_to_string(root.join(DIGEST_FILE)) {
+            Ok(digest) => digest,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).context("failed to read the cached release's digest"),
+        };
+        if digest.trim() != hex::encode(release.sha256) {
+            bail!(
+                "cached release {} has digest {}, expected {}",
+                release.tag,
+                digest.trim(),
+                hex::encode(release.sha256)
+            );
+        }
+        Ok(Some(vectors_dir(root)))
+    }
+
+    /// Returns the directory of the release's vectors, downloading the release into the cache
+    /// first if needed.
+    pub fn fetch(&self, release: &Release) -> anyhow::Result<PathBuf> {
+        if let Some(path) = self.get(release)? {
+            return Ok(path);
+        }
+        let archive = download(&release.url())?;
+        self.install(release, &archive)
+    }
+
+    /// Checks `archive` (a gzipped tarball of the release) against the release's digest and
+    /// unpacks it into the cache, returning the directory of its vectors.
+    pub fn install(&self, release: &Release, archive: &[u8]) -> anyhow::Result<PathBuf> {
+        let digest: [u8; 32] = Sha256::digest(archive).into();
+        if digest != release.sha256 {
+            bail!(
+                "archive of release {} has digest {}, expected {}",
+                release.tag,
+                hex::encode(digest),
+                hex::encode(release.sha256)
+            );
+        }
+
+        // Unpack into a temporary directory and move it into place once complete, so an
+        // interrupted fetch never leaves a partial release behind.
+        let root = self.dir.join(&release.tag);
+        let tmp = self.dir.join(format!(".{}.partial", release.tag));
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        fs::create_dir_all(&tmp).context("failed to create the corpus cache")?;
+        unpack(archive, &tmp)
+            .with_context(|| format!("failed to unpack release {}", release.tag))?;
+        fs::write(tmp.join(DIGEST_FILE), hex::encode(release.sha256))?;
+        if root.exists() {
+            fs::remove_dir_all(&root)?;
+        }
+        fs::rename(&tmp, &root)?;
+        Ok(vectors_dir(root))
+    }
+}
+
+/// Returns the corpus selected by [`CORPUS_VAR`], fetching it into the default cache (see
+/// [`CorpusCache::from_env`]) if needed, or `None` if the variable isn't set.
+pub fn corpus_from_env() -> anyhow::Result<Option<PathBuf>> {
+    let Ok(release) = std::env::var(CORPUS_VAR) else {
+        return Ok(None);
+    };
+    let release: Release = release.parse()?;
+    CorpusCache::from_env().fetch(&release).map(Some)
+}
+
+/// Releases keep their vectors under `corpus`.
+fn vectors_dir(root: PathBuf) -> PathBuf {
+    let corpus = root.join("corpus");
+    if corpus.is_dir() {
+        corpus
+    } else {
+        root
+    }
+}
+
+fn download(url: &str) -> anyhow::Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", url])
+        .output()
+        .context("failed to run curl")?;
+    if !output.status.success() {
+        bail!(
+            "failed to download {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Unpacks a gzipped tarball into `dest`, stripping the top-level directory that GitHub archives
+/// wrap their contents in. Only regular files and directories are unpacked: links and special files
+/// are rejected, as they could point outside of `dest`.
+fn unpack(archive: &[u8], dest: &Path) -> anyhow::Result<()> {
+    let mut archive = tar::Archive::new(GzDecoder::new(archive));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        match entry.header().entry_type() {
+            EntryType::Regular | EntryType::Continuous | EntryType::Directory => {}
+            // The archive's metadata (e.g., the commit it was built from).
+            EntryType::XGlobalHeader => continue,
+            kind => bail!(
+                "archive entry {} has unsupported type {:?}",
+                path.display(),
+                kind
+            ),
+        }
+        if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            bail!("archive entry {} escapes the archive", path.display());
+        }
+        let relative: PathBuf = path.components().skip(1).collect();
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn release(tag: &str, archive: &[u8]) -> Release {
+        Release {
+            tag: tag.to_owned(),
+            sha256: Sha256::digest(archive).into(),
+        }
+    }
+
+    #[test]
+    fn parse_release() {
+        let digest = "ab".repeat(32);
+        let release: Release = format!("v1.2.3@{digest}").parse().unwrap();
+        assert_eq!(release.tag, "v1.2.3");
+        assert_eq!(release.to_string(), format!("v1.2.3@{digest}"));
+        assert_eq!(
+            release.url(),
+            format!("{REPOSITORY}/archive/refs/tags/v1.2.3.tar.gz")
+        );
+
+        assert!("v1.2.3".parse::<Release>().is_err());
+        assert!("v1.2.3@abcd".parse::<Release>().is_err());
+        assert!(format!("../x@{digest}").parse::<Release>().is_err());
+        assert!(format!("a/b@{digest}").parse::<Release>().is_err());
+    }
+
+    #[test]
+    fn install_and_list() {
+        let dir = std::env::temp_dir().join(format!("corpus-cache-{}", std::process::id()));
+        let cache = CorpusCache::new(&dir);
+        assert!(cache.list().unwrap().is_empty());
+
+        let data = archive(&[
+            ("fvm-test-vectors-v1/README.md", b"readme"),
+            ("fvm-test-vectors-v1/corpus/a/vector.json", b"{}"),
+        ]);
+        let v1 = release("v1", &data);
+        assert_eq!(cache.get(&v1).unwrap(), None);
+
+        // A mismatching archive is rejected before anything is unpacked.
+        let mut bad = v1.clone();
+        bad.sha256[0] ^= 1;
+        assert!(cache.install(&bad, &data).is_err());
+        assert!(cache.list().unwrap().is_empty());
+
+        let path = cache.install(&v1, &data).unwrap();
+        assert_eq!(path, dir.join("v1").join("corpus"));
+        assert_eq!(fs::read(path.join("a/vector.json")).unwrap(), b"{}");
+        assert_eq!(cache.get(&v1).unwrap(), Some(path.clone()));
+        // Fetching a cached release doesn't download it again.
+        assert_eq!(cache.fetch(&v1).unwrap(), path);
+        assert_eq!(cache.list().unwrap(), vec![v1]);
+
+        // A different release with the same tag doesn't match the cached one.
+        assert!(cache.get(&bad).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_escaping_entries() {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(1);
+        header.set_mode(0o644);
+        // `append_data` refuses ".." components, so write the path into the header directly.
+        header.as_old_mut().name[..11].copy_from_slice(b"top/../evil");
+        header.set_cksum();
+        builder.append(&header, &b"x"[..]).unwrap();
+        let data = builder.into_inner().unwrap().finish().unwrap();
+
+        let dir = std::env::temp_dir().join(format!("corpus-escape-{}", std::process::id()));
+        let cache = CorpusCache::new(&dir);
+        assert!(cache.install(&release("v1", &data), &data).is_err());
+        assert!(!dir.join("evil").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn rejects_links() {
+        for kind in [EntryType::Symlink, EntryType::Link] {
+            let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(kind);
+            header.set_size(0);
+            header.set_mode(0o644);
+            builder
+                .append_link(&mut header, "top/corpus/link", "/etc/passwd")
+                .unwrap();
+            let data = builder.into_inner().unwrap().finish().unwrap();
+
+            let dir = std::env::temp_dir().join(format!("corpus-link-{}", std::process::id()));
+            let cache = CorpusCache::new(&dir);
+            assert!(cache.install(&release("v1", &data), &data).is_err());
+            assert!(fs::symlink_metadata(dir.join(".v1.partial/corpus/link")).is_err());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
@@ -1,17 +1,20 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-use std::fmt;
-use std::time::Instant;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context as _, Result};
 use cid::Cid;
 use fmt::Display;
 use fvm::engine::MultiEngine;
 use fvm::executor::{ApplyKind, ApplyRet, DefaultExecutor, Executor};
 use fvm::kernel::Context;
 use fvm::machine::Machine;
+use fvm::state_diff::StateDiff;
 use fvm::state_tree::{ActorState, StateTree};
-use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::{from_slice, CborStore};
 use fvm_shared::address::Protocol;
 use fvm_shared::crypto::signature::SECP_SIG_LEN;
@@ -24,7 +27,7 @@ use regex::Regex;
 use walkdir::DirEntry;
 
 use crate::tracing::TestTraceFun;
-use crate::vector::{MessageVector, Variant};
+use crate::vector::{Capabilities, MessageVector, Variant};
 use crate::vm::{TestKernel, TestMachine, TestStatsRef};
 
 lazy_static! {
@@ -44,6 +47,10 @@ lazy_static! {
         .map(|nv| {
             nv.parse::<u32>().expect("PRICE_NETWORK_VERSION should be a number").into()
         });
+
+    /// If set, the receipts, execution traces, and state diffs of failed vectors are written to
+    /// this directory.
+    static ref FAILURE_DUMP_DIR: Option<PathBuf> = std::env::var_os("FAILURE_DUMP_DIR").map(PathBuf::from);
 }
 
 /// Checks if the file is a runnable vector.
@@ -189,6 +196,105 @@ fn compare_state_roots(bs: &MemoryBlockstore, root: &Cid, vector: &MessageVector
     ))
 }
 
+/// Reports a failed vector variant, dumping its artifacts into [`FAILURE_DUMP_DIR`] (if set).
+fn fail<B: Blockstore>(
+    v: &MessageVector,
+    id: &str,
+    reason: anyhow::Error,
+    rets: &[(Duration, ApplyRet)],
+    bs: &B,
+    roots: Option<(Cid, Cid)>,
+) -> Result<VariantResult> {
+    if let Some(dir) = &*FAILURE_DUMP_DIR {
+        if let Err(err) = dump_failure(dir, v, id, &reason, rets, bs, roots) {
+            log::error!(
+                "failed to dump artifacts of failed vector {}: {:#}",
+                id,
+                err
+            );
+        }
+    }
+    Ok(VariantResult::Failed {
+        id: id.to_owned(),
+        reason,
+    })
+}
+
+/// Writes the artifacts of a failed vector variant into `<dir>/<vector id>/<variant id>/`:
+///
+/// - `failure.txt`: the failure reason.
+/// - `receipts.txt`: the expected and actual receipts of all executed messages.
+/// - `trace.<i>.txt`: the execution trace of the i'th message (if tracing was enabled).
+/// - `state_diff.txt`: the difference between the expected and actual state trees, if the failure
+///   was a state root mismatch.
+fn dump_failure<B: Blockstore>(
+    dir: &Path,
+    v: &MessageVector,
+    variant_id: &str,
+    reason: &anyhow::Error,
+    rets: &[(Duration, ApplyRet)],
+    bs: &B,
+    roots: Option<(Cid, Cid)>,
+) -> Result<()> {
+    let vector_id = v.meta.as_ref().map(|m| m.id.as_str()).unwrap_or("unknown");
+    let dir = dir
+        .join(path_component(vector_id))
+        .join(path_component(variant_id));
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create directory {}", dir.display()))?;
+
+    fs::write(dir.join("failure.txt"), format!("{:?}\n", reason))?;
+
+    let mut receipts = String::new();
+    for (i, (_, ret)) in rets.iter().enumerate() {
+        let expected = v.expected_receipts(i).ok();
+        writeln!(receipts, "msg {}:", i)?;
+        writeln!(receipts, "  expected: {:?}", expected)?;
+        writeln!(receipts, "  actual: {:?}", ret.msg_receipt)?;
+        if let Some(info) = &ret.failure_info {
+            writeln!(receipts, "  failure: {}", info)?;
+        }
+    }
+    fs::write(dir.join("receipts.txt"), receipts)?;
+
+    for (i, (_, ret)) in rets.iter().enumerate() {
+        if !ret.exec_trace.is_empty() {
+            fs::write(
+                dir.join(format!("trace.{}.txt", i)),
+                format!("{:#?}\n", ret.exec_trace),
+            )?;
+        }
+    }
+
+    if let Some((expected, actual)) = roots {
+        let diff = StateDiff::compute(bs, &expected, &actual)
+            .context("failed to compute the state diff")?;
+        fs::write(
+            dir.join("state_diff.txt"),
+            format!("--- expected {}\n+++ actual {}\n{}", expected, actual, diff),
+        )?;
+    }
+
+    log::info!("dumped artifacts of failed vector to {}", dir.display());
+    Ok(())
+}
+
+/// Sanitizes a vector or variant id for use as a single path component, so that ids taken from
+/// untrusted vectors can't escape the dump directory: anything outside `[A-Za-z0-9._-]` becomes an
+/// underscore, as do the otherwise special `.` and `..` components.
+fn path_component(id: &str) -> String {
+    match id {
+        "" | "." | ".." => "_".repeat(id.len().max(1)),
+        _ => id
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' | '-' => c,
+                _ => '_',
+            })
+            .collect(),
+    }
+}
+
 /// Represents the result from running a vector.
 pub enum VariantResult {
     /// The vector succeeded.
@@ -210,6 +316,10 @@ pub fn run_variant(
 ) -> anyhow::Result<VariantResult> {
     let id = variant.id.clone();
 
+    if let Some(reason) = Capabilities::default().variant_unsupported_reason(variant) {
+        return Ok(VariantResult::Skipped { reason, id });
+    }
+
     // We can't expect gas as the final state to match if we apply a price override.
     if PRICE_NETWORK_VERSION.is_some() {
         check_correctness = false;
@@ -221,7 +331,7 @@ pub fn run_variant(
         variant,
         bs,
         stats,
-        trace.is_some(),
+        trace.is_some() || FAILURE_DUMP_DIR.is_some(),
         *PRICE_NETWORK_VERSION,
     )?;
     let engine = engines
@@ -238,6 +348,21 @@ pub fn run_variant(
         )
         .unwrap();
 
+    // The syscall vectors' messages are sent to test actors, which must be preloaded too.
+    if v.is_syscall_vector() {
+        for m in &v.apply_messages {
+            let msg: Message = from_slice(&m.bytes)?;
+            if let Some(actor) = machine.state_tree().get_actor_by_address(&msg.to)? {
+                if machine.builtin_actors().name_by_code(&actor.code).is_none() {
+                    engine
+                        .acquire()
+                        .preload(&actor.code, machine.blockstore())
+                        .with_context(|| format!("failed to preload the code of {}", msg.to))?;
+                }
+            }
+        }
+    }
+
     let mut exec: DefaultExecutor<TestKernel> = DefaultExecutor::new(engine, machine)?;
     let mut rets = Vec::new();
 
@@ -255,18 +380,50 @@ pub fn run_variant(
         let start = Instant::now();
         let ret = match exec.execute_message(msg, ApplyKind::Explicit, raw_length) {
             Ok(ret) => ret,
-            Err(e) => return Ok(VariantResult::Failed { id, reason: e }),
+            Err(e) => return fail(v, &id, e, &rets, exec.blockstore(), None),
         };
+        rets.push((start.elapsed(), ret));
 
         if check_correctness {
-            // Compare the actual receipt with the expected receipt.
-            let expected_receipt = &v.postconditions.receipts[i];
-            if let Err(err) = check_msg_result(expected_receipt, &ret, i) {
-                return Ok(VariantResult::Failed { id, reason: err });
+            // Compare the actual receipt with the expected receipt(s).
+            let ret = &rets.last().unwrap().1;
+            let expected_receipts = match v.expected_receipts(i) {
+                Ok(receipts) => receipts,
+                Err(err) => return fail(v, &id, err, &rets, exec.blockstore(), None),
+            };
+            for expected_receipt in expected_receipts {
+                if let Err(err) = check_msg_result(expected_receipt, ret, i) {
+                    return fail(v, &id, err, &rets, exec.blockstore(), None);
+                }
             }
         }
 
-        rets.push((start.elapsed(), ret));
+        // Check the intermediate state root, if the vector specifies one.
+        if let Some(expected_root) = m.state_root.filter(|_| check_correctness) {
+            let root = match exec.flush() {
+                Ok(cid) => cid,
+                Err(err) => {
+                    let reason = err.context("flushing executor failed");
+                    return fail(v, &id, reason, &rets, exec.blockstore(), None);
+                }
+            };
+            if root != expected_root {
+                let reason = anyhow!(
+                    "wrong state root after msg {}; expected {}, but got {}",
+                    i,
+                    expected_root,
+                    root
+                );
+                return fail(
+                    v,
+                    &id,
+                    reason,
+                    &rets,
+                    exec.blockstore(),
+                    Some((expected_root, root)),
+                );
+            }
+        }
     }
 
     // Flush the machine, obtain the blockstore, and compare the
@@ -274,10 +431,8 @@ pub fn run_variant(
     let final_root = match exec.flush() {
         Ok(cid) => cid,
         Err(err) => {
-            return Ok(VariantResult::Failed {
-                id,
-                reason: err.context("flushing executor failed"),
-            });
+            let reason = err.context("flushing executor failed");
+            return fail(v, &id, reason, &rets, exec.blockstore(), None);
         }
     };
 
@@ -294,10 +449,15 @@ pub fn run_variant(
         let bs = machine.into_store().into_inner();
 
         if let Err(err) = compare_state_roots(&bs, &final_root, v) {
-            return Ok(VariantResult::Failed {
-                id,
-                reason: err.context("comparing state roots failed"),
-            });
+            let roots = (v.postconditions.state_tree.root_cid, final_root);
+            return fail(
+                v,
+                &id,
+                err.context("comparing state roots failed"),
+                &rets,
+                &bs,
+                Some(roots),
+            );
         }
     }
 
@@ -310,3 +470,26 @@ pub fn run_variant(
 
     Ok(VariantResult::Ok { id })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_path_components() {
+        assert_eq!(path_component("msg-apply_1.json"), "msg-apply_1.json");
+        assert_eq!(path_component("../../etc/passwd"), ".._.._etc_passwd");
+        assert_eq!(path_component("/abs"), "_abs");
+        assert_eq!(path_component("a\\b:c"), "a_b_c");
+        assert_eq!(path_component("ünïcode"), "_n_code");
+        assert_eq!(path_component(""), "_");
+        assert_eq!(path_component("."), "_");
+        assert_eq!(path_component(".."), "__");
+
+        let dir = Path::new("/tmp/dump");
+        assert_eq!(
+            dir.join(path_component("..")).join(path_component("../x")),
+            Path::new("/tmp/dump/__/.._x")
+        );
+    }
+}
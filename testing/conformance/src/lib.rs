@@ -4,9 +4,12 @@
 
 pub mod actors;
 pub mod cidjson;
+pub mod corpus;
 pub mod driver;
 pub mod externs;
+pub mod matrix;
 pub mod rand;
+pub mod syscall_vectors;
 pub mod tracing;
 pub mod vector;
 pub mod vm;
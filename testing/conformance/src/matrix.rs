@@ -0,0 +1,223 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Runs test vector variants at several network versions (and their price lists) and compares the
+//! results, producing a compatibility matrix. This is useful when preparing network upgrades, to see
+//! which vectors change behavior (exit codes, gas, or state) from one network version to the next.
+
+use std::fmt::{self, Display};
+
+use anyhow::anyhow;
+use cid::Cid;
+use fvm::engine::MultiEngine;
+use fvm::executor::{ApplyKind, DefaultExecutor, Executor};
+use fvm::machine::Machine;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::from_slice;
+use fvm_shared::address::Protocol;
+use fvm_shared::crypto::signature::SECP_SIG_LEN;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::version::NetworkVersion;
+
+use crate::vector::{MessageVector, Variant};
+use crate::vm::{TestKernel, TestMachine};
+
+/// The results of executing a variant's messages at a single network version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    /// The exit code and gas used of each message.
+    pub receipts: Vec<(ExitCode, u64)>,
+    /// The final state root.
+    pub state_root: Cid,
+}
+
+/// How an [`Outcome`] compares to a reference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// Identical exit codes, gas, and state root.
+    Same,
+    /// Identical exit codes and state root, different gas.
+    GasDiffers,
+    /// Different exit codes or state root.
+    Differs,
+}
+
+impl Outcome {
+    /// Compares this outcome against another one.
+    pub fn compare(&self, other: &Outcome) -> Comparison {
+        let exit_codes = |o: &Outcome| o.receipts.iter().map(|r| r.0).collect::<Vec<_>>();
+        if self == other {
+            Comparison::Same
+        } else if self.state_root == other.state_root && exit_codes(self) == exit_codes(other) {
+            Comparison::GasDiffers
+        } else {
+            Comparison::Differs
+        }
+    }
+}
+
+/// One row of a [`VariantMatrix`].
+#[derive(Debug)]
+pub struct MatrixEntry {
+    pub network_version: NetworkVersion,
+    /// The outcome, or the reason the variant couldn't be executed at this network version.
+    pub result: anyhow::Result<Outcome>,
+}
+
+/// The results of executing a variant at several network versions.
+#[derive(Debug)]
+pub struct VariantMatrix {
+    pub id: String,
+    /// The outcome expected by the vector (at the variant's own network version).
+    pub expected: Outcome,
+    pub entries: Vec<MatrixEntry>,
+}
+
+impl VariantMatrix {
+    /// Returns true if the variant behaves identically at all network versions.
+    pub fn is_uniform(&self) -> bool {
+        let mut outcomes = self.entries.iter().map(|e| e.result.as_ref().ok());
+        match outcomes.next() {
+            Some(Some(first)) => outcomes.all(|o| o == Some(first)),
+            _ => false,
+        }
+    }
+}
+
+impl Display for VariantMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "variant {}:", self.id)?;
+        let baseline = self.entries.first().and_then(|e| e.result.as_ref().ok());
+        for entry in &self.entries {
+            write!(f, "  nv{:<3} ", entry.network_version)?;
+            match &entry.result {
+                Ok(outcome) => {
+                    let vs = |other: Option<&Outcome>| match other.map(|o| outcome.compare(o)) {
+                        Some(Comparison::Same) => "same",
+                        Some(Comparison::GasDiffers) => "gas differs",
+                        Some(Comparison::Differs) => "DIFFERS",
+                        None => "-",
+                    };
+                    let gas: u64 = outcome.receipts.iter().map(|r| r.1).sum();
+                    writeln!(
+                        f,
+                        "gas={:<12} root={} vs expected: {:<12} vs nv{}: {}",
+                        gas,
+                        outcome.state_root,
+                        vs(Some(&self.expected)),
+                        self.entries[0].network_version,
+                        vs(baseline),
+                    )?;
+                }
+                Err(err) => writeln!(f, "error: {:#}", err)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Executes the variant at each of the given network versions (using each version's price list),
+/// without checking correctness, and collects the results.
+pub fn run_variant_matrix(
+    bs: &MemoryBlockstore,
+    v: &MessageVector,
+    variant: &Variant,
+    engines: &MultiEngine,
+    network_versions: &[NetworkVersion],
+) -> anyhow::Result<VariantMatrix> {
+    let expected = Outcome {
+        receipts: v
+            .apply_messages
+            .iter()
+            .enumerate()
+            .map(|(i, _)| {
+                let r = v.expected_receipts(i)?[0];
+                Ok((r.exit_code, r.gas_used))
+            })
+            .collect::<anyhow::Result<_>>()?,
+        state_root: v.postconditions.state_tree.root_cid,
+    };
+    let entries = network_versions
+        .iter()
+        .map(|&nv| {
+            let variant = Variant {
+                nv: nv.into(),
+                ..variant.clone()
+            };
+            MatrixEntry {
+                network_version: nv,
+                result: execute(bs.clone(), v, &variant, engines),
+            }
+        })
+        .collect();
+    Ok(VariantMatrix {
+        id: variant.id.clone(),
+        expected,
+        entries,
+    })
+}
+
+/// Applies the vector's messages, returning their receipts and the final state root.
+fn execute(
+    bs: MemoryBlockstore,
+    v: &MessageVector,
+    variant: &Variant,
+    engines: &MultiEngine,
+) -> anyhow::Result<Outcome> {
+    let machine = TestMachine::new_for_vector(v, variant, bs, None, false, None)?;
+    let engine = engines
+        .get(&machine.context().network)
+        .map_err(|e| anyhow!(e))?;
+    engine.acquire().preload_all(
+        machine.blockstore(),
+        machine.builtin_actors().builtin_actor_codes(),
+    )?;
+
+    let mut exec: DefaultExecutor<TestKernel> = DefaultExecutor::new(engine, machine)?;
+    let mut receipts = Vec::with_capacity(v.apply_messages.len());
+    for m in &v.apply_messages {
+        let msg: Message = from_slice(&m.bytes)?;
+        let mut raw_length = m.bytes.len();
+        if msg.from.protocol() == Protocol::Secp256k1 {
+            // 65 bytes signature + 1 byte type + 3 bytes for field info.
+            raw_length += SECP_SIG_LEN + 4;
+        }
+        let ret = exec.execute_message(msg, ApplyKind::Explicit, raw_length)?;
+        receipts.push((ret.msg_receipt.exit_code, ret.msg_receipt.gas_used));
+    }
+    let state_root = exec.flush()?;
+    Ok(Outcome {
+        receipts,
+        state_root,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_outcomes() {
+        let outcome = |exit_code, gas, root: &[u8]| Outcome {
+            receipts: vec![(ExitCode::OK, 10), (exit_code, gas)],
+            state_root: Cid::new_v1(
+                fvm_ipld_encoding::IPLD_RAW,
+                cid::multihash::Multihash::wrap(0, root).unwrap(),
+            ),
+        };
+        let base = outcome(ExitCode::OK, 20, b"a");
+        assert_eq!(base.compare(&base.clone()), Comparison::Same);
+        assert_eq!(
+            base.compare(&outcome(ExitCode::OK, 21, b"a")),
+            Comparison::GasDiffers
+        );
+        assert_eq!(
+            base.compare(&outcome(ExitCode::USR_FORBIDDEN, 20, b"a")),
+            Comparison::Differs
+        );
+        assert_eq!(
+            base.compare(&outcome(ExitCode::OK, 20, b"b")),
+            Comparison::Differs
+        );
+    }
+}
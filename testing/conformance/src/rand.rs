@@ -4,6 +4,7 @@
 
 use fvm::externs::Rand;
 use fvm_shared::clock::ChainEpoch;
+use fvm_shared::randomness::{draw_randomness, DomainSeparationTag, RANDOMNESS_LENGTH};
 
 use crate::vector::{RandomnessKind, RandomnessMatch, RandomnessRule};
 
@@ -47,6 +48,22 @@ impl ReplayingRand {
         }
         None
     }
+
+    /// Computes the randomness an actor would draw for the purpose `pers` from the recorded (or
+    /// fallback) base randomness, the same way as the SDK does.
+    pub fn draw(
+        &self,
+        kind: RandomnessKind,
+        pers: DomainSeparationTag,
+        epoch: ChainEpoch,
+        entropy: &[u8],
+    ) -> anyhow::Result<[u8; RANDOMNESS_LENGTH]> {
+        let rbase = match kind {
+            RandomnessKind::Chain => self.get_chain_randomness(epoch)?,
+            RandomnessKind::Beacon => self.get_beacon_randomness(epoch)?,
+        };
+        Ok(draw_randomness(&rbase, pers, epoch, entropy))
+    }
 }
 
 impl Rand for ReplayingRand {
@@ -0,0 +1,1377 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Golden execution vectors for the FVM's syscalls.
+//!
+//! Each vector exercises a single syscall. For each of its cases (typically a successful call and
+//! the syscall's principal failure modes), the vector deploys a small test actor whose `invoke`
+//! makes the call, and sends it a message. The test actor returns the syscall's output on success,
+//! and exits with [`ERRNO_EXIT_BASE`] plus the syscall's error number on failure, so the expected
+//! receipts (and gas) record the syscall's behavior.
+//!
+//! The vectors are committed under `syscall-vectors/`, and the `syscall_vectors` test checks them
+//! against this generator and runs them. Changes to a syscall's behavior therefore show up as
+//! vector diffs. Regenerate the vectors with `cargo run --bin syscall-vectors`.
+//!
+//! The conformance test kernel doesn't verify seal, aggregate, replica update and batched PoSt
+//! proofs, nor consensus faults: it only charges for them. Their vectors record that charge, and
+//! the decoding of the syscalls' inputs.
+//!
+//! Syscalls that aren't linked into the conformance test machine (`crypto::verify_signature`,
+//! `actor::upgrade_actor`, `actor::install_actor` and `actor::install_code`) are not covered.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context as _};
+use base64::Engine as _;
+use cid::Cid;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::executor::block_on;
+use fvm::engine::MultiEngine;
+use fvm::executor::{ApplyKind, DefaultExecutor, Executor};
+use fvm::machine::{Machine, Manifest, BURNT_FUNDS_ACTOR_ID};
+use fvm::state_tree::{ActorState, StateTree};
+use fvm::{init_actor, system_actor};
+use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
+use fvm_ipld_car::CarHeader;
+use fvm_ipld_encoding::{from_slice, to_vec, CborStore, RawBytes, DAG_CBOR};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::bigint_ser::BigIntSer;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::crypto::signature::SECP_SIG_LEN;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::piece::{PaddedPieceSize, PieceInfo};
+use fvm_shared::randomness::Randomness;
+use fvm_shared::sector::{
+    AggregateSealVerifyProofAndInfos, RegisteredAggregateProof, RegisteredPoStProof,
+    RegisteredSealProof, RegisteredUpdateProof, ReplicaUpdateInfo, SealVerifyInfo, SectorID,
+    StoragePower, WindowPoStVerifyInfo,
+};
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::sys::out::{crypto, ipld, network, send, vm};
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::well_known::WellKnownActor;
+use fvm_shared::{commcid, ActorID, IPLD_RAW};
+use ipld_core::ipld::Ipld;
+use multihash_codetable::{Code, MultihashDigest};
+use serde_json::json;
+
+use crate::actors::{bundle_manifest, is_bundled, load_actors};
+use crate::vector::{MessageVector, PostConditions, PreConditions, StateTreeVector, Variant};
+use crate::vm::{TestKernel, TestMachine};
+
+/// The generator recorded in the metadata of the vectors (see [`MessageVector::is_syscall_vector`]).
+pub const GENERATOR: &str = "fvm_conformance_tests::syscall_vectors";
+
+/// The exit code of a test actor whose syscall failed is this plus the syscall's error number.
+pub const ERRNO_EXIT_BASE: u32 = 32;
+
+/// The network version the vectors are generated (and run) at, unless a vector needs a later one
+/// (see [`SyscallVector::at`]).
+pub const NETWORK_VERSION: NetworkVersion = NetworkVersion::V21;
+/// The epoch the vectors are generated (and run) at.
+pub const EPOCH: ChainEpoch = 2000;
+const BASE_FEE: u64 = 100;
+const GAS_LIMIT: u64 = 10_000_000_000;
+
+/// The ID of the account sending the messages.
+pub const SENDER_ID: ActorID = 100;
+/// The ID of an account with a delegated (f4) address.
+pub const DELEGATED_ID: ActorID = 101;
+/// The ID of the test actor of a vector's first case (the next cases follow).
+pub const FIRST_CASE_ID: ActorID = 1000;
+/// An actor ID that isn't assigned.
+const MISSING_ID: ActorID = 9999;
+/// The balance of the test actors, in attoFIL.
+const CASE_BALANCE: u64 = 1000;
+/// The method the test actors are invoked with.
+const CASE_METHOD: u64 = 2;
+
+/// Where the test actors write their output (the return value of a case).
+const OUT: u32 = 256;
+/// Where the test actors' inputs are placed. Inputs also use the following 1 KiB slots, `IN2`...
+const IN: u32 = 1024;
+const IN2: u32 = 2048;
+const IN3: u32 = 3072;
+const IN4: u32 = 4096;
+
+/// The wasm signatures of the syscalls, by module and name. Syscalls returning a value take a
+/// pointer to write it to as their first parameter.
+#[rustfmt::skip]
+const SYSCALLS: &[(&str, &str, &str)] = &[
+    ("vm", "exit", "(param i32 i32 i32 i32) (result i32)"),
+    ("vm", "message_context", "(param i32) (result i32)"),
+    ("network", "context", "(param i32) (result i32)"),
+    ("network", "tipset_cid", "(param i32 i64 i32 i32) (result i32)"),
+    ("network", "total_fil_circ_supply", "(param i32) (result i32)"),
+    ("network", "summary", "(param i32) (result i32)"),
+    ("ipld", "block_open", "(param i32 i32) (result i32)"),
+    ("ipld", "block_create", "(param i32 i64 i32 i32) (result i32)"),
+    ("ipld", "block_read", "(param i32 i32 i32 i32 i32) (result i32)"),
+    ("ipld", "block_stat", "(param i32 i32) (result i32)"),
+    ("ipld", "block_link", "(param i32 i32 i64 i32 i32 i32) (result i32)"),
+    ("ipld", "block_quota", "(param i32) (result i32)"),
+    ("self", "root", "(param i32 i32 i32) (result i32)"),
+    ("self", "set_root", "(param i32) (result i32)"),
+    ("self", "current_balance", "(param i32) (result i32)"),
+    ("self", "self_destruct", "(param i32) (result i32)"),
+    ("actor", "resolve_address", "(param i32 i32 i32) (result i32)"),
+    ("actor", "lookup_delegated_address", "(param i32 i64 i32 i32) (result i32)"),
+    ("actor", "get_actor_code_cid", "(param i32 i64 i32 i32) (result i32)"),
+    ("actor", "next_actor_address", "(param i32 i32 i32) (result i32)"),
+    ("actor", "create_actor", "(param i64 i32 i32 i32) (result i32)"),
+    ("actor", "get_builtin_actor_type", "(param i32 i32) (result i32)"),
+    ("actor", "get_code_cid_for_type", "(param i32 i32 i32 i32) (result i32)"),
+    ("actor", "balance_of", "(param i32 i64) (result i32)"),
+    ("crypto", "verify_bls_aggregate", "(param i32 i32 i32 i32 i32 i32) (result i32)"),
+    ("crypto", "recover_secp_public_key", "(param i32 i32 i32) (result i32)"),
+    ("crypto", "hash", "(param i32 i64 i32 i32 i32 i32) (result i32)"),
+    ("crypto", "compute_unsealed_sector_cid", "(param i32 i64 i32 i32 i32 i32) (result i32)"),
+    ("crypto", "verify_post", "(param i32 i32 i32) (result i32)"),
+    ("crypto", "verify_consensus_fault", "(param i32 i32 i32 i32 i32 i32 i32) (result i32)"),
+    ("crypto", "verify_aggregate_seals", "(param i32 i32 i32) (result i32)"),
+    ("crypto", "verify_replica_update", "(param i32 i32 i32) (result i32)"),
+    ("crypto", "batch_verify_seals", "(param i32 i32 i32) (result i32)"),
+    ("crypto", "verify_seal_batch", "(param i32 i32 i32) (result i32)"),
+    ("crypto", "verify_post_batch", "(param i32 i32 i32) (result i32)"),
+    ("event", "emit_event", "(param i32 i32 i32 i32 i32 i32) (result i32)"),
+    ("rand", "get_chain_randomness", "(param i32 i64) (result i32)"),
+    ("rand", "get_beacon_randomness", "(param i32 i64) (result i32)"),
+    ("gas", "charge", "(param i32 i32 i64) (result i32)"),
+    ("gas", "available", "(param i32) (result i32)"),
+    ("send", "send", "(param i32 i32 i32 i64 i32 i64 i64 i64 i64) (result i32)"),
+    ("debug", "log", "(param i32 i32) (result i32)"),
+    ("debug", "log_structured", "(param i32 i32 i32 i32 i32) (result i32)"),
+    ("debug", "enabled", "(param i32) (result i32)"),
+    ("debug", "store_artifact", "(param i32 i32 i32 i32) (result i32)"),
+];
+
+/// The golden vector of a syscall, or of a group of instructions (under the `wasm` module).
+pub struct SyscallVector {
+    /// The syscall's module (e.g., `ipld`).
+    pub module: &'static str,
+    /// The syscall's name (e.g., `block_open`).
+    pub name: &'static str,
+    pub cases: Vec<Case>,
+    /// The network version the vector is generated (and run) at.
+    pub network_version: NetworkVersion,
+}
+
+impl SyscallVector {
+    fn new(module: &'static str, name: &'static str, cases: Vec<Case>) -> Self {
+        Self {
+            module,
+            name,
+            cases,
+            network_version: NETWORK_VERSION,
+        }
+    }
+
+    /// Generates the vector at the given network version, for syscalls that are only linked from
+    /// that version onwards.
+    fn at(mut self, network_version: NetworkVersion) -> Self {
+        self.network_version = network_version;
+        self
+    }
+
+    /// The vector's ID (e.g., `syscall-ipld-block_open`).
+    pub fn id(&self) -> String {
+        format!("syscall-{}-{}", self.module, self.name)
+    }
+
+    /// The path of the vector, relative to the vectors' directory.
+    pub fn path(&self) -> PathBuf {
+        Path::new(self.module).join(format!("{}.json", self.name))
+    }
+}
+
+/// A call to a syscall, made by a test actor.
+pub struct Case {
+    pub name: &'static str,
+    /// The body of the test actor's `invoke`: instructions leaving the error number of the
+    /// syscall on the stack. They may use the local `$id` and the memory below [`OUT`] as scratch
+    /// space.
+    body: String,
+    /// The inputs placed in the test actor's memory, by offset.
+    data: Vec<(u32, Vec<u8>)>,
+    /// An expression computing how many bytes of output (from [`OUT`]) to return on success.
+    ret_len: String,
+}
+
+impl Case {
+    fn new(name: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            name,
+            body: body.into(),
+            data: Vec::new(),
+            ret_len: "(i32.const 0)".into(),
+        }
+    }
+
+    /// Places an input in the test actor's memory.
+    fn data(mut self, offset: u32, data: impl Into<Vec<u8>>) -> Self {
+        self.data.push((offset, data.into()));
+        self
+    }
+
+    /// Returns `len` bytes of output.
+    fn returns(mut self, len: usize) -> Self {
+        self.ret_len = format!("(i32.const {len})");
+        self
+    }
+
+    /// Returns a length prefixed output: the length written (by the syscall) at [`OUT`], followed
+    /// by that many bytes.
+    fn returns_sized(mut self) -> Self {
+        self.ret_len = format!("(i32.add (i32.const 4) (i32.load (i32.const {OUT})))");
+        self
+    }
+
+    /// The test actor's code, in the wasm text format.
+    fn wat(&self) -> String {
+        let mut wat = String::from("(module\n");
+        for (module, name, sig) in SYSCALLS {
+            let func = format!("${module}.{name} ");
+            if self.body.contains(&func) || matches!(*name, "exit" | "block_create") {
+                wat += &format!("  (import \"{module}\" \"{name}\" (func {func}{sig}))\n");
+            }
+        }
+        wat += "  (memory (export \"memory\") 1)\n";
+        for (offset, data) in &self.data {
+            let escaped: String = data.iter().map(|b| format!("\\{b:02x}")).collect();
+            wat += &format!("  (data (i32.const {offset}) \"{escaped}\")\n");
+        }
+        wat += &format!(
+            r#"  (func (export "invoke") (param $params i32) (result i32)
+    (local $errno i32)
+    (local $id i32)
+    {body}
+    local.set $errno
+    (if (local.get $errno)
+      (then
+        (call $vm.exit (i32.add (i32.const {ERRNO_EXIT_BASE}) (local.get $errno))
+          (i32.const 0) (i32.const 0) (i32.const 0))
+        unreachable))
+    (local.set $id (i32.const 0))
+    (if (i32.ne {ret_len} (i32.const 0))
+      (then
+        (drop (call $ipld.block_create (i32.const 0) (i64.const {IPLD_RAW}) (i32.const {OUT})
+          {ret_len}))
+        (local.set $id (i32.load (i32.const 0)))))
+    (local.get $id)))
+"#,
+            body = self.body,
+            ret_len = self.ret_len,
+        );
+        wat
+    }
+}
+
+/// Creates a block with the given codec from an input, storing its ID in `$id`.
+fn create_block(codec: u64, offset: u32, len: usize) -> String {
+    format!(
+        "(drop (call $ipld.block_create (i32.const 8) (i64.const {codec}) (i32.const {offset}) \
+         (i32.const {len}))) (local.set $id (i32.load (i32.const 8)))"
+    )
+}
+
+fn cid_of(codec: u64, data: &[u8]) -> Cid {
+    Cid::new_v1(codec, Code::Blake2b256.digest(data))
+}
+
+/// The (CBOR) state of every test actor.
+const CASE_STATE: &[u8] = &[0x80];
+const BLAKE2B_256: u64 = 0xb220;
+
+fn sender_address() -> Address {
+    Address::new_secp256k1(&[4; 65]).unwrap()
+}
+
+fn delegated_address() -> Address {
+    Address::new_delegated(10, &[0xfe; 20]).unwrap()
+}
+
+/// Returns the golden vectors of all syscalls.
+pub fn syscall_vectors() -> Vec<SyscallVector> {
+    let missing_cid = cid_of(DAG_CBOR, b"missing").to_bytes();
+    let not_builtin_cid = cid_of(IPLD_RAW, b"not builtin").to_bytes();
+    let invalid_cid = vec![0xff; 8];
+    let comm_p = commcid::data_commitment_v1_to_cid(&[0; 32]).unwrap();
+    let comm_r = commcid::replica_commitment_v1_to_cid(&[0; 32]).unwrap();
+    let seal_info = SealVerifyInfo {
+        registered_proof: RegisteredSealProof::StackedDRG2KiBV1P1,
+        sector_id: SectorID {
+            miner: 1000,
+            number: 1,
+        },
+        deal_ids: vec![],
+        randomness: Randomness(vec![1; 32]),
+        interactive_randomness: Randomness(vec![2; 32]),
+        proof: vec![3; 192],
+        sealed_cid: comm_r,
+        unsealed_cid: comm_p,
+    };
+    let post_info = WindowPoStVerifyInfo {
+        randomness: Randomness(vec![1; 32]),
+        proofs: vec![fvm_shared::sector::PoStProof {
+            post_proof: RegisteredPoStProof::StackedDRGWindow2KiBV1P1,
+            proof_bytes: vec![3; 192],
+        }],
+        challenged_sectors: vec![],
+        prover: 1000,
+    };
+
+    let ok_exit = |code: u32| {
+        format!("(call $vm.exit (i32.const {code}) (i32.const 0) (i32.const {IN}) (i32.const 7)) unreachable")
+    };
+    let message_context = |ret: u32| format!("(call $vm.message_context (i32.const {ret}))");
+    let tipset_cid = |epoch: ChainEpoch| {
+        format!("(call $network.tipset_cid (i32.const {OUT}) (i64.const {epoch}) (i32.const 260) (i32.const 100))")
+    };
+    let block_create = |codec: u64, len: usize| {
+        format!("(call $ipld.block_create (i32.const {OUT}) (i64.const {codec}) (i32.const {IN}) (i32.const {len}))")
+    };
+    let block_read = |id: &str, offset: u32, len: u32| {
+        format!("(call $ipld.block_read (i32.const {OUT}) {id} (i32.const {offset}) (i32.const 260) (i32.const {len}))")
+    };
+    let block_link = |id: &str, hash: u64, len: u32| {
+        format!("(call $ipld.block_link (i32.const {OUT}) {id} (i64.const {hash}) (i32.const 32) (i32.const 260) (i32.const {len}))")
+    };
+    let self_root =
+        |len: u32| format!("(call $self.root (i32.const {OUT}) (i32.const 260) (i32.const {len}))");
+    let resolve_address = |len: usize| {
+        format!(
+            "(call $actor.resolve_address (i32.const {OUT}) (i32.const {IN}) (i32.const {len}))"
+        )
+    };
+    let lookup_delegated = |id: ActorID, len: u32| {
+        format!("(call $actor.lookup_delegated_address (i32.const {OUT}) (i64.const {id}) (i32.const 260) (i32.const {len}))")
+    };
+    let actor_code_cid = |id: ActorID, len: u32| {
+        format!("(call $actor.get_actor_code_cid (i32.const {OUT}) (i64.const {id}) (i32.const 260) (i32.const {len}))")
+    };
+    let next_actor_address = |len: u32| {
+        format!(
+            "(call $actor.next_actor_address (i32.const {OUT}) (i32.const 260) (i32.const {len}))"
+        )
+    };
+    let code_cid_for_type = |typ: i32, len: u32| {
+        format!("(call $actor.get_code_cid_for_type (i32.const {OUT}) (i32.const {typ}) (i32.const 260) (i32.const {len}))")
+    };
+    let balance_of =
+        |id: ActorID| format!("(call $actor.balance_of (i32.const {OUT}) (i64.const {id}))");
+    let verify_bls_aggregate = |signers: u32| {
+        format!("(call $crypto.verify_bls_aggregate (i32.const {OUT}) (i32.const {signers}) (i32.const {IN}) (i32.const {IN2}) (i32.const {IN3}) (i32.const {IN4}))")
+    };
+    let recover_secp = format!(
+        "(call $crypto.recover_secp_public_key (i32.const {OUT}) (i32.const {IN}) (i32.const {IN2}))"
+    );
+    let hash = |code: u64, len: u32| {
+        format!("(call $crypto.hash (i32.const {OUT}) (i64.const {code}) (i32.const {IN}) (i32.const 5) (i32.const 260) (i32.const {len}))")
+    };
+    let unsealed_cid = |proof: i64, len: usize| {
+        format!("(call $crypto.compute_unsealed_sector_cid (i32.const {OUT}) (i64.const {proof}) (i32.const {IN}) (i32.const {len}) (i32.const 260) (i32.const 100))")
+    };
+    let verify = |name: &str, len: usize| {
+        format!("(call $crypto.{name} (i32.const {OUT}) (i32.const {IN}) (i32.const {len}))")
+    };
+    let verify_batch = |name: &str, len: usize| {
+        format!("(call $crypto.{name} (i32.const {IN}) (i32.const {len}) (i32.const {OUT}))")
+    };
+    let consensus_fault = |h1_len: u32| {
+        format!("(call $crypto.verify_consensus_fault (i32.const {OUT}) (i32.const {IN}) (i32.const {h1_len}) (i32.const {IN2}) (i32.const 2) (i32.const {IN3}) (i32.const 0))")
+    };
+    let emit_event = |entries: u32, key_len: u32, val_len: u32| {
+        format!("(call $event.emit_event (i32.const {IN}) (i32.const {entries}) (i32.const {IN2}) (i32.const {key_len}) (i32.const {IN3}) (i32.const {val_len}))")
+    };
+    let randomness = |name: &str, epoch: ChainEpoch| {
+        format!("(call $rand.{name} (i32.const {OUT}) (i64.const {epoch}))")
+    };
+    let gas_charge = |len: usize, amount: i64| {
+        format!("(call $gas.charge (i32.const {IN}) (i32.const {len}) (i64.const {amount}))")
+    };
+    let send = |len: usize, method: u64, value: u64| {
+        format!("(call $send.send (i32.const {OUT}) (i32.const {IN}) (i32.const {len}) (i64.const {method}) (i32.const 0) (i64.const 0) (i64.const {value}) (i64.const -1) (i64.const 0))")
+    };
+    let debug_log =
+        |offset: u32, len: u32| format!("(call $debug.log (i32.const {offset}) (i32.const {len}))");
+    let log_structured = |fields_len: usize| {
+        format!("(call $debug.log_structured (i32.const 1) (i32.const {IN}) (i32.const 5) (i32.const {IN2}) (i32.const {fields_len}))")
+    };
+    let store_artifact = |name_len: usize| {
+        format!("(call $debug.store_artifact (i32.const {IN}) (i32.const {name_len}) (i32.const {IN2}) (i32.const 4))")
+    };
+
+    let sender = sender_address().to_bytes();
+    let unknown = Address::new_secp256k1(&[5; 65]).unwrap().to_bytes();
+    let sender_id = Address::new_id(SENDER_ID).to_bytes();
+    let missing_id = Address::new_id(MISSING_ID).to_bytes();
+    let delegated = delegated_address().to_bytes();
+    let unassigned_delegated = Address::new_delegated(10, &[0xfd; 20]).unwrap().to_bytes();
+    let other_namespace = Address::new_delegated(1234, &[0xfd; 20])
+        .unwrap()
+        .to_bytes();
+    let unassigned_robust = Address::new_actor(b"unassigned").to_bytes();
+    let mut bls_zero = [0u8; 48];
+    bls_zero[0] = 192;
+    let bls_zero = Address::new_bls(&bls_zero).unwrap().to_bytes();
+    let pieces = to_vec(&vec![PieceInfo {
+        size: PaddedPieceSize(2048),
+        cid: comm_p,
+    }])
+    .unwrap();
+    let post = to_vec(&post_info).unwrap();
+    let aggregate = to_vec(&AggregateSealVerifyProofAndInfos {
+        miner: 1000,
+        seal_proof: RegisteredSealProof::StackedDRG2KiBV1P1,
+        aggregate_proof: RegisteredAggregateProof::SnarkPackV2,
+        proof: vec![3; 192],
+        infos: vec![],
+    })
+    .unwrap();
+    let replica_update = to_vec(&ReplicaUpdateInfo {
+        update_proof_type: RegisteredUpdateProof::StackedDRG2KiBV1,
+        old_sealed_cid: comm_r,
+        new_sealed_cid: comm_r,
+        new_unsealed_cid: comm_p,
+        proof: vec![3; 192],
+    })
+    .unwrap();
+    let seals = to_vec(&vec![seal_info]).unwrap();
+    let posts = to_vec(&vec![post_info]).unwrap();
+    // A BLS public key (the G1 generator) and a signature (the G2 generator) that don't match.
+    let bls_key = hex::decode("97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb").unwrap();
+    let bls_sig = hex::decode("93e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8").unwrap();
+    // A secp256k1 signature whose r is the x coordinate of the generator: it recovers to some key.
+    let secp_sig = |recovery_id: u8| {
+        let mut sig =
+            hex::decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        sig.extend([0; 31]);
+        sig.extend([1, recovery_id]);
+        sig
+    };
+    // An event with one raw entry, keyed "a".
+    let event_entry = |codec: u64, key_len: u32| {
+        let mut entry = 3u64.to_le_bytes().to_vec();
+        entry.extend(codec.to_le_bytes());
+        entry.extend(key_len.to_le_bytes());
+        entry.extend(4u32.to_le_bytes());
+        entry
+    };
+    let structured_fields = to_vec(&vec![("key", "value")]).unwrap();
+
+    vec![
+        SyscallVector::new(
+            "vm",
+            "exit",
+            vec![
+                Case::new("user_code", ok_exit(42)).data(IN, "failure"),
+                Case::new("system_code", ok_exit(7)).data(IN, "failure"),
+                Case::new(
+                    "return_block",
+                    format!(
+                        "{} (call $vm.exit (i32.const 0) (local.get $id) (i32.const 0) (i32.const 0)) unreachable",
+                        create_block(IPLD_RAW, IN, 5)
+                    ),
+                )
+                .data(IN, "hello"),
+            ],
+        ),
+        SyscallVector::new(
+            "vm",
+            "message_context",
+            vec![
+                Case::new("ok", message_context(OUT)).returns(size_of::<vm::MessageContext>()),
+                Case::new("out_of_bounds", message_context(65530)),
+            ],
+        ),
+        SyscallVector::new(
+            "network",
+            "context",
+            vec![Case::new("ok", format!("(call $network.context (i32.const {OUT}))"))
+                .returns(size_of::<network::NetworkContext>())],
+        ),
+        SyscallVector::new(
+            "network",
+            "tipset_cid",
+            vec![
+                Case::new("negative_epoch", tipset_cid(-1)),
+                Case::new("current_epoch", tipset_cid(EPOCH)),
+                Case::new("future_epoch", tipset_cid(EPOCH + 1)),
+            ],
+        ),
+        SyscallVector::new(
+            "network",
+            "total_fil_circ_supply",
+            vec![Case::new(
+                "ok",
+                format!("(call $network.total_fil_circ_supply (i32.const {OUT}))"),
+            )
+            .returns(size_of::<fvm_shared::sys::TokenAmount>())],
+        ),
+        SyscallVector::new(
+            "network",
+            "summary",
+            vec![Case::new("ok", format!("(call $network.summary (i32.const {OUT}))"))
+                .returns(size_of::<network::NetworkSummary>())],
+        )
+        .at(NetworkVersion::V26),
+        SyscallVector::new(
+            "ipld",
+            "block_create",
+            vec![
+                Case::new("dag_cbor", block_create(DAG_CBOR, 3))
+                    .data(IN, [0x82, 0x01, 0x02])
+                    .returns(4),
+                Case::new("raw", block_create(IPLD_RAW, 3))
+                    .data(IN, "raw")
+                    .returns(4),
+                Case::new("unsupported_codec", block_create(0x70, 3)).data(IN, "raw"),
+                Case::new("invalid_cbor", block_create(DAG_CBOR, 1)).data(IN, [0xff]),
+            ],
+        ),
+        SyscallVector::new(
+            "ipld",
+            "block_open",
+            vec![
+                Case::new(
+                    "ok",
+                    format!(
+                        "(drop {}) (call $ipld.block_open (i32.const {OUT}) (i32.const 260))",
+                        self_root(100)
+                    ),
+                )
+                .returns(size_of::<ipld::IpldOpen>()),
+                Case::new(
+                    "not_found",
+                    format!("(call $ipld.block_open (i32.const {OUT}) (i32.const {IN}))"),
+                )
+                .data(IN, missing_cid.clone()),
+                Case::new(
+                    "invalid_cid",
+                    format!("(call $ipld.block_open (i32.const {OUT}) (i32.const {IN}))"),
+                )
+                .data(IN, invalid_cid.clone()),
+            ],
+        ),
+        SyscallVector::new(
+            "ipld",
+            "block_read",
+            vec![
+                Case::new(
+                    "ok",
+                    format!("{} {}", create_block(IPLD_RAW, IN, 11), block_read("(local.get $id)", 0, 11)),
+                )
+                .data(IN, "hello world")
+                .returns(4 + 11),
+                Case::new(
+                    "partial",
+                    format!("{} {}", create_block(IPLD_RAW, IN, 11), block_read("(local.get $id)", 6, 3)),
+                )
+                .data(IN, "hello world")
+                .returns(4 + 3),
+                Case::new(
+                    "offset_past_end",
+                    format!("{} {}", create_block(IPLD_RAW, IN, 11), block_read("(local.get $id)", 20, 3)),
+                )
+                .data(IN, "hello world")
+                .returns(4),
+                Case::new("invalid_handle", block_read("(i32.const 99)", 0, 11)),
+            ],
+        ),
+        SyscallVector::new(
+            "ipld",
+            "block_stat",
+            vec![
+                Case::new(
+                    "ok",
+                    format!(
+                        "{} (call $ipld.block_stat (i32.const {OUT}) (local.get $id))",
+                        create_block(DAG_CBOR, IN, 3)
+                    ),
+                )
+                .data(IN, [0x82, 0x01, 0x02])
+                .returns(size_of::<ipld::IpldStat>()),
+                Case::new(
+                    "invalid_handle",
+                    format!("(call $ipld.block_stat (i32.const {OUT}) (i32.const 99))"),
+                ),
+            ],
+        ),
+        SyscallVector::new(
+            "ipld",
+            "block_link",
+            vec![
+                Case::new(
+                    "ok",
+                    format!(
+                        "{} {}",
+                        create_block(DAG_CBOR, IN, 3),
+                        block_link("(local.get $id)", BLAKE2B_256, 100)
+                    ),
+                )
+                .data(IN, [0x82, 0x01, 0x02])
+                .returns_sized(),
+                Case::new(
+                    "unsupported_hash",
+                    format!(
+                        "{} {}",
+                        create_block(DAG_CBOR, IN, 3),
+                        block_link("(local.get $id)", 0x1234, 100)
+                    ),
+                )
+                .data(IN, [0x82, 0x01, 0x02]),
+                Case::new(
+                    "buffer_too_small",
+                    format!(
+                        "{} {}",
+                        create_block(DAG_CBOR, IN, 3),
+                        block_link("(local.get $id)", BLAKE2B_256, 8)
+                    ),
+                )
+                .data(IN, [0x82, 0x01, 0x02]),
+                Case::new("invalid_handle", block_link("(i32.const 99)", BLAKE2B_256, 100)),
+            ],
+        ),
+        SyscallVector::new(
+            "ipld",
+            "block_quota",
+            vec![Case::new("ok", format!("(call $ipld.block_quota (i32.const {OUT}))"))
+                .returns(size_of::<ipld::IpldQuota>())],
+        )
+        .at(NetworkVersion::V26),
+        SyscallVector::new(
+            "self",
+            "root",
+            vec![
+                Case::new("ok", self_root(100)).returns_sized(),
+                Case::new("buffer_too_small", self_root(8)),
+            ],
+        ),
+        SyscallVector::new(
+            "self",
+            "set_root",
+            vec![
+                Case::new(
+                    "ok",
+                    format!(
+                        "{} (drop {}) (call $self.set_root (i32.const 260))",
+                        create_block(DAG_CBOR, IN, 2),
+                        block_link("(local.get $id)", BLAKE2B_256, 100)
+                    ),
+                )
+                .data(IN, [0x81, 0x01]),
+                Case::new("unreachable_cid", format!("(call $self.set_root (i32.const {IN}))"))
+                    .data(IN, missing_cid.clone()),
+                Case::new("invalid_cid", format!("(call $self.set_root (i32.const {IN}))"))
+                    .data(IN, invalid_cid.clone()),
+            ],
+        ),
+        SyscallVector::new(
+            "self",
+            "current_balance",
+            vec![Case::new("ok", format!("(call $self.current_balance (i32.const {OUT}))"))
+                .returns(size_of::<fvm_shared::sys::TokenAmount>())],
+        ),
+        SyscallVector::new(
+            "self",
+            "self_destruct",
+            vec![
+                Case::new("burn_funds", "(call $self.self_destruct (i32.const 1))"),
+                Case::new("unspent_funds", "(call $self.self_destruct (i32.const 0))"),
+            ],
+        ),
+        SyscallVector::new(
+            "actor",
+            "resolve_address",
+            vec![
+                Case::new("ok", resolve_address(sender.len()))
+                    .data(IN, sender.clone())
+                    .returns(8),
+                Case::new("id_address", resolve_address(sender_id.len()))
+                    .data(IN, sender_id.clone())
+                    .returns(8),
+                // ID addresses resolve without checking that the actor exists.
+                Case::new("missing_id_address", resolve_address(missing_id.len()))
+                    .data(IN, missing_id.clone())
+                    .returns(8),
+                Case::new("delegated", resolve_address(delegated.len()))
+                    .data(IN, delegated)
+                    .returns(8),
+                Case::new("not_found", resolve_address(unknown.len())).data(IN, unknown),
+                Case::new("unassigned_delegated", resolve_address(unassigned_delegated.len()))
+                    .data(IN, unassigned_delegated.clone()),
+                Case::new("invalid_address", resolve_address(2)).data(IN, [0x09, 0x01]),
+            ],
+        ),
+        SyscallVector::new(
+            "actor",
+            "lookup_delegated_address",
+            vec![
+                Case::new("ok", lookup_delegated(DELEGATED_ID, 100)).returns_sized(),
+                Case::new("no_delegated_address", lookup_delegated(SENDER_ID, 100)).returns(4),
+                Case::new("not_found", lookup_delegated(MISSING_ID, 100)).returns(4),
+                Case::new("buffer_too_small", lookup_delegated(DELEGATED_ID, 4)),
+            ],
+        ),
+        SyscallVector::new(
+            "actor",
+            "get_actor_code_cid",
+            vec![
+                Case::new("ok", actor_code_cid(SENDER_ID, 100)).returns_sized(),
+                Case::new("not_found", actor_code_cid(MISSING_ID, 100)),
+                Case::new("buffer_too_small", actor_code_cid(SENDER_ID, 8)),
+            ],
+        ),
+        SyscallVector::new(
+            "actor",
+            "next_actor_address",
+            vec![
+                Case::new("ok", next_actor_address(100)).returns_sized(),
+                Case::new("buffer_too_small", next_actor_address(1)),
+            ],
+        ),
+        SyscallVector::new(
+            "actor",
+            "create_actor",
+            vec![Case::new(
+                "forbidden",
+                format!(
+                    "(drop {}) (call $actor.create_actor (i64.const 5000) (i32.const 260) (i32.const 0) (i32.const 0))",
+                    actor_code_cid(SENDER_ID, 100)
+                ),
+            )],
+        ),
+        SyscallVector::new(
+            "actor",
+            "get_builtin_actor_type",
+            vec![
+                Case::new(
+                    "account",
+                    format!(
+                        "(drop {}) (call $actor.get_builtin_actor_type (i32.const {OUT}) (i32.const 260))",
+                        actor_code_cid(SENDER_ID, 100)
+                    ),
+                )
+                .returns(4),
+                Case::new(
+                    "not_builtin",
+                    format!("(call $actor.get_builtin_actor_type (i32.const {OUT}) (i32.const {IN}))"),
+                )
+                .data(IN, not_builtin_cid)
+                .returns(4),
+                Case::new(
+                    "invalid_cid",
+                    format!("(call $actor.get_builtin_actor_type (i32.const {OUT}) (i32.const {IN}))"),
+                )
+                .data(IN, invalid_cid.clone()),
+            ],
+        ),
+        SyscallVector::new(
+            "actor",
+            "get_code_cid_for_type",
+            vec![
+                // Type 4 is the account actor.
+                Case::new("account", code_cid_for_type(4, 100)).returns_sized(),
+                Case::new("unknown_type", code_cid_for_type(1000, 100)),
+                Case::new("buffer_too_small", code_cid_for_type(4, 8)),
+            ],
+        ),
+        SyscallVector::new(
+            "actor",
+            "balance_of",
+            vec![
+                Case::new("ok", balance_of(SENDER_ID))
+                    .returns(size_of::<fvm_shared::sys::TokenAmount>()),
+                Case::new("not_found", balance_of(MISSING_ID)),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "verify_bls_aggregate",
+            vec![
+                Case::new("invalid_signature", verify_bls_aggregate(1))
+                    .data(IN, bls_sig.clone())
+                    .data(IN2, bls_key.clone())
+                    .data(IN3, "hello")
+                    .data(IN4, 5u32.to_le_bytes())
+                    .returns(4),
+                Case::new("invalid_public_key", verify_bls_aggregate(1))
+                    .data(IN, bls_sig)
+                    .data(IN2, [0; 48])
+                    .data(IN3, "hello")
+                    .data(IN4, 5u32.to_le_bytes())
+                    .returns(4),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "recover_secp_public_key",
+            vec![
+                Case::new("ok", recover_secp.clone())
+                    .data(IN, [7; 32])
+                    .data(IN2, secp_sig(0))
+                    .returns(65),
+                Case::new("invalid_recovery_id", recover_secp.clone())
+                    .data(IN, [7; 32])
+                    .data(IN2, secp_sig(4)),
+                Case::new("invalid_signature", recover_secp)
+                    .data(IN, [7; 32])
+                    .data(IN2, [0; SECP_SIG_LEN]),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "hash",
+            vec![
+                Case::new("blake2b_256", hash(BLAKE2B_256, 32))
+                    .data(IN, "hello")
+                    .returns_sized(),
+                Case::new("sha2_256", hash(0x12, 32))
+                    .data(IN, "hello")
+                    .returns_sized(),
+                Case::new("keccak_256", hash(0x1b, 32))
+                    .data(IN, "hello")
+                    .returns_sized(),
+                Case::new("ripemd_160", hash(0x1053, 20))
+                    .data(IN, "hello")
+                    .returns_sized(),
+                Case::new("truncated", hash(BLAKE2B_256, 16))
+                    .data(IN, "hello")
+                    .returns_sized(),
+                Case::new("unsupported", hash(0x11, 20)).data(IN, "hello"),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "compute_unsealed_sector_cid",
+            vec![
+                Case::new("ok", unsealed_cid(5, pieces.len()))
+                    .data(IN, pieces.clone())
+                    .returns_sized(),
+                Case::new("invalid_proof_type", unsealed_cid(-1, pieces.len()))
+                    .data(IN, pieces),
+                Case::new("invalid_pieces", unsealed_cid(5, 1)).data(IN, [0xff]),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "verify_post",
+            vec![
+                Case::new("no_challenged_sectors", verify("verify_post", post.len()))
+                    .data(IN, post)
+                    .returns(4),
+                Case::new("invalid_input", verify("verify_post", 1)).data(IN, [0xff]),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "verify_consensus_fault",
+            vec![
+                Case::new("no_fault", consensus_fault(2))
+                    .data(IN, "h1")
+                    .data(IN2, "h2")
+                    .returns(size_of::<crypto::VerifyConsensusFault>()),
+                Case::new("out_of_bounds", consensus_fault(100_000)),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "verify_aggregate_seals",
+            vec![
+                Case::new("ok", verify("verify_aggregate_seals", aggregate.len()))
+                    .data(IN, aggregate)
+                    .returns(4),
+                Case::new("invalid_input", verify("verify_aggregate_seals", 1)).data(IN, [0xff]),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "verify_replica_update",
+            vec![
+                Case::new("ok", verify("verify_replica_update", replica_update.len()))
+                    .data(IN, replica_update)
+                    .returns(4),
+                Case::new("invalid_input", verify("verify_replica_update", 1)).data(IN, [0xff]),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "batch_verify_seals",
+            vec![
+                Case::new("ok", verify_batch("batch_verify_seals", seals.len()))
+                    .data(IN, seals.clone())
+                    .returns(1),
+                Case::new("invalid_input", verify_batch("batch_verify_seals", 1)).data(IN, [0xff]),
+            ],
+        ),
+        SyscallVector::new(
+            "crypto",
+            "verify_seal_batch",
+            vec![
+                Case::new("ok", verify_batch("verify_seal_batch", seals.len()))
+                    .data(IN, seals)
+                    .returns(1),
+                Case::new("invalid_input", verify_batch("verify_seal_batch", 1)).data(IN, [0xff]),
+            ],
+        )
+        .at(NetworkVersion::V26),
+        SyscallVector::new(
+            "crypto",
+            "verify_post_batch",
+            vec![
+                Case::new("ok", verify_batch("verify_post_batch", posts.len()))
+                    .data(IN, posts)
+                    .returns(1),
+                Case::new("invalid_input", verify_batch("verify_post_batch", 1)).data(IN, [0xff]),
+            ],
+        )
+        .at(NetworkVersion::V26),
+        SyscallVector::new(
+            "event",
+            "emit_event",
+            vec![
+                Case::new("ok", emit_event(1, 1, 4))
+                    .data(IN, event_entry(IPLD_RAW, 1))
+                    .data(IN2, "a")
+                    .data(IN3, "data"),
+                Case::new("unsupported_codec", emit_event(1, 1, 4))
+                    .data(IN, event_entry(0x70, 1))
+                    .data(IN2, "a")
+                    .data(IN3, "data"),
+                Case::new("key_too_long", emit_event(1, 40, 4))
+                    .data(IN, event_entry(IPLD_RAW, 40))
+                    .data(IN2, [b'a'; 40])
+                    .data(IN3, "data"),
+                Case::new("length_mismatch", emit_event(1, 2, 4))
+                    .data(IN, event_entry(IPLD_RAW, 1))
+                    .data(IN2, "ab")
+                    .data(IN3, "data"),
+            ],
+        ),
+        SyscallVector::new(
+            "rand",
+            "get_chain_randomness",
+            vec![
+                Case::new("ok", randomness("get_chain_randomness", EPOCH - 1)).returns(32),
+                // The (negative) lookback is charged as a huge one.
+                Case::new("future_epoch", randomness("get_chain_randomness", EPOCH + 1)),
+                Case::new("long_lookback", randomness("get_chain_randomness", EPOCH - 1000)).returns(32),
+            ],
+        ),
+        SyscallVector::new(
+            "rand",
+            "get_beacon_randomness",
+            vec![
+                Case::new("ok", randomness("get_beacon_randomness", EPOCH - 1)).returns(32),
+                // The (negative) lookback is charged as a huge one.
+                Case::new("future_epoch", randomness("get_beacon_randomness", EPOCH + 1)),
+                Case::new("long_lookback", randomness("get_beacon_randomness", EPOCH - 1000)).returns(32),
+            ],
+        ),
+        SyscallVector::new(
+            "gas",
+            "charge",
+            vec![
+                Case::new("ok", gas_charge(4, 1000)).data(IN, "test"),
+                Case::new("invalid_name", gas_charge(1, 1000)).data(IN, [0xff]),
+                Case::new("out_of_gas", gas_charge(4, i64::MAX)).data(IN, "test"),
+            ],
+        ),
+        SyscallVector::new(
+            "gas",
+            "available",
+            vec![Case::new("ok", format!("(call $gas.available (i32.const {OUT}))")).returns(8)],
+        ),
+        SyscallVector::new(
+            "send",
+            "send",
+            vec![
+                Case::new("ok", send(sender_id.len(), 0, 1))
+                    .data(IN, sender_id.clone())
+                    .returns(size_of::<send::Send>()),
+                Case::new("insufficient_funds", send(sender_id.len(), 0, CASE_BALANCE + 1))
+                    .data(IN, sender_id),
+                Case::new("not_found", send(missing_id.len(), 0, 0)).data(IN, missing_id),
+                Case::new("invalid_recipient", send(1, 0, 0)).data(IN, [0xff]),
+                // Sends to unassigned addresses may create actors, see `ImplicitCreation`. Account
+                // creation isn't covered: the bundled account actor imports syscalls this kernel
+                // no longer links.
+                Case::new("create_placeholder", send(unassigned_delegated.len(), 0, 1))
+                    .data(IN, unassigned_delegated)
+                    .returns(size_of::<send::Send>()),
+                Case::new("bls_zero_address", send(bls_zero.len(), 0, 1)).data(IN, bls_zero),
+                Case::new("unassigned_robust", send(unassigned_robust.len(), 0, 1))
+                    .data(IN, unassigned_robust),
+                Case::new("other_namespace", send(other_namespace.len(), 0, 1))
+                    .data(IN, other_namespace),
+            ],
+        ),
+        SyscallVector::new(
+            "debug",
+            "log",
+            // Actor debugging is disabled, so the debug syscalls do nothing (not even checking
+            // their arguments).
+            vec![
+                Case::new("disabled", debug_log(IN, 5)).data(IN, "hello"),
+                Case::new("disabled_out_of_bounds", debug_log(65530, 100)),
+            ],
+        ),
+        SyscallVector::new(
+            "debug",
+            "log_structured",
+            vec![
+                Case::new("disabled", log_structured(structured_fields.len()))
+                    .data(IN, "hello")
+                    .data(IN2, structured_fields),
+                Case::new("disabled_invalid_fields", log_structured(1))
+                    .data(IN, "hello")
+                    .data(IN2, [0xff]),
+            ],
+        )
+        .at(NetworkVersion::V26),
+        SyscallVector::new(
+            "debug",
+            "enabled",
+            vec![Case::new("disabled", format!("(call $debug.enabled (i32.const {OUT}))")).returns(4)],
+        ),
+        SyscallVector::new(
+            "debug",
+            "store_artifact",
+            vec![
+                Case::new("disabled", store_artifact(8))
+                    .data(IN, "artifact")
+                    .data(IN2, "data"),
+                Case::new("disabled_invalid_name", store_artifact(4))
+                    .data(IN, "../x")
+                    .data(IN2, "data"),
+            ],
+        ),
+        SyscallVector::new(
+            "wasm",
+            "float",
+            // Floating point is allowed by default, with the NaNs it produces canonicalized.
+            vec![
+                Case::new("f32_nan", format!("(i32.store (i32.const {OUT}) (i32.reinterpret_f32 (f32.div (f32.const 0) (f32.const 0)))) (i32.const 0)")).returns(4),
+                Case::new("f64_nan", format!("(i64.store (i32.const {OUT}) (i64.reinterpret_f64 (f64.sqrt (f64.const -1)))) (i32.const 0)")).returns(8),
+                Case::new("nan_payload", format!("(i32.store (i32.const {OUT}) (i32.reinterpret_f32 (f32.add (f32.reinterpret_i32 (i32.const 0x7fa00001)) (f32.const 1)))) (i32.const 0)")).returns(4),
+                Case::new("arithmetic", format!("(f64.store (i32.const {OUT}) (f64.div (f64.const 1) (f64.const 3))) (i32.const 0)")).returns(8),
+                Case::new("trunc_nan", "(drop (i32.trunc_f32_s (f32.div (f32.const 0) (f32.const 0)))) (i32.const 0)"),
+            ],
+        ),
+    ]
+}
+
+/// Puts an actor with a CBOR state into the state tree.
+fn put_actor<S: serde::Serialize>(
+    tree: &mut StateTree<&MemoryBlockstore>,
+    id: ActorID,
+    code: Cid,
+    state: &S,
+    balance: TokenAmount,
+    delegated_address: Option<Address>,
+) -> anyhow::Result<()> {
+    let state = tree.store().put_cbor(state, Code::Blake2b256)?;
+    tree.set_actor(
+        id,
+        ActorState::new(code, state, balance, 0, delegated_address),
+    );
+    Ok(())
+}
+
+/// Creates the state the vector's messages are applied to, returning its root.
+fn setup(bs: &MemoryBlockstore, vector: &SyscallVector) -> anyhow::Result<Cid> {
+    let manifest_root =
+        bundle_manifest("v11", "mainnet").context("missing the v11 mainnet actors bundle")?;
+    let (version, data): (u32, Cid) = bs
+        .get_cbor(&manifest_root)?
+        .context("missing the actors manifest")?;
+    let manifest = Manifest::load(bs, &data, version)?;
+    let account_code = *manifest.get_account_code();
+
+    let mut tree = StateTree::new(bs, StateTreeVersion::V5)?;
+    put_actor(
+        &mut tree,
+        system_actor::SYSTEM_ACTOR_ID,
+        *manifest.get_system_code(),
+        &system_actor::State {
+            builtin_actors: data,
+        },
+        TokenAmount::default(),
+        None,
+    )?;
+    put_actor(
+        &mut tree,
+        init_actor::INIT_ACTOR_ID,
+        *manifest.get_init_code(),
+        &init_actor::State::new_test(bs),
+        TokenAmount::default(),
+        None,
+    )?;
+    put_actor(
+        &mut tree,
+        BURNT_FUNDS_ACTOR_ID,
+        account_code,
+        &fvm::account_actor::State {
+            address: Address::new_id(BURNT_FUNDS_ACTOR_ID),
+        },
+        TokenAmount::default(),
+        None,
+    )?;
+
+    // The leading fields of the power and reward actors' states, read by `network::summary`.
+    let power = StoragePower::from(1u64 << 40);
+    let zero = StoragePower::default();
+    for (actor, state) in [
+        (
+            WellKnownActor::StoragePower,
+            to_vec(&(
+                BigIntSer(&power),
+                BigIntSer(&power),
+                BigIntSer(&(&power * 10)),
+                BigIntSer(&(&power * 10)),
+                TokenAmount::from_whole(1000),
+            ))?,
+        ),
+        (
+            WellKnownActor::Reward,
+            to_vec(&(
+                BigIntSer(&zero),
+                BigIntSer(&zero),
+                0,
+                BigIntSer(&zero),
+                TokenAmount::from_whole(20),
+            ))?,
+        ),
+    ] {
+        let code = *manifest
+            .code_by_name(actor.code_name())
+            .with_context(|| format!("missing the {} actor's code", actor.name()))?;
+        let state = bs.put(
+            Code::Blake2b256,
+            &Block {
+                codec: DAG_CBOR,
+                data: &state,
+            },
+        )?;
+        tree.set_actor(
+            actor.id(),
+            ActorState::new(code, state, TokenAmount::default(), 0, None),
+        );
+    }
+
+    for (id, address, delegated) in [
+        (SENDER_ID, sender_address(), None),
+        (DELEGATED_ID, delegated_address(), Some(delegated_address())),
+    ] {
+        let assigned = tree.register_new_address(&address)?;
+        if assigned != id {
+            return Err(anyhow!(
+                "expected account {address} to get ID {id}, got {assigned}"
+            ));
+        }
+        put_actor(
+            &mut tree,
+            id,
+            account_code,
+            &fvm::account_actor::State { address },
+            TokenAmount::from_whole(1000),
+            delegated,
+        )?;
+    }
+
+    for (i, case) in vector.cases.iter().enumerate() {
+        let wasm = wat::parse_str(case.wat())
+            .with_context(|| format!("invalid test actor for case {}", case.name))?;
+        let code = bs.put(
+            Code::Blake2b256,
+            &Block {
+                codec: IPLD_RAW,
+                data: &wasm,
+            },
+        )?;
+        let state = bs.put(
+            Code::Blake2b256,
+            &Block {
+                codec: DAG_CBOR,
+                data: CASE_STATE,
+            },
+        )?;
+        tree.set_actor(
+            FIRST_CASE_ID + i as ActorID,
+            ActorState::new(code, state, TokenAmount::from_atto(CASE_BALANCE), 0, None),
+        );
+    }
+
+    Ok(tree.flush()?)
+}
+
+/// Collects the blocks reachable from the roots that aren't part of the bundled actors.
+fn collect_blocks(bs: &MemoryBlockstore, roots: &[Cid]) -> anyhow::Result<Vec<(Cid, Vec<u8>)>> {
+    fn links(ipld: &Ipld, out: &mut Vec<Cid>) {
+        match ipld {
+            Ipld::Link(cid) => out.push(*cid),
+            Ipld::List(list) => list.iter().for_each(|i| links(i, out)),
+            Ipld::Map(map) => map.values().for_each(|i| links(i, out)),
+            _ => {}
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut stack: Vec<_> = roots.iter().rev().copied().collect();
+    let mut blocks = Vec::new();
+    while let Some(cid) = stack.pop() {
+        if !seen.insert(cid) || is_bundled(&cid) {
+            continue;
+        }
+        let data = bs
+            .get(&cid)?
+            .with_context(|| format!("missing block {cid}"))?;
+        if cid.codec() == DAG_CBOR {
+            let mut children = Vec::new();
+            links(&from_slice(&data)?, &mut children);
+            stack.extend(children.into_iter().rev());
+        }
+        blocks.push((cid, data));
+    }
+    Ok(blocks)
+}
+
+/// Writes the blocks reachable from the roots (except the bundled actors) into a gzipped CAR.
+fn write_car(bs: &MemoryBlockstore, roots: Vec<Cid>) -> anyhow::Result<Vec<u8>> {
+    let blocks = collect_blocks(bs, &roots)?;
+    let mut car = futures::io::Cursor::new(Vec::new());
+    block_on(
+        CarHeader::new(roots, 1).write_stream_async(&mut car, &mut futures::stream::iter(blocks)),
+    )?;
+    let mut gz = GzEncoder::new(Vec::new(), Compression::best());
+    gz.write_all(car.get_ref())?;
+    Ok(gz.finish()?)
+}
+
+/// Generates a syscall vector: applies its messages and records the resulting receipts and state.
+pub fn generate(
+    vector: &SyscallVector,
+    engines: &MultiEngine,
+) -> anyhow::Result<serde_json::Value> {
+    let bs = MemoryBlockstore::new();
+    load_actors(&bs)?;
+    let pre_root = setup(&bs, vector)?;
+
+    let messages: Vec<_> = (0..vector.cases.len())
+        .map(|i| Message {
+            version: 0,
+            from: sender_address(),
+            to: Address::new_id(FIRST_CASE_ID + i as ActorID),
+            sequence: i as u64,
+            value: TokenAmount::default(),
+            method_num: CASE_METHOD,
+            params: RawBytes::default(),
+            gas_limit: GAS_LIMIT,
+            gas_fee_cap: TokenAmount::from_atto(BASE_FEE),
+            gas_premium: TokenAmount::default(),
+        })
+        .collect();
+    let variant = Variant {
+        id: format!("nv{}", u32::from(vector.network_version)),
+        epoch: EPOCH,
+        nv: vector.network_version.into(),
+    };
+    let pre = MessageVector {
+        selector: None,
+        meta: None,
+        car: Vec::new(),
+        preconditions: PreConditions {
+            state_tree: StateTreeVector { root_cid: pre_root },
+            basefee: Some(BASE_FEE.into()),
+            circ_supply: None,
+            variants: vec![variant.clone()],
+        },
+        apply_messages: Vec::new(),
+        postconditions: PostConditions {
+            state_tree: StateTreeVector { root_cid: pre_root },
+            receipts: Vec::new(),
+            receipts_roots: Vec::new(),
+        },
+        randomness: Vec::new(),
+    };
+
+    let machine = TestMachine::new_for_vector(&pre, &variant, bs, None, false, None)?;
+    let engine = engines.get(&machine.context().network)?;
+    engine.acquire().preload_all(
+        machine.blockstore(),
+        machine.builtin_actors().builtin_actor_codes(),
+    )?;
+    for i in 0..vector.cases.len() {
+        let actor = machine
+            .state_tree()
+            .get_actor(FIRST_CASE_ID + i as ActorID)?
+            .context("missing test actor")?;
+        engine
+            .acquire()
+            .preload(&actor.code, machine.blockstore())?;
+    }
+
+    let mut exec: DefaultExecutor<TestKernel> = DefaultExecutor::new(engine, machine)?;
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let mut apply_messages = Vec::new();
+    let mut receipts = Vec::new();
+    for (case, msg) in vector.cases.iter().zip(messages) {
+        let bytes = to_vec(&msg)?;
+        let raw_length = bytes.len() + SECP_SIG_LEN + 4;
+        let ret = exec.execute_message(msg, ApplyKind::Explicit, raw_length)?;
+        apply_messages.push(json!({
+            "case": case.name,
+            "bytes": b64.encode(&bytes),
+        }));
+        receipts.push(json!({
+            "exit_code": ret.msg_receipt.exit_code.value(),
+            "return": b64.encode(ret.msg_receipt.return_data.bytes()),
+            "gas_used": ret.msg_receipt.gas_used,
+        }));
+    }
+    let post_root = exec.flush()?;
+    let bs = exec
+        .into_machine()
+        .context("machine poisoned")?
+        .into_store()
+        .into_inner();
+
+    let id = vector.id();
+    Ok(json!({
+        "class": "message",
+        "_meta": {
+            "id": id,
+            "description": format!("{}::{}: {}", vector.module, vector.name,
+                vector.cases.iter().map(|c| c.name).collect::<Vec<_>>().join(", ")),
+            "gen": [{"source": GENERATOR}],
+        },
+        "car": b64.encode(write_car(&bs, vec![pre_root, post_root])?),
+        "preconditions": {
+            "state_tree": {"root_cid": {"/": pre_root.to_string()}},
+            "basefee": BASE_FEE,
+            "variants": [{"id": variant.id, "epoch": variant.epoch, "nv": variant.nv}],
+        },
+        "apply_messages": apply_messages,
+        "postconditions": {
+            "state_tree": {"root_cid": {"/": post_root.to_string()}},
+            "receipts": receipts,
+        },
+    }))
+}
+
+/// Renders a vector as it's committed.
+pub fn to_json(vector: &serde_json::Value) -> anyhow::Result<String> {
+    Ok(serde_json::to_string_pretty(vector)? + "\n")
+}
+
+/// The directory the vectors are committed to.
+pub fn vectors_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("syscall-vectors")
+}
@@ -5,6 +5,7 @@
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
+use std::ops::RangeInclusive;
 use std::path::Path;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -13,6 +14,7 @@ use anyhow::{anyhow, Context as _};
 use cid::Cid;
 use flate2::bufread::GzDecoder;
 use futures::AsyncRead;
+use fvm::machine::SUPPORTED_NETWORK_VERSIONS;
 use fvm_ipld_blockstore::MemoryBlockstore;
 use fvm_ipld_car::load_car;
 use fvm_ipld_encoding::tuple::*;
@@ -62,29 +64,216 @@ pub struct PreConditions {
 #[derive(Debug, Deserialize, Clone)]
 pub struct PostConditions {
     pub state_tree: StateTreeVector,
-    #[serde(with = "message_receipt_vec")]
+    #[serde(default, with = "message_receipt_vec")]
     pub receipts: Vec<Receipt>,
     #[serde(default, with = "super::cidjson::vec")]
     pub receipts_roots: Vec<Cid>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Selector {
-    #[serde(default)]
-    pub chaos_actor: Option<String>,
-    #[serde(default)]
-    pub min_protocol_version: Option<String>,
-    #[serde(default, rename = "requires:consensus_fault_extern")]
-    pub consensus_fault: Option<String>,
-}
+/// A vector's selector: the capabilities it requires of the runner, keyed by tag (e.g.,
+/// `requires:events`, `min_nv`). See [`Capability`].
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(transparent)]
+pub struct Selector(pub HashMap<String, serde_json::Value>);
 
 impl Selector {
+    /// Parses the capabilities required by this selector. Flags set to anything but `"true"` (or
+    /// `true`) are not required.
+    pub fn capabilities(&self) -> Vec<Capability> {
+        let mut caps: Vec<_> = self
+            .0
+            .iter()
+            .filter_map(|(tag, value)| Capability::parse(tag, value))
+            .collect();
+        caps.sort_by_key(|c| c.to_string());
+        caps
+    }
+
+    /// Returns why the default [`Capabilities`] can't apply vectors with this selector, if they
+    /// can't.
+    pub fn unsupported_reason(&self) -> Option<String> {
+        Capabilities::default().unsupported_reason(self)
+    }
+
     /// Returns whether this runner supports applying vectors with this selector.
     pub fn supported(&self) -> bool {
-        self.chaos_actor.as_deref() != Some("true")
-            && self.consensus_fault.as_deref() != Some("true")
-            // Chocolate requires Network Version 14 which `TestMachine::import_actors` no longer loads.
-            && self.min_protocol_version.as_deref() != Some("chocolate")
+        self.unsupported_reason().is_none()
+    }
+}
+
+/// A capability required by a vector, parsed from one of its selector's tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Capability {
+    /// `chaos_actor`: the chaos actor must be deployed.
+    ChaosActor,
+    /// `requires:consensus_fault_extern`: the externs must verify consensus faults.
+    ConsensusFaultExtern,
+    /// `requires:chain-id`: the machine must be configured with the vector's chain ID.
+    ChainId,
+    /// `requires:events`: actor events must be recorded and checked.
+    Events,
+    /// `min_nv` (or `requires:min-nv`): the runner must support this network version.
+    MinNetworkVersion(u32),
+    /// `min_protocol_version`: the runner must support this named network upgrade.
+    MinProtocolVersion(String),
+    /// Any other `requires:` tag, or a malformed one. Never supported.
+    Unknown(String),
+}
+
+/// Network upgrade names, as used by `min_protocol_version`, indexed by network version.
+const PROTOCOL_VERSIONS: &[&str] = &[
+    "genesis",
+    "breeze",
+    "smoke",
+    "ignition",
+    "actorsv2",
+    "tape",
+    "kumquat",
+    "calico",
+    "persian",
+    "orange",
+    "trust",
+    "norwegian",
+    "turbo",
+    "hyperdrive",
+    "chocolate",
+    "ohsnap",
+    "skyr",
+    "shark",
+    "hygge",
+    "lightning",
+    "thunder",
+    "watermelon",
+    "dragon",
+    "waffle",
+    "tuktuk",
+    "teep",
+];
+
+/// Network upgrades whose vectors are never supported, even at a supported network version.
+const UNSUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[
+    // Chocolate requires Network Version 14 which `TestMachine::import_actors` no longer loads.
+    "chocolate",
+];
+
+impl Capability {
+    /// Parses a selector tag, returning `None` if it doesn't require anything of the runner.
+    pub fn parse(tag: &str, value: &serde_json::Value) -> Option<Self> {
+        let enabled = match value {
+            serde_json::Value::Bool(b) => *b,
+            serde_json::Value::String(s) => s == "true",
+            _ => false,
+        };
+        let number = match value {
+            serde_json::Value::Number(n) => n.as_u64().and_then(|n| n.try_into().ok()),
+            serde_json::Value::String(s) => s.parse().ok(),
+            _ => None,
+        };
+        let cap = match tag.strip_prefix("requires:").unwrap_or(tag) {
+            "chaos_actor" => enabled.then_some(Capability::ChaosActor)?,
+            "consensus_fault_extern" => enabled.then_some(Capability::ConsensusFaultExtern)?,
+            "chain-id" | "chain_id" => enabled.then_some(Capability::ChainId)?,
+            "events" => enabled.then_some(Capability::Events)?,
+            "min_nv" | "min-nv" => match number {
+                Some(nv) => Capability::MinNetworkVersion(nv),
+                None => Capability::Unknown(format!("{tag}={value}")),
+            },
+            "min_protocol_version" => match value.as_str() {
+                Some(name) => Capability::MinProtocolVersion(name.to_ascii_lowercase()),
+                None => Capability::Unknown(format!("{tag}={value}")),
+            },
+            _ if tag.starts_with("requires:") => Capability::Unknown(format!("{tag}={value}")),
+            // Other tags are informational.
+            _ => return None,
+        };
+        Some(cap)
+    }
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Capability::ChaosActor => f.write_str("chaos-actor"),
+            Capability::ConsensusFaultExtern => f.write_str("consensus-fault-extern"),
+            Capability::ChainId => f.write_str("chain-id"),
+            Capability::Events => f.write_str("events"),
+            Capability::MinNetworkVersion(nv) => write!(f, "min-nv={nv}"),
+            Capability::MinProtocolVersion(name) => write!(f, "min-protocol-version={name}"),
+            Capability::Unknown(tag) => write!(f, "unknown capability {tag}"),
+        }
+    }
+}
+
+/// The capabilities of a runner, used to skip vectors (and variants) it can't apply.
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    /// Whether the chaos actor is deployed.
+    pub chaos_actor: bool,
+    /// Whether the externs verify consensus faults.
+    pub consensus_fault_extern: bool,
+    /// Whether the machine is configured with the vector's chain ID.
+    pub chain_id: bool,
+    /// Whether actor events are recorded and checked.
+    pub events: bool,
+    /// The network versions the machine supports.
+    pub network_versions: RangeInclusive<u32>,
+}
+
+impl Default for Capabilities {
+    /// The capabilities of this FVM build's test machine.
+    fn default() -> Self {
+        Self {
+            chaos_actor: false,
+            consensus_fault_extern: false,
+            chain_id: false,
+            events: false,
+            network_versions: u32::from(*SUPPORTED_NETWORK_VERSIONS.start())
+                ..=u32::from(*SUPPORTED_NETWORK_VERSIONS.end()),
+        }
+    }
+}
+
+impl Capabilities {
+    /// Returns whether this runner provides the capability.
+    pub fn provides(&self, cap: &Capability) -> bool {
+        match cap {
+            Capability::ChaosActor => self.chaos_actor,
+            Capability::ConsensusFaultExtern => self.consensus_fault_extern,
+            Capability::ChainId => self.chain_id,
+            Capability::Events => self.events,
+            Capability::MinNetworkVersion(nv) => nv <= self.network_versions.end(),
+            Capability::MinProtocolVersion(name) => {
+                !UNSUPPORTED_PROTOCOL_VERSIONS.contains(&name.as_str())
+                    && PROTOCOL_VERSIONS
+                        .iter()
+                        .position(|n| n == name)
+                        .is_some_and(|nv| nv as u32 <= *self.network_versions.end())
+            }
+            Capability::Unknown(_) => false,
+        }
+    }
+
+    /// Returns why this runner can't apply vectors with this selector, if it can't.
+    pub fn unsupported_reason(&self, selector: &Selector) -> Option<String> {
+        let missing: Vec<_> = selector
+            .capabilities()
+            .into_iter()
+            .filter(|cap| !self.provides(cap))
+            .map(|cap| cap.to_string())
+            .collect();
+        (!missing.is_empty()).then(|| format!("requires {}", missing.join(", ")))
+    }
+
+    /// Returns why this runner can't apply this variant, if it can't.
+    pub fn variant_unsupported_reason(&self, variant: &Variant) -> Option<String> {
+        (!self.network_versions.contains(&variant.nv)).then(|| {
+            format!(
+                "network version {} not supported (supported: {}-{})",
+                variant.nv,
+                self.network_versions.start(),
+                self.network_versions.end()
+            )
+        })
     }
 }
 
@@ -137,6 +326,15 @@ pub struct MessageVector {
 }
 
 impl MessageVector {
+    /// Returns true if the vector was generated by [`crate::syscall_vectors`].
+    pub fn is_syscall_vector(&self) -> bool {
+        self.meta.as_ref().is_some_and(|meta| {
+            meta.gen
+                .iter()
+                .any(|gen| gen.source == crate::syscall_vectors::GENERATOR)
+        })
+    }
+
     /// Loads a message vector from a file.
     pub fn from_file(path: &Path) -> anyhow::Result<Self> {
         let file = File::open(path)?;
@@ -179,6 +377,23 @@ impl MessageVector {
     pub fn is_supported(&self) -> bool {
         self.selector.as_ref().map_or(true, Selector::supported)
     }
+
+    /// Returns the receipts expected from the `i`th message: its own receipt and/or the
+    /// corresponding postcondition receipt. Fails if the vector expects neither, as the message's
+    /// result couldn't be checked.
+    pub fn expected_receipts(&self, i: usize) -> anyhow::Result<Vec<&Receipt>> {
+        let receipts: Vec<_> = self
+            .apply_messages
+            .get(i)
+            .and_then(|m| m.receipt.as_ref())
+            .into_iter()
+            .chain(self.postconditions.receipts.get(i))
+            .collect();
+        if receipts.is_empty() {
+            return Err(anyhow!("no expected receipt for message {}", i));
+        }
+        Ok(receipts)
+    }
 }
 
 impl MessageVector {
@@ -213,6 +428,13 @@ pub struct ApplyMessage {
     pub bytes: Vec<u8>,
     #[serde(default)]
     pub epoch_offset: Option<ChainEpoch>,
+    /// The receipt expected from this message, checked as soon as the message has been applied
+    /// (in addition to the corresponding entry in the postcondition receipts, if any).
+    #[serde(default, with = "message_receipt_opt")]
+    pub receipt: Option<Receipt>,
+    /// The state root expected after applying this message (a checkpoint).
+    #[serde(default, with = "super::cidjson::opt")]
+    pub state_root: Option<Cid>,
 }
 
 mod base64_bytes {
@@ -235,6 +457,19 @@ mod base64_bytes {
 }
 
 mod message_receipt_vec {
+    use super::message_receipt_opt::MessageReceiptVector;
+    use super::*;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Receipt>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: Vec<MessageReceiptVector> = Deserialize::deserialize(deserializer)?;
+        Ok(s.into_iter().map(Into::into).collect())
+    }
+}
+
+mod message_receipt_opt {
     use fvm_ipld_encoding::RawBytes;
     use fvm_shared::error::ExitCode;
 
@@ -248,19 +483,23 @@ mod message_receipt_vec {
         gas_used: u64,
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Receipt>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s: Vec<MessageReceiptVector> = Deserialize::deserialize(deserializer)?;
-        Ok(s.into_iter()
-            .map(|v| Receipt {
+    impl From<MessageReceiptVector> for Receipt {
+        fn from(v: MessageReceiptVector) -> Self {
+            Receipt {
                 exit_code: v.exit_code,
                 return_data: RawBytes::new(v.return_value),
                 gas_used: v.gas_used,
                 events_root: None,
-            })
-            .collect())
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Receipt>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: Option<MessageReceiptVector> = Deserialize::deserialize(deserializer)?;
+        Ok(s.map(Into::into))
     }
 }
 
@@ -275,3 +514,170 @@ mod message_receipt_vec {
 //         ChainMessage::Unsigned(msg)
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(message_receipts: &[bool], receipts: usize) -> MessageVector {
+        let receipt = serde_json::json!({ "exit_code": 0, "gas_used": 1, "return": "" });
+        let cid = "bafy2bzacedeemdvmwaxscbd2lorvahhhaggvzodoulzuzhexov4kzk5gbiqaa";
+        let root = serde_json::json!({ "root_cid": { "/": cid } });
+        let messages: Vec<_> = message_receipts
+            .iter()
+            .map(|&r| match r {
+                true => serde_json::json!({ "bytes": "", "receipt": receipt }),
+                false => serde_json::json!({ "bytes": "" }),
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "car": "",
+            "preconditions": { "state_tree": root },
+            "apply_messages": messages,
+            "postconditions": {
+                "state_tree": root,
+                "receipts": vec![receipt; receipts],
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn expected_receipts() {
+        // Postcondition receipts only.
+        let v = vector(&[false, false], 2);
+        assert_eq!(v.expected_receipts(1).unwrap().len(), 1);
+
+        // Per-message receipts are checked alongside the postcondition receipts.
+        let v = vector(&[true, true], 1);
+        assert_eq!(v.expected_receipts(0).unwrap().len(), 2);
+        assert_eq!(v.expected_receipts(1).unwrap().len(), 1);
+
+        // A message without any receipt can't be checked.
+        let v = vector(&[true, false], 1);
+        assert!(v.expected_receipts(1).is_err());
+        let v = vector(&[false], 0);
+        assert!(v.expected_receipts(0).is_err());
+    }
+
+    #[test]
+    fn syscall_vectors() {
+        let mut v = vector(&[], 0);
+        assert!(!v.is_syscall_vector());
+
+        let meta = |source: &str| MetaData {
+            id: "test".into(),
+            version: String::new(),
+            description: String::new(),
+            comment: String::new(),
+            gen: vec![GenerationData {
+                source: source.into(),
+                version: String::new(),
+            }],
+        };
+        v.meta = Some(meta("github.com/filecoin-project/test-vectors"));
+        assert!(!v.is_syscall_vector());
+        v.meta = Some(meta(crate::syscall_vectors::GENERATOR));
+        assert!(v.is_syscall_vector());
+    }
+
+    #[test]
+    fn parse_capabilities() {
+        use serde_json::json;
+
+        let parse = |tag, value| Capability::parse(tag, &value);
+
+        // Flags are only required when set.
+        assert_eq!(
+            parse("chaos_actor", json!("true")),
+            Some(Capability::ChaosActor)
+        );
+        assert_eq!(
+            parse("chaos_actor", json!(true)),
+            Some(Capability::ChaosActor)
+        );
+        assert_eq!(parse("chaos_actor", json!("false")), None);
+        assert_eq!(
+            parse("requires:consensus_fault_extern", json!("true")),
+            Some(Capability::ConsensusFaultExtern)
+        );
+        assert_eq!(
+            parse("requires:chain-id", json!(true)),
+            Some(Capability::ChainId)
+        );
+        assert_eq!(
+            parse("requires:events", json!("true")),
+            Some(Capability::Events)
+        );
+        assert_eq!(parse("requires:events", json!(1)), None);
+
+        // Network versions, by number or by name.
+        assert_eq!(
+            parse("min_nv", json!(21)),
+            Some(Capability::MinNetworkVersion(21))
+        );
+        assert_eq!(
+            parse("requires:min-nv", json!("22")),
+            Some(Capability::MinNetworkVersion(22))
+        );
+        assert_eq!(
+            parse("min_nv", json!("latest")),
+            Some(Capability::Unknown("min_nv=\"latest\"".into()))
+        );
+        assert_eq!(
+            parse("min_protocol_version", json!("Hygge")),
+            Some(Capability::MinProtocolVersion("hygge".into()))
+        );
+
+        // Unknown requirements are never supported; other tags are informational.
+        assert_eq!(
+            parse("requires:teleportation", json!("true")),
+            Some(Capability::Unknown(
+                "requires:teleportation=\"true\"".into()
+            ))
+        );
+        assert_eq!(parse("comment", json!("anything")), None);
+    }
+
+    #[test]
+    fn default_capabilities() {
+        let caps = Capabilities::default();
+        let supported = |tags: serde_json::Value| {
+            let selector: Selector = serde_json::from_value(tags).unwrap();
+            caps.unsupported_reason(&selector).is_none()
+        };
+
+        assert!(supported(serde_json::json!({ "comment": "x" })));
+        assert!(supported(serde_json::json!({ "chaos_actor": "false" })));
+        assert!(!supported(serde_json::json!({ "chaos_actor": "true" })));
+        assert!(!supported(
+            serde_json::json!({ "requires:chain-id": "true" })
+        ));
+        assert!(!supported(serde_json::json!({ "requires:events": "true" })));
+        assert!(supported(serde_json::json!({ "min_nv": 21 })));
+        assert!(!supported(serde_json::json!({ "min_nv": 1000 })));
+        assert!(supported(
+            serde_json::json!({ "min_protocol_version": "hygge" })
+        ));
+        assert!(!supported(
+            serde_json::json!({ "min_protocol_version": "chocolate" })
+        ));
+        assert!(!supported(
+            serde_json::json!({ "min_protocol_version": "unknown" })
+        ));
+
+        let variant = |nv| Variant {
+            id: "v".into(),
+            epoch: 0,
+            nv,
+        };
+        let (min, max) = (
+            u32::from(*SUPPORTED_NETWORK_VERSIONS.start()),
+            u32::from(*SUPPORTED_NETWORK_VERSIONS.end()),
+        );
+        assert!(caps.variant_unsupported_reason(&variant(min)).is_none());
+        assert!(caps.variant_unsupported_reason(&variant(max)).is_none());
+        assert!(caps.variant_unsupported_reason(&variant(min - 1)).is_some());
+        assert!(caps.variant_unsupported_reason(&variant(max + 1)).is_some());
+    }
+}
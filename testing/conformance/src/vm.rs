@@ -5,7 +5,7 @@ use std::sync::{Arc, Mutex};
 use fvm::kernel::filecoin::{DefaultFilecoinKernel, FilecoinKernel};
 
 use fvm::call_manager::{CallManager, DefaultCallManager};
-use fvm::gas::price_list_by_network_version;
+use fvm::gas::try_price_list_by_network_version;
 use fvm::machine::limiter::MemoryLimiter;
 use fvm::machine::{DefaultMachine, Machine, MachineContext, Manifest, NetworkConfig};
 use fvm::state_tree::StateTree;
@@ -66,7 +66,7 @@ impl TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
         tracing: bool,
         price_network_version: Option<NetworkVersion>,
     ) -> anyhow::Result<TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>>> {
-        let network_version = variant.nv.into();
+        let network_version: NetworkVersion = variant.nv.into();
         let base_fee = v
             .preconditions
             .basefee
@@ -77,11 +77,15 @@ impl TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
 
         let externs = TestExterns::new(&v.randomness);
 
-        let mut nc = NetworkConfig::new(network_version);
+        let nc = NetworkConfig::try_new(network_version)
+            .ok_or_else(|| anyhow::anyhow!("unsupported network version {network_version}"))?;
         let mut mc = nc.for_epoch(epoch, (epoch * 30) as u64, state_root);
-        // Allow overriding prices to some other network version.
+        // Allow overriding prices to some other network version. This must be applied to the
+        // machine context: the context is copied out of the network config above, so setting the
+        // config's price list here (as we used to) silently kept the vector's own prices.
         if let Some(nv) = price_network_version {
-            nc.price_list = price_list_by_network_version(nv);
+            mc.price_list = try_price_list_by_network_version(nv)
+                .ok_or_else(|| anyhow::anyhow!("no price list for network version {nv}"))?;
         }
         mc.set_base_fee(base_fee);
         mc.tracing = tracing;
@@ -148,6 +152,14 @@ where
             local_stats: TestStats::default(),
         }
     }
+
+    fn record_witness(&self) {
+        self.machine.record_witness()
+    }
+
+    fn take_witness(&self) -> Result<Vec<Cid>> {
+        self.machine.take_witness()
+    }
 }
 
 type InnerTestKernel = DefaultFilecoinKernel<DefaultCallManager<TestMachine>>;
@@ -250,6 +262,23 @@ impl FilecoinKernel for TestKernel {
         Ok(vec![true; vis.len()])
     }
 
+    // NOT forwarded
+    fn verify_seal_batch(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
+        let charge = self.price_list().on_verify_seal_batch(vis);
+        let _ = self.charge_gas(&charge.name, charge.total())?;
+        Ok(vec![true; vis.len()])
+    }
+
+    // NOT forwarded
+    fn verify_post_batch(
+        &self,
+        infos: &[fvm_shared::sector::WindowPoStVerifyInfo],
+    ) -> Result<Vec<bool>> {
+        let charge = self.price_list().on_verify_post_batch(infos);
+        let _ = self.charge_gas(&charge.name, charge.total())?;
+        Ok(vec![true; infos.len()])
+    }
+
     // NOT forwarded
     fn verify_consensus_fault(
         &self,
@@ -281,6 +310,10 @@ impl FilecoinKernel for TestKernel {
     fn total_fil_circ_supply(&self) -> Result<TokenAmount> {
         self.0.total_fil_circ_supply()
     }
+
+    fn network_summary(&self) -> Result<NetworkSummary> {
+        self.0.network_summary()
+    }
 }
 
 /// Wrap a `ResourceLimiter` and collect statistics.
@@ -15,10 +15,11 @@ use async_std::{stream, sync, task};
 use colored::*;
 use futures::{Future, StreamExt, TryFutureExt, TryStreamExt};
 use fvm::engine::MultiEngine;
+use fvm_conformance_tests::corpus::corpus_from_env;
 use fvm_conformance_tests::driver::*;
 use fvm_conformance_tests::report;
 use fvm_conformance_tests::tracing::{TestTraceExporter, TestTraceExporterRef};
-use fvm_conformance_tests::vector::{MessageVector, Selector};
+use fvm_conformance_tests::vector::MessageVector;
 use fvm_conformance_tests::vm::{TestStatsGlobal, TestStatsRef};
 use itertools::Itertools;
 use lazy_static::lazy_static;
@@ -65,8 +66,10 @@ lazy_static! {
 async fn conformance_test_runner() -> anyhow::Result<()> {
     env_logger::init();
 
-    let path = var("VECTOR").unwrap_or_else(|_| "test-vectors/corpus".to_owned());
-    let path = Path::new(path.as_str()).to_path_buf();
+    let path = match var("VECTOR") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => corpus_from_env()?.unwrap_or_else(|| PathBuf::from("test-vectors/corpus")),
+    };
     let stats = TestStatsGlobal::new_ref();
 
     // Optionally create a component to export gas charge traces.
@@ -226,14 +229,15 @@ async fn run_vector(
         "message" => {
             let v: MessageVector =
                 serde_json::from_str(&vector_json).context("failed to parse message vector")?;
-            let skip = !v.selector.as_ref().map_or(true, Selector::supported);
-            if skip {
+            if let Some(reason) = v.selector.as_ref().and_then(|s| s.unsupported_reason()) {
+                let reason = format!("selector not supported: {reason}");
                 Ok(either::Either::Left(
-                    v.preconditions.variants.into_iter().map(|variant| {
+                    v.preconditions.variants.into_iter().map(move |variant| {
+                        let reason = reason.clone();
                         futures::future::Either::Left(async move {
                             Ok(VariantResult::Skipped {
                                 id: variant.id,
-                                reason: "selector not supported".to_owned(),
+                                reason,
                             })
                         })
                     }),
@@ -0,0 +1,121 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Checks the committed syscall vectors against their generator, and runs them.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context as _};
+use base64::Engine as _;
+use flate2::read::GzDecoder;
+use fvm::engine::MultiEngine;
+use fvm_conformance_tests::driver::{run_variant, VariantResult};
+use fvm_conformance_tests::syscall_vectors::{generate, syscall_vectors, to_json, vectors_dir};
+use fvm_conformance_tests::vector::MessageVector;
+use lazy_static::lazy_static;
+use walkdir::WalkDir;
+
+lazy_static! {
+    static ref ENGINES: MultiEngine = MultiEngine::new(1);
+}
+
+const REGENERATE: &str = "regenerate the vectors with `cargo run --bin syscall-vectors`";
+
+/// Replaces a vector's gzipped CAR with its decompressed contents, as gzip's output isn't stable.
+fn normalize(mut vector: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let car = vector["car"].as_str().context("vector without a car")?;
+    let mut decompressed = Vec::new();
+    GzDecoder::new(&*b64.decode(car)?).read_to_end(&mut decompressed)?;
+    vector["car"] = b64.encode(decompressed).into();
+    Ok(vector)
+}
+
+#[test]
+fn syscall_vectors_are_up_to_date() -> anyhow::Result<()> {
+    let dir = vectors_dir();
+    let mut expected = HashSet::new();
+    for vector in syscall_vectors() {
+        let path = dir.join(vector.path());
+        let generated = generate(&vector, &ENGINES).with_context(|| vector.id())?;
+        let committed = fs::read_to_string(&path)
+            .with_context(|| format!("missing vector {}; {REGENERATE}", path.display()))?;
+        let committed: serde_json::Value = serde_json::from_str(&committed)?;
+        if normalize(committed)? != normalize(generated.clone())? {
+            return Err(anyhow!(
+                "vector {} is out of date; {REGENERATE}\n{}",
+                path.display(),
+                to_json(&generated)?
+            ));
+        }
+        expected.insert(path);
+    }
+    for entry in WalkDir::new(&dir) {
+        let path = entry?.into_path();
+        if path.is_file() && !expected.contains(&path) {
+            return Err(anyhow!("stale vector {}; {REGENERATE}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn syscall_vectors_pass() -> anyhow::Result<()> {
+    let paths: Vec<PathBuf> = WalkDir::new(vectors_dir())
+        .into_iter()
+        .map(|e| Ok(e?.into_path()))
+        .filter(|p: &anyhow::Result<PathBuf>| p.as_ref().map_or(true, |p| p.is_file()))
+        .collect::<anyhow::Result<_>>()?;
+    assert!(!paths.is_empty(), "no syscall vectors");
+
+    for path in paths {
+        let v = MessageVector::from_file(&path)?;
+        for variant in &v.preconditions.variants {
+            let (bs, _) = v.seed_blockstore().await?;
+            match run_variant(bs, &v, variant, &ENGINES, true, None, None)? {
+                VariantResult::Ok { .. } => {}
+                VariantResult::Skipped { reason, .. } => {
+                    return Err(anyhow!("{} skipped: {reason}", path.display()))
+                }
+                VariantResult::Failed { reason, .. } => {
+                    return Err(reason.context(format!("{} failed", path.display())))
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn missing_receipts_fail() -> anyhow::Result<()> {
+    let path = vectors_dir().join("self").join("current_balance.json");
+    let mut v = MessageVector::from_file(&path)?;
+    let expected = v.postconditions.receipts.clone();
+    let variant = v.preconditions.variants[0].clone();
+
+    // A vector without any receipt for a message can't pass.
+    v.postconditions.receipts.clear();
+    let (bs, _) = v.seed_blockstore().await?;
+    match run_variant(bs, &v, &variant, &ENGINES, true, None, None)? {
+        VariantResult::Failed { reason, .. } => {
+            assert!(reason
+                .to_string()
+                .contains("no expected receipt for message 0"));
+        }
+        _ => return Err(anyhow!("{} passed without receipts", path.display())),
+    }
+
+    // The message's own receipt is enough.
+    v.apply_messages[0].receipt = expected.into_iter().next();
+    let (bs, _) = v.seed_blockstore().await?;
+    match run_variant(bs, &v, &variant, &ENGINES, true, None, None)? {
+        VariantResult::Ok { .. } => Ok(()),
+        _ => Err(anyhow!(
+            "{} failed with its message's receipt",
+            path.display()
+        )),
+    }
+}
@@ -4,11 +4,10 @@ use anyhow::{Context, Result};
 use cid::Cid;
 use fvm::machine::{Manifest, BURNT_FUNDS_ACTOR_ID};
 use fvm::state_tree::{ActorState, StateTree};
-use fvm::{init_actor, system_actor};
+use fvm::{eam_actor, init_actor, system_actor};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::CborStore;
 use fvm_shared::address::Address;
-use fvm_shared::ActorID;
 use multihash_codetable::Code;
 
 use crate::error::Error::{FailedToLoadManifest, FailedToSetState};
@@ -73,8 +72,6 @@ pub fn set_init_actor(
 }
 
 pub fn set_eam_actor(state_tree: &mut StateTree<impl Blockstore>, eam_code_cid: Cid) -> Result<()> {
-    const EAM_ACTOR_ID: ActorID = 10;
-
     let eam_state_cid = state_tree
         .store()
         .put_cbor(&[(); 0], Code::Blake2b256)
@@ -88,7 +85,7 @@ pub fn set_eam_actor(state_tree: &mut StateTree<impl Blockstore>, eam_code_cid:
         delegated_address: None,
     };
 
-    state_tree.set_actor(EAM_ACTOR_ID, eam_actor_state);
+    state_tree.set_actor(eam_actor::EAM_ACTOR_ID, eam_actor_state);
     Ok(())
 }
 
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 mod builtin;
 pub mod bundle;
-pub mod custom_kernel;
 pub mod dummy;
 pub mod error;
 pub mod tester;
 pub mod testkit;
+
+pub use fvm_custom_kernel_example as custom_kernel;
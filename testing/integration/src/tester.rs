@@ -12,13 +12,15 @@ use fvm::{init_actor, system_actor};
 use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::{ser, CborStore};
 use fvm_shared::address::{Address, Protocol};
+use fvm_shared::crypto::signature::{Signature, SignatureType, SECP_SIG_LEN};
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::state::StateTreeVersion;
 use fvm_shared::version::NetworkVersion;
 use fvm_shared::{ActorID, IPLD_RAW};
 use lazy_static::lazy_static;
 use libsecp256k1::{PublicKey, SecretKey};
-use multihash_codetable::Code;
+use multihash_codetable::{Code, MultihashDigest};
+use rand::{CryptoRng, Rng, SeedableRng};
 
 use crate::builtin::{
     fetch_builtin_code_cid, set_burnt_funds_account, set_eam_actor, set_init_actor, set_sys_actor,
@@ -40,6 +42,74 @@ pub type IntegrationExecutor<B, E> =
 
 pub type Account = (ActorID, Address);
 
+/// The private key of an account created by a [`Tester`].
+#[derive(Clone, Debug)]
+pub enum AccountKey {
+    Secp256k1(SecretKey),
+    Bls(bls_signatures::PrivateKey),
+}
+
+impl AccountKey {
+    /// Generates a random key of the given type.
+    pub fn generate<R: Rng + CryptoRng>(key_type: SignatureType, rng: &mut R) -> Self {
+        match key_type {
+            SignatureType::Secp256k1 => AccountKey::Secp256k1(SecretKey::random(rng)),
+            SignatureType::BLS => AccountKey::Bls(bls_signatures::PrivateKey::generate(rng)),
+        }
+    }
+
+    /// Deterministically derives a key of the given type from `seed`.
+    pub fn from_seed(key_type: SignatureType, seed: u64) -> Self {
+        Self::generate(key_type, &mut rand_chacha::ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    pub fn key_type(&self) -> SignatureType {
+        match self {
+            AccountKey::Secp256k1(_) => SignatureType::Secp256k1,
+            AccountKey::Bls(_) => SignatureType::BLS,
+        }
+    }
+
+    /// Returns the public key, serialized as in the key's address.
+    pub fn public_key(&self) -> Vec<u8> {
+        use bls_signatures::Serialize;
+        match self {
+            AccountKey::Secp256k1(key) => PublicKey::from_secret_key(key).serialize().to_vec(),
+            AccountKey::Bls(key) => key.public_key().as_bytes(),
+        }
+    }
+
+    /// Returns the key's public key address.
+    pub fn address(&self) -> Address {
+        match self {
+            AccountKey::Secp256k1(_) => Address::new_secp256k1(&self.public_key()),
+            AccountKey::Bls(_) => Address::new_bls(&self.public_key()),
+        }
+        .expect("valid public key")
+    }
+
+    /// Signs `data` as Filecoin wallets do (secp256k1 keys sign its blake2b-256 hash).
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        use bls_signatures::Serialize;
+        match self {
+            AccountKey::Secp256k1(key) => {
+                let hash: [u8; 32] = Code::Blake2b256
+                    .digest(data)
+                    .digest()
+                    .try_into()
+                    .expect("blake2b-256 digests are 32 bytes");
+                let (sig, recovery_id) =
+                    libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), key);
+                let mut bytes = Vec::with_capacity(SECP_SIG_LEN);
+                bytes.extend_from_slice(&sig.serialize());
+                bytes.push(recovery_id.serialize());
+                Signature::new_secp256k1(bytes)
+            }
+            AccountKey::Bls(key) => Signature::new_bls(key.sign(data).as_bytes()),
+        }
+    }
+}
+
 /// Execution options
 #[derive(Clone, Debug, Default)]
 pub struct ExecutionOptions {
@@ -122,16 +192,29 @@ where
     /// Creates new accounts in the testing context
     /// Inserts the specified number of accounts in the state tree, all with 1000 FIL，returning their IDs and Addresses.
     pub fn create_accounts<const N: usize>(&mut self) -> Result<[Account; N]> {
-        use rand::SeedableRng;
-
-        let rng = &mut rand_chacha::ChaCha8Rng::seed_from_u64(8);
+        let accounts = self.create_accounts_with_keys::<N>(SignatureType::Secp256k1, 8)?;
+        Ok(accounts.map(|(account, _)| account))
+    }
 
-        let mut ret: [Account; N] = [(0, Address::default()); N];
-        for account in ret.iter_mut().take(N) {
-            let priv_key = SecretKey::random(rng);
-            *account = self.make_secp256k1_account(priv_key, INITIAL_ACCOUNT_BALANCE.clone())?;
+    /// Inserts the specified number of accounts in the state tree, all with 1000 FIL, controlled by
+    /// keys of the given type deterministically derived from `seed`. Returns the accounts along
+    /// with their keys, so that tests can sign on their behalf.
+    pub fn create_accounts_with_keys<const N: usize>(
+        &mut self,
+        key_type: SignatureType,
+        seed: u64,
+    ) -> Result<[(Account, AccountKey); N]> {
+        let rng = &mut rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        let keys: [AccountKey; N] = std::array::from_fn(|_| AccountKey::generate(key_type, rng));
+
+        let mut accounts = Vec::with_capacity(N);
+        for key in keys {
+            let account = self.make_account(&key, INITIAL_ACCOUNT_BALANCE.clone())?;
+            accounts.push((account, key));
         }
-        Ok(ret)
+        Ok(accounts
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("created {N} accounts")))
     }
 
     pub fn create_account(&mut self) -> Result<Account> {
@@ -307,6 +390,34 @@ where
         Ok(())
     }
 
+    /// Returns the state of the given actor, read from the executor's state tree once the machine
+    /// has been instantiated, and from the initial state tree otherwise.
+    pub fn actor_state(&self, id: ActorID) -> Result<Option<ActorState>> {
+        let state = match &self.executor {
+            Some(executor) => executor.state_tree().get_actor(id)?,
+            None => self.state_tree.as_ref().unwrap().get_actor(id)?,
+        };
+        Ok(state)
+    }
+
+    /// Returns the code CID of the given actor, or an error if the actor doesn't exist.
+    pub fn actor_code(&self, id: ActorID) -> Result<Cid> {
+        self.actor_state(id)?
+            .map(|act| act.code)
+            .ok_or_else(|| anyhow!("actor {} not found", id))
+    }
+
+    /// Asserts that the given actor's code is the given WASM binary, e.g., to check the outcome of
+    /// an upgrade.
+    pub fn assert_actor_code(&self, id: ActorID, wasm_bin: &[u8]) {
+        assert_eq!(
+            self.actor_code(id).unwrap(),
+            wasm_code_cid(wasm_bin),
+            "unexpected code for actor {}",
+            id
+        );
+    }
+
     /// Get blockstore
     pub fn blockstore(&self) -> &dyn Blockstore {
         if self.executor.is_some() {
@@ -322,8 +433,12 @@ where
         priv_key: SecretKey,
         init_balance: TokenAmount,
     ) -> Result<Account> {
-        let pub_key = PublicKey::from_secret_key(&priv_key);
-        let pub_key_addr = Address::new_secp256k1(&pub_key.serialize())?;
+        self.make_account(&AccountKey::Secp256k1(priv_key), init_balance)
+    }
+
+    /// Put account controlled by the specified key (of any type) with the specified balance
+    pub fn make_account(&mut self, key: &AccountKey, init_balance: TokenAmount) -> Result<Account> {
+        let pub_key_addr = key.address();
 
         let state_tree = self
             .state_tree
@@ -422,6 +537,15 @@ impl BasicTester {
     }
 }
 
+/// Computes the code CID under which [`Tester::set_actor_from_bin`] installs a WASM binary.
+pub fn wasm_code_cid(wasm_binary: &[u8]) -> Cid {
+    Block {
+        codec: IPLD_RAW,
+        data: wasm_binary,
+    }
+    .cid(Code::Blake2b256)
+}
+
 /// Inserts the WASM code for the actor into the blockstore.
 fn put_wasm_code(blockstore: &impl Blockstore, wasm_binary: &[u8]) -> Result<Cid> {
     let cid = blockstore.put(
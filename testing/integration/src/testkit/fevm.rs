@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use anyhow::Result;
+use fvm::eam_actor::EAM_ACTOR_ID;
 use fvm::executor::{ApplyKind, ApplyRet, Executor};
 use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_encoding::{strict_bytes, BytesSer, RawBytes};
@@ -12,7 +13,7 @@ use num_traits::Zero;
 
 use crate::tester::{BasicAccount, BasicTester};
 
-pub const EAM_ADDRESS: Address = Address::new_id(10);
+pub const EAM_ADDRESS: Address = Address::new_id(EAM_ACTOR_ID);
 pub const DEFAULT_GAS: u64 = 10_000_000_000;
 
 pub fn create_contract(
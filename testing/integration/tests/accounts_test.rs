@@ -0,0 +1,103 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+mod bundles;
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{AccountKey, Tester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::crypto::signature::{self, SignatureType};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::{Message, SignedMessage};
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::METHOD_SEND;
+
+fn tester() -> Tester<MemoryBlockstore, DummyExterns> {
+    new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn seeded_accounts() {
+    for key_type in [SignatureType::Secp256k1, SignatureType::BLS] {
+        let [(a, key_a), (b, key_b)] = tester().create_accounts_with_keys(key_type, 42).unwrap();
+        assert_ne!(a.1, b.1);
+        assert_eq!(key_a.key_type(), key_type);
+        assert_eq!(key_a.address(), a.1);
+        assert_eq!(key_b.address(), b.1);
+
+        // The same seed yields the same accounts in another tester...
+        let [(a2, _), (b2, _)] = tester().create_accounts_with_keys(key_type, 42).unwrap();
+        assert_eq!((a, b), (a2, b2));
+
+        // ... but not with a different seed.
+        let [(a3, _)] = tester().create_accounts_with_keys(key_type, 43).unwrap();
+        assert_ne!(a.1, a3.1);
+    }
+
+    // The default accounts are seeded too.
+    let [a, b] = tester().create_accounts().unwrap();
+    let [(a2, _), (b2, _)] = tester()
+        .create_accounts_with_keys(SignatureType::Secp256k1, 8)
+        .unwrap();
+    assert_eq!((a, b), (a2, b2));
+}
+
+#[test]
+fn signed_messages() {
+    for key_type in [SignatureType::Secp256k1, SignatureType::BLS] {
+        let mut tester = tester();
+        let [((_, sender), key), ((_, receiver), _)] =
+            tester.create_accounts_with_keys(key_type, 7).unwrap();
+
+        let message = Message {
+            from: sender,
+            to: receiver,
+            gas_limit: 1_000_000_000,
+            method_num: METHOD_SEND,
+            value: TokenAmount::from_atto(100),
+            ..Message::default()
+        };
+
+        // Wallets sign the message CID.
+        let signed = SignedMessage {
+            signature: key.sign(&message.cid().to_bytes()),
+            message: message.clone(),
+        };
+        assert_eq!(signed.signature.sig_type, key_type);
+        signature::verify(
+            key_type,
+            &signed.signature.bytes,
+            &message.cid().to_bytes(),
+            &sender,
+        )
+        .unwrap();
+
+        tester.instantiate_machine(DummyExterns).unwrap();
+        let res = tester
+            .executor
+            .as_mut()
+            .unwrap()
+            .execute_message(signed.message, ApplyKind::Explicit, 100)
+            .unwrap();
+        assert!(
+            res.msg_receipt.exit_code.is_success(),
+            "{:?}",
+            res.failure_info
+        );
+    }
+}
+
+#[test]
+fn key_from_seed() {
+    for key_type in [SignatureType::Secp256k1, SignatureType::BLS] {
+        let key = AccountKey::from_seed(key_type, 1);
+        assert_eq!(key.address(), AccountKey::from_seed(key_type, 1).address());
+        assert_ne!(key.address(), AccountKey::from_seed(key_type, 2).address());
+    }
+}
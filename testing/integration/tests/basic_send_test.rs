@@ -120,3 +120,58 @@ fn basic_send() {
         assert_eq!(charges, case.trace);
     }
 }
+
+#[test]
+fn dry_run_send() {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let (sender_id, sender) = tester.create_account().unwrap();
+    let receiver = Address::new_delegated(10, b"foobar").expect("failed to construct f4 address");
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+    let sender_state = executor.state_tree().get_actor(sender_id).unwrap().unwrap();
+
+    let message = Message {
+        from: sender,
+        to: receiver,
+        gas_limit: 1000000000,
+        method_num: METHOD_SEND,
+        sequence: 0,
+        value: TokenAmount::from_atto(1),
+        ..Message::default()
+    };
+
+    // The dry run executes and charges the message as usual...
+    let res = executor
+        .execute_message(message.clone(), ApplyKind::DryRun, 100)
+        .unwrap();
+    assert!(res.msg_receipt.exit_code.is_success());
+    assert!(res.dry_run);
+    assert!(res.msg_receipt.gas_used > 0);
+    assert!(!res.exec_trace.is_empty());
+
+    // ...but leaves the state untouched.
+    assert_eq!(
+        executor.state_tree().get_actor(sender_id).unwrap(),
+        Some(sender_state)
+    );
+    assert_eq!(executor.state_tree().lookup_id(&receiver).unwrap(), None);
+
+    // So the same message can still be applied.
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(res.msg_receipt.exit_code.is_success());
+    assert!(!res.dry_run);
+    assert!(executor
+        .state_tree()
+        .lookup_id(&receiver)
+        .unwrap()
+        .is_some());
+}
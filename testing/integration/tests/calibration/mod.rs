@@ -95,7 +95,7 @@ pub struct Obs {
     pub compute_gas: u64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct RegressionResult {
     pub label: String,
     pub intercept: f64,
@@ -114,11 +114,16 @@ const NOP_ACTOR: &str = r#"
 
 // Utility function to instantiation integration tester
 pub fn instantiate_tester() -> TestEnv {
+    instantiate_tester_at(NetworkVersion::V21)
+}
+
+// Instantiates the integration tester at a specific network version, for scenarios covering
+// features introduced after the default calibration network version.
+pub fn instantiate_tester_at(network_version: NetworkVersion) -> TestEnv {
     let blockstore = MemoryBlockstore::default();
     let root = bundle::import_bundle(&blockstore, actors_v12::BUNDLE_CAR).unwrap();
     // Instantiate tester
-    let mut tester =
-        Tester::new(NetworkVersion::V21, StateTreeVersion::V5, root, blockstore).unwrap();
+    let mut tester = Tester::new(network_version, StateTreeVersion::V5, root, blockstore).unwrap();
 
     let sender: [Account; 1] = tester.create_accounts().unwrap();
 
@@ -270,7 +275,114 @@ pub fn least_squares(label: String, obs: &[Obs], var_idx: usize) -> RegressionRe
     }
 }
 
+/// The fixed overheads measured by calling a no-op syscall, and invoking a no-op actor.
+pub struct Baseline {
+    /// Observations of the `OnSyscall` and `OnMethodInvocation` charges, labelled as `syscall`
+    /// and `invocation` respectively.
+    pub obs: Vec<Obs>,
+    /// Regression of the `OnSyscall` time against the number of iterations.
+    pub syscall: RegressionResult,
+    /// Regression of the `OnMethodInvocation` time against the number of iterations.
+    pub invocation: RegressionResult,
+}
+
+lazy_static! {
+    /// The baseline, measured once per test run on a dedicated tester.
+    pub static ref BASELINE: Baseline = measure_baseline(&mut instantiate_tester());
+}
+
+/// Measure the fixed overheads across a number of iteration counts. Neither should depend on the
+/// number of iterations, so the intercepts of the regressions are the overheads themselves.
+pub fn measure_baseline(te: &mut TestEnv) -> Baseline {
+    use fvm_gas_calibration_shared::{Method, OnSendParams, OnSyscallParams};
+
+    let mut syscall_obs = Vec::new();
+    let mut invocation_obs = Vec::new();
+
+    for iterations in [10, 100, 1000, 5000] {
+        let ret = te.execute_or_die(Method::OnSyscall as u64, &OnSyscallParams { iterations });
+        // Only syscalls which didn't charge anything else have their time recorded, i.e. the
+        // no-ops, as opposed to e.g. the ones reading the parameters.
+        let obs = ret
+            .exec_trace
+            .iter()
+            .filter_map(|e| match e {
+                ExecutionEvent::GasCharge(charge) if charge.name == "OnSyscall" => {
+                    charge.elapsed.get().map(|elapsed| Obs {
+                        charge: charge.name.to_string(),
+                        label: "syscall".into(),
+                        elapsed_nanos: elapsed.as_nanos(),
+                        variables: vec![iterations],
+                        compute_gas: charge.compute_gas.as_milligas(),
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        syscall_obs.extend(eliminate_outliers(obs, 0.02, Eliminate::Top));
+
+        let params = OnSendParams {
+            iterations,
+            value_transfer: false,
+            invoke: true,
+        };
+        let ret = te.execute_or_die(Method::OnSend as u64, &params);
+        // The first invocation is the calibration actor itself.
+        let obs = collect_raw_obs(&ret, "OnMethodInvocation", "invocation", iterations)
+            .into_iter()
+            .skip(1)
+            .collect();
+        invocation_obs.extend(eliminate_outliers(obs, 0.02, Eliminate::Top));
+    }
+
+    let syscall = least_squares("syscall".into(), &syscall_obs, 0);
+    let invocation = least_squares("invocation".into(), &invocation_obs, 0);
+    syscall_obs.extend(invocation_obs);
+
+    Baseline {
+        obs: syscall_obs,
+        syscall,
+        invocation,
+    }
+}
+
+/// Collect the observations of a charge, with the fixed syscall overhead subtracted once per
+/// syscall: only from the first observation following each `OnSyscall` charge, not from the
+/// further charges the same syscall makes, nor from charges made outside of syscalls.
 pub fn collect_obs(ret: &ApplyRet, name: &str, label: &str, size: usize) -> Vec<Obs> {
+    let overhead = BASELINE.syscall.intercept.max(0.0) as u128;
+    let mut in_syscall = false;
+    ret.exec_trace
+        .iter()
+        .filter_map(|t| match t {
+            ExecutionEvent::GasCharge(charge) if charge.name == "OnSyscall" => {
+                in_syscall = true;
+                None
+            }
+            ExecutionEvent::GasCharge(charge) if charge.name == "wasm_exec" => {
+                in_syscall = false;
+                None
+            }
+            ExecutionEvent::GasCharge(charge) if charge.name == name => {
+                let mut elapsed_nanos = charge.elapsed.get().unwrap().as_nanos();
+                if std::mem::take(&mut in_syscall) {
+                    elapsed_nanos = elapsed_nanos.saturating_sub(overhead);
+                }
+                Some(Obs {
+                    charge: charge.name.to_string(),
+                    label: label.to_owned(),
+                    elapsed_nanos,
+                    variables: vec![size],
+                    compute_gas: charge.compute_gas.as_milligas(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Collect the observations of a charge as measured.
+pub fn collect_raw_obs(ret: &ApplyRet, name: &str, label: &str, size: usize) -> Vec<Obs> {
     ret.exec_trace
         .iter()
         .filter_map(|t| match t {
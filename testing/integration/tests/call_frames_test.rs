@@ -0,0 +1,46 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+
+mod bundles;
+mod state_actor;
+use state_actor::{instantiate_tester, SET_METHOD};
+
+#[test]
+fn call_frame_summaries() {
+    let (sender, mut tester, actor_address) = instantiate_tester();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    // Setting the value writes the new state.
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: SET_METHOD,
+        params: RawBytes::serialize(1i64).unwrap(),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(res.msg_receipt.exit_code.is_success());
+
+    let [frame] = &res.call_frames[..] else {
+        panic!("expected a single call frame, got {:?}", res.call_frames);
+    };
+    assert_eq!(frame.depth, 0);
+    assert_eq!(frame.caller, sender.0);
+    assert_eq!(frame.callee, actor_address);
+    assert_eq!(frame.method, SET_METHOD);
+    assert_eq!(frame.exit_code, Some(ExitCode::OK));
+    assert!(frame.gas_used.round_up() > 0);
+    assert!(frame.gas_used.round_up() <= res.msg_receipt.gas_used);
+    assert_eq!(frame.self_gas_used, frame.gas_used);
+    assert_eq!(frame.events_emitted, 0);
+    assert_eq!(frame.blocks_written, 1);
+}
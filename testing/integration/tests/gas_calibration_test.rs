@@ -6,6 +6,24 @@ use calibration::*;
 #[cfg(feature = "calibration")]
 use fvm_gas_calibration_shared::*;
 
+#[test]
+#[cfg(feature = "calibration")]
+fn baseline() {
+    // Measured on a dedicated tester, and subtracted from the observations of the other tests.
+    let baseline = &*BASELINE;
+
+    for reg in [&baseline.syscall, &baseline.invocation] {
+        assert!(
+            reg.intercept > 0.0,
+            "{} overhead should be positive",
+            reg.label
+        );
+    }
+
+    let regs = vec![baseline.syscall.clone(), baseline.invocation.clone()];
+    export("Baseline", &baseline.obs, &regs).unwrap();
+}
+
 #[test]
 #[cfg(feature = "calibration")]
 fn on_block() {
@@ -230,6 +248,7 @@ fn utf8_validation() {
 #[cfg(feature = "calibration")]
 fn on_hashing() {
     use fvm_shared::crypto::hash::SupportedHashes;
+    use fvm_shared::version::NetworkVersion;
     use rand::{thread_rng, Rng};
 
     const CHARGE_NAME: &str = "OnHashing";
@@ -241,12 +260,15 @@ fn on_hashing() {
         SupportedHashes::Blake2b512,
         SupportedHashes::Keccak256,
         SupportedHashes::Ripemd160,
+        SupportedHashes::Blake3_256,
+        SupportedHashes::Sha3_512,
     ];
 
     let sizes = common_sizes();
     let iterations = 100;
 
-    let mut te = instantiate_tester();
+    // Blake3 and sha3-512 are only supported from nv26.
+    let mut te = instantiate_tester_at(NetworkVersion::V26);
     let mut obs = Vec::new();
     let mut rng = thread_rng();
 
@@ -7,7 +7,6 @@ use std::rc::Rc;
 use anyhow::anyhow;
 use cid::Cid;
 use fvm::executor::{ApplyKind, Executor, ThreadedExecutor};
-use fvm::machine::Machine;
 use fvm_integration_tests::dummy::DummyExterns;
 use fvm_integration_tests::tester::{Account, IntegrationExecutor, Tester};
 use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
@@ -18,11 +17,12 @@ use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
 use fvm_shared::message::Message;
 use fvm_shared::state::StateTreeVersion;
+use fvm_shared::upgrade::VersionedState;
 use fvm_shared::version::NetworkVersion;
 use fvm_test_actors::wasm_bin::{
     ADDRESS_ACTOR_BINARY, CREATE_ACTOR_BINARY, CUSTOM_SYSCALL_ACTOR_BINARY, EXIT_DATA_ACTOR_BINARY,
-    HELLO_WORLD_ACTOR_BINARY, IPLD_ACTOR_BINARY, OOM_ACTOR_BINARY, READONLY_ACTOR_BINARY,
-    SSELF_ACTOR_BINARY, STACK_OVERFLOW_ACTOR_BINARY, SYSCALL_ACTOR_BINARY,
+    FORWARD_ACTOR_BINARY, HELLO_WORLD_ACTOR_BINARY, IPLD_ACTOR_BINARY, OOM_ACTOR_BINARY,
+    READONLY_ACTOR_BINARY, SSELF_ACTOR_BINARY, STACK_OVERFLOW_ACTOR_BINARY, SYSCALL_ACTOR_BINARY,
     SYSCALL_ACTOR_BINARY_FIP0079, UPGRADE_ACTOR_BINARY, UPGRADE_RECEIVE_ACTOR_BINARY,
 };
 use num_traits::Zero;
@@ -30,7 +30,7 @@ use num_traits::Zero;
 mod bundles;
 use bundles::*;
 use fvm_shared::chainid::ChainID;
-use fvm_shared::ActorID;
+use fvm_shared::{ActorID, MethodNum};
 
 /// The state object.
 #[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, Default)]
@@ -40,6 +40,21 @@ pub struct State {
 
 const NV_FOR_TEST: NetworkVersion = NetworkVersion::V21;
 
+#[test]
+fn test_actor_code_cids() {
+    use fvm_integration_tests::tester::wasm_code_cid;
+    use fvm_test_actors::wasm_bin::{HELLO_WORLD_ACTOR_CODE_CID, SYSCALL_ACTOR_CODE_CID_FIP0079};
+
+    assert_eq!(
+        wasm_code_cid(HELLO_WORLD_ACTOR_BINARY).to_string(),
+        HELLO_WORLD_ACTOR_CODE_CID
+    );
+    assert_eq!(
+        wasm_code_cid(SYSCALL_ACTOR_BINARY_FIP0079).to_string(),
+        SYSCALL_ACTOR_CODE_CID_FIP0079
+    );
+}
+
 #[test]
 fn hello_world() {
     // Instantiate tester
@@ -88,13 +103,13 @@ fn hello_world() {
 
 #[test]
 fn ipld() {
-    // Instantiate tester
-    let mut tester = new_tester(
-        NV_FOR_TEST,
-        StateTreeVersion::V5,
-        MemoryBlockstore::default(),
-    )
-    .unwrap();
+    // The ipld actor queries the block quota, which is only available from nv26. We only have a
+    // bundle for nv21, but this test doesn't depend on the builtin actors.
+    let blockstore = MemoryBlockstore::default();
+    let root =
+        fvm_integration_tests::bundle::import_bundle(&blockstore, actors_v12::BUNDLE_CAR).unwrap();
+    let mut tester =
+        Tester::new(NetworkVersion::V26, StateTreeVersion::V5, root, blockstore).unwrap();
 
     let sender: [Account; 1] = tester.create_accounts().unwrap();
 
@@ -140,12 +155,18 @@ fn ipld() {
 
 #[test]
 fn syscalls() {
-    syscalls_inner(SYSCALL_ACTOR_BINARY)
+    syscalls_inner(SYSCALL_ACTOR_BINARY, 1)
 }
 
 #[test]
 fn syscalls_fip_0079() {
-    syscalls_inner(SYSCALL_ACTOR_BINARY_FIP0079)
+    syscalls_inner(SYSCALL_ACTOR_BINARY_FIP0079, 1)
+}
+
+#[test]
+fn syscalls_error_paths() {
+    // Method 2 calls every syscall with invalid arguments, and checks the exact errors returned.
+    syscalls_inner(SYSCALL_ACTOR_BINARY, 2)
 }
 
 #[test]
@@ -153,7 +174,7 @@ fn syscalls_wasm_properly_imported() {
     assert_ne!(SYSCALL_ACTOR_BINARY, SYSCALL_ACTOR_BINARY_FIP0079)
 }
 
-fn syscalls_inner(wasm_bin: &[u8]) {
+fn syscalls_inner(wasm_bin: &[u8], method_num: MethodNum) {
     // Instantiate tester
     let mut tester = new_tester(
         NV_FOR_TEST,
@@ -192,7 +213,7 @@ fn syscalls_inner(wasm_bin: &[u8]) {
         from: sender[0].1,
         to: actor_address,
         gas_limit: 1000000000,
-        method_num: 1,
+        method_num,
         sequence: 100, // sequence == nonce
         ..Message::default()
     };
@@ -674,6 +695,26 @@ fn no_memory() {
     );
 }
 
+#[test]
+fn user_defined_exit_code() {
+    // Custom actors may abort with codes in the user-defined range, and they reach the receipt
+    // unchanged.
+    test_exitcode(
+        r#"(module
+             (type (;0;) (func (param i32 i32 i32 i32) (result i32)))
+             (import "vm" "exit" (func $fvm_sdk::sys::vm::exit::syscall (type 0)))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0x10042)
+               (i32.const 0)
+               (i32.const 0)
+               (i32.const 0)
+               (call $fvm_sdk::sys::vm::exit::syscall)
+               unreachable))"#,
+        ExitCode::user(0x42),
+    );
+}
+
 #[test]
 fn backtraces() {
     // Note: this test **does not actually assert anything**, but it's useful to
@@ -1120,6 +1161,109 @@ fn readonly_actor_tests() {
     assert!(res.msg_receipt.events_root.is_none());
 }
 
+#[test]
+fn forward_params_block() {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [(_sender_id, sender_address)] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            FORWARD_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let params = RawBytes::serialize("forwarded".repeat(1000)).unwrap();
+    let mut forward = |method_num, sequence| {
+        let message = Message {
+            from: sender_address,
+            to: actor_address,
+            gas_limit: 1000000000,
+            method_num,
+            params: params.clone(),
+            sequence,
+            ..Message::default()
+        };
+
+        let res = executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+        assert!(
+            res.msg_receipt.exit_code.is_success(),
+            "{:?}",
+            res.failure_info
+        );
+        assert_eq!(res.msg_receipt.return_data, params);
+        res.msg_receipt.gas_used
+    };
+
+    // Method 2 forwards the parameters block as-is, method 3 copies it.
+    let forwarded_gas = forward(2, 0);
+    let copied_gas = forward(3, 1);
+    assert!(
+        forwarded_gas < copied_gas,
+        "forwarding used {forwarded_gas} gas, copying used {copied_gas}"
+    );
+}
+
+#[test]
+fn no_reentry_send_flag() {
+    for nv in [NetworkVersion::V25, NetworkVersion::V26] {
+        // We only have a bundle for nv21, but this test doesn't depend on the builtin actors.
+        let blockstore = MemoryBlockstore::default();
+        let root =
+            fvm_integration_tests::bundle::import_bundle(&blockstore, actors_v12::BUNDLE_CAR)
+                .unwrap();
+        let mut tester = Tester::new(nv, StateTreeVersion::V5, root, blockstore).unwrap();
+
+        let [(_sender_id, sender_address)] = tester.create_accounts().unwrap();
+
+        let state_cid = tester.set_state(&[(); 0]).unwrap();
+        let actor_address = Address::new_id(10000);
+        tester
+            .set_actor_from_bin(
+                READONLY_ACTOR_BINARY,
+                state_cid,
+                actor_address,
+                TokenAmount::zero(),
+            )
+            .unwrap();
+
+        tester.instantiate_machine(DummyExterns).unwrap();
+        let executor = tester.executor.as_mut().unwrap();
+
+        let message = Message {
+            from: sender_address,
+            to: actor_address,
+            gas_limit: 1000000000,
+            method_num: 6,
+            ..Message::default()
+        };
+
+        let res = executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+        assert!(
+            res.msg_receipt.exit_code.is_success(),
+            "nv{nv}: {:?}",
+            res.failure_info
+        );
+    }
+}
+
 #[test]
 fn custom_syscall() {
     // Instantiate tester
@@ -1173,21 +1317,12 @@ fn custom_syscall() {
 
 #[test]
 fn upgrade_actor_test() {
-    // inline function to calculate cid from address
-    let calc_cid_func = |bytes: &[u8]| -> Cid {
-        fvm_ipld_blockstore::Block {
-            codec: fvm_shared::IPLD_RAW,
-            data: bytes,
-        }
-        .cid(multihash_codetable::Code::Blake2b256)
-    };
-
     let receiver = Address::new_id(10000);
     let receiver2 = Address::new_id(10001);
     let receiver3 = Address::new_id(10002);
 
     // inline function to reset the tester framework so we can have clean slate between test cases
-    let init_tester = || -> (Tester<MemoryBlockstore, DummyExterns>, [Account; 1]) {
+    let init_tester = || -> (Tester<MemoryBlockstore, DummyExterns>, [Account; 1], Cid) {
         let mut tester = new_tester(
             NetworkVersion::V21,
             StateTreeVersion::V5,
@@ -1232,7 +1367,7 @@ fn upgrade_actor_test() {
 
         tester.instantiate_machine(DummyExterns).unwrap();
 
-        (tester, sender)
+        (tester, sender, state_cid)
     };
 
     struct Case {
@@ -1240,8 +1375,11 @@ fn upgrade_actor_test() {
         method_num: u64,
         // if set, this is the expected receipt data
         return_data: Option<i64>,
-        // if set, this is the expected code cid after the upgrade
-        expected_cid: Option<Cid>,
+        // the expected code of the actor after the message has been applied
+        expected_code: &'static [u8],
+        // if set, this is the expected version of the versioned state written by the upgrade,
+        // otherwise the state root must be left untouched
+        state_version: Option<u64>,
     }
 
     let cases = {
@@ -1251,38 +1389,52 @@ fn upgrade_actor_test() {
             Case {
                 method_num: 1,
                 return_data: Some(666),
-                expected_cid: Some(calc_cid_func(UPGRADE_RECEIVE_ACTOR_BINARY)),
+                expected_code: UPGRADE_RECEIVE_ACTOR_BINARY,
+                state_version: Some(1),
             },
             // test that when `upgrade` endpoint rejects upgrade that we get the returned exit code
             Case {
                 method_num: 2,
                 return_data: None,
-                expected_cid: None,
+                expected_code: UPGRADE_ACTOR_BINARY,
+                state_version: None,
             },
             // test recursive update
             Case {
                 method_num: 3,
                 return_data: Some(444),
-                expected_cid: Some(calc_cid_func(UPGRADE_RECEIVE_ACTOR_BINARY)),
+                expected_code: UPGRADE_RECEIVE_ACTOR_BINARY,
+                state_version: None,
             },
             // test sending a message to ourself (putting us on the call stack)
             Case {
                 method_num: 4,
                 return_data: None,
-                expected_cid: None,
+                expected_code: UPGRADE_ACTOR_BINARY,
+                state_version: None,
             },
             // test that calling an upgrade after self destruct fails with IllegalOperation
             Case {
                 method_num: 5,
                 return_data: None,
-                expected_cid: None,
+                expected_code: UPGRADE_ACTOR_BINARY,
+                state_version: None,
+            },
+            // test that state written by a rejected upgrade is rolled back along with the code
+            Case {
+                method_num: 6,
+                return_data: None,
+                expected_code: UPGRADE_ACTOR_BINARY,
+                state_version: None,
             },
         ]
     };
 
     for case in cases.into_iter() {
-        let (mut tester, sender) = init_tester();
-        let executor = tester.executor.as_mut().unwrap();
+        let (mut tester, sender, state_cid) = init_tester();
+        let id = receiver.id().unwrap();
+
+        tester.assert_actor_code(id, UPGRADE_ACTOR_BINARY);
 
         let message = Message {
             from: sender[0].1,
@@ -1294,7 +1446,10 @@ fn upgrade_actor_test() {
             ..Message::default()
         };
 
-        let res = executor
+        let res = tester
+            .executor
+            .as_mut()
+            .unwrap()
             .execute_message(message, ApplyKind::Explicit, 100)
             .unwrap();
 
@@ -1310,15 +1465,22 @@ fn upgrade_actor_test() {
             assert_eq!(val, return_data);
         }
 
-        // if this test case should have changed the code cid, check that it did
-        if let Some(expected_cid) = case.expected_cid {
-            let code = executor
-                .state_tree()
-                .get_actor(receiver.id().unwrap())
-                .unwrap()
-                .unwrap()
-                .code;
-            assert_eq!(code, expected_cid);
+        // method 5 self-destructs, so there's no actor left to check
+        if case.method_num == 5 {
+            continue;
+        }
+
+        tester.assert_actor_code(id, case.expected_code);
+
+        // if the upgrade should have written a versioned state, check its version
+        let state_root = tester.actor_state(id).unwrap().unwrap().state;
+        match case.state_version {
+            Some(state_version) => {
+                let block = tester.blockstore().get(&state_root).unwrap().unwrap();
+                let state: VersionedState = fvm_ipld_encoding::from_slice(&block).unwrap();
+                assert_eq!(state.version, state_version);
+            }
+            None => assert_eq!(state_root, state_cid),
         }
     }
 }
@@ -0,0 +1,213 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![cfg(test)]
+
+mod bundles;
+use bundles::*;
+use fvm::call_manager::DefaultCallManager;
+use fvm::machine::{DefaultMachine, Machine};
+use fvm_integration_tests::custom_kernel::DefaultCustomKernel;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::INITIAL_ACCOUNT_BALANCE;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_mpool_simulator::{Admission, MpoolSimulator, Rejection};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, METHOD_SEND};
+
+const RAW_LENGTH: usize = 100;
+
+type TestKernel =
+    DefaultCustomKernel<DefaultCallManager<DefaultMachine<MemoryBlockstore, DummyExterns>>>;
+
+fn setup() -> (ActorID, Address, MpoolSimulator<TestKernel>) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let (sender_id, sender) = tester.create_account().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let mpool = MpoolSimulator::new(tester.executor.take().unwrap());
+    (sender_id, sender, mpool)
+}
+
+fn send(from: Address, sequence: u64, value: u64) -> Message {
+    Message {
+        from,
+        to: Address::new_id(10),
+        gas_limit: 1000000000,
+        method_num: METHOD_SEND,
+        sequence,
+        value: TokenAmount::from_atto(value),
+        ..Message::default()
+    }
+}
+
+#[test]
+fn mpool_nonce_ordering() {
+    let (sender_id, sender, mut mpool) = setup();
+
+    // Messages can't skip too far ahead of the next expected nonce.
+    assert!(matches!(
+        mpool.admit(send(sender, 5, 1), RAW_LENGTH).unwrap(),
+        Err(Rejection::NonceGap { next: 0, got: 5 })
+    ));
+
+    // But a small gap is fine, and is closed by later messages.
+    assert!(matches!(
+        mpool.admit(send(sender, 2, 1), RAW_LENGTH).unwrap(),
+        Ok(Admission::Added)
+    ));
+    assert!(matches!(
+        mpool.admit(send(sender, 0, 1), RAW_LENGTH).unwrap(),
+        Ok(Admission::Added)
+    ));
+
+    // Only the message before the gap can be included.
+    let applied = mpool.include_pending().unwrap();
+    assert_eq!(applied.len(), 1);
+    assert!(applied[0].1.msg_receipt.exit_code.is_success());
+    assert_eq!(mpool.pending(sender_id).count(), 1);
+
+    // Stale nonces are rejected.
+    assert!(matches!(
+        mpool.admit(send(sender, 0, 1), RAW_LENGTH).unwrap(),
+        Err(Rejection::NonceTooLow {
+            expected: 1,
+            got: 0
+        })
+    ));
+
+    // Filling the gap unblocks the rest.
+    assert!(matches!(
+        mpool.admit(send(sender, 1, 1), RAW_LENGTH).unwrap(),
+        Ok(Admission::Added)
+    ));
+    let applied = mpool.include_pending().unwrap();
+    assert_eq!(
+        applied.iter().map(|(m, _)| m.sequence).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(mpool.pending(sender_id).count(), 0);
+}
+
+#[test]
+fn mpool_insufficient_balance() {
+    let (_, sender, mut mpool) = setup();
+    let half = u64::try_from(INITIAL_ACCOUNT_BALANCE.atto()).unwrap() / 2;
+
+    assert!(matches!(
+        mpool.admit(send(sender, 0, half), RAW_LENGTH).unwrap(),
+        Ok(Admission::Added)
+    ));
+    // The pending message's value counts against the sender's balance.
+    assert!(matches!(
+        mpool.admit(send(sender, 1, half + 1), RAW_LENGTH).unwrap(),
+        Err(Rejection::InsufficientBalance { .. })
+    ));
+    // Replacing the pending message frees up its value.
+    assert!(matches!(
+        mpool.admit(send(sender, 0, half + 1), RAW_LENGTH).unwrap(),
+        Ok(Admission::Replaced(_))
+    ));
+}
+
+#[test]
+fn mpool_replace_by_fee() {
+    let (sender_id, sender, mut mpool) = setup();
+
+    let msg = Message {
+        gas_premium: TokenAmount::from_atto(100),
+        gas_fee_cap: TokenAmount::from_atto(0),
+        ..send(sender, 0, 1)
+    };
+    assert!(matches!(
+        mpool.admit(msg.clone(), RAW_LENGTH).unwrap(),
+        Ok(Admission::Added)
+    ));
+
+    // The replacement must pay at least 25% more premium.
+    let underpriced = Message {
+        gas_premium: TokenAmount::from_atto(124),
+        value: TokenAmount::from_atto(2),
+        ..msg.clone()
+    };
+    assert_eq!(
+        mpool.admit(underpriced, RAW_LENGTH).unwrap().unwrap_err(),
+        Rejection::ReplacementUnderpriced {
+            required: TokenAmount::from_atto(125),
+            got: TokenAmount::from_atto(124),
+        }
+    );
+
+    let replacement = Message {
+        gas_premium: TokenAmount::from_atto(125),
+        value: TokenAmount::from_atto(2),
+        ..msg.clone()
+    };
+    match mpool.admit(replacement.clone(), RAW_LENGTH).unwrap() {
+        Ok(Admission::Replaced(old)) => assert_eq!(*old, msg),
+        other => panic!("expected replacement, got {other:?}"),
+    }
+    assert_eq!(
+        mpool.pending(sender_id).collect::<Vec<_>>(),
+        vec![&replacement]
+    );
+}
+
+#[test]
+fn mpool_estimate_gas() {
+    let (sender_id, sender, mut mpool) = setup();
+    let msg = send(sender, 0, 1);
+
+    let estimate = mpool.estimate_gas(&msg, RAW_LENGTH).unwrap();
+    assert!(estimate.exit_code.is_success());
+    assert!(estimate.gas_used > 0);
+
+    // Estimation doesn't change the state.
+    let sender_state = mpool
+        .executor()
+        .state_tree()
+        .get_actor(sender_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(sender_state.sequence, 0);
+    assert_eq!(sender_state.balance, *INITIAL_ACCOUNT_BALANCE);
+
+    // And the estimate matches what the executor charges.
+    let msg = Message {
+        gas_limit: estimate.gas_used,
+        ..msg
+    };
+    assert!(matches!(
+        mpool.admit(msg, RAW_LENGTH).unwrap(),
+        Ok(Admission::Added)
+    ));
+    let applied = mpool.include_pending().unwrap();
+    assert_eq!(applied[0].1.msg_receipt.gas_used, estimate.gas_used);
+    assert!(applied[0].1.msg_receipt.exit_code.is_success());
+}
+
+#[test]
+fn mpool_estimate_gas_ignores_fee_cap() {
+    let (_, sender, mut mpool) = setup();
+
+    // The sender can't cover this fee cap for the block gas limit, but estimation doesn't charge
+    // for gas.
+    let msg = Message {
+        gas_fee_cap: INITIAL_ACCOUNT_BALANCE.clone(),
+        gas_premium: TokenAmount::from_atto(1),
+        ..send(sender, 0, 1)
+    };
+    let estimate = mpool.estimate_gas(&msg, RAW_LENGTH).unwrap();
+    assert!(estimate.exit_code.is_success());
+    let unpriced = mpool.estimate_gas(&send(sender, 0, 1), RAW_LENGTH).unwrap();
+    assert_eq!(estimate.gas_used, unpriced.gas_used);
+}
@@ -0,0 +1,53 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, Tester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::STATE_ACTOR_BINARY;
+use num_traits::Zero;
+
+use crate::bundles::new_tester;
+
+/// The state actor's ID.
+#[allow(dead_code)]
+pub const STATE_ACTOR_ID: u64 = 10000;
+
+/// Sets the value held by the state actor.
+#[allow(dead_code)]
+pub const SET_METHOD: u64 = 1;
+/// Returns the value held by the state actor.
+#[allow(dead_code)]
+pub const GET_METHOD: u64 = 2;
+/// Sets the value in a subcall, then aborts, reverting it.
+#[allow(dead_code)]
+pub const SET_AND_REVERT_METHOD: u64 = 3;
+
+/// Instantiates an integration tester with an account, and the state actor holding `0` deployed
+/// at `f010000`.
+pub fn instantiate_tester() -> (Account, Tester<MemoryBlockstore, DummyExterns>, Address) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&0i64).unwrap();
+    let actor_address = Address::new_id(STATE_ACTOR_ID);
+    tester
+        .set_actor_from_bin(
+            STATE_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    (sender, tester, actor_address)
+}
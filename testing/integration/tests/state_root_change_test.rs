@@ -0,0 +1,133 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use cid::Cid;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::Machine;
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::ActorID;
+
+mod bundles;
+mod state_actor;
+use state_actor::{
+    instantiate_tester, GET_METHOD, SET_AND_REVERT_METHOD, SET_METHOD, STATE_ACTOR_ID,
+};
+
+/// Returns the state root changes traced while applying a message.
+fn root_changes(ret: &ApplyRet) -> Vec<(ActorID, Cid, Cid)> {
+    ret.exec_trace
+        .iter()
+        .filter_map(|e| match e {
+            ExecutionEvent::StateRootChange {
+                actor,
+                old_root,
+                new_root,
+            } => Some((*actor, *old_root, *new_root)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn state_root_change_trace() {
+    let (sender, mut tester, actor_address) = instantiate_tester();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let old_root = executor
+        .state_tree()
+        .get_actor(STATE_ACTOR_ID)
+        .unwrap()
+        .unwrap()
+        .state;
+
+    // Setting the value changes the state root.
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: SET_METHOD,
+        params: RawBytes::serialize(1i64).unwrap(),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(res.msg_receipt.exit_code.is_success());
+
+    let new_root = executor
+        .state_tree()
+        .get_actor(STATE_ACTOR_ID)
+        .unwrap()
+        .unwrap()
+        .state;
+    assert_ne!(old_root, new_root);
+    assert_eq!(
+        root_changes(&res),
+        vec![(STATE_ACTOR_ID, old_root, new_root)]
+    );
+
+    // Reading the value doesn't.
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: GET_METHOD,
+        sequence: 1,
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(res.msg_receipt.exit_code.is_success());
+    assert!(root_changes(&res).is_empty());
+}
+
+#[test]
+fn reverted_state_root_change() {
+    let (sender, mut tester, actor_address) = instantiate_tester();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let root = executor
+        .state_tree()
+        .get_actor(STATE_ACTOR_ID)
+        .unwrap()
+        .unwrap()
+        .state;
+
+    // The value is set in a subcall, which succeeds, but the outer call then aborts.
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: SET_AND_REVERT_METHOD,
+        params: RawBytes::serialize(1i64).unwrap(),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_ASSERTION_FAILED);
+
+    // The subcall was traced, but not its reverted state root change.
+    let calls = res
+        .exec_trace
+        .iter()
+        .filter(|e| matches!(e, ExecutionEvent::Call { method, .. } if *method == SET_METHOD))
+        .count();
+    assert_eq!(calls, 1);
+    assert!(root_changes(&res).is_empty());
+    assert_eq!(
+        executor
+            .state_tree()
+            .get_actor(STATE_ACTOR_ID)
+            .unwrap()
+            .unwrap()
+            .state,
+        root
+    );
+}
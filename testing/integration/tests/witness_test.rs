@@ -0,0 +1,76 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm::executor::{ApplyKind, Executor};
+use fvm::machine::Machine;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateRoot;
+
+mod bundles;
+mod state_actor;
+use state_actor::{instantiate_tester, GET_METHOD};
+
+#[test]
+fn witness() {
+    // Instantiate tester
+    let (sender, mut tester, actor_address) = instantiate_tester();
+
+    // Instantiate machine, recording the blocks read by each message.
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                mc.enable_witness();
+            },
+        )
+        .unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    let root = executor.flush().unwrap();
+    let StateRoot { actors, .. } = executor
+        .state_tree()
+        .store()
+        .get_cbor(&root)
+        .unwrap()
+        .unwrap();
+
+    // This also caches the actor in the state tree.
+    let state_root = executor
+        .state_tree()
+        .get_actor(actor_address.id().unwrap())
+        .unwrap()
+        .unwrap()
+        .state;
+
+    // Read the actor's value, twice: the second message is served the sender and the actor from
+    // the state tree's caches.
+    for sequence in 0..2 {
+        let message = Message {
+            from: sender.1,
+            to: actor_address,
+            gas_limit: 1000000000,
+            method_num: GET_METHOD,
+            sequence,
+            ..Message::default()
+        };
+
+        let res = executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+        assert!(res.msg_receipt.exit_code.is_success());
+
+        // The actor read its state, and the actors' HAMT proves the actors it was served, so
+        // both must be part of the witness.
+        for cid in [state_root, actors] {
+            assert!(
+                res.witness.contains(&cid),
+                "witness {:?} of message {} doesn't contain {}",
+                res.witness,
+                sequence,
+                cid
+            );
+        }
+    }
+}
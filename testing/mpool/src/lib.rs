@@ -0,0 +1,290 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! A message pool admission simulator for tests.
+//!
+//! [`MpoolSimulator`] wraps a [`DefaultExecutor`] and mimics the checks a node's message pool
+//! performs before accepting a message (nonce ordering, nonce gaps, sender balance, and
+//! replace-by-fee), gas estimation by speculative execution, and finally block inclusion by
+//! applying the pending messages against the real executor.
+
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use fvm::executor::{ApplyKind, ApplyRet, DefaultExecutor, Executor};
+use fvm::kernel::Context as _;
+use fvm::machine::Machine;
+use fvm::Kernel;
+use fvm_shared::bigint::Zero;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::{ActorID, BLOCK_GAS_LIMIT};
+
+/// Message pool admission policy.
+#[derive(Clone, Debug)]
+pub struct MpoolConfig {
+    /// The maximum number of nonces a message may skip past the next expected nonce.
+    pub max_nonce_gap: u64,
+    /// The minimum premium (as a percentage of the replaced message's premium) a message must pay
+    /// to replace a pending message with the same nonce.
+    pub replace_by_fee_percent: u64,
+}
+
+impl Default for MpoolConfig {
+    fn default() -> Self {
+        Self {
+            max_nonce_gap: 4,
+            replace_by_fee_percent: 125,
+        }
+    }
+}
+
+/// The reason a message was refused admission to the pool.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Rejection {
+    #[error("invalid message: {0}")]
+    InvalidMessage(String),
+    #[error("gas limit {limit} is below the message inclusion cost {required}")]
+    GasLimitTooLow { required: u64, limit: u64 },
+    #[error("sender does not exist")]
+    SenderNotFound,
+    #[error("nonce {got} is below the sender's current nonce {expected}")]
+    NonceTooLow { expected: u64, got: u64 },
+    #[error("nonce {got} is too far ahead of the next expected nonce {next}")]
+    NonceGap { next: u64, got: u64 },
+    #[error("gas premium {got} is below the replacement minimum {required}")]
+    ReplacementUnderpriced {
+        required: TokenAmount,
+        got: TokenAmount,
+    },
+    #[error("sender balance {available} can't cover pending messages costing {required}")]
+    InsufficientBalance {
+        required: TokenAmount,
+        available: TokenAmount,
+    },
+}
+
+/// The result of admitting a message to the pool.
+#[derive(Clone, Debug)]
+pub enum Admission {
+    /// The message was added to the pool.
+    Added,
+    /// The message replaced a pending message with the same nonce, returned here.
+    Replaced(Box<Message>),
+}
+
+/// The result of estimating a message's gas usage.
+#[derive(Clone, Debug)]
+pub struct GasEstimate {
+    /// The gas used by the message when executed with the block gas limit.
+    pub gas_used: u64,
+    /// The exit code of the speculative execution.
+    pub exit_code: ExitCode,
+}
+
+/// Simulates message pool admission and block inclusion against a [`DefaultExecutor`].
+pub struct MpoolSimulator<K: Kernel> {
+    executor: DefaultExecutor<K>,
+    config: MpoolConfig,
+    // Pending messages (and their on-chain lengths) by sender, then by nonce.
+    pending: BTreeMap<ActorID, BTreeMap<u64, (Message, usize)>>,
+}
+
+impl<K: Kernel> MpoolSimulator<K> {
+    /// Creates a simulator with the default [`MpoolConfig`].
+    pub fn new(executor: DefaultExecutor<K>) -> Self {
+        Self::with_config(executor, MpoolConfig::default())
+    }
+
+    /// Creates a simulator with the specified [`MpoolConfig`].
+    pub fn with_config(executor: DefaultExecutor<K>, config: MpoolConfig) -> Self {
+        Self {
+            executor,
+            config,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the underlying executor.
+    pub fn executor(&mut self) -> &mut DefaultExecutor<K> {
+        &mut self.executor
+    }
+
+    /// Consumes the simulator, returning the underlying executor. Pending messages are dropped.
+    pub fn into_executor(self) -> DefaultExecutor<K> {
+        self.executor
+    }
+
+    /// Returns the pending messages from the given sender, in nonce order.
+    pub fn pending(&self, sender: ActorID) -> impl Iterator<Item = &Message> {
+        self.pending
+            .get(&sender)
+            .into_iter()
+            .flat_map(|msgs| msgs.values().map(|(msg, _)| msg))
+    }
+
+    /// Validates a message against the current state and the pending messages, adding it to the
+    /// pool if accepted. `raw_length` is the length of the message as it appears on-chain.
+    ///
+    /// The outer error indicates a failure to read the state, not a rejected message.
+    pub fn admit(
+        &mut self,
+        msg: Message,
+        raw_length: usize,
+    ) -> anyhow::Result<Result<Admission, Rejection>> {
+        if let Err(e) = msg.check() {
+            return Ok(Err(Rejection::InvalidMessage(e.to_string())));
+        }
+
+        let inclusion_cost = self
+            .executor
+            .context()
+            .price_list
+            .on_chain_message(raw_length)
+            .total()
+            .round_up();
+        if inclusion_cost > msg.gas_limit {
+            return Ok(Err(Rejection::GasLimitTooLow {
+                required: inclusion_cost,
+                limit: msg.gas_limit,
+            }));
+        }
+
+        let state_tree = self.executor.state_tree();
+        let Some(sender) = state_tree
+            .lookup_id(&msg.from)
+            .with_context(|| format!("failed to lookup actor {}", msg.from))?
+        else {
+            return Ok(Err(Rejection::SenderNotFound));
+        };
+        let sender_state = state_tree
+            .get_actor(sender)
+            .with_context(|| format!("failed to load actor {}", msg.from))?
+            .ok_or_else(|| anyhow!("actor {} has an ID but no state", msg.from))?;
+
+        if msg.sequence < sender_state.sequence {
+            return Ok(Err(Rejection::NonceTooLow {
+                expected: sender_state.sequence,
+                got: msg.sequence,
+            }));
+        }
+
+        let pending = self.pending.entry(sender).or_default();
+        let replaced = pending.get(&msg.sequence).map(|(m, _)| m);
+        match replaced {
+            Some(old) => {
+                let required =
+                    (&old.gas_premium * self.config.replace_by_fee_percent).div_ceil(100);
+                if msg.gas_premium < required {
+                    return Ok(Err(Rejection::ReplacementUnderpriced {
+                        required,
+                        got: msg.gas_premium,
+                    }));
+                }
+            }
+            None => {
+                let mut next = sender_state.sequence;
+                while pending.contains_key(&next) {
+                    next += 1;
+                }
+                if msg.sequence > next + self.config.max_nonce_gap {
+                    return Ok(Err(Rejection::NonceGap {
+                        next,
+                        got: msg.sequence,
+                    }));
+                }
+            }
+        }
+
+        let required: TokenAmount = pending
+            .values()
+            .map(|(m, _)| m)
+            .filter(|m| m.sequence != msg.sequence)
+            .chain(std::iter::once(&msg))
+            .map(max_cost)
+            .sum();
+        if required > sender_state.balance {
+            return Ok(Err(Rejection::InsufficientBalance {
+                required,
+                available: sender_state.balance,
+            }));
+        }
+
+        Ok(Ok(match pending.insert(msg.sequence, (msg, raw_length)) {
+            Some((old, _)) => Admission::Replaced(Box::new(old)),
+            None => Admission::Added,
+        }))
+    }
+
+    /// Executes a message against the current state, then reverts all state changes.
+    ///
+    /// Errors are fatal and leave the underlying executor unusable.
+    pub fn execute_speculative(
+        &mut self,
+        msg: Message,
+        raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        self.executor.state_tree_mut().begin_transaction();
+        let ret = self
+            .executor
+            .execute_message(msg, ApplyKind::Explicit, raw_length)?;
+        self.executor.state_tree_mut().end_transaction(true)?;
+        Ok(ret)
+    }
+
+    /// Estimates the gas used by a message by executing it speculatively with the block gas limit.
+    /// The message's fee cap and premium are zeroed, so the estimate doesn't depend on whether the
+    /// sender could pay for the block gas limit.
+    pub fn estimate_gas(
+        &mut self,
+        msg: &Message,
+        raw_length: usize,
+    ) -> anyhow::Result<GasEstimate> {
+        let msg = Message {
+            gas_limit: BLOCK_GAS_LIMIT,
+            gas_fee_cap: TokenAmount::zero(),
+            gas_premium: TokenAmount::zero(),
+            ..msg.clone()
+        };
+        let ret = self.execute_speculative(msg, raw_length)?;
+        Ok(GasEstimate {
+            gas_used: ret.msg_receipt.gas_used,
+            exit_code: ret.msg_receipt.exit_code,
+        })
+    }
+
+    /// Simulates block inclusion: applies every pending message whose nonce immediately follows its
+    /// sender's current nonce (in nonce order), removing them from the pool. Messages that remain
+    /// blocked behind a nonce gap stay pending.
+    pub fn include_pending(&mut self) -> anyhow::Result<Vec<(Message, ApplyRet)>> {
+        let mut applied = Vec::new();
+        for (&sender, pending) in self.pending.iter_mut() {
+            loop {
+                let sequence = self
+                    .executor
+                    .state_tree()
+                    .get_actor(sender)?
+                    .ok_or_else(|| anyhow!("pending sender {sender} no longer exists"))?
+                    .sequence;
+                // Drop anything made stale by the previous message.
+                pending.retain(|&nonce, _| nonce >= sequence);
+                let Some((msg, raw_length)) = pending.remove(&sequence) else {
+                    break;
+                };
+                let ret =
+                    self.executor
+                        .execute_message(msg.clone(), ApplyKind::Explicit, raw_length)?;
+                applied.push((msg, ret));
+            }
+        }
+        self.pending.retain(|_, msgs| !msgs.is_empty());
+        Ok(applied)
+    }
+}
+
+/// The maximum amount a message can cost its sender: the value transferred plus the fee cap for
+/// the full gas limit.
+fn max_cost(msg: &Message) -> TokenAmount {
+    &msg.gas_fee_cap * msg.gas_limit + &msg.value
+}
@@ -0,0 +1,56 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::{to_vec, CBOR};
+use fvm_sdk as sdk;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::sys::SendFlags;
+
+/// The method receiving the forwarded parameters.
+const RECEIVE_METHOD: u64 = 4;
+
+#[no_mangle]
+pub fn invoke(blk: u32) -> u32 {
+    sdk::initialize();
+
+    let receiver = Address::new_id(sdk::message::receiver());
+    match sdk::message::method_number() {
+        2 => {
+            // Forward our parameters block as-is, and return the return block as-is.
+            let ret = sdk::send::send_block(
+                &receiver,
+                RECEIVE_METHOD,
+                blk,
+                TokenAmount::default(),
+                None,
+                SendFlags::empty(),
+            )
+            .unwrap();
+            assert_eq!(ret.exit_code, 0);
+            ret.return_id
+        }
+        3 => {
+            // Same as above, but copy the parameters and the return value.
+            let params = sdk::message::params_raw(blk).unwrap();
+            let ret = sdk::send::send(
+                &receiver,
+                RECEIVE_METHOD,
+                params,
+                TokenAmount::default(),
+                None,
+                SendFlags::empty(),
+            )
+            .unwrap();
+            assert!(ret.exit_code.is_success());
+            let ret = ret.return_data.unwrap();
+            sdk::ipld::put_block(ret.codec, &ret.data).unwrap()
+        }
+        RECEIVE_METHOD => {
+            let params = sdk::message::params_raw(blk).unwrap().unwrap();
+            assert_eq!(params.codec, CBOR);
+            assert_eq!(params.data, to_vec(&"forwarded".repeat(1000)).unwrap());
+            blk
+        }
+        _ => panic!("unexpected method"),
+    }
+}
@@ -55,6 +55,7 @@ fn dispatch(method: Method, params_ptr: u32) -> Result<()> {
         Method::OnSend => dispatch_to(on_send, params_ptr),
         Method::OnEvent => dispatch_to(on_event, params_ptr),
         Method::OnScanIpldLinks => dispatch_to(on_scan_ipld_links, params_ptr),
+        Method::OnSyscall => dispatch_to(on_syscall, params_ptr),
     }
 }
 
@@ -176,6 +177,14 @@ fn on_send(p: OnSendParams) -> Result<()> {
     Ok(())
 }
 
+fn on_syscall(p: OnSyscallParams) -> Result<()> {
+    for _i in 0..p.iterations {
+        // Checking the available gas doesn't charge anything beyond the syscall itself.
+        fvm_sdk::gas::available();
+    }
+    Ok(())
+}
+
 fn on_event(p: OnEventParams) -> Result<()> {
     let mut value = vec![0; p.total_value_size];
 
@@ -18,8 +18,10 @@ pub fn invoke(_: u32) -> u32 {
 
     test_open_block();
     test_read_block();
+    test_streaming_read();
     test_create_block();
     test_stat_block();
+    test_block_quota();
     test_link_block();
 
     #[cfg(coverage)]
@@ -159,6 +161,57 @@ fn test_read_block() {
     }
 }
 
+fn test_streaming_read() {
+    let test_bytes = gen_test_bytes(10 << 10);
+    let k = sdk::ipld::put(0xb220, 32, DAG_CBOR, &test_bytes).unwrap();
+
+    // Read into a caller-provided buffer.
+    {
+        let mut buf = vec![0u8; 2 * test_bytes.len()];
+        let size = sdk::ipld::get_into(&k, &mut buf).unwrap();
+        assert_eq!(size, test_bytes.len());
+        assert_eq!(test_bytes, buf[..size], "should have read entire block");
+
+        // The block must fit.
+        let res = sdk::ipld::get_into(&k, &mut buf[..test_bytes.len() - 1]);
+        assert_eq!(res, Err(ErrorNumber::BufferTooSmall));
+    }
+
+    // Read in chunks.
+    {
+        let reader = sdk::ipld::BlockReader::open(&k).unwrap();
+        assert_eq!(reader.codec(), DAG_CBOR);
+        assert_eq!(reader.size() as usize, test_bytes.len());
+
+        let chunks: Vec<Vec<u8>> = reader.chunks(1000).collect::<Result<_, _>>().unwrap();
+        assert_eq!(chunks.len(), test_bytes.len().div_ceil(1000));
+        assert!(chunks.iter().all(|c| c.len() <= 1000));
+        assert_eq!(test_bytes, chunks.concat(), "chunks should cover the block");
+    }
+
+    // Read piece by piece.
+    {
+        let mut reader = sdk::ipld::BlockReader::open(&k).unwrap();
+        let mut buf = [0u8; 10];
+        assert_eq!(reader.read(&mut buf).unwrap(), 10);
+        assert_eq!(&test_bytes[..10], &buf[..]);
+        assert_eq!(reader.remaining() as usize, test_bytes.len() - 10);
+
+        let mut rest = vec![0u8; test_bytes.len()];
+        assert_eq!(reader.read(&mut rest).unwrap(), test_bytes.len() - 10);
+        assert_eq!(&test_bytes[10..], &rest[..test_bytes.len() - 10]);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0, "should be at the end");
+    }
+
+    // Identity-hashed blocks are read from the CID.
+    {
+        let k = sdk::ipld::put(fvm_shared::IDENTITY_HASH, 3, DAG_CBOR, b"foo").unwrap();
+        let mut buf = [0u8; 3];
+        assert_eq!(sdk::ipld::get_into(&k, &mut buf).unwrap(), 3);
+        assert_eq!(&buf, b"foo");
+    }
+}
+
 fn test_create_block() {
     unsafe {
         // Test creating a block with invalid codec
@@ -207,6 +260,20 @@ fn test_stat_block() {
     }
 }
 
+fn test_block_quota() {
+    let bytes = gen_test_bytes(10 << 10);
+
+    let before = sdk::ipld::quota();
+    sdk::ipld::put_block(DAG_CBOR, &bytes).unwrap();
+    let after = sdk::ipld::quota();
+
+    assert_eq!({ after.blocks_remaining }, { before.blocks_remaining } - 1);
+    assert_eq!(
+        { after.bytes_remaining },
+        { before.bytes_remaining } - bytes.len() as u64
+    );
+}
+
 fn test_link_block() {
     let bytes = gen_test_bytes(10 << 10);
 
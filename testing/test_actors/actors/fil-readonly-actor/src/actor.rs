@@ -10,6 +10,7 @@ use fvm_shared::crypto::hash::SupportedHashes;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::event::{Entry, Flags};
 use fvm_shared::sys::SendFlags;
+use fvm_shared::version::NetworkVersion;
 use fvm_shared::METHOD_SEND;
 use sdk::error::{ActorDeleteError, StateUpdateError};
 use sdk::sys::ErrorNumber;
@@ -181,6 +182,36 @@ fn invoke_method(blk: u32, method: u64) -> u32 {
             assert!(sdk::vm::read_only());
             sdk::vm::abort(42, None)
         }
+        6 => {
+            let receiver = Address::new_id(sdk::message::receiver());
+            let send = |to: &Address, method, flags| {
+                sdk::send::send(to, method, None, Default::default(), None, flags)
+            };
+
+            // The flag is only supported from nv26.
+            if sdk::network::version() < NetworkVersion::V26 {
+                let resp = send(&receiver, 7, SendFlags::NO_REENTRY);
+                assert_eq!(resp, Err(ErrorNumber::IllegalArgument));
+                return 0;
+            }
+
+            // Calls back into this actor are rejected while it sends with NO_REENTRY...
+            let resp = send(&receiver, 7, SendFlags::NO_REENTRY);
+            assert_eq!(resp, Err(ErrorNumber::Forbidden));
+
+            // ... but other actors can still be called...
+            assert!(send(&account, METHOD_SEND, SendFlags::NO_REENTRY)
+                .unwrap()
+                .exit_code
+                .is_success());
+
+            // ... and the guard is released once the send returns.
+            assert!(send(&receiver, 7, SendFlags::empty())
+                .unwrap()
+                .exit_code
+                .is_success());
+        }
+        7 => {}
         _ => panic!("unexpected method"),
     }
     0
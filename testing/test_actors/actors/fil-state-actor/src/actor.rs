@@ -0,0 +1,62 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::{from_slice, to_vec, CBOR, DAG_CBOR};
+use fvm_sdk as sdk;
+use fvm_shared::address::Address;
+use fvm_shared::crypto::hash::SupportedHashes;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::sys::SendFlags;
+
+/// Sets the value held in the actor's state (its state root is the CBOR-encoded value).
+const SET_METHOD: u64 = 1;
+/// Returns the value held in the actor's state.
+const GET_METHOD: u64 = 2;
+/// Sets the value in a subcall, then aborts, reverting it.
+const SET_AND_REVERT_METHOD: u64 = 3;
+
+#[no_mangle]
+pub fn invoke(blk: u32) -> u32 {
+    sdk::initialize();
+
+    match sdk::message::method_number() {
+        SET_METHOD => {
+            let params = sdk::message::params_raw(blk).unwrap().unwrap();
+            let value: i64 = from_slice(&params.data).unwrap();
+            let root = sdk::ipld::put(
+                SupportedHashes::Blake2b256.into(),
+                32,
+                DAG_CBOR,
+                &to_vec(&value).unwrap(),
+            )
+            .unwrap();
+            sdk::sself::set_root(&root).unwrap();
+            0
+        }
+        GET_METHOD => {
+            let root = sdk::sself::root().unwrap();
+            let value: i64 = from_slice(&sdk::ipld::get(&root).unwrap()).unwrap();
+            sdk::ipld::put_block(CBOR, &to_vec(&value).unwrap()).unwrap()
+        }
+        SET_AND_REVERT_METHOD => {
+            let params = sdk::message::params_raw(blk).unwrap();
+            let receiver = Address::new_id(sdk::message::receiver());
+            let ret = sdk::send::send(
+                &receiver,
+                SET_METHOD,
+                params,
+                TokenAmount::default(),
+                None,
+                SendFlags::empty(),
+            )
+            .unwrap();
+            assert!(ret.exit_code.is_success());
+
+            sdk::vm::abort(
+                ExitCode::USR_ASSERTION_FAILED.value(),
+                Some("reverting the value set"),
+            )
+        }
+        _ => panic!("unexpected method"),
+    }
+}
@@ -8,7 +8,10 @@ use fvm_shared::crypto::hash::SupportedHashes as SharedSupportedHashes;
 use fvm_shared::crypto::signature::{Signature, SECP_SIG_LEN};
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::sector::RegisteredSealProof;
-use multihash_codetable::{Blake2b256, Blake2b512, Keccak256, Ripemd160, Sha2_256};
+use fvm_shared::version::NetworkVersion;
+use multihash_codetable::{
+    Blake2b256, Blake2b512, Blake3_256, Keccak256, Ripemd160, Sha2_256, Sha3_512,
+};
 use multihash_derive::MultihashDigest;
 use std::ptr;
 
@@ -26,12 +29,22 @@ pub enum SupportedHashes {
     Keccak256,
     #[mh(code = 0x1053, hasher = Ripemd160)]
     Ripemd160,
+    #[mh(code = 0x1e, hasher = Blake3_256)]
+    Blake3_256,
+    #[mh(code = 0x14, hasher = Sha3_512)]
+    Sha3_512,
 }
 
 #[no_mangle]
 pub fn invoke(_: u32) -> u32 {
     sdk::initialize();
 
+    // Method 2 checks the error paths of every syscall.
+    if sdk::message::method_number() == 2 {
+        crate::errors::test_error_paths();
+        return 0;
+    }
+
     test_secp_signature();
     test_bls_signature();
     test_bls_aggregate();
@@ -302,6 +315,33 @@ fn test_expected_hash() {
 
         assert_eq!(local_digest.digest(), digest.as_slice());
     }
+    // blake3 and sha3-512 (only supported from nv26)
+    if sdk::network::version() >= NetworkVersion::V26 {
+        let local_digest = SupportedHashes::Blake3_256.digest(test_bytes);
+        let digest = sdk::crypto::hash_blake3(test_bytes);
+        assert_eq!(local_digest.digest(), digest.as_slice());
+
+        let local_digest = SupportedHashes::Sha3_512.digest(test_bytes);
+        let digest = sdk::crypto::hash_sha3_512(test_bytes);
+        assert_eq!(local_digest.digest(), digest.as_slice());
+    } else {
+        let mut buffer = [0u8; 64];
+        for hasher in [
+            SharedSupportedHashes::Blake3_256,
+            SharedSupportedHashes::Sha3_512,
+        ] {
+            let res = unsafe {
+                sdk::sys::crypto::hash(
+                    hasher as u64,
+                    test_bytes.as_ptr(),
+                    test_bytes.len() as u32,
+                    buffer.as_mut_ptr(),
+                    buffer.len() as u32,
+                )
+            };
+            assert_eq!(res, Err(ErrorNumber::IllegalArgument));
+        }
+    }
 
     // hash_owned and hash_into should return the same digest
     {
@@ -446,10 +486,20 @@ fn test_network_context() {
     use fvm_shared::econ::TokenAmount;
     use fvm_shared::version::NetworkVersion;
     assert_eq!(sdk::network::chain_id(), ChainID::from(1)); // hehe we are ETH now
-    assert_eq!(sdk::network::curr_epoch(), 0);
+    assert_eq!(sdk::network::current_epoch(), 0);
     assert_eq!(sdk::network::version(), NetworkVersion::V21);
     assert_eq!(sdk::network::tipset_timestamp(), 0);
     assert_eq!(sdk::network::base_fee(), TokenAmount::from_atto(100));
+
+    // The syscall rejects the current epoch, and negative ones.
+    assert_eq!(
+        sdk::network::tipset_cid(0),
+        Err(sdk::error::EpochBoundsError::Invalid)
+    );
+    assert_eq!(
+        sdk::network::tipset_cid(-1),
+        Err(sdk::error::EpochBoundsError::Invalid)
+    );
 }
 
 fn test_message_context() {
@@ -0,0 +1,413 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Calls every syscall with invalid arguments (malformed and unreachable CIDs, out of bounds
+//! offsets, huge lengths, invalid codecs and handles, ...) and checks the exact error returned.
+//!
+//! This pins down the syscall error surface: changing the error returned for any of these cases is
+//! a consensus-visible change, and must be deliberate.
+
+use fvm_ipld_encoding::{DAG_CBOR, IPLD_RAW};
+use fvm_sdk as sdk;
+use fvm_sdk::sys;
+use fvm_shared::address::Address;
+use fvm_shared::crypto::hash::SupportedHashes;
+use fvm_shared::error::ErrorNumber;
+use fvm_shared::event::Flags;
+use fvm_shared::sys::{EventEntry, SendFlags};
+use multihash_codetable::{Code, MultihashDigest};
+
+/// An offset past the end of the actor's memory.
+const OOB: u32 = u32::MAX;
+
+/// A length larger than the actor's memory.
+const HUGE: u32 = u32::MAX / 2;
+
+/// A block handle that was never allocated.
+const BAD_HANDLE: u32 = 0xdead;
+
+/// An actor that doesn't exist.
+const MISSING_ACTOR: u64 = 9191919;
+
+/// Bytes that don't start with a valid CID: the version varint never terminates.
+const MALFORMED_CID: [u8; 16] = [0xff; 16];
+
+/// Asserts that a raw syscall fails with the given error number.
+macro_rules! expect_err {
+    ($code:ident, $call:expr) => {{
+        #[allow(unused_unsafe)]
+        let res = unsafe { $call }.map(|_| ());
+        assert_eq!(
+            res,
+            Err(ErrorNumber::$code),
+            "{} should fail with {}",
+            stringify!($call),
+            ErrorNumber::$code
+        );
+    }};
+}
+
+fn out_of_bounds<T>() -> *mut T {
+    OOB as *mut T
+}
+
+pub fn test_error_paths() {
+    test_ipld_errors();
+    test_self_errors();
+    test_actor_errors();
+    test_send_errors();
+    test_event_errors();
+    test_network_errors();
+    test_gas_errors();
+    test_crypto_errors();
+    test_debug_errors();
+}
+
+fn test_ipld_errors() {
+    use sys::ipld::*;
+
+    let data = b"foo";
+    let unreachable = cid(data).to_bytes();
+    let mut buf = [0u8; 128];
+
+    // block_open
+    expect_err!(NotFound, block_open(unreachable.as_ptr()));
+    expect_err!(IllegalArgument, block_open(MALFORMED_CID.as_ptr()));
+    expect_err!(IllegalArgument, block_open(out_of_bounds()));
+
+    // block_create
+    expect_err!(IllegalCodec, block_create(0xdead, data.as_ptr(), 3));
+    expect_err!(IllegalArgument, block_create(IPLD_RAW, data.as_ptr(), HUGE));
+    expect_err!(IllegalArgument, block_create(IPLD_RAW, out_of_bounds(), 3));
+    // A byte string header without the bytes.
+    expect_err!(Serialization, block_create(DAG_CBOR, b"\x43".as_ptr(), 1));
+
+    let id = unsafe { block_create(IPLD_RAW, data.as_ptr(), 3) }.unwrap();
+
+    // block_read
+    expect_err!(
+        InvalidHandle,
+        block_read(BAD_HANDLE, 0, buf.as_mut_ptr(), 3)
+    );
+    expect_err!(IllegalArgument, block_read(id, 0, buf.as_mut_ptr(), HUGE));
+    expect_err!(IllegalArgument, block_read(id, 0, out_of_bounds(), 3));
+    expect_err!(
+        IllegalArgument,
+        block_read(id, u32::MAX, buf.as_mut_ptr(), 3)
+    );
+
+    // block_stat
+    expect_err!(InvalidHandle, block_stat(BAD_HANDLE));
+
+    // block_link
+    let blake2b = SupportedHashes::Blake2b256 as u64;
+    let len = buf.len() as u32;
+    expect_err!(
+        InvalidHandle,
+        block_link(BAD_HANDLE, blake2b, 32, buf.as_mut_ptr(), len)
+    );
+    expect_err!(
+        IllegalCid,
+        block_link(
+            id,
+            SupportedHashes::Sha2_256 as u64,
+            32,
+            buf.as_mut_ptr(),
+            len
+        )
+    );
+    expect_err!(
+        IllegalCid,
+        block_link(id, blake2b, 20, buf.as_mut_ptr(), len)
+    );
+    expect_err!(
+        BufferTooSmall,
+        block_link(id, blake2b, 32, buf.as_mut_ptr(), 1)
+    );
+    expect_err!(
+        IllegalArgument,
+        block_link(id, blake2b, 32, out_of_bounds(), len)
+    );
+    expect_err!(
+        IllegalArgument,
+        block_link(id, blake2b, 32, buf.as_mut_ptr(), HUGE)
+    );
+}
+
+fn test_self_errors() {
+    use sys::sself::*;
+
+    let unreachable = cid(b"foo").to_bytes();
+    let mut buf = [0u8; 128];
+
+    expect_err!(BufferTooSmall, root(buf.as_mut_ptr(), 1));
+    expect_err!(IllegalArgument, root(out_of_bounds(), 128));
+    expect_err!(IllegalArgument, root(buf.as_mut_ptr(), HUGE));
+
+    expect_err!(NotFound, set_root(unreachable.as_ptr()));
+    expect_err!(IllegalArgument, set_root(MALFORMED_CID.as_ptr()));
+    expect_err!(IllegalArgument, set_root(out_of_bounds()));
+}
+
+fn test_actor_errors() {
+    use sys::actor::*;
+
+    let missing = Address::new_actor(b"missing").to_bytes();
+    let code = cid(b"code").to_bytes();
+    let mut buf = [0u8; 128];
+    let len = buf.len() as u32;
+    let receiver = sdk::message::receiver();
+
+    // resolve_address
+    expect_err!(
+        NotFound,
+        resolve_address(missing.as_ptr(), missing.len() as u32)
+    );
+    expect_err!(IllegalArgument, resolve_address(b"\xff".as_ptr(), 1));
+    expect_err!(IllegalArgument, resolve_address(out_of_bounds(), 21));
+    expect_err!(IllegalArgument, resolve_address(missing.as_ptr(), HUGE));
+
+    // lookup_delegated_address
+    expect_err!(
+        NotFound,
+        lookup_delegated_address(MISSING_ACTOR, buf.as_mut_ptr(), len)
+    );
+    expect_err!(
+        IllegalArgument,
+        lookup_delegated_address(receiver, out_of_bounds(), len)
+    );
+
+    // get_actor_code_cid
+    expect_err!(
+        NotFound,
+        get_actor_code_cid(MISSING_ACTOR, buf.as_mut_ptr(), len)
+    );
+    expect_err!(
+        BufferTooSmall,
+        get_actor_code_cid(receiver, buf.as_mut_ptr(), 1)
+    );
+    expect_err!(
+        IllegalArgument,
+        get_actor_code_cid(receiver, out_of_bounds(), len)
+    );
+    expect_err!(
+        IllegalArgument,
+        get_actor_code_cid(receiver, buf.as_mut_ptr(), HUGE)
+    );
+
+    // get_builtin_actor_type
+    expect_err!(
+        IllegalArgument,
+        get_builtin_actor_type(MALFORMED_CID.as_ptr())
+    );
+    expect_err!(IllegalArgument, get_builtin_actor_type(out_of_bounds()));
+
+    // get_code_cid_for_type
+    expect_err!(
+        IllegalArgument,
+        get_code_cid_for_type(9999, buf.as_mut_ptr(), len)
+    );
+    expect_err!(
+        IllegalArgument,
+        get_code_cid_for_type(1, out_of_bounds(), len)
+    );
+
+    // next_actor_address
+    expect_err!(BufferTooSmall, next_actor_address(buf.as_mut_ptr(), 1));
+    expect_err!(IllegalArgument, next_actor_address(out_of_bounds(), len));
+
+    // create_actor
+    expect_err!(
+        Forbidden,
+        create_actor(20000, code.as_ptr(), std::ptr::null(), 0)
+    );
+    expect_err!(
+        IllegalArgument,
+        create_actor(20000, MALFORMED_CID.as_ptr(), std::ptr::null(), 0)
+    );
+    expect_err!(
+        IllegalArgument,
+        create_actor(20000, code.as_ptr(), out_of_bounds(), 22)
+    );
+
+    // balance_of
+    expect_err!(NotFound, balance_of(MISSING_ACTOR));
+}
+
+fn test_send_errors() {
+    let recipient = Address::new_id(MISSING_ACTOR).to_bytes();
+    let len = recipient.len() as u32;
+    let flags = SendFlags::empty();
+
+    expect_err!(
+        IllegalArgument,
+        sys::send::send(b"\xff".as_ptr(), 1, 0, 0, 0, 0, u64::MAX, flags)
+    );
+    expect_err!(
+        IllegalArgument,
+        sys::send::send(out_of_bounds(), len, 0, 0, 0, 0, u64::MAX, flags)
+    );
+    expect_err!(
+        IllegalArgument,
+        sys::send::send(
+            recipient.as_ptr(),
+            len,
+            0,
+            0,
+            0,
+            0,
+            u64::MAX,
+            SendFlags::from_bits_retain(1 << 63)
+        )
+    );
+    expect_err!(
+        InvalidHandle,
+        sys::send::send(
+            recipient.as_ptr(),
+            len,
+            0,
+            BAD_HANDLE,
+            0,
+            0,
+            u64::MAX,
+            flags
+        )
+    );
+}
+
+fn test_event_errors() {
+    use sys::event::emit_event;
+
+    let entry = |codec, key_len| EventEntry {
+        flags: Flags::FLAG_INDEXED_ALL,
+        codec,
+        key_len,
+        val_len: 0,
+    };
+    let valid = [entry(IPLD_RAW, 1)];
+    let key = b"k";
+
+    expect_err!(
+        IllegalCodec,
+        emit_event(
+            [entry(0xdead, 1)].as_ptr(),
+            1,
+            key.as_ptr(),
+            1,
+            key.as_ptr(),
+            0
+        )
+    );
+    expect_err!(
+        IllegalArgument,
+        emit_event(valid.as_ptr(), u32::MAX, key.as_ptr(), 1, key.as_ptr(), 0)
+    );
+    expect_err!(
+        IllegalArgument,
+        emit_event(out_of_bounds(), 1, key.as_ptr(), 1, key.as_ptr(), 0)
+    );
+    expect_err!(
+        IllegalArgument,
+        emit_event(valid.as_ptr(), 1, key.as_ptr(), HUGE, key.as_ptr(), 0)
+    );
+    expect_err!(
+        IllegalArgument,
+        emit_event(valid.as_ptr(), 1, key.as_ptr(), 1, out_of_bounds(), 1)
+    );
+    // The key isn't valid utf8.
+    expect_err!(
+        IllegalArgument,
+        emit_event(valid.as_ptr(), 1, b"\xff".as_ptr(), 1, key.as_ptr(), 0)
+    );
+    // The headers don't cover the whole key buffer.
+    expect_err!(
+        IllegalArgument,
+        emit_event(valid.as_ptr(), 1, b"kk".as_ptr(), 2, key.as_ptr(), 0)
+    );
+}
+
+fn test_network_errors() {
+    let mut buf = [0u8; 128];
+    let len = buf.len() as u32;
+    let epoch = sdk::network::current_epoch();
+
+    // Randomness from the future.
+    expect_err!(IllegalArgument, sys::rand::get_chain_randomness(epoch + 1));
+    expect_err!(IllegalArgument, sys::rand::get_beacon_randomness(epoch + 1));
+
+    // Tipsets from the future, the current epoch, and before genesis.
+    for epoch in [epoch + 1, epoch, -1] {
+        expect_err!(
+            IllegalArgument,
+            sys::network::tipset_cid(epoch, buf.as_mut_ptr(), len)
+        );
+    }
+    expect_err!(
+        IllegalArgument,
+        sys::network::tipset_cid(epoch - 1, out_of_bounds(), len)
+    );
+}
+
+fn test_gas_errors() {
+    expect_err!(IllegalArgument, sys::gas::charge(b"\xff".as_ptr(), 1, 0));
+    expect_err!(IllegalArgument, sys::gas::charge(out_of_bounds(), 1, 0));
+    expect_err!(IllegalArgument, sys::gas::charge(b"foo".as_ptr(), HUGE, 0));
+}
+
+fn test_crypto_errors() {
+    use sys::crypto::*;
+
+    let mut buf = [0u8; 64];
+    let garbage = b"\xff\xff\xff\xff";
+
+    expect_err!(
+        IllegalArgument,
+        hash(0xdead, garbage.as_ptr(), 4, buf.as_mut_ptr(), 64)
+    );
+    expect_err!(
+        IllegalArgument,
+        verify_bls_aggregate(
+            u32::MAX,
+            buf.as_ptr(),
+            buf.as_ptr() as *const _,
+            buf.as_ptr(),
+            buf.as_ptr() as *const u32
+        )
+    );
+    expect_err!(
+        IllegalArgument,
+        verify_bls_aggregate(
+            1,
+            out_of_bounds(),
+            buf.as_ptr() as *const _,
+            buf.as_ptr(),
+            buf.as_ptr() as *const u32
+        )
+    );
+
+    // Parameters that aren't valid CBOR.
+    expect_err!(IllegalArgument, verify_post(garbage.as_ptr(), 4));
+    expect_err!(IllegalArgument, verify_aggregate_seals(garbage.as_ptr(), 4));
+    expect_err!(IllegalArgument, verify_replica_update(garbage.as_ptr(), 4));
+    expect_err!(IllegalArgument, verify_post(out_of_bounds(), 4));
+}
+
+fn test_debug_errors() {
+    // Debug syscalls are no-ops unless debugging is enabled.
+    if !sdk::debug::enabled() {
+        return;
+    }
+    use sys::debug::*;
+
+    expect_err!(IllegalArgument, log(b"\xff".as_ptr(), 1));
+    expect_err!(IllegalArgument, log(out_of_bounds(), 1));
+    // `log_structured` is only linked from nv26, after the network version this actor runs at
+    // (its error paths are tested in the fvm crate).
+    expect_err!(
+        IllegalArgument,
+        store_artifact(b"\xff".as_ptr(), 1, b"data".as_ptr(), 4)
+    );
+}
+
+fn cid(data: &[u8]) -> cid::Cid {
+    cid::Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(data))
+}
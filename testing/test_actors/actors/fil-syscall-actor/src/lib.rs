@@ -2,3 +2,5 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 #[cfg(target_arch = "wasm32")]
 mod actor;
+#[cfg(target_arch = "wasm32")]
+mod errors;
@@ -116,6 +116,32 @@ pub fn invoke(_: u32) -> u32 {
             let res = sdk::actor::upgrade_actor(&new_code_cid, None);
             assert_eq!(res, Err(ErrorNumber::IllegalOperation));
         }
+        // test that state written by a rejected upgrade is rolled back along with the code
+        6 => {
+            let self_addr = Address::new_id(sdk::message::receiver());
+            let old_code_cid = sdk::actor::get_actor_code_cid(&self_addr).unwrap();
+            let old_root = sdk::sself::root().unwrap();
+
+            let new_code_cid = sdk::actor::get_actor_code_cid(&Address::new_id(10001)).unwrap();
+            let params = IpldBlock::serialize_cbor(&SomeStruct { value: 3 }).unwrap();
+            let res = sdk::actor::upgrade_actor(&new_code_cid, params).unwrap();
+            assert_eq!(
+                UPGRADE_FAILED_EXIT_CODE,
+                res.exit_code.value(),
+                "invalid exit code returned from upgrade_actor"
+            );
+
+            assert_eq!(
+                sdk::actor::get_actor_code_cid(&self_addr).unwrap(),
+                old_code_cid,
+                "code was not rolled back"
+            );
+            assert_eq!(
+                sdk::sself::root().unwrap(),
+                old_root,
+                "state was not rolled back"
+            );
+        }
         // test that calling an upgrade with actor already on the call stack fails
         99 => {
             let new_code_cid = sdk::actor::get_actor_code_cid(&Address::new_id(10000)).unwrap();
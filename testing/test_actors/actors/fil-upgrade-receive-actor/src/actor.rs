@@ -1,11 +1,13 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use cid::Cid;
 use fvm_ipld_encoding::ipld_block::IpldBlock;
-use fvm_ipld_encoding::{to_vec, CBOR};
+use fvm_ipld_encoding::{to_vec, CBOR, DAG_CBOR};
 use fvm_sdk as sdk;
 use fvm_shared::address::Address;
+use fvm_shared::crypto::hash::SupportedHashes;
 use fvm_shared::error::ExitCode;
-use fvm_shared::upgrade::UpgradeInfo;
+use fvm_shared::upgrade::{encode_versioned, UpgradeInfo, Versioned};
 use serde_tuple::*;
 
 #[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
@@ -13,6 +15,19 @@ struct SomeStruct {
     value: u64,
 }
 
+/// The state written on upgrade, wrapped in a versioned envelope so that later code can detect
+/// (and migrate) this layout.
+#[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+struct UpgradedState {
+    old_code_cid: Cid,
+}
+
+impl Versioned for UpgradedState {
+    const VERSION: u64 = 1;
+}
+
+const UPGRADE_FAILED_EXIT_CODE: u32 = 19;
+
 #[no_mangle]
 pub fn upgrade(params_id: u32, upgrade_info_id: u32) -> u32 {
     sdk::initialize();
@@ -44,6 +59,14 @@ pub fn upgrade(params_id: u32, upgrade_info_id: u32) -> u32 {
                 "expected invalid receiver error"
             );
 
+            let state = encode_versioned(&UpgradedState {
+                old_code_cid: ui.old_code_cid,
+            })
+            .unwrap();
+            let root =
+                sdk::ipld::put(SupportedHashes::Blake2b256.into(), 32, DAG_CBOR, &state).unwrap();
+            sdk::sself::set_root(&root).unwrap();
+
             let block_id = sdk::ipld::put_block(CBOR, &to_vec(&666).unwrap()).unwrap();
             sdk::debug::log(format!(
                 "[upgrade] params:1, returning block_id {}",
@@ -60,6 +83,19 @@ pub fn upgrade(params_id: u32, upgrade_info_id: u32) -> u32 {
             ));
             block_id
         }
+        3 => {
+            // migrate the state, then reject the upgrade: both the new state and the new code
+            // must be rolled back
+            sdk::debug::log("[upgrade-receive-actor] params:3, rejecting upgrade".to_string());
+            let state = encode_versioned(&UpgradedState {
+                old_code_cid: ui.old_code_cid,
+            })
+            .unwrap();
+            let root =
+                sdk::ipld::put(SupportedHashes::Blake2b256.into(), 32, DAG_CBOR, &state).unwrap();
+            sdk::sself::set_root(&root).unwrap();
+            sdk::vm::exit(UPGRADE_FAILED_EXIT_CODE, None, None)
+        }
         other => {
             panic!("unexpected value: {}", other);
         }